@@ -63,7 +63,9 @@ async fn main(mut ctx: Context) -> Result<()> {
     let storage = Arc::new(AuthenticatedAttributeStorage::new(store.clone()));
     let issuer = issuer_client.public_identity().await?;
     let r = route![channel.clone(), "credentials"];
-    client.present_credential_mutual(r, &[issuer], storage, None).await?;
+    client
+        .present_credential_mutual(r, &[issuer], storage, None, None)
+        .await?;
 
     // Send a message to the worker at address "echoer".
     ctx.send(route![channel, "echoer"], "Hello Ockam!".to_string()).await?;
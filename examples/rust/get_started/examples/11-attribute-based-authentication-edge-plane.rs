@@ -131,6 +131,7 @@ async fn start_node(ctx: Context, project_information_path: &str, token: OneTime
         .present_credential(
             route![secure_channel_address.clone(), DefaultAddress::CREDENTIALS_SERVICE],
             None,
+            None,
         )
         .await?;
 
@@ -154,6 +155,7 @@ async fn start_node(ctx: Context, project_information_path: &str, token: OneTime
             vec![&project.authority_public_identity()],
             Arc::new(storage),
             None,
+            None,
         )
         .await?;
     println!("credential exchange done");
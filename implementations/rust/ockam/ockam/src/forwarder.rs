@@ -1,12 +1,16 @@
 use crate::Context;
 use core::str::from_utf8;
-use ockam_core::compat::sync::Arc;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, RwLock};
 use ockam_core::compat::{boxed::Box, vec::Vec};
 use ockam_core::{
-    Address, AllowOnwardAddress, Any, DenyAll, IncomingAccessControl, LocalMessage, Result, Route,
+    async_trait, route, Address, AllowSourceAddress, Any, DenyAll, IncomingAccessControl,
+    LocalMessage, Mailbox, Mailboxes, OutgoingAccessControl, Priority, RelayMessage, Result, Route,
     Routed, TransportMessage, Worker,
 };
 use ockam_node::WorkerBuilder;
+#[cfg(feature = "std")]
+use ockam_node::{Backoff, RestartPolicy};
 use tracing::info;
 
 /// Alias worker to register remote workers under local names.
@@ -14,9 +18,14 @@ use tracing::info;
 /// To talk with this worker, you can use the
 /// [`RemoteForwarder`](crate::remote::RemoteForwarder) which is a
 /// compatible client for this server.
+///
+/// Registering an alias that already has a forwarder refreshes that
+/// forwarder with the route of the new registration, which lets static
+/// remote forwarders re-register with their heartbeats.
 #[non_exhaustive]
 pub struct ForwardingService {
     forwarders_incoming_access_control: Arc<dyn IncomingAccessControl>,
+    forwarders: Arc<RegisteredForwarders>,
 }
 
 impl ForwardingService {
@@ -27,11 +36,52 @@ impl ForwardingService {
         service_incoming_access_control: impl IncomingAccessControl,
         forwarders_incoming_access_control: impl IncomingAccessControl,
     ) -> Result<()> {
+        let forwarders = Arc::new(RegisteredForwarders::default());
         let s = Self {
             forwarders_incoming_access_control: Arc::new(forwarders_incoming_access_control),
+            forwarders: forwarders.clone(),
         };
-        ctx.start_worker(address.into(), s, service_incoming_access_control, DenyAll)
-            .await?;
+        WorkerBuilder::with_access_control(
+            Arc::new(service_incoming_access_control),
+            forwarders,
+            address,
+            s,
+        )
+        .start(ctx)
+        .await?;
+        Ok(())
+    }
+
+    /// Start a forwarding service owned by the given
+    /// [`Supervisor`](crate::Supervisor), and restarted when it panics
+    #[cfg(feature = "std")]
+    pub async fn create_supervised(
+        ctx: &Context,
+        address: impl Into<Address>,
+        service_incoming_access_control: impl IncomingAccessControl,
+        forwarders_incoming_access_control: impl IncomingAccessControl,
+        supervisor: impl Into<Address>,
+    ) -> Result<()> {
+        let forwarders_incoming_access_control: Arc<dyn IncomingAccessControl> =
+            Arc::new(forwarders_incoming_access_control);
+        let forwarders = Arc::new(RegisteredForwarders::default());
+        let service_forwarders = forwarders.clone();
+        let factory = move || Self {
+            forwarders_incoming_access_control: forwarders_incoming_access_control.clone(),
+            forwarders: service_forwarders.clone(),
+        };
+
+        WorkerBuilder::with_access_control(
+            Arc::new(service_incoming_access_control),
+            forwarders,
+            address,
+            factory(),
+        )
+        .with_restart_policy(RestartPolicy::OnPanic(Backoff::default()), factory)
+        .with_supervisor(supervisor)
+        .start(ctx)
+        .await?;
+
         Ok(())
     }
 }
@@ -46,22 +96,76 @@ impl Worker for ForwardingService {
         ctx: &mut Self::Context,
         msg: Routed<Self::Message>,
     ) -> Result<()> {
+        let alias = Forwarder::alias(msg.payload());
+
+        // A registration for a known alias refreshes its forwarder, unless
+        // that forwarder has been stopped in the meantime
+        if let Some(refresh_address) = alias.as_ref().and_then(|a| self.forwarders.get(a)) {
+            let mut message = msg.local_message().clone();
+            message.transport_mut().onward_route = route![refresh_address];
+            if ctx.forward(message).await.is_ok() {
+                return Ok(());
+            }
+        }
+
         let forward_route = msg.return_route();
         let payload = msg.into_transport_message().payload;
-        Forwarder::create(
+        let refresh_address = Forwarder::create(
             ctx,
+            alias.clone(),
             forward_route,
             payload,
             self.forwarders_incoming_access_control.clone(),
         )
         .await?;
+        if let Some(alias) = alias {
+            self.forwarders.insert(alias, refresh_address);
+        }
 
         Ok(())
     }
 }
 
+/// The forwarders of a [`ForwardingService`], by alias
+///
+/// As an [`OutgoingAccessControl`] this only lets the forwarding
+/// service send registrations to the refresh address of its forwarders.
+#[derive(Debug, Default)]
+struct RegisteredForwarders(RwLock<BTreeMap<Address, Address>>);
+
+impl RegisteredForwarders {
+    fn get(&self, alias: &Address) -> Option<Address> {
+        self.0.read().unwrap().get(alias).cloned()
+    }
+
+    fn insert(&self, alias: Address, refresh_address: Address) {
+        self.0.write().unwrap().insert(alias, refresh_address);
+    }
+}
+
+#[async_trait]
+impl OutgoingAccessControl for RegisteredForwarders {
+    async fn is_authorized(&self, relay_msg: &RelayMessage) -> Result<bool> {
+        let next_hop = relay_msg.onward_route().next()?;
+        Ok(self.0.read().unwrap().values().any(|a| a == next_hop))
+    }
+}
+
+/// Allow messages to the next hop of the current route of a forwarder
+#[derive(Debug)]
+struct ForwardNextHop(Arc<RwLock<Address>>);
+
+#[async_trait]
+impl OutgoingAccessControl for ForwardNextHop {
+    async fn is_authorized(&self, relay_msg: &RelayMessage) -> Result<bool> {
+        Ok(*self.0.read().unwrap() == *relay_msg.onward_route().next()?)
+    }
+}
+
 struct Forwarder {
     forward_route: Route,
+    next_hop: Arc<RwLock<Address>>,
+    refresh_address: Address,
     // this option will be `None` after this worker is initialized, because
     // while initializing, the worker will send the payload contained in this
     // field to the `forward_route`, to indicate a successful connection
@@ -69,41 +173,62 @@ struct Forwarder {
 }
 
 impl Forwarder {
+    /// The alias requested by a registration payload, ephemeral forwarders
+    /// registering with "register" get a random address
+    fn alias(registration_payload: &[u8]) -> Option<Address> {
+        // TODO: assume that the first byte is length, ignore it.
+        // We have to improve this actually parse the payload.
+        match from_utf8(registration_payload.get(1..)?) {
+            Ok("register") | Err(_) => None,
+            Ok(v) => Some(Address::from_string(v)),
+        }
+    }
+
+    /// Start a forwarder and return the address it is refreshed at
     async fn create(
         ctx: &Context,
+        alias: Option<Address>,
         forward_route: Route,
         registration_payload: Vec<u8>,
         incoming_access_control: Arc<dyn IncomingAccessControl>,
-    ) -> Result<()> {
-        let random_address = Address::random_tagged("Forwarder.service");
-
-        // TODO: assume that the first byte is length, ignore it.
-        // We have to improve this actually parse the payload.
-        let address = match registration_payload.get(1..) {
-            Some(address) => match from_utf8(address) {
-                Ok(v) => Address::from_string(v),
-                Err(_e) => random_address,
-            },
-            None => random_address,
-        };
+    ) -> Result<Address> {
+        let address = alias.unwrap_or_else(|| Address::random_tagged("Forwarder.service"));
+        let refresh_address = Address::random_tagged("Forwarder.refresh");
         info!("Created new alias for {}", forward_route);
 
-        let next_hop = forward_route.next()?.clone();
+        let next_hop = Arc::new(RwLock::new(forward_route.next()?.clone()));
         let forwarder = Self {
             forward_route,
-            payload: Some(registration_payload.clone()),
+            next_hop: next_hop.clone(),
+            refresh_address: refresh_address.clone(),
+            payload: Some(registration_payload),
         };
 
-        WorkerBuilder::with_access_control(
-            incoming_access_control,
-            Arc::new(AllowOnwardAddress(next_hop)), // TODO: @ac we can actually check not only the next hop, but that the whole forward_route is the beginning of a onward_route
+        // TODO: @ac we can actually check not only the next hop, but that the whole forward_route is the beginning of a onward_route
+        let main = Mailbox::new(
             address,
-            forwarder,
-        )
-        .start(ctx)
-        .await?;
+            incoming_access_control,
+            Arc::new(ForwardNextHop(next_hop)),
+        );
+        let refresh = Mailbox::new(
+            refresh_address.clone(),
+            Arc::new(AllowSourceAddress(ctx.address())),
+            Arc::new(DenyAll),
+        );
+        WorkerBuilder::with_mailboxes(Mailboxes::new(main, vec![refresh]), forwarder)
+            .start(ctx)
+            .await?;
 
-        Ok(())
+        Ok(refresh_address)
+    }
+
+    /// Send the registration payload back on the forward route
+    async fn confirm(&self, ctx: &Context, payload: Vec<u8>) -> Result<()> {
+        let msg = TransportMessage::v1(self.forward_route.clone(), ctx.address(), payload);
+
+        // The confirmation also answers the heartbeats of the remote forwarder
+        ctx.forward(LocalMessage::new(msg, Vec::new()).with_priority(Priority::Control))
+            .await
     }
 }
 
@@ -117,10 +242,7 @@ impl Worker for Forwarder {
             .payload
             .take()
             .expect("payload must be available on init");
-        let msg = TransportMessage::v1(self.forward_route.clone(), ctx.address(), payload);
-
-        ctx.forward(LocalMessage::new(msg, Vec::new())).await?;
-        Ok(())
+        self.confirm(ctx, payload).await
     }
 
     async fn handle_message(
@@ -128,6 +250,20 @@ impl Worker for Forwarder {
         ctx: &mut Self::Context,
         msg: Routed<Self::Message>,
     ) -> Result<()> {
+        if msg.msg_addr() == self.refresh_address {
+            // The alias was registered again, possibly on a new route
+            self.forward_route = msg.return_route();
+            *self.next_hop.write().unwrap() = self.forward_route.next()?.clone();
+            info!(
+                "Refreshed alias {} for {}",
+                ctx.address(),
+                self.forward_route
+            );
+            return self
+                .confirm(ctx, msg.into_transport_message().payload)
+                .await;
+        }
+
         let mut message = msg.into_local_message();
         let transport_message = message.transport_mut();
 
@@ -27,8 +27,12 @@ pub use ockam_macros::{node, test};
 
 // Export node implementation
 pub use ockam_node::{
-    debugger, Context, DelayedEvent, Executor, MessageReceiveOptions, MessageSendReceiveOptions,
-    NodeBuilder, WorkerBuilder,
+    debugger, Context, DelayedEvent, Dispatch, Executor, MessageReceiveOptions,
+    MessageSendReceiveOptions, NodeBuilder, WorkerBuilder, WorkerPool,
+};
+#[cfg(feature = "std")]
+pub use ockam_node::{
+    Backoff, RestartPolicy, Supervisor, SupervisorMessage, WorkerEvent, WorkerEventKind,
 };
 // ---
 
@@ -60,8 +64,8 @@ pub use ockam_identity as identity;
 
 pub use ockam_core::{
     allow, deny, errcode, route, Address, Any, AsyncTryClone, Encoded, Error, LocalMessage,
-    Mailbox, Mailboxes, Message, Processor, ProtocolId, Result, Route, Routed, TransportMessage,
-    Worker,
+    Mailbox, Mailboxes, Message, Priority, Processor, ProtocolId, Result, Route, Routed,
+    TransportMessage, Worker,
 };
 
 /// Access Control
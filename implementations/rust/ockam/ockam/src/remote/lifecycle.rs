@@ -87,6 +87,31 @@ impl RemoteForwarder {
         hub_route: impl Into<Route>,
         alias: impl Into<String>,
         trust_options: RemoteForwarderTrustOptions,
+    ) -> Result<RemoteForwarderInfo> {
+        let registration_route = route![hub_route.into(), "static_forwarding_service"];
+        Self::create_static_with_heartbeats(ctx, registration_route, alias, trust_options).await
+    }
+
+    /// Create and start static RemoteForwarder at predefined address with the
+    /// `forwarding_service` of a rust node at the given route
+    ///
+    /// The heartbeats register the alias again, so that the forwarder is
+    /// recreated when the node restarts.
+    pub async fn create_static_on_node(
+        ctx: &Context,
+        node_route: impl Into<Route>,
+        alias: impl Into<String>,
+        trust_options: RemoteForwarderTrustOptions,
+    ) -> Result<RemoteForwarderInfo> {
+        let registration_route = route![node_route.into(), "forwarding_service"];
+        Self::create_static_with_heartbeats(ctx, registration_route, alias, trust_options).await
+    }
+
+    async fn create_static_with_heartbeats(
+        ctx: &Context,
+        registration_route: Route,
+        alias: impl Into<String>,
+        trust_options: RemoteForwarderTrustOptions,
     ) -> Result<RemoteForwarderInfo> {
         let addresses = Addresses::generate(ForwardType::Static);
 
@@ -98,8 +123,6 @@ impl RemoteForwarder {
             ))
             .await?;
 
-        let registration_route = route![hub_route.into(), "static_forwarding_service"];
-
         let heartbeat = DelayedEvent::create(ctx, addresses.heartbeat.clone(), vec![]).await?;
         let heartbeat_source_address = heartbeat.address();
 
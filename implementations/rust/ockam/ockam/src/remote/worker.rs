@@ -5,7 +5,7 @@ use ockam_core::compat::{
     string::{String, ToString},
     vec::Vec,
 };
-use ockam_core::{Any, Decodable, Result, Routed, Worker};
+use ockam_core::{Any, Decodable, Priority, Result, Routed, Worker};
 use tracing::{debug, info};
 
 #[crate::worker]
@@ -32,11 +32,13 @@ impl Worker for RemoteForwarder {
         msg: Routed<Self::Message>,
     ) -> Result<()> {
         if msg.msg_addr() == self.addresses.heartbeat {
-            // Heartbeat message, send registration message
-            ctx.send_from_address(
+            // Heartbeat message, send registration message ahead of the
+            // forwarded data
+            ctx.send_from_address_with_priority(
                 self.registration_route.clone(),
                 self.registration_payload.clone(),
                 self.addresses.main_remote.clone(),
+                Priority::Control,
             )
             .await?;
 
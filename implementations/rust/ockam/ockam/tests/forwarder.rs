@@ -6,6 +6,8 @@ use ockam_core::{route, Address, AllowAll, Result};
 use ockam_identity::{
     Identity, SecureChannelListenerTrustOptions, SecureChannelTrustOptions, TrustEveryonePolicy,
 };
+use ockam_node::loopback::{self, LoopbackTransport};
+use ockam_node::sim::{self, Link, Network};
 use ockam_node::{Context, MessageReceiveOptions, MessageSendReceiveOptions};
 use ockam_transport_tcp::{TcpConnectionTrustOptions, TcpListenerTrustOptions, TcpTransport};
 use ockam_vault::Vault;
@@ -302,3 +304,143 @@ async fn test4(ctx: &mut Context) -> Result<()> {
 
     ctx.stop().await
 }
+
+// Cloud, Server and Client are isolated nodes in the same process, connected with the loopback
+// transport. Cloud: Hosts a Forwarding service. Server: Creates a Forwarder on Cloud.
+// Client: Reaches to the Server's Echoer through Cloud
+#[ockam_macros::test]
+async fn test5(ctx: &mut Context) -> Result<()> {
+    let transport = LoopbackTransport::new();
+
+    let cloud = transport.start_node("cloud").await?;
+    ForwardingService::create(&cloud, "forwarding_service", AllowAll, AllowAll).await?;
+
+    let server = transport.start_node("server").await?;
+    server
+        .start_worker("echoer", Echoer, AllowAll, AllowAll)
+        .await?;
+    let remote_info = RemoteForwarder::create(
+        &server,
+        route![loopback::address("cloud")],
+        RemoteForwarderTrustOptions::new(),
+    )
+    .await?;
+
+    let client = transport.start_node("client").await?;
+    let resp = client
+        .send_and_receive::<String>(
+            route![
+                loopback::address("cloud"),
+                remote_info.remote_address(),
+                "echoer"
+            ],
+            "Hello".to_string(),
+        )
+        .await?;
+
+    assert_eq!(resp, "Hello");
+
+    for mut node in [cloud, server, client] {
+        node.stop().await?;
+    }
+    ctx.stop().await
+}
+
+// Cloud, Server and Client are nodes on a simulated network with latency. Server: Creates a
+// Forwarder on Cloud. Client: Reaches to the Server's Echoer through Cloud, also after a
+// partition between Server and Cloud heals
+#[ockam_macros::test(virtual_clock)]
+async fn test6(ctx: &mut Context) -> Result<()> {
+    let network = Network::new(0);
+    network.set_default_link(Link::new(Duration::from_millis(50)));
+    network.attach(ctx, "client").await?;
+
+    let mut cloud = network.start_node("cloud").await?;
+    ForwardingService::create(&cloud, "forwarding_service", AllowAll, AllowAll).await?;
+
+    let mut server = network.start_node("server").await?;
+    server
+        .start_worker("echoer", Echoer, AllowAll, AllowAll)
+        .await?;
+    let remote_info = RemoteForwarder::create(
+        &server,
+        route![sim::address("cloud")],
+        RemoteForwarderTrustOptions::new(),
+    )
+    .await?;
+    let route = route![
+        sim::address("cloud"),
+        remote_info.remote_address(),
+        "echoer"
+    ];
+
+    let resp = ctx
+        .send_and_receive::<String>(route.clone(), "Hello".to_string())
+        .await?;
+    assert_eq!(resp, "Hello");
+
+    network.partition(&["server"], &["cloud"]);
+    ctx.send(route.clone(), "Lost".to_string()).await?;
+    let options = MessageReceiveOptions::new().with_timeout(Duration::from_secs(5));
+    assert!(ctx.receive_extended::<String>(options).await.is_err());
+
+    network.heal();
+    let resp = ctx
+        .send_and_receive::<String>(route, "Hello again".to_string())
+        .await?;
+    assert_eq!(resp, "Hello again");
+
+    server.stop().await?;
+    cloud.stop().await?;
+    ctx.stop().await
+}
+
+// Cloud, Server and Client are nodes on a simulated network with latency. Server: Creates a
+// static Forwarder with heartbeats on Cloud. Client: Reaches to the Server's Echoer through
+// Cloud, also after Cloud has lost the Forwarder
+#[ockam_macros::test(virtual_clock)]
+async fn test7(ctx: &mut Context) -> Result<()> {
+    let network = Network::new(0);
+    network.set_default_link(Link::new(Duration::from_millis(50)));
+    network.attach(ctx, "client").await?;
+
+    let mut cloud = network.start_node("cloud").await?;
+    ForwardingService::create(&cloud, "forwarding_service", AllowAll, AllowAll).await?;
+
+    let mut server = network.start_node("server").await?;
+    server
+        .start_worker("echoer", Echoer, AllowAll, AllowAll)
+        .await?;
+    let remote_info = RemoteForwarder::create_static_on_node(
+        &server,
+        route![sim::address("cloud")],
+        "alias",
+        RemoteForwarderTrustOptions::new(),
+    )
+    .await?;
+    assert_eq!(remote_info.remote_address(), "alias");
+    let route = route![sim::address("cloud"), "alias", "echoer"];
+
+    // The heartbeats refresh the existing Forwarder
+    ctx.sleep(Duration::from_secs(12)).await;
+    let resp = ctx
+        .send_and_receive::<String>(route.clone(), "Hello".to_string())
+        .await?;
+    assert_eq!(resp, "Hello");
+
+    cloud.stop_worker("alias").await?;
+    ctx.send(route.clone(), "Lost".to_string()).await?;
+    let options = MessageReceiveOptions::new().with_timeout(Duration::from_secs(1));
+    assert!(ctx.receive_extended::<String>(options).await.is_err());
+
+    // The next heartbeat creates the Forwarder again
+    ctx.sleep(Duration::from_secs(10)).await;
+    let resp = ctx
+        .send_and_receive::<String>(route, "Hello again".to_string())
+        .await?;
+    assert_eq!(resp, "Hello again");
+
+    server.stop().await?;
+    cloud.stop().await?;
+    ctx.stop().await
+}
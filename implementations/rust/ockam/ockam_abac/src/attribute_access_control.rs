@@ -16,7 +16,7 @@ use ockam_core::compat::format;
 use ockam_core::compat::string::ToString;
 use ockam_core::compat::sync::Arc;
 use ockam_identity::authenticated_storage::{
-    AuthenticatedAttributeStorage, AuthenticatedStorage, IdentityAttributeStorage,
+    AttributesEntry, AuthenticatedAttributeStorage, AuthenticatedStorage, IdentityAttributeStorage,
 };
 use ockam_identity::IdentitySecureChannelLocalInfo;
 
@@ -73,6 +73,34 @@ where {
     }
 }
 
+impl AbacAccessControl {
+    /// Environment values which can be part of the audience of a credential
+    const AUDIENCE_KEYS: [&'static str; 3] =
+        ["resource.id", "resource.project_id", "resource.node_id"];
+
+    /// Attributes coming from a credential restricted to an audience are only used if the
+    /// audience names the resource, its project or the node
+    fn is_in_audience(&self, attrs: &AttributesEntry) -> bool {
+        let audience = match attrs.audience() {
+            Some(audience) => audience,
+            None => return true,
+        };
+        let in_audience = Self::AUDIENCE_KEYS
+            .iter()
+            .any(|k| match self.environment.get(k) {
+                Ok(Str(s)) => audience.contains(s),
+                _ => false,
+            });
+        if !in_audience {
+            log::debug! {
+                policy = %self.expression,
+                "attributes issued for another audience ignored"
+            }
+        }
+        in_audience
+    }
+}
+
 #[async_trait]
 impl IncomingAccessControl for AbacAccessControl {
     /// Return true if the sender of the message is validated by the expression stored in AbacAccessControl
@@ -91,7 +119,8 @@ impl IncomingAccessControl for AbacAccessControl {
         let mut environment = self.environment.clone();
 
         // Get identity attributes and populate the environment:
-        if let Some(attrs) = self.attributes.get_attributes(&id).await? {
+        let attrs = self.attributes.get_attributes(&id).await?;
+        if let Some(attrs) = attrs.filter(|attrs| self.is_in_audience(attrs)) {
             for (key, value) in attrs.attrs() {
                 if key.find(|c: char| c.is_whitespace()).is_some() {
                     log::warn! {
@@ -162,3 +191,34 @@ impl IncomingAccessControl for AbacAccessControl {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::compat::collections::BTreeMap;
+    use ockam_identity::authenticated_storage::mem::InMemoryStorage;
+    use ockam_identity::credential::Timestamp;
+
+    #[test]
+    fn attributes_only_used_by_their_audience() {
+        let mut env = Env::new();
+        env.put("resource.id", str("outlet"))
+            .put("resource.project_id", str("project42"));
+        let access_control = AbacAccessControl::new(
+            Arc::new(AuthenticatedAttributeStorage::new(Arc::new(
+                InMemoryStorage::new(),
+            ))),
+            Bool(true),
+            env,
+        );
+
+        let entry = |audience: Option<&[&str]>| {
+            AttributesEntry::new(BTreeMap::new(), Timestamp::from(0), None, None)
+                .with_audience(audience.map(|a| a.iter().map(|s| s.to_string()).collect()))
+        };
+        assert!(access_control.is_in_audience(&entry(None)));
+        assert!(access_control.is_in_audience(&entry(Some(&["outlet"]))));
+        assert!(access_control.is_in_audience(&entry(Some(&["other", "project42"]))));
+        assert!(!access_control.is_in_audience(&entry(Some(&["inlet"]))));
+    }
+}
@@ -1,16 +1,20 @@
 pub mod types;
 
 use core::fmt;
-use minicbor::Decoder;
-use ockam_core::api::decode_option;
-use ockam_core::api::{Method, Request, Response};
+use ockam_core::api::rpc::{dispatch, Call, Handle, Handler, Reply};
 use ockam_core::compat::sync::Arc;
-use ockam_core::{self, Address, DenyAll, Result, Route, Routed, Worker};
+use ockam_core::{self, Result, Route, Routed, Worker};
 use ockam_identity::authenticated_storage::{AttributesEntry, IdentityAttributeStorageReader};
 use ockam_identity::IdentityIdentifier;
-use ockam_node::api::request;
+use ockam_node::api;
 use ockam_node::Context;
-use tracing::trace;
+
+ockam_core::endpoints! {
+    /// List the attributes of all known identities.
+    pub struct ListAttributes<'a>: Get "/" -> Vec<(IdentityIdentifier, AttributesEntry)>;
+    /// Get the attributes of an identity.
+    pub struct GetAttributes<'a>: Get "/:id" -> AttributesEntry;
+}
 
 /// Auth API server.
 pub struct Server {
@@ -27,56 +31,50 @@ impl Worker for Server {
         ctx: &mut Context,
         msg: Routed<Self::Message>,
     ) -> ockam_core::Result<()> {
-        let r = self.on_request(msg.as_body()).await?;
+        let r = dispatch(self, Self::HANDLERS, msg.as_body()).await?;
         ctx.send(msg.return_route(), r).await
     }
 }
 
 impl Server {
+    pub(crate) const HANDLERS: &'static [Handler<Self>] = &[
+        Handler::of::<ListAttributes>(),
+        Handler::of::<GetAttributes>(),
+    ];
+
     pub fn new(s: Arc<dyn IdentityAttributeStorageReader>) -> Self {
         Server { store: s }
     }
+}
 
-    async fn on_request(&mut self, data: &[u8]) -> Result<Vec<u8>> {
-        let mut dec = Decoder::new(data);
-        let req: Request = dec.decode()?;
+#[ockam_core::async_trait]
+impl Handle<ListAttributes> for Server {
+    async fn handle<'a>(
+        &'a mut self,
+        _: Call<'a, ListAttributes>,
+    ) -> Result<Reply<Vec<(IdentityIdentifier, AttributesEntry)>>> {
+        Ok(Reply::Ok(self.store.list().await?))
+    }
+}
 
-        trace! {
-            target: "ockam_api::auth::server",
-            id     = %req.id(),
-            method = ?req.method(),
-            path   = %req.path(),
-            body   = %req.has_body(),
-            "request"
+#[ockam_core::async_trait]
+impl Handle<GetAttributes> for Server {
+    async fn handle<'a>(
+        &'a mut self,
+        call: Call<'a, GetAttributes>,
+    ) -> Result<Reply<AttributesEntry>> {
+        let identifier = IdentityIdentifier::try_from(call.param("id")?.to_string())?;
+        match self.store.get_attributes(&identifier).await? {
+            Some(a) => Ok(Reply::Ok(a)),
+            None => Ok(Reply::NotFound),
         }
-
-        let res = match req.method() {
-            Some(Method::Get) => match req.path_segments::<2>().as_slice() {
-                [""] => Response::ok(req.id())
-                    .body(self.store.list().await?)
-                    .to_vec()?,
-                [id] => {
-                    let identifier = IdentityIdentifier::try_from(id.to_string())?;
-                    if let Some(a) = self.store.get_attributes(&identifier).await? {
-                        Response::ok(req.id()).body(a).to_vec()?
-                    } else {
-                        Response::not_found(req.id()).to_vec()?
-                    }
-                }
-                _ => ockam_core::api::unknown_path(&req).to_vec()?,
-            },
-            _ => ockam_core::api::invalid_method(&req).to_vec()?,
-        };
-
-        Ok(res)
     }
 }
 
 /// Auth API client.
 pub struct Client {
-    ctx: Context,
     route: Route,
-    buf: Vec<u8>,
+    client: api::Client,
 }
 
 impl fmt::Debug for Client {
@@ -89,32 +87,15 @@ impl fmt::Debug for Client {
 
 impl Client {
     pub async fn new(r: Route, ctx: &Context) -> ockam_core::Result<Self> {
-        let ctx = ctx
-            .new_detached(
-                Address::random_tagged("AuthClient.detached"),
-                DenyAll,
-                DenyAll,
-            )
-            .await?;
-        Ok(Client {
-            ctx,
-            route: r,
-            buf: Vec::new(),
-        })
+        let client = api::Client::new(r.clone(), ctx).await?;
+        Ok(Client { route: r, client })
     }
 
     pub async fn get(&mut self, id: &str) -> ockam_core::Result<Option<AttributesEntry>> {
-        let label = "get attribute";
-        let req = Request::get(format!("/{id}"));
-        self.buf = request(&self.ctx, label, None, self.route.clone(), req).await?;
-        decode_option(label, "attribute", &self.buf)
+        self.client.call_option::<GetAttributes>(&[id], ()).await
     }
+
     pub async fn list(&mut self) -> ockam_core::Result<Vec<(IdentityIdentifier, AttributesEntry)>> {
-        let label = "list known identities";
-        let req = Request::get("/");
-        self.buf = request(&self.ctx, label, None, self.route.clone(), req).await?;
-        let a: Option<Vec<(IdentityIdentifier, AttributesEntry)>> =
-            decode_option(label, "attribute", &self.buf)?;
-        Ok(a.unwrap())
+        self.client.call::<ListAttributes>(&[], ()).await
     }
 }
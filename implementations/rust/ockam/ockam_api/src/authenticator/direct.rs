@@ -17,7 +17,7 @@ use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::time::{Duration, Instant};
 use tracing::{trace, warn};
-use types::AddMember;
+use types::{AddMember, IssueCredential};
 
 use crate::authenticator::direct::types::CreateToken;
 
@@ -169,7 +169,11 @@ impl CredentialIssuer {
         })
     }
 
-    async fn issue_credential(&self, from: &IdentityIdentifier) -> Result<Option<Credential>> {
+    async fn issue_credential(
+        &self,
+        from: &IdentityIdentifier,
+        audience: &[String],
+    ) -> Result<Option<Credential>> {
         match self.store.get_attributes(from).await? {
             Some(entry) => {
                 let crd = entry
@@ -180,6 +184,9 @@ impl CredentialIssuer {
                         |crd, (a, v)| crd.with_attribute(a, v),
                     )
                     .with_attribute(PROJECT_ID, &self.project);
+                let crd = audience
+                    .iter()
+                    .fold(crd, |crd, a| crd.with_audience(a.clone()));
                 Ok(Some(self.ident.issue_credential(crd).await?))
             }
             None => Ok(None),
@@ -208,7 +215,13 @@ impl Worker for CredentialIssuer {
             }
             let res = match (req.method(), req.path()) {
                 (Some(Method::Post), "/") | (Some(Method::Post), "/credential") => {
-                    match self.issue_credential(from).await {
+                    // The audience of the credential can optionally be restricted by the member
+                    let req_body = if req.has_body() {
+                        dec.decode::<IssueCredential>()?
+                    } else {
+                        IssueCredential::new(Vec::new())
+                    };
+                    match self.issue_credential(from, req_body.audience()).await {
                         Ok(Some(crd)) => Response::ok(req.id()).body(crd).to_vec()?,
                         Ok(None) => {
                             // Again, this has already been checked by the access control, so if we
@@ -497,6 +510,13 @@ impl CredentialIssuerClient {
     pub async fn credential(&self) -> Result<Credential> {
         self.0.request(&Request::post("/")).await
     }
+
+    /// Get a credential which can only be presented to the given audience
+    pub async fn credential_with_audience(&self, audience: Vec<String>) -> Result<Credential> {
+        self.0
+            .request(&Request::post("/").body(IssueCredential::new(audience)))
+            .await
+    }
 }
 
 pub struct DirectAuthenticatorClient(RpcClient);
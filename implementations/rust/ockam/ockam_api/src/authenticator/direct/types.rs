@@ -77,3 +77,27 @@ impl<'a> CreateToken<'a> {
             .collect()
     }
 }
+
+#[derive(Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct IssueCredential {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<5182937>,
+    #[b(1)] audience: Vec<String>,
+}
+
+impl IssueCredential {
+    /// Request a credential restricted to the given audience
+    pub fn new(audience: Vec<String>) -> Self {
+        IssueCredential {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            audience,
+        }
+    }
+
+    pub fn audience(&self) -> &[String] {
+        &self.audience
+    }
+}
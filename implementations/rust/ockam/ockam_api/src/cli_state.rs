@@ -513,6 +513,11 @@ impl IdentityState {
         self.persist()
     }
 
+    pub fn set_change_history(&mut self, change_history: IdentityChangeHistory) -> Result<()> {
+        self.config.change_history = change_history;
+        self.persist()
+    }
+
     pub async fn get(
         &self,
         ctx: &ockam::Context,
@@ -1230,6 +1235,14 @@ impl CredentialsState {
         Ok(CredentialState { path })
     }
 
+    /// Replace an existing credential, e.g. after it has been refreshed
+    pub async fn update(&self, name: &str, cred: CredentialConfig) -> Result<CredentialState> {
+        let state = self.get(name)?;
+        let contents = serde_json::to_string(&cred)?;
+        std::fs::write(&state.path, contents)?;
+        Ok(state)
+    }
+
     pub fn list(&self) -> Result<Vec<CredentialState>> {
         let mut creds = vec![];
         for entry in std::fs::read_dir(&self.dir)? {
@@ -80,19 +80,16 @@ impl ConfluentConfigResponse<'_> {
 mod node {
     use std::time::Duration;
 
-    use minicbor::{Decode, Decoder, Encode};
+    use minicbor::Encode;
     use tracing::trace;
 
     use ockam_core::api::Request;
     use ockam_core::{self, Result};
     use ockam_node::Context;
 
-    use crate::cloud::addon::ConfluentConfig;
-    use crate::cloud::project::{InfluxDBTokenLeaseManagerConfig, OktaConfig};
     use crate::cloud::{
         BareCloudRequestWrapper, CloudRequestWrapper, ORCHESTRATOR_RESTART_TIMEOUT,
     };
-    use crate::error::ApiError;
     use crate::nodes::NodeManagerWorker;
 
     const TARGET: &str = "ockam_api::cloud::addon";
@@ -102,10 +99,9 @@ mod node {
         pub(crate) async fn list_addons(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
             project_id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "list_addons";
@@ -125,44 +121,18 @@ mod node {
             .await
         }
 
-        pub(crate) async fn configure_addon(
+        /// Configure an addon, the type of its configuration depends on
+        /// the addon and is picked by the route of the request.
+        pub(crate) async fn configure_addon<T: Encode<()>>(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
-            project_id: &str,
-            addon_id: &str,
-        ) -> Result<Vec<u8>> {
-            // TODO: Add on ids should not be magic strings
-            match addon_id {
-                "okta" => {
-                    self.configure_addon_impl::<OktaConfig>(ctx, dec, project_id, addon_id)
-                        .await
-                }
-                "influxdb_token_lease_manager" => {
-                    self.configure_addon_impl::<InfluxDBTokenLeaseManagerConfig>(
-                        ctx, dec, project_id, addon_id,
-                    )
-                    .await
-                }
-                "confluent" => {
-                    self.configure_addon_impl::<ConfluentConfig>(ctx, dec, project_id, addon_id)
-                        .await
-                }
-                _ => Err(ApiError::generic(&format!("Unknown addon: {addon_id}"))),
-            }
-        }
-
-        async fn configure_addon_impl<'a, T: Encode<()> + Decode<'a, ()>>(
-            &mut self,
-            ctx: &mut Context,
-            dec: &mut Decoder<'a>,
+            req_wrapper: CloudRequestWrapper<'_, T>,
             project_id: &str,
             addon_id: &str,
         ) -> Result<Vec<u8>> {
             let label = "configure_addon";
             trace!(target: TARGET, project_id, addon_id, "configuring addon");
 
-            let req_wrapper: CloudRequestWrapper<T> = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
             let req_body = req_wrapper.req;
 
@@ -185,11 +155,10 @@ mod node {
         pub(crate) async fn disable_addon(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
             project_id: &str,
             addon_id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "disable_addon";
@@ -21,7 +21,6 @@ impl Token {
 mod node {
     use std::time::Duration;
 
-    use minicbor::Decoder;
     use tracing::trace;
 
     use ockam_core::api::Request;
@@ -41,9 +40,8 @@ mod node {
         pub(crate) async fn enroll_auth0(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: CloudRequestWrapper<'_, AuthenticateAuth0Token>,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: CloudRequestWrapper<AuthenticateAuth0Token> = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
             let req_body: AuthenticateAuth0Token = req_wrapper.req;
             let req_builder = Request::post("v0/enroll").body(req_body);
@@ -68,9 +66,8 @@ mod node {
         pub(crate) async fn generate_enrollment_token(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: CloudRequestWrapper<'_, Attributes>,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: CloudRequestWrapper<Attributes> = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
             let req_body: Attributes = req_wrapper.req;
             let req_body = RequestEnrollmentToken::new(req_body);
@@ -96,9 +93,8 @@ mod node {
         pub(crate) async fn authenticate_enrollment_token(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: CloudRequestWrapper<'_, EnrollmentToken>,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: CloudRequestWrapper<EnrollmentToken> = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
             let req_body: EnrollmentToken = req_wrapper.req;
             let req_builder = Request::post("v0/enroll").body(req_body);
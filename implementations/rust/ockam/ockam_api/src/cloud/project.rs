@@ -296,7 +296,6 @@ impl<'a> CreateProject<'a> {
 mod node {
     use std::time::Duration;
 
-    use minicbor::Decoder;
     use tracing::trace;
 
     use ockam_core::api::Request;
@@ -316,10 +315,9 @@ mod node {
         pub(crate) async fn create_project(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: CloudRequestWrapper<'_, CreateProject<'_>>,
             space_id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: CloudRequestWrapper<CreateProject> = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
             let req_body = req_wrapper.req;
 
@@ -344,9 +342,8 @@ mod node {
         pub(crate) async fn list_projects(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "list_projects";
@@ -369,10 +366,9 @@ mod node {
         pub(crate) async fn get_project(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
             project_id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "get_project";
@@ -395,11 +391,10 @@ mod node {
         pub(crate) async fn delete_project(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
             space_id: &str,
             project_id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "delete_project";
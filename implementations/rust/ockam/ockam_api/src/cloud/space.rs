@@ -58,7 +58,6 @@ impl<'a> CreateSpace<'a> {
 }
 
 mod node {
-    use minicbor::Decoder;
     use tracing::trace;
 
     use ockam_core::api::Request;
@@ -75,9 +74,8 @@ mod node {
         pub(crate) async fn create_space(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: CloudRequestWrapper<'_, CreateSpace<'_>>,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: CloudRequestWrapper<CreateSpace> = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
             let req_body = req_wrapper.req;
 
@@ -101,9 +99,8 @@ mod node {
         pub(crate) async fn list_spaces(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "list_spaces";
@@ -126,10 +123,9 @@ mod node {
         pub(crate) async fn get_space(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
             id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "get_space";
@@ -152,10 +148,9 @@ mod node {
         pub(crate) async fn delete_space(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
             id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "delete_space";
@@ -86,7 +86,6 @@ pub struct Subscription<'a> {
 }
 
 mod node {
-    use minicbor::Decoder;
     use tracing::trace;
 
     use ockam_core::api::Request;
@@ -105,10 +104,9 @@ mod node {
         pub(crate) async fn unsubscribe(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
             id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "unsubscribe";
@@ -131,10 +129,9 @@ mod node {
         pub(crate) async fn update_subscription_space(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: CloudRequestWrapper<'_, String>,
             id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: CloudRequestWrapper<String> = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
             let req_body = req_wrapper.req;
 
@@ -157,10 +154,9 @@ mod node {
         pub(crate) async fn update_subscription_contact_info(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: CloudRequestWrapper<'_, String>,
             id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: CloudRequestWrapper<String> = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
             let req_body = req_wrapper.req;
 
@@ -183,9 +179,8 @@ mod node {
         pub(crate) async fn list_subscriptions(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "list_subscriptions";
@@ -207,10 +202,9 @@ mod node {
         pub(crate) async fn get_subscription(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: BareCloudRequestWrapper<'_>,
             id: &str,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: BareCloudRequestWrapper = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
 
             let label = "get_subscription";
@@ -232,9 +226,8 @@ mod node {
         pub(crate) async fn activate_subscription(
             &mut self,
             ctx: &mut Context,
-            dec: &mut Decoder<'_>,
+            req_wrapper: CloudRequestWrapper<'_, ActivateSubscription<'_>>,
         ) -> Result<Vec<u8>> {
-            let req_wrapper: CloudRequestWrapper<ActivateSubscription> = dec.decode()?;
             let cloud_multiaddr = req_wrapper.multiaddr()?;
             let req_body = req_wrapper.req;
 
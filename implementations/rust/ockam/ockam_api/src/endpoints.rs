@@ -0,0 +1,320 @@
+//! A machine-readable description of the API of a node.
+//!
+//! [`ENDPOINTS`] lists the endpoints of the node manager and of the
+//! services it starts, with the names of the CDDL rules of their request
+//! and response bodies in the [`schema`](crate::schema).  The entries are
+//! built from the same [`Endpoint`] types as the route tables of the
+//! services, and a test checks that both list the same endpoints.
+
+use crate::nodes::{api as nm, NODEMANAGER_ADDR};
+use crate::schema;
+use crate::{auth, identity, vault, verifier, DefaultAddress};
+use ockam_core::api::rpc::{match_path, Endpoint};
+use ockam_core::api::Method;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value as Json};
+
+/// An endpoint of a service.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct EndpointInfo {
+    /// The address of the service.
+    pub service: &'static str,
+    #[serde(serialize_with = "method_name")]
+    pub method: Method,
+    /// The path pattern, where segments starting with `:` are parameters.
+    pub path: &'static str,
+    /// The CDDL rule of the request body.
+    pub request: Option<&'static str>,
+    /// The CDDL rule of the response body.
+    pub response: Option<&'static str>,
+}
+
+impl EndpointInfo {
+    pub const fn new(service: &'static str, method: Method, path: &'static str) -> Self {
+        EndpointInfo {
+            service,
+            method,
+            path,
+            request: None,
+            response: None,
+        }
+    }
+
+    /// The endpoint `E` of a service.
+    pub const fn of<E: Endpoint>(service: &'static str) -> Self {
+        Self::new(service, E::METHOD, E::PATH)
+    }
+
+    pub const fn request(mut self, rule: &'static str) -> Self {
+        self.request = Some(rule);
+        self
+    }
+
+    pub const fn response(mut self, rule: &'static str) -> Self {
+        self.response = Some(rule);
+        self
+    }
+
+    /// Check if a request matches this endpoint.
+    pub fn matches(&self, method: Method, path: &str) -> bool {
+        self.method == method && match_path(self.path, path).is_some()
+    }
+}
+
+fn method_name<S: Serializer>(method: &Method, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(method)
+}
+
+const NM: &str = NODEMANAGER_ADDR;
+const VAULT: &str = DefaultAddress::VAULT_SERVICE;
+const IDENTITY: &str = DefaultAddress::IDENTITY_SERVICE;
+const AUTHENTICATED: &str = DefaultAddress::AUTHENTICATED_SERVICE;
+const VERIFIER: &str = DefaultAddress::VERIFIER;
+
+#[rustfmt::skip]
+pub const ENDPOINTS: &[EndpointInfo] = &[
+    // Node manager
+    EndpointInfo::of::<nm::GetNodeStatus>(NM).response("node_status"),
+    EndpointInfo::of::<nm::ListTcpConnections>(NM).response("transport_list"),
+    EndpointInfo::of::<nm::CreateTcpConnection>(NM)
+        .request("create_transport")
+        .response("transport_status"),
+    EndpointInfo::of::<nm::DeleteTcpConnection>(NM).request("delete_transport"),
+    EndpointInfo::of::<nm::ListTcpListeners>(NM).response("transport_list"),
+    EndpointInfo::of::<nm::CreateTcpListener>(NM)
+        .request("create_transport")
+        .response("transport_status"),
+    EndpointInfo::of::<nm::DeleteTcpListener>(NM).request("delete_transport"),
+    EndpointInfo::of::<nm::GetCredential>(NM)
+        .request("get_credential_request")
+        .response("credential"),
+    EndpointInfo::of::<nm::PresentCredential>(NM).request("present_credential_request"),
+    EndpointInfo::of::<nm::PushIdentityUpdate>(NM).request("push_identity_update_request"),
+    EndpointInfo::of::<nm::PullIdentityUpdate>(NM).request("pull_identity_update_request"),
+    EndpointInfo::of::<nm::ListSecureChannels>(NM).response("secure_channels"),
+    EndpointInfo::of::<nm::CreateSecureChannel>(NM)
+        .request("create_secure_channel_request")
+        .response("create_secure_channel_response"),
+    EndpointInfo::of::<nm::DeleteSecureChannel>(NM)
+        .request("delete_secure_channel_request")
+        .response("delete_secure_channel_response"),
+    EndpointInfo::of::<nm::ShowSecureChannel>(NM)
+        .request("show_secure_channel_request")
+        .response("show_secure_channel_response"),
+    EndpointInfo::of::<nm::ListSecureChannelListeners>(NM).response("secure_channel_listeners"),
+    EndpointInfo::of::<nm::CreateSecureChannelListener>(NM)
+        .request("create_secure_channel_listener_request"),
+    EndpointInfo::of::<nm::DeleteSecureChannelListener>(NM)
+        .request("delete_secure_channel_listener_request")
+        .response("delete_secure_channel_listener_response"),
+    EndpointInfo::of::<nm::ListServices>(NM).response("service_list"),
+    EndpointInfo::of::<nm::StartVaultService>(NM).request("start_vault_service_request"),
+    EndpointInfo::of::<nm::StartIdentityService>(NM).request("start_identity_service_request"),
+    EndpointInfo::of::<nm::StartAuthenticatedService>(NM)
+        .request("start_authenticated_service_request"),
+    EndpointInfo::of::<nm::StartUppercaseService>(NM).request("start_uppercase_service_request"),
+    EndpointInfo::of::<nm::StartEchoerService>(NM).request("start_echoer_service_request"),
+    EndpointInfo::of::<nm::StartHopService>(NM).request("start_hop_service_request"),
+    EndpointInfo::of::<nm::StartAuthenticatorService>(NM).request("start_authenticator_request"),
+    EndpointInfo::of::<nm::StartVerifierService>(NM).request("start_verifier_service"),
+    EndpointInfo::of::<nm::StartCredentialsService>(NM).request("start_credentials_service"),
+    EndpointInfo::of::<nm::StartOktaIdentityProviderService>(NM)
+        .request("start_okta_identity_provider_request"),
+    EndpointInfo::of::<nm::StartKafkaConsumerService>(NM).request("start_kafka_consumer_service"),
+    EndpointInfo::of::<nm::StartKafkaProducerService>(NM).request("start_kafka_producer_service"),
+    EndpointInfo::of::<nm::StartKafkaOutletService>(NM).request("start_kafka_outlet_service"),
+    EndpointInfo::of::<nm::CreateForwarder>(NM)
+        .request("create_forwarder")
+        .response("forwarder_info"),
+    EndpointInfo::of::<nm::ListInlets>(NM).response("inlet_list"),
+    EndpointInfo::of::<nm::CreateInlet>(NM)
+        .request("create_inlet")
+        .response("inlet_status"),
+    EndpointInfo::of::<nm::ShowInlet>(NM).response("inlet_status"),
+    EndpointInfo::of::<nm::DeleteInlet>(NM).response("inlet_status"),
+    EndpointInfo::of::<nm::ListOutlets>(NM).response("outlet_list"),
+    EndpointInfo::of::<nm::CreateOutlet>(NM)
+        .request("create_outlet")
+        .response("outlet_status"),
+    EndpointInfo::of::<nm::ShowOutlet>(NM).response("outlet_status"),
+    EndpointInfo::of::<nm::DeleteOutlet>(NM).response("outlet_status"),
+    EndpointInfo::of::<nm::ListWorkers>(NM).response("worker_list"),
+    EndpointInfo::of::<nm::ListPolicies>(NM).response("policy_list"),
+    EndpointInfo::of::<nm::GetPolicy>(NM).response("policy"),
+    EndpointInfo::of::<nm::SetPolicy>(NM).request("policy"),
+    EndpointInfo::of::<nm::DeletePolicy>(NM),
+    EndpointInfo::of::<nm::CreateSpace>(NM)
+        .request("create_space_request")
+        .response("space"),
+    EndpointInfo::of::<nm::ListSpaces>(NM)
+        .request("bare_cloud_request")
+        .response("spaces"),
+    EndpointInfo::of::<nm::GetSpace>(NM)
+        .request("bare_cloud_request")
+        .response("space"),
+    EndpointInfo::of::<nm::DeleteSpace>(NM).request("bare_cloud_request"),
+    EndpointInfo::of::<nm::CreateProject>(NM)
+        .request("create_project_request")
+        .response("project"),
+    EndpointInfo::of::<nm::ListProjects>(NM)
+        .request("bare_cloud_request")
+        .response("projects"),
+    EndpointInfo::of::<nm::GetProject>(NM)
+        .request("bare_cloud_request")
+        .response("project"),
+    EndpointInfo::of::<nm::DeleteProject>(NM).request("bare_cloud_request"),
+    EndpointInfo::of::<nm::EnrollAuth0>(NM).request("authenticate_auth0_token_request"),
+    EndpointInfo::of::<nm::GenerateEnrollmentToken>(NM)
+        .request("generate_enrollment_token_request")
+        .response("enrollment_token"),
+    EndpointInfo::of::<nm::AuthenticateEnrollmentToken>(NM)
+        .request("authenticate_enrollment_token_request"),
+    EndpointInfo::of::<nm::ActivateSubscription>(NM)
+        .request("activate_subscription_request")
+        .response("subscription"),
+    EndpointInfo::of::<nm::ListSubscriptions>(NM)
+        .request("bare_cloud_request")
+        .response("subscriptions"),
+    EndpointInfo::of::<nm::GetSubscription>(NM)
+        .request("bare_cloud_request")
+        .response("subscription"),
+    EndpointInfo::of::<nm::UpdateSubscriptionContactInfo>(NM)
+        .request("update_subscription_contact_info_request")
+        .response("subscription"),
+    EndpointInfo::of::<nm::UpdateSubscriptionSpace>(NM)
+        .request("update_subscription_space_request")
+        .response("subscription"),
+    EndpointInfo::of::<nm::Unsubscribe>(NM)
+        .request("bare_cloud_request")
+        .response("subscription"),
+    EndpointInfo::of::<nm::SendMessage>(NM)
+        .request("send_message")
+        .response("message_reply"),
+    EndpointInfo::of::<nm::ListAddons>(NM)
+        .request("bare_cloud_request")
+        .response("addons"),
+    EndpointInfo::of::<nm::ConfigureOktaAddon>(NM).request("configure_okta_addon_request"),
+    EndpointInfo::of::<nm::ConfigureInfluxDBAddon>(NM).request("configure_influxdb_addon_request"),
+    EndpointInfo::of::<nm::ConfigureConfluentAddon>(NM)
+        .request("configure_confluent_addon_request"),
+    EndpointInfo::of::<nm::DisableAddon>(NM).request("bare_cloud_request"),
+
+    // Vault service
+    EndpointInfo::of::<vault::GetSecret>(VAULT)
+        .request("get_secret_request")
+        .response("get_secret_response"),
+    EndpointInfo::of::<vault::GetPublicKey>(VAULT).response("public_key_response"),
+    EndpointInfo::of::<vault::CreateSecret>(VAULT)
+        .request("create_secret_request")
+        .response("create_secret_response"),
+    EndpointInfo::of::<vault::DeleteSecret>(VAULT),
+    EndpointInfo::of::<vault::Ecdh>(VAULT)
+        .request("ecdh_request")
+        .response("ecdh_response"),
+    EndpointInfo::of::<vault::ComputeKeyId>(VAULT)
+        .request("compute_key_id_request")
+        .response("compute_key_id_response"),
+    EndpointInfo::of::<vault::Sha256>(VAULT)
+        .request("sha256_request")
+        .response("sha256_response"),
+    EndpointInfo::of::<vault::HkdfSha256>(VAULT)
+        .request("hkdf_sha256_request")
+        .response("hkdf_sha256_response"),
+    EndpointInfo::of::<vault::Sign>(VAULT)
+        .request("sign_request")
+        .response("sign_response"),
+    EndpointInfo::of::<vault::Verify>(VAULT)
+        .request("vault_verify_request")
+        .response("vault_verify_response"),
+    EndpointInfo::of::<vault::Encrypt>(VAULT)
+        .request("encrypt_request")
+        .response("encrypt_response"),
+    EndpointInfo::of::<vault::Decrypt>(VAULT)
+        .request("decrypt_request")
+        .response("decrypt_response"),
+
+    // Identity service
+    EndpointInfo::of::<identity::GetIdentity>(IDENTITY)
+        .response("identity_create_response"),
+    EndpointInfo::of::<identity::CreateIdentity>(IDENTITY)
+        .response("identity_create_response"),
+    EndpointInfo::of::<identity::ValidateIdentityChangeHistory>(IDENTITY)
+        .request("validate_identity_change_history_request")
+        .response("validate_identity_change_history_response"),
+    EndpointInfo::of::<identity::CreateSignature>(IDENTITY)
+        .request("create_signature_request")
+        .response("create_signature_response"),
+    EndpointInfo::of::<identity::VerifySignature>(IDENTITY)
+        .request("verify_signature_request")
+        .response("verify_signature_response"),
+    EndpointInfo::of::<identity::CompareIdentityChangeHistory>(IDENTITY)
+        .request("compare_identity_change_history_request")
+        .response("identity_history_comparison"),
+
+    // Authenticated attributes
+    EndpointInfo::of::<auth::ListAttributes>(AUTHENTICATED).response("attributes_entries"),
+    EndpointInfo::of::<auth::GetAttributes>(AUTHENTICATED).response("attributes_entry"),
+
+    // Credential verifier
+    EndpointInfo::of::<verifier::Verify>(VERIFIER)
+        .request("verify_request")
+        .response("verify_response"),
+];
+
+/// Find the endpoint matching a request, optionally restricted to the
+/// endpoints of a service.
+pub fn find(method: Method, path: &str, service: Option<&str>) -> Option<&'static EndpointInfo> {
+    ENDPOINTS
+        .iter()
+        .filter(|e| service.map_or(true, |s| e.service == s))
+        .find(|e| e.matches(method, path))
+}
+
+/// The API description as a JSON document, with the endpoints and the
+/// CDDL schema of their bodies.
+pub fn document() -> Json {
+    json!({
+        "endpoints": ENDPOINTS,
+        "schema": schema::text(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Server;
+    use crate::identity::IdentityService;
+    use crate::nodes::NodeManagerWorker;
+    use crate::vault::VaultService;
+    use crate::verifier::Verifier;
+    use ockam_core::api::rpc::Handler;
+
+    /// The method and path of the endpoints of a service, sorted.
+    fn routes(it: impl Iterator<Item = (Method, &'static str)>) -> Vec<String> {
+        let mut routes: Vec<String> = it.map(|(m, p)| format!("{m} {p}")).collect();
+        routes.sort();
+        routes
+    }
+
+    fn assert_agree<S: Send, C: Send>(service: &str, handlers: &[Handler<S, C>]) {
+        assert_eq!(
+            routes(
+                ENDPOINTS
+                    .iter()
+                    .filter(|e| e.service == service)
+                    .map(|e| (e.method, e.path))
+            ),
+            routes(handlers.iter().map(|h| (h.method(), h.path()))),
+            "endpoints of {service}"
+        )
+    }
+
+    #[test]
+    fn endpoints_agree_with_route_tables() {
+        assert_agree(NM, NodeManagerWorker::HANDLERS);
+        assert_agree(VAULT, VaultService::HANDLERS);
+        assert_agree(IDENTITY, IdentityService::HANDLERS);
+        assert_agree(AUTHENTICATED, Server::HANDLERS);
+        assert_agree(VERIFIER, Verifier::HANDLERS);
+    }
+}
@@ -0,0 +1,269 @@
+//! Conversion between Ockam credentials and W3C Verifiable Credentials encoded as JWT (JWT-VC)
+
+use crate::error::ApiError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::Arc;
+use ockam_core::vault::{PublicKey, SecretType, Signature};
+use ockam_core::Result;
+use ockam_identity::authenticated_storage::AttributesEntry;
+use ockam_identity::credential::{
+    Credential, CredentialData, Timestamp, Unverified, MAX_CREDENTIAL_VALIDITY,
+};
+use ockam_identity::{Identity, IdentityIdentifier, IdentityStateConst, IdentityVault};
+use p256::ecdsa::{Signature as P256Signature, VerifyingKey};
+use p256::pkcs8::{DecodePublicKey, EncodePublicKey};
+use p256::EncodedPoint;
+use serde_json::{json, Map, Value};
+
+const VC_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+const VC_TYPE: &str = "VerifiableCredential";
+
+/// Export a credential issued by the given identity as a JWT-VC.
+///
+/// The token is signed with the issuer key with the given label, the root key when not set.
+/// Ed25519 keys produce `EdDSA` tokens and NIST P-256 keys produce `ES256` tokens.
+/// Attribute values must be valid UTF-8 since they are exported as JSON strings.
+pub async fn export_credential(
+    issuer: &Identity,
+    credential: &Credential,
+    key_label: Option<&str>,
+) -> Result<String> {
+    let unverified: CredentialData<Unverified> = minicbor::decode(credential.unverified_data())?;
+    let data = issuer
+        .to_public()
+        .await?
+        .verify_credential(credential, unverified.unverified_subject(), issuer.vault())
+        .await?;
+
+    let label = key_label.unwrap_or(IdentityStateConst::ROOT_LABEL);
+    let public_key = issuer.change_history().await.get_public_key(label)?;
+
+    let mut subject = Map::new();
+    for (name, value) in data.attributes().iter() {
+        if name == "id" {
+            return Err(ApiError::generic(
+                "the `id` attribute is reserved for the credential subject",
+            ));
+        }
+        let value = std::str::from_utf8(value)
+            .map_err(|_| ApiError::message(format!("attribute {name} is not valid UTF-8")))?;
+        subject.insert(name.clone(), Value::String(value.to_string()));
+    }
+    subject.insert("id".to_string(), Value::String(data.subject().to_string()));
+
+    let header = json!({
+        "alg": algorithm(&public_key)?,
+        "typ": "JWT",
+        "kid": format!("{}#{}", issuer.identifier(), label),
+    });
+    let mut payload = json!({
+        "iss": issuer.identifier().to_string(),
+        "sub": data.subject().to_string(),
+        "iat": data.created_at().unix_time(),
+        "nbf": data.created_at().unix_time(),
+        "exp": data.expires_at().unix_time(),
+        "vc": {
+            "@context": [VC_CONTEXT],
+            "type": [VC_TYPE],
+            "credentialSubject": subject,
+        },
+    });
+    if let Some(audience) = data.audience() {
+        payload["aud"] = json!(audience);
+    }
+
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header).map_err(ApiError::from)?),
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&payload).map_err(ApiError::from)?)
+    );
+    let signature = issuer
+        .create_signature(signing_input.as_bytes(), Some(label))
+        .await?;
+    let signature = match public_key.stype() {
+        // The vault produces DER signatures while JWS expects the raw `r || s` form
+        SecretType::NistP256 => P256Signature::from_der(signature.as_ref())
+            .map_err(ApiError::wrap)?
+            .to_vec(),
+        _ => signature.as_ref().to_vec(),
+    };
+
+    Ok(format!(
+        "{signing_input}.{}",
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+/// Verify a JWT-VC signed by a trusted issuer key and map its claims to the attributes of
+/// its subject.
+///
+/// The subject must be an Ockam identity. Claims of the credential subject other than `id`
+/// become attributes, string claims are kept as is and other claims are stored as JSON.
+/// The attributes are attested by the `iss` claim when it is an Ockam identity and are
+/// restricted to the `aud` claim when present. The token must have an `exp` claim and the
+/// attributes expire at most [`MAX_CREDENTIAL_VALIDITY`] from now.
+pub async fn verify_jwt_vc(
+    token: &str,
+    issuer_key: &PublicKey,
+    vault: Arc<dyn IdentityVault>,
+) -> Result<(IdentityIdentifier, AttributesEntry)> {
+    let token = token.trim();
+    let mut parts = token.split('.');
+    let (header, payload, signature) =
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(h), Some(p), Some(s), None) => (h, p, s),
+            _ => return Err(ApiError::generic("invalid JWT format")),
+        };
+
+    let signing_input = &token[..header.len() + 1 + payload.len()];
+    let header: Value = decode_part(header)?;
+    if header["alg"].as_str() != Some(algorithm(issuer_key)?) {
+        return Err(ApiError::generic(
+            "JWT algorithm doesn't match the issuer key",
+        ));
+    }
+
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(ApiError::wrap)?;
+    let signature = match issuer_key.stype() {
+        SecretType::NistP256 => P256Signature::try_from(signature.as_slice())
+            .map_err(ApiError::wrap)?
+            .to_der()
+            .as_bytes()
+            .to_vec(),
+        _ => signature,
+    };
+    if !vault
+        .verify(
+            &Signature::new(signature),
+            issuer_key,
+            signing_input.as_bytes(),
+        )
+        .await?
+    {
+        return Err(ApiError::generic("invalid JWT signature"));
+    }
+
+    let payload: Value = decode_part(payload)?;
+    let now = Timestamp::now().ok_or_else(|| ApiError::generic("invalid system time"))?;
+    let expires = payload["exp"]
+        .as_u64()
+        .ok_or_else(|| ApiError::generic("JWT has no expiration time"))?;
+    if expires <= now.unix_time() {
+        return Err(ApiError::generic("JWT has expired"));
+    }
+    // The attributes don't outlive a credential issued now
+    let expires = Timestamp::from(expires.min(now.unix_time() + MAX_CREDENTIAL_VALIDITY.as_secs()));
+    if matches!(payload["nbf"].as_u64(), Some(nbf) if nbf > now.unix_time()) {
+        return Err(ApiError::generic("JWT is not valid yet"));
+    }
+
+    let credential_subject = payload["vc"]["credentialSubject"]
+        .as_object()
+        .ok_or_else(|| ApiError::generic("JWT has no credential subject"))?;
+    let subject = credential_subject
+        .get("id")
+        .and_then(Value::as_str)
+        .or_else(|| payload["sub"].as_str())
+        .ok_or_else(|| ApiError::generic("JWT has no subject"))?;
+    let subject = IdentityIdentifier::try_from(subject)?;
+
+    let attrs: BTreeMap<String, Vec<u8>> = credential_subject
+        .iter()
+        .filter(|(name, _)| name.as_str() != "id")
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(s) => s.as_bytes().to_vec(),
+                v => v.to_string().into_bytes(),
+            };
+            (name.clone(), value)
+        })
+        .collect();
+    let attested_by = payload["iss"]
+        .as_str()
+        .and_then(|iss| IdentityIdentifier::try_from(iss).ok());
+    let audience = match &payload["aud"] {
+        Value::String(aud) => Some(vec![aud.clone()]),
+        Value::Array(aud) => Some(
+            aud.iter()
+                .filter_map(|a| a.as_str().map(String::from))
+                .collect(),
+        ),
+        _ => None,
+    };
+
+    Ok((
+        subject,
+        AttributesEntry::new(attrs, now, Some(expires), attested_by).with_audience(audience),
+    ))
+}
+
+/// Encode a public key as a JSON Web Key
+pub fn public_key_to_jwk(key: &PublicKey) -> Result<Value> {
+    match key.stype() {
+        SecretType::Ed25519 => Ok(json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "x": URL_SAFE_NO_PAD.encode(key.data()),
+        })),
+        SecretType::NistP256 => {
+            let point = VerifyingKey::from_public_key_der(key.data())
+                .map_err(ApiError::message)?
+                .to_encoded_point(false);
+            match (point.x(), point.y()) {
+                (Some(x), Some(y)) => Ok(json!({
+                    "kty": "EC",
+                    "crv": "P-256",
+                    "x": URL_SAFE_NO_PAD.encode(x),
+                    "y": URL_SAFE_NO_PAD.encode(y),
+                })),
+                _ => Err(ApiError::generic("invalid P-256 public key")),
+            }
+        }
+        _ => Err(ApiError::generic("unsupported key type")),
+    }
+}
+
+/// Decode a public key from a JSON Web Key
+pub fn public_key_from_jwk(jwk: &Value) -> Result<PublicKey> {
+    let coordinate = |name: &str| -> Result<Vec<u8>> {
+        let value = jwk[name]
+            .as_str()
+            .ok_or_else(|| ApiError::message(format!("JWK has no `{name}` coordinate")))?;
+        URL_SAFE_NO_PAD.decode(value).map_err(ApiError::wrap)
+    };
+    match (jwk["kty"].as_str(), jwk["crv"].as_str()) {
+        (Some("OKP"), Some("Ed25519")) => Ok(PublicKey::new(coordinate("x")?, SecretType::Ed25519)),
+        (Some("EC"), Some("P-256")) => {
+            let (x, y) = (coordinate("x")?, coordinate("y")?);
+            if x.len() != 32 || y.len() != 32 {
+                return Err(ApiError::generic("invalid P-256 coordinates"));
+            }
+            let point = EncodedPoint::from_affine_coordinates(
+                x.as_slice().into(),
+                y.as_slice().into(),
+                false,
+            );
+            let der = VerifyingKey::from_encoded_point(&point)
+                .map_err(ApiError::wrap)?
+                .to_public_key_der()
+                .map_err(ApiError::message)?;
+            Ok(PublicKey::new(der.as_ref().to_vec(), SecretType::NistP256))
+        }
+        _ => Err(ApiError::generic("unsupported JWK key type")),
+    }
+}
+
+fn algorithm(key: &PublicKey) -> Result<&'static str> {
+    match key.stype() {
+        SecretType::Ed25519 => Ok("EdDSA"),
+        SecretType::NistP256 => Ok("ES256"),
+        _ => Err(ApiError::generic("unsupported key type")),
+    }
+}
+
+fn decode_part<T: serde::de::DeserializeOwned>(part: &str) -> Result<T> {
+    let bytes = URL_SAFE_NO_PAD.decode(part).map_err(ApiError::wrap)?;
+    Ok(serde_json::from_slice(&bytes).map_err(ApiError::from)?)
+}
@@ -1,14 +1,16 @@
 #[cfg(test)]
 mod test {
-    use bytes::{Buf, BufMut, BytesMut};
+    use bytes::{Buf, BufMut, Bytes, BytesMut};
     use indexmap::IndexMap;
     use kafka_protocol::messages::produce_request::{PartitionProduceData, TopicProduceData};
     use kafka_protocol::messages::{
         fetch_request::{FetchPartition, FetchTopic},
         fetch_response::FetchableTopicResponse,
         fetch_response::PartitionData,
-        ApiKey, BrokerId, FetchRequest, FetchResponse, ProduceRequest, RequestHeader,
-        ResponseHeader, TopicName,
+        metadata_response::MetadataResponseBroker,
+        ApiKey, BrokerId, FetchRequest, FetchResponse, MetadataRequest, MetadataResponse,
+        ProduceRequest, RequestHeader, ResponseHeader, SaslAuthenticateRequest,
+        SaslAuthenticateResponse, SaslHandshakeRequest, SaslHandshakeResponse, TopicName,
     };
     use kafka_protocol::protocol::Builder;
     use kafka_protocol::protocol::Decodable as KafkaDecodable;
@@ -19,12 +21,12 @@ mod test {
         Compression, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions, TimestampType,
     };
     use ockam::compat::tokio::io::DuplexStream;
-    use ockam::Context;
-    use ockam_core::async_trait;
+    use ockam::{Context, ForwardingService};
     use ockam_core::compat::sync::Arc;
-    use ockam_core::{route, Address, AllowAll, Route};
-    use ockam_identity::TrustEveryonePolicy;
+    use ockam_core::{async_trait, route, Address, AllowAll, RelayMessage, Route};
+    use ockam_identity::{Identity, TrustEveryonePolicy};
     use ockam_node::compat::tokio;
+    use std::io::Write;
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::time::Duration;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -32,61 +34,52 @@ mod test {
     use tokio::net::TcpStream;
     use tokio::sync::Mutex;
     use tokio::task::JoinHandle;
+    use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+    use tokio_rustls::TlsAcceptor;
     use uuid::Uuid;
 
-    use crate::hop::Hop;
     use crate::kafka::protocol_aware::utils::{encode_request, encode_response};
-    use crate::kafka::secure_channel_map::ForwarderCreator;
+    use crate::kafka::protocol_aware::ENCRYPTED_KEY_HEADER;
     use crate::kafka::{
-        KafkaPortalListener, KafkaSecureChannelControllerImpl,
-        KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS,
+        AnyTopicAccessControl, KafkaBrokerConnectionOptions, KafkaEncryptionOptions,
+        KafkaOutletBrokerMap, KafkaOutletListener, KafkaPortalListener,
+        KafkaSecureChannelControllerImpl, ProducerTrust, TopicAccessControl,
+        KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS, ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS,
     };
     use crate::nodes::registry::KafkaServiceKind;
     use crate::test::NodeManagerHandle;
+    use crate::DefaultAddress;
 
-    //TODO: upgrade to 13 by adding a metadata request to map uuid<=>topic_name
     const TEST_KAFKA_API_VERSION: i16 = 12;
 
-    struct HopForwarderCreator {}
-
-    #[async_trait]
-    impl ForwarderCreator for HopForwarderCreator {
-        async fn create_forwarder(&self, context: &Context, alias: String) -> ockam::Result<()> {
-            trace!("creating mock forwarder for: {alias}");
-            //replicating the same logic of the orchestrator by adding consumer__
-            context
-                .start_worker(
-                    Address::from_string(format!("consumer__{alias}")),
-                    Hop,
-                    AllowAll,
-                    AllowAll,
-                )
-                .await?;
-            Ok(())
-        }
-    }
-
     async fn create_kafka_service(
         context: &Context,
         handle: &NodeManagerHandle,
+        identity: Arc<Identity>,
         listener_address: Address,
         outlet_route: Route,
         kind: KafkaServiceKind,
+        encryption_options: KafkaEncryptionOptions,
     ) -> ockam::Result<u16> {
-        let secure_channel_controller = KafkaSecureChannelControllerImpl::new_extended(
-            handle.identity.clone(),
-            route![],
-            HopForwarderCreator {},
-        );
+        //the forwarding service of the same node acts as relay, the producer
+        //always uses the identity of the node
+        let secure_channel_controller =
+            KafkaSecureChannelControllerImpl::new_with_relay(identity.clone(), route![])
+                .with_producer_trust(ProducerTrust::Identifiers(vec![handle
+                    .identity
+                    .identifier()
+                    .clone()]));
 
-        //the possibility to accept secure channels is the only real
+        //the possibility to distribute data keys is the only real
         //difference between consumer and producer
-        if let KafkaServiceKind::Consumer = kind {
+        if let KafkaServiceKind::Producer = kind {
             secure_channel_controller
-                .create_consumer_listener(context)
+                .create_producer_listener(
+                    context,
+                    Arc::new(AnyTopicAccessControl(Arc::new(AllowAll))),
+                )
                 .await?;
-            handle
-                .identity
+            identity
                 .create_secure_channel_listener(
                     KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS,
                     TrustEveryonePolicy,
@@ -106,6 +99,7 @@ mod test {
         KafkaPortalListener::create(
             context,
             secure_channel_controller.into_trait(),
+            encryption_options,
             outlet_route,
             listener_address,
             "127.0.0.1".parse().unwrap(),
@@ -122,47 +116,604 @@ mod test {
         context: &mut Context,
     ) -> ockam::Result<()> {
         let handler = crate::util::test::start_manager_for_tests(context).await?;
+        let (encrypted_records, plain_records) = produce_and_fetch_through_mock_kafka(
+            context,
+            &handler,
+            vec![create_record(None, Default::default())],
+            KafkaEncryptionOptions::default(),
+        )
+        .await?;
+        let encrypted_record = &encrypted_records[0];
+        let plain_record = &plain_records[0];
+
+        assert_ne!(
+            encrypted_record.value.as_ref().unwrap(),
+            "hello world!".as_bytes()
+        );
+        assert_eq!(
+            plain_record.value.as_ref().unwrap(),
+            "hello world!".as_bytes()
+        );
+        Ok(())
+    }
 
-        let consumer_bootstrap_port = create_kafka_service(
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn producer__flow_with_mock_kafka__key_and_headers_encryption_and_decryption(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let mut headers = IndexMap::new();
+        headers.insert(
+            StrBytes::from_str("customer-email"),
+            Some(Bytes::from("alice@example.com")),
+        );
+        headers.insert(
+            StrBytes::from_str("trace-id"),
+            Some(Bytes::from("1234567890")),
+        );
+
+        let encryption_options = KafkaEncryptionOptions {
+            key_hash_secret: Some(b"secret".to_vec()),
+            encrypted_headers: vec!["customer-email".to_string()],
+        };
+
+        let handler = crate::util::test::start_manager_for_tests(context).await?;
+        let (encrypted_records, plain_records) = produce_and_fetch_through_mock_kafka(
             context,
             &handler,
-            Address::from_string("kafka_consumer_listener"),
-            route!["kafka_consumer_outlet"],
-            KafkaServiceKind::Consumer,
+            vec![
+                create_record(Some(Bytes::from("customer-42")), headers.clone()),
+                create_record(Some(Bytes::from("customer-42")), headers.clone()),
+            ],
+            encryption_options,
         )
         .await?;
+        let encrypted_record = &encrypted_records[0];
+        let plain_record = &plain_records[0];
 
-        let producer_bootstrap_port = create_kafka_service(
+        //the broker sees only a hash of the key, the same key always produces the same hash
+        let broker_key = encrypted_record.key.as_ref().unwrap();
+        assert_ne!(broker_key, "customer-42".as_bytes());
+        assert_eq!(broker_key, encrypted_records[1].key.as_ref().unwrap());
+
+        assert_ne!(
+            encrypted_record
+                .headers
+                .get("customer-email")
+                .unwrap()
+                .as_ref()
+                .unwrap(),
+            "alice@example.com".as_bytes()
+        );
+        assert_eq!(
+            encrypted_record
+                .headers
+                .get("trace-id")
+                .unwrap()
+                .as_ref()
+                .unwrap(),
+            "1234567890".as_bytes()
+        );
+        assert!(encrypted_record.headers.contains_key(ENCRYPTED_KEY_HEADER));
+
+        assert_eq!(plain_record.key.as_ref().unwrap(), "customer-42".as_bytes());
+        assert_eq!(plain_record.headers, headers);
+        Ok(())
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 10_000)]
+    async fn producer__flow_with_mock_kafka__several_consumer_identities_decrypt(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let handler = crate::util::test::start_manager_for_tests(context).await?;
+        let other_identity = Arc::new(Identity::create(context, handler.identity.vault()).await?);
+        assert_ne!(handler.identity.identifier(), other_identity.identifier());
+
+        let (encrypted_records, plain_records) = produce_and_fetch_with_consumers(
             context,
             &handler,
-            Address::from_string("kafka_producer_listener"),
-            route!["kafka_producer_outlet"],
-            KafkaServiceKind::Producer,
+            vec![create_record(None, Default::default())],
+            KafkaEncryptionOptions::default(),
+            vec![handler.identity.clone(), other_identity],
         )
         .await?;
 
-        //before produce a new key, the consumer has to issue a Fetch request
-        // so the sidecar can react by creating the forwarder for the partition 1 of 'my-topic'
-        {
-            let mut consumer_mock_kafka = TcpServerSimulator::start("127.0.0.1:0").await;
-            handler
-                .tcp
-                .create_outlet(
-                    "kafka_consumer_outlet",
-                    format!("127.0.0.1:{}", consumer_mock_kafka.port),
-                    AllowAll,
-                )
-                .await?;
+        assert_ne!(
+            encrypted_records[0].value.as_ref().unwrap(),
+            "hello world!".as_bytes()
+        );
+        assert_eq!(plain_records.len(), 2);
+        for records in plain_records {
+            assert_eq!(
+                records[0].value.as_ref().unwrap(),
+                "hello world!".as_bytes()
+            );
+        }
+        Ok(())
+    }
 
-            simulate_first_kafka_consumer_empty_reply_and_ignore_result(
-                consumer_bootstrap_port,
-                &mut consumer_mock_kafka,
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 10_000)]
+    async fn consumer__untrusted_producer__data_key_not_retrieved(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let handler = crate::util::test::start_manager_for_tests(context).await?;
+        ForwardingService::create(
+            context,
+            DefaultAddress::FORWARDING_SERVICE,
+            AllowAll,
+            AllowAll,
+        )
+        .await?;
+
+        let producer =
+            KafkaSecureChannelControllerImpl::new_with_relay(handler.identity.clone(), route![]);
+        producer
+            .create_producer_listener(context, Arc::new(AnyTopicAccessControl(Arc::new(AllowAll))))
+            .await?;
+        handler
+            .identity
+            .create_secure_channel_listener(
+                KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS,
+                TrustEveryonePolicy,
             )
-            .await;
-            drop(consumer_mock_kafka);
-            //drop the outlet and re-create it when we need it later
-            context.stop_worker("kafka_consumer_outlet").await?;
+            .await?;
+        let encrypted = producer
+            .into_trait()
+            .encrypt_content_for(context, "topic", b"hello world!".to_vec())
+            .await?;
+
+        let consumer_identity =
+            Arc::new(Identity::create(context, handler.identity.vault()).await?);
+        let impostor = Identity::create(context, handler.identity.vault()).await?;
+
+        //whoever serves the data key could forge records
+        let consumer =
+            KafkaSecureChannelControllerImpl::new_with_relay(consumer_identity.clone(), route![])
+                .with_producer_trust(ProducerTrust::Identifiers(vec![impostor
+                    .identifier()
+                    .clone()]))
+                .into_trait();
+        //the secure channel is never established, rather than waiting for its timeout
+        //the retrieval is only given a few seconds
+        let retrieval = tokio::time::timeout(
+            Duration::from_secs(2),
+            consumer.decrypt_content_for(
+                context,
+                "topic",
+                &encrypted.producer_id,
+                encrypted.data_key_id,
+                encrypted.content.clone(),
+            ),
+        )
+        .await;
+        assert!(!matches!(retrieval, Ok(Ok(_))));
+
+        let consumer =
+            KafkaSecureChannelControllerImpl::new_with_relay(consumer_identity, route![])
+                .with_producer_trust(ProducerTrust::Identifiers(vec![handler
+                    .identity
+                    .identifier()
+                    .clone()]))
+                .into_trait();
+        let content = consumer
+            .decrypt_content_for(
+                context,
+                "topic",
+                &encrypted.producer_id,
+                encrypted.data_key_id,
+                encrypted.content,
+            )
+            .await?;
+        assert_eq!(content, b"hello world!");
+
+        context.stop().await
+    }
+
+    /// Only serves the data keys of one topic
+    struct SingleTopicAccessControl(&'static str);
+
+    #[async_trait]
+    impl TopicAccessControl for SingleTopicAccessControl {
+        async fn is_authorized(
+            &self,
+            topic_name: &str,
+            _message: &RelayMessage,
+        ) -> ockam::Result<bool> {
+            Ok(topic_name == self.0)
         }
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 10_000)]
+    async fn consumer__other_topic__data_key_not_retrieved(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let handler = crate::util::test::start_manager_for_tests(context).await?;
+        ForwardingService::create(
+            context,
+            DefaultAddress::FORWARDING_SERVICE,
+            AllowAll,
+            AllowAll,
+        )
+        .await?;
+
+        let producer =
+            KafkaSecureChannelControllerImpl::new_with_relay(handler.identity.clone(), route![]);
+        producer
+            .create_producer_listener(context, Arc::new(SingleTopicAccessControl("topic-a")))
+            .await?;
+        handler
+            .identity
+            .create_secure_channel_listener(
+                KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS,
+                TrustEveryonePolicy,
+            )
+            .await?;
+        let producer = producer.into_trait();
+        let encrypted_a = producer
+            .encrypt_content_for(context, "topic-a", b"hello a!".to_vec())
+            .await?;
+        let encrypted_b = producer
+            .encrypt_content_for(context, "topic-b", b"hello b!".to_vec())
+            .await?;
+
+        let consumer_identity =
+            Arc::new(Identity::create(context, handler.identity.vault()).await?);
+        let consumer =
+            KafkaSecureChannelControllerImpl::new_with_relay(consumer_identity, route![])
+                .with_producer_trust(ProducerTrust::Identifiers(vec![handler
+                    .identity
+                    .identifier()
+                    .clone()]))
+                .into_trait();
+
+        //the consumer is not authorized for the topic of the key
+        let retrieval = consumer
+            .decrypt_content_for(
+                context,
+                "topic-b",
+                &encrypted_b.producer_id,
+                encrypted_b.data_key_id,
+                encrypted_b.content.clone(),
+            )
+            .await;
+        assert!(retrieval.is_err());
+
+        //the key doesn't belong to the topic the consumer is authorized for
+        let retrieval = consumer
+            .decrypt_content_for(
+                context,
+                "topic-a",
+                &encrypted_b.producer_id,
+                encrypted_b.data_key_id,
+                encrypted_b.content,
+            )
+            .await;
+        assert!(retrieval.is_err());
+
+        let content = consumer
+            .decrypt_content_for(
+                context,
+                "topic-a",
+                &encrypted_a.producer_id,
+                encrypted_a.data_key_id,
+                encrypted_a.content,
+            )
+            .await?;
+        assert_eq!(content, b"hello a!");
+
+        context.stop().await
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 10_000)]
+    async fn outlet__tls_broker_with_sasl__credentials_injected_and_brokers_exposed(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let handler = crate::util::test::start_manager_for_tests(context).await?;
+
+        let certificate = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let mut ca_certificate = tempfile::NamedTempFile::new().unwrap();
+        ca_certificate
+            .write_all(certificate.serialize_pem().unwrap().as_bytes())
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let broker_port = listener.local_addr().unwrap().port();
+        let broker_handle = tokio::spawn(simulate_tls_broker_with_sasl(
+            listener,
+            certificate,
+            broker_port,
+        ));
+
+        let options = KafkaBrokerConnectionOptions::new()
+            .with_tls(Some(ca_certificate.path()))?
+            .with_sasl_plain("user", "password");
+        let broker_map = KafkaOutletBrokerMap::new(options, Arc::new(AllowAll));
+        KafkaOutletListener::create(
+            context,
+            Address::from_string(ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS),
+            format!("localhost:{broker_port}"),
+            broker_map.clone(),
+        )
+        .await?;
+
+        let (_, socket_address) = handler
+            .tcp
+            .create_inlet(
+                "127.0.0.1:0".to_string(),
+                route![ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS],
+                AllowAll,
+            )
+            .await?;
+
+        //the client doesn't know anything about tls or sasl
+        let mut stream = TcpStream::connect(socket_address).await.unwrap();
+        send_kafka_request(
+            &mut stream,
+            create_request_header(ApiKey::MetadataKey, TEST_KAFKA_API_VERSION, 2),
+            MetadataRequest::default(),
+            ApiKey::MetadataKey,
+        )
+        .await;
+        let response: MetadataResponse =
+            read_kafka_response::<_, ResponseHeader, MetadataResponse>(
+                &mut stream,
+                ApiKey::MetadataKey,
+            )
+            .await;
+        assert_eq!(1, response.brokers.len());
+
+        let auth_bytes = broker_handle.await.unwrap();
+        assert_eq!(b"\0user\0password".as_slice(), auth_bytes.as_ref());
+        assert_eq!(
+            Some(format!("localhost:{broker_port}")),
+            broker_map.retrieve_broker(1).await
+        );
+
+        drop(stream);
+        context.stop().await
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn outlet__client_sasl_exchange__passed_through_to_broker(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let handler = crate::util::test::start_manager_for_tests(context).await?;
+        let mut mock_kafka = TcpServerSimulator::start("127.0.0.1:0").await;
+
+        KafkaOutletListener::create(
+            context,
+            Address::from_string(ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS),
+            format!("127.0.0.1:{}", mock_kafka.port),
+            KafkaOutletBrokerMap::new(KafkaBrokerConnectionOptions::new(), Arc::new(AllowAll)),
+        )
+        .await?;
+
+        let (_, socket_address) = handler
+            .tcp
+            .create_inlet(
+                "127.0.0.1:0".to_string(),
+                route![ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS],
+                AllowAll,
+            )
+            .await?;
+
+        let mut stream = TcpStream::connect(socket_address).await.unwrap();
+        let request = encode_request(
+            &create_request_header(ApiKey::SaslHandshakeKey, 1, 0),
+            &SaslHandshakeRequest::builder()
+                .mechanism(StrBytes::from_str("SCRAM-SHA-512"))
+                .build()
+                .unwrap(),
+            1,
+            ApiKey::SaslHandshakeKey,
+        )
+        .unwrap();
+        stream.write_u32(request.len() as u32).await.unwrap();
+        stream.write_all(&request).await.unwrap();
+
+        let received = read_packet(mock_kafka.stream()).await;
+        assert_eq!(&request[..], &received[0..request.len()]);
+
+        let response = encode_response(
+            &ResponseHeader::builder()
+                .correlation_id(0)
+                .unknown_tagged_fields(Default::default())
+                .build()
+                .unwrap(),
+            &SaslHandshakeResponse::builder()
+                .error_code(0)
+                .mechanisms(vec![StrBytes::from_str("SCRAM-SHA-512")])
+                .build()
+                .unwrap(),
+            1,
+            ApiKey::SaslHandshakeKey,
+        )
+        .unwrap();
+        mock_kafka
+            .stream()
+            .write_u32(response.len() as u32)
+            .await
+            .unwrap();
+        mock_kafka.stream().write_all(&response).await.unwrap();
+
+        let received = read_packet(&mut stream).await;
+        assert_eq!(&response[..], &received[0..response.len()]);
+
+        drop(stream);
+        context.stop().await?;
+        mock_kafka.destroy_and_wait().await;
+        Ok(())
+    }
+
+    /// Accepts a single TLS connection and behaves like a broker requiring
+    /// SASL/PLAIN, then answers a metadata request advertising itself as
+    /// broker 1. Returns the SASL authentication bytes sent by the outlet.
+    async fn simulate_tls_broker_with_sasl(
+        listener: TcpListener,
+        certificate: rcgen::Certificate,
+        port: u16,
+    ) -> Bytes {
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![Certificate(certificate.serialize_der().unwrap())],
+                PrivateKey(certificate.serialize_private_key_der()),
+            )
+            .unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut stream = TlsAcceptor::from(Arc::new(config))
+            .accept(socket)
+            .await
+            .unwrap();
+
+        let handshake: SaslHandshakeRequest =
+            read_versioned_request(&mut stream, ApiKey::SaslHandshakeKey, 1).await;
+        assert_eq!("PLAIN", &*handshake.mechanism);
+        send_versioned_response(
+            &mut stream,
+            0,
+            SaslHandshakeResponse::builder()
+                .error_code(0)
+                .mechanisms(vec![StrBytes::from_str("PLAIN")])
+                .build()
+                .unwrap(),
+            ApiKey::SaslHandshakeKey,
+            1,
+        )
+        .await;
+
+        let authenticate: SaslAuthenticateRequest =
+            read_versioned_request(&mut stream, ApiKey::SaslAuthenticateKey, 1).await;
+        send_versioned_response(
+            &mut stream,
+            1,
+            SaslAuthenticateResponse::default(),
+            ApiKey::SaslAuthenticateKey,
+            1,
+        )
+        .await;
+
+        let _metadata: MetadataRequest =
+            read_versioned_request(&mut stream, ApiKey::MetadataKey, TEST_KAFKA_API_VERSION).await;
+        let mut response = MetadataResponse::default();
+        let mut broker = MetadataResponseBroker::default();
+        broker.host = StrBytes::from_str("localhost");
+        broker.port = port as i32;
+        response.brokers.insert(BrokerId(1), broker);
+        send_versioned_response(
+            &mut stream,
+            2,
+            response,
+            ApiKey::MetadataKey,
+            TEST_KAFKA_API_VERSION,
+        )
+        .await;
+
+        authenticate.auth_bytes
+    }
+
+    async fn read_versioned_request<S: AsyncReadExt + Unpin, T: KafkaDecodable>(
+        stream: &mut S,
+        api_key: ApiKey,
+        api_version: i16,
+    ) -> T {
+        let buffer = read_packet(stream).await;
+        let mut buffer = BytesMut::from(buffer.as_slice());
+        let header =
+            RequestHeader::decode(&mut buffer, api_key.request_header_version(api_version))
+                .unwrap();
+        assert_eq!(api_key as i16, header.request_api_key);
+        T::decode(&mut buffer, api_version).unwrap()
+    }
+
+    async fn send_versioned_response<S: AsyncWriteExt + Unpin, T: KafkaEncodable>(
+        stream: &mut S,
+        correlation_id: i32,
+        body: T,
+        api_key: ApiKey,
+        api_version: i16,
+    ) {
+        let header = ResponseHeader::builder()
+            .correlation_id(correlation_id)
+            .unknown_tagged_fields(Default::default())
+            .build()
+            .unwrap();
+        let encoded = encode_response(&header, &body, api_version, api_key).unwrap();
+        stream.write_u32(encoded.len() as u32).await.unwrap();
+        stream.write_all(&encoded).await.unwrap();
+        stream.flush().await.unwrap();
+    }
+
+    fn create_request_header(
+        api_key: ApiKey,
+        api_version: i16,
+        correlation_id: i32,
+    ) -> RequestHeader {
+        RequestHeader::builder()
+            .request_api_version(api_version)
+            .correlation_id(correlation_id)
+            .request_api_key(api_key as i16)
+            .unknown_tagged_fields(Default::default())
+            .client_id(None)
+            .build()
+            .unwrap()
+    }
+
+    /// Sends the records through a producer and a consumer and returns both
+    /// the records as seen by the broker and the ones received by the consumer.
+    /// The node is stopped once the flow completes.
+    async fn produce_and_fetch_through_mock_kafka(
+        context: &mut Context,
+        handler: &NodeManagerHandle,
+        records: Vec<Record>,
+        encryption_options: KafkaEncryptionOptions,
+    ) -> ockam::Result<(Vec<Record>, Vec<Record>)> {
+        let (encrypted_records, mut plain_records) = produce_and_fetch_with_consumers(
+            context,
+            handler,
+            records,
+            encryption_options,
+            vec![handler.identity.clone()],
+        )
+        .await?;
+        Ok((encrypted_records, plain_records.remove(0)))
+    }
+
+    /// Sends the records through a producer and then fetches them through one
+    /// consumer for each identity, returns the records as seen by the broker
+    /// and the ones received by each consumer.
+    /// The node is stopped once the flow completes.
+    async fn produce_and_fetch_with_consumers(
+        context: &mut Context,
+        handler: &NodeManagerHandle,
+        records: Vec<Record>,
+        encryption_options: KafkaEncryptionOptions,
+        consumer_identities: Vec<Arc<Identity>>,
+    ) -> ockam::Result<(Vec<Record>, Vec<Vec<Record>>)> {
+        ForwardingService::create(
+            context,
+            DefaultAddress::FORWARDING_SERVICE,
+            AllowAll,
+            AllowAll,
+        )
+        .await?;
+
+        let producer_bootstrap_port = create_kafka_service(
+            context,
+            handler,
+            handler.identity.clone(),
+            Address::from_string("kafka_producer_listener"),
+            route!["kafka_producer_outlet"],
+            KafkaServiceKind::Producer,
+            encryption_options.clone(),
+        )
+        .await?;
 
         let mut producer_mock_kafka = TcpServerSimulator::start("127.0.0.1:0").await;
         handler
@@ -176,6 +727,7 @@ mod test {
         let request = simulate_kafka_producer_and_read_request(
             producer_bootstrap_port,
             &mut producer_mock_kafka,
+            records,
         )
         .await;
 
@@ -194,65 +746,92 @@ mod test {
             .unwrap();
 
         let mut encrypted_body = BytesMut::from(encrypted_body.as_ref());
-        let records = RecordBatchDecoder::decode(&mut encrypted_body).unwrap();
-
-        assert_ne!(
-            records.get(0).unwrap().value.as_ref().unwrap(),
-            "hello world!".as_bytes()
-        );
+        let encrypted_records = RecordBatchDecoder::decode(&mut encrypted_body).unwrap();
 
-        let mut consumer_mock_kafka = TcpServerSimulator::start("127.0.0.1:0").await;
-        handler
-            .tcp
-            .create_outlet(
-                "kafka_consumer_outlet",
-                format!("127.0.0.1:{}", consumer_mock_kafka.port),
-                AllowAll,
+        //every consumer retrieves the data key from the producer using its own identity
+        let mut consumers_plain_records = Vec::new();
+        let mut consumers_mock_kafka = Vec::new();
+        for (index, identity) in consumer_identities.into_iter().enumerate() {
+            let consumer_bootstrap_port = create_kafka_service(
+                context,
+                handler,
+                identity,
+                Address::from_string(format!("kafka_consumer_listener_{index}")),
+                route![format!("kafka_consumer_outlet_{index}")],
+                KafkaServiceKind::Consumer,
+                encryption_options.clone(),
             )
             .await?;
-        let plain_fetch_response = simulate_kafka_consumer_and_read_response(
-            consumer_bootstrap_port,
-            &mut consumer_mock_kafka,
-            &request,
-        )
-        .await;
 
-        let plain_content = plain_fetch_response
-            .responses
-            .get(0)
-            .as_ref()
-            .unwrap()
-            .partitions
-            .get(0)
-            .as_ref()
-            .unwrap()
-            .records
-            .as_ref()
-            .unwrap();
+            let mut consumer_mock_kafka = TcpServerSimulator::start("127.0.0.1:0").await;
+            handler
+                .tcp
+                .create_outlet(
+                    format!("kafka_consumer_outlet_{index}"),
+                    format!("127.0.0.1:{}", consumer_mock_kafka.port),
+                    AllowAll,
+                )
+                .await?;
+            let plain_fetch_response = simulate_kafka_consumer_and_read_response(
+                consumer_bootstrap_port,
+                &mut consumer_mock_kafka,
+                &request,
+            )
+            .await;
 
-        let mut plain_content = BytesMut::from(plain_content.as_ref());
-        let records = RecordBatchDecoder::decode(&mut plain_content).unwrap();
+            let plain_content = plain_fetch_response
+                .responses
+                .get(0)
+                .as_ref()
+                .unwrap()
+                .partitions
+                .get(0)
+                .as_ref()
+                .unwrap()
+                .records
+                .as_ref()
+                .unwrap();
 
-        assert_eq!(
-            records.get(0).as_ref().unwrap().value.as_ref().unwrap(),
-            "hello world!".as_bytes()
-        );
+            let mut plain_content = BytesMut::from(plain_content.as_ref());
+            consumers_plain_records.push(RecordBatchDecoder::decode(&mut plain_content).unwrap());
+            consumers_mock_kafka.push(consumer_mock_kafka);
+        }
 
         context.stop().await?;
-        consumer_mock_kafka.destroy_and_wait().await;
+        for consumer_mock_kafka in consumers_mock_kafka {
+            consumer_mock_kafka.destroy_and_wait().await;
+        }
         producer_mock_kafka.destroy_and_wait().await;
-        Ok(())
+        Ok((encrypted_records, consumers_plain_records))
+    }
+
+    fn create_record(key: Option<Bytes>, headers: IndexMap<StrBytes, Option<Bytes>>) -> Record {
+        Record {
+            transactional: false,
+            control: false,
+            partition_leader_epoch: 0,
+            producer_id: 0,
+            producer_epoch: 0,
+            timestamp_type: TimestampType::Creation,
+            offset: 0,
+            sequence: 0,
+            timestamp: 0,
+            key,
+            value: Some(BytesMut::from("hello world!").freeze()),
+            headers,
+        }
     }
 
     async fn simulate_kafka_producer_and_read_request(
         producer_bootstrap_port: u16,
         producer_mock_kafka: &mut TcpServerSimulator,
+        records: Vec<Record>,
     ) -> ProduceRequest {
         let mut kafka_client_connection =
             TcpStream::connect(format!("127.0.0.1:{producer_bootstrap_port}"))
                 .await
                 .unwrap();
-        send_kafka_produce_request(&mut kafka_client_connection).await;
+        send_kafka_produce_request(&mut kafka_client_connection, records).await;
         read_kafka_request::<&mut DuplexStream, RequestHeader, ProduceRequest>(
             producer_mock_kafka.stream(),
             ApiKey::ProduceKey,
@@ -260,7 +839,7 @@ mod test {
         .await
     }
 
-    async fn send_kafka_produce_request(stream: &mut TcpStream) {
+    async fn send_kafka_produce_request(stream: &mut TcpStream, records: Vec<Record>) {
         let header = RequestHeader::builder()
             .request_api_key(ApiKey::ProduceKey as i16)
             .request_api_version(TEST_KAFKA_API_VERSION)
@@ -273,21 +852,7 @@ mod test {
         let mut encoded = BytesMut::new();
         RecordBatchEncoder::encode(
             &mut encoded,
-            vec![Record {
-                transactional: false,
-                control: false,
-                partition_leader_epoch: 0,
-                producer_id: 0,
-                producer_epoch: 0,
-                timestamp_type: TimestampType::Creation,
-                offset: 0,
-                sequence: 0,
-                timestamp: 0,
-                key: None,
-                value: Some(BytesMut::from("hello world!").freeze()),
-                headers: Default::default(),
-            }]
-            .iter(),
+            records.iter(),
             &RecordEncodeOptions {
                 version: 2,
                 compression: Compression::None,
@@ -321,26 +886,6 @@ mod test {
         send_kafka_request(stream, header, request, ApiKey::ProduceKey).await;
     }
 
-    //this is needed in order to make the consumer create the forwarders to the secure
-    //channel
-    async fn simulate_first_kafka_consumer_empty_reply_and_ignore_result(
-        consumer_bootstrap_port: u16,
-        mock_kafka_connection: &mut TcpServerSimulator,
-    ) {
-        let mut kafka_client_connection =
-            TcpStream::connect(format!("127.0.0.1:{consumer_bootstrap_port}"))
-                .await
-                .unwrap();
-        send_kafka_fetch_request(&mut kafka_client_connection).await;
-        //we don't want the answer, but we need to be sure the
-        // message passed through and the forwarder had been created
-        mock_kafka_connection
-            .stream
-            .read_exact(&mut [0; 4])
-            .await
-            .unwrap();
-    }
-
     //we use the encrypted producer request to generate the encrypted fetch response
     async fn simulate_kafka_consumer_and_read_response(
         consumer_bootstrap_port: u16,
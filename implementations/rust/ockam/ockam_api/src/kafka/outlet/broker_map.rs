@@ -0,0 +1,77 @@
+use ockam::compat::tokio::sync::Mutex;
+use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{IncomingAccessControl, Result};
+use ockam_node::Context;
+
+use crate::kafka::kafka_outlet_address;
+use crate::kafka::outlet::listener::KafkaOutletListener;
+use crate::kafka::outlet::KafkaBrokerConnectionOptions;
+
+type BrokerId = i32;
+
+/// Shared structure for every outlet portal to keep track of which brokers
+/// are exposed, with the relative broker address.
+/// Also takes care of creating the outlet listener of a broker the first time
+/// it's advertised.
+#[derive(Clone)]
+pub(crate) struct KafkaOutletBrokerMap {
+    broker_map: Arc<Mutex<HashMap<BrokerId, String>>>,
+    options: Arc<KafkaBrokerConnectionOptions>,
+    access_control: Arc<dyn IncomingAccessControl>,
+}
+
+impl KafkaOutletBrokerMap {
+    pub(crate) fn new(
+        options: KafkaBrokerConnectionOptions,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Self {
+        Self {
+            broker_map: Default::default(),
+            options: Arc::new(options),
+            access_control,
+        }
+    }
+
+    pub(super) fn options(&self) -> &KafkaBrokerConnectionOptions {
+        &self.options
+    }
+
+    pub(super) fn access_control(&self) -> Arc<dyn IncomingAccessControl> {
+        self.access_control.clone()
+    }
+
+    #[cfg(test)]
+    pub(crate) async fn retrieve_broker(&self, broker_id: BrokerId) -> Option<String> {
+        self.broker_map.lock().await.get(&broker_id).cloned()
+    }
+
+    /// Asserts the presence of an outlet listener for a broker, `peer` is
+    /// expected as `host:port`
+    pub(crate) async fn assert_outlet_for_broker(
+        &self,
+        context: &Context,
+        broker_id: BrokerId,
+        peer: String,
+    ) -> Result<()> {
+        let mut broker_map = self.broker_map.lock().await;
+        if let Some(current_peer) = broker_map.get(&broker_id) {
+            if current_peer != &peer {
+                warn!("broker {broker_id} moved from {current_peer} to {peer}, keeping the first");
+            }
+            return Ok(());
+        }
+
+        KafkaOutletListener::create(
+            context,
+            kafka_outlet_address(broker_id),
+            peer.clone(),
+            self.clone(),
+        )
+        .await?;
+
+        debug!("exposing broker {broker_id} at {peer}");
+        broker_map.insert(broker_id, peer);
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use kafka_protocol::messages::{
+    ApiKey, RequestHeader, ResponseHeader, SaslAuthenticateRequest, SaslAuthenticateResponse,
+    SaslHandshakeRequest, SaslHandshakeResponse,
+};
+use kafka_protocol::protocol::{Builder, Decodable, Encodable, StrBytes};
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_node::tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use ockam_node::tokio::net::TcpStream;
+use std::convert::TryFrom;
+use std::path::Path;
+use tokio_rustls::rustls::{Certificate, ClientConfig, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+
+use crate::kafka::portal_worker::MAX_KAFKA_MESSAGE_SIZE;
+
+const SASL_HANDSHAKE_VERSION: i16 = 1;
+const SASL_AUTHENTICATE_VERSION: i16 = 1;
+const SASL_PLAIN_MECHANISM: &str = "PLAIN";
+const SASL_SCRAM_PREFIX: &str = "SCRAM-";
+const SASL_CLIENT_ID: &str = "ockam";
+
+/// Stream toward a broker, either plain tcp or tls
+pub(crate) trait BrokerStream: AsyncRead + AsyncWrite + Send + Sync + Unpin {}
+impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin> BrokerStream for T {}
+
+/// Credentials used by the outlet to authenticate to the brokers
+#[derive(Clone)]
+pub(crate) struct SaslPlainCredentials {
+    username: String,
+    password: String,
+}
+
+/// How the outlet connects to the brokers.
+/// When SASL credentials are set the outlet authenticates every connection
+/// before relaying any client message, otherwise the SASL exchange of the
+/// client, if any, is passed through untouched.
+#[derive(Clone, Default)]
+pub(crate) struct KafkaBrokerConnectionOptions {
+    tls: Option<Arc<ClientConfig>>,
+    sasl_plain: Option<SaslPlainCredentials>,
+}
+
+impl KafkaBrokerConnectionOptions {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use TLS toward the brokers, the brokers certificates are verified
+    /// using the system root certificates and the optional PEM certificate
+    pub(crate) fn with_tls(mut self, ca_certificate: Option<&Path>) -> Result<Self> {
+        let mut root_store = RootCertStore::empty();
+        match rustls_native_certs::load_native_certs() {
+            Ok(certificates) => {
+                for certificate in certificates {
+                    //invalid system certificates are ignored
+                    let _ = root_store.add(&Certificate(certificate.0));
+                }
+            }
+            Err(cause) => warn!("cannot load system root certificates: {cause}"),
+        }
+
+        if let Some(path) = ca_certificate {
+            let pem = std::fs::read(path).map_err(|cause| {
+                Error::new(
+                    Origin::Transport,
+                    Kind::NotFound,
+                    format!("cannot read {}: {cause}", path.display()),
+                )
+            })?;
+            let certificates = rustls_pemfile::certs(&mut pem.as_slice())
+                .map_err(|cause| Error::new(Origin::Transport, Kind::Invalid, cause))?;
+            let (added, _ignored) = root_store.add_parsable_certificates(&certificates);
+            if added == 0 {
+                return Err(Error::new(
+                    Origin::Transport,
+                    Kind::Invalid,
+                    format!("no valid certificate in {}", path.display()),
+                ));
+            }
+        }
+
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+        self.tls = Some(Arc::new(config));
+        Ok(self)
+    }
+
+    /// Authenticate every connection with the SASL `mechanism`, using the
+    /// password read from `password_file`.
+    /// Only PLAIN is supported, SCRAM mechanisms are rejected.
+    pub(crate) fn with_sasl(
+        self,
+        mechanism: &str,
+        username: impl Into<String>,
+        password_file: &Path,
+    ) -> Result<Self> {
+        let mechanism = mechanism.to_ascii_uppercase();
+        if mechanism.starts_with(SASL_SCRAM_PREFIX) {
+            return Err(sasl_error(format!(
+                "{mechanism} is not supported by the outlet, only {SASL_PLAIN_MECHANISM} is"
+            )));
+        } else if mechanism != SASL_PLAIN_MECHANISM {
+            return Err(sasl_error(format!("unknown mechanism {mechanism}")));
+        }
+
+        let password = std::fs::read_to_string(password_file).map_err(|cause| {
+            Error::new(
+                Origin::Transport,
+                Kind::NotFound,
+                format!("cannot read {}: {cause}", password_file.display()),
+            )
+        })?;
+        //a trailing newline is not part of the password
+        let password = password.trim_end_matches(&['\r', '\n'][..]);
+        Ok(self.with_sasl_plain(username, password))
+    }
+
+    /// Authenticate every connection using SASL/PLAIN
+    pub(crate) fn with_sasl_plain(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.sasl_plain = Some(SaslPlainCredentials {
+            username: username.into(),
+            password: password.into(),
+        });
+        self
+    }
+
+    /// Opens a connection to the broker, `peer` is expected as `host:port`
+    pub(crate) async fn connect(&self, peer: &str) -> Result<Box<dyn BrokerStream>> {
+        let stream = TcpStream::connect(peer)
+            .await
+            .map_err(|cause| Error::new(Origin::Transport, Kind::Io, cause))?;
+
+        let mut stream: Box<dyn BrokerStream> = if let Some(config) = &self.tls {
+            let host = peer.rsplit_once(':').map(|(host, _)| host).unwrap_or(peer);
+            let server_name = ServerName::try_from(host)
+                .map_err(|cause| Error::new(Origin::Transport, Kind::Invalid, cause))?;
+            let stream = TlsConnector::from(config.clone())
+                .connect(server_name, stream)
+                .await
+                .map_err(|cause| Error::new(Origin::Transport, Kind::Io, cause))?;
+            Box::new(stream)
+        } else {
+            Box::new(stream)
+        };
+
+        if let Some(credentials) = &self.sasl_plain {
+            sasl_plain_authenticate(&mut stream, credentials).await?;
+            debug!("authenticated to {peer} as {}", credentials.username);
+        }
+
+        Ok(stream)
+    }
+}
+
+/// Runs the SaslHandshake and SaslAuthenticate exchange on a fresh connection
+async fn sasl_plain_authenticate(
+    stream: &mut Box<dyn BrokerStream>,
+    credentials: &SaslPlainCredentials,
+) -> Result<()> {
+    let mut handshake = SaslHandshakeRequest::default();
+    handshake.mechanism = StrBytes::from_str(SASL_PLAIN_MECHANISM);
+    send_request(
+        stream,
+        ApiKey::SaslHandshakeKey,
+        SASL_HANDSHAKE_VERSION,
+        0,
+        &handshake,
+    )
+    .await?;
+    let response: SaslHandshakeResponse =
+        read_response(stream, ApiKey::SaslHandshakeKey, SASL_HANDSHAKE_VERSION).await?;
+    if response.error_code != 0 {
+        return Err(sasl_error(format!(
+            "{SASL_PLAIN_MECHANISM} not supported by the broker, supported: {:?} \
+            (the outlet doesn't support SCRAM)",
+            response.mechanisms
+        )));
+    }
+
+    //PLAIN message is: authzid NUL authcid NUL passwd, with an empty authzid
+    let mut auth_bytes = BytesMut::new();
+    auth_bytes.put_u8(0);
+    auth_bytes.put_slice(credentials.username.as_bytes());
+    auth_bytes.put_u8(0);
+    auth_bytes.put_slice(credentials.password.as_bytes());
+
+    let mut authenticate = SaslAuthenticateRequest::default();
+    authenticate.auth_bytes = auth_bytes.freeze();
+    send_request(
+        stream,
+        ApiKey::SaslAuthenticateKey,
+        SASL_AUTHENTICATE_VERSION,
+        1,
+        &authenticate,
+    )
+    .await?;
+    let response: SaslAuthenticateResponse = read_response(
+        stream,
+        ApiKey::SaslAuthenticateKey,
+        SASL_AUTHENTICATE_VERSION,
+    )
+    .await?;
+    if response.error_code != 0 {
+        return Err(sasl_error(format!(
+            "authentication failed: {}",
+            response
+                .error_message
+                .map(|message| message.to_string())
+                .unwrap_or_default()
+        )));
+    }
+
+    Ok(())
+}
+
+async fn send_request<T: Encodable>(
+    stream: &mut Box<dyn BrokerStream>,
+    api_key: ApiKey,
+    api_version: i16,
+    correlation_id: i32,
+    body: &T,
+) -> Result<()> {
+    let header = RequestHeader::builder()
+        .request_api_key(api_key as i16)
+        .request_api_version(api_version)
+        .correlation_id(correlation_id)
+        .client_id(Some(StrBytes::from_str(SASL_CLIENT_ID)))
+        .unknown_tagged_fields(Default::default())
+        .build()
+        .map_err(|cause| sasl_error(cause.to_string()))?;
+
+    let mut buffer = BytesMut::new();
+    header
+        .encode(&mut buffer, api_key.request_header_version(api_version))
+        .map_err(|_| sasl_error("cannot encode request header"))?;
+    body.encode(&mut buffer, api_version)
+        .map_err(|_| sasl_error("cannot encode request"))?;
+
+    let mut frame = BytesMut::with_capacity(buffer.len() + 4);
+    frame.put_u32(buffer.len() as u32);
+    frame.put_slice(&buffer);
+
+    stream
+        .write_all(&frame)
+        .await
+        .map_err(|cause| Error::new(Origin::Transport, Kind::Io, cause))
+}
+
+async fn read_response<T: Decodable>(
+    stream: &mut Box<dyn BrokerStream>,
+    api_key: ApiKey,
+    api_version: i16,
+) -> Result<T> {
+    let length = stream
+        .read_u32()
+        .await
+        .map_err(|cause| Error::new(Origin::Transport, Kind::Io, cause))?;
+    if length > MAX_KAFKA_MESSAGE_SIZE {
+        return Err(sasl_error("response is bigger than maximum size"));
+    }
+
+    let mut buffer = vec![0; length as usize];
+    stream
+        .read_exact(&mut buffer)
+        .await
+        .map_err(|cause| Error::new(Origin::Transport, Kind::Io, cause))?;
+
+    let mut buffer = Bytes::from(buffer);
+    ResponseHeader::decode(&mut buffer, api_key.response_header_version(api_version))
+        .map_err(|_| sasl_error("cannot decode response header"))?;
+    let response =
+        T::decode(&mut buffer, api_version).map_err(|_| sasl_error("cannot decode response"))?;
+    if buffer.has_remaining() {
+        warn!("ignoring {} trailing bytes", buffer.remaining());
+    }
+    Ok(response)
+}
+
+fn sasl_error(message: impl Into<String>) -> Error {
+    Error::new(
+        Origin::Transport,
+        Kind::Unsupported,
+        format!("kafka sasl: {}", message.into()),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::KafkaBrokerConnectionOptions;
+
+    #[allow(non_snake_case)]
+    #[test]
+    fn sasl__password_file_and_mechanisms__only_plain_accepted() {
+        let dir = tempfile::tempdir().unwrap();
+        let password_file = dir.path().join("password");
+        std::fs::write(&password_file, "secret\n").unwrap();
+
+        let options = KafkaBrokerConnectionOptions::new()
+            .with_sasl("plain", "user", &password_file)
+            .unwrap();
+        assert_eq!(options.sasl_plain.unwrap().password, "secret");
+
+        for mechanism in ["SCRAM-SHA-256", "SCRAM-SHA-512", "GSSAPI"] {
+            assert!(KafkaBrokerConnectionOptions::new()
+                .with_sasl(mechanism, "user", &password_file)
+                .is_err());
+        }
+        assert!(KafkaBrokerConnectionOptions::new()
+            .with_sasl("PLAIN", "user", &dir.path().join("missing"))
+            .is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, DenyAll, Error, Mailboxes, Result, Routed, Worker};
+use ockam_node::{Context, WorkerBuilder};
+use ockam_transport_tcp::PortalMessage;
+
+use crate::kafka::outlet::portal::KafkaOutletPortal;
+use crate::kafka::outlet::KafkaOutletBrokerMap;
+
+/// Outlet listener of a single broker, or of the bootstrap server.
+/// Every ping coming from an inlet spawns a new portal with its own
+/// connection to the broker.
+pub(crate) struct KafkaOutletListener {
+    peer: String,
+    broker_map: KafkaOutletBrokerMap,
+}
+
+impl KafkaOutletListener {
+    /// Starts the listener at `address`, `peer` is expected as `host:port`
+    pub(crate) async fn create(
+        context: &Context,
+        address: Address,
+        peer: String,
+        broker_map: KafkaOutletBrokerMap,
+    ) -> Result<()> {
+        let access_control = broker_map.access_control();
+        WorkerBuilder::with_mailboxes(
+            Mailboxes::main(address, access_control, Arc::new(DenyAll)),
+            Self { peer, broker_map },
+        )
+        .start(context)
+        .await?;
+        Ok(())
+    }
+}
+
+#[ockam::worker]
+impl Worker for KafkaOutletListener {
+    type Message = PortalMessage;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        context: &mut Self::Context,
+        message: Routed<Self::Message>,
+    ) -> Result<()> {
+        if let PortalMessage::Ping = message.as_body() {
+        } else {
+            return Err(Error::new(
+                Origin::Transport,
+                Kind::Protocol,
+                "expected a ping to open the kafka outlet portal",
+            ));
+        }
+
+        let address = KafkaOutletPortal::start(
+            context,
+            self.peer.clone(),
+            message.return_route(),
+            self.broker_map.clone(),
+        )
+        .await?;
+
+        debug!(
+            "created kafka outlet portal at {address} toward {}",
+            self.peer
+        );
+        Ok(())
+    }
+}
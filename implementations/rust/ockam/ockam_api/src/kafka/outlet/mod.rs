@@ -0,0 +1,15 @@
+//! Outlet side of the kafka portal, it connects to the brokers on behalf of
+//! the kafka clients and is meant to run next to the kafka cluster.
+//!
+//! Each broker is exposed at [`kafka_outlet_address`](crate::kafka::kafka_outlet_address)
+//! as soon as it's advertised in a metadata response, the bootstrap server is exposed
+//! at [`ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS`](crate::kafka::ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS).
+
+mod broker_map;
+mod connector;
+mod listener;
+mod portal;
+
+pub(crate) use broker_map::KafkaOutletBrokerMap;
+pub(crate) use connector::KafkaBrokerConnectionOptions;
+pub(crate) use listener::KafkaOutletListener;
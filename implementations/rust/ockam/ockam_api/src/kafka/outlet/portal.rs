@@ -0,0 +1,338 @@
+use bytes::{Buf, Bytes, BytesMut};
+use kafka_protocol::messages::{ApiKey, MetadataResponse, ResponseHeader};
+use kafka_protocol::protocol::Decodable;
+use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{
+    async_trait, route, Address, AllowAll, AllowOnwardAddresses, AllowSourceAddress, Any,
+    Decodable as OckamDecodable, DenyAll, Encodable, Error, LocalMessage, Mailbox, Mailboxes,
+    Processor, Result, Route, Routed, TransportMessage, Worker,
+};
+use ockam_node::tokio::io::{AsyncReadExt, AsyncWriteExt, ReadHalf, WriteHalf};
+use ockam_node::{Context, ProcessorBuilder, WorkerBuilder};
+use ockam_transport_tcp::{PortalInternalMessage, PortalMessage, MAX_PAYLOAD_SIZE};
+
+use crate::kafka::length_delimited::KafkaMessageDecoder;
+use crate::kafka::outlet::connector::BrokerStream;
+use crate::kafka::outlet::KafkaOutletBrokerMap;
+
+type CorrelationId = i32;
+
+/// Api version of the metadata requests sent by the client, the responses
+/// are used to expose every broker advertised
+#[derive(Clone, Default)]
+struct MetadataRequests(Arc<Mutex<HashMap<CorrelationId, i16>>>);
+
+/// Relays the traffic between the inlet side and the broker.
+///
+/// Unlike the tcp outlet the connection can be wrapped in TLS and
+/// authenticated with SASL before any client message is relayed.
+/// Messages are relayed as they are, the requests and responses are only
+/// decoded to learn the address of the brokers from metadata responses.
+pub(super) struct KafkaOutletPortal {
+    write_half: WriteHalf<Box<dyn BrokerStream>>,
+    read_half: Option<ReadHalf<Box<dyn BrokerStream>>>,
+    pong_route: Route,
+    remote_address: Address,
+    internal_address: Address,
+    receiver_address: Address,
+    broker_map: KafkaOutletBrokerMap,
+    decoder: KafkaMessageDecoder,
+    metadata_requests: MetadataRequests,
+    is_disconnecting: bool,
+}
+
+impl KafkaOutletPortal {
+    /// Connects to the broker and starts the portal, returns the address
+    /// the inlet side communicates with
+    pub(super) async fn start(
+        context: &Context,
+        peer: String,
+        pong_route: Route,
+        broker_map: KafkaOutletBrokerMap,
+    ) -> Result<Address> {
+        let stream = broker_map.options().connect(&peer).await.map_err(|cause| {
+            warn!("cannot connect to kafka broker {peer}: {cause}");
+            cause
+        })?;
+        let (read_half, write_half) = ockam_node::tokio::io::split(stream);
+
+        let internal_address = Address::random_tagged("KafkaOutletPortal.internal");
+        let remote_address = Address::random_tagged("KafkaOutletPortal.remote");
+        let receiver_address = Address::random_tagged("KafkaOutletReceiver");
+
+        let internal_mailbox = Mailbox::new(
+            internal_address.clone(),
+            Arc::new(AllowSourceAddress(receiver_address.clone())),
+            Arc::new(DenyAll),
+        );
+        let remote_mailbox = Mailbox::new(
+            remote_address.clone(),
+            broker_map.access_control(),
+            Arc::new(AllowAll),
+        );
+
+        let worker = Self {
+            write_half,
+            read_half: Some(read_half),
+            pong_route,
+            remote_address: remote_address.clone(),
+            internal_address,
+            receiver_address,
+            broker_map,
+            decoder: KafkaMessageDecoder::new(),
+            metadata_requests: Default::default(),
+            is_disconnecting: false,
+        };
+
+        WorkerBuilder::with_mailboxes(
+            Mailboxes::new(internal_mailbox, vec![remote_mailbox]),
+            worker,
+        )
+        .start(context)
+        .await?;
+
+        Ok(remote_address)
+    }
+
+    /// Keeps track of metadata requests, the kafka request header starts with
+    /// api key, api version and correlation id
+    fn track_requests(&mut self, payload: &[u8]) -> Result<()> {
+        for mut request in self.decoder.decode_messages(BytesMut::from(payload))? {
+            if request.len() < 8 {
+                return Err(invalid_message());
+            }
+            let api_key = request.get_i16();
+            let api_version = request.get_i16();
+            let correlation_id = request.get_i32();
+            if api_key == ApiKey::MetadataKey as i16 {
+                self.metadata_requests
+                    .0
+                    .lock()
+                    .unwrap()
+                    .insert(correlation_id, api_version);
+            }
+        }
+        Ok(())
+    }
+
+    async fn disconnect(&mut self, context: &Context, notify_remote: bool) -> Result<()> {
+        self.is_disconnecting = true;
+        if notify_remote {
+            context
+                .send_from_address(
+                    self.pong_route.clone(),
+                    PortalMessage::Disconnect,
+                    self.remote_address.clone(),
+                )
+                .await?;
+        }
+
+        //the receiver may have already stopped itself
+        let _ = context.stop_processor(self.receiver_address.clone()).await;
+        context.stop_worker(self.internal_address.clone()).await
+    }
+}
+
+#[async_trait]
+impl Worker for KafkaOutletPortal {
+    type Message = Any;
+    type Context = Context;
+
+    async fn initialize(&mut self, context: &mut Self::Context) -> Result<()> {
+        context
+            .send_from_address(
+                self.pong_route.clone(),
+                PortalMessage::Pong,
+                self.remote_address.clone(),
+            )
+            .await?;
+
+        let read_half = self.read_half.take().ok_or_else(invalid_message)?;
+        let next_hop = self.pong_route.next()?.clone();
+        let receiver = KafkaOutletReceiver {
+            read_half,
+            buffer: Vec::with_capacity(MAX_PAYLOAD_SIZE),
+            onward_route: self.pong_route.clone(),
+            sender_address: self.remote_address.clone(),
+            internal_address: self.internal_address.clone(),
+            broker_map: self.broker_map.clone(),
+            decoder: KafkaMessageDecoder::new(),
+            metadata_requests: self.metadata_requests.clone(),
+        };
+
+        let mailbox = Mailbox::new(
+            self.receiver_address.clone(),
+            Arc::new(DenyAll),
+            Arc::new(AllowOnwardAddresses(vec![
+                next_hop,
+                self.internal_address.clone(),
+            ])),
+        );
+        ProcessorBuilder::with_mailboxes(Mailboxes::new(mailbox, vec![]), receiver)
+            .start(context)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_message(
+        &mut self,
+        context: &mut Self::Context,
+        message: Routed<Self::Message>,
+    ) -> Result<()> {
+        if self.is_disconnecting {
+            return Ok(());
+        }
+
+        if message.onward_route().recipient()? == self.internal_address {
+            //the broker closed the connection, the receiver already notified the inlet
+            let PortalInternalMessage::Disconnect =
+                PortalInternalMessage::decode(message.payload())?;
+            return self.disconnect(context, false).await;
+        }
+
+        match PortalMessage::decode(message.payload())? {
+            PortalMessage::Payload(payload) => {
+                if let Err(cause) = self.track_requests(&payload) {
+                    warn!("cannot decode kafka request: {cause}");
+                    return self.disconnect(context, true).await;
+                }
+
+                //tls streams are buffered, so flushing is needed
+                let result = match self.write_half.write_all(&payload).await {
+                    Ok(()) => self.write_half.flush().await,
+                    Err(cause) => Err(cause),
+                };
+                if let Err(cause) = result {
+                    warn!("cannot write to kafka broker: {cause}");
+                    self.disconnect(context, true).await?;
+                }
+                Ok(())
+            }
+            PortalMessage::Disconnect => self.disconnect(context, false).await,
+            PortalMessage::Ping | PortalMessage::Pong => Err(Error::new(
+                Origin::Transport,
+                Kind::Protocol,
+                "unexpected message on an open kafka outlet portal",
+            )),
+        }
+    }
+}
+
+/// Reads the broker responses and sends them to the inlet side
+struct KafkaOutletReceiver {
+    read_half: ReadHalf<Box<dyn BrokerStream>>,
+    buffer: Vec<u8>,
+    onward_route: Route,
+    sender_address: Address,
+    internal_address: Address,
+    broker_map: KafkaOutletBrokerMap,
+    decoder: KafkaMessageDecoder,
+    metadata_requests: MetadataRequests,
+}
+
+impl KafkaOutletReceiver {
+    /// Exposes every broker advertised in metadata responses
+    async fn track_responses(&mut self, context: &Context) -> Result<()> {
+        for response in self
+            .decoder
+            .decode_messages(BytesMut::from(self.buffer.as_slice()))?
+        {
+            let mut response = response.freeze();
+            if response.len() < 4 {
+                return Err(invalid_message());
+            }
+            let correlation_id = (&response[0..4]).get_i32();
+            let api_version = self
+                .metadata_requests
+                .0
+                .lock()
+                .unwrap()
+                .remove(&correlation_id);
+
+            if let Some(api_version) = api_version {
+                let metadata = decode_metadata_response(&mut response, api_version)?;
+                for (broker_id, broker) in metadata.brokers {
+                    self.broker_map
+                        .assert_outlet_for_broker(
+                            context,
+                            broker_id.0,
+                            format!("{}:{}", &*broker.host, broker.port),
+                        )
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn send_disconnect(&self, context: &Context) -> Result<()> {
+        if let Err(cause) = context
+            .send(
+                route![self.internal_address.clone()],
+                PortalInternalMessage::Disconnect,
+            )
+            .await
+        {
+            warn!("cannot notify the kafka outlet portal about dropped connection: {cause}");
+        }
+
+        let message = TransportMessage::v1(
+            self.onward_route.clone(),
+            self.sender_address.clone(),
+            PortalMessage::Disconnect.encode()?,
+        );
+        context.forward(LocalMessage::new(message, vec![])).await
+    }
+}
+
+#[async_trait]
+impl Processor for KafkaOutletReceiver {
+    type Context = Context;
+
+    async fn process(&mut self, context: &mut Self::Context) -> Result<bool> {
+        self.buffer.clear();
+
+        if let Err(cause) = self.read_half.read_buf(&mut self.buffer).await {
+            warn!("kafka broker connection read failed: {cause}");
+            self.send_disconnect(context).await?;
+            return Ok(false);
+        }
+
+        if self.buffer.is_empty() {
+            self.send_disconnect(context).await?;
+            return Ok(false);
+        }
+
+        if let Err(cause) = self.track_responses(context).await {
+            warn!("cannot decode kafka response: {cause}");
+            self.send_disconnect(context).await?;
+            return Ok(false);
+        }
+
+        for chunk in self.buffer.chunks(MAX_PAYLOAD_SIZE) {
+            let message = TransportMessage::v1(
+                self.onward_route.clone(),
+                self.sender_address.clone(),
+                PortalMessage::Payload(chunk.to_vec()).encode()?,
+            );
+            context.forward(LocalMessage::new(message, vec![])).await?;
+        }
+
+        Ok(true)
+    }
+}
+
+fn decode_metadata_response(buffer: &mut Bytes, api_version: i16) -> Result<MetadataResponse> {
+    ResponseHeader::decode(
+        buffer,
+        ApiKey::MetadataKey.response_header_version(api_version),
+    )
+    .map_err(|_| invalid_message())?;
+    MetadataResponse::decode(buffer, api_version).map_err(|_| invalid_message())
+}
+
+fn invalid_message() -> Error {
+    Error::new(Origin::Transport, Kind::Invalid, "invalid kafka message")
+}
@@ -7,7 +7,7 @@ use ockam_node::Context;
 
 use crate::kafka::inlet_map::KafkaInletMap;
 use crate::kafka::portal_worker::KafkaPortalWorker;
-use crate::kafka::protocol_aware::TopicUuidMap;
+use crate::kafka::protocol_aware::{KafkaEncryptionOptions, TopicNames};
 use crate::kafka::secure_channel_map::KafkaSecureChannelController;
 use crate::port_range::PortRange;
 
@@ -16,7 +16,8 @@ use crate::port_range::PortRange;
 pub(crate) struct KafkaPortalListener {
     inlet_map: KafkaInletMap,
     secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
-    uuid_to_name: TopicUuidMap,
+    encryption_options: Arc<KafkaEncryptionOptions>,
+    topic_names: TopicNames,
 }
 
 #[ockam::worker]
@@ -33,7 +34,8 @@ impl Worker for KafkaPortalListener {
         let worker_address = KafkaPortalWorker::start_kafka_portal(
             context,
             self.secure_channel_controller.clone(),
-            self.uuid_to_name.clone(),
+            self.encryption_options.clone(),
+            self.topic_names.clone(),
             self.inlet_map.clone(),
         )
         .await?;
@@ -64,6 +66,7 @@ impl KafkaPortalListener {
     pub(crate) async fn create(
         context: &Context,
         secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
+        encryption_options: KafkaEncryptionOptions,
         interceptor_route: Route,
         listener_address: Address,
         bind_host: String,
@@ -75,7 +78,8 @@ impl KafkaPortalListener {
                 Self {
                     inlet_map: KafkaInletMap::new(interceptor_route, bind_host, port_range),
                     secure_channel_controller,
-                    uuid_to_name: Default::default(),
+                    encryption_options: Arc::new(encryption_options),
+                    topic_names: Default::default(),
                 },
                 AllowAll,
                 AllowAll,
@@ -12,7 +12,7 @@ use ockam_transport_tcp::{PortalMessage, MAX_PAYLOAD_SIZE};
 
 use crate::kafka::inlet_map::KafkaInletMap;
 use crate::kafka::length_delimited::{length_encode, KafkaMessageDecoder};
-use crate::kafka::protocol_aware::{Interceptor, TopicUuidMap};
+use crate::kafka::protocol_aware::{Interceptor, KafkaEncryptionOptions, TopicNames};
 use crate::kafka::secure_channel_map::KafkaSecureChannelController;
 
 ///by default kafka supports up to 1MB messages, 16MB is the maximum suggested
@@ -245,10 +245,12 @@ impl KafkaPortalWorker {
     pub(crate) async fn start_kafka_portal(
         context: &mut Context,
         secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
-        uuid_to_name: TopicUuidMap,
+        encryption_options: Arc<KafkaEncryptionOptions>,
+        topic_names: TopicNames,
         inlet_map: KafkaInletMap,
     ) -> ockam_core::Result<Address> {
-        let shared_protocol_state = Interceptor::new(secure_channel_controller, uuid_to_name);
+        let shared_protocol_state =
+            Interceptor::new(secure_channel_controller, encryption_options, topic_names);
 
         let inlet_address = Address::random_tagged("KafkaPortalWorker.inlet");
         let outlet_address = Address::random_tagged("KafkaPortalWorker.outlet");
@@ -589,6 +591,7 @@ mod test {
             context,
             secure_channel_controller,
             Default::default(),
+            Default::default(),
             inlet_map,
         )
         .await
@@ -645,6 +648,7 @@ mod test {
             context,
             secure_channel_controller,
             Default::default(),
+            Default::default(),
             inlet_map.clone(),
         )
         .await?;
@@ -0,0 +1,203 @@
+use crate::kafka::portal_worker::InterceptError;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use kafka_protocol::records::{
+    Compression, Record, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions, CASTAGNOLI,
+};
+use std::io::{Error, ErrorKind, Read, Write};
+
+//record batch v2 layout, see https://kafka.apache.org/documentation/#recordbatch
+const BATCH_LENGTH_OFFSET: usize = 8;
+const BATCH_HEADER_SIZE: usize = 12;
+const MAGIC_OFFSET: usize = 16;
+const CRC_OFFSET: usize = 17;
+const ATTRIBUTES_OFFSET: usize = 21;
+const RECORDS_OFFSET: usize = 61;
+const COMPRESSION_MASK: i16 = 0x7;
+
+/// Records of a single batch along the compression used to encode it
+pub(crate) struct RecordBatch {
+    pub(crate) compression: Compression,
+    pub(crate) records: Vec<Record>,
+}
+
+/// Decodes every record batch contained in the buffer, keeping track of the
+/// compression codec of each so it can be re-encoded the same way.
+///
+/// `kafka_protocol` only handles gzip and snappy, lz4 and zstd batches
+/// are decompressed here before being decoded.
+pub(crate) fn decode_record_batches(content: &[u8]) -> Result<Vec<RecordBatch>, InterceptError> {
+    let mut content = content;
+    let mut batches = Vec::new();
+
+    while content.has_remaining() {
+        if content.len() < BATCH_HEADER_SIZE {
+            return Err(invalid_data());
+        }
+        let batch_length = (&content[BATCH_LENGTH_OFFSET..BATCH_HEADER_SIZE]).get_i32();
+        let batch_size =
+            BATCH_HEADER_SIZE + usize::try_from(batch_length).map_err(|_| invalid_data())?;
+        if content.len() < batch_size || batch_size <= MAGIC_OFFSET {
+            return Err(invalid_data());
+        }
+
+        let (batch, remaining) = content.split_at(batch_size);
+        content = remaining;
+
+        //legacy message sets are converted to the current format
+        let magic = batch[MAGIC_OFFSET];
+        let compression = if magic < 2 {
+            Compression::None
+        } else {
+            compression_of(batch)?
+        };
+
+        let mut batch = match compression {
+            Compression::Lz4 | Compression::Zstd => decompress_batch(batch, compression)?,
+            _ => BytesMut::from(batch),
+        };
+
+        let records = RecordBatchDecoder::decode(&mut batch).map_err(|_| invalid_data())?;
+        batches.push(RecordBatch {
+            compression,
+            records,
+        });
+    }
+
+    Ok(batches)
+}
+
+/// Encodes every record batch using the compression codec of each batch
+pub(crate) fn encode_record_batches(batches: &[RecordBatch]) -> Result<Bytes, InterceptError> {
+    let mut encoded = BytesMut::new();
+
+    for batch in batches {
+        match batch.compression {
+            Compression::Lz4 | Compression::Zstd => {
+                let mut uncompressed = BytesMut::new();
+                encode(&mut uncompressed, &batch.records, Compression::None)?;
+                compress_batches(&mut encoded, uncompressed.freeze(), batch.compression)?;
+            }
+            compression => encode(&mut encoded, &batch.records, compression)?,
+        }
+    }
+
+    Ok(encoded.freeze())
+}
+
+fn encode(
+    buffer: &mut BytesMut,
+    records: &[Record],
+    compression: Compression,
+) -> Result<(), InterceptError> {
+    RecordBatchEncoder::encode(
+        buffer,
+        records.iter(),
+        &RecordEncodeOptions {
+            version: 2,
+            compression,
+        },
+    )
+    .map_err(|_| invalid_data())
+}
+
+fn compression_of(batch: &[u8]) -> Result<Compression, InterceptError> {
+    if batch.len() < RECORDS_OFFSET {
+        return Err(invalid_data());
+    }
+    let attributes = (&batch[ATTRIBUTES_OFFSET..ATTRIBUTES_OFFSET + 2]).get_i16();
+    match attributes & COMPRESSION_MASK {
+        0 => Ok(Compression::None),
+        1 => Ok(Compression::Gzip),
+        2 => Ok(Compression::Snappy),
+        3 => Ok(Compression::Lz4),
+        4 => Ok(Compression::Zstd),
+        other => {
+            warn!("unknown kafka compression codec: {other}");
+            Err(invalid_data())
+        }
+    }
+}
+
+/// Rewrites the batch with uncompressed records
+fn decompress_batch(batch: &[u8], compression: Compression) -> Result<BytesMut, InterceptError> {
+    let mut records = Vec::new();
+    match compression {
+        Compression::Lz4 => {
+            lz4_flex::frame::FrameDecoder::new(&batch[RECORDS_OFFSET..])
+                .read_to_end(&mut records)
+                .map_err(InterceptError::Io)?;
+        }
+        Compression::Zstd => {
+            records =
+                zstd::stream::decode_all(&batch[RECORDS_OFFSET..]).map_err(InterceptError::Io)?;
+        }
+        _ => return Err(invalid_data()),
+    }
+
+    rebuild_batch(&batch[..RECORDS_OFFSET], &records, Compression::None)
+}
+
+/// Compresses the records of every batch in the buffer
+fn compress_batches(
+    target: &mut BytesMut,
+    mut uncompressed: Bytes,
+    compression: Compression,
+) -> Result<(), InterceptError> {
+    while uncompressed.has_remaining() {
+        let batch_length = (&uncompressed[BATCH_LENGTH_OFFSET..BATCH_HEADER_SIZE]).get_i32();
+        let batch = uncompressed.split_to(BATCH_HEADER_SIZE + batch_length as usize);
+
+        let records = match compression {
+            Compression::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder
+                    .write_all(&batch[RECORDS_OFFSET..])
+                    .map_err(InterceptError::Io)?;
+                encoder
+                    .finish()
+                    .map_err(|_| InterceptError::Io(Error::from(ErrorKind::Other)))?
+            }
+            Compression::Zstd => {
+                zstd::stream::encode_all(&batch[RECORDS_OFFSET..], 0).map_err(InterceptError::Io)?
+            }
+            _ => return Err(invalid_data()),
+        };
+
+        target.extend_from_slice(&rebuild_batch(
+            &batch[..RECORDS_OFFSET],
+            &records,
+            compression,
+        )?);
+    }
+    Ok(())
+}
+
+/// Builds a batch from an existing header and new records content, updating
+/// length, compression attribute and crc accordingly
+fn rebuild_batch(
+    header: &[u8],
+    records: &[u8],
+    compression: Compression,
+) -> Result<BytesMut, InterceptError> {
+    let mut batch = BytesMut::with_capacity(header.len() + records.len());
+    batch.extend_from_slice(header);
+    batch.extend_from_slice(records);
+
+    let batch_length =
+        i32::try_from(batch.len() - BATCH_HEADER_SIZE).map_err(|_| invalid_data())?;
+    (&mut batch[BATCH_LENGTH_OFFSET..BATCH_HEADER_SIZE]).put_i32(batch_length);
+
+    let attributes = (&batch[ATTRIBUTES_OFFSET..ATTRIBUTES_OFFSET + 2]).get_i16();
+    let attributes = (attributes & !COMPRESSION_MASK) | compression as i16;
+    (&mut batch[ATTRIBUTES_OFFSET..ATTRIBUTES_OFFSET + 2]).put_i16(attributes);
+
+    //the crc covers everything from the attributes to the end of the batch
+    let crc = CASTAGNOLI.checksum(&batch[ATTRIBUTES_OFFSET..]);
+    (&mut batch[CRC_OFFSET..ATTRIBUTES_OFFSET]).put_u32(crc);
+
+    Ok(batch)
+}
+
+fn invalid_data() -> InterceptError {
+    InterceptError::Io(Error::from(ErrorKind::InvalidData))
+}
@@ -1,4 +1,4 @@
-use crate::kafka::secure_channel_map::{KafkaSecureChannelController, UniqueSecureChannelId};
+use crate::kafka::secure_channel_map::{DataKeyId, KafkaSecureChannelController};
 use kafka_protocol::messages::ApiKey;
 use minicbor::{Decode, Encode};
 use ockam_core::compat::{
@@ -6,10 +6,13 @@ use ockam_core::compat::{
     fmt::Debug,
     sync::{Arc, Mutex},
 };
-use ockam_core::AsyncTryClone;
+use ockam_core::errcode::{Kind, Origin};
 #[cfg(feature = "tag")]
 use ockam_core::TypeTag;
+use ockam_core::{AsyncTryClone, Error, Result};
+use std::path::Path;
 
+mod compression;
 mod request;
 mod response;
 mod tests;
@@ -23,37 +26,110 @@ struct RequestInfo {
 
 type CorrelationId = i32;
 
-/// map shared across all kafka workers, since the client might request it
-/// only from one connection
-pub(super) type TopicUuidMap = Arc<Mutex<HashMap<String, String>>>;
+/// Header used to carry the encrypted record key, the broker only sees
+/// a keyed hash of the original key in its place
+pub(crate) const ENCRYPTED_KEY_HEADER: &str = "ockam_encrypted_key";
+
+/// Which parts of a record are encrypted besides its value.
+/// Producer and consumer are expected to share the same options.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KafkaEncryptionOptions {
+    /// When set the record key is encrypted and moved into the
+    /// [`ENCRYPTED_KEY_HEADER`] header, the broker-visible key is replaced by
+    /// an HMAC of the original key using this secret.
+    /// Since the hash is deterministic, records with the same key keep
+    /// landing on the same partition.
+    pub(crate) key_hash_secret: Option<Vec<u8>>,
+    /// Names of the headers whose value is encrypted
+    pub(crate) encrypted_headers: Vec<String>,
+}
+
+/// Minimum length of the secret hashing record keys, the broker must not
+/// be able to recover low-entropy keys (user ids, emails...) with a dictionary
+pub(crate) const MIN_KEY_HASH_SECRET_LENGTH: usize = 32;
+
+impl KafkaEncryptionOptions {
+    /// Read the secret hashing record keys from a file.
+    /// Every producer of a topic must be configured with the same secret
+    /// so that records with the same key land on the same partition.
+    pub(crate) fn read_key_hash_secret(path: &Path) -> Result<Vec<u8>> {
+        let mut secret = std::fs::read(path).map_err(|cause| {
+            Error::new(
+                Origin::Application,
+                Kind::NotFound,
+                format!("cannot read {}: {cause}", path.display()),
+            )
+        })?;
+        //a trailing newline is not part of the secret
+        let len = secret
+            .iter()
+            .rposition(|byte| !byte.is_ascii_whitespace())
+            .map_or(0, |last| last + 1);
+        secret.truncate(len);
+        if secret.len() < MIN_KEY_HASH_SECRET_LENGTH {
+            return Err(Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                format!(
+                    "the record key secret in {} must be at least {MIN_KEY_HASH_SECRET_LENGTH} bytes long",
+                    path.display()
+                ),
+            ));
+        }
+        Ok(secret)
+    }
+}
 
 #[derive(AsyncTryClone)]
 pub(crate) struct Interceptor {
     request_map: Arc<Mutex<HashMap<CorrelationId, RequestInfo>>>,
-    uuid_to_name: TopicUuidMap,
     secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
+    encryption_options: Arc<KafkaEncryptionOptions>,
+    topic_names: TopicNames,
+}
+
+/// Topic names by topic id, learnt from metadata responses.
+/// Fetch responses only name topics by id since version 13, and they are
+/// received on other connections than metadata responses, so the names are
+/// shared by every connection of a listener.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TopicNames {
+    inner: Arc<Mutex<HashMap<[u8; 16], String>>>,
+}
+
+impl TopicNames {
+    fn insert(&self, topic_id: [u8; 16], topic_name: String) {
+        self.inner.lock().unwrap().insert(topic_id, topic_name);
+    }
+
+    fn get(&self, topic_id: &[u8; 16]) -> Option<String> {
+        self.inner.lock().unwrap().get(topic_id).cloned()
+    }
 }
 
 #[derive(Debug, Clone, Decode, Encode)]
 #[rustfmt::skip]
 #[cbor(map)]
-///Wraps the content within every record batch
+///Wraps the encrypted content of every record value, key or header
 struct MessageWrapper {
     #[cfg(feature = "tag")]
-    #[n(0)] tag: TypeTag<1652220>,
-    #[b(1)] secure_channel_identifier: UniqueSecureChannelId,
-    #[b(2)] content: Vec<u8>
+    #[n(0)] tag: TypeTag<3718545>,
+    #[n(1)] producer_id: String,
+    #[n(2)] data_key_id: DataKeyId,
+    #[b(3)] content: Vec<u8>
 }
 
 impl Interceptor {
     pub(crate) fn new(
         secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
-        uuid_to_name: TopicUuidMap,
+        encryption_options: Arc<KafkaEncryptionOptions>,
+        topic_names: TopicNames,
     ) -> Interceptor {
         Self {
             request_map: Arc::new(Mutex::new(Default::default())),
-            uuid_to_name,
             secure_channel_controller,
+            encryption_options,
+            topic_names,
         }
     }
 }
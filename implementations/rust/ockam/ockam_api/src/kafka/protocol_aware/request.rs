@@ -1,24 +1,26 @@
 use bytes::{Bytes, BytesMut};
-use kafka_protocol::messages::fetch_request::FetchRequest;
+use hmac::{Hmac, Mac};
 use kafka_protocol::messages::produce_request::ProduceRequest;
 use kafka_protocol::messages::request_header::RequestHeader;
-use kafka_protocol::messages::ApiKey;
+use kafka_protocol::messages::{ApiKey, TopicName};
 use kafka_protocol::protocol::buf::ByteBuf;
 use kafka_protocol::protocol::Decodable;
-use kafka_protocol::records::{
-    Compression, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions,
-};
+use kafka_protocol::records::Record;
 use minicbor::encode::Encoder;
 #[cfg(feature = "tag")]
 use ockam_core::TypeTag;
 use ockam_node::Context;
+use sha2::Sha256;
 use std::convert::TryFrom;
 use std::io::{Error, ErrorKind};
 use tracing::{trace, warn};
 
 use crate::kafka::portal_worker::InterceptError;
-use crate::kafka::protocol_aware::utils::{decode_body, encode_request};
-use crate::kafka::protocol_aware::{Interceptor, MessageWrapper, RequestInfo};
+use crate::kafka::protocol_aware::compression::{decode_record_batches, encode_record_batches};
+use crate::kafka::protocol_aware::utils::{decode_body, encode_request, string_to_str_bytes};
+use crate::kafka::protocol_aware::{
+    Interceptor, MessageWrapper, RequestInfo, ENCRYPTED_KEY_HEADER,
+};
 
 impl Interceptor {
     ///Parse request and map request <=> response
@@ -70,11 +72,7 @@ impl Interceptor {
                     .handle_produce_request(context, &mut buffer, &header)
                     .await;
             }
-            ApiKey::FetchKey => {
-                self.handle_fetch_request(context, &mut buffer, &header)
-                    .await?;
-            }
-            ApiKey::MetadataKey | ApiKey::FindCoordinatorKey => {
+            ApiKey::FetchKey | ApiKey::MetadataKey | ApiKey::FindCoordinatorKey => {
                 self.request_map.lock().unwrap().insert(
                     header.correlation_id,
                     RequestInfo {
@@ -101,57 +99,6 @@ impl Interceptor {
         Ok(original)
     }
 
-    async fn handle_fetch_request(
-        &self,
-        context: &mut Context,
-        buffer: &mut Bytes,
-        header: &RequestHeader,
-    ) -> Result<(), InterceptError> {
-        let request: FetchRequest = decode_body(buffer, header.request_api_version)?;
-
-        //we intercept every partition interested by the kafka client
-        //and create a forwarder for each
-        for topic in &request.topics {
-            let topic_id = if header.request_api_version <= 12 {
-                topic.topic.0.to_string()
-            } else {
-                //fetch operation using version >= 13 don't use topic name
-                //anymore but uses uuid instead, we built a map using
-                //previous Metadata requests
-                let topic_id = topic.topic_id.to_string();
-                self.uuid_to_name
-                    .lock()
-                    .unwrap()
-                    .get(&topic_id)
-                    .cloned()
-                    .ok_or_else(|| {
-                        warn!("missing map from uuid {topic_id} to name");
-                        InterceptError::Io(Error::from(ErrorKind::InvalidData))
-                    })?
-            };
-
-            let partitions: Vec<i32> = topic
-                .partitions
-                .iter()
-                .map(|partition| partition.partition)
-                .collect();
-
-            self.secure_channel_controller
-                .start_forwarders_for(context, &topic_id, partitions)
-                .await
-                .map_err(InterceptError::Ockam)?
-        }
-
-        self.request_map.lock().unwrap().insert(
-            header.correlation_id,
-            RequestInfo {
-                request_api_key: ApiKey::FetchKey,
-                request_api_version: header.request_api_version,
-            },
-        );
-        Ok(())
-    }
-
     async fn handle_produce_request(
         &self,
         context: &mut Context,
@@ -161,59 +108,21 @@ impl Interceptor {
         let mut request: ProduceRequest = decode_body(buffer, header.request_api_version)?;
 
         //the content can be set in multiple topics and partitions in a single message
-        //for each we wrap the content and add the secure channel identifier of
+        //for each we wrap the content and add the data key identifier of
         //the encrypted content
         for (topic_name, topic) in request.topic_data.iter_mut() {
             for data in &mut topic.partition_data {
                 if let Some(content) = data.records.take() {
-                    let mut content = BytesMut::from(content.as_ref());
-                    let mut records = RecordBatchDecoder::decode(&mut content)
-                        .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
-
-                    for record in records.iter_mut() {
-                        if let Some(record_value) = record.value.take() {
-                            let encrypted_content = self
-                                .secure_channel_controller
-                                .encrypt_content_for(
-                                    context,
-                                    topic_name,
-                                    data.index,
-                                    record_value.to_vec(),
-                                )
-                                .await
-                                .map_err(InterceptError::Ockam)?;
-
-                            //TODO: to target multiple consumers we could duplicate
-                            // the content with a dedicated encryption for each consumer
-                            let wrapper = MessageWrapper {
-                                #[cfg(feature = "tag")]
-                                tag: TypeTag,
-                                secure_channel_identifier: encrypted_content.secure_channel_id,
-                                content: encrypted_content.content,
-                            };
-
-                            let mut write_buffer = Vec::with_capacity(1024);
-                            let mut encoder = Encoder::new(&mut write_buffer);
-                            encoder.encode(wrapper).map_err(|_err| {
-                                InterceptError::Io(Error::from(ErrorKind::InvalidData))
-                            })?;
-
-                            record.value = Some(write_buffer.into());
+                    //the producer compression codec is kept for each record batch
+                    let mut batches = decode_record_batches(&content)?;
+
+                    for batch in batches.iter_mut() {
+                        for record in batch.records.iter_mut() {
+                            self.encrypt_record(context, topic_name, record).await?;
                         }
                     }
 
-                    let mut encoded = BytesMut::new();
-                    RecordBatchEncoder::encode(
-                        &mut encoded,
-                        records.iter(),
-                        &RecordEncodeOptions {
-                            version: 2,
-                            compression: Compression::None,
-                        },
-                    )
-                    .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
-
-                    data.records = Some(encoded.freeze());
+                    data.records = Some(encode_record_batches(&batches)?);
                 }
             }
         }
@@ -225,4 +134,89 @@ impl Interceptor {
             ApiKey::ProduceKey,
         )
     }
+
+    /// Encrypts the value of the record, and when configured its key and
+    /// selected headers
+    async fn encrypt_record(
+        &self,
+        context: &mut Context,
+        topic_name: &TopicName,
+        record: &mut Record,
+    ) -> Result<(), InterceptError> {
+        if let Some(record_value) = record.value.take() {
+            record.value = Some(
+                self.encrypt_field(context, topic_name, record_value)
+                    .await?,
+            );
+        }
+
+        for header_name in &self.encryption_options.encrypted_headers {
+            if let Some(header_value) = record
+                .headers
+                .get_mut(header_name.as_str())
+                .and_then(|value| value.take())
+            {
+                let encrypted = self
+                    .encrypt_field(context, topic_name, header_value)
+                    .await?;
+                record
+                    .headers
+                    .insert(string_to_str_bytes(header_name.clone()), Some(encrypted));
+            }
+        }
+
+        if let Some(key_hash_secret) = &self.encryption_options.key_hash_secret {
+            if let Some(record_key) = record.key.take() {
+                //the broker partitions records using the key, so we replace it
+                //with a deterministic hash and carry the encrypted key in a header
+                record.key = Some(keyed_hash(key_hash_secret, &record_key)?);
+                let encrypted = self.encrypt_field(context, topic_name, record_key).await?;
+                record.headers.insert(
+                    string_to_str_bytes(ENCRYPTED_KEY_HEADER.to_string()),
+                    Some(encrypted),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Encrypts the content and wraps it along the data key identifier, any
+    /// consumer authorized by the producer can retrieve the data key
+    async fn encrypt_field(
+        &self,
+        context: &mut Context,
+        topic_name: &TopicName,
+        content: Bytes,
+    ) -> Result<Bytes, InterceptError> {
+        let encrypted_content = self
+            .secure_channel_controller
+            .encrypt_content_for(context, topic_name, content.to_vec())
+            .await
+            .map_err(InterceptError::Ockam)?;
+
+        let wrapper = MessageWrapper {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            producer_id: encrypted_content.producer_id,
+            data_key_id: encrypted_content.data_key_id,
+            content: encrypted_content.content,
+        };
+
+        let mut write_buffer = Vec::with_capacity(1024);
+        let mut encoder = Encoder::new(&mut write_buffer);
+        encoder
+            .encode(wrapper)
+            .map_err(|_err| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
+
+        Ok(write_buffer.into())
+    }
+}
+
+/// HMAC-SHA256 of the record key, used as broker-visible key
+fn keyed_hash(secret: &[u8], key: &[u8]) -> Result<Bytes, InterceptError> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+        .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidInput)))?;
+    mac.update(key);
+    Ok(Bytes::from(mac.finalize().into_bytes().to_vec()))
 }
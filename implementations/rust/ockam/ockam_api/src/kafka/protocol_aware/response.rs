@@ -9,17 +9,18 @@ use kafka_protocol::messages::response_header::ResponseHeader;
 use kafka_protocol::messages::ApiKey;
 use kafka_protocol::protocol::buf::ByteBuf;
 use kafka_protocol::protocol::Decodable;
-use kafka_protocol::records::{
-    Compression, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions,
-};
+use kafka_protocol::records::Record;
 use minicbor::decode::Decoder;
 use ockam_node::Context;
 use tracing::{info, trace, warn};
 
 use crate::kafka::inlet_map::KafkaInletMap;
 use crate::kafka::portal_worker::InterceptError;
+use crate::kafka::protocol_aware::compression::{decode_record_batches, encode_record_batches};
 use crate::kafka::protocol_aware::utils::{decode_body, encode_response, string_to_str_bytes};
-use crate::kafka::protocol_aware::{Interceptor, MessageWrapper, RequestInfo};
+use crate::kafka::protocol_aware::{
+    Interceptor, MessageWrapper, RequestInfo, ENCRYPTED_KEY_HEADER,
+};
 
 impl Interceptor {
     pub(crate) async fn intercept_response(
@@ -123,23 +124,13 @@ impl Interceptor {
     ) -> Result<BytesMut, InterceptError> {
         let mut response: MetadataResponse = decode_body(buffer, request_info.request_api_version)?;
 
-        //we need to keep a map of topic uuid to topic name since fetch
-        //operations only use uuid
-        if request_info.request_api_version >= 10 {
-            for (topic_name, topic) in &response.topics {
-                let topic_id = topic.topic_id.to_string();
-                let topic_name = topic_name.to_string();
-
-                trace!("metadata adding to map: {topic_id} => {topic_name}");
-                self.uuid_to_name
-                    .lock()
-                    .unwrap()
-                    .insert(topic_id, topic_name);
-            }
-        }
-
         trace!("metadata response before: {:?}", &response);
 
+        for (topic_name, topic) in response.topics.iter() {
+            self.topic_names
+                .insert(*topic.topic_id.as_bytes(), topic_name.to_string());
+        }
+
         for (broker_id, info) in response.brokers.iter_mut() {
             let inlet_address: SocketAddr = inlet_map
                 .assert_inlet_for_broker(context, broker_id.0)
@@ -221,46 +212,31 @@ impl Interceptor {
 
         //in every response we want to decrypt the message content
         //we take every record batch content, unwrap and decode it
-        //using the relative secure channel
+        //using the data key of the producer
         for response in response.responses.iter_mut() {
+            let topic_name = if request_info.request_api_version < 13 {
+                response.topic.to_string()
+            } else {
+                self.topic_names
+                    .get(response.topic_id.as_bytes())
+                    .ok_or_else(|| {
+                        warn!("fetch response for an unknown topic id");
+                        InterceptError::Io(Error::from(ErrorKind::InvalidData))
+                    })?
+            };
+
             for partition in response.partitions.iter_mut() {
                 if let Some(content) = partition.records.take() {
-                    let mut content = BytesMut::from(content.as_ref());
-                    let mut records = RecordBatchDecoder::decode(&mut content)
-                        .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
-
-                    for record in records.iter_mut() {
-                        if let Some(record_value) = record.value.take() {
-                            let message_wrapper: MessageWrapper =
-                                Decoder::new(record_value.as_ref()).decode().map_err(|_| {
-                                    InterceptError::Io(Error::from(ErrorKind::InvalidData))
-                                })?;
-
-                            let decrypted_content = self
-                                .secure_channel_controller
-                                .decrypt_content_for(
-                                    context,
-                                    message_wrapper.secure_channel_identifier,
-                                    message_wrapper.content,
-                                )
-                                .await
-                                .map_err(InterceptError::Ockam)?;
-
-                            record.value = Some(decrypted_content.into());
+                    //the compression codec is kept for each record batch
+                    let mut batches = decode_record_batches(&content)?;
+
+                    for batch in batches.iter_mut() {
+                        for record in batch.records.iter_mut() {
+                            self.decrypt_record(context, &topic_name, record).await?;
                         }
                     }
 
-                    let mut encoded = BytesMut::new();
-                    RecordBatchEncoder::encode(
-                        &mut encoded,
-                        records.iter(),
-                        &RecordEncodeOptions {
-                            version: 2,
-                            compression: Compression::None,
-                        },
-                    )
-                    .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
-                    partition.records = Some(encoded.freeze());
+                    partition.records = Some(encode_record_batches(&batches)?);
                 }
             }
         }
@@ -272,4 +248,71 @@ impl Interceptor {
             ApiKey::FetchKey,
         )
     }
+
+    /// Decrypts the value of the record, and when present its key and
+    /// selected headers
+    async fn decrypt_record(
+        &self,
+        context: &mut Context,
+        topic_name: &str,
+        record: &mut Record,
+    ) -> Result<(), InterceptError> {
+        if let Some(record_value) = record.value.take() {
+            record.value = Some(
+                self.decrypt_field(context, topic_name, &record_value)
+                    .await?,
+            );
+        }
+
+        for header_name in &self.encryption_options.encrypted_headers {
+            if let Some(header_value) = record
+                .headers
+                .get_mut(header_name.as_str())
+                .and_then(|value| value.take())
+            {
+                let decrypted = self
+                    .decrypt_field(context, topic_name, &header_value)
+                    .await?;
+                record
+                    .headers
+                    .insert(string_to_str_bytes(header_name.clone()), Some(decrypted));
+            }
+        }
+
+        //the original key is restored in place of the keyed hash
+        if let Some(Some(encrypted_key)) = record.headers.shift_remove(ENCRYPTED_KEY_HEADER) {
+            record.key = Some(
+                self.decrypt_field(context, topic_name, &encrypted_key)
+                    .await?,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Unwraps the content and decrypts it using the data key of the producer
+    async fn decrypt_field(
+        &self,
+        context: &mut Context,
+        topic_name: &str,
+        content: &Bytes,
+    ) -> Result<Bytes, InterceptError> {
+        let message_wrapper: MessageWrapper = Decoder::new(content.as_ref())
+            .decode()
+            .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
+
+        let decrypted_content = self
+            .secure_channel_controller
+            .decrypt_content_for(
+                context,
+                topic_name,
+                &message_wrapper.producer_id,
+                message_wrapper.data_key_id,
+                message_wrapper.content,
+            )
+            .await
+            .map_err(InterceptError::Ockam)?;
+
+        Ok(decrypted_content.into())
+    }
 }
@@ -1,15 +1,31 @@
 #[cfg(test)]
 mod test {
     use crate::kafka::inlet_map::KafkaInletMap;
+    use crate::kafka::protocol_aware::compression::{
+        decode_record_batches, encode_record_batches, RecordBatch,
+    };
     use crate::kafka::protocol_aware::utils::{encode_request, encode_response};
-    use crate::kafka::protocol_aware::{Interceptor, UniqueSecureChannelId};
-    use crate::kafka::secure_channel_map::{KafkaEncryptedContent, KafkaSecureChannelController};
+    use crate::kafka::protocol_aware::{
+        Interceptor, KafkaEncryptionOptions, MIN_KEY_HASH_SECRET_LENGTH,
+    };
+    use crate::kafka::secure_channel_map::{
+        DataKeyId, KafkaEncryptedContent, KafkaSecureChannelController,
+    };
     use crate::port_range::PortRange;
+    use bytes::Bytes;
+    use indexmap::IndexMap;
+    use kafka_protocol::messages::fetch_request::{FetchPartition, FetchTopic};
+    use kafka_protocol::messages::fetch_response::{FetchableTopicResponse, PartitionData};
+    use kafka_protocol::messages::produce_request::{PartitionProduceData, TopicProduceData};
     use kafka_protocol::messages::ApiKey;
     use kafka_protocol::messages::BrokerId;
     use kafka_protocol::messages::{ApiVersionsRequest, MetadataRequest, MetadataResponse};
     use kafka_protocol::messages::{ApiVersionsResponse, RequestHeader, ResponseHeader};
-    use kafka_protocol::protocol::{Builder, StrBytes};
+    use kafka_protocol::messages::{FetchRequest, FetchResponse, ProduceRequest, TopicName};
+    use kafka_protocol::messages::{SaslAuthenticateRequest, SaslAuthenticateResponse};
+    use kafka_protocol::messages::{SaslHandshakeRequest, SaslHandshakeResponse};
+    use kafka_protocol::protocol::{Builder, Decodable, StrBytes};
+    use kafka_protocol::records::{Compression, Record, TimestampType};
     use ockam_core::compat::sync::Arc;
     use ockam_core::{async_trait, route};
     use ockam_node::Context;
@@ -22,32 +38,25 @@ mod test {
             &self,
             _context: &mut Context,
             _topic_name: &str,
-            _partition_id: i32,
             content: Vec<u8>,
         ) -> ockam_core::Result<KafkaEncryptedContent> {
             Ok(KafkaEncryptedContent {
                 content,
-                secure_channel_id: 0,
+                producer_id: "producer".to_string(),
+                data_key_id: 0,
             })
         }
 
         async fn decrypt_content_for(
             &self,
             _context: &mut Context,
-            _secure_channel_id: UniqueSecureChannelId,
+            _topic_name: &str,
+            _producer_id: &str,
+            _data_key_id: DataKeyId,
             encrypted_content: Vec<u8>,
         ) -> ockam_core::Result<Vec<u8>> {
             Ok(encrypted_content)
         }
-
-        async fn start_forwarders_for(
-            &self,
-            _context: &mut Context,
-            _topic_id: &str,
-            _partitions: Vec<i32>,
-        ) -> ockam_core::Result<()> {
-            Ok(())
-        }
     }
 
     #[allow(non_snake_case)]
@@ -58,6 +67,7 @@ mod test {
         let interceptor = Interceptor::new(
             Arc::new(DummySecureChannelController {}),
             Default::default(),
+            Default::default(),
         );
 
         let inlet_map = KafkaInletMap::new(
@@ -200,4 +210,409 @@ mod test {
 
         context.stop().await
     }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn interceptor__compressed_record_batches__compression_kept(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let interceptor = Interceptor::new(
+            Arc::new(DummySecureChannelController {}),
+            Default::default(),
+            Default::default(),
+        );
+
+        let inlet_map = KafkaInletMap::new(
+            route![],
+            "invalid-address".to_string(),
+            PortRange::new(0, 0).unwrap(),
+        );
+
+        let mut correlation_id = 0;
+        for compression in [
+            Compression::None,
+            Compression::Gzip,
+            Compression::Snappy,
+            Compression::Lz4,
+            Compression::Zstd,
+        ] {
+            let records = encode_record_batches(&[RecordBatch {
+                compression,
+                records: vec![create_record("hello"), create_record("world")],
+            }])
+            .unwrap();
+
+            //produce
+            let result = interceptor
+                .intercept_request(
+                    context,
+                    encode_request(
+                        &create_request_header(ApiKey::ProduceKey, PRODUCE_VERSION, correlation_id),
+                        &create_produce_request(records),
+                        PRODUCE_VERSION,
+                        ApiKey::ProduceKey,
+                    )
+                    .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            let mut result = result.freeze();
+            RequestHeader::decode(
+                &mut result,
+                ApiKey::ProduceKey.request_header_version(PRODUCE_VERSION),
+            )
+            .unwrap();
+            let request = ProduceRequest::decode(&mut result, PRODUCE_VERSION).unwrap();
+            let encrypted_records = request
+                .topic_data
+                .get(&TopicName::from(StrBytes::from_str(TOPIC_NAME)))
+                .unwrap()
+                .partition_data[0]
+                .records
+                .clone()
+                .unwrap();
+
+            let batches = decode_record_batches(&encrypted_records).unwrap();
+            assert_eq!(1, batches.len());
+            assert_eq!(compression, batches[0].compression);
+            assert_eq!(2, batches[0].records.len());
+            assert_ne!(
+                batches[0].records[0].value.as_ref().unwrap(),
+                "hello".as_bytes()
+            );
+
+            //fetch
+            correlation_id += 1;
+            interceptor
+                .intercept_request(
+                    context,
+                    encode_request(
+                        &create_request_header(ApiKey::FetchKey, FETCH_VERSION, correlation_id),
+                        &create_fetch_request(),
+                        FETCH_VERSION,
+                        ApiKey::FetchKey,
+                    )
+                    .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            let result = interceptor
+                .intercept_response(
+                    context,
+                    encode_response(
+                        &ResponseHeader::builder()
+                            .correlation_id(correlation_id)
+                            .unknown_tagged_fields(Default::default())
+                            .build()
+                            .unwrap(),
+                        &create_fetch_response(encrypted_records),
+                        FETCH_VERSION,
+                        ApiKey::FetchKey,
+                    )
+                    .unwrap(),
+                    &inlet_map,
+                )
+                .await
+                .unwrap();
+
+            let mut result = result.freeze();
+            ResponseHeader::decode(
+                &mut result,
+                ApiKey::FetchKey.response_header_version(FETCH_VERSION),
+            )
+            .unwrap();
+            let response = FetchResponse::decode(&mut result, FETCH_VERSION).unwrap();
+            let plain_records = response.responses[0].partitions[0].records.clone().unwrap();
+
+            let batches = decode_record_batches(&plain_records).unwrap();
+            assert_eq!(1, batches.len());
+            assert_eq!(compression, batches[0].compression);
+            assert_eq!(
+                batches[0].records[0].value.as_ref().unwrap(),
+                "hello".as_bytes()
+            );
+            assert_eq!(
+                batches[0].records[1].value.as_ref().unwrap(),
+                "world".as_bytes()
+            );
+
+            correlation_id += 1;
+        }
+
+        context.stop().await
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn interceptor__sasl_exchange__passed_through_unchanged(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let interceptor = Interceptor::new(
+            Arc::new(DummySecureChannelController {}),
+            Default::default(),
+            Default::default(),
+        );
+
+        let inlet_map = KafkaInletMap::new(
+            route![],
+            "invalid-address".to_string(),
+            PortRange::new(0, 0).unwrap(),
+        );
+
+        for api_version in 0..2 {
+            let handshake_request = encode_request(
+                &create_request_header(ApiKey::SaslHandshakeKey, api_version, 0),
+                &SaslHandshakeRequest::builder()
+                    .mechanism(StrBytes::from_str("PLAIN"))
+                    .build()
+                    .unwrap(),
+                api_version,
+                ApiKey::SaslHandshakeKey,
+            )
+            .unwrap();
+            let result = interceptor
+                .intercept_request(context, handshake_request.clone())
+                .await
+                .unwrap();
+            assert_eq!(handshake_request, result);
+
+            let handshake_response = encode_response(
+                &ResponseHeader::builder()
+                    .correlation_id(0)
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap(),
+                &SaslHandshakeResponse::builder()
+                    .error_code(0)
+                    .mechanisms(vec![StrBytes::from_str("PLAIN")])
+                    .build()
+                    .unwrap(),
+                api_version,
+                ApiKey::SaslHandshakeKey,
+            )
+            .unwrap();
+            let result = interceptor
+                .intercept_response(context, handshake_response.clone(), &inlet_map)
+                .await
+                .unwrap();
+            assert_eq!(handshake_response, result);
+
+            let authenticate_request = encode_request(
+                &create_request_header(ApiKey::SaslAuthenticateKey, api_version, 1),
+                &SaslAuthenticateRequest::builder()
+                    .auth_bytes(Bytes::from_static(b"\0user\0password"))
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap(),
+                api_version,
+                ApiKey::SaslAuthenticateKey,
+            )
+            .unwrap();
+            let result = interceptor
+                .intercept_request(context, authenticate_request.clone())
+                .await
+                .unwrap();
+            assert_eq!(authenticate_request, result);
+
+            let authenticate_response = encode_response(
+                &ResponseHeader::builder()
+                    .correlation_id(1)
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap(),
+                &SaslAuthenticateResponse::builder()
+                    .error_code(0)
+                    .error_message(None)
+                    .auth_bytes(Bytes::new())
+                    .session_lifetime_ms(0)
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap(),
+                api_version,
+                ApiKey::SaslAuthenticateKey,
+            )
+            .unwrap();
+            let result = interceptor
+                .intercept_response(context, authenticate_response.clone(), &inlet_map)
+                .await
+                .unwrap();
+            assert_eq!(authenticate_response, result);
+        }
+
+        context.stop().await
+    }
+
+    #[allow(non_snake_case)]
+    #[test]
+    fn key_hash_secret__read_from_file__short_secrets_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret");
+
+        std::fs::write(&path, "too short\n").unwrap();
+        assert!(KafkaEncryptionOptions::read_key_hash_secret(&path).is_err());
+
+        let secret = "a".repeat(MIN_KEY_HASH_SECRET_LENGTH);
+        std::fs::write(&path, format!("{secret}\n")).unwrap();
+        assert_eq!(
+            secret.into_bytes(),
+            KafkaEncryptionOptions::read_key_hash_secret(&path).unwrap()
+        );
+
+        assert!(KafkaEncryptionOptions::read_key_hash_secret(&dir.path().join("missing")).is_err());
+    }
+
+    #[allow(non_snake_case)]
+    #[test]
+    fn record_batches__compressed_codecs__smaller_than_uncompressed() {
+        let large_value = "a".repeat(10_000);
+        let uncompressed = encode_record_batches(&[RecordBatch {
+            compression: Compression::None,
+            records: vec![create_record(&large_value)],
+        }])
+        .unwrap();
+
+        for compression in [
+            Compression::Gzip,
+            Compression::Snappy,
+            Compression::Lz4,
+            Compression::Zstd,
+        ] {
+            let compressed = encode_record_batches(&[RecordBatch {
+                compression,
+                records: vec![create_record(&large_value)],
+            }])
+            .unwrap();
+            assert!(compressed.len() < uncompressed.len() / 10);
+
+            let batches = decode_record_batches(&compressed).unwrap();
+            assert_eq!(compression, batches[0].compression);
+            assert_eq!(
+                batches[0].records[0].value.as_ref().unwrap(),
+                large_value.as_bytes()
+            );
+        }
+    }
+
+    const TOPIC_NAME: &str = "my-topic";
+    const PRODUCE_VERSION: i16 = 9;
+    const FETCH_VERSION: i16 = 12;
+
+    fn create_record(value: &str) -> Record {
+        Record {
+            transactional: false,
+            control: false,
+            partition_leader_epoch: 0,
+            producer_id: 0,
+            producer_epoch: 0,
+            timestamp_type: TimestampType::Creation,
+            offset: 0,
+            sequence: 0,
+            timestamp: 0,
+            key: None,
+            value: Some(Bytes::from(value.to_string())),
+            headers: Default::default(),
+        }
+    }
+
+    fn create_request_header(api_key: ApiKey, version: i16, correlation_id: i32) -> RequestHeader {
+        RequestHeader::builder()
+            .request_api_version(version)
+            .correlation_id(correlation_id)
+            .request_api_key(api_key as i16)
+            .unknown_tagged_fields(Default::default())
+            .client_id(None)
+            .build()
+            .unwrap()
+    }
+
+    fn create_produce_request(records: Bytes) -> ProduceRequest {
+        let mut topic_data = IndexMap::new();
+        topic_data.insert(
+            TopicName::from(StrBytes::from_str(TOPIC_NAME)),
+            TopicProduceData::builder()
+                .partition_data(vec![PartitionProduceData::builder()
+                    .index(0)
+                    .records(Some(records))
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap()])
+                .unknown_tagged_fields(Default::default())
+                .build()
+                .unwrap(),
+        );
+        ProduceRequest::builder()
+            .transactional_id(None)
+            .acks(0)
+            .timeout_ms(0)
+            .topic_data(topic_data)
+            .unknown_tagged_fields(Default::default())
+            .build()
+            .unwrap()
+    }
+
+    fn create_fetch_request() -> FetchRequest {
+        FetchRequest::builder()
+            .cluster_id(None)
+            .replica_id(BrokerId::default())
+            .max_wait_ms(0)
+            .min_bytes(0)
+            .max_bytes(0)
+            .isolation_level(0)
+            .session_id(0)
+            .session_epoch(0)
+            .topics(vec![FetchTopic::builder()
+                .topic(TopicName::from(StrBytes::from_str(TOPIC_NAME)))
+                .topic_id(Default::default())
+                .partitions(vec![FetchPartition::builder()
+                    .partition(0)
+                    .current_leader_epoch(0)
+                    .fetch_offset(0)
+                    .last_fetched_epoch(0)
+                    .log_start_offset(0)
+                    .partition_max_bytes(0)
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap()])
+                .unknown_tagged_fields(Default::default())
+                .build()
+                .unwrap()])
+            .forgotten_topics_data(Default::default())
+            .rack_id(Default::default())
+            .unknown_tagged_fields(Default::default())
+            .build()
+            .unwrap()
+    }
+
+    fn create_fetch_response(records: Bytes) -> FetchResponse {
+        FetchResponse::builder()
+            .throttle_time_ms(Default::default())
+            .error_code(Default::default())
+            .session_id(Default::default())
+            .responses(vec![FetchableTopicResponse::builder()
+                .topic(TopicName::from(StrBytes::from_str(TOPIC_NAME)))
+                .topic_id(Default::default())
+                .partitions(vec![PartitionData::builder()
+                    .partition_index(0)
+                    .error_code(Default::default())
+                    .high_watermark(Default::default())
+                    .last_stable_offset(Default::default())
+                    .log_start_offset(Default::default())
+                    .diverging_epoch(Default::default())
+                    .current_leader(Default::default())
+                    .snapshot_id(Default::default())
+                    .aborted_transactions(Default::default())
+                    .preferred_read_replica(Default::default())
+                    .records(Some(records))
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap()])
+                .unknown_tagged_fields(Default::default())
+                .build()
+                .unwrap()])
+            .unknown_tagged_fields(Default::default())
+            .build()
+            .unwrap()
+    }
 }
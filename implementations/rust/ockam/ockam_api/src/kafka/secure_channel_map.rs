@@ -1,108 +1,236 @@
 use crate::kafka::{
-    KAFKA_SECURE_CHANNEL_CONTROLLER_ADDRESS, KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS,
+    KAFKA_DATA_KEY_DISTRIBUTOR_ADDRESS, KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS,
     ORCHESTRATOR_KAFKA_CONSUMERS,
 };
+use crate::DefaultAddress;
 use ockam::remote::{RemoteForwarder, RemoteForwarderTrustOptions};
-use ockam_core::compat::collections::{HashMap, HashSet};
+use ockam_abac::{Action, Env, PolicyAccessControl, PolicyStorage, Resource};
+use ockam_core::compat::collections::HashMap;
 use ockam_core::compat::sync::Arc;
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::Message;
-use ockam_core::{async_trait, route, Address, AllowAll, Error, Result, Route, Routed, Worker};
-use ockam_identity::api::{
-    DecryptionRequest, DecryptionResponse, EncryptionRequest, EncryptionResponse,
+use ockam_core::vault::{
+    KeyId, Secret, SecretAttributes, SecretKey, SecretPersistence, SecretType,
+    AES256_SECRET_LENGTH_U32,
 };
+use ockam_core::{
+    async_trait, route, Address, AllowAll, Error, IncomingAccessControl, Message, RelayMessage,
+    Result, Route, Routed, Worker,
+};
+use ockam_identity::authenticated_storage::IdentityAttributeStorage;
 use ockam_identity::{
-    Identity, SecureChannelRegistryEntry, SecureChannelTrustOptions, TrustEveryonePolicy,
+    Identity, IdentityIdentifier, PublicIdentity, SecureChannelTrustOptions, TrustEveryonePolicy,
+    TrustMultiIdentifiersPolicy,
 };
 use ockam_node::compat::tokio::sync::Mutex;
-use ockam_node::Context;
+use ockam_node::{Context, WorkerBuilder};
 use serde::{Deserialize, Serialize};
 
+/// Length of the nonce prepended to every encrypted content
+const NONCE_LENGTH: usize = 12;
+
 pub(crate) struct KafkaEncryptedContent {
-    /// The encrypted content
+    /// The encrypted content, prefixed by the nonce
     pub(crate) content: Vec<u8>,
-    /// The secure channel id used to encrypt the content
-    pub(crate) secure_channel_id: UniqueSecureChannelId,
+    /// The producer which encrypted the content, used by consumers to
+    /// retrieve the data key
+    pub(crate) producer_id: String,
+    /// The data key used to encrypt the content
+    pub(crate) data_key_id: DataKeyId,
 }
 
 /// Offer simple APIs to encrypt and decrypt kafka messages.
-/// Underneath it creates secure channels for each topic/partition
-/// and uses them to encrypt the content.
-/// Multiple secure channels may be created for the same topic/partition
-/// but each will be explicitly labelled.
+/// Every producer encrypts the content using a symmetric data key for each
+/// topic, and distributes data keys to consumers on request.
+/// Consumers retrieve data keys using a dedicated secure channel for
+/// each producer, so any number of consumer identities can decrypt the same
+/// content as long as the producer access control authorizes them.
 /// It's the same for both producer and consumer although it could be split
 /// into two distinct implementations.
 /// This is a proxy trait to avoid propagating the vault implementation.
 #[async_trait]
 pub(crate) trait KafkaSecureChannelController: Send + Sync {
-    /// Encrypts the content specifically for the consumer waiting for that topic name and
-    /// partition.
-    /// To do so it'll create a secure channel which will be used for key exchange only.
-    /// The secure channel will be created only once and then re-used, hence the first time will
-    /// be slower, and may take up to few seconds.
+    /// Encrypts the content using the data key of the topic.
+    /// The data key is created the first time a topic is used, then re-used.
     async fn encrypt_content_for(
         &self,
         context: &mut Context,
         topic_name: &str,
-        partition_id: i32,
         content: Vec<u8>,
     ) -> Result<KafkaEncryptedContent>;
 
-    /// Decrypts the content based on the unique secure channel identifier
-    /// the secure channel is expected to be already initialized.
+    /// Decrypts the content of a topic using the data key of the producer.
+    /// When the data key is unknown, it's retrieved from the producer, the first
+    /// time will be slower since it requires a secure channel, and may take up
+    /// to few seconds.
     async fn decrypt_content_for(
         &self,
         context: &mut Context,
-        secure_channel_id: UniqueSecureChannelId,
+        topic_name: &str,
+        producer_id: &str,
+        data_key_id: DataKeyId,
         encrypted_content: Vec<u8>,
     ) -> Result<Vec<u8>>;
-
-    /// Starts forwarders in the orchestrator for each {partition}_{topic_name} combination
-    /// should be used only by the consumer.
-    /// does nothing if they were already created, but fails it they already exist.
-    async fn start_forwarders_for(
-        &self,
-        context: &mut Context,
-        topic_id: &str,
-        partitions: Vec<i32>,
-    ) -> Result<()>;
 }
 
 #[async_trait]
 pub(crate) trait ForwarderCreator: Send + Sync + 'static {
     async fn create_forwarder(&self, context: &Context, alias: String) -> Result<()>;
+
+    /// The route consumers use to reach the forwarder created with `alias`
+    fn forwarder_route(&self, alias: &str) -> Route;
 }
 
-pub(crate) struct RemoteForwarderCreator {
-    hub_route: Route,
+/// Creates the producer forwarder on a remote node
+pub(crate) enum RemoteForwarderCreator {
+    /// The orchestrator kafka consumers service of the project, which adds
+    /// the `consumer__` prefix to the alias
+    Orchestrator { project_route: Route },
+    /// A self-hosted node running a forwarding service, the alias is kept as it is
+    Relay { relay_route: Route },
 }
 
 #[async_trait]
 impl ForwarderCreator for RemoteForwarderCreator {
     async fn create_forwarder(&self, context: &Context, alias: String) -> Result<()> {
         trace!("creating remote forwarder for: {alias}");
-        let remote_forwarder_information = RemoteForwarder::create_static(
-            context,
-            self.hub_route.clone(),
-            alias.clone(),
-            RemoteForwarderTrustOptions::new(),
-        )
-        .await?;
+        let remote_forwarder_information = match self {
+            RemoteForwarderCreator::Orchestrator { project_route } => {
+                RemoteForwarder::create_static(
+                    context,
+                    route![project_route.clone(), ORCHESTRATOR_KAFKA_CONSUMERS],
+                    alias,
+                    RemoteForwarderTrustOptions::new(),
+                )
+                .await?
+            }
+            RemoteForwarderCreator::Relay { relay_route } => {
+                RemoteForwarder::create_static_on_node(
+                    context,
+                    relay_route.clone(),
+                    alias,
+                    RemoteForwarderTrustOptions::new(),
+                )
+                .await?
+            }
+        };
         trace!("remote forwarder created: {remote_forwarder_information:?}");
         Ok(())
     }
+
+    fn forwarder_route(&self, alias: &str) -> Route {
+        match self {
+            RemoteForwarderCreator::Orchestrator { project_route } => {
+                route![project_route.clone(), format!("consumer__{alias}")]
+            }
+            RemoteForwarderCreator::Relay { relay_route } => {
+                route![relay_route.clone(), alias]
+            }
+        }
+    }
 }
 
-///Unique identifier for a specific secure_channel.
-/// Used in order to distinguish between secure channels created between
-/// the same identities.
+/// Request sent by the consumer to the producer to retrieve the data key
+/// of a topic
 #[derive(Debug, Clone, Serialize, Deserialize, Message)]
-struct SecureChannelIdentifierMessage {
-    secure_channel_identifier: UniqueSecureChannelId,
+struct DataKeyRequest {
+    topic_name: String,
+    data_key_id: DataKeyId,
+}
+
+/// The data key secret, missing when the producer doesn't know the key,
+/// when the key belongs to another topic or when the consumer is not
+/// authorized for the topic
+#[derive(Clone, Serialize, Deserialize, Message)]
+struct DataKeyResponse {
+    secret: Option<SecretKey>,
+}
+
+/// How a consumer authenticates the producer serving a data key, since
+/// whoever serves data keys is able to forge records
+#[derive(Clone)]
+pub(crate) enum ProducerTrust {
+    /// The producer is one of these identities
+    Identifiers(Vec<IdentityIdentifier>),
+    /// The producer presents a credential issued by one of the authorities
+    /// for the same project as the consumer
+    Credential {
+        authorities: Vec<PublicIdentity>,
+        attributes_storage: Arc<dyn IdentityAttributeStorage>,
+        project_id: String,
+    },
+}
+
+/// Authorizes the consumers retrieving the data keys of a topic
+#[async_trait]
+pub(crate) trait TopicAccessControl: Send + Sync + 'static {
+    async fn is_authorized(&self, topic_name: &str, message: &RelayMessage) -> Result<bool>;
+}
+
+/// The same access control for every topic
+pub(crate) struct AnyTopicAccessControl(pub(crate) Arc<dyn IncomingAccessControl>);
+
+#[async_trait]
+impl TopicAccessControl for AnyTopicAccessControl {
+    async fn is_authorized(&self, _topic_name: &str, message: &RelayMessage) -> Result<bool> {
+        self.0.is_authorized(message).await
+    }
+}
+
+/// Evaluates the policy of a resource and action with the topic of the
+/// requested data key as the `resource.topic` attribute
+pub(crate) struct TopicPolicyAccessControl {
+    policies: Arc<dyn PolicyStorage>,
+    attributes: Arc<dyn IdentityAttributeStorage>,
+    resource: Resource,
+    action: Action,
+    environment: Env,
+}
+
+impl TopicPolicyAccessControl {
+    pub(crate) fn new(
+        policies: Arc<dyn PolicyStorage>,
+        attributes: Arc<dyn IdentityAttributeStorage>,
+        resource: Resource,
+        action: Action,
+        environment: Env,
+    ) -> Self {
+        Self {
+            policies,
+            attributes,
+            resource,
+            action,
+            environment,
+        }
+    }
+}
+
+#[async_trait]
+impl TopicAccessControl for TopicPolicyAccessControl {
+    async fn is_authorized(&self, topic_name: &str, message: &RelayMessage) -> Result<bool> {
+        let mut environment = self.environment.clone();
+        environment.put("resource.topic", ockam_abac::expr::str(topic_name));
+        PolicyAccessControl::new(
+            self.policies.clone(),
+            self.attributes.clone(),
+            self.resource.clone(),
+            self.action.clone(),
+            environment,
+        )
+        .is_authorized(message)
+        .await
+    }
+}
+
+impl Default for ProducerTrust {
+    /// No producer is trusted
+    fn default() -> Self {
+        ProducerTrust::Identifiers(Vec::new())
+    }
 }
 
 pub(crate) struct KafkaSecureChannelControllerImpl<F: ForwarderCreator> {
     inner: Arc<Mutex<InnerSecureChannelControllerImpl<F>>>,
+    producer_trust: ProducerTrust,
 }
 
 //had to manually implement since #[derive(Clone)] doesn't work well in this situation
@@ -110,68 +238,113 @@ impl<F: ForwarderCreator> Clone for KafkaSecureChannelControllerImpl<F> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            producer_trust: self.producer_trust.clone(),
         }
     }
 }
 
-/// An identifier of the secure channel **instance**
-pub(crate) type UniqueSecureChannelId = u64;
-type TopicPartition = (String, i32);
+/// An identifier of the data key, unique for each producer
+pub(crate) type DataKeyId = u64;
+
+/// A data key created by the producer, only served for its topic
+struct DataKey {
+    topic_name: String,
+    key_id: KeyId,
+}
+
+/// The data key currently used by the producer for a topic
+struct TopicDataKey {
+    data_key_id: DataKeyId,
+    key_id: KeyId,
+    nonce: u64,
+}
+
 struct InnerSecureChannelControllerImpl<F: ForwarderCreator> {
-    //we are using encryptor api address as unique _local_ identifier
-    //of the secure channel
-    id_encryptor_map: HashMap<UniqueSecureChannelId, Address>,
-    topic_encryptor_map: HashMap<TopicPartition, (UniqueSecureChannelId, Address)>,
     identity: Arc<Identity>,
-    project_route: Route,
-    topic_forwarder_set: HashSet<TopicPartition>,
     forwarder_creator: F,
+    //used as alias of the producer forwarder
+    producer_id: String,
+    //producer side: data keys used to encrypt, and every key ever created
+    //to answer consumers
+    topic_key_map: HashMap<String, TopicDataKey>,
+    data_key_map: HashMap<DataKeyId, DataKey>,
+    //consumer side: secure channels toward producers and retrieved keys
+    producer_encryptor_map: HashMap<String, Address>,
+    retrieved_key_map: HashMap<(String, DataKeyId), KeyId>,
 }
 
 impl KafkaSecureChannelControllerImpl<RemoteForwarderCreator> {
+    /// Producer forwarders are created on the orchestrator project
     pub(crate) fn new(
         identity: Arc<Identity>,
         project_route: Route,
     ) -> KafkaSecureChannelControllerImpl<RemoteForwarderCreator> {
         Self::new_extended(
             identity,
-            project_route.clone(),
-            RemoteForwarderCreator {
-                hub_route: route![project_route, ORCHESTRATOR_KAFKA_CONSUMERS],
-            },
+            RemoteForwarderCreator::Orchestrator { project_route },
         )
     }
+
+    /// Producer forwarders are created on a self-hosted relay node
+    pub(crate) fn new_with_relay(
+        identity: Arc<Identity>,
+        relay_route: Route,
+    ) -> KafkaSecureChannelControllerImpl<RemoteForwarderCreator> {
+        Self::new_extended(identity, RemoteForwarderCreator::Relay { relay_route })
+    }
 }
 
 impl<F: ForwarderCreator> KafkaSecureChannelControllerImpl<F> {
     /// to manually specify `ForwarderCreator`, for testing purposes
     pub(crate) fn new_extended(
         identity: Arc<Identity>,
-        project_route: Route,
         forwarder_creator: F,
     ) -> KafkaSecureChannelControllerImpl<F> {
         Self {
             inner: Arc::new(Mutex::new(InnerSecureChannelControllerImpl {
-                id_encryptor_map: Default::default(),
-                topic_encryptor_map: Default::default(),
-                topic_forwarder_set: Default::default(),
                 identity,
                 forwarder_creator,
-                project_route,
+                producer_id: format!("kafka_producer_{:016x}", rand::random::<u64>()),
+                topic_key_map: Default::default(),
+                data_key_map: Default::default(),
+                producer_encryptor_map: Default::default(),
+                retrieved_key_map: Default::default(),
             })),
+            producer_trust: Default::default(),
         }
     }
 
-    pub(crate) async fn create_consumer_listener(&self, context: &Context) -> Result<()> {
-        context
-            .start_worker(
-                Address::from_string(KAFKA_SECURE_CHANNEL_CONTROLLER_ADDRESS),
-                SecureChannelControllerListener::<F> {
-                    controller: self.clone(),
-                },
-                AllowAll,
-                AllowAll,
-            )
+    /// Set how the consumer authenticates producers, no producer is trusted by default
+    pub(crate) fn with_producer_trust(mut self, producer_trust: ProducerTrust) -> Self {
+        self.producer_trust = producer_trust;
+        self
+    }
+
+    /// Starts the worker distributing data keys to consumers and creates
+    /// the forwarder consumers use to reach it.
+    /// Only consumers authorized by the access control for the topic of a
+    /// data key receive it.
+    pub(crate) async fn create_producer_listener(
+        &self,
+        context: &Context,
+        access_control: Arc<dyn TopicAccessControl>,
+    ) -> Result<()> {
+        WorkerBuilder::with_access_control(
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+            Address::from_string(KAFKA_DATA_KEY_DISTRIBUTOR_ADDRESS),
+            DataKeyDistributor::<F> {
+                controller: self.clone(),
+                access_control,
+            },
+        )
+        .start(context)
+        .await?;
+
+        let inner = self.inner.lock().await;
+        inner
+            .forwarder_creator
+            .create_forwarder(context, inner.producer_id.clone())
             .await
     }
 
@@ -179,23 +352,35 @@ impl<F: ForwarderCreator> KafkaSecureChannelControllerImpl<F> {
         Arc::new(self)
     }
 
-    //add a mapping from remote producer
-    async fn add_mapping(&self, id: UniqueSecureChannelId, encryptor_address: Address) {
-        self.inner
-            .lock()
-            .await
-            .id_encryptor_map
-            .insert(id, encryptor_address);
+    /// Exports the data key, when known and created for the topic
+    async fn export_data_key(
+        &self,
+        topic_name: &str,
+        data_key_id: DataKeyId,
+    ) -> Result<Option<SecretKey>> {
+        let inner = self.inner.lock().await;
+        match inner.data_key_map.get(&data_key_id) {
+            Some(data_key) if data_key.topic_name == topic_name => {
+                let secret = inner
+                    .identity
+                    .vault()
+                    .secret_export(&data_key.key_id)
+                    .await?;
+                Ok(Some(secret.try_as_key()?.clone()))
+            }
+            _ => Ok(None),
+        }
     }
 }
 
-struct SecureChannelControllerListener<F: ForwarderCreator> {
+struct DataKeyDistributor<F: ForwarderCreator> {
     controller: KafkaSecureChannelControllerImpl<F>,
+    access_control: Arc<dyn TopicAccessControl>,
 }
 
 #[ockam::worker]
-impl<F: ForwarderCreator> Worker for SecureChannelControllerListener<F> {
-    type Message = SecureChannelIdentifierMessage;
+impl<F: ForwarderCreator> Worker for DataKeyDistributor<F> {
+    type Message = DataKeyRequest;
     type Context = Context;
 
     async fn handle_message(
@@ -203,123 +388,291 @@ impl<F: ForwarderCreator> Worker for SecureChannelControllerListener<F> {
         context: &mut Self::Context,
         message: Routed<Self::Message>,
     ) -> Result<()> {
-        //todo: is there a better way to extract it from the context?
-        let encryptor_address = message.return_route().next().cloned()?;
-
-        self.controller
-            .add_mapping(message.secure_channel_identifier, encryptor_address.clone())
-            .await;
+        let relay_message = RelayMessage::new(
+            message.sender()?,
+            message.msg_addr(),
+            message.local_message().clone(),
+        );
+        let topic_name = &message.topic_name;
+        let secret = if self
+            .access_control
+            .is_authorized(topic_name, &relay_message)
+            .await?
+        {
+            let secret = self
+                .controller
+                .export_data_key(topic_name, message.data_key_id)
+                .await?;
+            if secret.is_none() {
+                warn!(
+                    "unknown data key requested for topic {topic_name}: {}",
+                    message.data_key_id
+                );
+            }
+            secret
+        } else {
+            warn!("unauthorized data key request for topic {topic_name}");
+            None
+        };
 
-        context.send(message.return_route(), ()).await
+        context
+            .send(message.return_route(), DataKeyResponse { secret })
+            .await
     }
 }
 
 impl<F: ForwarderCreator> KafkaSecureChannelControllerImpl<F> {
-    ///returns encryptor api address
-    async fn get_or_create_secure_channel_for(
+    /// Returns the data key of the topic along the nonce to use, a new data key
+    /// is created for a new topic or when every nonce had been used
+    async fn next_data_key_for(
         &self,
-        context: &mut Context,
         topic_name: &str,
-        partition: i32,
-    ) -> Result<(UniqueSecureChannelId, SecureChannelRegistryEntry)> {
-        //here we should have the orchestrator address and expect forwarders to be
-        // present in the orchestrator with the format "consumer_{partition}_{topic_name}"
+    ) -> Result<(DataKeyId, KeyId, [u8; NONCE_LENGTH])> {
+        let mut inner = self.inner.lock().await;
 
-        let topic_partition_key = (topic_name.to_string(), partition);
-        //consumer__ prefix is added by the orchestrator
-        let topic_partition_address = format!("consumer__{topic_name}_{partition}");
+        let exhausted = match inner.topic_key_map.get(topic_name) {
+            Some(data_key) => data_key.nonce == u64::MAX,
+            None => true,
+        };
 
-        let mut inner = self.inner.lock().await;
+        if exhausted {
+            let key_id = inner
+                .identity
+                .vault()
+                .secret_generate(SecretAttributes::new(
+                    SecretType::Aes,
+                    SecretPersistence::Ephemeral,
+                    AES256_SECRET_LENGTH_U32,
+                ))
+                .await?;
 
-        let (random_unique_id, encryptor_address) = {
-            if let Some(encryptor_address) = inner.topic_encryptor_map.get(&topic_partition_key) {
-                encryptor_address.clone()
-            } else {
-                trace!("creating new secure channel to {topic_partition_address}");
-
-                // This route should not use Sessions because we are using tunnel over existing
-                // secure channel
-                let trust_options =
-                    SecureChannelTrustOptions::new().with_trust_policy(TrustEveryonePolicy);
-                let encryptor_address = inner
-                    .identity
-                    .create_secure_channel(
-                        route![
-                            inner.project_route.clone(),
-                            topic_partition_address.clone(),
-                            KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS
-                        ],
-                        trust_options,
-                    )
-                    .await?;
+            let data_key_id: DataKeyId = rand::random();
+            trace!("created data key {data_key_id} for topic {topic_name}");
+            inner.data_key_map.insert(
+                data_key_id,
+                DataKey {
+                    topic_name: topic_name.to_string(),
+                    key_id: key_id.clone(),
+                },
+            );
+            inner.topic_key_map.insert(
+                topic_name.to_string(),
+                TopicDataKey {
+                    data_key_id,
+                    key_id,
+                    nonce: 0,
+                },
+            );
+        }
 
-                trace!("created secure channel to {topic_partition_address}");
+        let data_key = inner
+            .topic_key_map
+            .get_mut(topic_name)
+            .expect("data key should be present");
+        let nonce = data_key.nonce;
+        data_key.nonce += 1;
 
-                let random_unique_id: UniqueSecureChannelId = rand::random();
-                inner.topic_encryptor_map.insert(
-                    topic_partition_key,
-                    (random_unique_id, encryptor_address.clone()),
-                );
+        let mut nonce_bytes = [0; NONCE_LENGTH];
+        nonce_bytes[NONCE_LENGTH - 8..].copy_from_slice(&nonce.to_be_bytes());
+        Ok((data_key.data_key_id, data_key.key_id.clone(), nonce_bytes))
+    }
 
-                let message = SecureChannelIdentifierMessage {
-                    secure_channel_identifier: random_unique_id,
-                };
-
-                //communicate to the other end the random id associated with this
-                //secure channel, and wait to an empty reply to avoid race conditions
-                //on the order of encryption/decryption of messages
-                context
-                    .send_and_receive(
-                        route![
-                            encryptor_address.clone(),
-                            KAFKA_SECURE_CHANNEL_CONTROLLER_ADDRESS
-                        ],
-                        message,
-                    )
-                    .await?;
+    /// Returns the data key of the producer, retrieving it from the producer
+    /// the first time.
+    /// The lock is not held while waiting for the producer, so that slow
+    /// retrievals don't delay the other partitions.
+    async fn get_or_retrieve_data_key(
+        &self,
+        context: &mut Context,
+        topic_name: &str,
+        producer_id: &str,
+        data_key_id: DataKeyId,
+    ) -> Result<KeyId> {
+        let key = (producer_id.to_string(), data_key_id);
+        let (identity, encryptor_address, producer_route) = {
+            let inner = self.inner.lock().await;
+            if let Some(key_id) = inner.retrieved_key_map.get(&key) {
+                return Ok(key_id.clone());
+            }
+            (
+                inner.identity.clone(),
+                inner.producer_encryptor_map.get(producer_id).cloned(),
+                inner.forwarder_creator.forwarder_route(producer_id),
+            )
+        };
 
-                trace!("assigned id {random_unique_id} to {topic_partition_address}");
-                (random_unique_id, encryptor_address)
+        let encryptor_address = match encryptor_address {
+            Some(encryptor_address) => encryptor_address,
+            None => {
+                let encryptor_address = self
+                    .create_producer_channel(&identity, producer_route)
+                    .await?;
+                self.inner
+                    .lock()
+                    .await
+                    .producer_encryptor_map
+                    .insert(producer_id.to_string(), encryptor_address.clone());
+                encryptor_address
             }
         };
 
-        inner
-            .identity
-            .secure_channel_registry()
-            .get_channel_by_encryptor_address(&encryptor_address)
-            .map(|entry| (random_unique_id, entry))
-            .ok_or_else(|| Error::new(Origin::Channel, Kind::Unknown, "secure channel down"))
+        let response: DataKeyResponse = context
+            .send_and_receive(
+                route![encryptor_address, KAFKA_DATA_KEY_DISTRIBUTOR_ADDRESS],
+                DataKeyRequest {
+                    topic_name: topic_name.to_string(),
+                    data_key_id,
+                },
+            )
+            .await?;
+
+        let secret = response.secret.ok_or_else(|| {
+            Error::new(
+                Origin::Channel,
+                Kind::NotFound,
+                format!("data key {data_key_id} of topic {topic_name} not served by {producer_id}"),
+            )
+        })?;
+
+        let key_id = identity
+            .vault()
+            .secret_import(
+                Secret::Key(secret),
+                SecretAttributes::new(
+                    SecretType::Aes,
+                    SecretPersistence::Ephemeral,
+                    AES256_SECRET_LENGTH_U32,
+                ),
+            )
+            .await?;
+
+        trace!("retrieved data key {data_key_id} from {producer_id}");
+        self.inner
+            .lock()
+            .await
+            .retrieved_key_map
+            .insert(key, key_id.clone());
+        Ok(key_id)
     }
 
-    ///return decryptor api address
-    async fn get_secure_channel_for(
+    /// Creates a secure channel to the producer and authenticates it
+    async fn create_producer_channel(
         &self,
-        secure_channel_id: UniqueSecureChannelId,
-    ) -> Result<SecureChannelRegistryEntry> {
-        let inner = self.inner.lock().await;
-        if let Some(encryptor_address) = inner.id_encryptor_map.get(&secure_channel_id) {
-            inner
-                .identity
-                .secure_channel_registry()
-                .get_channel_list()
-                .iter()
-                .find(|entry| {
-                    entry.encryptor_messaging_address() == encryptor_address
-                        && !entry.is_initiator()
-                })
-                .cloned()
-                .ok_or_else(|| {
-                    Error::new(
-                        Origin::Channel,
-                        Kind::Unknown,
-                        "secure channel no longer exists",
-                    )
+        identity: &Identity,
+        producer_route: Route,
+    ) -> Result<Address> {
+        trace!("creating new secure channel to {producer_route}");
+
+        // This route should not use Sessions because we are using tunnel over existing
+        // secure channel
+        let trust_options = match &self.producer_trust {
+            ProducerTrust::Identifiers(identifiers) => SecureChannelTrustOptions::new()
+                .with_trust_policy(TrustMultiIdentifiersPolicy::new(identifiers.clone())),
+            //the producer is authenticated with its credential once the channel exists
+            ProducerTrust::Credential { .. } => {
+                SecureChannelTrustOptions::new().with_trust_policy(TrustEveryonePolicy)
+            }
+        };
+        let encryptor_address = identity
+            .create_secure_channel(
+                route![
+                    producer_route.clone(),
+                    KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS
+                ],
+                trust_options,
+            )
+            .await?;
+
+        //the producer authorizes consumers using the attributes of
+        //their credential
+        let credentials_route = route![
+            encryptor_address.clone(),
+            DefaultAddress::CREDENTIALS_SERVICE
+        ];
+        let presented = match &self.producer_trust {
+            ProducerTrust::Identifiers(_) => {
+                if identity.credential().await.is_some() {
+                    identity
+                        .present_credential(credentials_route, None, None)
+                        .await
+                } else {
+                    Ok(())
+                }
+            }
+            ProducerTrust::Credential {
+                authorities,
+                attributes_storage,
+                project_id,
+            } => {
+                Self::authenticate_producer(
+                    identity,
+                    &encryptor_address,
+                    credentials_route,
+                    authorities,
+                    attributes_storage.clone(),
+                    project_id,
+                )
+                .await
+            }
+        };
+        if let Err(err) = presented {
+            warn!("cannot authenticate the producer at {producer_route}: {err}");
+            identity.stop_secure_channel(&encryptor_address).await?;
+            return Err(err);
+        }
+
+        trace!("created secure channel to {producer_route}");
+        Ok(encryptor_address)
+    }
+
+    /// Exchanges credentials with the producer and checks that it is a
+    /// member of the project
+    async fn authenticate_producer(
+        identity: &Identity,
+        encryptor_address: &Address,
+        credentials_route: Route,
+        authorities: &[PublicIdentity],
+        attributes_storage: Arc<dyn IdentityAttributeStorage>,
+        project_id: &str,
+    ) -> Result<()> {
+        identity
+            .present_credential_mutual(
+                credentials_route,
+                authorities,
+                attributes_storage.clone(),
+                None,
+                None,
+            )
+            .await?;
+
+        let producer = identity
+            .secure_channel_registry()
+            .get_channel_by_encryptor_address(encryptor_address)
+            .map(|channel| channel.their_id().clone())
+            .ok_or_else(|| {
+                Error::new(Origin::Channel, Kind::NotFound, "unknown producer channel")
+            })?;
+        let own_id = identity.identifier().to_string();
+        let member = attributes_storage
+            .get_attributes(&producer)
+            .await?
+            // A credential restricted to an audience must be valid for the project or the node
+            .filter(|entry| {
+                entry.audience().map_or(true, |audience| {
+                    audience.iter().any(|a| a == project_id || a == &own_id)
                 })
+            })
+            .and_then(|entry| entry.attrs().get("project_id").cloned())
+            .map_or(false, |producer_project_id| {
+                producer_project_id == project_id.as_bytes()
+            });
+        if member {
+            Ok(())
         } else {
             Err(Error::new(
                 Origin::Channel,
-                Kind::Unknown,
-                "missing secure channel",
+                Kind::NotFound,
+                format!("the producer {producer} is not a member of the project {project_id}"),
             ))
         }
     }
@@ -329,85 +682,67 @@ impl<F: ForwarderCreator> KafkaSecureChannelControllerImpl<F> {
 impl<F: ForwarderCreator> KafkaSecureChannelController for KafkaSecureChannelControllerImpl<F> {
     async fn encrypt_content_for(
         &self,
-        context: &mut Context,
+        _context: &mut Context,
         topic_name: &str,
-        partition_id: i32,
         content: Vec<u8>,
     ) -> Result<KafkaEncryptedContent> {
-        let (unique_id, secure_channel_entry) = self
-            .get_or_create_secure_channel_for(context, topic_name, partition_id)
-            .await?;
-
-        trace!("encrypting content with {unique_id}");
-        let encryption_response: EncryptionResponse = context
-            .send_and_receive(
-                route![secure_channel_entry.encryptor_api_address().clone()],
-                EncryptionRequest(content),
-            )
-            .await?;
+        let (data_key_id, key_id, nonce) = self.next_data_key_for(topic_name).await?;
 
-        let encrypted_content = match encryption_response {
-            EncryptionResponse::Ok(p) => p,
-            EncryptionResponse::Err(cause) => {
+        trace!("encrypting content with {data_key_id}");
+        let vault = self.inner.lock().await.identity.vault();
+        let encrypted_content = vault
+            .aead_aes_gcm_encrypt(&key_id, &content, &nonce, &data_key_id.to_be_bytes())
+            .await
+            .map_err(|cause| {
                 warn!("cannot encrypt kafka message");
-                return Err(cause);
-            }
-        };
+                cause
+            })?;
 
-        trace!("encrypted content with {unique_id}");
+        let mut content = Vec::with_capacity(NONCE_LENGTH + encrypted_content.len());
+        content.extend_from_slice(&nonce);
+        content.extend_from_slice(&encrypted_content);
+
+        trace!("encrypted content with {data_key_id}");
         Ok(KafkaEncryptedContent {
-            content: encrypted_content,
-            secure_channel_id: unique_id,
+            content,
+            producer_id: self.inner.lock().await.producer_id.clone(),
+            data_key_id,
         })
     }
 
     async fn decrypt_content_for(
         &self,
         context: &mut Context,
-        secure_channel_id: UniqueSecureChannelId,
+        topic_name: &str,
+        producer_id: &str,
+        data_key_id: DataKeyId,
         encrypted_content: Vec<u8>,
     ) -> Result<Vec<u8>> {
-        let secure_channel_entry = self.get_secure_channel_for(secure_channel_id).await?;
+        if encrypted_content.len() < NONCE_LENGTH {
+            return Err(Error::new(
+                Origin::Channel,
+                Kind::Invalid,
+                "encrypted content is too short",
+            ));
+        }
 
-        let decrypt_response = context
-            .send_and_receive(
-                route![secure_channel_entry.decryptor_api_address().clone()],
-                DecryptionRequest(encrypted_content),
-            )
+        let key_id = self
+            .get_or_retrieve_data_key(context, topic_name, producer_id, data_key_id)
             .await?;
 
-        let decrypted_content = match decrypt_response {
-            DecryptionResponse::Ok(p) => p,
-            DecryptionResponse::Err(cause) => {
+        let (nonce, encrypted_content) = encrypted_content.split_at(NONCE_LENGTH);
+        let vault = self.inner.lock().await.identity.vault();
+        vault
+            .aead_aes_gcm_decrypt(
+                &key_id,
+                encrypted_content,
+                nonce,
+                &data_key_id.to_be_bytes(),
+            )
+            .await
+            .map_err(|cause| {
                 error!("cannot decrypt kafka message: closing connection");
-                return Err(cause);
-            }
-        };
-
-        Ok(decrypted_content)
-    }
-
-    async fn start_forwarders_for(
-        &self,
-        context: &mut Context,
-        topic_name: &str,
-        partitions: Vec<i32>,
-    ) -> Result<()> {
-        let mut inner = self.inner.lock().await;
-
-        for partition in partitions {
-            let topic_key: TopicPartition = (topic_name.to_string(), partition);
-            if inner.topic_forwarder_set.contains(&topic_key) {
-                continue;
-            }
-            let alias = format!("{topic_name}_{partition}");
-            inner
-                .forwarder_creator
-                .create_forwarder(context, alias)
-                .await?;
-            inner.topic_forwarder_set.insert(topic_key);
-        }
-
-        Ok(())
+                cause
+            })
     }
 }
@@ -5,18 +5,21 @@ pub mod cli_state;
 pub mod cloud;
 pub mod config;
 pub mod echoer;
+pub mod endpoints;
 pub mod error;
 pub mod hop;
 pub mod identity;
+pub mod jwt_vc;
 pub mod kafka;
 pub mod nodes;
 pub mod okta;
 pub mod port_range;
+pub mod schema;
+pub mod store_and_forward;
 pub mod uppercase;
 pub mod vault;
 pub mod verifier;
 
-mod schema;
 mod session;
 mod util;
 pub use util::*;
@@ -38,6 +41,7 @@ impl DefaultAddress {
     pub const ECHO_SERVICE: &'static str = "echo";
     pub const HOP_SERVICE: &'static str = "hop";
     pub const CREDENTIALS_SERVICE: &'static str = "credentials";
+    pub const IDENTITY_UPDATE_SERVICE: &'static str = "identity_update";
     pub const SECURE_CHANNEL_LISTENER: &'static str = "api";
     pub const DIRECT_AUTHENTICATOR: &'static str = "direct_authenticator";
     pub const CREDENTIAL_ISSUER: &'static str = "credential_issuer";
@@ -47,6 +51,8 @@ impl DefaultAddress {
     pub const OKTA_IDENTITY_PROVIDER: &'static str = "okta";
     pub const KAFKA_CONSUMER: &'static str = "kafka_consumer";
     pub const KAFKA_PRODUCER: &'static str = "kafka_producer";
+    pub const KAFKA_OUTLET: &'static str = "kafka_outlet";
+    pub const SUPERVISOR: &'static str = "supervisor";
 }
 
 pub mod actions {
@@ -58,6 +64,7 @@ pub mod resources {
     use ockam_abac::Resource;
     pub const INLET: Resource = Resource::assert_inline("tcp-inlet");
     pub const OUTLET: Resource = Resource::assert_inline("tcp-outlet");
+    pub const KAFKA_PRODUCER: Resource = Resource::assert_inline("kafka-producer");
 }
 
 use core::fmt;
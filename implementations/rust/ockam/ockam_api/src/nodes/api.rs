@@ -0,0 +1,199 @@
+//! The [`Endpoint`](ockam_core::api::rpc::Endpoint)s of the node manager.
+//!
+//! The node manager routes these endpoints with its own route table, see
+//! [`NodeManagerWorker`](super::NodeManagerWorker).  The endpoints relayed
+//! to the Orchestrator controller take a [`CloudRequestWrapper`] carrying
+//! the route to the controller.
+
+use crate::cloud::addon::{Addon, ConfluentConfig};
+use crate::cloud::enroll::auth0::AuthenticateAuth0Token;
+use crate::cloud::enroll::enrollment_token::EnrollmentToken;
+use crate::cloud::project::{self, InfluxDBTokenLeaseManagerConfig, OktaConfig};
+use crate::cloud::space::{self, Space};
+use crate::cloud::subscription::{self, Subscription};
+use crate::cloud::{BareCloudRequestWrapper, CloudRequestWrapper};
+use crate::nodes::models::base::NodeStatus;
+use crate::nodes::models::credentials::{GetCredentialRequest, PresentCredentialRequest};
+use crate::nodes::models::forwarder::{self, ForwarderInfo};
+use crate::nodes::models::identity::{PullIdentityUpdateRequest, PushIdentityUpdateRequest};
+use crate::nodes::models::policy::{Policy, PolicyList};
+use crate::nodes::models::portal::{self, InletList, InletStatus, OutletList, OutletStatus};
+use crate::nodes::models::secure_channel::*;
+use crate::nodes::models::services::*;
+use crate::nodes::models::transport::{
+    CreateTransport, DeleteTransport, TransportList, TransportStatus,
+};
+use crate::nodes::models::workers::WorkerList;
+use crate::nodes::service::message;
+use ockam_identity::credential::{Attributes, Credential};
+
+ockam_core::endpoints! {
+    // ==*== Basic node information ==*==
+    /// Get the status of the node.
+    pub struct GetNodeStatus<'a>: Get "/node" -> NodeStatus<'a>;
+    /// List the workers of the node.
+    pub struct ListWorkers<'a>: Get "/node/workers" -> WorkerList<'a>;
+
+    // ==*== Tcp connections and listeners ==*==
+    /// List the TCP connections.
+    pub struct ListTcpConnections<'a>: Get "/node/tcp/connection" -> TransportList<'a>;
+    /// Create a TCP connection.
+    pub struct CreateTcpConnection<'a>: Post "/node/tcp/connection" (CreateTransport<'a>) -> TransportStatus<'a>;
+    /// Delete a TCP connection.
+    pub struct DeleteTcpConnection<'a>: Delete "/node/tcp/connection" (DeleteTransport<'a>) -> ();
+    /// List the TCP listeners.
+    pub struct ListTcpListeners<'a>: Get "/node/tcp/listener" -> TransportList<'a>;
+    /// Create a TCP listener.
+    pub struct CreateTcpListener<'a>: Post "/node/tcp/listener" (CreateTransport<'a>) -> TransportStatus<'a>;
+    /// Delete a TCP listener.
+    pub struct DeleteTcpListener<'a>: Delete "/node/tcp/listener" (DeleteTransport<'a>) -> ();
+
+    // ==*== Credentials ==*==
+    /// Get a credential from the authority of the node.
+    pub struct GetCredential<'a>: Post "/node/credentials/actions/get" (GetCredentialRequest) -> Credential;
+    /// Present the credential of the node to a remote node.
+    pub struct PresentCredential<'a>: Post "/node/credentials/actions/present" (PresentCredentialRequest<'a>) -> ();
+
+    // ==*== Identity updates ==*==
+    /// Push the identity history of the node to a remote node.
+    pub struct PushIdentityUpdate<'a>: Post "/node/identities/actions/push" (PushIdentityUpdateRequest<'a>) -> ();
+    /// Pull the identity history of an identity from a remote node.
+    pub struct PullIdentityUpdate<'a>: Post "/node/identities/actions/pull" (PullIdentityUpdateRequest<'a>) -> ();
+
+    // ==*== Secure channels ==*==
+    /// List the addresses of the secure channels.
+    pub struct ListSecureChannels<'a>: Get "/node/secure_channel" -> Vec<String>;
+    /// Create a secure channel.
+    pub struct CreateSecureChannel<'a>: Post "/node/secure_channel" (CreateSecureChannelRequest<'a>) -> CreateSecureChannelResponse<'a>;
+    /// Delete a secure channel.
+    pub struct DeleteSecureChannel<'a>: Delete "/node/secure_channel" (DeleteSecureChannelRequest<'a>) -> DeleteSecureChannelResponse<'a>;
+    /// Show a secure channel.
+    pub struct ShowSecureChannel<'a>: Get "/node/show_secure_channel" (ShowSecureChannelRequest<'a>) -> ShowSecureChannelResponse<'a>;
+    /// List the addresses of the secure channel listeners.
+    pub struct ListSecureChannelListeners<'a>: Get "/node/secure_channel_listener" -> Vec<String>;
+    /// Create a secure channel listener.
+    pub struct CreateSecureChannelListener<'a>: Post "/node/secure_channel_listener" (CreateSecureChannelListenerRequest<'a>) -> ();
+    /// Delete a secure channel listener.
+    pub struct DeleteSecureChannelListener<'a>: Delete "/node/secure_channel_listener" (DeleteSecureChannelListenerRequest<'a>) -> DeleteSecureChannelListenerResponse<'a>;
+
+    // ==*== Services ==*==
+    /// List the services of the node.
+    pub struct ListServices<'a>: Get "/node/services" -> ServiceList<'a>;
+    /// Start a vault service.
+    pub struct StartVaultService<'a>: Post "/node/services/vault_service" (StartVaultServiceRequest<'a>) -> ();
+    /// Start an identity service.
+    pub struct StartIdentityService<'a>: Post "/node/services/identity_service" (StartIdentityServiceRequest<'a>) -> ();
+    /// Start an authenticated attributes service.
+    pub struct StartAuthenticatedService<'a>: Post "/node/services/authenticated" (StartAuthenticatedServiceRequest<'a>) -> ();
+    /// Start an uppercase service.
+    pub struct StartUppercaseService<'a>: Post "/node/services/uppercase" (StartUppercaseServiceRequest<'a>) -> ();
+    /// Start an echoer service.
+    pub struct StartEchoerService<'a>: Post "/node/services/echo" (StartEchoerServiceRequest<'a>) -> ();
+    /// Start a hop service.
+    pub struct StartHopService<'a>: Post "/node/services/hop" (StartHopServiceRequest<'a>) -> ();
+    /// Start the direct authenticator, credential issuer and enrollment
+    /// token services.
+    pub struct StartAuthenticatorService<'a>: Post "/node/services/direct_authenticator" (StartAuthenticatorRequest<'a>) -> ();
+    /// Start a credential verifier service.
+    pub struct StartVerifierService<'a>: Post "/node/services/verifier" (crate::nodes::models::services::StartVerifierService<'a>) -> ();
+    /// Start a credentials service.
+    pub struct StartCredentialsService<'a>: Post "/node/services/credentials" (crate::nodes::models::services::StartCredentialsService<'a>) -> ();
+    /// Start an Okta identity provider service.
+    pub struct StartOktaIdentityProviderService<'a>: Post "/node/services/okta" (StartOktaIdentityProviderRequest<'a>) -> ();
+    /// Start a Kafka consumer service.
+    pub struct StartKafkaConsumerService<'a>: Post "/node/services/kafka_consumer" (StartServiceRequest<'a, StartKafkaConsumerRequest<'a>>) -> ();
+    /// Start a Kafka producer service.
+    pub struct StartKafkaProducerService<'a>: Post "/node/services/kafka_producer" (StartServiceRequest<'a, StartKafkaProducerRequest<'a>>) -> ();
+    /// Start a Kafka outlet service.
+    pub struct StartKafkaOutletService<'a>: Post "/node/services/kafka_outlet" (StartServiceRequest<'a, StartKafkaOutletRequest<'a>>) -> ();
+
+    // ==*== Forwarders ==*==
+    /// Create a forwarder at a remote node.
+    pub struct CreateForwarder<'a>: Post "/node/forwarder" (forwarder::CreateForwarder<'a>) -> ForwarderInfo<'a>;
+
+    // ==*== Inlets & Outlets ==*==
+    /// List the TCP inlets.
+    pub struct ListInlets<'a>: Get "/node/inlet" -> InletList<'a>;
+    /// Create a TCP inlet.
+    pub struct CreateInlet<'a>: Post "/node/inlet" (portal::CreateInlet<'a>) -> InletStatus<'a>;
+    /// Show a TCP inlet.
+    pub struct ShowInlet<'a>: Get "/node/inlet/:alias" -> InletStatus<'a>;
+    /// Delete a TCP inlet.
+    pub struct DeleteInlet<'a>: Delete "/node/inlet/:alias" -> InletStatus<'a>;
+    /// List the TCP outlets.
+    pub struct ListOutlets<'a>: Get "/node/outlet" -> OutletList<'a>;
+    /// Create a TCP outlet.
+    pub struct CreateOutlet<'a>: Post "/node/outlet" (portal::CreateOutlet<'a>) -> OutletStatus<'a>;
+    /// Show a TCP outlet.
+    pub struct ShowOutlet<'a>: Get "/node/outlet/:alias" -> OutletStatus<'a>;
+    /// Delete a TCP outlet.
+    pub struct DeleteOutlet<'a>: Delete "/node/outlet/:alias" -> OutletStatus<'a>;
+
+    // ==*== Policies ==*==
+    /// List the policies of a resource.
+    pub struct ListPolicies<'a>: Get "/policy/:resource" -> PolicyList;
+    /// Get the policy of an action on a resource.
+    pub struct GetPolicy<'a>: Get "/policy/:resource/:action" -> Policy;
+    /// Set the policy of an action on a resource.
+    pub struct SetPolicy<'a>: Post "/policy/:resource/:action" (Policy) -> ();
+    /// Delete the policy of an action on a resource.
+    pub struct DeletePolicy<'a>: Delete "/policy/:resource/:action" -> ();
+
+    // ==*== Spaces ==*==
+    /// Create a space.
+    pub struct CreateSpace<'a>: Post "/v0/spaces" (CloudRequestWrapper<'a, space::CreateSpace<'a>>) -> Space<'a>;
+    /// List the spaces.
+    pub struct ListSpaces<'a>: Get "/v0/spaces" (BareCloudRequestWrapper<'a>) -> Vec<Space<'a>>;
+    /// Get a space.
+    pub struct GetSpace<'a>: Get "/v0/spaces/:id" (BareCloudRequestWrapper<'a>) -> Space<'a>;
+    /// Delete a space.
+    pub struct DeleteSpace<'a>: Delete "/v0/spaces/:id" (BareCloudRequestWrapper<'a>) -> ();
+
+    // ==*== Projects ==*==
+    /// Create a project in a space.
+    pub struct CreateProject<'a>: Post "/v0/projects/:space_id" (CloudRequestWrapper<'a, project::CreateProject<'a>>) -> project::Project<'a>;
+    /// List the projects.
+    pub struct ListProjects<'a>: Get "/v0/projects" (BareCloudRequestWrapper<'a>) -> Vec<project::Project<'a>>;
+    /// Get a project.
+    pub struct GetProject<'a>: Get "/v0/projects/:project_id" (BareCloudRequestWrapper<'a>) -> project::Project<'a>;
+    /// Delete a project of a space.
+    pub struct DeleteProject<'a>: Delete "/v0/projects/:space_id/:project_id" (BareCloudRequestWrapper<'a>) -> ();
+
+    // ==*== Enroll ==*==
+    /// Enroll with an Auth0 token.
+    pub struct EnrollAuth0<'a>: Post "/v0/enroll/auth0" (CloudRequestWrapper<'a, AuthenticateAuth0Token>) -> ();
+    /// Generate an enrollment token for the given attributes.
+    pub struct GenerateEnrollmentToken<'a>: Get "/v0/enroll/token" (CloudRequestWrapper<'a, Attributes>) -> EnrollmentToken;
+    /// Enroll with an enrollment token.
+    pub struct AuthenticateEnrollmentToken<'a>: Put "/v0/enroll/token" (CloudRequestWrapper<'a, EnrollmentToken>) -> ();
+
+    // ==*== Subscriptions ==*==
+    /// Activate a subscription.
+    pub struct ActivateSubscription<'a>: Post "/subscription" (CloudRequestWrapper<'a, subscription::ActivateSubscription<'a>>) -> Subscription<'a>;
+    /// List the subscriptions.
+    pub struct ListSubscriptions<'a>: Get "/subscription" (BareCloudRequestWrapper<'a>) -> Vec<Subscription<'a>>;
+    /// Get a subscription.
+    pub struct GetSubscription<'a>: Get "/subscription/:id" (BareCloudRequestWrapper<'a>) -> Subscription<'a>;
+    /// Update the contact information of a subscription.
+    pub struct UpdateSubscriptionContactInfo<'a>: Put "/subscription/:id/contact_info" (CloudRequestWrapper<'a, String>) -> Subscription<'a>;
+    /// Move a subscription to another space.
+    pub struct UpdateSubscriptionSpace<'a>: Put "/subscription/:id/space_id" (CloudRequestWrapper<'a, String>) -> Subscription<'a>;
+    /// Unsubscribe.
+    pub struct Unsubscribe<'a>: Put "/subscription/:id/unsubscribe" (BareCloudRequestWrapper<'a>) -> Subscription<'a>;
+
+    // ==*== Addons ==*==
+    /// List the addons of a project.
+    pub struct ListAddons<'a>: Get "/:project_id/addons" (BareCloudRequestWrapper<'a>) -> Vec<Addon<'a>>;
+    /// Configure the Okta addon of a project.
+    pub struct ConfigureOktaAddon<'a>: Put "/:project_id/addons/okta" (CloudRequestWrapper<'a, OktaConfig<'a>>) -> ();
+    /// Configure the InfluxDB token lease manager addon of a project.
+    pub struct ConfigureInfluxDBAddon<'a>: Put "/:project_id/addons/influxdb_token_lease_manager" (CloudRequestWrapper<'a, InfluxDBTokenLeaseManagerConfig<'a>>) -> ();
+    /// Configure the Confluent addon of a project.
+    pub struct ConfigureConfluentAddon<'a>: Put "/:project_id/addons/confluent" (CloudRequestWrapper<'a, ConfluentConfig<'a>>) -> ();
+    /// Disable an addon of a project.
+    pub struct DisableAddon<'a>: Delete "/:project_id/addons/:addon_id" (BareCloudRequestWrapper<'a>) -> ();
+
+    // ==*== Messages ==*==
+    /// Send a message to a remote worker and return its reply.
+    pub struct SendMessage<'a>: Post "/v0/message" (message::SendMessage<'a>) -> Vec<u8>;
+}
@@ -4,6 +4,8 @@ use crate::echoer::Echoer;
 use crate::lmdb::LmdbStorage;
 use crate::nodes::authority_node::authority::EnrollerCheck::{AnyMember, EnrollerOnly};
 use crate::nodes::authority_node::Configuration;
+use crate::nodes::SERVICE_POOL_SIZE;
+use crate::okta::OidcProvider;
 use crate::{actions, DefaultAddress};
 use ockam_abac::expr::{and, eq, ident, str};
 use ockam_abac::{AbacAccessControl, Env};
@@ -18,7 +20,7 @@ use ockam_identity::{
     Identity, IdentityVault, PublicIdentity, SecureChannelListenerTrustOptions,
     SecureChannelRegistry, TrustEveryonePolicy,
 };
-use ockam_node::{Context, WorkerBuilder};
+use ockam_node::{Context, WorkerBuilder, WorkerPool};
 use ockam_transport_tcp::{TcpListenerTrustOptions, TcpTransport};
 use ockam_vault::storage::FileStorage;
 use ockam_vault::Vault;
@@ -248,6 +250,30 @@ impl Authority {
         Ok(())
     }
 
+    /// Start the identity update service, so that project members can get the latest
+    /// change history of the authority and of other members, and push their own
+    pub async fn start_identity_update_service(
+        &self,
+        sessions: &Sessions,
+        secure_channel_session_id: &SessionId,
+        configuration: &Configuration,
+    ) -> Result<()> {
+        let address = DefaultAddress::IDENTITY_UPDATE_SERVICE.to_string();
+        sessions.add_consumer(
+            &Address::from_string(address.clone()),
+            secure_channel_session_id,
+            SessionPolicy::SpawnerAllowMultipleMessages,
+        );
+
+        let abac = self.create_abac_policy(configuration, address.clone(), AnyMember);
+        self.identity
+            .start_identity_update_worker(address.clone(), abac)
+            .await?;
+
+        info!("started an identity update service at '{address}'");
+        Ok(())
+    }
+
     /// Start the Okta service to retrieve attributes authenticated by Okta
     pub async fn start_okta(&self, ctx: &Context, configuration: &Configuration) -> Result<()> {
         if let Some(okta) = configuration.clone().okta {
@@ -258,15 +284,37 @@ impl Authority {
                 okta.tenant_base_url(),
                 okta.certificate(),
                 okta.attributes().as_slice(),
-            )?;
+            )?
+            .with_rules(okta.attribute_rules.clone());
 
-            ctx.start_worker(
-                okta.address,
-                okta_worker,
-                AllowAll, // FIXME: @ac
-                AllowAll,
-            )
-            .await?;
+            WorkerPool::new(okta.address, SERVICE_POOL_SIZE, move || okta_worker.clone())
+                .with_access_control(AllowAll, AllowAll) // FIXME: @ac
+                .start(ctx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Start an OpenID Connect identity provider service to retrieve attributes
+    /// from the claims of the provider tokens
+    pub async fn start_oidc(&self, ctx: &Context, configuration: &Configuration) -> Result<()> {
+        if let Some(oidc) = configuration.clone().oidc {
+            let mut provider = OidcProvider::new(&oidc.issuer, None)?.with_audience(oidc.audience);
+            if let Some(jwks_uri) = oidc.jwks_uri {
+                provider = provider.with_jwks_uri(jwks_uri);
+            }
+            let oidc_worker = crate::okta::Server::new_oidc(
+                configuration.project_identifier(),
+                self.attributes_storage()
+                    .as_identity_attribute_storage_writer(),
+                provider,
+                oidc.attribute_rules,
+            );
+
+            WorkerPool::new(oidc.address, SERVICE_POOL_SIZE, move || oidc_worker.clone())
+                .with_access_control(AllowAll, AllowAll) // FIXME: @ac
+                .start(ctx)
+                .await?;
         }
         Ok(())
     }
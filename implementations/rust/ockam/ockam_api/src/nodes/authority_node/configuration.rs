@@ -1,4 +1,5 @@
 use crate::bootstrapped_identities_store::PreTrustedIdentities;
+use crate::okta::AttributeRule;
 use crate::DefaultAddress;
 use ockam_core::compat::collections::HashMap;
 use ockam_core::compat::fmt;
@@ -47,6 +48,10 @@ pub struct Configuration {
 
     /// optional configuration for the okta service
     pub okta: Option<OktaConfiguration>,
+
+    /// optional configuration for an OpenID Connect identity provider service
+    #[serde(default)]
+    pub oidc: Option<OidcConfiguration>,
 }
 
 /// Local and private functions for the authority configuration
@@ -85,6 +90,10 @@ pub struct OktaConfiguration {
 
     /// list of attribute names managed by Okta
     pub attributes: Vec<String>,
+
+    /// rules mapping Okta claims, e.g. groups, to attributes
+    #[serde(default)]
+    pub attribute_rules: Vec<AttributeRule>,
 }
 
 impl OktaConfiguration {
@@ -104,6 +113,24 @@ impl OktaConfiguration {
     }
 }
 
+/// Configuration for a generic OpenID Connect identity provider service
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct OidcConfiguration {
+    pub address: String,
+
+    /// URL of the provider, as found in the `iss` claim of its tokens
+    pub issuer: String,
+
+    /// audience which the tokens must be issued for, e.g. the client id of the project
+    pub audience: String,
+
+    /// URL of the provider keys, taken from the discovery document when not set
+    pub jwks_uri: Option<String>,
+
+    /// rules mapping the token claims to attributes
+    pub attribute_rules: Vec<AttributeRule>,
+}
+
 /// This struct represents an identity that the Authority accepts
 /// as having all its attributes fully authenticated
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -1,3 +1,4 @@
+pub mod api;
 pub mod authority_node;
 pub mod config;
 pub(crate) mod connection;
@@ -8,5 +9,9 @@ pub mod service;
 /// A const address to bind and send messages to
 pub const NODEMANAGER_ADDR: &str = "_internal.nodemanager";
 
+/// Number of workers handling the messages of a stateless service
+/// concurrently
+pub(crate) const SERVICE_POOL_SIZE: usize = 4;
+
 /// The main node-manager service running on remote nodes
 pub use service::{IdentityOverride, NodeManager, NodeManagerWorker};
@@ -15,15 +15,18 @@ pub struct GetCredentialRequest {
     #[n(0)] tag: TypeTag<8479533>,
     #[n(1)] overwrite: bool,
     #[n(2)] pub identity_name: Option<String>,
+    /// Audience the credential is restricted to, none when empty
+    #[b(3)] pub audience: Vec<String>,
 }
 
 impl GetCredentialRequest {
-    pub fn new(overwrite: bool, identity_name: Option<String>) -> Self {
+    pub fn new(overwrite: bool, identity_name: Option<String>, audience: Vec<String>) -> Self {
         Self {
             #[cfg(feature = "tag")]
             tag: TypeTag,
             overwrite,
             identity_name,
+            audience,
         }
     }
 
@@ -40,15 +43,24 @@ pub struct PresentCredentialRequest<'a> {
     #[n(0)] tag: TypeTag<3698687>,
     #[b(1)] pub route: Cow<'a, str>,
     #[n(2)] pub oneway: bool,
+    /// Attributes disclosed from a selective-disclosure credential, all of them when not set
+    #[b(3)] pub disclosed_attributes: Option<Vec<String>>,
 }
 
 impl<'a> PresentCredentialRequest<'a> {
-    pub fn new(route: &MultiAddr, oneway: bool) -> Self {
+    pub fn new(route: &MultiAddr, oneway: bool, disclosed_attributes: Option<Vec<String>>) -> Self {
         Self {
             #[cfg(feature = "tag")]
             tag: TypeTag,
             route: route.to_string().into(),
             oneway,
+            disclosed_attributes,
         }
     }
+
+    pub fn disclosed_attributes(&self) -> Option<Vec<&str>> {
+        self.disclosed_attributes
+            .as_ref()
+            .map(|names| names.iter().map(String::as_str).collect())
+    }
 }
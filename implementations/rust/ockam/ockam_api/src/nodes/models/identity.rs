@@ -3,6 +3,7 @@ use ockam_core::compat::borrow::Cow;
 use serde::Serialize;
 
 use ockam_core::CowBytes;
+use ockam_multiaddr::MultiAddr;
 
 #[cfg(feature = "tag")]
 use ockam_core::TypeTag;
@@ -46,3 +47,46 @@ impl<'a> ShortIdentityResponse<'a> {
         }
     }
 }
+
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PushIdentityUpdateRequest<'a> {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<4510392>,
+    #[b(1)] pub route: Cow<'a, str>,
+    /// Identity to push, our own one when not set
+    #[b(2)] pub identity_id: Option<Cow<'a, str>>,
+}
+
+impl<'a> PushIdentityUpdateRequest<'a> {
+    pub fn new(route: &MultiAddr, identity_id: Option<&'a str>) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            route: route.to_string().into(),
+            identity_id: identity_id.map(|id| id.into()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PullIdentityUpdateRequest<'a> {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<9254117>,
+    #[b(1)] pub route: Cow<'a, str>,
+    #[b(2)] pub identity_id: Cow<'a, str>,
+}
+
+impl<'a> PullIdentityUpdateRequest<'a> {
+    pub fn new(route: &MultiAddr, identity_id: &'a str) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            route: route.to_string().into(),
+            identity_id: identity_id.into(),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use minicbor::{Decode, Encode};
 use ockam_core::compat::net::Ipv4Addr;
 use ockam_core::{CowBytes, CowStr};
+use ockam_identity::IdentityIdentifier;
 
 use serde::Serialize;
 
@@ -44,7 +45,11 @@ pub struct StartKafkaConsumerRequest<'a> {
     #[b(1)] bootstrap_server_ip: CowStr<'a>,
     #[n(2)] bootstrap_server_port: u16,
     #[n(3)] brokers_port_range: (u16, u16),
-    #[b(4)] project_route: CowStr<'a>,
+    #[b(4)] project_route: Option<CowStr<'a>>,
+    #[b(5)] encrypted_headers: Vec<CowStr<'a>>,
+    #[b(6)] relay_route: Option<CowStr<'a>>,
+    #[b(7)] outlet_route: Option<CowStr<'a>>,
+    #[b(8)] producer_identifiers: Vec<CowStr<'a>>,
 }
 
 impl<'a> StartKafkaConsumerRequest<'a> {
@@ -52,16 +57,43 @@ impl<'a> StartKafkaConsumerRequest<'a> {
         bootstrap_server_ip: Ipv4Addr,
         bootstrap_server_port: u16,
         brokers_port_range: impl Into<(u16, u16)>,
-        project_route: MultiAddr,
+        project_route: Option<MultiAddr>,
+        encrypted_headers: Vec<String>,
     ) -> Self {
         Self {
             bootstrap_server_ip: bootstrap_server_ip.to_string().into(),
             bootstrap_server_port,
             brokers_port_range: brokers_port_range.into(),
-            project_route: project_route.to_string().into(),
+            project_route: project_route.map(|route| route.to_string().into()),
+            encrypted_headers: encrypted_headers.into_iter().map(CowStr::from).collect(),
+            relay_route: None,
+            outlet_route: None,
+            producer_identifiers: Vec::new(),
         }
     }
 
+    /// Only retrieve data keys from these producers, required when the
+    /// node cannot verify the credentials of producers
+    pub fn with_producer_identifiers(mut self, identifiers: Vec<IdentityIdentifier>) -> Self {
+        self.producer_identifiers = identifiers
+            .into_iter()
+            .map(|identifier| identifier.to_string().into())
+            .collect();
+        self
+    }
+
+    /// Reach the kafka outlet and create forwarders on self-hosted nodes
+    /// instead of a project
+    pub fn with_self_hosted_relay(
+        mut self,
+        relay_route: MultiAddr,
+        outlet_route: MultiAddr,
+    ) -> Self {
+        self.relay_route = Some(relay_route.to_string().into());
+        self.outlet_route = Some(outlet_route.to_string().into());
+        self
+    }
+
     pub fn bootstrap_server_ip(&self) -> &CowStr<'a> {
         &self.bootstrap_server_ip
     }
@@ -71,8 +103,20 @@ impl<'a> StartKafkaConsumerRequest<'a> {
     pub fn brokers_port_range(&self) -> (u16, u16) {
         self.brokers_port_range
     }
-    pub fn project_route(&self) -> &CowStr<'a> {
-        &self.project_route
+    pub fn project_route(&self) -> Option<&CowStr<'a>> {
+        self.project_route.as_ref()
+    }
+    pub fn encrypted_headers(&self) -> &[CowStr<'a>] {
+        &self.encrypted_headers
+    }
+    pub fn producer_identifiers(&self) -> &[CowStr<'a>] {
+        &self.producer_identifiers
+    }
+    pub fn relay_route(&self) -> Option<&CowStr<'a>> {
+        self.relay_route.as_ref()
+    }
+    pub fn outlet_route(&self) -> Option<&CowStr<'a>> {
+        self.outlet_route.as_ref()
     }
 }
 
@@ -83,7 +127,12 @@ pub struct StartKafkaProducerRequest<'a> {
     #[b(1)] bootstrap_server_ip: CowStr<'a>,
     #[n(2)] bootstrap_server_port: u16,
     #[n(3)] brokers_port_range: (u16, u16),
-    #[b(4)] project_route: CowStr<'a>,
+    #[b(4)] project_route: Option<CowStr<'a>>,
+    #[b(5)] encrypted_headers: Vec<CowStr<'a>>,
+    #[b(6)] record_key_secret_file: Option<CowStr<'a>>,
+    #[b(7)] relay_route: Option<CowStr<'a>>,
+    #[b(8)] outlet_route: Option<CowStr<'a>>,
+    #[b(9)] consumer_identifiers: Vec<CowStr<'a>>,
 }
 
 impl<'a> StartKafkaProducerRequest<'a> {
@@ -91,16 +140,45 @@ impl<'a> StartKafkaProducerRequest<'a> {
         bootstrap_server_ip: Ipv4Addr,
         bootstrap_server_port: u16,
         brokers_port_range: impl Into<(u16, u16)>,
-        project_route: MultiAddr,
+        project_route: Option<MultiAddr>,
+        encrypted_headers: Vec<String>,
+        record_key_secret_file: Option<String>,
     ) -> Self {
         Self {
             bootstrap_server_ip: bootstrap_server_ip.to_string().into(),
             bootstrap_server_port,
             brokers_port_range: brokers_port_range.into(),
-            project_route: project_route.to_string().into(),
+            project_route: project_route.map(|route| route.to_string().into()),
+            encrypted_headers: encrypted_headers.into_iter().map(CowStr::from).collect(),
+            record_key_secret_file: record_key_secret_file.map(CowStr::from),
+            relay_route: None,
+            outlet_route: None,
+            consumer_identifiers: Vec::new(),
         }
     }
 
+    /// Only serve data keys to these consumers, required when the node
+    /// cannot verify the credentials of consumers and has no policy for them
+    pub fn with_consumer_identifiers(mut self, identifiers: Vec<IdentityIdentifier>) -> Self {
+        self.consumer_identifiers = identifiers
+            .into_iter()
+            .map(|identifier| identifier.to_string().into())
+            .collect();
+        self
+    }
+
+    /// Reach the kafka outlet and create forwarders on self-hosted nodes
+    /// instead of a project
+    pub fn with_self_hosted_relay(
+        mut self,
+        relay_route: MultiAddr,
+        outlet_route: MultiAddr,
+    ) -> Self {
+        self.relay_route = Some(relay_route.to_string().into());
+        self.outlet_route = Some(outlet_route.to_string().into());
+        self
+    }
+
     pub fn bootstrap_server_ip(&self) -> &CowStr<'a> {
         &self.bootstrap_server_ip
     }
@@ -110,8 +188,92 @@ impl<'a> StartKafkaProducerRequest<'a> {
     pub fn brokers_port_range(&self) -> (u16, u16) {
         self.brokers_port_range
     }
-    pub fn project_route(&self) -> &CowStr<'a> {
-        &self.project_route
+    pub fn project_route(&self) -> Option<&CowStr<'a>> {
+        self.project_route.as_ref()
+    }
+    pub fn encrypted_headers(&self) -> &[CowStr<'a>] {
+        &self.encrypted_headers
+    }
+    /// File, on the producer node, holding the secret which hashes the
+    /// record keys, record keys are only encrypted when it is set
+    pub fn record_key_secret_file(&self) -> Option<&CowStr<'a>> {
+        self.record_key_secret_file.as_ref()
+    }
+    pub fn consumer_identifiers(&self) -> &[CowStr<'a>] {
+        &self.consumer_identifiers
+    }
+    pub fn relay_route(&self) -> Option<&CowStr<'a>> {
+        self.relay_route.as_ref()
+    }
+    pub fn outlet_route(&self) -> Option<&CowStr<'a>> {
+        self.outlet_route.as_ref()
+    }
+}
+
+/// Request body when instructing a node to start a Kafka outlet service,
+/// it connects to the brokers of the cluster reachable at `bootstrap_server`
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct StartKafkaOutletRequest<'a> {
+    #[b(1)] bootstrap_server: CowStr<'a>,
+    #[n(2)] tls: bool,
+    #[b(3)] tls_ca_certificate: Option<CowStr<'a>>,
+    #[b(4)] sasl_username: Option<CowStr<'a>>,
+    #[b(5)] sasl_password_file: Option<CowStr<'a>>,
+    #[b(6)] sasl_mechanism: Option<CowStr<'a>>,
+}
+
+impl<'a> StartKafkaOutletRequest<'a> {
+    pub fn new(bootstrap_server: impl Into<CowStr<'a>>) -> Self {
+        Self {
+            bootstrap_server: bootstrap_server.into(),
+            tls: false,
+            tls_ca_certificate: None,
+            sasl_username: None,
+            sasl_password_file: None,
+            sasl_mechanism: None,
+        }
+    }
+
+    /// Use TLS toward the brokers, with an optional PEM file of trusted certificates
+    pub fn with_tls(mut self, ca_certificate: Option<impl Into<CowStr<'a>>>) -> Self {
+        self.tls = true;
+        self.tls_ca_certificate = ca_certificate.map(Into::into);
+        self
+    }
+
+    /// Authenticate to the brokers with SASL, the password is read by the
+    /// outlet node from `password_file` so that it never leaves the node
+    pub fn with_sasl(
+        mut self,
+        mechanism: impl Into<CowStr<'a>>,
+        username: impl Into<CowStr<'a>>,
+        password_file: impl Into<CowStr<'a>>,
+    ) -> Self {
+        self.sasl_mechanism = Some(mechanism.into());
+        self.sasl_username = Some(username.into());
+        self.sasl_password_file = Some(password_file.into());
+        self
+    }
+
+    pub fn bootstrap_server(&self) -> &CowStr<'a> {
+        &self.bootstrap_server
+    }
+    pub fn tls(&self) -> bool {
+        self.tls
+    }
+    pub fn tls_ca_certificate(&self) -> Option<&CowStr<'a>> {
+        self.tls_ca_certificate.as_ref()
+    }
+    pub fn sasl_username(&self) -> Option<&CowStr<'a>> {
+        self.sasl_username.as_ref()
+    }
+    pub fn sasl_password_file(&self) -> Option<&CowStr<'a>> {
+        self.sasl_password_file.as_ref()
+    }
+    pub fn sasl_mechanism(&self) -> Option<&CowStr<'a>> {
+        self.sasl_mechanism.as_ref()
     }
 }
 
@@ -328,7 +490,8 @@ pub struct StartOktaIdentityProviderRequest<'a> {
     #[b(2)] tenant_base_url: CowStr<'a>,
     #[b(3)] certificate: CowStr<'a>,
     #[b(4)] attributes: Vec<&'a str>,
-    #[b(5)] proj: CowBytes<'a>
+    #[b(5)] proj: CowBytes<'a>,
+    #[b(6)] attribute_rules: Option<Vec<&'a str>>
 }
 
 impl<'a> StartOktaIdentityProviderRequest<'a> {
@@ -347,9 +510,16 @@ impl<'a> StartOktaIdentityProviderRequest<'a> {
             certificate: certificate.into(),
             attributes,
             proj: proj.into(),
+            attribute_rules: None,
         }
     }
 
+    /// Rules mapping the Okta claims to attributes, see [`crate::okta::AttributeRule`]
+    pub fn with_attribute_rules(mut self, attribute_rules: Vec<&'a str>) -> Self {
+        self.attribute_rules = Some(attribute_rules);
+        self
+    }
+
     pub fn address(&'a self) -> &'a str {
         &self.addr
     }
@@ -365,6 +535,9 @@ impl<'a> StartOktaIdentityProviderRequest<'a> {
     pub fn attributes(&self) -> &[&str] {
         &self.attributes
     }
+    pub fn attribute_rules(&self) -> &[&str] {
+        self.attribute_rules.as_deref().unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Decode, Encode)]
@@ -98,6 +98,9 @@ pub(crate) struct HopServiceInfo {}
 #[derive(Default)]
 pub(crate) struct VerifierServiceInfo {}
 
+#[derive(Default)]
+pub(crate) struct IdentityUpdateServiceInfo {}
+
 #[derive(Default)]
 pub(crate) struct CredentialsServiceInfo {}
 
@@ -107,6 +110,7 @@ pub(crate) struct AuthenticatorServiceInfo {}
 pub(crate) enum KafkaServiceKind {
     Consumer,
     Producer,
+    Outlet,
 }
 
 pub(crate) struct KafkaServiceInfo {
@@ -180,6 +184,7 @@ pub(crate) struct Registry {
     pub(crate) hop_services: BTreeMap<Address, HopServiceInfo>,
     pub(crate) verifier_services: BTreeMap<Address, VerifierServiceInfo>,
     pub(crate) credentials_services: BTreeMap<Address, CredentialsServiceInfo>,
+    pub(crate) identity_update_services: BTreeMap<Address, IdentityUpdateServiceInfo>,
     #[cfg(feature = "direct-authenticator")]
     pub(crate) authenticator_service: BTreeMap<Address, AuthenticatorServiceInfo>,
 
@@ -1,22 +1,22 @@
 //! Node Manager (Node Man, the superhero that we deserve)
 
-use minicbor::Decoder;
-
 use ockam::identity::{Identity, IdentityIdentifier, PublicIdentity};
-use ockam::{Address, Context, ForwardingService, Result, Routed, TcpTransport, Worker};
+use ockam::{
+    Address, Context, ForwardingService, Result, Routed, Supervisor, SupervisorMessage,
+    TcpTransport, Worker,
+};
 use ockam_abac::PolicyStorage;
-use ockam_core::api::{Error, Method, Request, Response, ResponseBuilder, Status};
+use ockam_core::api::rpc::dispatch_with;
 use ockam_core::compat::{
     boxed::Box,
     string::String,
     sync::{Arc, Mutex},
 };
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::{route, AllowAll, AsyncTryClone};
+use ockam_core::{route, AllowAll, AllowOnwardAddress, AsyncTryClone, DenyAll};
 use ockam_identity::authenticated_storage::{
     AuthenticatedAttributeStorage, AuthenticatedStorage, IdentityAttributeStorage,
 };
-use ockam_identity::credential::Credential;
 use ockam_identity::IdentityVault;
 use ockam_multiaddr::proto::{Project, Secure};
 use ockam_multiaddr::{MultiAddr, Protocol};
@@ -24,7 +24,6 @@ use ockam_node::compat::asynchronous::RwLock;
 use ockam_node::tokio;
 use ockam_node::tokio::task::JoinHandle;
 use std::collections::BTreeMap;
-use std::error::Error as _;
 use std::path::PathBuf;
 
 use super::models::secure_channel::CredentialExchangeMode;
@@ -36,9 +35,7 @@ use crate::config::cli::AuthoritiesConfig;
 use crate::config::lookup::ProjectLookup;
 use crate::error::ApiError;
 use crate::nodes::connection::Connection;
-use crate::nodes::models::base::NodeStatus;
 use crate::nodes::models::transport::{TransportMode, TransportType};
-use crate::nodes::models::workers::{WorkerList, WorkerStatus};
 use crate::session::util::{starts_with_host_tcp, starts_with_secure};
 use crate::session::{Medic, Sessions};
 use crate::{
@@ -50,14 +47,14 @@ pub mod message;
 
 mod credentials;
 mod forwarder;
+mod identity_update;
 mod policy;
 mod portals;
+mod routes;
 mod secure_channel;
 mod services;
 mod transport;
 
-const TARGET: &str = "ockam_api::nodemanager::service";
-
 pub(crate) type Alias = String;
 
 /// Generate a new alias for some user created extension
@@ -119,18 +116,26 @@ pub struct NodeManager {
     pub(crate) registry: Registry,
     sessions: Arc<Mutex<Sessions>>,
     medic: JoinHandle<Result<(), ockam_core::Error>>,
+    /// Context used to hand workers over to the node supervisor
+    supervisor: Context,
     policies: Arc<dyn PolicyStorage>,
     attributes_storage: Arc<dyn IdentityAttributeStorage>,
+    /// Name of the stored credential kept up to date when the credential is refreshed
+    credential_name: Option<String>,
 }
 
 pub struct NodeManagerWorker {
     node_manager: Arc<RwLock<NodeManager>>,
+    credential_refresh: Option<JoinHandle<()>>,
+    identity_update_sync: Option<JoinHandle<()>>,
 }
 
 impl NodeManagerWorker {
     pub fn new(node_manager: NodeManager) -> Self {
         NodeManagerWorker {
             node_manager: Arc::new(RwLock::new(node_manager)),
+            credential_refresh: None,
+            identity_update_sync: None,
         }
     }
 
@@ -190,7 +195,7 @@ pub struct NodeManagerProjectsOptions<'a> {
     ac: Option<&'a AuthoritiesConfig>,
     project_id: Option<String>,
     projects: BTreeMap<String, ProjectLookup>,
-    credential: Option<Credential>,
+    credential_name: Option<String>,
 }
 
 impl<'a> NodeManagerProjectsOptions<'a> {
@@ -198,13 +203,13 @@ impl<'a> NodeManagerProjectsOptions<'a> {
         ac: Option<&'a AuthoritiesConfig>,
         project_id: Option<String>,
         projects: BTreeMap<String, ProjectLookup>,
-        credential: Option<Credential>,
+        credential_name: Option<String>,
     ) -> Self {
         Self {
             ac,
             project_id,
             projects,
-            credential,
+            credential_name,
         }
     }
 }
@@ -266,10 +271,26 @@ impl NodeManager {
 
         let vault: Arc<dyn IdentityVault> = Arc::new(node_state.config.vault().await?);
         let identity = Arc::new(node_state.config.identity(ctx).await?);
-        if let Some(cred) = projects_options.credential {
-            identity.set_credential(cred.to_owned()).await;
+        if let Some(name) = &projects_options.credential_name {
+            let cred = cli_state
+                .credentials
+                .get(name)?
+                .config()
+                .await?
+                .credential()?;
+            identity.set_credential(cred).await;
         }
 
+        // Core services and portal listeners are owned by the node supervisor
+        Supervisor::start(ctx, DefaultAddress::SUPERVISOR).await?;
+        let supervisor = ctx
+            .new_detached(
+                Address::random_tagged("NodeManager.supervisor"),
+                DenyAll,
+                AllowOnwardAddress(DefaultAddress::SUPERVISOR.into()),
+            )
+            .await?;
+
         let medic = Medic::new();
         let sessions = medic.sessions();
 
@@ -290,11 +311,13 @@ impl NodeManager {
             registry: Default::default(),
             medic: {
                 let ctx = ctx.async_try_clone().await?;
-                tokio::spawn(medic.start(ctx))
+                tokio::spawn(medic.start(ctx, DefaultAddress::SUPERVISOR.into()))
             },
+            supervisor,
             sessions,
             policies,
             attributes_storage,
+            credential_name: projects_options.credential_name,
         };
 
         if !general_options.skip_defaults {
@@ -311,6 +334,25 @@ impl NodeManager {
         Ok(s)
     }
 
+    /// Give the ownership of a worker or processor to the node supervisor
+    pub(crate) async fn supervise(&self, address: &Address, processor: bool) {
+        let msg = SupervisorMessage::Adopt {
+            address: address.clone(),
+            processor,
+        };
+        if let Err(e) = self.supervisor.send(DefaultAddress::SUPERVISOR, msg).await {
+            warn!(%address, err = %e, "failed to hand over to the node supervisor");
+        }
+    }
+
+    /// Take back the ownership of a worker or processor from the node supervisor
+    pub(crate) async fn unsupervise(&self, address: &Address) {
+        let msg = SupervisorMessage::Release(address.clone());
+        if let Err(e) = self.supervisor.send(DefaultAddress::SUPERVISOR, msg).await {
+            warn!(%address, err = %e, "failed to release from the node supervisor");
+        }
+    }
+
     async fn configure_authorities(&mut self, ac: &AuthoritiesConfig) -> Result<()> {
         let vault = self.vault()?;
 
@@ -340,12 +382,15 @@ impl NodeManager {
             .await?;
         self.start_hop_service_impl(ctx, DefaultAddress::HOP_SERVICE.into())
             .await?;
+        self.start_identity_update_service_impl(DefaultAddress::IDENTITY_UPDATE_SERVICE.into())
+            .await?;
 
-        ForwardingService::create(
+        ForwardingService::create_supervised(
             ctx,
             DefaultAddress::FORWARDING_SERVICE,
             AllowAll, // FIXME: @ac
             AllowAll, // FIXME: @ac
+            DefaultAddress::SUPERVISOR,
         )
         .await?;
 
@@ -499,291 +544,6 @@ impl NodeManager {
     }
 }
 
-impl NodeManagerWorker {
-    //////// Request matching and response handling ////////
-
-    async fn handle_request(
-        &mut self,
-        ctx: &mut Context,
-        req: &Request<'_>,
-        dec: &mut Decoder<'_>,
-    ) -> Result<Vec<u8>> {
-        debug! {
-            target: TARGET,
-            id     = %req.id(),
-            method = ?req.method(),
-            path   = %req.path(),
-            body   = %req.has_body(),
-            "request"
-        }
-
-        use Method::*;
-        let path = req.path();
-        let path_segments = req.path_segments::<5>();
-        let method = match req.method() {
-            Some(m) => m,
-            None => todo!(),
-        };
-
-        let r = match (method, path_segments.as_slice()) {
-            // ==*== Basic node information ==*==
-            // TODO: create, delete, destroy remote nodes
-            (Get, ["node"]) => {
-                let node_manager = self.node_manager.read().await;
-                Response::ok(req.id())
-                    .body(NodeStatus::new(
-                        &node_manager.node_name,
-                        "Running",
-                        ctx.list_workers().await?.len() as u32,
-                        std::process::id() as i32,
-                        node_manager.transports.len() as u32,
-                    ))
-                    .to_vec()?
-            }
-
-            // ==*== Tcp Connection ==*==
-            // TODO: Get all tcp connections
-            (Get, ["node", "tcp", "connection"]) => {
-                let node_manager = self.node_manager.read().await;
-                self.get_tcp_con_or_list(req, &node_manager.transports, TransportMode::Connect)
-                    .to_vec()?
-            }
-            (Post, ["node", "tcp", "connection"]) => {
-                self.add_transport(req, dec).await?.to_vec()?
-            }
-            (Delete, ["node", "tcp", "connection"]) => {
-                self.delete_transport(req, dec).await?.to_vec()?
-            }
-
-            // ==*== Tcp Listeners ==*==
-            (Get, ["node", "tcp", "listener"]) => {
-                let node_manager = self.node_manager.read().await;
-                self.get_tcp_con_or_list(
-                    req,
-                    &node_manager.transports.clone(),
-                    TransportMode::Listen,
-                )
-                .to_vec()?
-            }
-            (Post, ["node", "tcp", "listener"]) => self.add_transport(req, dec).await?.to_vec()?,
-            (Delete, ["node", "tcp", "listener"]) => {
-                self.delete_transport(req, dec).await?.to_vec()?
-            }
-
-            // ==*== Credential ==*==
-            (Post, ["node", "credentials", "actions", "get"]) => self
-                .get_credential(req, dec, ctx)
-                .await?
-                .either(ResponseBuilder::to_vec, ResponseBuilder::to_vec)?,
-            (Post, ["node", "credentials", "actions", "present"]) => {
-                self.present_credential(req, dec).await?.to_vec()?
-            }
-
-            // ==*== Secure channels ==*==
-            // TODO: Change to RequestBuilder format
-            (Get, ["node", "secure_channel"]) => {
-                let node_manager = self.node_manager.read().await;
-                self.list_secure_channels(req, &node_manager.registry)
-                    .to_vec()?
-            }
-            (Get, ["node", "secure_channel_listener"]) => {
-                let node_manager = self.node_manager.read().await;
-                self.list_secure_channel_listener(req, &node_manager.registry)
-                    .to_vec()?
-            }
-            (Post, ["node", "secure_channel"]) => {
-                self.create_secure_channel(req, dec, ctx).await?.to_vec()?
-            }
-            (Delete, ["node", "secure_channel"]) => {
-                self.delete_secure_channel(req, dec).await?.to_vec()?
-            }
-            (Get, ["node", "show_secure_channel"]) => {
-                self.show_secure_channel(req, dec).await?.to_vec()?
-            }
-            (Post, ["node", "secure_channel_listener"]) => self
-                .create_secure_channel_listener(req, dec, ctx)
-                .await?
-                .to_vec()?,
-            (Delete, ["node", "secure_channel_listener"]) => self
-                .delete_secure_channel_listener(req, dec)
-                .await?
-                .to_vec()?,
-
-            // ==*== Services ==*==
-            (Post, ["node", "services", DefaultAddress::VAULT_SERVICE]) => {
-                self.start_vault_service(ctx, req, dec).await?.to_vec()?
-            }
-            (Post, ["node", "services", DefaultAddress::IDENTITY_SERVICE]) => {
-                self.start_identity_service(ctx, req, dec).await?.to_vec()?
-            }
-            (Post, ["node", "services", DefaultAddress::AUTHENTICATED_SERVICE]) => self
-                .start_authenticated_service(ctx, req, dec)
-                .await?
-                .to_vec()?,
-            (Post, ["node", "services", DefaultAddress::UPPERCASE_SERVICE]) => self
-                .start_uppercase_service(ctx, req, dec)
-                .await?
-                .to_vec()?,
-            (Post, ["node", "services", DefaultAddress::ECHO_SERVICE]) => {
-                self.start_echoer_service(ctx, req, dec).await?.to_vec()?
-            }
-            (Post, ["node", "services", DefaultAddress::HOP_SERVICE]) => {
-                self.start_hop_service(ctx, req, dec).await?.to_vec()?
-            }
-            (Post, ["node", "services", DefaultAddress::DIRECT_AUTHENTICATOR]) => self
-                .start_authenticator_service(ctx, req, dec)
-                .await?
-                .to_vec()?,
-            (Post, ["node", "services", DefaultAddress::VERIFIER]) => {
-                self.start_verifier_service(ctx, req, dec).await?.to_vec()?
-            }
-            (Post, ["node", "services", DefaultAddress::CREDENTIALS_SERVICE]) => self
-                .start_credentials_service(ctx, req, dec)
-                .await?
-                .to_vec()?,
-            (Post, ["node", "services", DefaultAddress::OKTA_IDENTITY_PROVIDER]) => self
-                .start_okta_identity_provider_service(ctx, req, dec)
-                .await?
-                .to_vec()?,
-            (Post, ["node", "services", DefaultAddress::KAFKA_CONSUMER]) => {
-                self.start_kafka_consumer_service(ctx, req, dec).await?
-            }
-            (Post, ["node", "services", DefaultAddress::KAFKA_PRODUCER]) => {
-                self.start_kafka_producer_service(ctx, req, dec).await?
-            }
-            (Get, ["node", "services"]) => {
-                let node_manager = self.node_manager.read().await;
-                self.list_services(req, &node_manager.registry).to_vec()?
-            }
-
-            // ==*== Forwarder commands ==*==
-            (Post, ["node", "forwarder"]) => self.create_forwarder(ctx, req.id(), dec).await?,
-
-            // ==*== Inlets & Outlets ==*==
-            (Get, ["node", "inlet"]) => {
-                let inlet_registry = {
-                    let node_manager = self.node_manager.read().await;
-                    &node_manager.registry.inlets.clone()
-                };
-                self.get_inlets(req, inlet_registry).to_vec()?
-            }
-            (Get, ["node", "inlet", alias]) => self.show_inlet(req, alias).await?.to_vec()?,
-            (Get, ["node", "outlet"]) => {
-                let outlet_registry = {
-                    let node_manager = self.node_manager.read().await;
-                    &node_manager.registry.outlets.clone()
-                };
-                self.get_outlets(req, outlet_registry).to_vec()?
-            }
-            (Get, ["node", "outlet", alias]) => self.show_outlet(req, alias).await?.to_vec()?,
-            (Post, ["node", "inlet"]) => self.create_inlet(req, dec, ctx).await?.to_vec()?,
-            (Post, ["node", "outlet"]) => self.create_outlet(req, dec).await?.to_vec()?,
-            (Delete, ["node", "outlet", alias]) => {
-                self.delete_outlet(req, alias).await?.to_vec()?
-            }
-            (Delete, ["node", "inlet", alias]) => self.delete_inlet(req, alias).await?.to_vec()?,
-            (Delete, ["node", "portal"]) => todo!(),
-
-            // ==*== Workers ==*==
-            (Get, ["node", "workers"]) => {
-                let workers = ctx.list_workers().await?;
-
-                let mut list = Vec::new();
-                workers
-                    .iter()
-                    .for_each(|addr| list.push(WorkerStatus::new(addr.address())));
-
-                Response::ok(req.id())
-                    .body(WorkerList::new(list))
-                    .to_vec()?
-            }
-
-            (Post, ["policy", resource, action]) => self
-                .node_manager
-                .read()
-                .await
-                .add_policy(resource, action, req, dec)
-                .await?
-                .to_vec()?,
-            (Get, ["policy", resource]) => self
-                .node_manager
-                .read()
-                .await
-                .list_policies(req, resource)
-                .await?
-                .to_vec()?,
-            (Get, ["policy", resource, action]) => self
-                .node_manager
-                .read()
-                .await
-                .get_policy(req, resource, action)
-                .await?
-                .either(ResponseBuilder::to_vec, ResponseBuilder::to_vec)?,
-            (Delete, ["policy", resource, action]) => self
-                .node_manager
-                .read()
-                .await
-                .del_policy(req, resource, action)
-                .await?
-                .to_vec()?,
-
-            // ==*== Spaces ==*==
-            (Post, ["v0", "spaces"]) => self.create_space(ctx, dec).await?,
-            (Get, ["v0", "spaces"]) => self.list_spaces(ctx, dec).await?,
-            (Get, ["v0", "spaces", id]) => self.get_space(ctx, dec, id).await?,
-            (Delete, ["v0", "spaces", id]) => self.delete_space(ctx, dec, id).await?,
-
-            // ==*== Projects ==*==
-            (Post, ["v0", "projects", space_id]) => self.create_project(ctx, dec, space_id).await?,
-            (Get, ["v0", "projects"]) => self.list_projects(ctx, dec).await?,
-            (Get, ["v0", "projects", project_id]) => self.get_project(ctx, dec, project_id).await?,
-            (Delete, ["v0", "projects", space_id, project_id]) => {
-                self.delete_project(ctx, dec, space_id, project_id).await?
-            }
-
-            // ==*== Enroll ==*==
-            (Post, ["v0", "enroll", "auth0"]) => self.enroll_auth0(ctx, dec).await?,
-            (Get, ["v0", "enroll", "token"]) => self.generate_enrollment_token(ctx, dec).await?,
-            (Put, ["v0", "enroll", "token"]) => {
-                self.authenticate_enrollment_token(ctx, dec).await?
-            }
-
-            // ==*== Subscriptions ==*==
-            (Post, ["subscription"]) => self.activate_subscription(ctx, dec).await?,
-            (Get, ["subscription", id]) => self.get_subscription(ctx, dec, id).await?,
-            (Get, ["subscription"]) => self.list_subscriptions(ctx, dec).await?,
-            (Put, ["subscription", id, "contact_info"]) => {
-                self.update_subscription_contact_info(ctx, dec, id).await?
-            }
-            (Put, ["subscription", id, "space_id"]) => {
-                self.update_subscription_space(ctx, dec, id).await?
-            }
-            (Put, ["subscription", id, "unsubscribe"]) => self.unsubscribe(ctx, dec, id).await?,
-
-            // ==*== Addons ==*==
-            (Get, [project_id, "addons"]) => self.list_addons(ctx, dec, project_id).await?,
-            (Put, [project_id, "addons", addon_id]) => {
-                self.configure_addon(ctx, dec, project_id, addon_id).await?
-            }
-            (Delete, [project_id, "addons", addon_id]) => {
-                self.disable_addon(ctx, dec, project_id, addon_id).await?
-            }
-
-            // ==*== Messages ==*==
-            (Post, ["v0", "message"]) => self.send_message(ctx, req, dec).await?,
-
-            // ==*== Catch-all for Unimplemented APIs ==*==
-            _ => {
-                warn!(%method, %path, "Called invalid endpoint");
-                Response::bad_request(req.id())
-                    .body(format!("Invalid endpoint: {path}"))
-                    .to_vec()?
-            }
-        };
-        Ok(r)
-    }
-}
-
 #[ockam::worker]
 impl Worker for NodeManagerWorker {
     type Message = Vec<u8>;
@@ -793,6 +553,15 @@ impl Worker for NodeManagerWorker {
         let mut node_manager = self.node_manager.write().await;
         if !node_manager.skip_defaults {
             node_manager.initialize_defaults(ctx).await?;
+            self.identity_update_sync = Some(tokio::spawn(Self::identity_update_sync_loop(
+                node_manager.identity.clone(),
+            )));
+        }
+
+        if node_manager.authorities().is_ok() {
+            self.credential_refresh = Some(tokio::spawn(Self::refresh_credential_loop(
+                self.node_manager.clone(),
+            )));
         }
 
         Ok(())
@@ -801,45 +570,17 @@ impl Worker for NodeManagerWorker {
     async fn shutdown(&mut self, _: &mut Self::Context) -> Result<()> {
         let node_manager = self.node_manager.read().await;
         node_manager.medic.abort();
+        if let Some(credential_refresh) = &self.credential_refresh {
+            credential_refresh.abort();
+        }
+        if let Some(identity_update_sync) = &self.identity_update_sync {
+            identity_update_sync.abort();
+        }
         Ok(())
     }
 
     async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Vec<u8>>) -> Result<()> {
-        let mut dec = Decoder::new(msg.as_body());
-        let req: Request = match dec.decode() {
-            Ok(r) => r,
-            Err(e) => {
-                error!("Failed to decode request: {:?}", e);
-                return Ok(());
-            }
-        };
-
-        let r = match self.handle_request(ctx, &req, &mut dec).await {
-            Ok(r) => r,
-            Err(err) => {
-                error! {
-                    target: TARGET,
-                    re     = %req.id(),
-                    method = ?req.method(),
-                    path   = %req.path(),
-                    code   = %err.code(),
-                    cause  = ?err.source(),
-                    "failed to handle request"
-                }
-                let err = Error::new(req.path())
-                    .with_message(format!("failed to handle request: {err} {req:?}"));
-                Response::builder(req.id(), Status::InternalServerError)
-                    .body(err)
-                    .to_vec()?
-            }
-        };
-        debug! {
-            target: TARGET,
-            re     = %req.id(),
-            method = ?req.method(),
-            path   = %req.path(),
-            "responding"
-        }
+        let r = dispatch_with(self, ctx, Self::HANDLERS, msg.as_body()).await?;
         ctx.send(msg.return_route(), r).await
     }
 }
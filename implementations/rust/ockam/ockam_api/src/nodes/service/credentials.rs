@@ -1,51 +1,45 @@
 use crate::authenticator::direct::{CredentialIssuerClient, RpcClient};
+use crate::cli_state::CredentialConfig;
 use crate::error::ApiError;
 use crate::local_multiaddr_to_route;
 use crate::nodes::models::credentials::{GetCredentialRequest, PresentCredentialRequest};
-use crate::nodes::service::map_multiaddr_err;
+use crate::nodes::service::{map_multiaddr_err, AuthorityInfo};
 use crate::nodes::NodeManager;
 use crate::{create_tcp_session, DefaultAddress};
 use either::Either;
-use minicbor::Decoder;
 use ockam::Result;
 use ockam_core::api::{Error, Request, Response, ResponseBuilder};
 use ockam_core::compat::sync::Arc;
-use ockam_core::route;
-use ockam_identity::credential::Credential;
-use ockam_identity::{Identity, IdentityVault};
+use ockam_core::{route, Address, AsyncTryClone, Route};
+use ockam_identity::credential::{Credential, CredentialData, Timestamp, Unverified};
+use ockam_identity::{
+    Identity, IdentityVault, PublicIdentity, SecureChannelTrustOptions, TrustIdentifierPolicy,
+};
 use ockam_multiaddr::MultiAddr;
-use ockam_node::Context;
+use ockam_node::compat::asynchronous::RwLock;
+use ockam_node::{tokio, Context};
+use ockam_transport_tcp::TcpTransport;
 use std::str::FromStr;
+use std::time::Duration;
 
 use super::NodeManagerWorker;
 
-impl NodeManager {
-    pub(super) async fn get_credential_impl(
-        &mut self,
-        identity: &Identity,
-        overwrite: bool,
-    ) -> Result<()> {
-        debug!("Credential check: looking for identity");
-
-        if identity.credential().await.is_some() && !overwrite {
-            return Err(ApiError::generic("credential already exists"));
-        }
-
-        debug!("Credential check: looking for authorities...");
-        let authorities = self.authorities()?;
-
-        // Take first authority
-        let authority = authorities
-            .as_ref()
-            .first()
-            .ok_or_else(|| ApiError::generic("No known Authority"))?;
-
-        debug!("Getting credential from : {}", authority.addr);
+/// What is needed to retrieve a credential from the project authority, so that the network
+/// round-trips can happen without holding the node manager lock
+struct CredentialRetriever {
+    authority: AuthorityInfo,
+    authorities: Vec<PublicIdentity>,
+    tcp_transport: TcpTransport,
+}
 
-        let allowed = vec![authority.identity.identifier().clone()];
+impl CredentialRetriever {
+    /// Get a credential for `identity` from the authority over a new secure channel, verify
+    /// it and set it on the identity. Return the secure channel address and route.
+    async fn retrieve(&self, identity: &Identity, audience: &[String]) -> Result<(Address, Route)> {
+        debug!("Getting credential from : {}", self.authority.addr);
 
         let authority_tcp_session =
-            match create_tcp_session(&authority.addr, &self.tcp_transport).await {
+            match create_tcp_session(&self.authority.addr, &self.tcp_transport).await {
                 Some(authority_tcp_session) => authority_tcp_session,
                 None => {
                     error!("INVALID ROUTE");
@@ -54,50 +48,248 @@ impl NodeManager {
             };
 
         debug!("Create secure channel to project authority");
-        let sc = self
-            .create_secure_channel_internal(
-                identity,
-                authority_tcp_session.route,
-                Some(allowed),
-                None,
-                authority_tcp_session.session,
+        let mut trust_options = SecureChannelTrustOptions::new().with_trust_policy(
+            TrustIdentifierPolicy::new(self.authority.identity.identifier().clone()),
+        );
+        if let Some((sessions, session_id)) = &authority_tcp_session.session {
+            trust_options = trust_options.as_consumer(sessions, session_id);
+        }
+        let sc = identity
+            .create_secure_channel_extended(
+                authority_tcp_session.route.clone(),
+                trust_options,
+                Duration::from_secs(120),
             )
             .await?;
         debug!("Created secure channel to project authority");
 
-        // Borrow checker issues...
-        let authorities = self.authorities()?;
-
         let client = CredentialIssuerClient::new(
             RpcClient::new(
-                route![sc, DefaultAddress::CREDENTIAL_ISSUER],
+                route![sc.clone(), DefaultAddress::CREDENTIAL_ISSUER],
                 identity.ctx(),
             )
             .await?,
         );
-        let credential = client.credential().await?;
+        let credential = if audience.is_empty() {
+            client.credential().await?
+        } else {
+            client.credential_with_audience(audience.to_vec()).await?
+        };
         debug!("Got credential");
 
         identity
-            .verify_self_credential(&credential, authorities.public_identities().iter())
+            .verify_self_credential(&credential, self.authorities.iter())
             .await?;
         debug!("Verified self credential");
 
         identity.set_credential(credential.to_owned()).await;
 
+        Ok((sc, authority_tcp_session.route))
+    }
+}
+
+impl NodeManager {
+    /// Get what is needed to retrieve a credential from the first known authority
+    async fn credential_retriever(&self) -> Result<CredentialRetriever> {
+        debug!("Credential check: looking for authorities...");
+        let authorities = self.authorities()?;
+
+        // Take first authority
+        let authority = authorities
+            .as_ref()
+            .first()
+            .ok_or_else(|| ApiError::generic("No known Authority"))?;
+
+        Ok(CredentialRetriever {
+            authority: authority.clone(),
+            authorities: authorities.public_identities(),
+            tcp_transport: self.tcp_transport.async_try_clone().await?,
+        })
+    }
+
+    pub(super) async fn get_credential_impl(
+        &mut self,
+        identity: &Identity,
+        overwrite: bool,
+        audience: &[String],
+    ) -> Result<()> {
+        debug!("Credential check: looking for identity");
+
+        if identity.credential().await.is_some() && !overwrite {
+            return Err(ApiError::generic("credential already exists"));
+        }
+
+        let retriever = self.credential_retriever().await?;
+        let (sc, sc_route) = retriever.retrieve(identity, audience).await?;
+        self.registry.secure_channels.insert(
+            sc,
+            sc_route,
+            Some(vec![retriever.authority.identity.identifier().clone()]),
+        );
+
         Ok(())
     }
+
+    /// Time left before the node credential must be refreshed, `None` if there is no credential
+    async fn credential_refresh_delay(&self) -> Result<Option<Duration>> {
+        let credential = match self.identity.credential().await {
+            Some(credential) => credential,
+            None => return Ok(None),
+        };
+        let now = Timestamp::now().ok_or_else(|| ApiError::generic("invalid system time"))?;
+        let authorities = self.authorities()?.public_identities();
+        match self
+            .identity
+            .verify_self_credential(&credential, authorities.iter())
+            .await
+        {
+            Ok(data) => Ok(Some(refresh_delay(
+                data.created_at(),
+                data.expires_at(),
+                now,
+            ))),
+            // Expired or otherwise invalid credentials are replaced right away
+            Err(_) => Ok(Some(Duration::ZERO)),
+        }
+    }
+
+    /// Persist the node credential if it was loaded from a stored credential
+    async fn store_refreshed_credential(&self) -> Result<()> {
+        if let (Some(name), Some(credential)) =
+            (&self.credential_name, self.identity.credential().await)
+        {
+            let issuer = self.cli_state.credentials.get(name)?.config().await?.issuer;
+            let encoded = hex::encode(minicbor::to_vec(&credential)?);
+            self.cli_state
+                .credentials
+                .update(name, CredentialConfig::new(issuer, encoded)?)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Delay before a credential issued at `created_at` and expiring at `expires_at` should be
+/// refreshed. Credentials are refreshed once 4/5 of their lifetime has elapsed.
+fn refresh_delay(created_at: Timestamp, expires_at: Timestamp, now: Timestamp) -> Duration {
+    let lifetime = expires_at
+        .unix_time()
+        .saturating_sub(created_at.unix_time());
+    let refresh_at = created_at.unix_time() + lifetime / 5 * 4;
+    Duration::from_secs(refresh_at.saturating_sub(now.unix_time()))
 }
 
+/// Delay before trying again when the credential could not be refreshed
+const CREDENTIAL_REFRESH_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Minimum delay between two refreshes, so that a credential which is invalid as soon as it is
+/// issued, e.g. because of a clock skew with the authority, doesn't make the node loop
+const CREDENTIAL_REFRESH_MIN_DELAY: Duration = Duration::from_secs(10);
+
+/// Delay before checking again when the node doesn't have a credential yet
+const CREDENTIAL_CHECK_DELAY: Duration = Duration::from_secs(60);
+
 impl NodeManagerWorker {
+    /// Get a new credential for the node identity from the project authority, with the same
+    /// audience as the current one, and persist it. The node manager is only locked to read
+    /// the authority details and to store the result, not during the retrieval.
+    async fn refresh_credential(node_manager: &RwLock<NodeManager>) -> Result<()> {
+        let (identity, retriever) = {
+            let node_manager = node_manager.read().await;
+            (
+                node_manager.identity.clone(),
+                node_manager.credential_retriever().await?,
+            )
+        };
+
+        let audience = match identity.credential().await {
+            Some(credential) => {
+                let data: CredentialData<Unverified> =
+                    minicbor::decode(credential.unverified_data())?;
+                data.unverified_audience().unwrap_or_default().to_vec()
+            }
+            None => Vec::new(),
+        };
+        let (sc, sc_route) = retriever.retrieve(&identity, &audience).await?;
+
+        let mut node_manager = node_manager.write().await;
+        node_manager.registry.secure_channels.insert(
+            sc,
+            sc_route,
+            Some(vec![retriever.authority.identity.identifier().clone()]),
+        );
+        node_manager.store_refreshed_credential().await
+    }
+
+    /// Keep the node credential valid: refresh it from the project authority before it
+    /// expires and present the new credential on the existing secure channels
+    pub(super) async fn refresh_credential_loop(node_manager: Arc<RwLock<NodeManager>>) {
+        loop {
+            let delay = match node_manager.read().await.credential_refresh_delay().await {
+                Ok(Some(delay)) => delay.max(CREDENTIAL_REFRESH_MIN_DELAY),
+                Ok(None) => CREDENTIAL_CHECK_DELAY,
+                Err(e) => {
+                    warn!("Credential refresh stopped: {e}");
+                    return;
+                }
+            };
+            tokio::time::sleep(delay).await;
+
+            // The credential may have been replaced while sleeping
+            match node_manager.read().await.credential_refresh_delay().await {
+                Ok(Some(delay)) if delay.is_zero() => {}
+                _ => continue,
+            }
+
+            if let Err(e) = Self::refresh_credential(&node_manager).await {
+                warn!("Failed to refresh credential: {e}");
+                tokio::time::sleep(CREDENTIAL_REFRESH_RETRY_DELAY).await;
+                continue;
+            }
+            info!("Refreshed node credential");
+
+            let (identity, authorities, enabled) = {
+                let node_manager = node_manager.read().await;
+                (
+                    node_manager.identity.clone(),
+                    node_manager
+                        .authorities()
+                        .map(|a| a.public_identities())
+                        .unwrap_or_default(),
+                    node_manager.enable_credential_checks,
+                )
+            };
+            if !enabled {
+                continue;
+            }
+            for channel in identity.secure_channel_registry().get_channel_list() {
+                if authorities
+                    .iter()
+                    .any(|a| a.identifier() == channel.their_id())
+                {
+                    continue;
+                }
+                let route = route![
+                    channel.encryptor_messaging_address().clone(),
+                    DefaultAddress::CREDENTIALS_SERVICE
+                ];
+                if let Err(e) = identity.present_credential(route, None, None).await {
+                    warn!(
+                        "Failed to present the refreshed credential to {}: {e}",
+                        channel.their_id()
+                    );
+                }
+            }
+        }
+    }
+
     pub(super) async fn get_credential(
         &mut self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        request: GetCredentialRequest,
         ctx: &Context,
     ) -> Result<Either<ResponseBuilder<Error<'_>>, ResponseBuilder<Credential>>> {
         let mut node_manager = self.node_manager.write().await;
-        let request: GetCredentialRequest = dec.decode()?;
 
         let identity = if let Some(identity) = &request.identity_name {
             let idt_state = node_manager.cli_state.identities.get(identity)?;
@@ -114,7 +306,7 @@ impl NodeManagerWorker {
         };
 
         node_manager
-            .get_credential_impl(&identity, request.is_overwrite())
+            .get_credential_impl(&identity, request.is_overwrite(), &request.audience)
             .await?;
 
         if let Some(c) = identity.credential().await {
@@ -128,10 +320,9 @@ impl NodeManagerWorker {
     pub(super) async fn present_credential(
         &self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        request: PresentCredentialRequest<'_>,
     ) -> Result<ResponseBuilder> {
         let node_manager = self.node_manager.read().await;
-        let request: PresentCredentialRequest = dec.decode()?;
 
         // TODO: Replace with self.connect?
         let route = MultiAddr::from_str(&request.route).map_err(map_multiaddr_err)?;
@@ -140,10 +331,11 @@ impl NodeManagerWorker {
             None => return Err(ApiError::generic("invalid credentials service route")),
         };
 
+        let disclosed_attributes = request.disclosed_attributes();
         if request.oneway {
             node_manager
                 .identity
-                .present_credential(route, None)
+                .present_credential(route, None, disclosed_attributes.as_deref())
                 .await?;
         } else {
             node_manager
@@ -153,6 +345,7 @@ impl NodeManagerWorker {
                     &node_manager.authorities()?.public_identities(),
                     node_manager.attributes_storage.clone(),
                     None,
+                    disclosed_attributes.as_deref(),
                 )
                 .await?;
         }
@@ -161,3 +354,99 @@ impl NodeManagerWorker {
         Ok(response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authenticator::direct::CredentialIssuer;
+    use crate::bootstrapped_identities_store::PreTrustedIdentities;
+    use crate::nodes::service::Authorities;
+    use ockam_core::compat::collections::{BTreeMap, HashMap};
+    use ockam_identity::authenticated_storage::AttributesEntry;
+    use ockam_identity::TrustEveryonePolicy;
+    use ockam_transport_tcp::TcpListenerTrustOptions;
+    use ockam_vault::Vault;
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 10_000)]
+    async fn refresh_credential__from_authority__credential_replaced_and_channel_registered(
+        context: &mut Context,
+    ) -> Result<()> {
+        let handle = crate::util::test::start_manager_for_tests(context).await?;
+        let identity = handle.node_manager.read().await.identity.clone();
+
+        // Start an authority issuing credentials to the node identity
+        let authority = Arc::new(Identity::create(context, Vault::create()).await?);
+        let now = Timestamp::now().unwrap();
+        let members = PreTrustedIdentities::from(HashMap::from([(
+            identity.identifier().clone(),
+            AttributesEntry::new(BTreeMap::new(), now, None, None),
+        )]));
+        let issuer =
+            CredentialIssuer::new(b"project42".to_vec(), Arc::new(members), authority.clone())
+                .await?;
+        context
+            .start_worker(
+                DefaultAddress::CREDENTIAL_ISSUER,
+                issuer,
+                ockam_core::AllowAll,
+                ockam_core::AllowAll,
+            )
+            .await?;
+        authority
+            .create_secure_channel_listener("authority_api", TrustEveryonePolicy)
+            .await?;
+        let (socket_addr, _) = handle
+            .tcp
+            .listen("127.0.0.1:0", TcpListenerTrustOptions::new())
+            .await?;
+        let addr = MultiAddr::from_str(&format!(
+            "/ip4/127.0.0.1/tcp/{}/service/authority_api",
+            socket_addr.port()
+        ))
+        .map_err(map_multiaddr_err)?;
+        handle.node_manager.write().await.authorities =
+            Some(Authorities::new(vec![AuthorityInfo {
+                identity: authority.to_public().await?,
+                addr,
+            }]));
+
+        // Without credential the node must get one right away
+        assert!(identity.credential().await.is_none());
+        NodeManagerWorker::refresh_credential(&handle.node_manager).await?;
+
+        let credential = identity.credential().await.unwrap();
+        identity
+            .verify_self_credential(&credential, [authority.to_public().await?].iter())
+            .await?;
+        let node_manager = handle.node_manager.read().await;
+        let delay = node_manager.credential_refresh_delay().await?.unwrap();
+        assert!(delay > CREDENTIAL_REFRESH_MIN_DELAY);
+        assert_eq!(node_manager.registry.secure_channels.list().len(), 1);
+        drop(node_manager);
+
+        context.stop().await
+    }
+
+    #[test]
+    fn refresh_delay_before_expiry() {
+        let created = Timestamp::from(1000);
+        let expires = Timestamp::from(2000);
+        assert_eq!(
+            refresh_delay(created, expires, Timestamp::from(1000)),
+            Duration::from_secs(800)
+        );
+        assert_eq!(
+            refresh_delay(created, expires, Timestamp::from(1500)),
+            Duration::from_secs(300)
+        );
+        assert_eq!(
+            refresh_delay(created, expires, Timestamp::from(1900)),
+            Duration::ZERO
+        );
+        assert_eq!(
+            refresh_delay(created, expires, Timestamp::from(3000)),
+            Duration::ZERO
+        );
+    }
+}
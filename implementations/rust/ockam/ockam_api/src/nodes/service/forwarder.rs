@@ -1,7 +1,5 @@
 use std::sync::Arc;
 
-use minicbor::Decoder;
-
 use ockam::compat::asynchronous::RwLock;
 use ockam::remote::{RemoteForwarder, RemoteForwarderTrustOptions};
 use ockam::Result;
@@ -26,11 +24,10 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &mut Context,
         rid: Id,
-        dec: &mut Decoder<'_>,
+        req: CreateForwarder<'_>,
     ) -> Result<Vec<u8>> {
         let manager = self.node_manager.clone();
         let mut node_manager = self.node_manager.write().await;
-        let req: CreateForwarder = dec.decode()?;
 
         debug!(addr = %req.address(), alias = ?req.alias(), "Handling CreateForwarder request");
 
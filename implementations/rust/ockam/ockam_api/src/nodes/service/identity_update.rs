@@ -0,0 +1,90 @@
+use crate::error::ApiError;
+use crate::local_multiaddr_to_route;
+use crate::nodes::connection::Connection;
+use crate::nodes::models::identity::{PullIdentityUpdateRequest, PushIdentityUpdateRequest};
+use crate::nodes::service::map_multiaddr_err;
+use crate::nodes::NodeManager;
+use crate::DefaultAddress;
+use ockam::{Context, Result, Route};
+use ockam_core::api::{Request, Response, ResponseBuilder};
+use ockam_core::compat::sync::Arc;
+use ockam_identity::{Identity, IdentityIdentifier};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::tokio;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::NodeManagerWorker;
+
+impl NodeManager {
+    /// Connect to the identity update service of another node
+    async fn identity_update_route(&mut self, ctx: &Context, route: &str) -> Result<Route> {
+        let addr = MultiAddr::from_str(route).map_err(map_multiaddr_err)?;
+        let (sec_chan, suffix) = self.connect(Connection::new(ctx, &addr)).await?;
+        let full = sec_chan.try_with(&suffix)?;
+        local_multiaddr_to_route(&full)
+            .ok_or_else(|| ApiError::generic("invalid identity update service route"))
+    }
+}
+
+/// Delay between two exchanges of change histories with the peers of the node
+const IDENTITY_UPDATE_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+impl NodeManagerWorker {
+    /// Periodically exchange change histories with the other party of every secure channel
+    /// of the node, including the channels to the project authority, so that newer histories
+    /// are applied and conflicts are reported without manual pushes and pulls
+    pub(super) async fn identity_update_sync_loop(identity: Arc<Identity>) {
+        loop {
+            tokio::time::sleep(IDENTITY_UPDATE_SYNC_INTERVAL).await;
+            identity
+                .sync_identity_updates(DefaultAddress::IDENTITY_UPDATE_SERVICE)
+                .await;
+        }
+    }
+
+    pub(super) async fn push_identity_update(
+        &self,
+        ctx: &Context,
+        req: &Request<'_>,
+        request: PushIdentityUpdateRequest<'_>,
+    ) -> Result<ResponseBuilder> {
+        let mut node_manager = self.node_manager.write().await;
+        let route = node_manager
+            .identity_update_route(ctx, &request.route)
+            .await?;
+
+        match request.identity_id {
+            Some(id) => {
+                let id = IdentityIdentifier::try_from(id.as_ref())?;
+                node_manager
+                    .identity
+                    .push_known_identity(route, &id)
+                    .await?
+            }
+            None => node_manager.identity.push_identity_update(route).await?,
+        }
+
+        Ok(Response::ok(req.id()))
+    }
+
+    pub(super) async fn pull_identity_update(
+        &self,
+        ctx: &Context,
+        req: &Request<'_>,
+        request: PullIdentityUpdateRequest<'_>,
+    ) -> Result<ResponseBuilder> {
+        let mut node_manager = self.node_manager.write().await;
+        let route = node_manager
+            .identity_update_route(ctx, &request.route)
+            .await?;
+
+        let id = IdentityIdentifier::try_from(request.identity_id.as_ref())?;
+        node_manager
+            .identity
+            .pull_identity_update(route, &id)
+            .await?;
+
+        Ok(Response::ok(req.id()))
+    }
+}
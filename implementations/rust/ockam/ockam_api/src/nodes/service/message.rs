@@ -38,7 +38,6 @@ impl<'a> SendMessage<'a> {
 }
 
 mod node {
-    use minicbor::Decoder;
     use tracing::trace;
 
     use crate::error::ApiError;
@@ -57,9 +56,8 @@ mod node {
             &mut self,
             ctx: &mut Context,
             req: &Request<'_>,
-            dec: &mut Decoder<'_>,
+            req_body: super::SendMessage<'_>,
         ) -> Result<Vec<u8>> {
-            let req_body: super::SendMessage = dec.decode()?;
             let multiaddr = req_body.multiaddr()?;
             let msg = req_body.message.to_vec();
             let msg_length = msg.len();
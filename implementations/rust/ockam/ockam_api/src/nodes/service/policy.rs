@@ -1,6 +1,5 @@
 use crate::nodes::models::policy::{Policy, PolicyList};
 use either::Either;
-use minicbor::Decoder;
 use ockam_abac::{Action, Resource};
 use ockam_core::api::{Error, Request, Response, ResponseBuilder};
 use ockam_core::Result;
@@ -13,9 +12,8 @@ impl NodeManager {
         resource: &str,
         action: &str,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        p: Policy,
     ) -> Result<ResponseBuilder<()>> {
-        let p: Policy = dec.decode()?;
         let r = Resource::new(resource);
         let a = Action::new(action);
         self.policies.set_policy(&r, &a, p.expression()).await?;
@@ -8,7 +8,6 @@ use crate::nodes::service::random_alias;
 use crate::session::{util, Data, Replacer, Session};
 use crate::{actions, resources};
 use crate::{local_multiaddr_to_route, try_multiaddr_to_addr};
-use minicbor::Decoder;
 use ockam::compat::tokio::time::timeout;
 use ockam::{Address, AsyncTryClone, Result};
 use ockam_abac::expr::{eq, ident, str};
@@ -29,7 +28,7 @@ const INLET_WORKER: &str = "inlet-worker";
 const OUTER_CHAN: &str = "outer-chan";
 
 impl NodeManager {
-    async fn access_control(
+    pub(super) async fn access_control(
         &self,
         r: &Resource,
         a: &Action,
@@ -41,6 +40,10 @@ impl NodeManager {
             env.put("resource.id", str(r.as_str()));
             env.put("action.id", str(a.as_str()));
             env.put("resource.project_id", str(pid));
+            env.put(
+                "resource.node_id",
+                str(self.identity.identifier().to_string()),
+            );
             // Check if a policy exists for (resource, action) and if not, then
             // create a default entry:
             if self.policies.get_policy(r, a).await?.is_none() {
@@ -103,13 +106,13 @@ impl NodeManagerWorker {
     pub(super) async fn create_inlet<'a>(
         &mut self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: CreateInlet<'_>,
         ctx: &Context,
     ) -> Result<ResponseBuilder<InletStatus<'a>>> {
         let manager = self.node_manager.clone();
         let mut node_manager = self.node_manager.write().await;
         let rid = req.id();
-        let req: CreateInlet = dec.decode()?;
+        let req = body;
 
         let listen_addr = req.listen_addr().to_string();
         let alias = req
@@ -194,6 +197,7 @@ impl NodeManagerWorker {
 
         Ok(match res {
             Ok((worker_addr, _)) => {
+                node_manager.supervise(&worker_addr, true).await;
                 // TODO: Use better way to store inlets?
                 node_manager.registry.inlets.insert(
                     alias.clone(),
@@ -254,6 +258,7 @@ impl NodeManagerWorker {
         info!(%alias, "Handling request to delete inlet portal");
         if let Some(inlet_to_delete) = node_manager.registry.inlets.remove(alias) {
             debug!(%alias, "Sucessfully removed inlet from node registry");
+            node_manager.unsupervise(&inlet_to_delete.worker_addr).await;
             let was_stopped = node_manager
                 .tcp_transport
                 .stop_inlet(inlet_to_delete.worker_addr.clone())
@@ -322,7 +327,7 @@ impl NodeManagerWorker {
     pub(super) async fn create_outlet<'a>(
         &mut self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: CreateOutlet<'_>,
     ) -> Result<ResponseBuilder<OutletStatus<'a>>> {
         let mut node_manager = self.node_manager.write().await;
         let CreateOutlet {
@@ -330,7 +335,7 @@ impl NodeManagerWorker {
             worker_addr,
             alias,
             ..
-        } = dec.decode()?;
+        } = body;
         let tcp_addr = tcp_addr.to_string();
         let resource = alias
             .as_deref()
@@ -359,6 +364,7 @@ impl NodeManagerWorker {
 
         Ok(match res {
             Ok(_) => {
+                node_manager.supervise(&worker_addr, false).await;
                 // TODO: Use better way to store outlets?
                 node_manager.registry.outlets.insert(
                     alias.clone(),
@@ -399,6 +405,9 @@ impl NodeManagerWorker {
         info!(%alias, "Handling request to delete outlet portal");
         if let Some(outlet_to_delete) = node_manager.registry.outlets.remove(alias) {
             debug!(%alias, "Successfully removed outlet from node registry");
+            node_manager
+                .unsupervise(&outlet_to_delete.worker_addr)
+                .await;
             let was_stopped = node_manager
                 .tcp_transport
                 .stop_outlet(outlet_to_delete.worker_addr.clone())
@@ -528,6 +537,7 @@ fn replacer(
 
                 // The previous inlet worker needs to be stopped:
                 if let Some(wa) = data.get::<Address>(INLET_WORKER) {
+                    this.unsupervise(&wa).await;
                     let _ = this.tcp_transport.stop_inlet(wa).await;
                 }
 
@@ -537,6 +547,7 @@ fn replacer(
                     .create_inlet_impl(bind, r, access)
                     .await?
                     .0;
+                this.supervise(&wa, true).await;
                 data.put(INLET_WORKER, wa);
 
                 Ok(without_outlet_address(rest))
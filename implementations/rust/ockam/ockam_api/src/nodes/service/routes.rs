@@ -0,0 +1,570 @@
+//! The route table of the node manager.
+
+use either::Either;
+use ockam::Context;
+use ockam_core::api::rpc::{Call, Endpoint, Handle, Handler, Reply};
+use ockam_core::api::{Response, ResponseBuilder};
+use ockam_core::{async_trait, Result};
+
+use super::NodeManagerWorker;
+use crate::nodes::api::*;
+use crate::nodes::models::base::NodeStatus;
+use crate::nodes::models::transport::TransportMode;
+use crate::nodes::models::workers::{WorkerList, WorkerStatus};
+
+/// Implement [`Handle`] for endpoints of the node manager whose handlers
+/// encode their own responses.
+///
+/// Each handler gets the worker and the [`Call`], whose context is the
+/// context of the node manager worker, and returns an encoded response.
+macro_rules! handlers {
+    ($($endpoint:ident($this:ident, $call:ident) $body:block)*) => {
+        $(
+            #[async_trait]
+            impl Handle<$endpoint, Context> for NodeManagerWorker {
+                #[allow(unused_mut)]
+                async fn handle<'a>(
+                    &'a mut self,
+                    mut $call: Call<'a, $endpoint, Context>,
+                ) -> Result<Reply<<$endpoint as Endpoint>::Res<'a>>> {
+                    let $this = self;
+                    Ok(Reply::Encoded($body))
+                }
+            }
+        )*
+    };
+}
+
+impl NodeManagerWorker {
+    /// The route table of the node manager.
+    pub(crate) const HANDLERS: &'static [Handler<Self, Context>] = &[
+        // ==*== Basic node information ==*==
+        // TODO: create, delete, destroy remote nodes
+        Handler::of::<GetNodeStatus>(),
+        Handler::of::<ListWorkers>(),
+        // ==*== Tcp connections and listeners ==*==
+        Handler::of::<ListTcpConnections>(),
+        Handler::of::<CreateTcpConnection>(),
+        Handler::of::<DeleteTcpConnection>(),
+        Handler::of::<ListTcpListeners>(),
+        Handler::of::<CreateTcpListener>(),
+        Handler::of::<DeleteTcpListener>(),
+        // ==*== Credentials ==*==
+        Handler::of::<GetCredential>(),
+        Handler::of::<PresentCredential>(),
+        // ==*== Identity updates ==*==
+        Handler::of::<PushIdentityUpdate>(),
+        Handler::of::<PullIdentityUpdate>(),
+        // ==*== Secure channels ==*==
+        Handler::of::<ListSecureChannels>(),
+        Handler::of::<CreateSecureChannel>(),
+        Handler::of::<DeleteSecureChannel>(),
+        Handler::of::<ShowSecureChannel>(),
+        Handler::of::<ListSecureChannelListeners>(),
+        Handler::of::<CreateSecureChannelListener>(),
+        Handler::of::<DeleteSecureChannelListener>(),
+        // ==*== Services ==*==
+        Handler::of::<ListServices>(),
+        Handler::of::<StartVaultService>(),
+        Handler::of::<StartIdentityService>(),
+        Handler::of::<StartAuthenticatedService>(),
+        Handler::of::<StartUppercaseService>(),
+        Handler::of::<StartEchoerService>(),
+        Handler::of::<StartHopService>(),
+        Handler::of::<StartAuthenticatorService>(),
+        Handler::of::<StartVerifierService>(),
+        Handler::of::<StartCredentialsService>(),
+        Handler::of::<StartOktaIdentityProviderService>(),
+        Handler::of::<StartKafkaConsumerService>(),
+        Handler::of::<StartKafkaProducerService>(),
+        Handler::of::<StartKafkaOutletService>(),
+        // ==*== Forwarders ==*==
+        Handler::of::<CreateForwarder>(),
+        // ==*== Inlets & Outlets ==*==
+        Handler::of::<ListInlets>(),
+        Handler::of::<CreateInlet>(),
+        Handler::of::<ShowInlet>(),
+        Handler::of::<DeleteInlet>(),
+        Handler::of::<ListOutlets>(),
+        Handler::of::<CreateOutlet>(),
+        Handler::of::<ShowOutlet>(),
+        Handler::of::<DeleteOutlet>(),
+        // ==*== Policies ==*==
+        Handler::of::<ListPolicies>(),
+        Handler::of::<GetPolicy>(),
+        Handler::of::<SetPolicy>(),
+        Handler::of::<DeletePolicy>(),
+        // ==*== Spaces ==*==
+        Handler::of::<CreateSpace>(),
+        Handler::of::<ListSpaces>(),
+        Handler::of::<GetSpace>(),
+        Handler::of::<DeleteSpace>(),
+        // ==*== Projects ==*==
+        Handler::of::<CreateProject>(),
+        Handler::of::<ListProjects>(),
+        Handler::of::<GetProject>(),
+        Handler::of::<DeleteProject>(),
+        // ==*== Enroll ==*==
+        Handler::of::<EnrollAuth0>(),
+        Handler::of::<GenerateEnrollmentToken>(),
+        Handler::of::<AuthenticateEnrollmentToken>(),
+        // ==*== Subscriptions ==*==
+        Handler::of::<ActivateSubscription>(),
+        Handler::of::<ListSubscriptions>(),
+        Handler::of::<GetSubscription>(),
+        Handler::of::<UpdateSubscriptionContactInfo>(),
+        Handler::of::<UpdateSubscriptionSpace>(),
+        Handler::of::<Unsubscribe>(),
+        // ==*== Addons ==*==
+        Handler::of::<ListAddons>(),
+        Handler::of::<ConfigureOktaAddon>(),
+        Handler::of::<ConfigureInfluxDBAddon>(),
+        Handler::of::<ConfigureConfluentAddon>(),
+        Handler::of::<DisableAddon>(),
+        // ==*== Messages ==*==
+        Handler::of::<SendMessage>(),
+    ];
+}
+
+fn either_to_vec<A: minicbor::Encode<()>, B: minicbor::Encode<()>>(
+    r: Either<ResponseBuilder<A>, ResponseBuilder<B>>,
+) -> Result<Vec<u8>> {
+    Ok(r.either(ResponseBuilder::to_vec, ResponseBuilder::to_vec)?)
+}
+
+handlers! {
+    // ==*== Basic node information ==*==
+    GetNodeStatus(this, call) {
+        let req = call.request();
+        let workers = call.ctx().list_workers().await?.len() as u32;
+        let node_manager = this.node_manager.read().await;
+        Response::ok(req.id())
+            .body(NodeStatus::new(
+                &node_manager.node_name,
+                "Running",
+                workers,
+                std::process::id() as i32,
+                node_manager.transports.len() as u32,
+            ))
+            .to_vec()?
+    }
+    ListWorkers(_this, call) {
+        let req = call.request();
+        let workers = call.ctx().list_workers().await?;
+        let list = workers
+            .iter()
+            .map(|addr| WorkerStatus::new(addr.address()))
+            .collect();
+        Response::ok(req.id()).body(WorkerList::new(list)).to_vec()?
+    }
+
+    // ==*== Tcp connections and listeners ==*==
+    ListTcpConnections(this, call) {
+        let node_manager = this.node_manager.read().await;
+        this.get_tcp_con_or_list(call.request(), &node_manager.transports, TransportMode::Connect)
+            .to_vec()?
+    }
+    CreateTcpConnection(this, call) {
+        let req = call.request();
+        this.add_transport(req, call.into_body()).await?.to_vec()?
+    }
+    DeleteTcpConnection(this, call) {
+        let req = call.request();
+        this.delete_transport(req, call.into_body()).await?.to_vec()?
+    }
+    ListTcpListeners(this, call) {
+        let node_manager = this.node_manager.read().await;
+        this.get_tcp_con_or_list(call.request(), &node_manager.transports, TransportMode::Listen)
+            .to_vec()?
+    }
+    CreateTcpListener(this, call) {
+        let req = call.request();
+        this.add_transport(req, call.into_body()).await?.to_vec()?
+    }
+    DeleteTcpListener(this, call) {
+        let req = call.request();
+        this.delete_transport(req, call.into_body()).await?.to_vec()?
+    }
+
+    // ==*== Credentials ==*==
+    GetCredential(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        either_to_vec(this.get_credential(req, body, ctx).await?)?
+    }
+    PresentCredential(this, call) {
+        let req = call.request();
+        this.present_credential(req, call.into_body()).await?.to_vec()?
+    }
+
+    // ==*== Identity updates ==*==
+    PushIdentityUpdate(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.push_identity_update(ctx, req, body).await?.to_vec()?
+    }
+    PullIdentityUpdate(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.pull_identity_update(ctx, req, body).await?.to_vec()?
+    }
+
+    // ==*== Secure channels ==*==
+    ListSecureChannels(this, call) {
+        let node_manager = this.node_manager.read().await;
+        this.list_secure_channels(call.request(), &node_manager.registry)
+            .to_vec()?
+    }
+    CreateSecureChannel(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.create_secure_channel(req, body, ctx).await?.to_vec()?
+    }
+    DeleteSecureChannel(this, call) {
+        let req = call.request();
+        this.delete_secure_channel(req, call.into_body()).await?.to_vec()?
+    }
+    ShowSecureChannel(this, call) {
+        let req = call.request();
+        this.show_secure_channel(req, call.into_body()).await?.to_vec()?
+    }
+    ListSecureChannelListeners(this, call) {
+        let node_manager = this.node_manager.read().await;
+        this.list_secure_channel_listener(call.request(), &node_manager.registry)
+            .to_vec()?
+    }
+    CreateSecureChannelListener(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.create_secure_channel_listener(req, body, ctx).await?.to_vec()?
+    }
+    DeleteSecureChannelListener(this, call) {
+        let req = call.request();
+        this.delete_secure_channel_listener(req, call.into_body()).await?.to_vec()?
+    }
+
+    // ==*== Services ==*==
+    ListServices(this, call) {
+        let node_manager = this.node_manager.read().await;
+        this.list_services(call.request(), &node_manager.registry).to_vec()?
+    }
+    StartVaultService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_vault_service(ctx, req, body).await?.to_vec()?
+    }
+    StartIdentityService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_identity_service(ctx, req, body).await?.to_vec()?
+    }
+    StartAuthenticatedService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_authenticated_service(ctx, req, body).await?.to_vec()?
+    }
+    StartUppercaseService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_uppercase_service(ctx, req, body).await?.to_vec()?
+    }
+    StartEchoerService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_echoer_service(ctx, req, body).await?.to_vec()?
+    }
+    StartHopService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_hop_service(ctx, req, body).await?.to_vec()?
+    }
+    StartAuthenticatorService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_authenticator_service(ctx, req, body).await?.to_vec()?
+    }
+    StartVerifierService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_verifier_service(ctx, req, body).await?.to_vec()?
+    }
+    StartCredentialsService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_credentials_service(ctx, req, body).await?.to_vec()?
+    }
+    StartOktaIdentityProviderService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_okta_identity_provider_service(ctx, req, body).await?.to_vec()?
+    }
+    StartKafkaConsumerService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_kafka_consumer_service(ctx, req, body).await?
+    }
+    StartKafkaProducerService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_kafka_producer_service(ctx, req, body).await?
+    }
+    StartKafkaOutletService(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.start_kafka_outlet_service(ctx, req, body).await?
+    }
+
+    // ==*== Forwarders ==*==
+    CreateForwarder(this, call) {
+        let rid = call.request().id();
+        let (ctx, body) = call.into_parts();
+        this.create_forwarder(ctx, rid, body).await?
+    }
+
+    // ==*== Inlets & Outlets ==*==
+    ListInlets(this, call) {
+        let inlets = this.node_manager.read().await.registry.inlets.clone();
+        this.get_inlets(call.request(), &inlets).to_vec()?
+    }
+    CreateInlet(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.create_inlet(req, body, ctx).await?.to_vec()?
+    }
+    ShowInlet(this, call) {
+        let alias = call.param("alias")?;
+        this.show_inlet(call.request(), alias).await?.to_vec()?
+    }
+    DeleteInlet(this, call) {
+        let alias = call.param("alias")?;
+        this.delete_inlet(call.request(), alias).await?.to_vec()?
+    }
+    ListOutlets(this, call) {
+        let outlets = this.node_manager.read().await.registry.outlets.clone();
+        this.get_outlets(call.request(), &outlets).to_vec()?
+    }
+    CreateOutlet(this, call) {
+        let req = call.request();
+        this.create_outlet(req, call.into_body()).await?.to_vec()?
+    }
+    ShowOutlet(this, call) {
+        let alias = call.param("alias")?;
+        this.show_outlet(call.request(), alias).await?.to_vec()?
+    }
+    DeleteOutlet(this, call) {
+        let alias = call.param("alias")?;
+        this.delete_outlet(call.request(), alias).await?.to_vec()?
+    }
+
+    // ==*== Policies ==*==
+    ListPolicies(this, call) {
+        let resource = call.param("resource")?;
+        this.node_manager
+            .read()
+            .await
+            .list_policies(call.request(), resource)
+            .await?
+            .to_vec()?
+    }
+    GetPolicy(this, call) {
+        let resource = call.param("resource")?;
+        let action = call.param("action")?;
+        either_to_vec(
+            this.node_manager
+                .read()
+                .await
+                .get_policy(call.request(), resource, action)
+                .await?,
+        )?
+    }
+    SetPolicy(this, call) {
+        let req = call.request();
+        let resource = call.param("resource")?;
+        let action = call.param("action")?;
+        this.node_manager
+            .read()
+            .await
+            .add_policy(resource, action, req, call.into_body())
+            .await?
+            .to_vec()?
+    }
+    DeletePolicy(this, call) {
+        let resource = call.param("resource")?;
+        let action = call.param("action")?;
+        this.node_manager
+            .read()
+            .await
+            .del_policy(call.request(), resource, action)
+            .await?
+            .to_vec()?
+    }
+
+    // ==*== Spaces ==*==
+    CreateSpace(this, call) {
+        let (ctx, body) = call.into_parts();
+        this.create_space(ctx, body).await?
+    }
+    ListSpaces(this, call) {
+        let (ctx, body) = call.into_parts();
+        this.list_spaces(ctx, body).await?
+    }
+    GetSpace(this, call) {
+        let id = call.param("id")?;
+        let (ctx, body) = call.into_parts();
+        this.get_space(ctx, body, id).await?
+    }
+    DeleteSpace(this, call) {
+        let id = call.param("id")?;
+        let (ctx, body) = call.into_parts();
+        this.delete_space(ctx, body, id).await?
+    }
+
+    // ==*== Projects ==*==
+    CreateProject(this, call) {
+        let space_id = call.param("space_id")?;
+        let (ctx, body) = call.into_parts();
+        this.create_project(ctx, body, space_id).await?
+    }
+    ListProjects(this, call) {
+        let (ctx, body) = call.into_parts();
+        this.list_projects(ctx, body).await?
+    }
+    GetProject(this, call) {
+        let project_id = call.param("project_id")?;
+        let (ctx, body) = call.into_parts();
+        this.get_project(ctx, body, project_id).await?
+    }
+    DeleteProject(this, call) {
+        let space_id = call.param("space_id")?;
+        let project_id = call.param("project_id")?;
+        let (ctx, body) = call.into_parts();
+        this.delete_project(ctx, body, space_id, project_id).await?
+    }
+
+    // ==*== Enroll ==*==
+    EnrollAuth0(this, call) {
+        let (ctx, body) = call.into_parts();
+        this.enroll_auth0(ctx, body).await?
+    }
+    GenerateEnrollmentToken(this, call) {
+        let (ctx, body) = call.into_parts();
+        this.generate_enrollment_token(ctx, body).await?
+    }
+    AuthenticateEnrollmentToken(this, call) {
+        let (ctx, body) = call.into_parts();
+        this.authenticate_enrollment_token(ctx, body).await?
+    }
+
+    // ==*== Subscriptions ==*==
+    ActivateSubscription(this, call) {
+        let (ctx, body) = call.into_parts();
+        this.activate_subscription(ctx, body).await?
+    }
+    ListSubscriptions(this, call) {
+        let (ctx, body) = call.into_parts();
+        this.list_subscriptions(ctx, body).await?
+    }
+    GetSubscription(this, call) {
+        let id = call.param("id")?;
+        let (ctx, body) = call.into_parts();
+        this.get_subscription(ctx, body, id).await?
+    }
+    UpdateSubscriptionContactInfo(this, call) {
+        let id = call.param("id")?;
+        let (ctx, body) = call.into_parts();
+        this.update_subscription_contact_info(ctx, body, id).await?
+    }
+    UpdateSubscriptionSpace(this, call) {
+        let id = call.param("id")?;
+        let (ctx, body) = call.into_parts();
+        this.update_subscription_space(ctx, body, id).await?
+    }
+    Unsubscribe(this, call) {
+        let id = call.param("id")?;
+        let (ctx, body) = call.into_parts();
+        this.unsubscribe(ctx, body, id).await?
+    }
+
+    // ==*== Addons ==*==
+    ListAddons(this, call) {
+        let project_id = call.param("project_id")?;
+        let (ctx, body) = call.into_parts();
+        this.list_addons(ctx, body, project_id).await?
+    }
+    ConfigureOktaAddon(this, call) {
+        let project_id = call.param("project_id")?;
+        let (ctx, body) = call.into_parts();
+        this.configure_addon(ctx, body, project_id, "okta").await?
+    }
+    ConfigureInfluxDBAddon(this, call) {
+        let project_id = call.param("project_id")?;
+        let (ctx, body) = call.into_parts();
+        this.configure_addon(ctx, body, project_id, "influxdb_token_lease_manager")
+            .await?
+    }
+    ConfigureConfluentAddon(this, call) {
+        let project_id = call.param("project_id")?;
+        let (ctx, body) = call.into_parts();
+        this.configure_addon(ctx, body, project_id, "confluent").await?
+    }
+    DisableAddon(this, call) {
+        let project_id = call.param("project_id")?;
+        let addon_id = call.param("addon_id")?;
+        let (ctx, body) = call.into_parts();
+        this.disable_addon(ctx, body, project_id, addon_id).await?
+    }
+
+    // ==*== Messages ==*==
+    SendMessage(this, call) {
+        let req = call.request();
+        let (ctx, body) = call.into_parts();
+        this.send_message(ctx, req, body).await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::NODEMANAGER_ADDR;
+    use minicbor::Decoder;
+    use ockam_core::api::{Request, Response, Status};
+    use ockam_core::route;
+    use ockam_node::api;
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 10_000)]
+    async fn handle_request__route_table__typed_responses_and_errors(
+        context: &mut Context,
+    ) -> Result<()> {
+        let _handle = crate::util::test::start_manager_for_tests(context).await?;
+        let mut client = api::Client::new(route![NODEMANAGER_ADDR], context).await?;
+
+        let workers = client.call::<ListWorkers>(&[], ()).await?;
+        assert!(workers
+            .list
+            .iter()
+            .any(|w| w.addr.as_ref() == NODEMANAGER_ADDR));
+        let status = client.call::<GetNodeStatus>(&[], ()).await?;
+        assert_eq!(status.status.as_ref(), "Running");
+
+        // Path parameters are passed to the handlers
+        assert!(client
+            .call_option::<ShowInlet>(&["unknown"], ())
+            .await?
+            .is_none());
+
+        // Error responses of the route table
+        for (req, expected) in [
+            (Request::get("/node/unknown"), Status::BadRequest),
+            (Request::put("/node/workers"), Status::MethodNotAllowed),
+        ] {
+            let buf: Vec<u8> = context
+                .send_and_receive(route![NODEMANAGER_ADDR], req.to_vec()?)
+                .await?;
+            let res: Response = Decoder::new(&buf).decode()?;
+            assert_eq!(res.status(), Some(expected));
+        }
+
+        context.stop().await
+    }
+}
@@ -11,7 +11,6 @@ use crate::nodes::models::secure_channel::{
 use crate::nodes::registry::Registry;
 use crate::nodes::NodeManager;
 use crate::{create_tcp_session, DefaultAddress};
-use minicbor::Decoder;
 use ockam::identity::TrustEveryonePolicy;
 use ockam::{Address, Result, Route};
 use ockam_core::api::{Request, Response, ResponseBuilder};
@@ -34,7 +33,7 @@ impl NodeManager {
         }
 
         debug!("Credential check: requesting...");
-        self.get_credential_impl(identity, false).await?;
+        self.get_credential_impl(identity, false, &[]).await?;
         debug!("Credential check: got new credential...");
 
         Ok(())
@@ -157,6 +156,7 @@ impl NodeManager {
                     .present_credential(
                         route![sc_addr.clone(), DefaultAddress::CREDENTIALS_SERVICE],
                         provided_credential.as_ref(),
+                        None,
                     )
                     .await?;
                 debug!(%sc_addr, "One-way credential presentation success");
@@ -174,6 +174,7 @@ impl NodeManager {
                         &authorities.public_identities(),
                         self.attributes_storage.clone(),
                         provided_credential.as_ref(),
+                        None,
                     )
                     .await?;
                 debug!(%sc_addr, "Mutual credential presentation success");
@@ -280,7 +281,7 @@ impl NodeManagerWorker {
     pub(super) async fn create_secure_channel<'a>(
         &mut self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: CreateSecureChannelRequest<'_>,
         ctx: &Context,
     ) -> Result<ResponseBuilder<CreateSecureChannelResponse<'a>>> {
         let mut node_manager = self.node_manager.write().await;
@@ -292,7 +293,7 @@ impl NodeManagerWorker {
             identity_name: identity,
             credential_name,
             ..
-        } = dec.decode()?;
+        } = body;
 
         // credential retrieved from request
         info!("Handling request to create a new secure channel: {}", addr);
@@ -336,9 +337,8 @@ impl NodeManagerWorker {
     pub(super) async fn delete_secure_channel<'a>(
         &mut self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: DeleteSecureChannelRequest<'_>,
     ) -> Result<ResponseBuilder<DeleteSecureChannelResponse<'a>>> {
-        let body: DeleteSecureChannelRequest = dec.decode()?;
         let addr = Address::from(body.channel.as_ref());
         info!(%addr, "Handling request to delete secure channel");
         let mut node_manager = self.node_manager.write().await;
@@ -358,10 +358,9 @@ impl NodeManagerWorker {
     pub(super) async fn show_secure_channel<'a>(
         &mut self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: ShowSecureChannelRequest<'_>,
     ) -> Result<ResponseBuilder<ShowSecureChannelResponse<'a>>> {
         let node_manager = self.node_manager.read().await;
-        let body: ShowSecureChannelRequest = dec.decode()?;
 
         let sc_address = Address::from(body.channel.as_ref());
 
@@ -378,7 +377,7 @@ impl NodeManagerWorker {
     pub(super) async fn create_secure_channel_listener(
         &mut self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: CreateSecureChannelListenerRequest<'_>,
         ctx: &Context,
     ) -> Result<ResponseBuilder<()>> {
         let mut node_manager = self.node_manager.write().await;
@@ -388,7 +387,7 @@ impl NodeManagerWorker {
             vault,
             identity,
             ..
-        } = dec.decode()?;
+        } = body;
 
         let authorized_identifiers = match authorized_identifiers {
             Some(ids) => {
@@ -419,9 +418,8 @@ impl NodeManagerWorker {
     pub(super) async fn delete_secure_channel_listener<'a>(
         &mut self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: DeleteSecureChannelListenerRequest<'_>,
     ) -> Result<ResponseBuilder<DeleteSecureChannelListenerResponse<'a>>> {
-        let body: DeleteSecureChannelListenerRequest = dec.decode()?;
         let addr = Address::from(body.addr.as_ref());
         info!(%addr, "Handling request to delete secure channel listener");
         let mut node_manager = self.node_manager.write().await;
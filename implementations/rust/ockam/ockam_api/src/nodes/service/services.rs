@@ -4,16 +4,19 @@ use crate::error::ApiError;
 use crate::hop::Hop;
 use crate::identity::IdentityService;
 use crate::kafka::{
-    KafkaPortalListener, KafkaSecureChannelControllerImpl, KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS,
-    ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS, ORCHESTRATOR_KAFKA_INTERCEPTOR_ADDRESS,
+    AnyTopicAccessControl, KafkaBrokerConnectionOptions, KafkaEncryptionOptions,
+    KafkaOutletBrokerMap, KafkaOutletListener, KafkaPortalListener,
+    KafkaSecureChannelControllerImpl, ProducerTrust, TopicAccessControl, TopicPolicyAccessControl,
+    KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS, ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS,
+    ORCHESTRATOR_KAFKA_INTERCEPTOR_ADDRESS,
 };
 use crate::nodes::connection::Connection;
 use crate::nodes::models::services::{
     ServiceList, ServiceStatus, StartAuthenticatedServiceRequest, StartAuthenticatorRequest,
     StartCredentialsService, StartEchoerServiceRequest, StartHopServiceRequest,
-    StartIdentityServiceRequest, StartKafkaConsumerRequest, StartKafkaProducerRequest,
-    StartOktaIdentityProviderRequest, StartServiceRequest, StartUppercaseServiceRequest,
-    StartVaultServiceRequest, StartVerifierService,
+    StartIdentityServiceRequest, StartKafkaConsumerRequest, StartKafkaOutletRequest,
+    StartKafkaProducerRequest, StartOktaIdentityProviderRequest, StartServiceRequest,
+    StartUppercaseServiceRequest, StartVaultServiceRequest, StartVerifierService,
 };
 use crate::nodes::registry::{
     AuthenticatorServiceInfo, CredentialsServiceInfo, KafkaServiceInfo, KafkaServiceKind, Registry,
@@ -26,19 +29,38 @@ use crate::vault::VaultService;
 use crate::{actions, resources};
 use crate::{local_multiaddr_to_route, DefaultAddress};
 use core::time::Duration;
-use minicbor::Decoder;
 use ockam::{Address, AsyncTryClone, Context, Result};
 use ockam_abac::expr::{and, eq, ident, str};
 use ockam_abac::{Action, Env, Expr, PolicyAccessControl, Resource};
 use ockam_core::api::{bad_request, Error, Request, Response, ResponseBuilder};
 use ockam_core::compat::sync::Arc;
-use ockam_core::{route, AllowAll, IncomingAccessControl};
+use ockam_core::{route, AllowAll, CowStr, IncomingAccessControl, Route};
+use ockam_identity::access_control::IdentityAccessControlBuilder;
 use ockam_identity::authenticated_storage::IdentityAttributeStorageReader;
+use ockam_identity::IdentityIdentifier;
 use ockam_multiaddr::proto::Project;
 use ockam_multiaddr::MultiAddr;
-use ockam_node::WorkerBuilder;
+use ockam_node::{WorkerBuilder, WorkerPool};
+use std::path::Path;
 
 use super::NodeManagerWorker;
+use crate::nodes::SERVICE_POOL_SIZE;
+
+/// Where a kafka consumer or producer reaches the kafka outlet and creates
+/// the forwarder consumers use to reach the producer
+pub(super) enum KafkaServiceRoutes {
+    /// Both are provided by the orchestrator project
+    Project {
+        project_name: String,
+        project_route: MultiAddr,
+    },
+    /// Both are self-hosted, the outlet node runs the kafka outlet service
+    /// and the relay node runs the forwarding service
+    SelfHosted {
+        relay_route: MultiAddr,
+        outlet_route: MultiAddr,
+    },
+}
 
 impl NodeManager {
     pub(super) async fn start_vault_service_impl(
@@ -122,6 +144,34 @@ impl NodeManager {
         Ok(())
     }
 
+    pub(super) async fn start_identity_update_service_impl(&mut self, addr: Address) -> Result<()> {
+        if self.registry.identity_update_services.contains_key(&addr) {
+            return Err(ApiError::generic(
+                "Identity update service exists at this address",
+            ));
+        }
+
+        // Change histories are only exchanged over secure channels, with project members
+        // when the node verifies credentials
+        let access_control: Arc<dyn IncomingAccessControl> = if self.enable_credential_checks {
+            let resource = Resource::new(&addr.to_string());
+            let project_id = self.project_id()?.to_string();
+            self.access_control(&resource, &actions::HANDLE_MESSAGE, Some(project_id))
+                .await?
+        } else {
+            Arc::new(IdentityAccessControlBuilder::new_with_any_id())
+        };
+        self.identity
+            .start_identity_update_worker(addr.clone(), access_control)
+            .await?;
+
+        self.registry
+            .identity_update_services
+            .insert(addr, Default::default());
+
+        Ok(())
+    }
+
     pub(super) async fn start_authenticated_service_impl(
         &mut self,
         ctx: &Context,
@@ -187,13 +237,10 @@ impl NodeManager {
             return Err(ApiError::generic("Echoer service exists at this address"));
         }
 
-        ctx.start_worker(
-            addr.clone(),
-            Echoer,
-            AllowAll, // FIXME: @ac
-            AllowAll,
-        )
-        .await?;
+        WorkerPool::new(addr.clone(), SERVICE_POOL_SIZE, || Echoer)
+            .with_access_control(AllowAll, AllowAll) // FIXME: @ac
+            .start(ctx)
+            .await?;
 
         self.registry
             .echoer_services
@@ -211,13 +258,10 @@ impl NodeManager {
             return Err(ApiError::generic("Hop service exists at this address"));
         }
 
-        ctx.start_worker(
-            addr.clone(),
-            Hop,
-            AllowAll, // FIXME: @ac
-            AllowAll,
-        )
-        .await?;
+        WorkerPool::new(addr.clone(), SERVICE_POOL_SIZE, || Hop)
+            .with_access_control(AllowAll, AllowAll) // FIXME: @ac
+            .start(ctx)
+            .await?;
 
         self.registry.hop_services.insert(addr, Default::default());
 
@@ -236,6 +280,10 @@ impl NodeManager {
         env.put("resource.id", str(r.as_str()));
         env.put("action.id", str(a.as_str()));
         env.put("resource.project_id", str(project_id));
+        env.put(
+            "resource.node_id",
+            str(self.identity.identifier().to_string()),
+        );
         // Check if a policy exists for (resource, action) and if not, then
         // create a default entry:
         if self.policies.get_policy(r, a).await?.is_none() {
@@ -390,6 +438,7 @@ impl NodeManager {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(super) async fn start_okta_identity_provider_service_impl(
         &mut self,
         ctx: &Context,
@@ -397,6 +446,7 @@ impl NodeManager {
         tenant_base_url: &str,
         certificate: &str,
         attributes: &[&str],
+        attribute_rules: &[&str],
         proj: &[u8],
     ) -> Result<()> {
         use crate::nodes::registry::OktaIdentityProviderServiceInfo;
@@ -412,15 +462,17 @@ impl NodeManager {
         let db = self
             .attributes_storage
             .as_identity_attribute_storage_writer();
+        let rules = attribute_rules
+            .iter()
+            .map(|r| r.parse())
+            .collect::<Result<Vec<crate::okta::AttributeRule>>>()?;
         let au =
-            crate::okta::Server::new(proj.to_vec(), db, tenant_base_url, certificate, attributes)?;
-        ctx.start_worker(
-            addr.clone(),
-            au,
-            AllowAll, // FIXME: @ac
-            AllowAll,
-        )
-        .await?;
+            crate::okta::Server::new(proj.to_vec(), db, tenant_base_url, certificate, attributes)?
+                .with_rules(rules);
+        WorkerPool::new(addr.clone(), SERVICE_POOL_SIZE, move || au.clone())
+            .with_access_control(AllowAll, AllowAll) // FIXME: @ac
+            .start(ctx)
+            .await?;
         self.registry
             .okta_identity_provider_services
             .insert(addr, OktaIdentityProviderServiceInfo::default());
@@ -431,52 +483,135 @@ impl NodeManager {
     pub(super) async fn start_kafka_service_impl<'a>(
         &mut self,
         context: &Context,
-        project_name: String,
+        routes: KafkaServiceRoutes,
         local_interceptor_address: Address,
         bind_ip: String,
         server_bootstrap_port: u16,
         brokers_port_range: (u16, u16),
-        project_route_multiaddr: MultiAddr,
         kind: KafkaServiceKind,
+        encrypted_headers: Vec<String>,
+        key_hash_secret: Option<Vec<u8>>,
+        producer_identifiers: Vec<IdentityIdentifier>,
+        consumer_identifiers: Vec<IdentityIdentifier>,
     ) -> Result<()> {
-        let connection = Connection::new(context, &project_route_multiaddr)
-            .with_authorized_identity(self.identity.clone().identifier().clone())
-            .with_timeout(Duration::from_secs(60));
-        let (maybe_tunnel_multiaddr, suffix_address) = self.connect(connection).await?;
+        let (interceptor_route, secure_channel_controller, producer_project_id) = match routes {
+            KafkaServiceRoutes::Project {
+                project_name,
+                project_route,
+            } => {
+                let connection = Connection::new(context, &project_route)
+                    .with_authorized_identity(self.identity.clone().identifier().clone())
+                    .with_timeout(Duration::from_secs(60));
+                let (maybe_tunnel_multiaddr, suffix_address) = self.connect(connection).await?;
+
+                let project_multiaddr = maybe_tunnel_multiaddr.try_with(&suffix_address)?;
+                let project_route = local_multiaddr_to_route(&project_multiaddr)
+                    .ok_or_else(|| ApiError::generic("invalid multiaddr"))?;
+                debug!("project_route: {project_route:?}");
+
+                // override default policy to allow incoming packets from the project
+                let (_addr, identity_identifier) = self.resolve_project(&project_name)?;
+                self.policies
+                    .set_policy(
+                        &resources::INLET,
+                        &actions::HANDLE_MESSAGE,
+                        &eq([
+                            ident("subject.identifier"),
+                            str(identity_identifier.to_string()),
+                        ]),
+                    )
+                    .await?;
+
+                let project_id = self
+                    .projects
+                    .get(&project_name)
+                    .map(|info| info.id.clone())
+                    .ok_or_else(|| {
+                        ApiError::message(format!("project {project_name} not found"))
+                    })?;
+
+                (
+                    route![
+                        local_interceptor_address.clone(),
+                        project_route.clone(),
+                        ORCHESTRATOR_KAFKA_INTERCEPTOR_ADDRESS,
+                    ],
+                    KafkaSecureChannelControllerImpl::new(self.identity.clone(), project_route),
+                    Some(project_id),
+                )
+            }
+            KafkaServiceRoutes::SelfHosted {
+                relay_route,
+                outlet_route,
+            } => {
+                let outlet_route = self.resolve_kafka_route(context, &outlet_route).await?;
+                let relay_route = self.resolve_kafka_route(context, &relay_route).await?;
+                debug!("outlet_route: {outlet_route:?}");
+                debug!("relay_route: {relay_route:?}");
+
+                //the kafka outlet exposes the brokers directly, there is no
+                //orchestrator interceptor in between
+                let project_id = if self.enable_credential_checks {
+                    Some(self.project_id()?.to_string())
+                } else {
+                    None
+                };
+                (
+                    route![local_interceptor_address.clone(), outlet_route],
+                    KafkaSecureChannelControllerImpl::new_with_relay(
+                        self.identity.clone(),
+                        relay_route,
+                    ),
+                    project_id,
+                )
+            }
+        };
 
-        let project_multiaddr = maybe_tunnel_multiaddr.try_with(&suffix_address)?;
-        let project_route = local_multiaddr_to_route(&project_multiaddr)
-            .ok_or_else(|| ApiError::generic("invalid multiaddr"))?;
+        //producers only serve data keys to the given consumers, or else to the
+        //consumers authorized by the policy for the topic of the data key
+        let data_key_access_control = match kind {
+            KafkaServiceKind::Producer => Some(
+                self.kafka_producer_access_control(
+                    producer_project_id.clone(),
+                    &consumer_identifiers,
+                )
+                .await?,
+            ),
+            _ => None,
+        };
+
+        //consumers only retrieve data keys from the given producers, or else from
+        //producers presenting a credential for the project
+        let producer_trust = if !producer_identifiers.is_empty() {
+            ProducerTrust::Identifiers(producer_identifiers)
+        } else {
+            match (&producer_project_id, self.authorities()) {
+                (Some(project_id), Ok(authorities)) => ProducerTrust::Credential {
+                    authorities: authorities.public_identities(),
+                    attributes_storage: self.attributes_storage.clone(),
+                    project_id: project_id.clone(),
+                },
+                _ => {
+                    if let KafkaServiceKind::Consumer = kind {
+                        return Err(ApiError::generic(
+                            "consumers must be given the identifiers of the producers \
+                            when the node doesn't verify credentials",
+                        ));
+                    }
+                    ProducerTrust::default()
+                }
+            }
+        };
+        let secure_channel_controller =
+            secure_channel_controller.with_producer_trust(producer_trust);
 
         let bootstrap_address_route = route![
-            local_interceptor_address.clone(),
-            project_route.clone(),
-            ORCHESTRATOR_KAFKA_INTERCEPTOR_ADDRESS,
+            interceptor_route.clone(),
             ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS
         ];
 
-        let interceptor_route = route![
-            local_interceptor_address.clone(),
-            project_route.clone(),
-            ORCHESTRATOR_KAFKA_INTERCEPTOR_ADDRESS,
-        ];
-
         debug!("bootstrap_address_route: {bootstrap_address_route:?}");
         debug!("interceptor_route: {interceptor_route:?}");
-        debug!("project_route: {project_route:?}");
-
-        // override default policy to allow incoming packets from the project
-        let (_addr, identity_identifier) = self.resolve_project(&project_name)?;
-        self.policies
-            .set_policy(
-                &resources::INLET,
-                &actions::HANDLE_MESSAGE,
-                &eq([
-                    ident("subject.identifier"),
-                    str(identity_identifier.to_string()),
-                ]),
-            )
-            .await?;
 
         self.tcp_transport
             .create_inlet(
@@ -486,17 +621,25 @@ impl NodeManager {
             )
             .await?;
 
-        let secure_channel_controller =
-            KafkaSecureChannelControllerImpl::new(self.identity.clone(), project_route);
+        let encryption_options = KafkaEncryptionOptions {
+            key_hash_secret,
+            encrypted_headers,
+        };
 
-        if let KafkaServiceKind::Consumer = kind {
+        //consumers retrieve data keys from the producer
+        if let Some(access_control) = data_key_access_control {
             secure_channel_controller
-                .create_consumer_listener(context)
+                .create_producer_listener(context, access_control)
                 .await?;
 
+            let authorized_identifiers = if consumer_identifiers.is_empty() {
+                None
+            } else {
+                Some(consumer_identifiers)
+            };
             self.create_secure_channel_listener_impl(
                 Address::from_string(KAFKA_SECURE_CHANNEL_LISTENER_ADDRESS),
-                None,
+                authorized_identifiers,
                 None,
                 None,
                 context,
@@ -507,6 +650,7 @@ impl NodeManager {
         KafkaPortalListener::create(
             context,
             secure_channel_controller.into_trait(),
+            encryption_options,
             interceptor_route,
             local_interceptor_address.clone(),
             bind_ip,
@@ -520,6 +664,106 @@ impl NodeManager {
             .insert(local_interceptor_address, KafkaServiceInfo::new(kind));
         Ok(())
     }
+
+    /// Authorizes the consumers retrieving the data keys of a producer: the
+    /// given consumers for every topic, or else the consumers authorized by
+    /// the `kafka-producer` policy, which can check the `resource.topic`
+    /// attribute. By default every member of the project is authorized.
+    /// Without a project the policy must be set explicitly, so that data keys
+    /// are never served to whoever can reach the producer.
+    async fn kafka_producer_access_control(
+        &self,
+        project_id: Option<String>,
+        consumer_identifiers: &[IdentityIdentifier],
+    ) -> Result<Arc<dyn TopicAccessControl>> {
+        if !consumer_identifiers.is_empty() {
+            return Ok(Arc::new(AnyTopicAccessControl(Arc::new(
+                IdentityAccessControlBuilder::new_with_ids(consumer_identifiers),
+            ))));
+        }
+
+        let r = &resources::KAFKA_PRODUCER;
+        let a = &actions::HANDLE_MESSAGE;
+        let mut env = Env::new();
+        env.put("resource.id", str(r.as_str()));
+        env.put("action.id", str(a.as_str()));
+        env.put(
+            "resource.node_id",
+            str(self.identity.identifier().to_string()),
+        );
+        let policy = self.policies.get_policy(r, a).await?;
+        match project_id {
+            Some(project_id) => {
+                env.put("resource.project_id", str(project_id));
+                if policy.is_none() {
+                    let rule = eq([ident("resource.project_id"), ident("subject.project_id")]);
+                    self.policies.set_policy(r, a, &rule).await?
+                }
+            }
+            None => {
+                if policy.is_none() {
+                    return Err(ApiError::generic(
+                        "producers must be given the identifiers of the consumers, or a policy \
+                        for the kafka-producer resource, when the node doesn't verify credentials",
+                    ));
+                }
+            }
+        }
+        Ok(Arc::new(TopicPolicyAccessControl::new(
+            self.policies.clone(),
+            self.attributes_storage.clone(),
+            r.clone(),
+            a.clone(),
+            env,
+        )))
+    }
+
+    /// Connects to a self-hosted node, returns the route to it
+    async fn resolve_kafka_route(&mut self, context: &Context, addr: &MultiAddr) -> Result<Route> {
+        let connection = Connection::new(context, addr).with_timeout(Duration::from_secs(60));
+        let (maybe_tunnel_multiaddr, suffix_address) = self.connect(connection).await?;
+        let multiaddr = maybe_tunnel_multiaddr.try_with(&suffix_address)?;
+        local_multiaddr_to_route(&multiaddr).ok_or_else(|| ApiError::generic("invalid multiaddr"))
+    }
+
+    pub(super) async fn start_kafka_outlet_service_impl(
+        &mut self,
+        context: &Context,
+        bootstrap_server: String,
+        options: KafkaBrokerConnectionOptions,
+    ) -> Result<()> {
+        let bootstrap_address = Address::from_string(ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS);
+        if self
+            .registry
+            .kafka_services
+            .contains_key(&bootstrap_address)
+        {
+            return Err(ApiError::generic("Kafka outlet already started"));
+        }
+
+        let project_id = if self.enable_credential_checks {
+            Some(self.project_id()?.to_string())
+        } else {
+            None
+        };
+        let access_control = self
+            .access_control(&resources::OUTLET, &actions::HANDLE_MESSAGE, project_id)
+            .await?;
+
+        KafkaOutletListener::create(
+            context,
+            bootstrap_address.clone(),
+            bootstrap_server,
+            KafkaOutletBrokerMap::new(options, access_control),
+        )
+        .await?;
+
+        self.registry.kafka_services.insert(
+            bootstrap_address,
+            KafkaServiceInfo::new(KafkaServiceKind::Outlet),
+        );
+        Ok(())
+    }
 }
 
 impl NodeManagerWorker {
@@ -527,10 +771,9 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &Context,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        req_body: StartVaultServiceRequest<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
-        let req_body: StartVaultServiceRequest = dec.decode()?;
         let addr = req_body.addr.to_string().into();
         node_manager.start_vault_service_impl(ctx, addr).await?;
         Ok(Response::ok(req.id()))
@@ -540,10 +783,9 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &Context,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        req_body: StartIdentityServiceRequest<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
-        let req_body: StartIdentityServiceRequest = dec.decode()?;
         let addr = req_body.addr.to_string().into();
         node_manager.start_identity_service_impl(ctx, addr).await?;
         Ok(Response::ok(req.id()))
@@ -553,10 +795,9 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &Context,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        req_body: StartAuthenticatedServiceRequest<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
-        let req_body: StartAuthenticatedServiceRequest = dec.decode()?;
         let addr = req_body.addr.to_string().into();
         node_manager
             .start_authenticated_service_impl(ctx, addr)
@@ -568,10 +809,9 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &Context,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        req_body: StartUppercaseServiceRequest<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
-        let req_body: StartUppercaseServiceRequest = dec.decode()?;
         let addr = req_body.addr.to_string().into();
         node_manager.start_uppercase_service_impl(ctx, addr).await?;
         Ok(Response::ok(req.id()))
@@ -581,10 +821,9 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &Context,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        req_body: StartEchoerServiceRequest<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
-        let req_body: StartEchoerServiceRequest = dec.decode()?;
         let addr = req_body.addr.to_string().into();
         node_manager.start_echoer_service_impl(ctx, addr).await?;
         Ok(Response::ok(req.id()))
@@ -594,10 +833,9 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &Context,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        req_body: StartHopServiceRequest<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
-        let req_body: StartHopServiceRequest = dec.decode()?;
         let addr = req_body.addr.to_string().into();
         node_manager.start_hop_service_impl(ctx, addr).await?;
         Ok(Response::ok(req.id()))
@@ -608,7 +846,7 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &Context,
         req: &'a Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: StartAuthenticatorRequest<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
         #[cfg(not(feature = "direct-authenticator"))]
@@ -616,7 +854,6 @@ impl NodeManagerWorker {
 
         #[cfg(feature = "direct-authenticator")]
         {
-            let body: StartAuthenticatorRequest = dec.decode()?;
             let addr: Address = body.address().into();
 
             node_manager
@@ -647,10 +884,9 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &Context,
         req: &'a Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: StartOktaIdentityProviderRequest<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
-        let body: StartOktaIdentityProviderRequest = dec.decode()?;
         let addr: Address = body.address().into();
         node_manager
             .start_okta_identity_provider_service_impl(
@@ -659,6 +895,7 @@ impl NodeManagerWorker {
                 body.tenant_base_url(),
                 body.certificate(),
                 body.attributes(),
+                body.attribute_rules(),
                 body.project(),
             )
             .await?;
@@ -669,23 +906,21 @@ impl NodeManagerWorker {
         &mut self,
         ctx: &Context,
         req: &'a Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: StartVerifierService<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
-        let body: StartVerifierService = dec.decode()?;
         let addr: Address = body.address().into();
 
         if node_manager.registry.verifier_services.contains_key(&addr) {
             return Err(ApiError::generic("Verifier service exists at this address"));
         }
 
-        let vs = crate::verifier::Verifier::new(node_manager.vault.clone());
-        ctx.start_worker(
-            addr.clone(),
-            vs,
-            AllowAll, // FIXME: @ac
-            AllowAll,
-        )
+        let vault = node_manager.vault.clone();
+        WorkerPool::new(addr.clone(), SERVICE_POOL_SIZE, move || {
+            crate::verifier::Verifier::new(vault.clone())
+        })
+        .with_access_control(AllowAll, AllowAll) // FIXME: @ac
+        .start(ctx)
         .await?;
 
         node_manager
@@ -700,10 +935,9 @@ impl NodeManagerWorker {
         &mut self,
         _ctx: &Context,
         req: &'a Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: StartCredentialsService<'_>,
     ) -> Result<ResponseBuilder> {
         let mut node_manager = self.node_manager.write().await;
-        let body: StartCredentialsService = dec.decode()?;
         let addr: Address = body.address().into();
         let oneway = body.oneway();
 
@@ -718,16 +952,24 @@ impl NodeManagerWorker {
         &mut self,
         context: &Context,
         req: &'a Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: StartServiceRequest<'_, StartKafkaConsumerRequest<'_>>,
     ) -> Result<Vec<u8>> {
         let mut node_manager = self.node_manager.write().await;
-        let body: StartServiceRequest<StartKafkaConsumerRequest> = dec.decode()?;
         let listener_address: Address = body.address().into();
         let body_req = body.request();
-
-        let project_route = body_req.project_route().to_string().parse()?;
-        let project_name = match self.extract_project(req, &project_route) {
-            Ok(project_name) => project_name,
+        let producer_identifiers = body_req
+            .producer_identifiers()
+            .iter()
+            .map(|identifier| IdentityIdentifier::try_from(identifier.as_ref()))
+            .collect::<Result<Vec<IdentityIdentifier>>>()?;
+
+        let routes = match self.extract_kafka_routes(
+            req,
+            body_req.project_route(),
+            body_req.relay_route(),
+            body_req.outlet_route(),
+        )? {
+            Ok(routes) => routes,
             Err(err) => {
                 return Ok(err.to_vec()?);
             }
@@ -736,13 +978,20 @@ impl NodeManagerWorker {
         node_manager
             .start_kafka_service_impl(
                 context,
-                project_name,
+                routes,
                 listener_address,
                 body_req.bootstrap_server_ip().to_string(),
                 body_req.bootstrap_server_port(),
                 body_req.brokers_port_range(),
-                project_route,
                 KafkaServiceKind::Consumer,
+                body_req
+                    .encrypted_headers()
+                    .iter()
+                    .map(|header| header.to_string())
+                    .collect(),
+                None,
+                producer_identifiers,
+                Vec::new(),
             )
             .await?;
 
@@ -753,16 +1002,34 @@ impl NodeManagerWorker {
         &mut self,
         context: &Context,
         req: &'a Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: StartServiceRequest<'_, StartKafkaProducerRequest<'_>>,
     ) -> Result<Vec<u8>> {
         let mut node_manager = self.node_manager.write().await;
-        let body: StartServiceRequest<StartKafkaProducerRequest> = dec.decode()?;
         let listener_address: Address = body.address().into();
         let body_req = body.request();
+        let consumer_identifiers = body_req
+            .consumer_identifiers()
+            .iter()
+            .map(|identifier| IdentityIdentifier::try_from(identifier.as_ref()))
+            .collect::<Result<Vec<IdentityIdentifier>>>()?;
+
+        let key_hash_secret = match body_req.record_key_secret_file() {
+            Some(path) => {
+                match KafkaEncryptionOptions::read_key_hash_secret(Path::new(path.as_ref())) {
+                    Ok(secret) => Some(secret),
+                    Err(err) => return Ok(bad_request(req, &err.to_string()).to_vec()?),
+                }
+            }
+            None => None,
+        };
 
-        let project_route = body_req.project_route().to_string().parse()?;
-        let project_name = match self.extract_project(req, &project_route) {
-            Ok(project_name) => project_name,
+        let routes = match self.extract_kafka_routes(
+            req,
+            body_req.project_route(),
+            body_req.relay_route(),
+            body_req.outlet_route(),
+        )? {
+            Ok(routes) => routes,
             Err(err) => {
                 return Ok(err.to_vec()?);
             }
@@ -771,28 +1038,114 @@ impl NodeManagerWorker {
         node_manager
             .start_kafka_service_impl(
                 context,
-                project_name,
+                routes,
                 listener_address,
                 body_req.bootstrap_server_ip().to_string(),
                 body_req.bootstrap_server_port(),
                 body_req.brokers_port_range(),
-                body_req.project_route().to_string().parse()?,
                 KafkaServiceKind::Producer,
+                body_req
+                    .encrypted_headers()
+                    .iter()
+                    .map(|header| header.to_string())
+                    .collect(),
+                key_hash_secret,
+                Vec::new(),
+                consumer_identifiers,
+            )
+            .await?;
+
+        Ok(Response::ok(req.id()).to_vec()?)
+    }
+
+    pub(super) async fn start_kafka_outlet_service<'a>(
+        &mut self,
+        context: &Context,
+        req: &'a Request<'_>,
+        body: StartServiceRequest<'_, StartKafkaOutletRequest<'_>>,
+    ) -> Result<Vec<u8>> {
+        let mut node_manager = self.node_manager.write().await;
+        let body_req = body.request();
+
+        let mut options = KafkaBrokerConnectionOptions::new();
+        if body_req.tls() {
+            let ca_certificate = body_req
+                .tls_ca_certificate()
+                .map(|path| Path::new(path.as_ref()));
+            options = match options.with_tls(ca_certificate) {
+                Ok(options) => options,
+                Err(err) => return Ok(bad_request(req, &err.to_string()).to_vec()?),
+            };
+        }
+        match (body_req.sasl_username(), body_req.sasl_password_file()) {
+            (Some(username), Some(password_file)) => {
+                let mechanism = body_req
+                    .sasl_mechanism()
+                    .map(|mechanism| mechanism.as_ref())
+                    .unwrap_or("PLAIN");
+                options = match options.with_sasl(
+                    mechanism,
+                    username.to_string(),
+                    Path::new(password_file.as_ref()),
+                ) {
+                    Ok(options) => options,
+                    Err(err) => return Ok(bad_request(req, &err.to_string()).to_vec()?),
+                };
+            }
+            (None, None) => {}
+            _ => {
+                return Ok(
+                    bad_request(req, "both sasl username and password file are required")
+                        .to_vec()?,
+                );
+            }
+        }
+
+        node_manager
+            .start_kafka_outlet_service_impl(
+                context,
+                body_req.bootstrap_server().to_string(),
+                options,
             )
             .await?;
 
         Ok(Response::ok(req.id()).to_vec()?)
     }
 
-    fn extract_project<'a>(
+    /// Either the project route, or both the relay and outlet routes are expected
+    fn extract_kafka_routes<'a>(
         &self,
         req: &'a Request<'_>,
-        project_route_multiaddr: &MultiAddr,
-    ) -> std::result::Result<String, ResponseBuilder<Error<'a>>> {
-        project_route_multiaddr
-            .first()
-            .and_then(|value| value.cast::<Project>().map(|p| p.to_string()))
-            .ok_or_else(|| bad_request(req, "invalid project route"))
+        project_route: Option<&CowStr>,
+        relay_route: Option<&CowStr>,
+        outlet_route: Option<&CowStr>,
+    ) -> Result<std::result::Result<KafkaServiceRoutes, ResponseBuilder<Error<'a>>>> {
+        match (project_route, relay_route, outlet_route) {
+            (Some(project_route), None, None) => {
+                let project_route: MultiAddr = project_route.to_string().parse()?;
+                let project_name = match project_route
+                    .first()
+                    .and_then(|value| value.cast::<Project>().map(|p| p.to_string()))
+                {
+                    Some(project_name) => project_name,
+                    None => return Ok(Err(bad_request(req, "invalid project route"))),
+                };
+                Ok(Ok(KafkaServiceRoutes::Project {
+                    project_name,
+                    project_route,
+                }))
+            }
+            (None, Some(relay_route), Some(outlet_route)) => {
+                Ok(Ok(KafkaServiceRoutes::SelfHosted {
+                    relay_route: relay_route.to_string().parse()?,
+                    outlet_route: outlet_route.to_string().parse()?,
+                }))
+            }
+            _ => Ok(Err(bad_request(
+                req,
+                "either a project route or both relay and outlet routes are required",
+            ))),
+        }
     }
 
     pub(super) fn list_services<'a>(
@@ -837,6 +1190,12 @@ impl NodeManagerWorker {
                 DefaultAddress::HOP_SERVICE,
             ))
         });
+        registry.identity_update_services.keys().for_each(|addr| {
+            list.push(ServiceStatus::new(
+                addr.address(),
+                DefaultAddress::IDENTITY_UPDATE_SERVICE,
+            ))
+        });
         registry.verifier_services.keys().for_each(|addr| {
             list.push(ServiceStatus::new(addr.address(), DefaultAddress::VERIFIER))
         });
@@ -852,6 +1211,7 @@ impl NodeManagerWorker {
                 match info.kind() {
                     KafkaServiceKind::Consumer => "kafka-consumer",
                     KafkaServiceKind::Producer => "kafka-producer",
+                    KafkaServiceKind::Outlet => "kafka-outlet",
                 },
             ))
         });
@@ -2,7 +2,6 @@ use crate::nodes::models::transport::{
     CreateTransport, DeleteTransport, TransportList, TransportMode, TransportStatus,
 };
 use crate::nodes::service::{random_alias, Alias, Transports};
-use minicbor::Decoder;
 use ockam::Result;
 use ockam_core::api::{Request, Response, ResponseBuilder};
 use ockam_transport_tcp::{TcpConnectionTrustOptions, TcpListenerTrustOptions};
@@ -36,10 +35,10 @@ impl NodeManagerWorker {
     pub(super) async fn add_transport<'a>(
         &self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: CreateTransport<'_>,
     ) -> Result<ResponseBuilder<TransportStatus<'a>>> {
         let mut node_manager = self.node_manager.write().await;
-        let CreateTransport { tt, tm, addr, .. } = dec.decode()?;
+        let CreateTransport { tt, tm, addr, .. } = body;
 
         use {super::TransportType::*, TransportMode::*};
 
@@ -97,10 +96,9 @@ impl NodeManagerWorker {
     pub(super) async fn delete_transport(
         &self,
         req: &Request<'_>,
-        dec: &mut Decoder<'_>,
+        body: DeleteTransport<'_>,
     ) -> Result<ResponseBuilder<()>> {
         let mut node_manager = self.node_manager.write().await;
-        let body: DeleteTransport = dec.decode()?;
         info!("Handling request to delete transport: {}", body.tid);
 
         let tid: Alias = body.tid.to_string();
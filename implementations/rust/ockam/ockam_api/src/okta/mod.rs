@@ -1,4 +1,9 @@
-use crate::error::ApiError;
+mod oidc;
+mod rules;
+
+pub use oidc::{Claims, OidcProvider, DEFAULT_CACHE_TTL};
+pub use rules::AttributeRule;
+
 use core::str;
 use minicbor::Decoder;
 use ockam::identity::credential::Timestamp;
@@ -9,16 +14,17 @@ use ockam_core::{self, Result, Routed, Worker};
 use ockam_identity::authenticated_storage::{AttributesEntry, IdentityAttributeStorageWriter};
 use ockam_identity::{IdentityIdentifier, IdentitySecureChannelLocalInfo};
 use ockam_node::Context;
-use reqwest::StatusCode;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use tracing::trace;
 
+/// Identity provider service: project members enroll with a token of the provider
+/// and get the attributes mapped from its claims
+#[derive(Clone)]
 pub struct Server {
     project: Vec<u8>,
     store: Arc<dyn IdentityAttributeStorageWriter>,
-    tenant_base_url: String,
-    certificate: reqwest::Certificate,
-    attributes: Vec<String>,
+    provider: OidcProvider,
+    rules: Vec<AttributeRule>,
 }
 
 #[ockam_core::worker]
@@ -40,6 +46,8 @@ impl Worker for Server {
 }
 
 impl Server {
+    /// Create an Okta identity provider, the given attributes are copied from the
+    /// claims of the Okta user
     pub fn new(
         project: Vec<u8>,
         store: Arc<dyn IdentityAttributeStorageWriter>,
@@ -47,15 +55,31 @@ impl Server {
         certificate: &str,
         attributes: &[&str],
     ) -> Result<Self> {
-        let certificate = reqwest::Certificate::from_pem(certificate.as_bytes())
-            .map_err(|err| ApiError::generic(&err.to_string()))?;
-        Ok(Server {
+        let provider = OidcProvider::new(tenant_base_url, Some(certificate))?
+            .with_userinfo_endpoint(format!("{tenant_base_url}/v1/userinfo"));
+        let rules = attributes.iter().map(|a| AttributeRule::copy(a)).collect();
+        Ok(Self::new_oidc(project, store, provider, rules))
+    }
+
+    /// Create an identity provider for any OpenID Connect provider
+    pub fn new_oidc(
+        project: Vec<u8>,
+        store: Arc<dyn IdentityAttributeStorageWriter>,
+        provider: OidcProvider,
+        rules: Vec<AttributeRule>,
+    ) -> Self {
+        Server {
             project,
             store,
-            tenant_base_url: tenant_base_url.to_string(),
-            certificate,
-            attributes: attributes.iter().map(|s| s.to_string()).collect(),
-        })
+            provider,
+            rules,
+        }
+    }
+
+    /// Map more claims to attributes
+    pub fn with_rules(mut self, rules: impl IntoIterator<Item = AttributeRule>) -> Self {
+        self.rules.extend(rules);
+        self
     }
 
     async fn on_request(&mut self, from: &IdentityIdentifier, data: &[u8]) -> Result<Vec<u8>> {
@@ -89,7 +113,7 @@ impl Server {
                         let entry = AttributesEntry::new(
                             attrs
                                 .into_iter()
-                                .map(|(k, v)| (k, v.as_bytes().to_vec()))
+                                .map(|(k, v)| (k, v.into_bytes()))
                                 .chain(
                                     [(
                                         crate::authenticator::direct::PROJECT_ID.to_owned(),
@@ -115,39 +139,14 @@ impl Server {
         Ok(res)
     }
 
-    async fn check_token(&mut self, token: &str) -> Result<Option<HashMap<String, String>>> {
-        let client = reqwest::ClientBuilder::new()
-            .tls_built_in_root_certs(false)
-            .add_root_certificate(self.certificate.clone())
-            .build()
-            .map_err(|err| ApiError::generic(&err.to_string()))?;
-        let res = client
-            .get(format!("{}/v1/userinfo", &self.tenant_base_url))
-            .header("Authorization", format!("Bearer {token}"))
-            .send()
-            .await;
-        if let Ok(res) = res {
-            match res.status() {
-                StatusCode::OK => {
-                    //TODO: Must have a configured list of fields to extract from the response,
-                    //      and add these to the credentia
-                    let doc: HashMap<String, serde_json::Value> = res
-                        .json()
-                        .await
-                        .map_err(|_err| ApiError::generic("Failed to authenticate with Okta"))?;
-                    debug!("userinfo received: {doc:?}");
-                    let mut custom_attrs = HashMap::new();
-                    for a in self.attributes.iter() {
-                        if let Some(v) = doc.get(a).and_then(|v| v.as_str()) {
-                            custom_attrs.insert(a.to_owned(), v.to_string());
-                        }
-                    }
-                    Ok(Some(custom_attrs))
-                }
-                _ => Ok(None),
+    async fn check_token(&mut self, token: &str) -> Result<Option<BTreeMap<String, String>>> {
+        match self.provider.validate(token).await {
+            Ok(Some(claims)) => Ok(Some(AttributeRule::apply(&self.rules, &claims))),
+            Ok(None) => Ok(None),
+            Err(err) => {
+                warn!("Failed to validate token: {err}");
+                Ok(None)
             }
-        } else {
-            Ok(None)
         }
     }
 }
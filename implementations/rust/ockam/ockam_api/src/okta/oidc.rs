@@ -0,0 +1,371 @@
+//! Validation of the tokens of an OpenID Connect provider
+
+use crate::error::ApiError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ockam_core::Result;
+use reqwest::StatusCode;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Claims of a validated token
+pub type Claims = Map<String, Value>;
+
+/// How long the provider keys and the userinfo responses are cached by default
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Minimum delay between two fetches of the provider keys when a token is signed
+/// with an unknown key
+const MIN_JWKS_REFRESH: Duration = Duration::from_secs(30);
+
+/// An OpenID Connect provider.
+///
+/// Tokens in the JWT format are validated locally with the keys published by the
+/// provider, which are fetched once and cached. Other tokens, and JWTs signed by keys
+/// which are not published, are checked against the userinfo endpoint when there is one,
+/// the responses are cached until the cache expires. Userinfo responses don't tell which
+/// audience a token was issued for, so only JWTs are accepted when an audience is required.
+/// A clone of a provider has its own caches.
+#[derive(Clone)]
+pub struct OidcProvider {
+    issuer: String,
+    audience: Option<String>,
+    jwks_uri: Option<String>,
+    userinfo_endpoint: Option<String>,
+    discovered: bool,
+    client: reqwest::Client,
+    cache_ttl: Duration,
+    jwks: Option<(Instant, Vec<Jwk>)>,
+    userinfo: HashMap<String, (Instant, Claims)>,
+}
+
+impl OidcProvider {
+    /// Create a provider for the given issuer URL. When a certificate is given it is
+    /// the only root certificate trusted to connect to the provider.
+    pub fn new(issuer: &str, certificate: Option<&str>) -> Result<Self> {
+        let builder = reqwest::ClientBuilder::new();
+        let builder = match certificate {
+            Some(certificate) => {
+                let certificate = reqwest::Certificate::from_pem(certificate.as_bytes())
+                    .map_err(|err| ApiError::generic(&err.to_string()))?;
+                builder
+                    .tls_built_in_root_certs(false)
+                    .add_root_certificate(certificate)
+            }
+            None => builder,
+        };
+        let client = builder
+            .build()
+            .map_err(|err| ApiError::generic(&err.to_string()))?;
+        Ok(OidcProvider {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            audience: None,
+            jwks_uri: None,
+            userinfo_endpoint: None,
+            discovered: false,
+            client,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            jwks: None,
+            userinfo: HashMap::new(),
+        })
+    }
+
+    /// Only accept tokens issued for this audience
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Use these keys instead of the ones of the discovery document
+    pub fn with_jwks_uri(mut self, jwks_uri: impl Into<String>) -> Self {
+        self.jwks_uri = Some(jwks_uri.into());
+        self
+    }
+
+    /// Use this userinfo endpoint instead of the one of the discovery document
+    pub fn with_userinfo_endpoint(mut self, userinfo_endpoint: impl Into<String>) -> Self {
+        self.userinfo_endpoint = Some(userinfo_endpoint.into());
+        self
+    }
+
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    /// Validate a token and return its claims, `None` if the token is not valid
+    pub async fn validate(&mut self, token: &str) -> Result<Option<Claims>> {
+        self.discover().await;
+        if let Some(jwt) = Jwt::parse(token) {
+            match self.validate_jwt(&jwt).await {
+                Ok(Some(claims)) => return Ok(Some(claims)),
+                Ok(None) => return Ok(None),
+                // The token may still be known by the userinfo endpoint
+                Err(err) if self.userinfo_endpoint.is_some() && self.audience.is_none() => {
+                    debug!("Local token validation failed: {err}")
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if self.audience.is_some() {
+            debug!("Only JWTs can be checked for the required audience");
+            return Ok(None);
+        }
+        self.check_userinfo(token).await
+    }
+
+    /// Fetch the discovery document of the provider once, to find the endpoints which
+    /// were not configured
+    async fn discover(&mut self) {
+        if self.discovered || (self.jwks_uri.is_some() && self.userinfo_endpoint.is_some()) {
+            return;
+        }
+        let url = format!("{}/.well-known/openid-configuration", self.issuer);
+        match self.get_json(&url, None).await {
+            Ok(Some(doc)) => {
+                let endpoint = |name: &str| doc.get(name).and_then(Value::as_str).map(String::from);
+                self.jwks_uri = self.jwks_uri.take().or_else(|| endpoint("jwks_uri"));
+                self.userinfo_endpoint = self
+                    .userinfo_endpoint
+                    .take()
+                    .or_else(|| endpoint("userinfo_endpoint"));
+                self.discovered = true;
+            }
+            Ok(None) => {
+                warn!("No discovery document at {url}");
+                self.discovered = true;
+            }
+            Err(err) => warn!("Failed to fetch the discovery document at {url}: {err}"),
+        }
+    }
+
+    /// Validate a JWT locally. Errors mean that the token couldn't be checked, e.g.
+    /// because its key is not published.
+    async fn validate_jwt(&mut self, jwt: &Jwt<'_>) -> Result<Option<Claims>> {
+        let key = match self.find_key(jwt).await? {
+            Some(key) => key,
+            None => return Err(ApiError::generic("token signed with an unknown key")),
+        };
+        if !key.verify(&jwt.alg, jwt.signing_input.as_bytes(), &jwt.signature) {
+            return Ok(None);
+        }
+
+        let claims = &jwt.claims;
+        if claims.get("iss").and_then(Value::as_str) != Some(self.issuer.as_str()) {
+            debug!("Token issuer doesn't match {}", self.issuer);
+            return Ok(None);
+        }
+        if let Some(audience) = &self.audience {
+            let valid = match claims.get("aud") {
+                Some(Value::String(aud)) => aud == audience,
+                Some(Value::Array(aud)) => aud.iter().any(|a| a.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !valid {
+                debug!("Token audience doesn't match {audience}");
+                return Ok(None);
+            }
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| ApiError::generic("invalid system time"))?
+            .as_secs();
+        match claims.get("exp").and_then(Value::as_u64) {
+            Some(exp) if exp > now => {}
+            _ => return Ok(None),
+        }
+        if matches!(claims.get("nbf").and_then(Value::as_u64), Some(nbf) if nbf > now) {
+            return Ok(None);
+        }
+        Ok(Some(claims.clone()))
+    }
+
+    /// Find the key of a token in the cached keys, the keys are fetched again when they
+    /// have expired or when the token uses an unknown key
+    async fn find_key(&mut self, jwt: &Jwt<'_>) -> Result<Option<Jwk>> {
+        let stale = match &self.jwks {
+            Some((fetched, keys)) => {
+                let elapsed = fetched.elapsed();
+                elapsed > self.cache_ttl
+                    || (Jwk::find(keys, jwt).is_none() && elapsed > MIN_JWKS_REFRESH)
+            }
+            None => true,
+        };
+        if stale {
+            let jwks_uri = self
+                .jwks_uri
+                .clone()
+                .ok_or_else(|| ApiError::generic("the provider doesn't publish its keys"))?;
+            let doc = self
+                .get_json(&jwks_uri, None)
+                .await?
+                .ok_or_else(|| ApiError::generic("failed to fetch the provider keys"))?;
+            let keys = doc
+                .get("keys")
+                .and_then(Value::as_array)
+                .map(|keys| keys.iter().filter_map(Jwk::parse).collect())
+                .unwrap_or_default();
+            self.jwks = Some((Instant::now(), keys));
+        }
+        Ok(self
+            .jwks
+            .as_ref()
+            .and_then(|(_, keys)| Jwk::find(keys, jwt))
+            .cloned())
+    }
+
+    async fn check_userinfo(&mut self, token: &str) -> Result<Option<Claims>> {
+        let endpoint = match &self.userinfo_endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => return Ok(None),
+        };
+        let ttl = self.cache_ttl;
+        self.userinfo
+            .retain(|_, (fetched, _)| fetched.elapsed() <= ttl);
+        if let Some((_, claims)) = self.userinfo.get(token) {
+            return Ok(Some(claims.clone()));
+        }
+
+        let claims = self.get_json(&endpoint, Some(token)).await?;
+        debug!("userinfo received: {claims:?}");
+        if let Some(claims) = &claims {
+            self.userinfo
+                .insert(token.to_string(), (Instant::now(), claims.clone()));
+        }
+        Ok(claims)
+    }
+
+    /// Get a JSON document, `None` if the request is not successful
+    async fn get_json(&self, url: &str, bearer: Option<&str>) -> Result<Option<Claims>> {
+        let mut req = self.client.get(url);
+        if let Some(token) = bearer {
+            req = req.header("Authorization", format!("Bearer {token}"));
+        }
+        let res = req
+            .send()
+            .await
+            .map_err(|err| ApiError::generic(&err.to_string()))?;
+        match res.status() {
+            StatusCode::OK => Ok(Some(
+                res.json()
+                    .await
+                    .map_err(|err| ApiError::generic(&err.to_string()))?,
+            )),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// A parsed, not yet validated, JWT
+struct Jwt<'a> {
+    alg: String,
+    kid: Option<String>,
+    claims: Claims,
+    signing_input: &'a str,
+    signature: Vec<u8>,
+}
+
+impl<'a> Jwt<'a> {
+    fn parse(token: &'a str) -> Option<Self> {
+        let mut parts = token.split('.');
+        let (header, payload, signature) =
+            match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                (Some(h), Some(p), Some(s), None) => (h, p, s),
+                _ => return None,
+            };
+        let decode = |part: &str| -> Option<Claims> {
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(part).ok()?).ok()
+        };
+        let header_claims = decode(header)?;
+        Some(Jwt {
+            alg: header_claims.get("alg")?.as_str()?.to_string(),
+            kid: header_claims
+                .get("kid")
+                .and_then(Value::as_str)
+                .map(String::from),
+            claims: decode(payload)?,
+            signing_input: &token[..header.len() + 1 + payload.len()],
+            signature: URL_SAFE_NO_PAD.decode(signature).ok()?,
+        })
+    }
+}
+
+/// A public key of the provider
+#[derive(Clone)]
+struct Jwk {
+    kid: Option<String>,
+    key: JwkKey,
+}
+
+#[derive(Clone)]
+enum JwkKey {
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    P256(Vec<u8>),
+    Ed25519(Vec<u8>),
+}
+
+impl Jwk {
+    /// Parse a JSON Web Key, unsupported keys are ignored
+    fn parse(jwk: &Value) -> Option<Self> {
+        let param = |name: &str| URL_SAFE_NO_PAD.decode(jwk.get(name)?.as_str()?).ok();
+        let key = match (
+            jwk.get("kty").and_then(Value::as_str),
+            jwk.get("crv").and_then(Value::as_str),
+        ) {
+            (Some("RSA"), _) => JwkKey::Rsa {
+                n: param("n")?,
+                e: param("e")?,
+            },
+            (Some("EC"), Some("P-256")) => {
+                // Uncompressed SEC1 point
+                let mut point = vec![0x04];
+                point.extend(param("x")?);
+                point.extend(param("y")?);
+                JwkKey::P256(point)
+            }
+            (Some("OKP"), Some("Ed25519")) => JwkKey::Ed25519(param("x")?),
+            _ => return None,
+        };
+        Some(Jwk {
+            kid: jwk.get("kid").and_then(Value::as_str).map(String::from),
+            key,
+        })
+    }
+
+    fn find<'k>(keys: &'k [Jwk], jwt: &Jwt<'_>) -> Option<&'k Jwk> {
+        keys.iter().find(|k| match &jwt.kid {
+            Some(kid) => k.kid.as_ref() == Some(kid),
+            None => k.supports(&jwt.alg),
+        })
+    }
+
+    fn supports(&self, alg: &str) -> bool {
+        matches!(
+            (&self.key, alg),
+            (JwkKey::Rsa { .. }, "RS256")
+                | (JwkKey::P256(_), "ES256")
+                | (JwkKey::Ed25519(_), "EdDSA")
+        )
+    }
+
+    fn verify(&self, alg: &str, message: &[u8], sig: &[u8]) -> bool {
+        if !self.supports(alg) {
+            return false;
+        }
+        match &self.key {
+            JwkKey::Rsa { n, e } => RsaPublicKeyComponents { n, e }
+                .verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, sig)
+                .is_ok(),
+            JwkKey::P256(point) => {
+                UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point)
+                    .verify(message, sig)
+                    .is_ok()
+            }
+            JwkKey::Ed25519(key) => UnparsedPublicKey::new(&signature::ED25519, key)
+                .verify(message, sig)
+                .is_ok(),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Mapping of the claims of an identity provider to credential attributes
+
+use crate::error::ApiError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use super::oidc::Claims;
+
+/// Rule setting a credential attribute from a claim of the identity provider.
+///
+/// Without `matches` the attribute is set when the claim is present, with `value` or with
+/// the claim value, the values of array claims are joined with commas. With `matches` the
+/// rule only applies when the claim is equal to, or for array claims such as groups
+/// contains, the given value.
+///
+/// The text format is `claim=attribute`, `claim=attribute:value` or
+/// `claim[match]=attribute:value`, e.g. `groups[Admins]=role:admin`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributeRule {
+    pub claim: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<String>,
+    pub attribute: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl AttributeRule {
+    /// Copy a claim to the attribute with the same name
+    pub fn copy(name: &str) -> Self {
+        AttributeRule {
+            claim: name.to_string(),
+            matches: None,
+            attribute: name.to_string(),
+            value: None,
+        }
+    }
+
+    /// Apply the rules to the given claims. Attributes set by several rules get the
+    /// distinct values joined with commas.
+    pub fn apply(rules: &[AttributeRule], claims: &Claims) -> BTreeMap<String, String> {
+        let mut attributes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for rule in rules {
+            let claim = match lookup(claims, &rule.claim) {
+                Some(claim) => claim,
+                None => continue,
+            };
+            let values = claim_values(claim);
+            let value = match (&rule.matches, &rule.value) {
+                (Some(m), _) if !values.contains(m) => continue,
+                (_, Some(v)) => v.clone(),
+                (Some(m), None) => m.clone(),
+                (None, None) if values.is_empty() => continue,
+                (None, None) => values.join(","),
+            };
+            let entry = attributes.entry(rule.attribute.clone()).or_default();
+            if !entry.contains(&value) {
+                entry.push(value);
+            }
+        }
+        attributes
+            .into_iter()
+            .map(|(k, v)| (k, v.join(",")))
+            .collect()
+    }
+}
+
+impl FromStr for AttributeRule {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ApiError::message(format!("invalid attribute rule: {s}"));
+        let (claim, attribute) = s.split_once('=').ok_or_else(invalid)?;
+        let (claim, matches) = match claim.strip_suffix(']').and_then(|c| c.split_once('[')) {
+            Some((claim, m)) => (claim, Some(m.to_string())),
+            None => (claim, None),
+        };
+        let (attribute, value) = match attribute.split_once(':') {
+            Some((attribute, value)) => (attribute, Some(value.to_string())),
+            None => (attribute, None),
+        };
+        if claim.is_empty() || attribute.is_empty() {
+            return Err(invalid());
+        }
+        Ok(AttributeRule {
+            claim: claim.to_string(),
+            matches,
+            attribute: attribute.to_string(),
+            value,
+        })
+    }
+}
+
+impl Display for AttributeRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.claim)?;
+        if let Some(m) = &self.matches {
+            write!(f, "[{m}]")?;
+        }
+        write!(f, "={}", self.attribute)?;
+        if let Some(v) = &self.value {
+            write!(f, ":{v}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Find a claim by name, or by a dotted path for nested claims
+fn lookup<'a>(claims: &'a Claims, name: &str) -> Option<&'a Value> {
+    if let Some(claim) = claims.get(name) {
+        return Some(claim);
+    }
+    let mut path = name.split('.');
+    let first = claims.get(path.next()?)?;
+    path.try_fold(first, |claim, segment| claim.get(segment))
+}
+
+fn claim_values(claim: &Value) -> Vec<String> {
+    match claim {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(values) => values.iter().flat_map(claim_values).collect(),
+        Value::Null | Value::Object(_) => vec![],
+        v => vec![v.to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_rules() {
+        for rule in [
+            "email=email",
+            "dept=department:eng",
+            "groups[Admins]=role:admin",
+        ] {
+            assert_eq!(AttributeRule::from_str(rule).unwrap().to_string(), rule);
+        }
+        let rule = AttributeRule::from_str("groups[Admins]=role:admin").unwrap();
+        assert_eq!(rule.claim, "groups");
+        assert_eq!(rule.matches.as_deref(), Some("Admins"));
+        assert_eq!(rule.attribute, "role");
+        assert_eq!(rule.value.as_deref(), Some("admin"));
+        assert!(AttributeRule::from_str("email").is_err());
+        assert!(AttributeRule::from_str("=email").is_err());
+    }
+
+    #[test]
+    fn apply_rules() {
+        let claims = json!({
+            "email": "alice@example.com",
+            "groups": ["Admins", "Developers"],
+            "address": {"country": "FR"},
+            "email_verified": true,
+        });
+        let rules: Vec<AttributeRule> = [
+            "email=email",
+            "groups[Admins]=role:admin",
+            "groups[Developers]=role:developer",
+            "groups[Sales]=role:sales",
+            "address.country=country",
+            "email_verified[true]=verified",
+            "missing=missing",
+        ]
+        .iter()
+        .map(|r| r.parse().unwrap())
+        .collect();
+
+        let attributes = AttributeRule::apply(&rules, claims.as_object().unwrap());
+        assert_eq!(attributes.len(), 4);
+        assert_eq!(attributes["email"], "alice@example.com");
+        assert_eq!(attributes["role"], "admin,developer");
+        assert_eq!(attributes["country"], "FR");
+        assert_eq!(attributes["verified"], "true");
+    }
+}
@@ -1,2 +1,14 @@
-#[allow(dead_code)]
+//! The CDDL schema of the API.
+
+#[cfg(feature = "cddl")]
+mod json;
+
+#[cfg(feature = "cddl")]
+pub use json::Schema;
+
 pub const SCHEMA: &str = core::include_str!("schema.cddl");
+
+/// The CDDL text of the `ockam_core` and API schemas.
+pub fn text() -> String {
+    [ockam_core::api::SCHEMA, SCHEMA].join("\n")
+}
@@ -0,0 +1,602 @@
+//! Conversion of request and response bodies between their CBOR encoding
+//! and JSON, following the rules of the schema.
+//!
+//! Map members with an integer key are named after the rule of their
+//! value, e.g. `1: node_name` becomes the JSON field `node_name`. Members
+//! whose value is not a rule reference, or whose rule is used by several
+//! members of the same map, keep their key as field name, e.g. `"1"`.
+//! Byte strings are represented as hex-encoded strings and type tags are
+//! omitted from JSON and added back when encoding.
+
+use crate::error::ApiError;
+use cddl_cat::context::{BasicContext, LookupContext};
+use cddl_cat::flatten::flatten_from_str;
+use cddl_cat::ivt::{KeyValue, Literal, Map, Node, PreludeType, RuleDef};
+use cddl_cat::{validate_cbor, ValidateResult};
+use minicbor::data::{Tag, Type};
+use minicbor::encode::{self, Write};
+use minicbor::{Decoder, Encoder};
+use ockam_core::Result;
+use serde::de::value::{Error as DeError, MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeOwned, Deserializer, IntoDeserializer, Visitor};
+use serde_json::{Map as Object, Number, Value as Json};
+
+/// The rules of the `ockam_core` and API schemas.
+pub struct Schema {
+    ctx: BasicContext,
+}
+
+/// A member of a map with an integer key.
+struct Field<'a> {
+    key: i128,
+    name: String,
+    node: &'a Node,
+    optional: bool,
+}
+
+impl Schema {
+    pub fn new() -> Result<Self> {
+        let rules = flatten_from_str(&Self::text()).map_err(ApiError::message)?;
+        Ok(Schema {
+            ctx: BasicContext::new(rules),
+        })
+    }
+
+    /// The CDDL text of the schema.
+    pub fn text() -> String {
+        super::text()
+    }
+
+    /// Check if the schema has a rule with the given name.
+    pub fn contains(&self, rule: &str) -> bool {
+        self.ctx.rules.contains_key(rule)
+    }
+
+    /// Validate a CBOR encoded value against a rule.
+    pub fn validate(&self, rule: &str, cbor: &[u8]) -> Result<()> {
+        let value = Cbor::decode(cbor)?;
+        self.validate_value(self.rule(rule)?, &value)
+    }
+
+    /// Convert a CBOR encoded value to JSON, following the given rule if any.
+    pub fn to_json(&self, rule: Option<&str>, cbor: &[u8]) -> Result<Json> {
+        let value = Cbor::decode(cbor)?;
+        match rule {
+            Some(rule) => self.node_to_json(&self.rule(rule)?.node, &value),
+            None => Ok(generic_to_json(&value)),
+        }
+    }
+
+    /// Convert a JSON value to its CBOR encoding, following and validating
+    /// the given rule if any.
+    ///
+    /// Without rule, object members whose name is an integer are encoded
+    /// with an integer key.
+    pub fn from_json(&self, rule: Option<&str>, json: &Json) -> Result<Vec<u8>> {
+        let value = match rule {
+            Some(rule) => {
+                let rule = self.rule(rule)?;
+                let value = self.node_from_json(&rule.node, json)?;
+                self.validate_value(rule, &value)?;
+                value
+            }
+            None => generic_from_json(json),
+        };
+        value.encode()
+    }
+
+    fn rule(&self, name: &str) -> Result<&RuleDef> {
+        self.ctx
+            .rules
+            .get(name)
+            .ok_or_else(|| ApiError::message(format!("unknown schema rule {name}")))
+    }
+
+    fn validate_value(&self, rule: &RuleDef, value: &Cbor) -> Result<()> {
+        validate_with(validate_cbor, rule, value, &self.ctx).map_err(ApiError::message)
+    }
+
+    fn validates(&self, node: &Node, value: &Cbor) -> bool {
+        let rule = RuleDef {
+            generic_parms: Vec::new(),
+            node: node.clone(),
+        };
+        self.validate_value(&rule, value).is_ok()
+    }
+
+    fn node_to_json(&self, node: &Node, value: &Cbor) -> Result<Json> {
+        match (node, value) {
+            (Node::Rule(r), _) => self.node_to_json(&self.rule(&r.name)?.node, value),
+            (Node::Occur(o), _) => self.node_to_json(&o.node, value),
+            (Node::KeyValue(kv), _) => self.node_to_json(&kv.value, value),
+            (Node::Choice(c), _) => match c.options.iter().find(|o| self.validates(o, value)) {
+                Some(option) => self.node_to_json(option, value),
+                None => Ok(generic_to_json(value)),
+            },
+            (Node::Map(m), Cbor::Map(entries)) => {
+                let mut object = Object::new();
+                match fields(m) {
+                    Some(fields) => {
+                        for (k, v) in entries {
+                            let field = match k {
+                                Cbor::Integer(k) => fields.iter().find(|f| f.key == *k),
+                                _ => None,
+                            };
+                            match field {
+                                Some(f) if is_type_tag(f.node) => {}
+                                Some(f) => {
+                                    object.insert(f.name.clone(), self.node_to_json(f.node, v)?);
+                                }
+                                None => {
+                                    object.insert(key_to_string(k), generic_to_json(v));
+                                }
+                            }
+                        }
+                    }
+                    None => {
+                        let kv = table_member(m);
+                        for (k, v) in entries {
+                            let v = match kv {
+                                Some(kv) => self.node_to_json(&kv.value, v)?,
+                                None => generic_to_json(v),
+                            };
+                            object.insert(key_to_string(k), v);
+                        }
+                    }
+                }
+                Ok(Json::Object(object))
+            }
+            (Node::Array(a), Cbor::Array(items)) => {
+                let items = match vector_member(&a.members) {
+                    Some(member) => items
+                        .iter()
+                        .map(|i| self.node_to_json(member, i))
+                        .collect::<Result<_>>()?,
+                    None => items
+                        .iter()
+                        .enumerate()
+                        .map(|(n, i)| match a.members.get(n) {
+                            Some(member) => self.node_to_json(member, i),
+                            None => Ok(generic_to_json(i)),
+                        })
+                        .collect::<Result<_>>()?,
+                };
+                Ok(Json::Array(items))
+            }
+            _ => Ok(generic_to_json(value)),
+        }
+    }
+
+    fn node_from_json(&self, node: &Node, json: &Json) -> Result<Cbor> {
+        match node {
+            Node::Rule(r) => self.node_from_json(&self.rule(&r.name)?.node, json),
+            Node::Occur(o) => self.node_from_json(&o.node, json),
+            Node::KeyValue(kv) => self.node_from_json(&kv.value, json),
+            Node::Literal(l) => {
+                let value = literal(l);
+                if generic_from_json(json) == value {
+                    Ok(value)
+                } else {
+                    Err(ApiError::message(format!("expected {l}, found {json}")))
+                }
+            }
+            Node::PreludeType(t) => prelude_from_json(*t, json),
+            Node::Choice(c) => c
+                .options
+                .iter()
+                .filter_map(|o| self.node_from_json(o, json).ok())
+                .find(|v| self.validates(node, v))
+                .ok_or_else(|| ApiError::message(format!("no choice matches {json}"))),
+            Node::Map(m) => {
+                let object = json.as_object().ok_or_else(|| {
+                    ApiError::message(format!("expected an object, found {json}"))
+                })?;
+                let mut entries = Vec::new();
+                match fields(m) {
+                    Some(fields) => {
+                        for f in &fields {
+                            if is_type_tag(f.node) {
+                                if let Node::Literal(l) = f.node {
+                                    entries.push((Cbor::Integer(f.key), literal(l)));
+                                }
+                            } else if let Some(v) = object.get(&f.name) {
+                                entries
+                                    .push((Cbor::Integer(f.key), self.node_from_json(f.node, v)?));
+                            } else if !f.optional {
+                                return Err(ApiError::message(format!("missing field {}", f.name)));
+                            }
+                        }
+                        for (name, v) in object {
+                            if fields.iter().any(|f| f.name == *name) {
+                                continue;
+                            }
+                            match name.parse::<i128>() {
+                                Ok(k) => entries.push((Cbor::Integer(k), generic_from_json(v))),
+                                Err(_) => {
+                                    return Err(ApiError::message(format!("unknown field {name}")))
+                                }
+                            };
+                        }
+                    }
+                    None => {
+                        let kv = table_member(m);
+                        for (name, v) in object {
+                            let name = Json::String(name.clone());
+                            let (k, v) = match kv {
+                                Some(kv) => (
+                                    self.node_from_json(&kv.key, &name)?,
+                                    self.node_from_json(&kv.value, v)?,
+                                ),
+                                None => (generic_from_json(&name), generic_from_json(v)),
+                            };
+                            entries.push((k, v));
+                        }
+                    }
+                }
+                Ok(Cbor::Map(entries))
+            }
+            Node::Array(a) => {
+                let items = json
+                    .as_array()
+                    .ok_or_else(|| ApiError::message(format!("expected an array, found {json}")))?;
+                let items = match vector_member(&a.members) {
+                    Some(member) => items
+                        .iter()
+                        .map(|i| self.node_from_json(member, i))
+                        .collect::<Result<_>>()?,
+                    None => items
+                        .iter()
+                        .enumerate()
+                        .map(|(n, i)| match a.members.get(n) {
+                            Some(member) => self.node_from_json(member, i),
+                            None => Ok(generic_from_json(i)),
+                        })
+                        .collect::<Result<_>>()?,
+                };
+                Ok(Cbor::Array(items))
+            }
+            _ => Ok(generic_from_json(json)),
+        }
+    }
+}
+
+/// The members of a map if all of them have an integer key.
+fn fields(map: &Map) -> Option<Vec<Field<'_>>> {
+    let mut fields = Vec::new();
+    for member in &map.members {
+        let (kv, optional) = match member {
+            Node::KeyValue(kv) => (kv, false),
+            Node::Occur(o) => match &*o.node {
+                Node::KeyValue(kv) => (kv, o.limits().0 == 0),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let key = match &*kv.key {
+            Node::Literal(Literal::Int(k)) => *k,
+            _ => return None,
+        };
+        let name = match &*kv.value {
+            Node::Rule(r) => r.name.clone(),
+            _ => key.to_string(),
+        };
+        fields.push(Field {
+            key,
+            name,
+            node: &kv.value,
+            optional,
+        })
+    }
+    // Rules used by several members do not make unique names.
+    let names: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
+    for f in fields.iter_mut() {
+        if names.iter().filter(|n| **n == f.name).count() > 1 {
+            f.name = f.key.to_string()
+        }
+    }
+    Some(fields)
+}
+
+/// The member of a map like `{* text => bytes}`.
+fn table_member(map: &Map) -> Option<&KeyValue> {
+    match map.members.as_slice() {
+        [Node::KeyValue(kv)] => Some(kv),
+        [Node::Occur(o)] => match &*o.node {
+            Node::KeyValue(kv) => Some(kv),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The element type of an array like `[* space]`.
+fn vector_member(members: &[Node]) -> Option<&Node> {
+    match members {
+        [Node::Occur(o)] => Some(&o.node),
+        _ => None,
+    }
+}
+
+/// Type tags are integer literals at key 0.
+fn is_type_tag(node: &Node) -> bool {
+    matches!(node, Node::Literal(Literal::Int(_)))
+}
+
+fn literal(l: &Literal) -> Cbor {
+    match l {
+        Literal::Bool(b) => Cbor::Bool(*b),
+        Literal::Int(i) => Cbor::Integer(*i),
+        Literal::Float(f) => Cbor::Float(*f),
+        Literal::Text(t) => Cbor::Text(t.clone()),
+        Literal::Bytes(b) => Cbor::Bytes(b.clone()),
+    }
+}
+
+fn prelude_from_json(t: PreludeType, json: &Json) -> Result<Cbor> {
+    let value = match (t, json) {
+        (PreludeType::Any, _) => Some(generic_from_json(json)),
+        (PreludeType::Nil, Json::Null) => Some(Cbor::Null),
+        (PreludeType::Bool, Json::Bool(b)) => Some(Cbor::Bool(*b)),
+        (PreludeType::Float, Json::Number(n)) => n.as_f64().map(Cbor::Float),
+        (PreludeType::Int | PreludeType::Uint | PreludeType::Nint, Json::Number(_)) => {
+            match generic_from_json(json) {
+                Cbor::Integer(i) => Some(Cbor::Integer(i)),
+                _ => None,
+            }
+        }
+        (PreludeType::Tstr, Json::String(s)) => Some(Cbor::Text(s.clone())),
+        (PreludeType::Bstr, Json::String(s)) => hex::decode(s).ok().map(Cbor::Bytes),
+        _ => None,
+    };
+    value.ok_or_else(|| ApiError::message(format!("expected {t}, found {json}")))
+}
+
+fn key_to_string(key: &Cbor) -> String {
+    match key {
+        Cbor::Text(t) => t.clone(),
+        Cbor::Integer(i) => i.to_string(),
+        other => generic_to_json(other).to_string(),
+    }
+}
+
+fn generic_to_json(value: &Cbor) -> Json {
+    match value {
+        Cbor::Null => Json::Null,
+        Cbor::Bool(b) => Json::Bool(*b),
+        Cbor::Integer(i) => {
+            if let Ok(i) = i64::try_from(*i) {
+                Json::from(i)
+            } else if let Ok(u) = u64::try_from(*i) {
+                Json::from(u)
+            } else {
+                Json::String(i.to_string())
+            }
+        }
+        Cbor::Float(f) => Number::from_f64(*f).map(Json::Number).unwrap_or(Json::Null),
+        Cbor::Bytes(b) => Json::String(hex::encode(b)),
+        Cbor::Text(t) => Json::String(t.clone()),
+        Cbor::Array(items) => Json::Array(items.iter().map(generic_to_json).collect()),
+        Cbor::Map(entries) => Json::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (key_to_string(k), generic_to_json(v)))
+                .collect(),
+        ),
+        Cbor::Tag(_, v) => generic_to_json(v),
+    }
+}
+
+fn generic_from_json(json: &Json) -> Cbor {
+    match json {
+        Json::Null => Cbor::Null,
+        Json::Bool(b) => Cbor::Bool(*b),
+        Json::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Cbor::Integer(i.into())
+            } else if let Some(u) = n.as_u64() {
+                Cbor::Integer(u.into())
+            } else {
+                Cbor::Float(n.as_f64().unwrap_or_default())
+            }
+        }
+        Json::String(s) => Cbor::Text(s.clone()),
+        Json::Array(items) => Cbor::Array(items.iter().map(generic_from_json).collect()),
+        Json::Object(object) => Cbor::Map(
+            object
+                .iter()
+                .map(|(k, v)| {
+                    let k = match k.parse::<i128>() {
+                        Ok(i) => Cbor::Integer(i),
+                        Err(_) => Cbor::Text(k.clone()),
+                    };
+                    (k, generic_from_json(v))
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// A decoded CBOR data item.
+#[derive(Debug, Clone, PartialEq)]
+enum Cbor {
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Float(f64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Cbor>),
+    Map(Vec<(Cbor, Cbor)>),
+    Tag(Tag, Box<Cbor>),
+}
+
+impl Cbor {
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut d = Decoder::new(bytes);
+        let value = Self::decode_item(&mut d).map_err(ApiError::message)?;
+        if d.position() != bytes.len() {
+            return Err(ApiError::generic("trailing bytes after CBOR data item"));
+        }
+        Ok(value)
+    }
+
+    fn decode_item(d: &mut Decoder<'_>) -> Result<Self, minicbor::decode::Error> {
+        let value = match d.datatype()? {
+            Type::Null | Type::Undefined => {
+                d.skip()?;
+                Cbor::Null
+            }
+            Type::Bool => Cbor::Bool(d.bool()?),
+            Type::U8 | Type::U16 | Type::U32 | Type::U64 => Cbor::Integer(d.u64()?.into()),
+            Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::Int => {
+                Cbor::Integer(d.int()?.into())
+            }
+            Type::F32 => Cbor::Float(d.f32()?.into()),
+            Type::F64 => Cbor::Float(d.f64()?),
+            Type::Bytes => Cbor::Bytes(d.bytes()?.to_vec()),
+            Type::BytesIndef => {
+                let mut bytes = Vec::new();
+                for chunk in d.bytes_iter()? {
+                    bytes.extend_from_slice(chunk?)
+                }
+                Cbor::Bytes(bytes)
+            }
+            Type::String => Cbor::Text(d.str()?.to_string()),
+            Type::StringIndef => {
+                let mut text = String::new();
+                for chunk in d.str_iter()? {
+                    text.push_str(chunk?)
+                }
+                Cbor::Text(text)
+            }
+            Type::Array | Type::ArrayIndef => {
+                let mut items = Vec::new();
+                match d.array()? {
+                    Some(n) => {
+                        for _ in 0..n {
+                            items.push(Self::decode_item(d)?)
+                        }
+                    }
+                    None => {
+                        while d.datatype()? != Type::Break {
+                            items.push(Self::decode_item(d)?)
+                        }
+                        d.skip()?
+                    }
+                }
+                Cbor::Array(items)
+            }
+            Type::Map | Type::MapIndef => {
+                let mut entries = Vec::new();
+                match d.map()? {
+                    Some(n) => {
+                        for _ in 0..n {
+                            entries.push((Self::decode_item(d)?, Self::decode_item(d)?))
+                        }
+                    }
+                    None => {
+                        while d.datatype()? != Type::Break {
+                            entries.push((Self::decode_item(d)?, Self::decode_item(d)?))
+                        }
+                        d.skip()?
+                    }
+                }
+                Cbor::Map(entries)
+            }
+            Type::Tag => Cbor::Tag(d.tag()?, Box::new(Self::decode_item(d)?)),
+            t => {
+                return Err(minicbor::decode::Error::type_mismatch(t)
+                    .with_message("unsupported CBOR data item"))
+            }
+        };
+        Ok(value)
+    }
+
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut e = Encoder::new(Vec::new());
+        self.encode_item(&mut e).map_err(ApiError::message)?;
+        Ok(e.into_writer())
+    }
+
+    fn encode_item<W: Write>(&self, e: &mut Encoder<W>) -> Result<(), encode::Error<W::Error>> {
+        match self {
+            Cbor::Null => e.null()?,
+            Cbor::Bool(b) => e.bool(*b)?,
+            Cbor::Integer(i) => e.int(
+                (*i).try_into()
+                    .map_err(|_| encode::Error::message("integer out of range"))?,
+            )?,
+            Cbor::Float(f) => e.f64(*f)?,
+            Cbor::Bytes(b) => e.bytes(b)?,
+            Cbor::Text(t) => e.str(t)?,
+            Cbor::Array(items) => {
+                e.array(items.len() as u64)?;
+                for item in items {
+                    item.encode_item(e)?
+                }
+                e
+            }
+            Cbor::Map(entries) => {
+                e.map(entries.len() as u64)?;
+                for (k, v) in entries {
+                    k.encode_item(e)?;
+                    v.encode_item(e)?
+                }
+                e
+            }
+            Cbor::Tag(t, v) => {
+                e.tag(*t)?;
+                return v.encode_item(e);
+            }
+        };
+        Ok(())
+    }
+}
+
+/// Validate a value with a function of cddl-cat.
+///
+/// cddl-cat validates its own CBOR value type, which is deserialized from
+/// the decoded value.
+fn validate_with<V: DeserializeOwned>(
+    validate: fn(&RuleDef, &V, &dyn LookupContext) -> ValidateResult,
+    rule: &RuleDef,
+    value: &Cbor,
+    ctx: &dyn LookupContext,
+) -> Result<()> {
+    let value = V::deserialize(value.clone()).map_err(ApiError::message)?;
+    validate(rule, &value, ctx).map_err(ApiError::message)
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for Cbor {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Cbor {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self {
+            Cbor::Null => visitor.visit_unit(),
+            Cbor::Bool(b) => visitor.visit_bool(b),
+            Cbor::Integer(i) => visitor.visit_i128(i),
+            Cbor::Float(f) => visitor.visit_f64(f),
+            Cbor::Bytes(b) => visitor.visit_byte_buf(b),
+            Cbor::Text(t) => visitor.visit_string(t),
+            Cbor::Array(items) => SeqDeserializer::new(items.into_iter()).deserialize_any(visitor),
+            Cbor::Map(entries) => {
+                MapDeserializer::new(entries.into_iter()).deserialize_any(visitor)
+            }
+            Cbor::Tag(_, v) => v.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
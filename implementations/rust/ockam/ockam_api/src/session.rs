@@ -1,11 +1,17 @@
 mod sessions;
+#[cfg(test)]
+mod tests;
 pub(crate) mod util;
 
 use crate::{local_multiaddr_to_route, DefaultAddress};
 use minicbor::{Decode, Encode};
-use ockam::{LocalMessage, Route, TransportMessage, Worker};
+use ockam::{
+    Backoff, LocalMessage, Priority, RestartPolicy, Route, TransportMessage, Worker, WorkerBuilder,
+};
 use ockam_core::compat::sync::{Arc, Mutex};
-use ockam_core::{Address, AllowAll, Decodable, DenyAll, Encodable, Error, Routed, LOCAL};
+use ockam_core::{
+    Address, AllowAll, Decodable, DenyAll, Encodable, Error, Mailboxes, Routed, LOCAL,
+};
 use ockam_multiaddr::MultiAddr;
 use ockam_node::tokio;
 use ockam_node::tokio::sync::mpsc;
@@ -49,7 +55,9 @@ impl Medic {
         self.sessions.clone()
     }
 
-    pub async fn start(self, ctx: Context) -> Result<(), Error> {
+    /// Start checking sessions, with the collector of the ping replies
+    /// owned by the given supervisor
+    pub async fn start(self, ctx: Context, supervisor: Address) -> Result<(), Error> {
         let ctx = ctx
             .new_detached(
                 Address::random_tagged("Medic.ctx"),
@@ -58,12 +66,18 @@ impl Medic {
             )
             .await?;
         let (tx, rx) = mpsc::channel(32);
-        ctx.start_worker(
-            Collector::address(),
-            Collector(tx),
-            AllowAll, // FIXME: @ac
-            DenyAll,
+        let collector = move || Collector(tx.clone());
+        WorkerBuilder::with_mailboxes(
+            Mailboxes::main(
+                Collector::address(),
+                Arc::new(AllowAll), // FIXME: @ac
+                Arc::new(DenyAll),
+            ),
+            collector(),
         )
+        .with_restart_policy(RestartPolicy::OnPanic(Backoff::default()), collector)
+        .with_supervisor(supervisor)
+        .start(&ctx)
         .await?;
         self.go(ctx, rx).await
     }
@@ -105,7 +119,7 @@ impl Medic {
                                 "send ping"
                             }
                             let t = TransportMessage::v1(r, Collector::address(), v);
-                            LocalMessage::new(t, Vec::new())
+                            LocalMessage::new(t, Vec::new()).with_priority(Priority::Control)
                         };
                         let sender = ctx.clone();
                         self.pings
@@ -0,0 +1,94 @@
+use super::{Medic, Session};
+use crate::echoer::Echoer;
+use ockam_core::{AllowAll, Any, AsyncTryClone, Result, Routed, Worker};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::sim::{self, Link, Network};
+use ockam_node::{tokio, Context};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Forwards messages to the node with the given name, so that the
+/// local session address `/service/<gateway>` reaches a simulated node.
+struct Gateway(&'static str);
+
+#[ockam::worker]
+impl Worker for Gateway {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut message = msg.into_local_message();
+        let transport_message = message.transport_mut();
+        transport_message.onward_route.step()?;
+        transport_message
+            .onward_route
+            .modify()
+            .prepend(sim::address(self.0));
+        ctx.forward(message).await
+    }
+}
+
+#[ockam_macros::test(virtual_clock, timeout = 120_000)]
+async fn medic_replaces_unresponsive_session(ctx: &mut Context) -> Result<()> {
+    let network = Network::new(0);
+    network.set_default_link(Link::new(Duration::from_millis(50)));
+    network.attach(ctx, "alice").await?;
+
+    let mut bob = network.start_node("bob").await?;
+    bob.start_worker("echo", Echoer, AllowAll, AllowAll).await?;
+    let mut carol = network.start_node("carol").await?;
+    carol
+        .start_worker("echo", Echoer, AllowAll, AllowAll)
+        .await?;
+
+    ctx.start_worker("to_bob", Gateway("bob"), AllowAll, AllowAll)
+        .await?;
+    ctx.start_worker("to_carol", Gateway("carol"), AllowAll, AllowAll)
+        .await?;
+
+    let to_bob: MultiAddr = "/service/to_bob".parse().unwrap();
+    let to_carol: MultiAddr = "/service/to_carol".parse().unwrap();
+
+    let replaced = Arc::new(AtomicUsize::new(0));
+    let mut session = Session::new(to_bob.clone());
+    session.set_replacer({
+        let replaced = replaced.clone();
+        let to_carol = to_carol.clone();
+        Box::new(move |_| {
+            replaced.fetch_add(1, Ordering::SeqCst);
+            let to_carol = to_carol.clone();
+            Box::pin(async move { Ok(to_carol) })
+        })
+    });
+
+    let medic = Medic::new();
+    let sessions = medic.sessions();
+    let key = sessions.lock().unwrap().add(session);
+    let medic = tokio::spawn(medic.start(ctx.async_try_clone().await?, "supervisor".into()));
+
+    // A responsive session is kept.
+    ctx.sleep(Duration::from_secs(30)).await;
+    {
+        let sessions = sessions.lock().unwrap();
+        let session = sessions.session(&key).unwrap();
+        assert_eq!(session.ping_address(), &to_bob);
+        assert_eq!(replaced.load(Ordering::SeqCst), 0);
+    }
+
+    // Once bob is unreachable, the session is replaced by one to carol.
+    network.partition(&["alice"], &["bob"]);
+    ctx.sleep(Duration::from_secs(30)).await;
+    {
+        let sessions = sessions.lock().unwrap();
+        let session = sessions.session(&key).unwrap();
+        assert_eq!(session.ping_address(), &to_carol);
+        assert!(session.pings().len() < 3);
+        assert_eq!(replaced.load(Ordering::SeqCst), 1);
+    }
+
+    medic.abort();
+    bob.stop().await?;
+    carol.stop().await?;
+    ctx.stop().await
+}
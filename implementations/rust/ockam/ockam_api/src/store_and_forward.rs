@@ -0,0 +1,460 @@
+//! Store-and-forward delivery of messages to peers which may be offline.
+//!
+//! A [`StoreAndForward`] worker is used as a hop of a route whose
+//! remainder contains a [`DeliveryReceiver`], usually on another node:
+//!
+//! ```text
+//! route![store_and_forward, tcp_connection, delivery_receiver, destination]
+//! ```
+//!
+//! Each message sent through the worker is persisted to an
+//! [`AuthenticatedStorage`], like the LMDB storage of a node, and sent to
+//! the receiver until the receiver confirms it. The receiver forwards the
+//! message to the rest of the route and acknowledges it to the worker,
+//! which then deletes it from storage. Unconfirmed messages are resent
+//! with an exponential backoff, including after a restart of the worker
+//! with the same storage and address.
+//!
+//! Delivery is at least once: a message whose confirmation is lost is
+//! delivered again.  A message is given up after the maximum number of
+//! attempts or age of its [`StoreAndForwardOptions`], and new messages
+//! are dropped while the storage holds its maximum number of messages.
+//!
+//! Each stored message has a random token, which the receiver returns in
+//! its confirmation.  Confirmations with an unknown token are ignored, so
+//! that only the peers that received a message can confirm it.
+
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{
+    Address, AllowAll, Any, Decodable, DenyAll, Encodable, IncomingAccessControl, LocalMessage,
+    LocalSourceOnly, Mailbox, Mailboxes, Message, OutgoingAccessControl, Result, Route, Routed,
+    TransportMessage, Worker,
+};
+use ockam_identity::authenticated_storage::AuthenticatedStorage;
+use ockam_node::{Context, DelayedEvent, WorkerBuilder};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The key under which the id of the next message is stored.
+const NEXT_ID: &str = "next_id";
+
+/// The random token of a stored message, returned in its confirmation.
+type Token = [u8; 16];
+
+/// A message sent by a [`StoreAndForward`] worker to a [`DeliveryReceiver`].
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+pub struct Envelope {
+    id: u64,
+    token: Token,
+    /// The return route of the message, from the store-and-forward worker.
+    return_route: Route,
+    payload: Vec<u8>,
+}
+
+/// The confirmation of the delivery of an [`Envelope`].
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+struct Ack {
+    id: u64,
+    token: Token,
+}
+
+/// Resend the messages whose delay expired.
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+struct Retry;
+
+/// A message persisted by a [`StoreAndForward`] worker.
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+struct Stored {
+    token: Token,
+    /// The POSIX timestamp of the reception of the message, in seconds.
+    created: u64,
+    message: TransportMessage,
+}
+
+/// The delays between delivery attempts of a message.
+///
+/// The delay starts at `initial` and doubles after each attempt, up to
+/// `max`.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Backoff { initial, max }
+    }
+
+    fn delay(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff::new(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+/// The delivery and storage limits of a [`StoreAndForward`] worker.
+#[derive(Debug, Clone, Copy)]
+pub struct StoreAndForwardOptions {
+    backoff: Backoff,
+    max_attempts: Option<u32>,
+    max_age: Option<Duration>,
+    max_messages: usize,
+}
+
+impl StoreAndForwardOptions {
+    /// Resend messages with the default [`Backoff`] for a day at most,
+    /// and store 1000 messages at most.
+    pub fn new() -> Self {
+        StoreAndForwardOptions {
+            backoff: Backoff::default(),
+            max_attempts: None,
+            max_age: Some(Duration::from_secs(24 * 60 * 60)),
+            max_messages: 1000,
+        }
+    }
+
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Give up a message after this number of delivery attempts.  The
+    /// attempts are counted from the start of the worker.
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Give up a message which is not delivered after this time since its
+    /// reception, or never when `None`.
+    pub fn with_max_age(mut self, age: Option<Duration>) -> Self {
+        self.max_age = age;
+        self
+    }
+
+    /// Drop new messages while this number of messages is stored.
+    pub fn with_max_messages(mut self, count: usize) -> Self {
+        self.max_messages = count;
+        self
+    }
+}
+
+impl Default for StoreAndForwardOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An unconfirmed message.
+struct Pending {
+    token: Token,
+    created: u64,
+    attempts: u32,
+    next_attempt: Instant,
+}
+
+/// The current POSIX timestamp in seconds.
+fn posix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// A worker persisting the messages routed through it and resending them
+/// until a [`DeliveryReceiver`] confirms their delivery.
+pub struct StoreAndForward {
+    storage: Arc<dyn AuthenticatedStorage>,
+    /// The storage namespace of the messages.
+    messages: String,
+    /// The storage namespace of the next message id.
+    next_id_namespace: String,
+    /// The address sending the messages and receiving their confirmations.
+    int_addr: Address,
+    /// The address of the retry timer.
+    timer_addr: Address,
+    options: StoreAndForwardOptions,
+    next_id: u64,
+    pending: BTreeMap<u64, Pending>,
+    retry: Option<DelayedEvent<Retry>>,
+}
+
+impl StoreAndForward {
+    /// Start a store-and-forward worker at the given address.
+    ///
+    /// The messages stored by a previous worker with the same address
+    /// and storage are resent.  The incoming access control applies to the
+    /// messages to store, the outgoing access control to their delivery.
+    pub async fn create(
+        ctx: &Context,
+        address: impl Into<Address>,
+        storage: Arc<dyn AuthenticatedStorage>,
+        options: StoreAndForwardOptions,
+        incoming_access_control: impl IncomingAccessControl,
+        outgoing_access_control: impl OutgoingAccessControl,
+    ) -> Result<()> {
+        let address = address.into();
+        let int_addr = Address::random_tagged("StoreAndForward.internal");
+        let timer_addr = Address::random_tagged("StoreAndForward.timer");
+        let worker = StoreAndForward {
+            storage,
+            messages: format!("store_and_forward.{}.messages", address.address()),
+            next_id_namespace: format!("store_and_forward.{}.{NEXT_ID}", address.address()),
+            int_addr: int_addr.clone(),
+            timer_addr: timer_addr.clone(),
+            options,
+            next_id: 0,
+            pending: BTreeMap::new(),
+            retry: None,
+        };
+        // Confirmations come from the receivers of the messages, wherever
+        // they are, and are checked against the tokens of the messages.
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(
+                address,
+                Arc::new(incoming_access_control),
+                Arc::new(DenyAll),
+            ),
+            vec![
+                Mailbox::new(
+                    int_addr,
+                    Arc::new(AllowAll),
+                    Arc::new(outgoing_access_control),
+                ),
+                Mailbox::new(timer_addr, Arc::new(LocalSourceOnly), Arc::new(DenyAll)),
+            ],
+        );
+        WorkerBuilder::with_mailboxes(mailboxes, worker)
+            .start(ctx)
+            .await?;
+        Ok(())
+    }
+
+    fn key(id: u64) -> String {
+        // Zero-padded so that storage keys sort like ids.
+        format!("{id:020}")
+    }
+
+    /// Persist a message and send it.
+    async fn store(&mut self, ctx: &Context, msg: Routed<Any>) -> Result<()> {
+        let mut msg = msg.into_transport_message();
+        msg.onward_route.step()?;
+        if msg.onward_route.is_empty() {
+            warn!("Dropping message without onward route");
+            return Ok(());
+        }
+        if self.pending.len() >= self.options.max_messages {
+            warn!(route = %msg.onward_route, "Storage is full, dropping message");
+            return Ok(());
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.storage
+            .set(
+                NEXT_ID,
+                self.next_id_namespace.clone(),
+                self.next_id.encode()?,
+            )
+            .await?;
+        debug!(%id, route = %msg.onward_route, "Storing message");
+        let stored = Stored {
+            token: rand::random(),
+            created: posix_time(),
+            message: msg,
+        };
+        self.storage
+            .set(&Self::key(id), self.messages.clone(), stored.encode()?)
+            .await?;
+
+        self.pending.insert(
+            id,
+            Pending {
+                token: stored.token,
+                created: stored.created,
+                attempts: 0,
+                next_attempt: Instant::now(),
+            },
+        );
+        self.retry(ctx).await
+    }
+
+    /// Check if a message exceeded its number of attempts or age.
+    fn is_expired(&self, p: &Pending) -> bool {
+        let o = &self.options;
+        o.max_attempts.map_or(false, |max| p.attempts >= max)
+            || o.max_age.map_or(false, |max| {
+                Duration::from_secs(posix_time().saturating_sub(p.created)) >= max
+            })
+    }
+
+    /// Remove a message from storage.
+    async fn remove(&mut self, id: u64) -> Result<()> {
+        self.pending.remove(&id);
+        self.storage.del(&Self::key(id), &self.messages).await
+    }
+
+    /// Send the messages whose delay expired and schedule the next retry.
+    async fn retry(&mut self, ctx: &Context) -> Result<()> {
+        let now = Instant::now();
+        let due: Vec<u64> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| p.next_attempt <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in due {
+            if self.pending.get(&id).map_or(false, |p| self.is_expired(p)) {
+                warn!(%id, "Giving up undelivered message");
+                self.remove(id).await?;
+                continue;
+            }
+            self.send(ctx, id).await?
+        }
+
+        let next = self.pending.values().map(|p| p.next_attempt).min();
+        if let (Some(next), Some(retry)) = (next, self.retry.as_mut()) {
+            retry.schedule(next.saturating_duration_since(now)).await?
+        }
+        Ok(())
+    }
+
+    async fn send(&mut self, ctx: &Context, id: u64) -> Result<()> {
+        let stored = match self.storage.get(&Self::key(id), &self.messages).await? {
+            Some(bytes) => Stored::decode(&bytes)?,
+            None => {
+                self.pending.remove(&id);
+                return Ok(());
+            }
+        };
+        if let Some(p) = self.pending.get_mut(&id) {
+            p.attempts += 1;
+            p.next_attempt = Instant::now() + self.options.backoff.delay(p.attempts);
+            debug!(%id, attempt = %p.attempts, "Sending stored message")
+        }
+        let msg = stored.message;
+        let envelope = Envelope {
+            id,
+            token: stored.token,
+            return_route: msg.return_route,
+            payload: msg.payload,
+        };
+        let res = ctx
+            .send_from_address(msg.onward_route, envelope, self.int_addr.clone())
+            .await;
+        if let Err(e) = res {
+            debug!(%id, %e, "Failed to send stored message, will retry")
+        }
+        Ok(())
+    }
+
+    async fn handle_ack(&mut self, msg: Routed<Any>) -> Result<()> {
+        let ack = Ack::decode(msg.payload())?;
+        match self.pending.get(&ack.id) {
+            Some(p) if p.token == ack.token => {
+                debug!(id = %ack.id, "Stored message delivered");
+                self.remove(ack.id).await
+            }
+            Some(_) => {
+                warn!(id = %ack.id, src = %msg.src_addr(), "Ignoring confirmation with invalid token");
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[ockam::worker]
+impl Worker for StoreAndForward {
+    type Context = Context;
+    type Message = Any;
+
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        self.retry = Some(DelayedEvent::create(ctx, self.timer_addr.clone(), Retry).await?);
+
+        if let Some(next_id) = self.storage.get(NEXT_ID, &self.next_id_namespace).await? {
+            self.next_id = u64::decode(&next_id)?
+        }
+        let start = Instant::now();
+        for key in self.storage.keys(&self.messages).await? {
+            let id = match key.parse::<u64>() {
+                Ok(id) => id,
+                Err(_) => {
+                    warn!(%key, "Ignoring invalid stored message key");
+                    continue;
+                }
+            };
+            if let Some(bytes) = self.storage.get(&key, &self.messages).await? {
+                let stored = Stored::decode(&bytes)?;
+                self.pending.insert(
+                    id,
+                    Pending {
+                        token: stored.token,
+                        created: stored.created,
+                        attempts: 0,
+                        next_attempt: start,
+                    },
+                );
+            }
+        }
+        if !self.pending.is_empty() {
+            info!(count = %self.pending.len(), "Resending stored messages")
+        }
+        self.retry(ctx).await
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let addr = msg.msg_addr();
+        if addr == self.timer_addr {
+            self.retry(ctx).await
+        } else if addr == self.int_addr {
+            self.handle_ack(msg).await
+        } else {
+            self.store(ctx, msg).await
+        }
+    }
+}
+
+/// A worker forwarding the messages of [`StoreAndForward`] workers to the
+/// rest of their route and confirming their delivery.
+pub struct DeliveryReceiver;
+
+#[ockam::worker]
+impl Worker for DeliveryReceiver {
+    type Context = Context;
+    type Message = Envelope;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Envelope>) -> Result<()> {
+        let mut onward_route = msg.onward_route();
+        onward_route.step()?;
+        let confirm_route = msg.return_route();
+
+        // Replies travel back to the node of the store-and-forward worker
+        // and from there follow the original return route.
+        let envelope = msg.body();
+        let ack = Ack {
+            id: envelope.id,
+            token: envelope.token,
+        };
+        let mut return_route = confirm_route.clone();
+        return_route
+            .modify()
+            .pop_back()
+            .append_route(envelope.return_route);
+
+        debug!(id = %envelope.id, route = %onward_route, "Delivering stored message");
+        let local_msg = LocalMessage::new(
+            TransportMessage::v1(onward_route, return_route, envelope.payload),
+            vec![],
+        );
+        ctx.forward(local_msg).await?;
+        ctx.send(confirm_route, ack).await
+    }
+}
@@ -1,18 +1,60 @@
 pub mod models;
 
-use core::convert::Infallible;
-
-use minicbor::encode::Write;
-use minicbor::{Decoder, Encode};
 use models::*;
-use ockam_core::api::{Error, Id, Method, Request, Response, Status};
+use ockam_core::api::rpc::{dispatch, Call, Endpoint, Handle, Handler, Reply};
+use ockam_core::api::Method;
+use ockam_core::async_trait;
 use ockam_core::compat::sync::Arc;
 use ockam_core::vault::{KeyId, Signature};
 use ockam_core::CowStr;
 use ockam_core::{Result, Routed, Worker};
 use ockam_identity::IdentityVault;
 use ockam_node::Context;
-use tracing::trace;
+
+/// Get the attributes or the value of a secret.
+///
+/// The response depends on the requested operation, which is therefore
+/// the context used to decode it.
+#[derive(Debug, Clone, Copy)]
+pub struct GetSecret;
+
+impl Endpoint for GetSecret {
+    const METHOD: Method = Method::Get;
+    const PATH: &'static str = "/secrets/:key_id";
+    const HAS_BODY: bool = true;
+    type Req<'a> = GetSecretRequest;
+    type Res<'a> = GetSecretResponse;
+    type Ctx = GetSecretRequestOperation;
+
+    fn context(req: &GetSecretRequest) -> GetSecretRequestOperation {
+        req.operation()
+    }
+}
+
+ockam_core::endpoints! {
+    /// Get the public key of a secret.
+    pub struct GetPublicKey<'a>: Get "/secrets/:key_id/public_key" -> PublicKeyResponse;
+    /// Generate or import a secret.
+    pub struct CreateSecret<'a>: Post "/secrets" (CreateSecretRequest) -> CreateSecretResponse<'a>;
+    /// Delete a secret.
+    pub struct DeleteSecret<'a>: Delete "/secrets/:key_id" -> ();
+    /// Compute an elliptic curve Diffie-Hellman shared secret.
+    pub struct Ecdh<'a>: Post "/ecdh" (EcdhRequest<'a>) -> EcdhResponse<'a>;
+    /// Compute the key id of a public key.
+    pub struct ComputeKeyId<'a>: Post "/compute_key_id" (ComputeKeyIdRequest) -> ComputeKeyIdResponse<'a>;
+    /// Compute a SHA-256 hash.
+    pub struct Sha256<'a>: Post "/sha256" (Sha256Request<'a>) -> Sha256Response;
+    /// Derive secrets with HKDF-SHA256.
+    pub struct HkdfSha256<'a>: Post "/hkdf" (HkdfSha256Request<'a>) -> HkdfSha256Response<'a>;
+    /// Sign data with a secret.
+    pub struct Sign<'a>: Post "/sign" (SignRequest<'a>) -> SignResponse<'a>;
+    /// Verify a signature.
+    pub struct Verify<'a>: Post "/verify" (VerifyRequest<'a>) -> VerifyResponse;
+    /// Encrypt data with AES-GCM.
+    pub struct Encrypt<'a>: Post "/encrypt" (EncryptRequest<'a>) -> EncryptResponse<'a>;
+    /// Decrypt data with AES-GCM.
+    pub struct Decrypt<'a>: Post "/decrypt" (DecryptRequest<'a>) -> DecryptResponse<'a>;
+}
 
 /// Vault Service Worker
 pub struct VaultService {
@@ -20,6 +62,21 @@ pub struct VaultService {
 }
 
 impl VaultService {
+    pub(crate) const HANDLERS: &'static [Handler<Self>] = &[
+        Handler::of::<GetSecret>(),
+        Handler::of::<GetPublicKey>(),
+        Handler::of::<CreateSecret>(),
+        Handler::of::<DeleteSecret>(),
+        Handler::of::<Ecdh>(),
+        Handler::of::<ComputeKeyId>(),
+        Handler::of::<Sha256>(),
+        Handler::of::<HkdfSha256>(),
+        Handler::of::<Sign>(),
+        Handler::of::<Verify>(),
+        Handler::of::<Encrypt>(),
+        Handler::of::<Decrypt>(),
+    ];
+
     /// Constructor
     pub fn new(vault: Arc<dyn IdentityVault>) -> Self {
         Self {
@@ -28,313 +85,176 @@ impl VaultService {
     }
 }
 
-impl VaultService {
-    fn response_for_bad_request<W>(req: &Request, msg: &str, enc: W) -> Result<()>
-    where
-        W: Write<Error = Infallible>,
-    {
-        let error = Error::new(req.path()).with_message(msg);
-
-        let error = if let Some(m) = req.method() {
-            error.with_method(m)
-        } else {
-            error
+#[async_trait]
+impl Handle<GetSecret> for VaultService {
+    async fn handle<'a>(
+        &'a mut self,
+        call: Call<'a, GetSecret>,
+    ) -> Result<Reply<GetSecretResponse>> {
+        let key_id: KeyId = call.param("key_id")?.to_string();
+        let body = match call.body().operation() {
+            GetSecretRequestOperation::GetAttributes => {
+                let resp = self.vault.secret_attributes_get(&key_id).await?;
+                GetSecretResponse::Attributes(GetSecretAttributesResponse::new(resp))
+            }
+            GetSecretRequestOperation::GetSecretBytes => {
+                let resp = self.vault.secret_export(&key_id).await?;
+                GetSecretResponse::Secret(ExportSecretResponse::new(resp))
+            }
         };
-
-        Response::bad_request(req.id()).body(error).encode(enc)?;
-
-        Ok(())
+        Ok(Reply::Ok(body))
     }
+}
 
-    fn ok_response<W, B>(req: &Request, body: Option<B>, enc: W) -> Result<()>
-    where
-        W: Write<Error = Infallible>,
-        B: Encode<()>,
-    {
-        Response::ok(req.id()).body(body).encode(enc)?;
-
-        Ok(())
+#[async_trait]
+impl Handle<GetPublicKey> for VaultService {
+    async fn handle<'a>(
+        &'a mut self,
+        call: Call<'a, GetPublicKey>,
+    ) -> Result<Reply<PublicKeyResponse>> {
+        let key_id: KeyId = call.param("key_id")?.to_string();
+        let public_key = self.vault.secret_public_key_get(&key_id).await?;
+        Ok(Reply::Ok(PublicKeyResponse::new(public_key)))
     }
+}
 
-    fn response_with_error<W>(
-        req: Option<&Request>,
-        status: Status,
-        error: &str,
-        enc: W,
-    ) -> Result<()>
-    where
-        W: Write<Error = Infallible>,
-    {
-        let (path, req_id) = match req {
-            None => ("", Id::fresh()),
-            Some(req) => (req.path(), req.id()),
+#[async_trait]
+impl Handle<CreateSecret> for VaultService {
+    async fn handle<'a>(
+        &'a mut self,
+        call: Call<'a, CreateSecret>,
+    ) -> Result<Reply<CreateSecretResponse<'a>>> {
+        let args = call.into_body();
+        let attributes = *args.attributes();
+        let key_id = match args.into_secret() {
+            Some(secret) => self.vault.secret_import(secret, attributes).await?,
+            None => self.vault.secret_generate(attributes).await?,
         };
-
-        let error = Error::new(path).with_message(error);
-
-        Response::builder(req_id, status).body(error).encode(enc)?;
-
-        Ok(())
+        Ok(Reply::Ok(CreateSecretResponse::new(key_id)))
     }
+}
 
-    async fn handle_request<W>(
-        &mut self,
-        req: &Request<'_>,
-        dec: &mut Decoder<'_>,
-        enc: W,
-    ) -> Result<()>
-    where
-        W: Write<Error = Infallible>,
-    {
-        trace! {
-            target: "ockam_vault::service",
-            id     = %req.id(),
-            method = ?req.method(),
-            path   = %req.path(),
-            body   = %req.has_body(),
-            "request"
-        }
-
-        let method = match req.method() {
-            Some(m) => m,
-            None => return Self::response_for_bad_request(req, "empty method", enc),
-        };
-
-        use Method::*;
-
-        match method {
-            Get => match req.path_segments::<3>().as_slice() {
-                ["secrets", key_id] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<GetSecretRequest>()?;
-
-                    let key_id: KeyId = key_id.to_string();
-
-                    match args.operation() {
-                        GetSecretRequestOperation::GetAttributes => {
-                            let resp = self.vault.secret_attributes_get(&key_id).await?;
-                            let body = GetSecretAttributesResponse::new(resp);
-
-                            Self::ok_response(req, Some(body), enc)
-                        }
-                        GetSecretRequestOperation::GetSecretBytes => {
-                            let resp = self.vault.secret_export(&key_id).await?;
-                            let body = ExportSecretResponse::new(resp);
-
-                            Self::ok_response(req, Some(body), enc)
-                        }
-                    }
-                }
-                ["secrets", key_id, "public_key"] => {
-                    let key_id: KeyId = key_id.to_string();
-
-                    let public_key = self.vault.secret_public_key_get(&key_id).await?;
-                    let body = PublicKeyResponse::new(public_key);
-
-                    Self::ok_response(req, Some(body), enc)
-                }
-                _ => Self::response_for_bad_request(req, "unknown path", enc),
-            },
-            Post => match req.path_segments::<3>().as_slice() {
-                ["secrets"] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<CreateSecretRequest>()?;
-
-                    let attributes = *args.attributes();
-
-                    let key_id = match args.into_secret() {
-                        Some(secret) => self.vault.secret_import(secret, attributes).await?,
-                        None => self.vault.secret_generate(attributes).await?,
-                    };
-
-                    let body = CreateSecretResponse::new(key_id);
-
-                    Self::ok_response(req, Some(body), enc)
-                }
-                ["ecdh"] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<EcdhRequest>()?;
-
-                    let (secret_key_id, public_key) = args.into_parts();
-                    let secret_key_id: KeyId = secret_key_id.into_owned();
-
-                    let dh = self
-                        .vault
-                        .ec_diffie_hellman(&secret_key_id, &public_key)
-                        .await?;
-
-                    Self::ok_response(req, Some(EcdhResponse::new(dh)), enc)
-                }
-                ["compute_key_id"] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<ComputeKeyIdRequest>()?;
-
-                    let key_id = self
-                        .vault
-                        .compute_key_id_for_public_key(args.public_key())
-                        .await?;
-
-                    Self::ok_response(req, Some(ComputeKeyIdResponse::new(key_id)), enc)
-                }
-                ["sha256"] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<Sha256Request>()?;
-
-                    let hash = self.vault.sha256(args.data()).await?;
-
-                    Self::ok_response(req, Some(Sha256Response::new(hash)), enc)
-                }
-                ["hkdf"] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<HkdfSha256Request>()?;
-
-                    let salt: KeyId = args.salt().to_string();
-                    let ikm = args.ikm().map(|i| i.to_string());
-
-                    let output = self
-                        .vault
-                        .hkdf_sha256(
-                            &salt,
-                            args.info(),
-                            ikm.as_ref(),
-                            args.output_attributes().to_vec(),
-                        )
-                        .await?;
-
-                    Self::ok_response(
-                        req,
-                        Some(HkdfSha256Response::new(
-                            output.into_iter().map(CowStr::from).collect(),
-                        )),
-                        enc,
-                    )
-                }
-                ["sign"] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<SignRequest>()?;
-
-                    let key_id: KeyId = args.key_id().to_string();
-
-                    let output = self.vault.sign(&key_id, args.data()).await?;
-
-                    Self::ok_response(req, Some(SignResponse::new(output.as_ref())), enc)
-                }
-                ["verify"] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<VerifyRequest>()?;
-
-                    // TODO: Optimize?
-                    let signature = Signature::new(args.signature().to_vec());
-
-                    let output = self
-                        .vault
-                        .verify(&signature, args.public_key(), args.data())
-                        .await?;
-
-                    Self::ok_response(req, Some(VerifyResponse::new(output)), enc)
-                }
-                ["encrypt"] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<EncryptRequest>()?;
-
-                    let key_id: KeyId = args.key_id().to_string();
-
-                    let output = self
-                        .vault
-                        .aead_aes_gcm_encrypt(&key_id, args.plaintext(), args.nonce(), args.aad())
-                        .await?;
-
-                    Self::ok_response(req, Some(EncryptResponse::new(output)), enc)
-                }
-                ["decrypt"] => {
-                    if !req.has_body() {
-                        return Self::response_for_bad_request(req, "empty body", enc);
-                    }
-
-                    let args = dec.decode::<DecryptRequest>()?;
-
-                    let key_id: KeyId = args.key_id().to_string();
-
-                    let output = self
-                        .vault
-                        .aead_aes_gcm_decrypt(&key_id, args.ciphertext(), args.nonce(), args.aad())
-                        .await?;
+#[async_trait]
+impl Handle<DeleteSecret> for VaultService {
+    async fn handle<'a>(&'a mut self, call: Call<'a, DeleteSecret>) -> Result<Reply<()>> {
+        let key_id: KeyId = call.param("key_id")?.to_string();
+        self.vault.secret_destroy(key_id).await?;
+        Ok(Reply::Ok(()))
+    }
+}
 
-                    Self::ok_response(req, Some(DecryptResponse::new(output)), enc)
-                }
-                _ => Self::response_for_bad_request(req, "unknown path", enc),
-            },
-            Delete => match req.path_segments::<2>().as_slice() {
-                ["secrets", key_id] => {
-                    let key_id: KeyId = key_id.to_string();
+#[async_trait]
+impl Handle<Ecdh> for VaultService {
+    async fn handle<'a>(&'a mut self, call: Call<'a, Ecdh>) -> Result<Reply<EcdhResponse<'a>>> {
+        let (secret_key_id, public_key) = call.into_body().into_parts();
+        let secret_key_id: KeyId = secret_key_id.into_owned();
+        let dh = self
+            .vault
+            .ec_diffie_hellman(&secret_key_id, &public_key)
+            .await?;
+        Ok(Reply::Ok(EcdhResponse::new(dh)))
+    }
+}
 
-                    self.vault.secret_destroy(key_id).await?;
+#[async_trait]
+impl Handle<ComputeKeyId> for VaultService {
+    async fn handle<'a>(
+        &'a mut self,
+        call: Call<'a, ComputeKeyId>,
+    ) -> Result<Reply<ComputeKeyIdResponse<'a>>> {
+        let key_id = self
+            .vault
+            .compute_key_id_for_public_key(call.body().public_key())
+            .await?;
+        Ok(Reply::Ok(ComputeKeyIdResponse::new(key_id)))
+    }
+}
 
-                    #[allow(unused_qualifications)]
-                    Self::ok_response(req, Option::<()>::None, enc)
-                }
-                _ => Self::response_for_bad_request(req, "unknown path", enc),
-            },
-            Put | Patch => Self::response_for_bad_request(req, "unknown method", enc),
-        }
+#[async_trait]
+impl Handle<Sha256> for VaultService {
+    async fn handle<'a>(&'a mut self, call: Call<'a, Sha256>) -> Result<Reply<Sha256Response>> {
+        let hash = self.vault.sha256(call.body().data()).await?;
+        Ok(Reply::Ok(Sha256Response::new(hash)))
     }
+}
 
-    async fn on_request(&mut self, data: &[u8]) -> Result<Vec<u8>> {
-        let mut buf = Vec::new();
+#[async_trait]
+impl Handle<HkdfSha256> for VaultService {
+    async fn handle<'a>(
+        &'a mut self,
+        call: Call<'a, HkdfSha256>,
+    ) -> Result<Reply<HkdfSha256Response<'a>>> {
+        let args = call.body();
+        let salt: KeyId = args.salt().to_string();
+        let ikm = args.ikm().map(|i| i.to_string());
+        let output = self
+            .vault
+            .hkdf_sha256(
+                &salt,
+                args.info(),
+                ikm.as_ref(),
+                args.output_attributes().to_vec(),
+            )
+            .await?;
+        Ok(Reply::Ok(HkdfSha256Response::new(
+            output.into_iter().map(CowStr::from).collect(),
+        )))
+    }
+}
 
-        let mut dec = Decoder::new(data);
-        let req: Request = match dec.decode() {
-            Ok(r) => r,
-            Err(_) => {
-                Self::response_with_error(
-                    None,
-                    Status::BadRequest,
-                    "invalid Request structure",
-                    &mut buf,
-                )?;
+#[async_trait]
+impl Handle<Sign> for VaultService {
+    async fn handle<'a>(&'a mut self, call: Call<'a, Sign>) -> Result<Reply<SignResponse<'a>>> {
+        let args = call.body();
+        let key_id: KeyId = args.key_id().to_string();
+        let output = self.vault.sign(&key_id, args.data()).await?;
+        Ok(Reply::Ok(SignResponse::new(output.as_ref().to_vec())))
+    }
+}
 
-                return Ok(buf);
-            }
-        };
+#[async_trait]
+impl Handle<Verify> for VaultService {
+    async fn handle<'a>(&'a mut self, call: Call<'a, Verify>) -> Result<Reply<VerifyResponse>> {
+        let args = call.body();
+        // TODO: Optimize?
+        let signature = Signature::new(args.signature().to_vec());
+        let output = self
+            .vault
+            .verify(&signature, args.public_key(), args.data())
+            .await?;
+        Ok(Reply::Ok(VerifyResponse::new(output)))
+    }
+}
 
-        match self.handle_request(&req, &mut dec, &mut buf).await {
-            Ok(_) => {}
-            Err(err) => Self::response_with_error(
-                Some(&req),
-                Status::InternalServerError,
-                &err.to_string(),
-                &mut buf,
-            )?,
-        };
+#[async_trait]
+impl Handle<Encrypt> for VaultService {
+    async fn handle<'a>(
+        &'a mut self,
+        call: Call<'a, Encrypt>,
+    ) -> Result<Reply<EncryptResponse<'a>>> {
+        let args = call.body();
+        let key_id: KeyId = args.key_id().to_string();
+        let output = self
+            .vault
+            .aead_aes_gcm_encrypt(&key_id, args.plaintext(), args.nonce(), args.aad())
+            .await?;
+        Ok(Reply::Ok(EncryptResponse::new(output)))
+    }
+}
 
-        Ok(buf)
+#[async_trait]
+impl Handle<Decrypt> for VaultService {
+    async fn handle<'a>(
+        &'a mut self,
+        call: Call<'a, Decrypt>,
+    ) -> Result<Reply<DecryptResponse<'a>>> {
+        let args = call.body();
+        let key_id: KeyId = args.key_id().to_string();
+        let output = self
+            .vault
+            .aead_aes_gcm_decrypt(&key_id, args.ciphertext(), args.nonce(), args.aad())
+            .await?;
+        Ok(Reply::Ok(DecryptResponse::new(output)))
     }
 }
 
@@ -348,7 +268,7 @@ impl Worker for VaultService {
         ctx: &mut Self::Context,
         msg: Routed<Self::Message>,
     ) -> Result<()> {
-        let buf = self.on_request(msg.as_body()).await?;
+        let buf = dispatch(self, Self::HANDLERS, msg.as_body()).await?;
         ctx.send(msg.return_route(), buf).await
     }
 }
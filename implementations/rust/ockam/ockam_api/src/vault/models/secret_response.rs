@@ -1,4 +1,6 @@
-use minicbor::{Decode, Encode};
+use super::GetSecretRequestOperation;
+use minicbor::encode::{self, Encoder, Write};
+use minicbor::{decode, Decode, Decoder, Encode};
 use ockam::vault::Secret;
 use ockam_core::vault::{PublicKey, SecretAttributes};
 use ockam_core::CowStr;
@@ -74,6 +76,41 @@ impl GetSecretAttributesResponse {
     }
 }
 
+/// Response body when getting a secret, depending on the requested
+/// [`GetSecretRequestOperation`](super::GetSecretRequestOperation).
+#[derive(Debug, Clone)]
+pub enum GetSecretResponse {
+    Attributes(GetSecretAttributesResponse),
+    Secret(ExportSecretResponse),
+}
+
+impl<C> Encode<C> for GetSecretResponse {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), encode::Error<W::Error>> {
+        match self {
+            GetSecretResponse::Attributes(r) => r.encode(e, ctx),
+            GetSecretResponse::Secret(r) => r.encode(e, ctx),
+        }
+    }
+}
+
+impl<'b> Decode<'b, GetSecretRequestOperation> for GetSecretResponse {
+    fn decode(
+        d: &mut Decoder<'b>,
+        op: &mut GetSecretRequestOperation,
+    ) -> Result<Self, decode::Error> {
+        match op {
+            GetSecretRequestOperation::GetAttributes => {
+                d.decode().map(GetSecretResponse::Attributes)
+            }
+            GetSecretRequestOperation::GetSecretBytes => d.decode().map(GetSecretResponse::Secret),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Encode, Decode)]
 #[rustfmt::skip]
 #[cbor(map)]
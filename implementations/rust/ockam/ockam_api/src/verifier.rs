@@ -1,18 +1,20 @@
 pub mod types;
 
-use either::Either;
-use minicbor::Decoder;
-use ockam_core::api::{self, Id, ResponseBuilder};
-use ockam_core::api::{Error, Method, Request, Response};
+use ockam_core::api::rpc::{dispatch, Call, Handle, Handler, Reply};
+use ockam_core::api::Status;
 use ockam_core::compat::sync::Arc;
 use ockam_core::{self, Result, Routed, Worker};
-use ockam_identity::credential::{Credential, CredentialData, Verified};
+use ockam_identity::credential::{Credential, CredentialData};
 use ockam_identity::{IdentityVault, PublicIdentity};
 use ockam_node::Context;
-use tracing::trace;
 
 use self::types::{VerifyRequest, VerifyResponse};
 
+ockam_core::endpoints! {
+    /// Verify a credential.
+    pub struct Verify<'a>: Post "/verify" (VerifyRequest<'a>) -> VerifyResponse;
+}
+
 pub struct Verifier {
     vault: Arc<dyn IdentityVault>,
 }
@@ -23,89 +25,45 @@ impl Worker for Verifier {
     type Message = Vec<u8>;
 
     async fn handle_message(&mut self, c: &mut Context, m: Routed<Self::Message>) -> Result<()> {
-        let r = self.on_request(m.as_body()).await?;
+        let r = dispatch(self, Self::HANDLERS, m.as_body()).await?;
         c.send(m.return_route(), r).await
     }
 }
 
 impl Verifier {
+    pub(crate) const HANDLERS: &'static [Handler<Self>] = &[Handler::of::<Verify>()];
+
     pub fn new(vault: Arc<dyn IdentityVault>) -> Self {
         Self { vault }
     }
+}
 
-    async fn on_request(&mut self, data: &[u8]) -> Result<Vec<u8>> {
-        let mut dec = Decoder::new(data);
-
-        let req: Request = match dec.decode() {
-            Ok(rq) => rq,
-            Err(e) => {
-                let err = Error::default().with_message(e.to_string());
-                return Ok(Response::bad_request(Id::default()).body(err).to_vec()?);
-            }
-        };
-
-        trace! {
-            target: "ockam_api::verifier",
-            id     = %req.id(),
-            method = ?req.method(),
-            path   = %req.path(),
-            body   = %req.has_body(),
-            "request"
-        }
-
-        let res = match req.method() {
-            Some(Method::Post) => match req.path_segments::<2>().as_slice() {
-                ["verify"] => {
-                    let vr: VerifyRequest = dec.decode()?;
-                    let cr: Credential = minicbor::decode(vr.credential())?;
-                    match self.verify(req.id(), &vr, &cr).await {
-                        Ok(Either::Left(err)) => err.to_vec()?,
-                        Ok(Either::Right(dat)) => {
-                            let exp = dat.expires_at();
-                            Response::ok(req.id())
-                                .body(VerifyResponse::new(dat.into_attributes(), exp))
-                                .to_vec()?
-                        }
-                        Err(err) => Response::internal_error(req.id())
-                            .body(err.to_string())
-                            .to_vec()?,
-                    }
-                }
-                _ => api::unknown_path(&req).to_vec()?,
-            },
-            _ => api::invalid_method(&req).to_vec()?,
-        };
-
-        Ok(res)
-    }
-
-    async fn verify<'a>(
-        &self,
-        id: Id,
-        req: &'a VerifyRequest<'a>,
-        cre: &Credential,
-    ) -> Result<Either<ResponseBuilder<Error<'_>>, CredentialData<Verified>>> {
-        let data = CredentialData::try_from(cre)?;
+#[ockam_core::async_trait]
+impl Handle<Verify> for Verifier {
+    async fn handle<'a>(&'a mut self, call: Call<'a, Verify>) -> Result<Reply<VerifyResponse>> {
+        let req = call.body();
+        let cre: Credential = minicbor::decode(req.credential())?;
+        let data = CredentialData::try_from(&cre)?;
 
         let ident = if let Some(ident) = req.authority(data.unverified_issuer()) {
             PublicIdentity::import(ident, self.vault.clone()).await?
         } else {
-            let err = Error::new("/verify").with_message("unauthorised issuer");
-            return Ok(Either::Left(Response::unauthorized(id).body(err)));
+            let msg = "unauthorised issuer".to_string();
+            return Ok(Reply::Error(Status::Unauthorized, msg));
         };
 
         let data = match ident
-            .verify_credential(cre, req.subject(), self.vault.clone())
+            .verify_credential(&cre, req.subject(), self.vault.clone())
             .await
         {
             Ok(data) => data,
             Err(err) => {
-                let err = Error::new("/verify")
-                    .with_message(format!("error verifying a credential: {err}"));
-                return Ok(Either::Left(Response::forbidden(id).body(err)));
+                let msg = format!("error verifying a credential: {err}");
+                return Ok(Reply::Error(Status::Forbidden, msg));
             }
         };
 
-        Ok(Either::Right(data))
+        let exp = data.expires_at();
+        Ok(Reply::Ok(VerifyResponse::new(data.into_attributes(), exp)))
     }
 }
@@ -70,5 +70,15 @@ async fn credential(ctx: &mut Context) -> Result<()> {
         data.attributes().get("project_id")
     );
     assert_eq!(Some(b"value".as_slice()), data.attributes().get("attr"));
+    assert_eq!(None, data.audience());
+
+    // Get a credential restricted to an audience:
+    let cred = c
+        .credential_with_audience(vec!["project42".to_string()])
+        .await?;
+    let data = pkey
+        .verify_credential(&cred, member_identity.identifier(), Vault::create())
+        .await?;
+    assert_eq!(Some(&["project42".to_string()][..]), data.audience());
     ctx.stop().await
 }
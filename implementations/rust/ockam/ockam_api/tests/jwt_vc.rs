@@ -0,0 +1,147 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ockam::identity::credential::{CredentialBuilder, Timestamp, MAX_CREDENTIAL_VALIDITY};
+use ockam::identity::Identity;
+use ockam::vault::{SecretAttributes, SecretPersistence, SecretType, SecretVault, Vault};
+use ockam_api::jwt_vc;
+use ockam_core::Result;
+use ockam_identity::IdentityStateConst;
+use ockam_node::Context;
+use serde_json::{json, Value};
+
+#[ockam_macros::test]
+async fn jwt_vc_ed25519(ctx: &mut Context) -> Result<()> {
+    let issuer = Identity::create(ctx, Vault::create()).await?;
+    let subject = Identity::create(ctx, Vault::create()).await?;
+
+    let credential = issuer
+        .issue_credential(
+            CredentialBuilder::from_attributes(
+                subject.identifier().clone(),
+                [("role".to_string(), "member".to_string())].into(),
+            )
+            .with_attribute("project", b"project42"),
+        )
+        .await?;
+
+    let token = jwt_vc::export_credential(&issuer, &credential, None).await?;
+
+    let issuer_key = issuer
+        .change_history()
+        .await
+        .get_public_key(IdentityStateConst::ROOT_LABEL)?;
+    let jwk = jwt_vc::public_key_to_jwk(&issuer_key)?;
+    assert_eq!(jwt_vc::public_key_from_jwk(&jwk)?, issuer_key);
+
+    let (id, entry) = jwt_vc::verify_jwt_vc(&token, &issuer_key, Vault::create()).await?;
+    assert_eq!(&id, subject.identifier());
+    assert_eq!(entry.attrs().get("role").unwrap(), b"member");
+    assert_eq!(entry.attrs().get("project").unwrap(), b"project42");
+    assert_eq!(entry.attested_by().as_ref(), Some(issuer.identifier()));
+    assert!(entry.expires().is_some());
+
+    // A token signed by another key is rejected
+    let other = Identity::create(ctx, Vault::create()).await?;
+    let other_key = other
+        .change_history()
+        .await
+        .get_public_key(IdentityStateConst::ROOT_LABEL)?;
+    assert!(jwt_vc::verify_jwt_vc(&token, &other_key, Vault::create())
+        .await
+        .is_err());
+
+    // A tampered token is rejected
+    let mut parts: Vec<&str> = token.split('.').collect();
+    parts[1] = "eyJzdWIiOiJmb3JnZWQifQ";
+    assert!(
+        jwt_vc::verify_jwt_vc(&parts.join("."), &issuer_key, Vault::create())
+            .await
+            .is_err()
+    );
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn jwt_vc_p256(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+    let issuer = Identity::create(ctx, vault.clone()).await?;
+    let subject = Identity::create(ctx, Vault::create()).await?;
+
+    let key = vault
+        .secret_generate(SecretAttributes::new(
+            SecretType::NistP256,
+            SecretPersistence::Ephemeral,
+            32,
+        ))
+        .await?;
+    issuer.add_key("jwt".to_string(), &key).await?;
+
+    let credential = issuer
+        .issue_credential(
+            CredentialBuilder::from_attributes(subject.identifier().clone(), Default::default())
+                .with_attribute("role", b"admin"),
+        )
+        .await?;
+
+    let token = jwt_vc::export_credential(&issuer, &credential, Some("jwt")).await?;
+
+    let issuer_key = issuer.change_history().await.get_public_key("jwt")?;
+    let jwk = jwt_vc::public_key_to_jwk(&issuer_key)?;
+    assert_eq!(jwk["kty"], "EC");
+    let issuer_key = jwt_vc::public_key_from_jwk(&jwk)?;
+
+    let (id, entry) = jwt_vc::verify_jwt_vc(&token, &issuer_key, Vault::create()).await?;
+    assert_eq!(&id, subject.identifier());
+    assert_eq!(entry.attrs().get("role").unwrap(), b"admin");
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn jwt_vc_expiration(ctx: &mut Context) -> Result<()> {
+    let issuer = Identity::create(ctx, Vault::create()).await?;
+    let subject = Identity::create(ctx, Vault::create()).await?;
+    let issuer_key = issuer
+        .change_history()
+        .await
+        .get_public_key(IdentityStateConst::ROOT_LABEL)?;
+    let now = u64::from(Timestamp::now().unwrap());
+    let vc = json!({ "credentialSubject": { "id": subject.identifier().to_string() } });
+
+    // A token without expiration time is rejected
+    let token = sign(&issuer, json!({ "vc": vc })).await?;
+    assert!(jwt_vc::verify_jwt_vc(&token, &issuer_key, Vault::create())
+        .await
+        .is_err());
+
+    // An expired token is rejected
+    let token = sign(&issuer, json!({ "exp": now - 1, "vc": vc })).await?;
+    assert!(jwt_vc::verify_jwt_vc(&token, &issuer_key, Vault::create())
+        .await
+        .is_err());
+
+    // The attributes of a long lived token expire with the longest credential validity
+    let token = sign(&issuer, json!({ "exp": now + 365 * 24 * 3600, "vc": vc })).await?;
+    let (_, entry) = jwt_vc::verify_jwt_vc(&token, &issuer_key, Vault::create()).await?;
+    let expires = u64::from(entry.expires().unwrap());
+    assert!(expires <= u64::from(Timestamp::now().unwrap()) + MAX_CREDENTIAL_VALIDITY.as_secs());
+
+    ctx.stop().await
+}
+
+async fn sign(issuer: &Identity, payload: Value) -> Result<String> {
+    let header = json!({ "alg": "EdDSA", "typ": "JWT" });
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(payload.to_string())
+    );
+    let signature = issuer
+        .create_signature(signing_input.as_bytes(), None)
+        .await?;
+    Ok(format!(
+        "{signing_input}.{}",
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
@@ -0,0 +1,177 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ockam_api::okta::{AttributeRule, OidcProvider};
+use ockam_core::Result;
+use ockam_node::tokio;
+use ockam_node::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use ockam_node::tokio::net::TcpListener;
+use ockam_node::Context;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const OPAQUE_TOKEN: &str = "opaque-token";
+
+/// Minimal OpenID Connect provider serving a discovery document, its keys and
+/// a userinfo endpoint, and counting the requests made to each path
+struct MockProvider {
+    issuer: String,
+    key: Ed25519KeyPair,
+    hits: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl MockProvider {
+    async fn start() -> MockProvider {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let hits = Arc::new(Mutex::new(HashMap::new()));
+
+        let documents: HashMap<String, Value> = [
+            (
+                "/.well-known/openid-configuration".to_string(),
+                json!({
+                    "issuer": issuer,
+                    "jwks_uri": format!("{issuer}/keys"),
+                    "userinfo_endpoint": format!("{issuer}/userinfo"),
+                }),
+            ),
+            (
+                "/keys".to_string(),
+                json!({"keys": [{
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "kid": "key1",
+                    "x": URL_SAFE_NO_PAD.encode(key.public_key().as_ref()),
+                }]}),
+            ),
+            (
+                "/userinfo".to_string(),
+                json!({"sub": "bob", "email": "bob@example.com", "groups": ["Developers"]}),
+            ),
+        ]
+        .into();
+
+        let counter = hits.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 8192];
+                let n = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split(' ').nth(1).unwrap_or_default().to_string();
+                *counter.lock().unwrap().entry(path.clone()).or_default() += 1;
+
+                let authorized = path != "/userinfo"
+                    || request.lines().any(|l| {
+                        l.eq_ignore_ascii_case(&format!("authorization: Bearer {OPAQUE_TOKEN}"))
+                    });
+                let (status, body) = match documents.get(&path) {
+                    Some(doc) if authorized => ("200 OK", doc.to_string()),
+                    Some(_) => ("401 Unauthorized", String::new()),
+                    None => ("404 Not Found", String::new()),
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        MockProvider { issuer, key, hits }
+    }
+
+    fn token(&self, claims: Value) -> String {
+        let encode = |v: &Value| URL_SAFE_NO_PAD.encode(serde_json::to_vec(v).unwrap());
+        let input = format!(
+            "{}.{}",
+            encode(&json!({"alg": "EdDSA", "typ": "JWT", "kid": "key1"})),
+            encode(&claims)
+        );
+        let signature = self.key.sign(input.as_bytes());
+        format!("{input}.{}", URL_SAFE_NO_PAD.encode(signature.as_ref()))
+    }
+
+    fn claims(&self, audience: &str, expires_in: i64) -> Value {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        json!({
+            "iss": self.issuer,
+            "aud": audience,
+            "sub": "alice",
+            "email": "alice@example.com",
+            "groups": ["Admins", "Developers"],
+            "iat": now,
+            "exp": now + expires_in,
+        })
+    }
+
+    fn hits(&self, path: &str) -> usize {
+        self.hits
+            .lock()
+            .unwrap()
+            .get(path)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[ockam_macros::test]
+async fn oidc_provider(ctx: &mut Context) -> Result<()> {
+    let mock = MockProvider::start().await;
+    let mut provider = OidcProvider::new(&mock.issuer, None)?.with_audience("ockam");
+    let rules: Vec<AttributeRule> = ["email=email", "groups[Admins]=role:admin"]
+        .iter()
+        .map(|r| r.parse().unwrap())
+        .collect();
+
+    // Valid tokens are validated locally with the cached provider keys
+    let token = mock.token(mock.claims("ockam", 300));
+    for _ in 0..3 {
+        let claims = provider.validate(&token).await?.unwrap();
+        let attributes = AttributeRule::apply(&rules, &claims);
+        assert_eq!(attributes["email"], "alice@example.com");
+        assert_eq!(attributes["role"], "admin");
+    }
+    assert_eq!(mock.hits("/.well-known/openid-configuration"), 1);
+    assert_eq!(mock.hits("/keys"), 1);
+    assert_eq!(mock.hits("/userinfo"), 0);
+
+    // Tokens for another audience, expired, from another issuer or tampered are rejected
+    let token = mock.token(mock.claims("other", 300));
+    assert!(provider.validate(&token).await?.is_none());
+    let token = mock.token(mock.claims("ockam", -10));
+    assert!(provider.validate(&token).await?.is_none());
+    let mut claims = mock.claims("ockam", 300);
+    claims["iss"] = json!("http://127.0.0.1:1");
+    assert!(provider.validate(&mock.token(claims)).await?.is_none());
+    let token = mock.token(mock.claims("ockam", 300));
+    let (input, _) = token.rsplit_once('.').unwrap();
+    let forged = format!("{input}.{}", URL_SAFE_NO_PAD.encode([0u8; 64]));
+    assert!(provider.validate(&forged).await?.is_none());
+    assert_eq!(mock.hits("/keys"), 1);
+
+    // Opaque tokens can't be checked for the audience
+    assert!(provider.validate(OPAQUE_TOKEN).await?.is_none());
+    assert_eq!(mock.hits("/userinfo"), 0);
+
+    // Without required audience, opaque tokens are checked with the userinfo endpoint and cached
+    let mut provider = OidcProvider::new(&mock.issuer, None)?;
+    for _ in 0..2 {
+        let claims = provider.validate(OPAQUE_TOKEN).await?.unwrap();
+        let attributes = AttributeRule::apply(&rules, &claims);
+        assert_eq!(attributes["email"], "bob@example.com");
+        assert!(!attributes.contains_key("role"));
+    }
+    assert_eq!(mock.hits("/userinfo"), 1);
+    assert!(provider.validate("unknown-token").await?.is_none());
+
+    ctx.stop().await
+}
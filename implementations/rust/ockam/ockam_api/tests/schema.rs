@@ -0,0 +1,309 @@
+use ockam_api::cloud::project::OktaConfig;
+use ockam_api::cloud::space::CreateSpace;
+use ockam_api::cloud::{BareCloudRequestWrapper, CloudRequestWrapper};
+use ockam_api::endpoints::{self, ENDPOINTS};
+use ockam_api::nodes::models::base::NodeStatus;
+use ockam_api::nodes::models::credentials::GetCredentialRequest;
+use ockam_api::nodes::models::forwarder::CreateForwarder;
+use ockam_api::nodes::models::policy::{Policy, PolicyList};
+use ockam_api::nodes::models::portal::{CreateInlet, InletList, InletStatus, OutletStatus};
+use ockam_api::nodes::models::secure_channel::{
+    CreateSecureChannelRequest, CredentialExchangeMode,
+};
+use ockam_api::nodes::models::services::{
+    ServiceList, ServiceStatus, StartKafkaConsumerRequest, StartServiceRequest,
+};
+use ockam_api::nodes::models::transport::{
+    CreateTransport, TransportList, TransportMode, TransportStatus, TransportType,
+};
+use ockam_api::nodes::models::workers::{WorkerList, WorkerStatus};
+use ockam_api::nodes::NODEMANAGER_ADDR;
+use ockam_api::schema::Schema;
+use ockam_api::vault::models::{
+    CreateSecretRequest, ExportSecretResponse, GetSecretAttributesResponse, HkdfSha256Request,
+    PublicKeyResponse,
+};
+use ockam_api::DefaultAddress;
+use ockam_core::api::Method;
+use ockam_core::vault::{
+    PublicKey, Secret, SecretAttributes, SecretKey, SecretPersistence, SecretType,
+};
+use ockam_core::Result;
+use ockam_identity::authenticated_storage::AttributesEntry;
+use ockam_identity::credential::{Credential, DelegationScope, Timestamp};
+use ockam_identity::{Identity, IdentityIdentifier};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::Context;
+use ockam_vault::Vault;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[test]
+fn bodies_to_and_from_json() -> Result<()> {
+    let schema = Schema::new()?;
+
+    let status = minicbor::to_vec(NodeStatus::new("n1", "Running", 12, 42, 1)).unwrap();
+    let json = schema.to_json(Some("node_status"), &status)?;
+    assert_eq!(
+        json,
+        json!({
+            "node_name": "n1",
+            "node_state": "Running",
+            "workers_count": 12,
+            "pid": 42,
+            "transports_count": 1
+        })
+    );
+    let cbor = schema.from_json(Some("node_status"), &json)?;
+    let decoded: NodeStatus = minicbor::decode(&cbor).unwrap();
+    assert_eq!(decoded.node_name, "n1");
+    assert_eq!(decoded.pid, 42);
+
+    let list = WorkerList::new(vec![WorkerStatus::new("w1"), WorkerStatus::new("w2")]);
+    let json = schema.to_json(Some("worker_list"), &minicbor::to_vec(list).unwrap())?;
+    assert_eq!(
+        json,
+        json!({"workers": [{"worker_address": "w1"}, {"worker_address": "w2"}]})
+    );
+
+    // Byte strings are hex-encoded
+    let json = json!({"identity": "00ff", "data": "0102"});
+    let cbor = schema.from_json(Some("create_signature_request"), &json)?;
+    assert_eq!(
+        schema.to_json(Some("create_signature_request"), &cbor)?,
+        json
+    );
+
+    // Bodies are validated against the schema
+    assert!(schema
+        .from_json(Some("node_status"), &json!({"node_name": "n1"}))
+        .is_err());
+    assert!(schema
+        .from_json(
+            Some("create_signature_request"),
+            &json!({"identity": "xyz"})
+        )
+        .is_err());
+
+    // Without rule, integer member names become integer keys
+    let cbor = schema.from_json(None, &json!({"1": "n1"}))?;
+    assert_eq!(schema.to_json(None, &cbor)?, json!({"1": "n1"}));
+    Ok(())
+}
+
+#[test]
+fn endpoint_registry() {
+    let schema = Schema::new().unwrap();
+    for e in ENDPOINTS {
+        for rule in e.request.iter().chain(e.response.iter()) {
+            assert!(schema.contains(rule), "unknown rule {rule}")
+        }
+    }
+
+    let workers = endpoints::find(Method::Get, "/node/workers", None).unwrap();
+    assert_eq!(workers.service, NODEMANAGER_ADDR);
+    assert_eq!(workers.response, Some("worker_list"));
+
+    let key = endpoints::find(Method::Get, "/secrets/k1/public_key", None).unwrap();
+    assert_eq!(key.service, DefaultAddress::VAULT_SERVICE);
+
+    let verify = endpoints::find(Method::Post, "/verify", Some(DefaultAddress::VERIFIER)).unwrap();
+    assert_eq!(verify.request, Some("verify_request"));
+    assert!(endpoints::find(Method::Put, "/node/workers", None).is_none());
+
+    let doc = endpoints::document();
+    assert_eq!(doc["endpoints"][1]["method"], "GET");
+    assert_eq!(doc["endpoints"][1]["path"], "/node/tcp/connection");
+}
+
+#[test]
+fn endpoint_bodies() {
+    let schema = Schema::new().unwrap();
+    let validate = |rule: &str, cbor: Vec<u8>| {
+        if let Err(e) = schema.validate(rule, &cbor) {
+            panic!("{rule}: {e}")
+        }
+    };
+    let route = MultiAddr::from_str("/dnsaddr/localhost/tcp/4000/service/api").unwrap();
+    let id = IdentityIdentifier::from_key_id("0123");
+
+    // Node manager
+    let tcp = TransportStatus::new(
+        TransportType::Tcp,
+        TransportMode::Connect,
+        "127.0.0.1:4000",
+        "w",
+        "t1",
+    );
+    validate(
+        "transport_list",
+        minicbor::to_vec(TransportList::new(vec![tcp])).unwrap(),
+    );
+    let create = CreateTransport::new(TransportType::Tcp, TransportMode::Listen, "127.0.0.1:4000");
+    validate("create_transport", minicbor::to_vec(create).unwrap());
+    let get = GetCredentialRequest::new(false, Some("i1".into()), vec!["project42".into()]);
+    validate("get_credential_request", minicbor::to_vec(get).unwrap());
+    let mut sc = CreateSecureChannelRequest::new(
+        &route,
+        Some(vec![id.clone()]),
+        CredentialExchangeMode::Mutual,
+        None,
+        None,
+    );
+    sc.timeout = Some(Duration::from_secs(10));
+    validate(
+        "create_secure_channel_request",
+        minicbor::to_vec(sc).unwrap(),
+    );
+    let services = ServiceList::new(vec![ServiceStatus::new("echo", "echoer")]);
+    validate("service_list", minicbor::to_vec(services).unwrap());
+    let kafka = StartKafkaConsumerRequest::new(
+        [127, 0, 0, 1].into(),
+        9092,
+        (4000, 4100),
+        Some(route.clone()),
+        vec![],
+    );
+    validate(
+        "start_kafka_consumer_service",
+        minicbor::to_vec(StartServiceRequest::new(kafka, "kafka")).unwrap(),
+    );
+    validate(
+        "create_forwarder",
+        minicbor::to_vec(CreateForwarder::at_project(
+            route.clone(),
+            Some("f1".into()),
+        ))
+        .unwrap(),
+    );
+    validate(
+        "create_inlet",
+        minicbor::to_vec(CreateInlet::via_project(
+            "127.0.0.1:5000".parse().unwrap(),
+            route.clone(),
+        ))
+        .unwrap(),
+    );
+    validate(
+        "create_inlet",
+        minicbor::to_vec(CreateInlet::via_project(
+            "[::1]:5000".parse().unwrap(),
+            route.clone(),
+        ))
+        .unwrap(),
+    );
+    let inlet = InletStatus::new("127.0.0.1:5000", "w", "i1", None, "/service/outlet");
+    validate(
+        "inlet_list",
+        minicbor::to_vec(InletList::new(vec![inlet])).unwrap(),
+    );
+    validate(
+        "outlet_status",
+        minicbor::to_vec(OutletStatus::new("127.0.0.1:6000", "w", "o1", None)).unwrap(),
+    );
+    let expr = ockam_abac::parse("(and (= subject.role \"member\") (< 1 2.5) true)")
+        .unwrap()
+        .unwrap();
+    validate(
+        "policy",
+        minicbor::to_vec(Policy::new(expr.clone())).unwrap(),
+    );
+    validate(
+        "policy_list",
+        minicbor::to_vec(PolicyList::new(vec![("handle_message".into(), expr)])).unwrap(),
+    );
+
+    // Orchestrator requests
+    validate(
+        "bare_cloud_request",
+        minicbor::to_vec(BareCloudRequestWrapper::bare(&route)).unwrap(),
+    );
+    let users = ["alice@example.com"];
+    let space = CloudRequestWrapper::new(CreateSpace::new("s1", &users), &route, Some("i1"));
+    validate("create_space_request", minicbor::to_vec(space).unwrap());
+    let okta = OktaConfig::new("https://okta.example.com", "cert", "client", &["email"]);
+    validate(
+        "configure_okta_addon_request",
+        minicbor::to_vec(CloudRequestWrapper::new(okta, &route, None::<&str>)).unwrap(),
+    );
+
+    // Vault
+    let attributes = SecretAttributes::new(SecretType::X25519, SecretPersistence::Ephemeral, 32);
+    let secret = Secret::Key(SecretKey::new(vec![1; 32]));
+    validate(
+        "create_secret_request",
+        minicbor::to_vec(CreateSecretRequest::new_import(attributes, secret.clone())).unwrap(),
+    );
+    validate(
+        "get_secret_response",
+        minicbor::to_vec(GetSecretAttributesResponse::new(attributes)).unwrap(),
+    );
+    validate(
+        "get_secret_response",
+        minicbor::to_vec(ExportSecretResponse::new(secret)).unwrap(),
+    );
+    validate(
+        "export_secret_response",
+        minicbor::to_vec(ExportSecretResponse::new(Secret::Aws("k1".into()))).unwrap(),
+    );
+    let public_key = PublicKey::new(vec![2; 32], SecretType::X25519);
+    validate(
+        "public_key_response",
+        minicbor::to_vec(PublicKeyResponse::new(public_key)).unwrap(),
+    );
+    let hkdf = HkdfSha256Request::new("k1", b"info".as_slice(), Some("k2"), vec![attributes]);
+    validate("hkdf_sha256_request", minicbor::to_vec(hkdf).unwrap());
+
+    // Authenticated attributes
+    let attrs = BTreeMap::from([("role".to_string(), b"member".to_vec())]);
+    let entry = AttributesEntry::new(
+        attrs,
+        Timestamp::from(1),
+        Some(Timestamp::from(2)),
+        Some(id.clone()),
+    )
+    .with_audience(Some(vec!["project42".into()]));
+    validate(
+        "attributes_entries",
+        minicbor::to_vec(vec![(id, entry)]).unwrap(),
+    );
+}
+
+#[ockam_macros::test]
+async fn credential_schema(ctx: &mut Context) -> Result<()> {
+    let schema = Schema::new()?;
+    let vault = Vault::create();
+    let issuer = Identity::create(ctx, vault.clone()).await?;
+    let delegate = Identity::create(ctx, vault.clone()).await?;
+    let subject = Identity::create(ctx, vault).await?;
+
+    let credential = issuer
+        .issue_credential(
+            Credential::builder(subject.identifier().clone())
+                .with_attribute("role", b"member")
+                .with_attribute("project_id", b"project42")
+                .with_selective_disclosure()
+                .with_audience("project42"),
+        )
+        .await?;
+    let credential = credential.disclose(&["role"])?;
+    schema.validate("credential", &minicbor::to_vec(&credential).unwrap())?;
+    schema.validate("credential_data", credential.unverified_data())?;
+
+    let scope = DelegationScope::new(Duration::from_secs(3600)).allow_value("role", b"member");
+    let delegation = issuer
+        .issue_credential(Credential::builder(delegate.identifier().clone()).with_delegation(scope))
+        .await?;
+    schema.validate("credential_data", delegation.unverified_data())?;
+    let credential = delegate
+        .issue_delegated_credential(
+            Credential::builder(subject.identifier().clone()).with_attribute("role", b"member"),
+            &delegation,
+        )
+        .await?;
+    schema.validate("credential", &minicbor::to_vec(&credential).unwrap())?;
+
+    ctx.stop().await
+}
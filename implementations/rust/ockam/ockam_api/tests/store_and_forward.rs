@@ -0,0 +1,189 @@
+use ockam_api::echoer::Echoer;
+use ockam_api::hop::Hop;
+use ockam_api::lmdb::LmdbStorage;
+use ockam_api::store_and_forward::{
+    Backoff, DeliveryReceiver, StoreAndForward, StoreAndForwardOptions,
+};
+use ockam_core::{route, AllowAll, Any, Message, Result, Routed, Worker};
+use ockam_identity::authenticated_storage::mem::InMemoryStorage;
+use ockam_identity::authenticated_storage::AuthenticatedStorage;
+use ockam_node::Context;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn options() -> StoreAndForwardOptions {
+    StoreAndForwardOptions::new().with_backoff(Backoff::new(
+        Duration::from_millis(50),
+        Duration::from_millis(200),
+    ))
+}
+
+async fn start(
+    ctx: &Context,
+    storage: Arc<dyn AuthenticatedStorage>,
+    options: StoreAndForwardOptions,
+) -> Result<()> {
+    StoreAndForward::create(ctx, "sf", storage, options, AllowAll, AllowAll).await
+}
+
+/// The confirmation of a stored message, as sent by a receiver.
+#[derive(Serialize, Deserialize, Message)]
+struct Ack {
+    id: u64,
+    token: [u8; 16],
+}
+
+/// A receiver confirming the messages with a forged token.
+struct Forger;
+
+#[ockam_core::worker]
+impl Worker for Forger {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let ack = Ack {
+            id: 0,
+            token: [0; 16],
+        };
+        ctx.send(msg.return_route(), ack).await
+    }
+}
+
+#[ockam_macros::test]
+async fn offline_hop_is_retried(ctx: &mut Context) -> Result<()> {
+    let storage = Arc::new(InMemoryStorage::new());
+    start(ctx, storage.clone(), options()).await?;
+    ctx.start_worker("receiver", DeliveryReceiver, AllowAll, AllowAll)
+        .await?;
+    ctx.start_worker("echoer", Echoer, AllowAll, AllowAll)
+        .await?;
+
+    // The hop to the receiver is not started yet
+    ctx.send(
+        route!["sf", "offline", "receiver", "echoer"],
+        "Hello".to_string(),
+    )
+    .await?;
+    ctx.sleep(Duration::from_millis(300)).await;
+    assert_eq!(
+        1,
+        storage.keys("store_and_forward.sf.messages").await?.len()
+    );
+
+    ctx.start_worker("offline", Hop, AllowAll, AllowAll).await?;
+
+    // The reply follows the original return route
+    let reply = ctx.receive::<String>().await?;
+    assert_eq!("Hello", reply.body());
+
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert!(storage
+        .keys("store_and_forward.sf.messages")
+        .await?
+        .is_empty());
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn stored_messages_survive_restarts(ctx: &mut Context) -> Result<()> {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(LmdbStorage::new(dir.path().join("lmdb")).await?);
+    start(ctx, storage.clone(), options()).await?;
+    ctx.start_worker("receiver", DeliveryReceiver, AllowAll, AllowAll)
+        .await?;
+
+    ctx.send(
+        route!["sf", "offline", "receiver", ctx.address()],
+        "1".to_string(),
+    )
+    .await?;
+    ctx.send(
+        route!["sf", "offline", "receiver", ctx.address()],
+        "2".to_string(),
+    )
+    .await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    ctx.stop_worker("sf").await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    ctx.start_worker("offline", Hop, AllowAll, AllowAll).await?;
+    start(ctx, storage.clone(), options()).await?;
+
+    let mut received = vec![
+        ctx.receive::<String>().await?.body(),
+        ctx.receive::<String>().await?.body(),
+    ];
+    received.sort();
+    assert_eq!(vec!["1", "2"], received);
+
+    // New messages get new ids
+    ctx.send(
+        route!["sf", "offline", "receiver", ctx.address()],
+        "3".to_string(),
+    )
+    .await?;
+    assert_eq!("3", ctx.receive::<String>().await?.body());
+
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert!(storage
+        .keys("store_and_forward.sf.messages")
+        .await?
+        .is_empty());
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn undelivered_messages_are_given_up(ctx: &mut Context) -> Result<()> {
+    let storage = Arc::new(InMemoryStorage::new());
+    start(ctx, storage.clone(), options().with_max_attempts(2)).await?;
+    ctx.send(route!["sf", "offline", ctx.address()], "1".to_string())
+        .await?;
+    ctx.sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        1,
+        storage.keys("store_and_forward.sf.messages").await?.len()
+    );
+
+    // Given up after the second attempt
+    ctx.sleep(Duration::from_millis(300)).await;
+    assert!(storage
+        .keys("store_and_forward.sf.messages")
+        .await?
+        .is_empty());
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn storage_is_bounded(ctx: &mut Context) -> Result<()> {
+    let storage = Arc::new(InMemoryStorage::new());
+    start(ctx, storage.clone(), options().with_max_messages(2)).await?;
+    for i in 0..3 {
+        ctx.send(route!["sf", "offline", ctx.address()], i.to_string())
+            .await?;
+    }
+    ctx.sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        2,
+        storage.keys("store_and_forward.sf.messages").await?.len()
+    );
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn forged_confirmations_are_ignored(ctx: &mut Context) -> Result<()> {
+    let storage = Arc::new(InMemoryStorage::new());
+    start(ctx, storage.clone(), options()).await?;
+    ctx.start_worker("forger", Forger, AllowAll, AllowAll)
+        .await?;
+    ctx.send(route!["sf", "forger", ctx.address()], "1".to_string())
+        .await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        1,
+        storage.keys("store_and_forward.sf.messages").await?.len()
+    );
+
+    ctx.stop().await
+}
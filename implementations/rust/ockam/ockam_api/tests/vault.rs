@@ -1,13 +1,13 @@
-use minicbor::Decoder;
+use minicbor::{Decoder, Encode};
 use ockam_api::vault::models::{
-    CreateSecretRequest, CreateSecretResponse, PublicKeyResponse, SignRequest, SignResponse,
-    VerifyRequest, VerifyResponse,
+    CreateSecretRequest, CreateSecretResponse, GetSecretRequest, GetSecretRequestOperation,
+    GetSecretResponse, PublicKeyResponse, SignRequest, SignResponse, VerifyRequest, VerifyResponse,
 };
-use ockam_api::vault::VaultService;
-use ockam_core::api::{Request, Response, Status};
+use ockam_api::vault::{CreateSecret, DeleteSecret, GetPublicKey, GetSecret, VaultService};
+use ockam_core::api::{Request, RequestBuilder, Response, Status};
 use ockam_core::vault::{SecretAttributes, SecretPersistence, SecretType};
 use ockam_core::{route, AllowAll, Result};
-use ockam_node::Context;
+use ockam_node::{api, Context};
 use ockam_vault::Vault;
 
 #[ockam_macros::test]
@@ -119,3 +119,63 @@ async fn full_flow(ctx: &mut Context) -> Result<()> {
 
     Ok(())
 }
+
+async fn status<T: Encode<()>>(
+    ctx: &Context,
+    req: RequestBuilder<'_, T>,
+) -> Result<Option<Status>> {
+    let buf: Vec<u8> = ctx
+        .send_and_receive(route!["vault_service"], req.to_vec()?)
+        .await?;
+    Ok(Decoder::new(&buf).decode::<Response>()?.status())
+}
+
+#[ockam_macros::test]
+async fn typed_client(ctx: &mut Context) -> Result<()> {
+    let service = VaultService::new(Vault::create());
+    ctx.start_worker("vault_service", service, AllowAll, AllowAll)
+        .await?;
+    let mut client = api::Client::new(route!["vault_service"], ctx).await?;
+
+    let attributes = SecretAttributes::new(SecretType::Ed25519, SecretPersistence::Ephemeral, 0);
+    let key_id = client
+        .call::<CreateSecret>(&[], CreateSecretRequest::new_generate(attributes))
+        .await?
+        .key_id()
+        .to_string();
+
+    let body = GetSecretRequest::new(GetSecretRequestOperation::GetAttributes);
+    match client.call::<GetSecret>(&[&key_id], body).await? {
+        GetSecretResponse::Attributes(res) => assert_eq!(*res.attributes(), attributes),
+        GetSecretResponse::Secret(_) => panic!("expected the secret attributes"),
+    }
+    let body = GetSecretRequest::new(GetSecretRequestOperation::GetSecretBytes);
+    assert!(matches!(
+        client.call::<GetSecret>(&[&key_id], body).await?,
+        GetSecretResponse::Secret(_)
+    ));
+
+    client.call::<GetPublicKey>(&[&key_id], ()).await?;
+    client.call::<DeleteSecret>(&[&key_id], ()).await?;
+    assert!(client.call::<GetPublicKey>(&[&key_id], ()).await.is_err());
+
+    // Error responses of the route table
+    assert_eq!(
+        status(ctx, Request::get("unknown")).await?,
+        Some(Status::BadRequest)
+    );
+    assert_eq!(
+        status(ctx, Request::put("secrets")).await?,
+        Some(Status::MethodNotAllowed)
+    );
+    assert_eq!(
+        status(ctx, Request::post("sign")).await?,
+        Some(Status::BadRequest)
+    );
+    assert_eq!(
+        status(ctx, Request::post("sign").body("not a sign request")).await?,
+        Some(Status::BadRequest)
+    );
+
+    ctx.stop().await
+}
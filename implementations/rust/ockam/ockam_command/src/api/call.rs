@@ -0,0 +1,112 @@
+use crate::node::NodeOpts;
+use crate::util::{extract_address_value, node_rpc, RpcBuilder};
+use crate::{docs, CommandGlobalOpts};
+use anyhow::anyhow;
+use clap::Args;
+use ockam::{Context, TcpTransport};
+use ockam_api::endpoints;
+use ockam_api::nodes::NODEMANAGER_ADDR;
+use ockam_api::schema::Schema;
+use ockam_core::api::{Cbor, Method, Request, Status};
+use ockam_multiaddr::MultiAddr;
+use std::str::FromStr;
+
+const HELP_DETAIL: &str = r#"
+The request body is given as JSON and encoded with the schema of the
+endpoint, as listed by `ockam api list`. The response body is printed as
+JSON. Byte strings are hex-encoded.
+
+```sh
+# Get the workers of node n1
+$ ockam api call --node n1 GET /node/workers
+
+# Hash some bytes with the vault service of node n1
+$ ockam api call --node n1 POST /sha256 --to vault_service --body '{"data": "00ff"}'
+```
+"#;
+
+/// Send a request to an API endpoint of a node
+#[derive(Clone, Debug, Args)]
+#[command(after_long_help = docs::after_help(HELP_DETAIL))]
+pub struct CallCommand {
+    #[command(flatten)]
+    node_opts: NodeOpts,
+
+    /// Request method: GET, POST, PUT, DELETE or PATCH
+    #[arg(value_parser = parse_method)]
+    method: Method,
+
+    /// Request path
+    path: String,
+
+    /// Address of the service to send the request to, defaults to the
+    /// service of the endpoint matching the method and path
+    #[arg(long, value_name = "ADDRESS")]
+    to: Option<String>,
+
+    /// Request body, as JSON
+    #[arg(long, value_name = "JSON")]
+    body: Option<String>,
+}
+
+impl CallCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(run_impl, (options, self))
+    }
+}
+
+fn parse_method(s: &str) -> Result<Method, String> {
+    match s.to_uppercase().as_str() {
+        "GET" => Ok(Method::Get),
+        "POST" => Ok(Method::Post),
+        "PUT" => Ok(Method::Put),
+        "DELETE" => Ok(Method::Delete),
+        "PATCH" => Ok(Method::Patch),
+        _ => Err(format!("unknown method {s}")),
+    }
+}
+
+async fn run_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, CallCommand),
+) -> crate::Result<()> {
+    let node_name = extract_address_value(&cmd.node_opts.api_node)?;
+    let schema = Schema::new()?;
+    let endpoint = endpoints::find(cmd.method, &cmd.path, cmd.to.as_deref());
+    let service = cmd
+        .to
+        .as_deref()
+        .or_else(|| endpoint.map(|e| e.service))
+        .unwrap_or(NODEMANAGER_ADDR);
+
+    let body = match &cmd.body {
+        Some(body) => {
+            let json = serde_json::from_str(body)?;
+            Some(schema.from_json(endpoint.and_then(|e| e.request), &json)?)
+        }
+        None => None,
+    };
+
+    let tcp = TcpTransport::create(&ctx).await?;
+    let mut rpc = RpcBuilder::new(&ctx, &opts, &node_name).tcp(&tcp)?;
+    if service != NODEMANAGER_ADDR {
+        rpc = rpc.to(&MultiAddr::from_str(&format!("/service/{service}"))?)?;
+    }
+    let mut rpc = rpc.build();
+    let req = Request::builder(cmd.method, cmd.path.as_str());
+    match &body {
+        Some(body) => rpc.request(req.body(Cbor(body))).await?,
+        None => rpc.request(req).await?,
+    }
+
+    let (res, dec) = rpc.check_response()?;
+    if res.status() != Some(Status::Ok) {
+        return Err(anyhow!(rpc.parse_err_msg(res, dec)).into());
+    }
+    if res.has_body() {
+        let body = &dec.input()[dec.position()..];
+        let json = schema.to_json(endpoint.and_then(|e| e.response), body)?;
+        println!("{}", serde_json::to_string_pretty(&json)?);
+    }
+    Ok(())
+}
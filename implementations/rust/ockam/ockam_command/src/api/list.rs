@@ -0,0 +1,40 @@
+use crate::{docs, CommandGlobalOpts, OutputFormat};
+use clap::Args;
+use ockam_api::endpoints::{self, ENDPOINTS};
+
+const HELP_DETAIL: &str = "";
+
+/// List the API endpoints of nodes, with the schema of their bodies
+#[derive(Clone, Debug, Args)]
+#[command(after_long_help = docs::after_help(HELP_DETAIL))]
+pub struct ListCommand;
+
+impl ListCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        if let Err(e) = run_impl(options) {
+            eprintln!("{e:?}");
+            std::process::exit(e.code());
+        }
+    }
+}
+
+fn run_impl(options: CommandGlobalOpts) -> crate::Result<()> {
+    match options.global_args.output_format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&endpoints::document())?)
+        }
+        OutputFormat::Plain => {
+            for e in ENDPOINTS {
+                println!(
+                    "{:<7}{:<45}{:<24}{} -> {}",
+                    e.method.to_string(),
+                    e.path,
+                    e.service,
+                    e.request.unwrap_or("-"),
+                    e.response.unwrap_or("-")
+                )
+            }
+        }
+    }
+    Ok(())
+}
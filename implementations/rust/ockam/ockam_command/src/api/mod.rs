@@ -0,0 +1,39 @@
+use crate::{docs, CommandGlobalOpts};
+use clap::{Args, Subcommand};
+
+use call::CallCommand;
+use list::ListCommand;
+
+mod call;
+mod list;
+
+const HELP_DETAIL: &str = "";
+
+/// Inspect and call the API of nodes
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    after_long_help = docs::after_help(HELP_DETAIL)
+)]
+pub struct ApiCommand {
+    #[command(subcommand)]
+    subcommand: ApiSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ApiSubcommand {
+    #[command(display_order = 800)]
+    List(ListCommand),
+    #[command(display_order = 801)]
+    Call(CallCommand),
+}
+
+impl ApiCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        match self.subcommand {
+            ApiSubcommand::List(c) => c.run(options),
+            ApiSubcommand::Call(c) => c.run(options),
+        }
+    }
+}
@@ -10,8 +10,9 @@ use ockam::AsyncTryClone;
 use ockam::Context;
 use ockam_api::bootstrapped_identities_store::PreTrustedIdentities;
 use ockam_api::nodes::authority_node;
-use ockam_api::nodes::authority_node::{OktaConfiguration, TrustedIdentity};
+use ockam_api::nodes::authority_node::{OidcConfiguration, OktaConfiguration, TrustedIdentity};
 use ockam_api::nodes::models::transport::{CreateTransportJson, TransportMode, TransportType};
+use ockam_api::okta::AttributeRule;
 use ockam_api::DefaultAddress;
 use ockam_core::compat::collections::HashMap;
 use ockam_core::compat::fmt;
@@ -78,6 +79,32 @@ pub struct CreateCommand {
     #[arg(long, group = "okta", value_name = "COMMA_SEPARATED_LIST", default_value = None)]
     attributes: Option<Vec<String>>,
 
+    /// OpenID Connect: URL of a provider whose tokens can be used to enroll, instead of Okta
+    /// (optional)
+    #[arg(
+        long,
+        value_name = "URL",
+        conflicts_with = "okta",
+        requires = "oidc_audience"
+    )]
+    oidc_issuer: Option<String>,
+
+    /// OpenID Connect: audience which the provider tokens must be issued for, required with
+    /// an OpenID Connect provider
+    #[arg(long, value_name = "STRING", requires = "oidc_issuer")]
+    oidc_audience: Option<String>,
+
+    /// OpenID Connect: URL of the provider keys, when it is not in the discovery document
+    /// (optional)
+    #[arg(long, value_name = "URL", requires = "oidc_issuer")]
+    oidc_jwks_uri: Option<String>,
+
+    /// Okta or OpenID Connect: rule mapping a claim to an attribute, in `claim=attribute`,
+    /// `claim=attribute:value` or `claim[match]=attribute:value` format,
+    /// e.g. `groups[Admins]=role:admin` (optional)
+    #[arg(long = "attribute-rule", value_name = "RULE", value_parser = parse_attribute_rule)]
+    attribute_rules: Vec<AttributeRule>,
+
     /// Run the node in foreground.
     #[arg(long, short, value_name = "BOOL", default_value_t = false)]
     foreground: bool,
@@ -141,6 +168,22 @@ async fn spawn_background_node(
         args.push("--attributes".to_string());
         args.push(attributes.join(","));
     }
+
+    for (arg, value) in [
+        ("--oidc-issuer", &cmd.oidc_issuer),
+        ("--oidc-audience", &cmd.oidc_audience),
+        ("--oidc-jwks-uri", &cmd.oidc_jwks_uri),
+    ] {
+        if let Some(value) = value {
+            args.push(arg.to_string());
+            args.push(value.clone());
+        }
+    }
+
+    for rule in &cmd.attribute_rules {
+        args.push("--attribute-rule".to_string());
+        args.push(rule.to_string());
+    }
     args.push(cmd.node_name.to_string());
 
     run_ockam(opts, &cmd.node_name, args)
@@ -233,6 +276,18 @@ async fn start_authority_node(
             tenant_base_url: tenant_base_url.clone(),
             certificate: certificate.clone(),
             attributes: attributes.clone(),
+            attribute_rules: command.attribute_rules.clone(),
+        }),
+        _ => None,
+    };
+
+    let oidc_configuration = match (&command.oidc_issuer, &command.oidc_audience) {
+        (Some(issuer), Some(audience)) => Some(OidcConfiguration {
+            address: DefaultAddress::OKTA_IDENTITY_PROVIDER.to_string(),
+            issuer: issuer.clone(),
+            audience: audience.clone(),
+            jwks_uri: command.oidc_jwks_uri.clone(),
+            attribute_rules: command.attribute_rules.clone(),
         }),
         _ => None,
     };
@@ -271,12 +326,23 @@ async fn start_authority_node(
         no_direct_authentication: command.no_direct_authentication,
         no_token_enrollment: command.no_token_enrollment,
         okta: okta_configuration,
+        oidc: oidc_configuration,
     };
     authority_node::start_node(&ctx, &configuration).await?;
 
     Ok(())
 }
 
+/// Return an attribute rule passed on the command line
+fn parse_attribute_rule(value: &str) -> Result<AttributeRule> {
+    value.parse::<AttributeRule>().map_err(|e| {
+        crate::Error::new(
+            exitcode::CONFIG,
+            anyhow!("Cannot parse the attribute rule: {e}"),
+        )
+    })
+}
+
 /// Return a list of trusted identities passed as a JSON string on the command line
 fn parse_trusted_identities(values: &str) -> Result<TrustedIdentities> {
     serde_json::from_str::<TrustedIdentities>(values).map_err(|e| {
@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use crate::{identity::default_identity_name, util::node_rpc, vault::default_vault_name};
+use crate::{CommandGlobalOpts, Result};
+use anyhow::anyhow;
+use clap::Args;
+use ockam::Context;
+use ockam_api::jwt_vc;
+use ockam_core::compat::sync::Arc;
+use ockam_identity::credential::Credential;
+use ockam_identity::IdentityStateConst;
+
+/// Export a credential issued by an identity as a JWT-VC signed with the issuer key
+#[derive(Clone, Debug, Args)]
+pub struct ExportJwtCommand {
+    /// Identity that issued the credential
+    #[arg(long = "as", default_value_t = default_identity_name())]
+    pub as_identity: String,
+
+    /// Label of the issuer key used to sign the token, the root key when not set
+    #[arg(long)]
+    pub key_label: Option<String>,
+
+    #[arg(group = "credential_value", value_name = "CREDENTIAL_STRING", long)]
+    pub credential: Option<String>,
+
+    #[arg(group = "credential_value", value_name = "CREDENTIAL_FILE", long)]
+    pub credential_path: Option<PathBuf>,
+
+    /// Print the signing key as a JWK instead, to be configured as trusted issuer key
+    #[arg(long)]
+    pub jwk: bool,
+
+    #[arg(default_value_t = default_vault_name())]
+    pub vault: String,
+}
+
+impl ExportJwtCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        node_rpc(run_impl, (opts, self));
+    }
+}
+
+async fn run_impl(ctx: Context, (opts, cmd): (CommandGlobalOpts, ExportJwtCommand)) -> Result<()> {
+    let vault = opts.state.vaults.get(&cmd.vault)?.get().await?;
+    let ident_state = opts.state.identities.get(&cmd.as_identity)?;
+    let ident = ident_state.get(&ctx, Arc::new(vault)).await?;
+
+    if cmd.jwk {
+        let label = cmd
+            .key_label
+            .as_deref()
+            .unwrap_or(IdentityStateConst::ROOT_LABEL);
+        let public_key = ident.change_history().await.get_public_key(label)?;
+        println!("{}", jwt_vc::public_key_to_jwk(&public_key)?);
+        return Ok(());
+    }
+
+    let cred_as_str = match (cmd.credential, cmd.credential_path) {
+        (_, Some(credential_path)) => tokio::fs::read_to_string(credential_path).await?,
+        (Some(credential), _) => credential,
+        _ => return Err(anyhow!("Credential or Credential Path argument must be provided").into()),
+    };
+    let bytes = hex::decode(cred_as_str.trim()).map_err(|e| anyhow!(e))?;
+    let credential: Credential = minicbor::decode(&bytes)?;
+
+    let token = jwt_vc::export_credential(&ident, &credential, cmd.key_label.as_deref()).await?;
+    println!("{token}");
+
+    Ok(())
+}
@@ -16,6 +16,10 @@ pub struct GetCommand {
 
     #[arg(long = "identity", value_name = "IDENTITY")]
     identity: Option<String>,
+
+    /// Restrict the credential to an audience, e.g. a project id or a node identifier
+    #[arg(long = "audience", value_name = "AUDIENCE")]
+    audience: Vec<String>,
 }
 
 impl GetCommand {
@@ -37,6 +41,7 @@ async fn run_impl(
     rpc.request(api::credentials::get_credential(
         cmd.overwrite,
         cmd.identity,
+        cmd.audience,
     ))
     .await?;
     Ok(())
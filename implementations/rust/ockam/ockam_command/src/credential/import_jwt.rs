@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+
+use crate::{util::node_rpc, vault::default_vault_name, CommandGlobalOpts, Result};
+use anyhow::anyhow;
+use clap::Args;
+use ockam::Context;
+use ockam_api::jwt_vc;
+use ockam_core::compat::sync::Arc;
+use ockam_identity::authenticated_storage::{
+    AuthenticatedAttributeStorage, IdentityAttributeStorageWriter,
+};
+
+/// Verify a JWT-VC signed by a trusted issuer key and store the attributes of its subject
+#[derive(Clone, Debug, Args)]
+pub struct ImportJwtCommand {
+    /// Public key of the trusted issuer as a JWK
+    #[arg(group = "issuer_key", value_name = "JWK_STRING", long)]
+    pub issuer_jwk: Option<String>,
+
+    #[arg(group = "issuer_key", value_name = "JWK_FILE", long)]
+    pub issuer_jwk_path: Option<PathBuf>,
+
+    #[arg(group = "token_value", value_name = "TOKEN_STRING", long)]
+    pub token: Option<String>,
+
+    #[arg(group = "token_value", value_name = "TOKEN_FILE", long)]
+    pub token_path: Option<PathBuf>,
+
+    #[arg(default_value_t = default_vault_name())]
+    pub vault: String,
+}
+
+impl ImportJwtCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        node_rpc(run_impl, (opts, self));
+    }
+}
+
+async fn run_impl(_ctx: Context, (opts, cmd): (CommandGlobalOpts, ImportJwtCommand)) -> Result<()> {
+    let jwk_as_str = match (cmd.issuer_jwk, cmd.issuer_jwk_path) {
+        (_, Some(jwk_path)) => tokio::fs::read_to_string(jwk_path).await?,
+        (Some(jwk), _) => jwk,
+        _ => return Err(anyhow!("Issuer JWK or Issuer JWK Path argument must be provided").into()),
+    };
+    let token = match (cmd.token, cmd.token_path) {
+        (_, Some(token_path)) => tokio::fs::read_to_string(token_path).await?,
+        (Some(token), _) => token,
+        _ => return Err(anyhow!("Token or Token Path argument must be provided").into()),
+    };
+
+    let issuer_key = jwt_vc::public_key_from_jwk(&serde_json::from_str(&jwk_as_str)?)?;
+    let vault = Arc::new(opts.state.vaults.get(&cmd.vault)?.get().await?);
+    let (subject, entry) = jwt_vc::verify_jwt_vc(&token, &issuer_key, vault).await?;
+
+    let storage =
+        AuthenticatedAttributeStorage::new(opts.state.identities.authenticated_storage().await?);
+    storage.put_attributes(&subject, entry.clone()).await?;
+
+    println!("Subject: {subject}");
+    for (name, value) in entry.attrs() {
+        println!("{:2}{name}: {}", "", String::from_utf8_lossy(value));
+    }
+    if let Some(expires) = entry.expires() {
+        println!("Expires: {}", expires.unix_time());
+    }
+
+    Ok(())
+}
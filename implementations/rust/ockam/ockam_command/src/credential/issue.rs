@@ -1,3 +1,4 @@
+use core::time::Duration;
 use ockam_core::compat::collections::HashMap;
 use ockam_core::compat::sync::Arc;
 
@@ -10,7 +11,10 @@ use crate::{
 use anyhow::Context as _;
 use clap::Args;
 use ockam::Context;
-use ockam_identity::{credential::CredentialBuilder, IdentityIdentifier};
+use ockam_identity::credential::{
+    Credential, CredentialBuilder, DelegationScope, MAX_CREDENTIAL_VALIDITY,
+};
+use ockam_identity::IdentityIdentifier;
 
 #[derive(Clone, Debug, Args)]
 pub struct IssueCommand {
@@ -24,6 +28,28 @@ pub struct IssueCommand {
     #[arg(short, long = "attribute", value_name = "ATTRIBUTE")]
     pub attributes: Vec<String>,
 
+    /// Commit to each attribute separately so that the holder can disclose only some of them
+    #[arg(long)]
+    pub selective_disclosure: bool,
+
+    /// Restrict the credential to an audience, e.g. a project id or a node identifier
+    #[arg(long = "audience", value_name = "AUDIENCE")]
+    pub audience: Vec<String>,
+
+    /// Issue a delegation credential allowing the subject to issue this attribute, in `key` or
+    /// `key=value` format to only allow the given value
+    #[arg(long = "delegate-attribute", value_name = "ATTRIBUTE")]
+    pub delegate_attributes: Vec<String>,
+
+    /// Maximum validity in seconds of the credentials issued with the delegation credential
+    #[arg(long, value_name = "SECONDS", requires = "delegate_attributes")]
+    pub delegate_max_validity: Option<u64>,
+
+    /// Issue the credential on behalf of an authority, with a delegation credential issued to
+    /// the `--as` identity
+    #[arg(long, value_name = "DELEGATION_CREDENTIAL")]
+    pub delegation: Option<String>,
+
     #[arg(default_value_t = default_vault_name())]
     pub vault: String,
 
@@ -47,6 +73,24 @@ impl IssueCommand {
         }
         Ok(attributes)
     }
+
+    fn delegation_scope(&self) -> Option<DelegationScope> {
+        if self.delegate_attributes.is_empty() {
+            return None;
+        }
+        let max_validity = self
+            .delegate_max_validity
+            .map(Duration::from_secs)
+            .unwrap_or(MAX_CREDENTIAL_VALIDITY);
+        let scope = self.delegate_attributes.iter().fold(
+            DelegationScope::new(max_validity),
+            |scope, attr| match attr.split_once('=') {
+                Some((key, value)) => scope.allow_value(key, value.as_bytes()),
+                None => scope.allow_attribute(attr),
+            },
+        );
+        Some(scope)
+    }
 }
 
 async fn run_impl(
@@ -54,14 +98,31 @@ async fn run_impl(
     (opts, cmd): (CommandGlobalOpts, IssueCommand),
 ) -> crate::Result<()> {
     let attrs = cmd.attributes()?;
-    let cred_builder = CredentialBuilder::from_attributes(cmd.for_identity.clone(), attrs);
+    let mut cred_builder = CredentialBuilder::from_attributes(cmd.for_identity.clone(), attrs);
+    if cmd.selective_disclosure {
+        cred_builder = cred_builder.with_selective_disclosure();
+    }
+    for audience in &cmd.audience {
+        cred_builder = cred_builder.with_audience(audience);
+    }
+    if let Some(scope) = cmd.delegation_scope() {
+        cred_builder = cred_builder.with_delegation(scope);
+    }
 
     let vault = opts.state.vaults.get(&cmd.vault)?.get().await?;
     let ident_state = opts.state.identities.get(&cmd.as_identity)?;
 
     let ident = ident_state.get(&ctx, Arc::new(vault)).await?;
 
-    let credential = ident.issue_credential(cred_builder).await?;
+    let credential = match &cmd.delegation {
+        Some(delegation) => {
+            let delegation: Credential = minicbor::decode(&hex::decode(delegation.trim())?)?;
+            ident
+                .issue_delegated_credential(cred_builder, &delegation)
+                .await?
+        }
+        None => ident.issue_credential(cred_builder).await?,
+    };
 
     print_encodable(credential, &cmd.encode_format)?;
 
@@ -1,4 +1,6 @@
+pub(crate) mod export_jwt;
 pub(crate) mod get;
+pub(crate) mod import_jwt;
 pub(crate) mod issue;
 pub(crate) mod list;
 pub(crate) mod present;
@@ -7,7 +9,9 @@ pub(crate) mod store;
 pub(crate) mod verify;
 
 use anyhow::anyhow;
+pub(crate) use export_jwt::ExportJwtCommand;
 pub(crate) use get::GetCommand;
+pub(crate) use import_jwt::ImportJwtCommand;
 pub(crate) use issue::IssueCommand;
 pub(crate) use list::ListCommand;
 use ockam::Context;
@@ -41,7 +45,9 @@ pub struct CredentialCommand {
 
 #[derive(Clone, Debug, Subcommand)]
 pub enum CredentialSubcommand {
+    ExportJwt(ExportJwtCommand),
     Get(GetCommand),
+    ImportJwt(ImportJwtCommand),
     Issue(IssueCommand),
     List(ListCommand),
     Present(PresentCommand),
@@ -53,7 +59,9 @@ pub enum CredentialSubcommand {
 impl CredentialCommand {
     pub fn run(self, options: CommandGlobalOpts) {
         match self.subcommand {
+            CredentialSubcommand::ExportJwt(c) => c.run(options),
             CredentialSubcommand::Get(c) => c.run(options),
+            CredentialSubcommand::ImportJwt(c) => c.run(options),
             CredentialSubcommand::Issue(c) => c.run(options),
             CredentialSubcommand::List(c) => c.run(options),
             CredentialSubcommand::Present(c) => c.run(options),
@@ -18,6 +18,10 @@ pub struct PresentCommand {
 
     #[arg(short, long)]
     pub oneway: bool,
+
+    /// Only disclose this attribute of a selective-disclosure credential, can be repeated
+    #[arg(long = "disclose", value_name = "ATTRIBUTE")]
+    pub disclosed_attributes: Vec<String>,
 }
 
 impl PresentCommand {
@@ -39,7 +43,13 @@ async fn run_impl(
     cmd: PresentCommand,
 ) -> crate::Result<()> {
     let mut rpc = Rpc::background(ctx, &opts, &cmd.node_opts.api_node)?;
-    rpc.request(api::credentials::present_credential(&cmd.to, cmd.oneway))
-        .await?;
+    let disclosed_attributes =
+        (!cmd.disclosed_attributes.is_empty()).then_some(cmd.disclosed_attributes);
+    rpc.request(api::credentials::present_credential(
+        &cmd.to,
+        cmd.oneway,
+        disclosed_attributes,
+    ))
+    .await?;
     Ok(())
 }
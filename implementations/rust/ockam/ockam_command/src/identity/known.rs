@@ -0,0 +1,220 @@
+use crate::node::NodeOpts;
+use crate::util::api;
+use crate::util::{node_rpc, Rpc};
+use crate::CommandGlobalOpts;
+use anyhow::anyhow;
+use clap::{Args, Subcommand};
+use ockam::Context;
+use ockam_core::compat::sync::Arc;
+use ockam_identity::authenticated_storage::AuthenticatedStorage;
+use ockam_identity::change_history::IdentityChangeHistory;
+use ockam_identity::IdentityStateConst;
+use ockam_multiaddr::MultiAddr;
+
+/// Inspect and propagate the change histories of identities known to this system
+#[derive(Clone, Debug, Args)]
+pub struct KnownCommand {
+    #[command(subcommand)]
+    subcommand: KnownSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum KnownSubcommand {
+    /// Print all known identities with the version of their change history
+    List(ListKnownCommand),
+    /// Print the change history of a known identity, `--full` for the whole history
+    Show(ShowKnownCommand),
+    /// Push the change history of the node identity, or of a known identity, to another node
+    Push(PushKnownCommand),
+    /// Pull the latest change history of an identity from another node
+    Pull(PullKnownCommand),
+}
+
+impl KnownCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        match self.subcommand {
+            KnownSubcommand::List(c) => c.run(options),
+            KnownSubcommand::Show(c) => c.run(options),
+            KnownSubcommand::Push(c) => c.run(options),
+            KnownSubcommand::Pull(c) => c.run(options),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct ListKnownCommand {}
+
+impl ListKnownCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(list_impl, (options, self))
+    }
+}
+
+async fn list_impl(
+    _ctx: Context,
+    (opts, _cmd): (CommandGlobalOpts, ListKnownCommand),
+) -> crate::Result<()> {
+    let storage = opts.state.identities.authenticated_storage().await?;
+    let ids = storage.keys(IdentityStateConst::CHANGE_HISTORY_KEY).await?;
+    if ids.is_empty() {
+        println!("No known identities");
+        return Ok(());
+    }
+    for (idx, id) in ids.iter().enumerate() {
+        let history = get_history(&storage, id, IdentityStateConst::CHANGE_HISTORY_KEY)
+            .await?
+            .ok_or_else(|| anyhow!("Identity {id} is not known"))?;
+        println!("Identity[{idx}]:");
+        println!("{:2}Identifier: {id}", "");
+        print_version(&history);
+        if get_history(
+            &storage,
+            id,
+            IdentityStateConst::CONFLICTING_CHANGE_HISTORY_KEY,
+        )
+        .await?
+        .is_some()
+        {
+            println!(
+                "{:2}Conflict: a conflicting change history was received",
+                ""
+            );
+        }
+        if idx < ids.len() - 1 {
+            println!();
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct ShowKnownCommand {
+    /// Identifier of the known identity
+    identity_id: String,
+
+    #[arg(short, long)]
+    full: bool,
+}
+
+impl ShowKnownCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(show_impl, (options, self))
+    }
+}
+
+async fn show_impl(
+    _ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, ShowKnownCommand),
+) -> crate::Result<()> {
+    let storage = opts.state.identities.authenticated_storage().await?;
+    let id = &cmd.identity_id;
+    let history = get_history(&storage, id, IdentityStateConst::CHANGE_HISTORY_KEY)
+        .await?
+        .ok_or_else(|| anyhow!("Identity {id} is not known"))?;
+    println!("Identifier: {id}");
+    print_version(&history);
+    if cmd.full {
+        println!("{history}");
+    }
+
+    if let Some(conflicting) = get_history(
+        &storage,
+        id,
+        IdentityStateConst::CONFLICTING_CHANGE_HISTORY_KEY,
+    )
+    .await?
+    {
+        println!();
+        println!("Conflicting change history:");
+        print_version(&conflicting);
+        if cmd.full {
+            println!("{conflicting}");
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct PushKnownCommand {
+    #[command(flatten)]
+    node_opts: NodeOpts,
+
+    /// Route to the identity update service of the other node
+    #[arg(long, display_order = 900, id = "ROUTE")]
+    to: MultiAddr,
+
+    /// Identifier of the known identity to push, the node identity when not set
+    identity_id: Option<String>,
+}
+
+impl PushKnownCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(push_impl, (options, self))
+    }
+}
+
+async fn push_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, PushKnownCommand),
+) -> crate::Result<()> {
+    let mut rpc = Rpc::background(&ctx, &opts, &cmd.node_opts.api_node)?;
+    rpc.request(api::identities::push_identity_update(
+        &cmd.to,
+        cmd.identity_id.as_deref(),
+    ))
+    .await?;
+    rpc.is_ok()?;
+    Ok(())
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct PullKnownCommand {
+    #[command(flatten)]
+    node_opts: NodeOpts,
+
+    /// Route to the identity update service of the other node
+    #[arg(long, display_order = 900, id = "ROUTE")]
+    from: MultiAddr,
+
+    /// Identifier of the identity to pull
+    identity_id: String,
+}
+
+impl PullKnownCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(pull_impl, (options, self))
+    }
+}
+
+async fn pull_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, PullKnownCommand),
+) -> crate::Result<()> {
+    let mut rpc = Rpc::background(&ctx, &opts, &cmd.node_opts.api_node)?;
+    rpc.request(api::identities::pull_identity_update(
+        &cmd.from,
+        &cmd.identity_id,
+    ))
+    .await?;
+    rpc.is_ok()?;
+    Ok(())
+}
+
+async fn get_history(
+    storage: &Arc<dyn AuthenticatedStorage>,
+    id: &str,
+    key: &str,
+) -> crate::Result<Option<IdentityChangeHistory>> {
+    match storage.get(id, key).await? {
+        Some(data) => Ok(Some(IdentityChangeHistory::import(&data)?)),
+        None => Ok(None),
+    }
+}
+
+fn print_version(history: &IdentityChangeHistory) {
+    let changes = history.as_ref();
+    println!("{:2}Changes: {}", "", changes.len());
+    if let Some(last) = changes.last() {
+        println!("{:2}Last change: {}", "", last.identifier());
+    }
+}
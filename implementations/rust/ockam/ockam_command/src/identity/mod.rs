@@ -1,13 +1,17 @@
 mod create;
 mod default;
 mod delete;
+mod known;
 mod list;
+mod recovery;
 mod show;
 
 pub(crate) use create::CreateCommand;
 pub(crate) use delete::DeleteCommand;
+use known::KnownCommand;
 pub(crate) use list::ListCommand;
 use ockam_api::cli_state::CliState;
+use recovery::RecoveryCommand;
 pub(crate) use show::ShowCommand;
 
 use crate::CommandGlobalOpts;
@@ -33,6 +37,10 @@ pub enum IdentitySubcommand {
     Default(DefaultCommand),
     /// Delete an identity
     Delete(DeleteCommand),
+    /// Manage the recovery key of an identity
+    Recovery(RecoveryCommand),
+    /// Inspect and propagate the change histories of known identities
+    Known(KnownCommand),
 }
 
 impl IdentityCommand {
@@ -43,6 +51,8 @@ impl IdentityCommand {
             IdentitySubcommand::List(c) => c.run(options),
             IdentitySubcommand::Delete(c) => c.run(options),
             IdentitySubcommand::Default(c) => c.run(options),
+            IdentitySubcommand::Recovery(c) => c.run(options),
+            IdentitySubcommand::Known(c) => c.run(options),
         }
     }
 }
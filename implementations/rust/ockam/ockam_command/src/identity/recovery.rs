@@ -0,0 +1,156 @@
+use crate::identity::default_identity_name;
+use crate::util::node_rpc;
+use crate::vault::default_vault_name;
+use crate::CommandGlobalOpts;
+use anyhow::{anyhow, Context as _};
+use clap::{Args, Subcommand};
+use ockam::Context;
+use ockam_core::compat::sync::Arc;
+use ockam_core::vault::{
+    Secret, SecretAttributes, SecretKey, SecretPersistence, SecretType, SecretVault,
+    CURVE25519_SECRET_LENGTH_U32,
+};
+use ockam_vault::Vault;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+/// Manage the recovery key of an identity
+#[derive(Clone, Debug, Args)]
+pub struct RecoveryCommand {
+    #[command(subcommand)]
+    subcommand: RecoverySubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum RecoverySubcommand {
+    /// Create a recovery key, store it in a file and commit its hash to the identity
+    Create(CreateRecoveryKeyCommand),
+    /// Replace the identity root key with its committed recovery key
+    Use(UseRecoveryKeyCommand),
+}
+
+impl RecoveryCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        match self.subcommand {
+            RecoverySubcommand::Create(c) => c.run(options),
+            RecoverySubcommand::Use(c) => c.run(options),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct CreateRecoveryKeyCommand {
+    /// Name of the identity
+    #[arg(default_value_t = default_identity_name())]
+    name: String,
+
+    /// File where the recovery key is written, it should be moved offline
+    #[arg(long, value_name = "FILE")]
+    key_file: PathBuf,
+
+    /// Vault name storing the identity root key
+    #[arg(long, default_value_t = default_vault_name())]
+    vault: String,
+}
+
+impl CreateRecoveryKeyCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(create_impl, (options, self))
+    }
+}
+
+async fn create_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, CreateRecoveryKeyCommand),
+) -> crate::Result<()> {
+    let vault = opts.state.vaults.get(&cmd.vault)?.get().await?;
+    let mut identity_state = opts.state.identities.get(&cmd.name)?;
+    let identity = identity_state.get(&ctx, Arc::new(vault)).await?;
+
+    // The recovery key never touches the identity vault
+    let recovery_vault = Vault::create();
+    let recovery_key = recovery_vault
+        .secret_generate(recovery_key_attributes())
+        .await?;
+    let public_key = recovery_vault.secret_public_key_get(&recovery_key).await?;
+    let secret = recovery_vault.secret_export(&recovery_key).await?;
+
+    // The key is saved before being committed, so that a committed key is never lost
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // Only the owner can read the key
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options
+        .open(&cmd.key_file)
+        .with_context(|| format!("cannot create recovery key file {:?}", cmd.key_file))?;
+    file.write_all(hex::encode(secret.try_as_key()?).as_bytes())?;
+
+    identity.commit_recovery_key(&public_key).await?;
+    identity_state.set_change_history(identity.change_history().await)?;
+
+    println!(
+        "Recovery key for identity '{}' written to {:?}",
+        cmd.name, cmd.key_file
+    );
+    Ok(())
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct UseRecoveryKeyCommand {
+    /// Name of the identity
+    #[arg(default_value_t = default_identity_name())]
+    name: String,
+
+    /// File containing the recovery key
+    #[arg(long, value_name = "FILE")]
+    key_file: PathBuf,
+
+    /// Vault name storing the new root key
+    #[arg(long, default_value_t = default_vault_name())]
+    vault: String,
+}
+
+impl UseRecoveryKeyCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(use_impl, (options, self))
+    }
+}
+
+async fn use_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, UseRecoveryKeyCommand),
+) -> crate::Result<()> {
+    let contents = std::fs::read_to_string(&cmd.key_file)
+        .with_context(|| format!("cannot read recovery key file {:?}", cmd.key_file))?;
+    let secret = hex::decode(contents.trim()).map_err(|e| anyhow!(e))?;
+
+    let vault = opts.state.vaults.get(&cmd.vault)?.get().await?;
+    let recovery_key = vault
+        .secret_import(
+            Secret::Key(SecretKey::new(secret)),
+            recovery_key_attributes(),
+        )
+        .await?;
+
+    let mut identity_state = opts.state.identities.get(&cmd.name)?;
+    let identity = identity_state.get(&ctx, Arc::new(vault)).await?;
+    identity.recover_root_key(&recovery_key).await?;
+    identity_state.set_change_history(identity.change_history().await)?;
+
+    println!(
+        "The root key of identity '{}' was replaced by its recovery key",
+        cmd.name
+    );
+    Ok(())
+}
+
+fn recovery_key_attributes() -> SecretAttributes {
+    SecretAttributes::new(
+        SecretType::Ed25519,
+        SecretPersistence::Persistent,
+        CURVE25519_SECRET_LENGTH_U32,
+    )
+}
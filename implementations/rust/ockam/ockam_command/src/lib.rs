@@ -2,6 +2,7 @@
 //! credential management, and authorization policy enforcement — at scale.
 
 mod admin;
+mod api;
 mod authenticated;
 mod authority;
 mod completion;
@@ -33,6 +34,7 @@ mod vault;
 mod version;
 mod worker;
 
+use api::ApiCommand;
 use authenticated::AuthenticatedCommand;
 use completion::CompletionCommand;
 use configuration::ConfigurationCommand;
@@ -223,6 +225,8 @@ pub enum OckamSubcommand {
     Policy(PolicyCommand),
     #[command(display_order = 821)]
     Worker(WorkerCommand),
+    #[command(display_order = 822)]
+    Api(ApiCommand),
 
     #[command(display_order = 900)]
     Completion(CompletionCommand),
@@ -290,6 +294,7 @@ impl OckamCommand {
             OckamSubcommand::Message(c) => c.run(options),
             OckamSubcommand::Policy(c) => c.run(options),
             OckamSubcommand::Worker(c) => c.run(options),
+            OckamSubcommand::Api(c) => c.run(options),
 
             OckamSubcommand::Completion(c) => c.run(),
 
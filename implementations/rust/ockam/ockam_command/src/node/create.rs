@@ -274,18 +274,6 @@ async fn run_foreground_node(
 
     let projects = cfg.inner().lookup().projects().collect();
 
-    let credential = match &cmd.credential {
-        Some(cred_name) => Some(
-            opts.state
-                .credentials
-                .get(cred_name)?
-                .config()
-                .await?
-                .credential()?,
-        ),
-        None => None,
-    };
-
     let pre_trusted_identities = load_pre_trusted_identities(&cmd)?;
 
     let node_man = NodeManager::create(
@@ -300,7 +288,7 @@ async fn run_foreground_node(
             Some(&cfg.authorities(&node_name)?.snapshot()),
             project_id,
             projects,
-            credential,
+            cmd.credential.clone(),
         ),
         NodeManagerTransportOptions::new(
             (
@@ -570,6 +558,7 @@ async fn start_authority_node(
             no_direct_authentication: true,
             no_token_enrollment: true,
             okta: None,
+            oidc: None,
         };
         authority_node::start_node(&ctx, &configuration).await?;
     }
@@ -71,6 +71,9 @@ pub struct OktaIdentityProviderConfig {
 
     pub(crate) attributes: Vec<String>,
 
+    #[serde(default)]
+    pub(crate) attribute_rules: Vec<String>,
+
     #[serde(default)]
     pub(crate) disabled: bool,
 }
@@ -6,10 +6,12 @@ use crate::Result;
 use anyhow::anyhow;
 use clap::{Args, Subcommand};
 use minicbor::Encode;
+use ockam::identity::IdentityIdentifier;
 use ockam::{Context, TcpTransport};
+use ockam_api::kafka::ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS;
 use ockam_api::nodes::models::services::{
-    StartKafkaConsumerRequest, StartKafkaProducerRequest, StartOktaIdentityProviderRequest,
-    StartServiceRequest,
+    StartKafkaConsumerRequest, StartKafkaOutletRequest, StartKafkaProducerRequest,
+    StartOktaIdentityProviderRequest, StartServiceRequest,
 };
 use ockam_api::port_range::PortRange;
 use ockam_api::DefaultAddress;
@@ -74,8 +76,23 @@ pub enum StartSubCommand {
         #[arg(long)]
         brokers_port_range: PortRange,
         /// The route to the project in ockam orchestrator, expected something like /project/<name>
-        #[arg(long)]
-        project_route: MultiAddr,
+        #[arg(long, required_unless_present = "relay_route")]
+        project_route: Option<MultiAddr>,
+        /// The route to a self-hosted node running the forwarding service,
+        /// used instead of the project to connect consumers to producers
+        #[arg(long, conflicts_with = "project_route", requires = "outlet_route")]
+        relay_route: Option<MultiAddr>,
+        /// The route to a self-hosted node running the kafka outlet service,
+        /// used instead of the project to reach the brokers
+        #[arg(long, conflicts_with = "project_route", requires = "relay_route")]
+        outlet_route: Option<MultiAddr>,
+        /// Names of the record headers encrypted by the producer, comma separated
+        #[arg(long, value_delimiter = ',')]
+        encrypted_headers: Vec<String>,
+        /// Identifiers of the producers trusted to serve data keys, comma separated.
+        /// When omitted, producers must present a credential for the project of the node
+        #[arg(long, value_delimiter = ',', value_name = "IDENTIFIERS")]
+        producer_identifiers: Vec<IdentityIdentifier>,
     },
     KafkaProducer {
         /// The local address of the service
@@ -92,8 +109,52 @@ pub enum StartSubCommand {
         #[arg(long)]
         brokers_port_range: PortRange,
         /// The route to the project in ockam orchestrator, expected something like /project/<name>
+        #[arg(long, required_unless_present = "relay_route")]
+        project_route: Option<MultiAddr>,
+        /// The route to a self-hosted node running the forwarding service,
+        /// used instead of the project to connect consumers to producers
+        #[arg(long, conflicts_with = "project_route", requires = "outlet_route")]
+        relay_route: Option<MultiAddr>,
+        /// The route to a self-hosted node running the kafka outlet service,
+        /// used instead of the project to reach the brokers
+        #[arg(long, conflicts_with = "project_route", requires = "relay_route")]
+        outlet_route: Option<MultiAddr>,
+        /// Names of the record headers to encrypt, comma separated
+        #[arg(long, value_delimiter = ',')]
+        encrypted_headers: Vec<String>,
+        /// Encrypt the record key, the broker only sees a hash of it
+        /// so records with the same key still land on the same partition.
+        /// The hash is keyed with the secret read from this file on the node,
+        /// at least 32 bytes long and shared by every producer of the topics
+        #[arg(long, value_name = "SECRET_FILE")]
+        encrypt_record_key: Option<String>,
+        /// Identifiers of the consumers allowed to retrieve data keys, comma separated.
+        /// When omitted, consumers must present a credential for the project of the node,
+        /// or be authorized by the kafka-producer policy of the node
+        #[arg(long, value_delimiter = ',', value_name = "IDENTIFIERS")]
+        consumer_identifiers: Vec<IdentityIdentifier>,
+    },
+    KafkaOutlet {
+        /// The kafka bootstrap server the outlet connects to, expected as host:port
         #[arg(long)]
-        project_route: MultiAddr,
+        bootstrap_server: String,
+        /// Connect to the brokers using TLS
+        #[arg(long)]
+        tls: bool,
+        /// PEM file with the certificates trusted to verify the brokers,
+        /// in addition to the system ones
+        #[arg(long, requires = "tls")]
+        tls_ca_certificate: Option<String>,
+        /// Authenticate to the brokers with SASL using this username,
+        /// when omitted the clients authenticate themselves through the outlet
+        #[arg(long, requires = "sasl_password_file")]
+        sasl_username: Option<String>,
+        /// File, on the node, holding the password used with --sasl-username
+        #[arg(long, requires = "sasl_username")]
+        sasl_password_file: Option<String>,
+        /// SASL mechanism used with --sasl-username, only PLAIN is supported
+        #[arg(long, default_value = "PLAIN", requires = "sasl_username")]
+        sasl_mechanism: String,
     },
 }
 
@@ -185,13 +246,25 @@ async fn run_impl(
             bootstrap_server_port,
             brokers_port_range,
             project_route,
+            relay_route,
+            outlet_route,
+            encrypted_headers,
+            producer_identifiers,
         } => {
             let payload = StartKafkaConsumerRequest::new(
                 bootstrap_server_ip,
                 bootstrap_server_port,
                 brokers_port_range,
                 project_route,
-            );
+                encrypted_headers,
+            )
+            .with_producer_identifiers(producer_identifiers);
+            let payload = match (relay_route, outlet_route) {
+                (Some(relay_route), Some(outlet_route)) => {
+                    payload.with_self_hosted_relay(relay_route, outlet_route)
+                }
+                _ => payload,
+            };
             let payload = StartServiceRequest::new(payload, &addr);
             let req = Request::post("/node/services/kafka_consumer").body(payload);
             start_service_impl(
@@ -211,13 +284,27 @@ async fn run_impl(
             bootstrap_server_port,
             brokers_port_range,
             project_route,
+            relay_route,
+            outlet_route,
+            encrypted_headers,
+            encrypt_record_key,
+            consumer_identifiers,
         } => {
             let payload = StartKafkaProducerRequest::new(
                 bootstrap_server_ip,
                 bootstrap_server_port,
                 brokers_port_range,
                 project_route,
-            );
+                encrypted_headers,
+                encrypt_record_key,
+            )
+            .with_consumer_identifiers(consumer_identifiers);
+            let payload = match (relay_route, outlet_route) {
+                (Some(relay_route), Some(outlet_route)) => {
+                    payload.with_self_hosted_relay(relay_route, outlet_route)
+                }
+                _ => payload,
+            };
             let payload = StartServiceRequest::new(payload, &addr);
             let req = Request::post("/node/services/kafka_producer").body(payload);
             start_service_impl(
@@ -231,6 +318,26 @@ async fn run_impl(
             )
             .await?
         }
+        StartSubCommand::KafkaOutlet {
+            bootstrap_server,
+            tls,
+            tls_ca_certificate,
+            sasl_username,
+            sasl_password_file,
+            sasl_mechanism,
+        } => {
+            let mut payload = StartKafkaOutletRequest::new(bootstrap_server);
+            if tls {
+                payload = payload.with_tls(tls_ca_certificate);
+            }
+            if let (Some(username), Some(password_file)) = (sasl_username, sasl_password_file) {
+                payload = payload.with_sasl(sasl_mechanism, username, password_file);
+            }
+            let addr = ORCHESTRATOR_KAFKA_BOOTSTRAP_ADDRESS;
+            let payload = StartServiceRequest::new(payload, addr);
+            let req = Request::post("/node/services/kafka_outlet").body(payload);
+            start_service_impl(ctx, &opts, node_name, addr, "KafkaOutlet", req, Some(&tcp)).await?
+        }
     }
 
     Ok(())
@@ -329,7 +436,8 @@ pub async fn start_okta_identity_provider(
         &cfg.certificate,
         cfg.attributes.iter().map(|s| s as &str).collect(),
         cfg.project.as_bytes(),
-    );
+    )
+    .with_attribute_rules(cfg.attribute_rules.iter().map(|s| s as &str).collect());
     let req = Request::post(format!(
         "/node/services/{}",
         DefaultAddress::OKTA_IDENTITY_PROVIDER
@@ -177,16 +177,18 @@ pub(crate) mod credentials {
     pub(crate) fn present_credential(
         to: &MultiAddr,
         oneway: bool,
+        disclosed_attributes: Option<Vec<String>>,
     ) -> RequestBuilder<PresentCredentialRequest> {
-        let b = PresentCredentialRequest::new(to, oneway);
+        let b = PresentCredentialRequest::new(to, oneway, disclosed_attributes);
         Request::post("/node/credentials/actions/present").body(b)
     }
 
     pub(crate) fn get_credential<'r>(
         overwrite: bool,
         identity_name: Option<String>,
+        audience: Vec<String>,
     ) -> RequestBuilder<'r, GetCredentialRequest> {
-        let b = GetCredentialRequest::new(overwrite, identity_name);
+        let b = GetCredentialRequest::new(overwrite, identity_name, audience);
         Request::post("/node/credentials/actions/get").body(b)
     }
 }
@@ -197,6 +199,30 @@ fn node_service(service_name: &str) -> String {
 }
 
 /// Helpers to create enroll API requests
+pub(crate) mod identities {
+    use ockam_api::nodes::models::identity::{
+        PullIdentityUpdateRequest, PushIdentityUpdateRequest,
+    };
+
+    use super::*;
+
+    pub(crate) fn push_identity_update<'r>(
+        to: &MultiAddr,
+        identity_id: Option<&'r str>,
+    ) -> RequestBuilder<'r, PushIdentityUpdateRequest<'r>> {
+        let b = PushIdentityUpdateRequest::new(to, identity_id);
+        Request::post("/node/identities/actions/push").body(b)
+    }
+
+    pub(crate) fn pull_identity_update<'r>(
+        from: &MultiAddr,
+        identity_id: &'r str,
+    ) -> RequestBuilder<'r, PullIdentityUpdateRequest<'r>> {
+        let b = PullIdentityUpdateRequest::new(from, identity_id);
+        Request::post("/node/identities/actions/pull").body(b)
+    }
+}
+
 pub(crate) mod enroll {
     use ockam_api::cloud::enroll::auth0::{Auth0Token, AuthenticateAuth0Token};
 
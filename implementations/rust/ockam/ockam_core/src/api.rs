@@ -10,6 +10,8 @@ use minicbor::encode::{self, Encoder, Write};
 use minicbor::{Decode, Decoder, Encode};
 use tinyvec::ArrayVec;
 
+pub mod rpc;
+
 pub const SCHEMA: &str = core::include_str!("schema.cddl");
 
 #[cfg(feature = "tag")]
@@ -174,7 +176,7 @@ pub fn internal_error<'a>(r: &'a Request, msg: &'a str) -> ResponseBuilder<Error
 pub struct Id(#[n(0)] u32);
 
 /// Request methods.
-#[derive(Debug, Copy, Clone, Encode, Decode)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode)]
 #[rustfmt::skip]
 #[cbor(index_only)]
 pub enum Method {
@@ -656,9 +658,19 @@ pub fn is_ok(label: &str, buf: &[u8]) -> Result<()> {
 
 /// Decode response and an optional body.
 pub fn decode_option<'a, 'b, T: Decode<'b, ()>>(
+    label: &'a str,
+    struct_name: impl Into<Option<&'a str>>,
+    buf: &'b [u8],
+) -> Result<Option<T>> {
+    decode_option_with(label, struct_name, buf, &mut ())
+}
+
+/// Decode a response with the given decoding context, see [`decode_option`].
+pub fn decode_option_with<'a, 'b, C, T: Decode<'b, C>>(
     label: &'a str,
     #[allow(unused_variables)] struct_name: impl Into<Option<&'a str>>,
     buf: &'b [u8],
+    ctx: &mut C,
 ) -> Result<Option<T>> {
     let mut d = Decoder::new(buf);
     let res = response(label, &mut d)?;
@@ -666,7 +678,7 @@ pub fn decode_option<'a, 'b, T: Decode<'b, ()>>(
         Some(Status::Ok) => {
             #[cfg(feature = "tag")]
             assert_response_match(struct_name, buf, cddl());
-            Ok(Some(d.decode()?))
+            Ok(Some(d.decode_with(ctx)?))
         }
         Some(Status::NotFound) => Ok(None),
         _ => Err(error(label, &res, &mut d)),
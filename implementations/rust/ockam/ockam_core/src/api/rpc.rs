@@ -0,0 +1,437 @@
+//! Typed endpoints and route tables for API services.
+//!
+//! An API service declares its [`Endpoint`]s with the
+//! [`endpoints!`](crate::endpoints) macro, implements [`Handle`] for each
+//! of them and lists them in a table of [`Handler`]s.  The [`dispatch`]
+//! function then takes care of decoding the request header and body,
+//! matching the request against the table and encoding the response,
+//! including the error responses for unknown paths, invalid methods and
+//! bad bodies.  Services whose handlers need more than their own state,
+//! e.g. the context of the worker, use [`dispatch_with`] instead.
+//!
+//! ```ignore
+//! ockam_core::endpoints! {
+//!     /// Get the attributes of an identity
+//!     pub struct GetAttributes<'a>: Get "/:id" -> AttributesEntry;
+//! }
+//!
+//! impl Server {
+//!     const HANDLERS: &'static [Handler<Self>] = &[Handler::of::<GetAttributes>()];
+//!
+//!     async fn on_request(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+//!         dispatch(self, Self::HANDLERS, data).await
+//!     }
+//! }
+//! ```
+//!
+//! The same endpoint types are used by the typed client of `ockam_node`.
+
+use super::{bad_request, internal_error, invalid_method, unknown_path};
+use super::{Error, Id, Method, Request, Response, Status};
+use crate::compat::boxed::Box;
+use crate::compat::string::{String, ToString};
+use crate::compat::vec::Vec;
+use crate::errcode::{Kind, Origin};
+use crate::{async_trait, Result};
+use core::future::Future;
+use core::pin::Pin;
+use minicbor::{Decode, Decoder, Encode};
+
+/// The CBOR encoding of `()`, the body of requests without body.
+const UNIT: &[u8] = &[0x80];
+
+/// A typed endpoint of an API service.
+///
+/// Endpoints are usually declared with the [`endpoints!`](crate::endpoints)
+/// macro.
+pub trait Endpoint: Send + Sync + 'static {
+    /// The request method.
+    const METHOD: Method;
+
+    /// The path pattern, where segments starting with `:` are parameters
+    /// matching any non-empty segment, e.g. `/secrets/:key_id`.
+    const PATH: &'static str;
+
+    /// Whether requests carry a body, `Req` is `()` otherwise.
+    const HAS_BODY: bool;
+
+    /// The request body.
+    type Req<'a>: Encode<()> + Decode<'a, ()> + Send;
+
+    /// The body of successful responses.
+    type Res<'a>: Encode<()> + Decode<'a, Self::Ctx> + Send;
+
+    /// The context needed to decode response bodies, e.g. the operation
+    /// requested when the shape of the response depends on it.
+    type Ctx: Send;
+
+    /// The context to decode the response to the given request body.
+    fn context(req: &Self::Req<'_>) -> Self::Ctx;
+
+    /// Build a request path by substituting the parameters of the path
+    /// pattern with the given values, in order.
+    fn path(params: &[&str]) -> Result<String> {
+        let mut params = params.iter();
+        let mut segments = Vec::new();
+        for segment in Self::PATH.split('/') {
+            if segment.starts_with(':') {
+                let value = params.next().ok_or_else(|| {
+                    crate::Error::new(
+                        Origin::Api,
+                        Kind::Invalid,
+                        format!("missing parameter {segment} of {}", Self::PATH),
+                    )
+                })?;
+                segments.push(*value)
+            } else {
+                segments.push(segment)
+            }
+        }
+        if params.next().is_some() {
+            return Err(crate::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!("too many parameters for {}", Self::PATH),
+            ));
+        }
+        Ok(segments.join("/"))
+    }
+}
+
+/// The outcome of a handled request, other than an internal error.
+#[derive(Debug)]
+pub enum Reply<T> {
+    /// Respond with status `Ok` and the given body.
+    Ok(T),
+    /// Respond with status `NotFound` and no body.
+    NotFound,
+    /// Respond with the given status and an error body with the given
+    /// message.
+    Error(Status, String),
+    /// Respond with an already encoded response, e.g. one relayed from
+    /// another service.
+    Encoded(Vec<u8>),
+}
+
+impl<T> From<T> for Reply<T> {
+    fn from(body: T) -> Self {
+        Reply::Ok(body)
+    }
+}
+
+/// A decoded request of an endpoint, with the dispatch context `C`.
+pub struct Call<'a, E: Endpoint, C = ()> {
+    request: &'a Request<'a>,
+    params: Vec<(&'a str, &'a str)>,
+    body: E::Req<'a>,
+    ctx: &'a mut C,
+}
+
+impl<'a, E: Endpoint, C> Call<'a, E, C> {
+    /// The request header.
+    pub fn request(&self) -> &'a Request<'a> {
+        self.request
+    }
+
+    /// The value of the given path parameter.
+    pub fn param(&self, name: &str) -> Result<&'a str> {
+        self.params
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| *v)
+            .ok_or_else(|| {
+                crate::Error::new(
+                    Origin::Api,
+                    Kind::Invalid,
+                    format!("unknown parameter {name} of {}", E::PATH),
+                )
+            })
+    }
+
+    /// The request body.
+    pub fn body(&self) -> &E::Req<'a> {
+        &self.body
+    }
+
+    /// Take the request body.
+    pub fn into_body(self) -> E::Req<'a> {
+        self.body
+    }
+
+    /// The dispatch context.
+    pub fn ctx(&mut self) -> &mut C {
+        self.ctx
+    }
+
+    /// Take the dispatch context and the request body.
+    pub fn into_parts(self) -> (&'a mut C, E::Req<'a>) {
+        (self.ctx, self.body)
+    }
+}
+
+/// Handle the requests of an endpoint.
+///
+/// Errors are turned into responses with status `InternalServerError`.
+#[async_trait]
+pub trait Handle<E: Endpoint, C: Send = ()>: Send {
+    /// Handle a request.
+    async fn handle<'a>(&'a mut self, call: Call<'a, E, C>) -> Result<Reply<E::Res<'a>>>;
+}
+
+/// A matched request, passed to the handler function of an endpoint.
+struct Incoming<'a, S, C> {
+    state: &'a mut S,
+    ctx: &'a mut C,
+    req: &'a Request<'a>,
+    params: Vec<(&'a str, &'a str)>,
+    body: &'a [u8],
+}
+
+type HandlerFn<S, C> =
+    for<'a> fn(Incoming<'a, S, C>) -> Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send + 'a>>;
+
+/// An entry of the route table of a service `S`, dispatched with the
+/// context `C`.
+pub struct Handler<S, C = ()> {
+    method: Method,
+    path: &'static str,
+    call: HandlerFn<S, C>,
+}
+
+impl<S: Send, C: Send> Handler<S, C> {
+    /// The handler of the endpoint `E`.
+    pub const fn of<E: Endpoint>() -> Self
+    where
+        S: Handle<E, C>,
+    {
+        Handler {
+            method: E::METHOD,
+            path: E::PATH,
+            call: call::<S, C, E>,
+        }
+    }
+
+    /// The request method of this handler.
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// The path pattern of this handler.
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+}
+
+fn call<S: Handle<E, C>, C: Send, E: Endpoint>(
+    incoming: Incoming<'_, S, C>,
+) -> Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send + '_>> {
+    let Incoming {
+        state,
+        ctx,
+        req,
+        params,
+        body,
+    } = incoming;
+    Box::pin(async move {
+        let body = if !E::HAS_BODY {
+            minicbor::decode(UNIT)?
+        } else if !req.has_body() {
+            return Ok(bad_request(req, "empty body").to_vec()?);
+        } else {
+            match minicbor::decode(body) {
+                Ok(body) => body,
+                Err(e) => {
+                    let msg = e.to_string();
+                    return Ok(bad_request(req, &msg).to_vec()?);
+                }
+            }
+        };
+        let call = Call {
+            request: req,
+            params,
+            body,
+            ctx,
+        };
+        let res = match state.handle(call).await? {
+            Reply::Ok(body) => Response::ok(req.id()).body(body).to_vec()?,
+            Reply::NotFound => Response::not_found(req.id()).to_vec()?,
+            Reply::Error(status, msg) => {
+                let mut e = Error::new(req.path()).with_message(msg);
+                if let Some(m) = req.method() {
+                    e = e.with_method(m)
+                }
+                Response::builder(req.id(), status).body(e).to_vec()?
+            }
+            Reply::Encoded(res) => res,
+        };
+        Ok(res)
+    })
+}
+
+/// Match a request path against a path pattern and return the values
+/// of the pattern parameters.
+pub fn match_path<'a>(pattern: &'a str, path: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+    let mut params = Vec::new();
+    let mut segments = path.trim_start_matches('/').split('/');
+    for p in pattern.trim_start_matches('/').split('/') {
+        let s = segments.next()?;
+        match p.strip_prefix(':') {
+            Some(name) if !s.is_empty() => params.push((name, s)),
+            None if p == s => {}
+            _ => return None,
+        }
+    }
+    if segments.next().is_some() {
+        return None;
+    }
+    Some(params)
+}
+
+/// Decode a request, pass it to the matching handler and return the
+/// encoded response.
+pub async fn dispatch<S: Send>(
+    state: &mut S,
+    handlers: &[Handler<S>],
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    dispatch_with(state, &mut (), handlers, data).await
+}
+
+/// Like [`dispatch`], passing the context `ctx` to the handler.
+pub async fn dispatch_with<S: Send, C: Send>(
+    state: &mut S,
+    ctx: &mut C,
+    handlers: &[Handler<S, C>],
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    let mut dec = Decoder::new(data);
+    let req: Request = match dec.decode() {
+        Ok(r) => r,
+        Err(e) => {
+            let err = Error::default().with_message(e.to_string());
+            return Ok(Response::bad_request(Id::default()).body(err).to_vec()?);
+        }
+    };
+
+    trace! {
+        target: "ockam_api",
+        id     = %req.id(),
+        method = ?req.method(),
+        path   = %req.path(),
+        body   = %req.has_body(),
+        "request"
+    }
+
+    let method = match req.method() {
+        Some(m) => m,
+        None => return Ok(invalid_method(&req).to_vec()?),
+    };
+
+    let mut known_path = false;
+    for handler in handlers {
+        if let Some(params) = match_path(handler.path, req.path()) {
+            if handler.method != method {
+                known_path = true;
+                continue;
+            }
+            let body = &data[dec.position()..];
+            let incoming = Incoming {
+                state,
+                ctx,
+                req: &req,
+                params,
+                body,
+            };
+            return match (handler.call)(incoming).await {
+                Ok(res) => Ok(res),
+                Err(e) => {
+                    let msg = e.to_string();
+                    Ok(internal_error(&req, &msg).to_vec()?)
+                }
+            };
+        }
+    }
+
+    if known_path {
+        Ok(invalid_method(&req).to_vec()?)
+    } else {
+        Ok(unknown_path(&req).to_vec()?)
+    }
+}
+
+/// Declare [`Endpoint`]s.
+///
+/// Each endpoint is a unit struct, declared with the name of the
+/// lifetime used by its body types, its method, its path pattern, an
+/// optional request body and its response body:
+///
+/// ```ignore
+/// ockam_core::endpoints! {
+///     /// Create a secret
+///     pub struct CreateSecret<'a>: Post "/secrets" (CreateSecretRequest<'a>) -> CreateSecretResponse<'a>;
+///     /// Delete a secret
+///     pub struct DeleteSecret<'a>: Delete "/secrets/:key_id" -> ();
+/// }
+/// ```
+#[macro_export]
+macro_rules! endpoints {
+    (@has_body) => { false };
+    (@has_body $req:ty) => { true };
+    (@req) => { () };
+    (@req $req:ty) => { $req };
+    ($(
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident<$lt:lifetime>: $method:ident $path:literal $(($req:ty))? -> $res:ty;
+    )*) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug, Clone, Copy)]
+            $vis struct $name;
+
+            impl $crate::api::rpc::Endpoint for $name {
+                const METHOD: $crate::api::Method = $crate::api::Method::$method;
+                const PATH: &'static str = $path;
+                const HAS_BODY: bool = $crate::endpoints!(@has_body $($req)?);
+                type Req<$lt> = $crate::endpoints!(@req $($req)?);
+                type Res<$lt> = $res;
+                type Ctx = ();
+
+                fn context(_: &Self::Req<'_>) -> Self::Ctx {}
+            }
+        )*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{match_path, Endpoint};
+
+    crate::endpoints! {
+        struct GetPublicKey<'a>: Get "/secrets/:key_id/public_key" -> ();
+        struct List<'a>: Get "/" -> ();
+    }
+
+    #[test]
+    fn path_patterns() {
+        assert_eq!(
+            match_path("/secrets/:key_id/public_key", "secrets/k1/public_key"),
+            Some(vec![("key_id", "k1")])
+        );
+        assert_eq!(match_path("/secrets/:key_id", "/secrets/"), None);
+        assert_eq!(match_path("/secrets/:key_id", "/secrets/k1/x"), None);
+        assert_eq!(match_path("/", "/"), Some(vec![]));
+        assert_eq!(match_path("/:id", "/"), None);
+        assert_eq!(match_path("/:id", "/i1"), Some(vec![("id", "i1")]));
+    }
+
+    #[test]
+    fn endpoint_paths() {
+        assert_eq!(
+            GetPublicKey::path(&["k1"]).unwrap(),
+            "/secrets/k1/public_key"
+        );
+        assert!(GetPublicKey::path(&[]).is_err());
+        assert!(GetPublicKey::path(&["k1", "k2"]).is_err());
+        assert_eq!(List::path(&[]).unwrap(), "/");
+    }
+}
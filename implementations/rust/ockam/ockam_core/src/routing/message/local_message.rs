@@ -30,6 +30,27 @@ impl LocalInfo {
     }
 }
 
+/// The priority class of a [`LocalMessage`].
+///
+/// Workers receive the messages with a `Control` priority before the
+/// messages with a `Normal` priority waiting in their mailbox.  This keeps
+/// control traffic, like heartbeats and session pings, responsive while
+/// a worker is flooded with data.  Messages with different priorities
+/// may be received in a different order than they were sent.
+///
+/// The priority is local to a node, it is not sent to other nodes by
+/// transports.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, Hash, Ord, PartialOrd, Eq, PartialEq,
+)]
+pub enum Priority {
+    /// Data traffic
+    #[default]
+    Normal,
+    /// Control traffic
+    Control,
+}
+
 /// A message type that is routed locally within a single node.
 ///
 /// `LocalMessage` consists of a [`TransportMessage`] and
@@ -61,6 +82,7 @@ impl LocalInfo {
 pub struct LocalMessage {
     transport_message: TransportMessage,
     local_info: Vec<LocalInfo>,
+    priority: Priority,
 }
 
 impl LocalMessage {
@@ -84,6 +106,10 @@ impl LocalMessage {
     pub fn dissolve(self) -> (TransportMessage, Vec<LocalInfo>) {
         (self.transport_message, self.local_info)
     }
+    /// Return the priority of this message.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
 }
 
 impl LocalMessage {
@@ -112,6 +138,13 @@ impl LocalMessage {
         LocalMessage {
             transport_message,
             local_info,
+            priority: Priority::Normal,
         }
     }
+
+    /// Set the priority of this message.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
 }
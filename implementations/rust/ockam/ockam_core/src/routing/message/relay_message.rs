@@ -1,4 +1,4 @@
-use crate::{Address, LocalMessage, Route};
+use crate::{Address, LocalMessage, Priority, Route};
 
 /// A message addressed to the relay responsible for delivery of the
 /// wrapped [`LocalMessage`]
@@ -46,6 +46,11 @@ impl RelayMessage {
         &self.local_msg.transport().return_route
     }
 
+    /// Priority of the wrapped `LocalMessage`
+    pub fn priority(&self) -> Priority {
+        self.local_msg.priority()
+    }
+
     /// Local message
     pub fn local_message(&self) -> &LocalMessage {
         &self.local_msg
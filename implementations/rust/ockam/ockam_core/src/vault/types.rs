@@ -39,8 +39,11 @@ cfg_if! {
         pub type PublicKeyVec = heapless::Vec<u8, 65>;
         /// Buffer for small vectors (e.g. an array of attributes). The maximum length is 4 elements.
         pub type SmallBuffer<T> = heapless::Vec<T, 4>;
-        /// Buffer for large binaries (e.g. encrypted data). The maximum length is 512 elements.
-        pub type Buffer<T> = heapless::Vec<T, 512>;
+        /// The maximum length of a [`Buffer`]: large enough to encrypt
+        /// the longest message carried by the BLE and serial transports.
+        pub const MAX_BUFFER_LENGTH: usize = 1024;
+        /// Buffer for large binaries (e.g. encrypted data). The maximum length is [`MAX_BUFFER_LENGTH`] elements.
+        pub type Buffer<T> = heapless::Vec<T, MAX_BUFFER_LENGTH>;
         /// Signature Vector. The maximum length is 64 characters.
         pub type KeyId = heapless::String<64>;
         /// Signature Vector. The maximum size is 112 bytes.
@@ -26,6 +26,14 @@ use atsame54_xpro as _;
 #[cfg(feature = "stm32f4")]
 use stm32f4xx_hal as _;
 
+/// The core clock at reset
+#[cfg(feature = "qemu")]
+const CORE_HZ: u32 = 25_000_000;
+#[cfg(feature = "atsame54")]
+const CORE_HZ: u32 = 48_000_000;
+#[cfg(feature = "stm32f4")]
+const CORE_HZ: u32 = 16_000_000;
+
 #[cfg(feature = "cortexm")]
 #[cortex_m_rt::entry]
 fn entry() -> ! {
@@ -42,6 +50,13 @@ fn entry() -> ! {
         tracing_subscriber::register();
     }
 
+    // register a timer driver, for the receive timeouts
+    #[cfg(feature = "cortexm")]
+    {
+        use hello_ockam_no_std::timer;
+        timer::register(CORE_HZ);
+    }
+
     // execute main program entry point
     match main() {
         Ok(_) => (),
@@ -75,7 +90,7 @@ async fn main(mut ctx: Context) -> Result<()> {
     let vault = Vault::create();
 
     // Create an Identity to represent Bob.
-    let bob = Identity::create(&ctx, vault).await?;
+    let bob = Identity::create(&ctx, vault.clone()).await?;
 
     // Create a secure channel listener for Bob that will wait for requests to
     // initiate an Authenticated Key Exchange.
@@ -7,3 +7,6 @@ mod hop;
 pub use hop::*;
 
 pub mod tracing_subscriber;
+
+#[cfg(feature = "cortexm")]
+pub mod timer;
@@ -0,0 +1,47 @@
+/// A timer driver for Cortex-M targets, counting SysTick interrupts
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::time::Duration;
+use cortex_m::peripheral::syst::SystClkSource;
+use cortex_m_rt::exception;
+use ockam::compat::tokio::time::{set_timer_driver, TimerDriver};
+
+/// The number of milliseconds elapsed since the driver was registered
+static MILLIS: AtomicU32 = AtomicU32::new(0);
+
+#[exception]
+fn SysTick() {
+    MILLIS.fetch_add(1, Ordering::Relaxed);
+}
+
+struct SysTickDriver;
+
+impl TimerDriver for SysTickDriver {
+    fn now(&self) -> Duration {
+        Duration::from_millis(MILLIS.load(Ordering::Relaxed) as u64)
+    }
+
+    fn wait_until(&self, deadline: Option<Duration>) {
+        // The next SysTick interrupt wakes the processor up
+        if deadline.is_some() {
+            cortex_m::asm::wfi();
+        }
+    }
+}
+
+static DRIVER: SysTickDriver = SysTickDriver;
+
+/// Start a millisecond SysTick for a core clocked at `core_hz` and
+/// register it as the driver of the executor timers
+pub fn register(core_hz: u32) {
+    let mut syst = match cortex_m::Peripherals::take() {
+        Some(peripherals) => peripherals.SYST,
+        None => return,
+    };
+    syst.set_clock_source(SystClkSource::Core);
+    syst.set_reload(core_hz / 1_000 - 1);
+    syst.clear_current();
+    syst.enable_counter();
+    syst.enable_interrupt();
+
+    set_timer_driver(&DRIVER).ok();
+}
@@ -0,0 +1,186 @@
+//! An XX secure channel established over a serial link
+//!
+//! The test runs on the host with either feature set:
+//!
+//! ```
+//! cargo test
+//! cargo test --no-default-features --features="alloc, no_std"
+//! ```
+//!
+//! Two UARTs of the same node are wired together in memory, one used
+//! by a BLE client and the other by a BLE server.  Without the standard
+//! library the timers of the node run on the host clock, as they would
+//! run on the hardware timer of a device.
+
+use core::convert::Infallible;
+use embedded_hal::serial;
+#[cfg(not(feature = "std"))]
+use ockam::compat::tokio::time::{set_timer_driver, TimerDriver};
+use ockam::{
+    identity::{Identity, TrustEveryonePolicy},
+    route,
+    vault::Vault,
+    Context, Executor, NodeBuilder, Result,
+};
+use ockam_transport_ble::driver::serial::SerialAdapter;
+use ockam_transport_ble::driver::{BleEvent, BleStreamDriver};
+use ockam_transport_ble::{BleClient, BleServer, BleTransport, BLE};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// The bytes in flight on one direction of the link
+type Wire = Arc<Mutex<VecDeque<u8>>>;
+
+/// A UART writing to one wire and reading from another
+struct Uart {
+    tx: Wire,
+    rx: Wire,
+}
+
+impl serial::Read<u8> for Uart {
+    type Error = Infallible;
+
+    fn read(&mut self) -> nb::Result<u8, Infallible> {
+        self.rx
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl serial::Write<u8> for Uart {
+    type Error = Infallible;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        self.tx.lock().unwrap().push_back(byte);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Two UARTs with the TX of each wired to the RX of the other
+fn uart_pair() -> (Uart, Uart) {
+    let a = Wire::default();
+    let b = Wire::default();
+    (
+        Uart {
+            tx: a.clone(),
+            rx: b.clone(),
+        },
+        Uart { tx: b, rx: a },
+    )
+}
+
+/// The monotonic clock of the host
+#[cfg(not(feature = "std"))]
+struct HostClock(Mutex<Option<std::time::Instant>>);
+
+#[cfg(not(feature = "std"))]
+impl TimerDriver for HostClock {
+    fn now(&self) -> core::time::Duration {
+        let mut origin = self.0.lock().unwrap();
+        origin.get_or_insert_with(std::time::Instant::now).elapsed()
+    }
+}
+
+/// Run the node timers on the host clock, as the standard runtime does
+fn use_host_clock() {
+    #[cfg(not(feature = "std"))]
+    {
+        static CLOCK: HostClock = HostClock(Mutex::new(None));
+        let _ = set_timer_driver(&CLOCK);
+    }
+}
+
+/// Send a message to the node itself through a secure channel over a
+/// serial link, and return the received message
+async fn send_over_serial_channel(ctx: &mut Context) -> Result<String> {
+    let (client_uart, server_uart) = uart_pair();
+    let ble = BleTransport::create(ctx).await?;
+    ble.listen(
+        BleServer::with_adapter(SerialAdapter::new(server_uart)),
+        "bob",
+    )
+    .await?;
+    ble.connect(
+        BleClient::with_adapter(SerialAdapter::new(client_uart)),
+        "bob",
+    )
+    .await?;
+
+    let vault = Vault::create();
+    let bob = Identity::create(ctx, vault.clone()).await?;
+    bob.create_secure_channel_listener("listener", TrustEveryonePolicy)
+        .await?;
+
+    let alice = Identity::create(ctx, vault).await?;
+    let channel = alice
+        .create_secure_channel(route![(BLE, "bob"), "listener"], TrustEveryonePolicy)
+        .await?;
+
+    ctx.send(route![channel, "app"], "Hello Ockam!".to_string())
+        .await?;
+    Ok(ctx.receive::<String>().await?.body())
+}
+
+#[test]
+fn secure_channel_over_serial_link() {
+    use_host_clock();
+
+    let (mut ctx, mut executor) = NodeBuilder::new().no_logging().build() as (Context, Executor);
+
+    // The node is stopped even if the exchange fails
+    let received = executor
+        .execute(async move {
+            let received = send_over_serial_channel(&mut ctx).await;
+            ctx.stop().await?;
+            received
+        })
+        .unwrap()
+        .unwrap();
+
+    assert_eq!("Hello Ockam!", received);
+}
+
+/// Write a corrupted fragment followed by a valid one, and return the
+/// first fragment received
+async fn receive_after_corrupted_fragment() -> Result<Vec<u8>> {
+    let (client_uart, server_uart) = uart_pair();
+    let wire = client_uart.tx.clone();
+    let mut client = SerialAdapter::new(client_uart);
+    let mut server = SerialAdapter::new(server_uart);
+
+    client.write(b"hello").await?;
+    // Flip a bit of the first byte after the leading frame delimiter
+    wire.lock().unwrap()[1] ^= 0x01;
+    client.write(b"world").await?;
+
+    let mut buffer = [0u8; 64];
+    loop {
+        if let BleEvent::Received(fragment) = server.poll(&mut buffer).await? {
+            return Ok(fragment.to_vec());
+        }
+    }
+}
+
+#[test]
+fn corrupted_fragments_are_dropped() {
+    use_host_clock();
+
+    let (mut ctx, mut executor) = NodeBuilder::new().no_logging().build() as (Context, Executor);
+
+    let received = executor
+        .execute(async move {
+            let received = receive_after_corrupted_fragment().await;
+            ctx.stop().await?;
+            received
+        })
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(b"world".to_vec(), received);
+}
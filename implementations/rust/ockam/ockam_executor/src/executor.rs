@@ -1,8 +1,6 @@
-use core::cell::UnsafeCell;
 use core::future::Future;
-use core::mem::MaybeUninit;
 use core::pin::Pin;
-use core::sync::atomic::{self, AtomicBool, AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use core::task::{Context, Poll, Waker};
 
 use crossbeam_queue::SegQueue;
@@ -10,75 +8,69 @@ use ockam_core::compat::boxed::Box;
 use ockam_core::compat::collections::BTreeMap;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::task::Wake;
+use spin::{Mutex, Once};
 
-/// Returns current executor.
-/// WARNING: TODO this is not thread-safe
-pub fn current() -> &'static Executor<'static> {
-    static INIT: AtomicBool = AtomicBool::new(false);
-    static mut EXECUTOR: UnsafeCell<MaybeUninit<Executor>> = UnsafeCell::new(MaybeUninit::uninit());
+use crate::time;
 
-    if INIT.load(Ordering::Relaxed) {
-        unsafe { &*(EXECUTOR.get() as *const Executor) }
-    } else {
-        unsafe {
-            let executorp = EXECUTOR.get() as *mut Executor;
-            executorp.write(Executor::new());
-            atomic::compiler_fence(Ordering::Release);
-            INIT.store(true, Ordering::Relaxed);
-            &*executorp
-        }
-    }
+/// Returns the executor of this program, creating it on first use.
+///
+/// The executor is created exactly once, even when several threads or
+/// interrupt handlers call this function concurrently.
+pub fn current() -> &'static Executor {
+    static EXECUTOR: Once<Executor> = Once::new();
+    EXECUTOR.call_once(Executor::new)
 }
 
-/// Executor
-pub struct Executor<'a> {
-    tasks: UnsafeCell<BTreeMap<TaskId, Box<Task>>>,
-    waker_cache: UnsafeCell<BTreeMap<TaskId, Waker>>,
-    // TODO tasks: Arc<Mutex<BTreeMap<TaskId, Box<Task>>>>,
-    // TODO waker_cache: Arc<Mutex<BTreeMap<TaskId, Waker>>>,
+/// A single-threaded executor
+///
+/// Tasks can be spawned, and woken, from any thread, but they are only
+/// polled by the thread running [`Executor::block_on`].
+pub struct Executor {
+    tasks: Mutex<BTreeMap<TaskId, Task>>,
+    waker_cache: Mutex<BTreeMap<TaskId, Waker>>,
     task_queue: Arc<SegQueue<TaskId>>,
-    marker: core::marker::PhantomData<&'a ()>,
 }
 
-impl<'a> Executor<'a> {
+impl Executor {
     pub fn new() -> Self {
         Self {
-            tasks: UnsafeCell::new(BTreeMap::new()),
-            waker_cache: UnsafeCell::new(BTreeMap::new()),
-            // TODO tasks: Arc::new(Mutex::new(BTreeMap::new())),
-            // TODO waker_cache: Arc::new(Mutex::new(BTreeMap::new())),
+            tasks: Mutex::new(BTreeMap::new()),
+            waker_cache: Mutex::new(BTreeMap::new()),
             task_queue: Arc::new(SegQueue::new()),
-            marker: core::marker::PhantomData,
         }
     }
 
+    /// Run the spawned tasks until `future` completes
+    ///
+    /// When no task is ready to make progress, the executor waits for
+    /// the next timer with the [`TimerDriver`](crate::time::TimerDriver).
     pub fn block_on<T>(&self, future: impl Future<Output = T>) -> T {
-        let mut node = Node {
-            id: TaskId::new(),
-            _name: "Node",
-            future: UnsafeCell::new(future),
-        };
-        let node_waker = NodeWaker::new(node.id);
+        pin_utils::pin_mut!(future);
+        let node_waker = Arc::new(NodeWaker {
+            woken: AtomicBool::new(true),
+        });
+        let waker = Waker::from(node_waker.clone());
 
-        let result = loop {
+        loop {
             // progress on main task
-            let mut context = Context::from_waker(&node_waker);
-            if let Poll::Ready(result) = node.poll(&mut context) {
-                // exit main task
-                break result;
+            if node_waker.woken.swap(false, Ordering::AcqRel) {
+                let mut context = Context::from_waker(&waker);
+                if let Poll::Ready(result) = future.as_mut().poll(&mut context) {
+                    // exit main task
+                    return result;
+                }
             }
 
-            let mut last_task = node.id.0;
+            let mut last_task = None;
             let mut task_budget = self.task_queue.len();
 
             while let Some(task_id) = self.task_queue.pop() {
                 // yield to looping tasks
-                if (task_id.0) == last_task {
+                if Some(task_id) == last_task {
                     self.task_queue.push(task_id);
                     break;
-                } else {
-                    last_task = task_id.0;
                 }
+                last_task = Some(task_id);
 
                 self.poll_task(task_id);
 
@@ -88,86 +80,71 @@ impl<'a> Executor<'a> {
                 }
                 task_budget -= 1;
             }
-            self.sleep_if_idle();
-        };
-        result
+
+            let next_deadline = time::wake_expired();
+            if self.task_queue.is_empty() && !node_waker.woken.load(Ordering::Acquire) {
+                time::wait_until(next_deadline);
+                time::wake_expired();
+            }
+        }
     }
 
     /// poll_task
     fn poll_task(&self, task_id: TaskId) {
-        let tasks = unsafe {
-            let tasksp = self.tasks.get();
-            &mut (*tasksp)
-        };
-        let task = match tasks.get_mut(&task_id) {
-            Some(task) => {
-                //let task_count = NEXT_ID.load(Ordering::Relaxed);
-                //trace!("poll task: {}@{} / {}", task.name, task.id.0, task_count);
-                task
-            }
+        // The task is taken out of the map while it is polled, so that it
+        // can spawn other tasks
+        let mut task = match self.tasks.lock().remove(&task_id) {
+            Some(task) => task,
             None => {
-                warn!("No task for id: {:?}", task_id);
+                // a task is queued again each time it is woken
+                trace!("No task for id: {:?}", task_id);
                 return;
             }
         };
 
-        let waker_cache = unsafe {
-            let waker_cachep = self.waker_cache.get();
-            &mut (*waker_cachep)
-        };
-        let waker = waker_cache
+        let waker = self
+            .waker_cache
+            .lock()
             .entry(task_id)
-            .or_insert_with(|| TaskWaker::new(task_id, self.task_queue.clone()));
+            .or_insert_with(|| TaskWaker::new(task_id, self.task_queue.clone()))
+            .clone();
 
-        let mut context = Context::from_waker(waker);
-        match task.poll(&mut context) {
+        let mut context = Context::from_waker(&waker);
+        match task.future.as_mut().poll(&mut context) {
             Poll::Ready(()) => {
-                // task completed, remove it and its cached waker
-                if let Some(task) = tasks.remove(&task_id) {
-                    drop(task);
-                }
-                waker_cache.remove(&task_id);
+                // task completed, drop it and its cached waker
+                debug!("completed task: {}@{}", task.name, task_id.0);
+                self.waker_cache.lock().remove(&task_id);
+            }
+            Poll::Pending => {
+                self.tasks.lock().insert(task_id, task);
             }
-            Poll::Pending => (),
         }
     }
 
     /// spawn
-    pub fn spawn(&self, future: impl Future + 'static) {
-        let task = Task::allocate(future);
-        debug!("spawning task: {}", task.id.0);
-        self.task_queue.push(task.id);
-        let tasks = unsafe {
-            let tasksp = self.tasks.get();
-            &mut (*tasksp)
-        };
-        if tasks.insert(task.id, task).is_some() {
-            panic!("task with same id already exists");
-        }
+    pub fn spawn(&self, future: impl Future + Send + 'static) {
+        self.spawn_with_name("Task", future)
     }
 
-    pub fn spawn_with_name(&self, name: &'static str, future: impl Future + 'static) {
-        let task = Task::allocate_with_name(name, future);
-        debug!("spawning task: {}@{}", name, task.id.0);
-        self.task_queue.push(task.id);
-        let tasks = unsafe {
-            let tasksp = self.tasks.get();
-            &mut (*tasksp)
+    pub fn spawn_with_name(&self, name: &'static str, future: impl Future + Send + 'static) {
+        let id = TaskId::new();
+        debug!("spawning task: {}@{}", name, id.0);
+        let task = Task {
+            name,
+            future: Box::pin(async {
+                // task terminating
+                future.await;
+            }),
         };
-        if tasks.insert(task.id, task).is_some() {
+        if self.tasks.lock().insert(id, task).is_some() {
             panic!("task with same id already exists");
         }
-    }
-
-    fn sleep_if_idle(&self) {
-        // TODO disable interrupts
-        if self.task_queue.is_empty() {
-            // TODO sleep
-        }
+        self.task_queue.push(id);
     }
 }
 
-impl<'a> Default for Executor<'a> {
+impl Default for Executor {
     fn default() -> Self {
         Self::new()
     }
@@ -175,65 +152,9 @@ impl<'a> Default for Executor<'a> {
 
 // - Task ---------------------------------------------------------------------
 
-type Task = Node<dyn Future<Output = ()> + 'static>;
-
-/// Node
-pub struct Node<F>
-where
-    F: ?Sized,
-{
-    id: TaskId,
-    _name: &'static str,
-    future: UnsafeCell<F>,
-    // TODO future: Pin<Box<F>>,
-}
-
-impl<F> Drop for Node<F>
-where
-    F: ?Sized,
-{
-    fn drop(&mut self) {
-        debug!("dropped task: {}", self.id.0);
-    }
-}
-
-impl<F, T> Node<F>
-where
-    F: ?Sized + Future<Output = T>,
-{
-    fn poll(&mut self, context: &mut Context) -> Poll<T> {
-        let future = unsafe {
-            let futurep = self.future.get();
-            &mut (*futurep)
-        };
-        unsafe { Pin::new_unchecked(future).poll(context) }
-    }
-}
-
-impl Task {
-    fn allocate(future: impl Future + 'static) -> Box<Task> {
-        Box::new(Node {
-            id: TaskId::new(),
-            _name: "Task",
-            future: UnsafeCell::new(async {
-                // task terminating
-                future.await;
-            }),
-            // TODO future: Box::pin(future),
-        })
-    }
-
-    fn allocate_with_name(name: &'static str, future: impl Future + 'static) -> Box<Task> {
-        Box::new(Node {
-            id: TaskId::new(),
-            _name: name,
-            future: UnsafeCell::new(async {
-                // task terminating
-                future.await;
-            }),
-            // TODO future: Box::pin(future),
-        })
-    }
+struct Task {
+    name: &'static str,
+    future: Pin<Box<dyn Future<Output = ()> + Send + 'static>>,
 }
 
 // - TaskId -------------------------------------------------------------------
@@ -250,32 +171,26 @@ impl TaskId {
 
 // - Waker --------------------------------------------------------------------
 
-struct NodeWaker;
-impl NodeWaker {
-    #[allow(clippy::new_ret_no_self)]
-    fn new(_task_id: TaskId) -> Waker {
-        Waker::from(Arc::new(NodeWaker {}))
-    }
+struct NodeWaker {
+    woken: AtomicBool,
 }
 
 impl Wake for NodeWaker {
     fn wake(self: Arc<Self>) {
-        // no-op
+        self.woken.store(true, Ordering::Release);
     }
 }
 
-struct TaskWaker<'a> {
+struct TaskWaker {
     task_id: TaskId,
     task_queue: Arc<SegQueue<TaskId>>,
-    marker: core::marker::PhantomData<&'a ()>,
 }
 
-impl<'a> TaskWaker<'a> {
+impl TaskWaker {
     fn new(task_id: TaskId, task_queue: Arc<SegQueue<TaskId>>) -> Waker {
         Waker::from(Arc::new(TaskWaker {
             task_id,
             task_queue,
-            marker: core::marker::PhantomData,
         }))
     }
 
@@ -284,7 +199,7 @@ impl<'a> TaskWaker<'a> {
     }
 }
 
-impl<'a> Wake for TaskWaker<'a> {
+impl Wake for TaskWaker {
     fn wake(self: Arc<Self>) {
         self.reschedule_task();
     }
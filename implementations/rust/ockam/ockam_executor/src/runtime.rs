@@ -19,26 +19,21 @@ where
 }
 
 /// block_future
-pub fn block_future<'r, F>(_runtime: &'r Runtime, _future: F) -> <F as Future>::Output
+pub fn block_future<'r, F>(_runtime: &'r Runtime, future: F) -> <F as Future>::Output
 where
     F: Future + Send,
     F::Output: Send,
 {
-    // task::block_in_place(move || {
-    //     let local = task::LocalSet::new();
-    //     local.block_on(rt, f)
-    // })
-    unimplemented!();
+    executor::current().block_on(future)
 }
 
 /// spawn
-pub fn spawn<F: 'static>(_future: F)
+pub fn spawn<F: 'static>(future: F)
 where
     F: Future + Send,
     F::Output: Send,
 {
-    // task::spawn(f)
-    unimplemented!();
+    executor::current().spawn(future);
 }
 
 /// Runtime
@@ -61,8 +56,7 @@ impl Runtime {
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        executor::current().spawn(future);
-        JoinHandle::new()
+        self.handle.spawn(future)
     }
 }
 
@@ -76,8 +70,17 @@ impl Handle {
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        executor::current().spawn(future);
-        JoinHandle::new()
+        let handle = JoinHandle::new();
+        let shared = handle.0.clone();
+        executor::current().spawn(async move {
+            let value = future.await;
+            let mut guard = shared.lock().unwrap();
+            guard.value = Some(value);
+            if let Some(waker) = guard.waker.take() {
+                waker.wake();
+            }
+        });
+        handle
     }
 }
 
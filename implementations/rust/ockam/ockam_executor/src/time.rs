@@ -1,22 +1,216 @@
+//! Timers driven by a platform [`TimerDriver`]
+//!
+//! An embedded target has no standard clock: it registers a driver
+//! reading its hardware timer with [`set_timer_driver`] before the
+//! executor is started.  Using a timer without a driver panics.
+//!
+//! Tests on a host can opt in to a [`VirtualClock`], which jumps to the
+//! next deadline whenever the executor has nothing else to do, so that
+//! programs waiting on timers run deterministically and without delay:
+//!
+//! ```
+//! use ockam_executor::time::{set_timer_driver, VirtualClock};
+//!
+//! static CLOCK: VirtualClock = VirtualClock::new();
+//! set_timer_driver(&CLOCK).ok();
+//! ```
+//!
+//! A virtual clock must not be used on a device, where it makes
+//! timeouts expire as soon as the executor waits for I/O.
 use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::task::{Context, Poll, Waker};
 pub use core::time::Duration;
+use ockam_core::compat::collections::BTreeMap;
 use pin_project_lite::pin_project;
+use spin::{Mutex, Once};
+
+/// A monotonic clock, and a way to wait for it, provided by the platform
+pub trait TimerDriver: Send + Sync {
+    /// The time elapsed since an arbitrary origin, which never decreases
+    fn now(&self) -> Duration;
+
+    /// Called by the executor when no task is ready to make progress
+    ///
+    /// An implementation can put the processor to sleep until `deadline`,
+    /// the deadline of the next timer if there is one, or until an
+    /// interrupt wakes a task.  It may also return right away, in which
+    /// case the executor keeps polling.
+    fn wait_until(&self, deadline: Option<Duration>) {
+        let _ = deadline;
+    }
+}
+
+/// A clock which only advances when the executor waits for a timer
+#[derive(Debug, Default)]
+pub struct VirtualClock {
+    now: Mutex<Duration>,
+}
+
+impl VirtualClock {
+    /// Create a clock starting at zero
+    pub const fn new() -> Self {
+        Self {
+            now: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock() += duration
+    }
+}
+
+impl TimerDriver for VirtualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock()
+    }
+
+    fn wait_until(&self, deadline: Option<Duration>) {
+        if let Some(deadline) = deadline {
+            let mut now = self.now.lock();
+            *now = (*now).max(deadline);
+        }
+    }
+}
+
+static DRIVER: Once<&'static dyn TimerDriver> = Once::new();
+
+/// Set the driver used by all timers
+///
+/// The driver can only be set once, before the first timer is used.
+/// Otherwise the driver in use is returned as an error.
+pub fn set_timer_driver(driver: &'static dyn TimerDriver) -> Result<(), &'static dyn TimerDriver> {
+    let mut installed = false;
+    let current = DRIVER.call_once(|| {
+        installed = true;
+        driver
+    });
+    if installed {
+        Ok(())
+    } else {
+        Err(*current)
+    }
+}
+
+/// The driver used by all timers
+///
+/// # Panics
+///
+/// Panics if no driver was set with [`set_timer_driver`].
+pub fn driver() -> &'static dyn TimerDriver {
+    *DRIVER
+        .get()
+        .expect("a timer driver must be set with `set_timer_driver` before using timers")
+}
+
+/// Let the driver, if one was set, wait until `deadline` or an interrupt
+pub(crate) fn wait_until(deadline: Option<Duration>) {
+    if let Some(driver) = DRIVER.get() {
+        driver.wait_until(deadline)
+    }
+}
+
+/// The current time of the timer driver
+pub fn now() -> Duration {
+    driver().now()
+}
+
+/// The wakers of the pending timers, by deadline and timer id
+static TIMERS: Mutex<BTreeMap<(Duration, usize), Waker>> = Mutex::new(BTreeMap::new());
+
+/// Wake the timers which expired and return the deadline of the next one
+pub(crate) fn wake_expired() -> Option<Duration> {
+    let mut timers = TIMERS.lock();
+    if timers.is_empty() {
+        // There are no timers, and maybe no driver
+        return None;
+    }
+    let now = now();
+    while let Some(entry) = timers.first_entry() {
+        if entry.key().0 > now {
+            return Some(entry.key().0);
+        }
+        entry.remove().wake();
+    }
+    None
+}
+
+/// A future completing at a deadline
+#[derive(Debug)]
+pub struct Sleep {
+    deadline: Duration,
+    id: usize,
+}
+
+impl Sleep {
+    /// The time at which this future completes
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Whether the deadline has passed
+    pub fn is_elapsed(&self) -> bool {
+        now() >= self.deadline
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_elapsed() {
+            TIMERS.lock().remove(&(self.deadline, self.id));
+            return Poll::Ready(());
+        }
+        TIMERS
+            .lock()
+            .insert((self.deadline, self.id), cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        TIMERS.lock().remove(&(self.deadline, self.id));
+    }
+}
+
+/// Wait until `duration` has elapsed
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(now() + duration)
+}
+
+/// Wait until the timer driver reaches `deadline`
+pub fn sleep_until(deadline: Duration) -> Sleep {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    Sleep {
+        deadline,
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+    }
+}
 
 pin_project! {
+    /// A future failing when its inner future doesn't complete in time
     #[derive(Debug)]
     pub struct Timeout<F> {
-        #[pin]
-        duration: Duration,
         #[pin]
         future: F,
+        delay: Sleep,
     }
 }
 
+/// Fail with [`error::Elapsed`] if `future` doesn't complete within
+/// `duration`
 pub fn timeout<F>(duration: Duration, future: F) -> Timeout<F>
 where
     F: Future,
 {
-    Timeout { duration, future }
+    Timeout {
+        future,
+        delay: sleep(duration),
+    }
 }
 
 impl<F> Future for Timeout<F>
@@ -25,32 +219,22 @@ where
 {
     type Output = Result<F::Output, error::Elapsed>;
 
-    fn poll(
-        self: core::pin::Pin<&mut Self>,
-        cx: &mut core::task::Context<'_>,
-    ) -> core::task::Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let timeout = self.project();
 
         // try polling the future
-        if let core::task::Poll::Ready(v) = timeout.future.poll(cx) {
-            return core::task::Poll::Ready(Ok(v));
+        if let Poll::Ready(v) = timeout.future.poll(cx) {
+            return Poll::Ready(Ok(v));
         }
 
-        // TODO check the timer
-        /*match timeout.delay.poll(cx) {
-            Poll::Ready(()) => Poll::Ready(Err(Elapsed::new())),
+        // then check the timer
+        match Pin::new(timeout.delay).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(error::Elapsed::new())),
             Poll::Pending => Poll::Pending,
-        }*/
-        core::task::Poll::Pending
+        }
     }
 }
 
-pub struct Sleep;
-
-pub async fn sleep(_duration: Duration) -> Sleep {
-    unimplemented!();
-}
-
 pub mod error {
     use core::fmt;
     use ockam_core::compat::{error, io};
@@ -59,7 +243,6 @@ pub mod error {
     pub struct Elapsed(());
 
     impl Elapsed {
-        #![allow(dead_code)]
         pub(crate) fn new() -> Self {
             Elapsed(())
         }
@@ -0,0 +1,57 @@
+use core::time::Duration;
+use ockam_executor::executor::{self, Executor};
+use ockam_executor::runtime::Runtime;
+use ockam_executor::time::{self, VirtualClock};
+
+/// Run the timers of the tests on a virtual clock
+fn virtual_clock() {
+    static CLOCK: VirtualClock = VirtualClock::new();
+    let _ = time::set_timer_driver(&CLOCK);
+}
+
+#[test]
+fn current_is_created_once_across_threads() {
+    let threads: Vec<_> = (0..8)
+        .map(|_| std::thread::spawn(|| executor::current() as *const Executor as usize))
+        .collect();
+    let addresses: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+    assert!(addresses.iter().all(|a| *a == addresses[0]));
+}
+
+#[test]
+fn join_handles_resolve_with_the_output_of_spawned_tasks() {
+    virtual_clock();
+    let runtime = Runtime::new().unwrap();
+    let handle = runtime.spawn(async {
+        time::sleep(Duration::from_millis(10)).await;
+        40 + 2
+    });
+    assert_eq!(42, executor::current().block_on(handle));
+}
+
+#[test]
+fn timers_use_the_virtual_clock() {
+    virtual_clock();
+    let start = time::now();
+    executor::current().block_on(time::sleep(Duration::from_secs(60)));
+    assert!(time::now() - start >= Duration::from_secs(60));
+}
+
+#[test]
+fn timeouts_elapse() {
+    virtual_clock();
+    let executor = executor::current();
+    assert!(executor
+        .block_on(time::timeout(
+            Duration::from_secs(1),
+            futures::future::pending::<()>()
+        ))
+        .is_err());
+    assert_eq!(
+        Ok(()),
+        executor.block_on(time::timeout(
+            Duration::from_secs(1),
+            time::sleep(Duration::from_millis(100))
+        ))
+    );
+}
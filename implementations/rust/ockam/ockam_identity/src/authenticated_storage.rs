@@ -36,6 +36,7 @@ pub struct AttributesEntry {
     #[n(2)] added: Timestamp,
     #[n(3)] expires: Option<Timestamp>,
     #[n(4)] attested_by: Option<IdentityIdentifier>,
+    #[b(5)] audience: Option<Vec<String>>,
 }
 
 impl AttributesEntry {
@@ -55,9 +56,16 @@ impl AttributesEntry {
             added,
             expires,
             attested_by,
+            audience: None,
         }
     }
 
+    /// Restrict the entry to the given audience, as in the credential it was taken from
+    pub fn with_audience(mut self, audience: Option<Vec<String>>) -> Self {
+        self.audience = audience;
+        self
+    }
+
     /// The entry attributes
     pub fn attrs(&self) -> &BTreeMap<String, Vec<u8>> {
         &self.attrs
@@ -76,6 +84,11 @@ impl AttributesEntry {
     pub fn attested_by(&self) -> Option<IdentityIdentifier> {
         self.attested_by.to_owned()
     }
+
+    /// Audience the attributes are restricted to, anyone when not set
+    pub fn audience(&self) -> Option<&[String]> {
+        self.audience.as_deref()
+    }
 }
 
 /// Trait implementing read access to an AuthenticatedIdentities table
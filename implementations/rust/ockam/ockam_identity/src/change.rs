@@ -1,4 +1,4 @@
-use crate::ChangeIdentifier;
+use crate::{ChangeIdentifier, IdentityError};
 use core::fmt;
 use ockam_core::compat::vec::Vec;
 use ockam_core::vault::PublicKey;
@@ -7,11 +7,17 @@ use serde::{Deserialize, Serialize};
 
 pub use crate::signature::*;
 
+mod commit_recovery_key;
 mod create_key;
+mod revoke_key;
 mod rotate_key;
+mod set_root_keys;
 
+pub use commit_recovery_key::*;
 pub use create_key::*;
+pub use revoke_key::*;
 pub use rotate_key::*;
+pub use set_root_keys::*;
 
 /// Possible types of [`crate::Identity`] changes
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -20,6 +26,12 @@ pub enum IdentityChange {
     CreateKey(CreateKeyChangeData),
     /// Rotate key
     RotateKey(RotateKeyChangeData),
+    /// Revoke key
+    RevokeKey(RevokeKeyChangeData),
+    /// Commit recovery key
+    CommitRecoveryKey(CommitRecoveryKeyChangeData),
+    /// Set root keys
+    SetRootKeys(SetRootKeysChangeData),
 }
 
 impl fmt::Display for IdentityChange {
@@ -27,6 +39,9 @@ impl fmt::Display for IdentityChange {
         match self {
             IdentityChange::CreateKey(data) => write!(f, " CreateKey:{}", data),
             IdentityChange::RotateKey(data) => write!(f, " RotateKey:{}", data),
+            IdentityChange::RevokeKey(data) => write!(f, " RevokeKey:{}", data),
+            IdentityChange::CommitRecoveryKey(data) => write!(f, " CommitRecoveryKey:{}", data),
+            IdentityChange::SetRootKeys(data) => write!(f, " SetRootKeys:{}", data),
         }
     }
 }
@@ -40,6 +55,9 @@ impl IdentityChange {
         match self {
             IdentityChange::CreateKey(data) => data.key_attributes().label(),
             IdentityChange::RotateKey(data) => data.key_attributes().label(),
+            IdentityChange::RevokeKey(data) => data.key_attributes().label(),
+            IdentityChange::CommitRecoveryKey(data) => data.key_attributes().label(),
+            IdentityChange::SetRootKeys(data) => data.key_attributes().label(),
         }
     }
 
@@ -47,6 +65,13 @@ impl IdentityChange {
         Ok(match self {
             IdentityChange::CreateKey(data) => data.public_key(),
             IdentityChange::RotateKey(data) => data.public_key(),
+            IdentityChange::RevokeKey(data) => data.public_key(),
+            // Only the hash of the recovery key is known until it's used
+            IdentityChange::CommitRecoveryKey(_) => {
+                return Err(IdentityError::InvalidInternalState.into())
+            }
+            // There is no single root key once the root has several keys
+            IdentityChange::SetRootKeys(_) => return Err(IdentityError::InvalidThreshold.into()),
         }
         .clone())
     }
@@ -55,6 +80,9 @@ impl IdentityChange {
         match self {
             IdentityChange::CreateKey(data) => data.prev_change_id(),
             IdentityChange::RotateKey(data) => data.prev_change_id(),
+            IdentityChange::RevokeKey(data) => data.prev_change_id(),
+            IdentityChange::CommitRecoveryKey(data) => data.prev_change_id(),
+            IdentityChange::SetRootKeys(data) => data.prev_change_id(),
         }
     }
 }
@@ -0,0 +1,92 @@
+use crate::change::{IdentityChange, IdentitySignedChange};
+use crate::{ChangeIdentifier, Identity, IdentityError, IdentityStateConst, KeyAttributes};
+use core::fmt;
+use ockam_core::compat::string::ToString;
+use ockam_core::vault::PublicKey;
+use ockam_core::{Encodable, Result};
+use serde::{Deserialize, Serialize};
+
+/// Commitment to a recovery key, only the hash of its public key is disclosed
+/// until the key is used to recover the root key
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommitRecoveryKeyChangeData {
+    prev_change_id: ChangeIdentifier,
+    key_attributes: KeyAttributes,
+    recovery_key_hash: [u8; 32],
+}
+
+impl CommitRecoveryKeyChangeData {
+    /// Return key attributes
+    pub fn key_attributes(&self) -> &KeyAttributes {
+        &self.key_attributes
+    }
+    /// Return the sha256 hash of the recovery public key
+    pub fn recovery_key_hash(&self) -> &[u8; 32] {
+        &self.recovery_key_hash
+    }
+    /// Previous change identifier, used to create a chain
+    pub fn prev_change_id(&self) -> &ChangeIdentifier {
+        &self.prev_change_id
+    }
+}
+
+impl CommitRecoveryKeyChangeData {
+    /// Create CommitRecoveryKeyChangeData
+    pub fn new(
+        prev_change_id: ChangeIdentifier,
+        key_attributes: KeyAttributes,
+        recovery_key_hash: [u8; 32],
+    ) -> Self {
+        Self {
+            prev_change_id,
+            key_attributes,
+            recovery_key_hash,
+        }
+    }
+}
+
+impl fmt::Display for CommitRecoveryKeyChangeData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "prev_change_id:{} key attibutes:{} recovery key hash:{}",
+            self.prev_change_id(),
+            self.key_attributes(),
+            hex::encode(self.recovery_key_hash())
+        )
+    }
+}
+
+impl Identity {
+    /// Commit recovery key change, signed by the root key
+    pub(crate) async fn make_commit_recovery_key_change(
+        &self,
+        recovery_key: &PublicKey,
+    ) -> Result<IdentitySignedChange> {
+        let change_history = self.change_history.read().await;
+        let prev_change_id = change_history.get_last_change_id()?;
+
+        let recovery_key_hash = self.vault.sha256(recovery_key.data()).await?;
+
+        let key_attributes =
+            KeyAttributes::default_with_label(IdentityStateConst::RECOVERY_LABEL.to_string());
+        let data =
+            CommitRecoveryKeyChangeData::new(prev_change_id, key_attributes, recovery_key_hash);
+
+        let change_block = IdentityChange::CommitRecoveryKey(data);
+        let change_block_binary = change_block
+            .encode()
+            .map_err(|_| IdentityError::BareError)?;
+
+        let change_id = self.vault.sha256(&change_block_binary).await?;
+        let change_id = ChangeIdentifier::from_hash(change_id);
+
+        let root_signatures = self
+            .create_root_signatures(change_history.as_ref(), &change_id)
+            .await?;
+
+        let signed_change = IdentitySignedChange::new(change_id, change_block, root_signatures);
+
+        Ok(signed_change)
+    }
+}
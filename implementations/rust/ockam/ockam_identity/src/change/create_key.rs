@@ -81,7 +81,6 @@ impl Identity {
         secret: Option<&KeyId>,
         prev_id: ChangeIdentifier,
         key_attributes: KeyAttributes,
-        root_key: Option<&KeyId>,
         vault: Arc<dyn IdentityVault>,
     ) -> Result<IdentitySignedChange> {
         let secret_key =
@@ -103,18 +102,10 @@ impl Identity {
         let self_signature = vault.sign(&secret_key, change_id.as_ref()).await?;
         let self_signature = Signature::new(SignatureType::SelfSign, self_signature);
 
-        let mut signatures = vec![self_signature];
-
-        // If we have root_key passed we should sign using it
-        // If there is no root_key - we're creating new identity, so we just generated root_key
-        if let Some(root_key) = root_key {
-            let root_signature = vault.sign(root_key, change_id.as_ref()).await?;
-            let root_signature = Signature::new(SignatureType::RootSign, root_signature);
-
-            signatures.push(root_signature);
-        }
-
-        let signed_change = IdentitySignedChange::new(change_id, change_block, signatures);
+        // Root signatures are added by the caller, unless we're creating a new
+        // identity and this key is the root key
+        let signed_change =
+            IdentitySignedChange::new(change_id, change_block, vec![self_signature]);
 
         Ok(signed_change)
     }
@@ -141,16 +132,24 @@ impl Identity {
             Err(_) => ChangeIdentifier::initial(to_hasher(self.vault.clone())).await,
         };
 
-        let root_secret = self.get_root_secret_key().await?;
-        let root_key = Some(&root_secret);
-
-        Self::make_create_key_change_static(
+        let change = Self::make_create_key_change_static(
             secret,
             prev_id,
             key_attributes,
-            root_key,
             self.vault.clone(),
         )
-        .await
+        .await?;
+
+        let mut signatures = change.signatures().to_vec();
+        signatures.extend(
+            self.create_root_signatures(change_history.as_ref(), change.identifier())
+                .await?,
+        );
+
+        Ok(IdentitySignedChange::new(
+            change.identifier().clone(),
+            change.change().clone(),
+            signatures,
+        ))
     }
 }
@@ -0,0 +1,96 @@
+use crate::change::{IdentityChange, IdentitySignedChange};
+use crate::change_history::IdentityChangeHistory;
+use crate::{ChangeIdentifier, Identity, IdentityError, IdentityStateConst, KeyAttributes};
+use core::fmt;
+use ockam_core::vault::PublicKey;
+use ockam_core::{Encodable, Result};
+use serde::{Deserialize, Serialize};
+
+/// RevokeKeyChangeData
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RevokeKeyChangeData {
+    prev_change_id: ChangeIdentifier,
+    key_attributes: KeyAttributes,
+    public_key: PublicKey,
+}
+
+impl RevokeKeyChangeData {
+    /// Return key attributes
+    pub fn key_attributes(&self) -> &KeyAttributes {
+        &self.key_attributes
+    }
+    /// Return the revoked public key
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+    /// Previous change identifier, used to create a chain
+    pub fn prev_change_id(&self) -> &ChangeIdentifier {
+        &self.prev_change_id
+    }
+}
+
+impl RevokeKeyChangeData {
+    /// Create RevokeKeyChangeData
+    pub fn new(
+        prev_change_id: ChangeIdentifier,
+        key_attributes: KeyAttributes,
+        public_key: PublicKey,
+    ) -> Self {
+        Self {
+            prev_change_id,
+            key_attributes,
+            public_key,
+        }
+    }
+}
+
+impl fmt::Display for RevokeKeyChangeData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "prev_change_id:{} key attibutes:{} public key:{}",
+            self.prev_change_id(),
+            self.key_attributes(),
+            self.public_key()
+        )
+    }
+}
+
+impl Identity {
+    /// Revoke key change, only signed by the root key since the revoked
+    /// key may be compromised
+    pub(crate) async fn make_revoke_key_change(
+        &self,
+        key_attributes: KeyAttributes,
+    ) -> Result<IdentitySignedChange> {
+        if key_attributes.label() == IdentityStateConst::ROOT_LABEL {
+            return Err(IdentityError::InvalidInternalState.into());
+        }
+
+        let change_history = self.change_history.read().await;
+        let prev_change_id = change_history.get_last_change_id()?;
+
+        let public_key = IdentityChangeHistory::get_public_key_static(
+            change_history.as_ref(),
+            key_attributes.label(),
+        )?;
+
+        let data = RevokeKeyChangeData::new(prev_change_id, key_attributes, public_key);
+
+        let change_block = IdentityChange::RevokeKey(data);
+        let change_block_binary = change_block
+            .encode()
+            .map_err(|_| IdentityError::BareError)?;
+
+        let change_id = self.vault.sha256(&change_block_binary).await?;
+        let change_id = ChangeIdentifier::from_hash(change_id);
+
+        let root_signatures = self
+            .create_root_signatures(change_history.as_ref(), &change_id)
+            .await?;
+
+        let signed_change = IdentitySignedChange::new(change_id, change_block, root_signatures);
+
+        Ok(signed_change)
+    }
+}
@@ -1,8 +1,9 @@
 use crate::change::{IdentityChange, IdentitySignedChange, Signature, SignatureType};
 use crate::change_history::IdentityChangeHistory;
-use crate::{ChangeIdentifier, Identity, IdentityError, KeyAttributes};
+use crate::{ChangeIdentifier, Identity, IdentityError, IdentityStateConst, KeyAttributes};
 use core::fmt;
-use ockam_core::vault::PublicKey;
+use ockam_core::compat::string::ToString;
+use ockam_core::vault::{KeyId, PublicKey};
 use ockam_core::{Encodable, Result};
 use serde::{Deserialize, Serialize};
 
@@ -65,7 +66,7 @@ impl Identity {
         let change_history = self.change_history.read().await;
         let prev_change_id = change_history.get_last_change_id()?;
 
-        let last_change_in_chain = IdentityChangeHistory::find_last_key_change(
+        let last_change_in_chain = IdentityChangeHistory::find_current_key_change(
             change_history.as_ref(),
             key_attributes.label(),
         )?
@@ -93,10 +94,9 @@ impl Identity {
         let self_signature = self.vault.sign(&secret_key, change_id.as_ref()).await?;
         let self_signature = Signature::new(SignatureType::SelfSign, self_signature);
 
-        let root_key = self.get_root_secret_key().await?;
-
-        let root_signature = self.vault.sign(&root_key, change_id.as_ref()).await?;
-        let root_signature = Signature::new(SignatureType::RootSign, root_signature);
+        let root_signatures = self
+            .create_root_signatures(change_history.as_ref(), &change_id)
+            .await?;
 
         let prev_signature = self
             .vault
@@ -104,11 +104,56 @@ impl Identity {
             .await?;
         let prev_signature = Signature::new(SignatureType::PrevSign, prev_signature);
 
-        let signed_change = IdentitySignedChange::new(
-            change_id,
-            change_block,
-            vec![self_signature, root_signature, prev_signature],
+        let mut signatures = vec![self_signature];
+        signatures.extend(root_signatures);
+        signatures.push(prev_signature);
+
+        let signed_change = IdentitySignedChange::new(change_id, change_block, signatures);
+
+        Ok(signed_change)
+    }
+
+    /// Rotate the root key to a previously committed recovery key.
+    /// The change is only signed by the recovery key since the current
+    /// root key is assumed to be lost
+    pub(crate) async fn make_recover_root_key_change(
+        &self,
+        recovery_key: &KeyId,
+    ) -> Result<IdentitySignedChange> {
+        let change_history = self.change_history.read().await;
+        let prev_change_id = change_history.get_last_change_id()?;
+
+        let secret_attributes = self.vault.secret_attributes_get(recovery_key).await?;
+        let public_key = self.vault.secret_public_key_get(recovery_key).await?;
+
+        let recovery_key_hash = IdentityChangeHistory::find_recovery_key_hash(
+            change_history.as_ref(),
+            self.vault.clone(),
+        )
+        .await?;
+        if recovery_key_hash != Some(self.vault.sha256(public_key.data()).await?) {
+            return Err(IdentityError::IdentityVerificationFailed.into());
+        }
+
+        let key_attributes = KeyAttributes::new(
+            IdentityStateConst::ROOT_LABEL.to_string(),
+            secret_attributes,
         );
+        let data = RotateKeyChangeData::new(prev_change_id, key_attributes, public_key);
+
+        let change_block = IdentityChange::RotateKey(data);
+        let change_block_binary = change_block
+            .encode()
+            .map_err(|_| IdentityError::BareError)?;
+
+        let change_id = self.vault.sha256(&change_block_binary).await?;
+        let change_id = ChangeIdentifier::from_hash(change_id);
+
+        let self_signature = self.vault.sign(recovery_key, change_id.as_ref()).await?;
+        let self_signature = Signature::new(SignatureType::SelfSign, self_signature);
+
+        let signed_change =
+            IdentitySignedChange::new(change_id, change_block, vec![self_signature]);
 
         Ok(signed_change)
     }
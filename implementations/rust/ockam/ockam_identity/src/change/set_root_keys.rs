@@ -0,0 +1,121 @@
+use crate::change::{IdentityChange, IdentitySignedChange, Signature, SignatureType};
+use crate::{ChangeIdentifier, Identity, IdentityError, IdentityStateConst, KeyAttributes};
+use core::fmt;
+use ockam_core::compat::{string::ToString, vec::Vec};
+use ockam_core::vault::PublicKey;
+use ockam_core::{Encodable, Result};
+use serde::{Deserialize, Serialize};
+
+/// Replaces the root key with a set of keys, `threshold` of them are
+/// needed to sign any change requiring a root signature
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetRootKeysChangeData {
+    prev_change_id: ChangeIdentifier,
+    key_attributes: KeyAttributes,
+    public_keys: Vec<PublicKey>,
+    threshold: u8,
+}
+
+impl SetRootKeysChangeData {
+    /// Return key attributes
+    pub fn key_attributes(&self) -> &KeyAttributes {
+        &self.key_attributes
+    }
+    /// Return the root public keys
+    pub fn public_keys(&self) -> &[PublicKey] {
+        &self.public_keys
+    }
+    /// Return the number of root signatures needed
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+    /// Previous change identifier, used to create a chain
+    pub fn prev_change_id(&self) -> &ChangeIdentifier {
+        &self.prev_change_id
+    }
+}
+
+impl SetRootKeysChangeData {
+    /// Create SetRootKeysChangeData
+    pub fn new(
+        prev_change_id: ChangeIdentifier,
+        key_attributes: KeyAttributes,
+        public_keys: Vec<PublicKey>,
+        threshold: u8,
+    ) -> Self {
+        Self {
+            prev_change_id,
+            key_attributes,
+            public_keys,
+            threshold,
+        }
+    }
+}
+
+impl fmt::Display for SetRootKeysChangeData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "prev_change_id:{} key attibutes:{} threshold:{} public keys:",
+            self.prev_change_id(),
+            self.key_attributes(),
+            self.threshold()
+        )?;
+        for public_key in self.public_keys() {
+            write!(f, " {}", public_key)?;
+        }
+        Ok(())
+    }
+}
+
+impl Identity {
+    /// Set root keys change, signed by every new root key and by the
+    /// current root keys
+    pub(crate) async fn make_set_root_keys_change(
+        &self,
+        public_keys: Vec<PublicKey>,
+        threshold: u8,
+    ) -> Result<IdentitySignedChange> {
+        if threshold == 0 || threshold as usize > public_keys.len() {
+            return Err(IdentityError::InvalidThreshold.into());
+        }
+
+        let change_history = self.change_history.read().await;
+        let prev_change_id = change_history.get_last_change_id()?;
+
+        let key_attributes =
+            KeyAttributes::default_with_label(IdentityStateConst::ROOT_LABEL.to_string());
+        let data =
+            SetRootKeysChangeData::new(prev_change_id, key_attributes, public_keys, threshold);
+
+        let change_block = IdentityChange::SetRootKeys(data);
+        let change_block_binary = change_block
+            .encode()
+            .map_err(|_| IdentityError::BareError)?;
+
+        let change_id = self.vault.sha256(&change_block_binary).await?;
+        let change_id = ChangeIdentifier::from_hash(change_id);
+
+        // Every new key proves it is controlled by a cosigner
+        let mut signatures = Vec::new();
+        if let IdentityChange::SetRootKeys(data) = &change_block {
+            for public_key in data.public_keys() {
+                let self_signature = self
+                    .sign_with_available_key(public_key, change_id.as_ref())
+                    .await?
+                    .ok_or(IdentityError::ThresholdNotReached)?;
+                signatures.push(Signature::new(SignatureType::SelfSign, self_signature));
+            }
+        }
+        signatures.extend(
+            self.create_root_signatures(change_history.as_ref(), &change_id)
+                .await?,
+        );
+
+        Ok(IdentitySignedChange::new(
+            change_id,
+            change_block,
+            signatures,
+        ))
+    }
+}
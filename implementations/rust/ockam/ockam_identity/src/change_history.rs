@@ -1,5 +1,7 @@
 //! Identity history
-use crate::change::IdentityChange::{CreateKey, RotateKey};
+use crate::change::IdentityChange::{
+    CommitRecoveryKey, CreateKey, RevokeKey, RotateKey, SetRootKeys,
+};
 use crate::change::{IdentitySignedChange, SignatureType};
 use crate::{
     ChangeIdentifier, IdentityError, IdentityIdentifier, IdentityStateConst, IdentityVault,
@@ -40,7 +42,6 @@ impl fmt::Display for IdentityChangeHistory {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "Change History:")?;
         for (i_num, ident) in self.0.iter().enumerate() {
-            let public_key = ident.change().public_key().unwrap();
             writeln!(f, "  Change[{}]:", i_num)?;
             writeln!(f, "    identifier: {}", ident.identifier())?;
             writeln!(f, "    change:")?;
@@ -50,7 +51,24 @@ impl fmt::Display for IdentityChangeHistory {
                 ident.change().previous_change_identifier()
             )?;
             writeln!(f, "      label:        {}", ident.change().label())?;
-            writeln!(f, "      public_key:   {}", public_key)?;
+            match ident.change() {
+                CommitRecoveryKey(data) => writeln!(
+                    f,
+                    "      recovery_key_hash: {}",
+                    hex::encode(data.recovery_key_hash())
+                )?,
+                SetRootKeys(data) => {
+                    writeln!(f, "      threshold:    {}", data.threshold())?;
+                    for public_key in data.public_keys() {
+                        writeln!(f, "      public_key:   {}", public_key)?;
+                    }
+                }
+                change => writeln!(
+                    f,
+                    "      public_key:   {}",
+                    change.public_key().map_err(|_| fmt::Error)?
+                )?,
+            }
             writeln!(f, "    signatures:")?;
             for (sig_num, sig) in ident.signatures().iter().enumerate() {
                 writeln!(f, "      [{}]: {}", sig_num, sig)?;
@@ -202,26 +220,68 @@ impl IdentityChangeHistory {
             .ok_or_else(|| IdentityError::InvalidInternalState.into())
     }
 
-    pub(crate) fn find_last_key_change_public_key(
-        existing_changes: &[IdentitySignedChange],
+    /// Last change that created or rotated the key with the given label,
+    /// fails if the key was revoked
+    pub(crate) fn find_current_key_change<'a>(
+        existing_changes: &'a [IdentitySignedChange],
         label: &str,
-    ) -> Result<PublicKey> {
-        let last_key_change = Self::find_last_key_change(existing_changes, label)?;
+    ) -> Result<&'a IdentitySignedChange> {
+        let change = Self::find_last_key_change(existing_changes, label)?;
+        if let RevokeKey(_) = change.change() {
+            return Err(IdentityError::KeyRevoked.into());
+        }
+        Ok(change)
+    }
 
-        last_key_change.change().public_key()
+    /// Hash of the committed recovery key, if it wasn't used yet to recover
+    /// the root key
+    pub(crate) async fn find_recovery_key_hash(
+        existing_changes: &[IdentitySignedChange],
+        vault: Arc<dyn IdentityVault>,
+    ) -> Result<Option<[u8; 32]>> {
+        let mut recovery_key_hash = None;
+        for change in existing_changes {
+            match change.change() {
+                CommitRecoveryKey(data) => recovery_key_hash = Some(*data.recovery_key_hash()),
+                RotateKey(data)
+                    if recovery_key_hash.is_some()
+                        && data.key_attributes().label() == IdentityStateConst::ROOT_LABEL =>
+                {
+                    let hash = vault.sha256(data.public_key().data()).await?;
+                    if Some(hash) == recovery_key_hash {
+                        recovery_key_hash = None;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(recovery_key_hash)
     }
 
-    pub(crate) fn get_current_root_public_key(
+    /// Current root keys, along with the number of root signatures needed
+    pub(crate) fn get_current_root_keys(
         existing_changes: &[IdentitySignedChange],
-    ) -> Result<PublicKey> {
-        Self::find_last_key_change_public_key(existing_changes, IdentityStateConst::ROOT_LABEL)
+    ) -> Result<(Vec<PublicKey>, u8)> {
+        let change =
+            Self::find_current_key_change(existing_changes, IdentityStateConst::ROOT_LABEL)?;
+        match change.change() {
+            SetRootKeys(data) => Ok((data.public_keys().to_vec(), data.threshold())),
+            change => Ok((vec![change.public_key()?], 1)),
+        }
+    }
+
+    /// Return true if the root is made of several keys
+    pub(crate) fn has_threshold_root(existing_changes: &[IdentitySignedChange]) -> Result<bool> {
+        let change =
+            Self::find_current_key_change(existing_changes, IdentityStateConst::ROOT_LABEL)?;
+        Ok(matches!(change.change(), SetRootKeys(_)))
     }
 
     pub(crate) fn get_public_key_static(
         changes: &[IdentitySignedChange],
         label: &str,
     ) -> Result<PublicKey> {
-        let change = Self::find_last_key_change(changes, label)?;
+        let change = Self::find_current_key_change(changes, label)?;
         change.change().public_key()
     }
 
@@ -250,46 +310,147 @@ impl IdentityChangeHistory {
             root_sign: u8,
         }
 
+        // Changes signed by the root need a signature from `threshold` distinct root keys
+        let (root_keys, root_threshold) = if existing_changes.is_empty() {
+            (Vec::new(), 0)
+        } else {
+            Self::get_current_root_keys(existing_changes)?
+        };
+
         let mut signatures_check = match new_change.change() {
             CreateKey(_) => {
                 // Should have self signature and root signature
                 // There is no Root signature for the very first change
-                let root_sign = u8::from(!existing_changes.is_empty());
+                SignaturesCheck {
+                    self_sign: 1,
+                    prev_sign: 0,
+                    root_sign: root_threshold,
+                }
+            }
+            RotateKey(data)
+                if data.key_attributes().label() == IdentityStateConst::ROOT_LABEL
+                    && new_change
+                        .signatures()
+                        .iter()
+                        .all(|s| s.stype() == &SignatureType::SelfSign) =>
+            {
+                // Root key recovery: the new root key should match the committed
+                // recovery key, and only sign the change itself
+                let recovery_key_hash =
+                    match Self::find_recovery_key_hash(existing_changes, vault.clone()).await? {
+                        Some(recovery_key_hash) => recovery_key_hash,
+                        None => return deny(),
+                    };
+                if vault.sha256(data.public_key().data()).await? != recovery_key_hash {
+                    return deny();
+                }
 
                 SignaturesCheck {
                     self_sign: 1,
                     prev_sign: 0,
-                    root_sign,
+                    root_sign: 0,
                 }
             }
-            RotateKey(_) => {
+            RotateKey(data) => {
+                // Several root keys are replaced with SetRootKeys
+                if data.key_attributes().label() == IdentityStateConst::ROOT_LABEL
+                    && Self::has_threshold_root(existing_changes)?
+                {
+                    return deny();
+                }
+
                 // Should have self signature, root signature, and previous key signature
                 SignaturesCheck {
                     self_sign: 1,
                     prev_sign: 1,
-                    root_sign: 1,
+                    root_sign: root_threshold,
+                }
+            }
+            SetRootKeys(data) => {
+                let public_keys = data.public_keys();
+                if existing_changes.is_empty()
+                    || data.key_attributes().label() != IdentityStateConst::ROOT_LABEL
+                    || data.threshold() == 0
+                    || data.threshold() as usize > public_keys.len()
+                    || public_keys.len() > u8::MAX as usize
+                    || public_keys
+                        .iter()
+                        .enumerate()
+                        .any(|(i, k)| public_keys[..i].contains(k))
+                {
+                    return deny();
+                }
+
+                // Should have a self signature of every new root key and the current root signatures
+                SignaturesCheck {
+                    self_sign: public_keys.len() as u8,
+                    prev_sign: 0,
+                    root_sign: root_threshold,
+                }
+            }
+            CommitRecoveryKey(data) => {
+                if data.key_attributes().label() != IdentityStateConst::RECOVERY_LABEL {
+                    return deny();
+                }
+
+                // Should only have root signature
+                SignaturesCheck {
+                    self_sign: 0,
+                    prev_sign: 0,
+                    root_sign: root_threshold,
                 }
             }
+            RevokeKey(data) => {
+                // Only the root key can revoke a key, the root key itself cannot be revoked
+                if data.key_attributes().label() == IdentityStateConst::ROOT_LABEL {
+                    return deny();
+                }
+
+                // The revoked key should be the current key with that label
+                match Self::get_public_key_static(existing_changes, data.key_attributes().label()) {
+                    Ok(public_key) if &public_key == data.public_key() => {}
+                    _ => return deny(),
+                }
+
+                SignaturesCheck {
+                    self_sign: 0,
+                    prev_sign: 0,
+                    root_sign: root_threshold,
+                }
+            }
+        };
+
+        let self_keys = match new_change.change() {
+            SetRootKeys(data) => data.public_keys().to_vec(),
+            change => change.public_key().into_iter().collect(),
         };
+        let prev_keys: Vec<PublicKey> =
+            Self::get_public_key_static(existing_changes, new_change.change().label())
+                .into_iter()
+                .collect();
+
+        let mut used_root_keys = vec![false; root_keys.len()];
+        let mut used_self_keys = vec![false; self_keys.len()];
+        let mut used_prev_keys = vec![false; prev_keys.len()];
 
         for signature in new_change.signatures() {
             let counter;
-            let public_key = match signature.stype() {
+            let (public_keys, used_keys) = match signature.stype() {
                 SignatureType::RootSign => {
                     if existing_changes.is_empty() {
                         return Err(IdentityError::VerifyFailed.into());
                     }
 
                     counter = &mut signatures_check.root_sign;
-                    Self::get_current_root_public_key(existing_changes)?
+                    (&root_keys, &mut used_root_keys)
                 }
                 SignatureType::SelfSign => {
                     counter = &mut signatures_check.self_sign;
-                    new_change.change().public_key()?
+                    (&self_keys, &mut used_self_keys)
                 }
                 SignatureType::PrevSign => {
                     counter = &mut signatures_check.prev_sign;
-                    Self::get_public_key_static(existing_changes, new_change.change().label())?
+                    (&prev_keys, &mut used_prev_keys)
                 }
             };
 
@@ -297,10 +458,20 @@ impl IdentityChangeHistory {
                 return Err(IdentityError::VerifyFailed.into());
             }
 
-            if !vault
-                .verify(signature.data(), &public_key, change_id.as_ref())
-                .await?
-            {
+            // Each key can only sign once
+            let mut verified = false;
+            for (public_key, used) in public_keys.iter().zip(used_keys.iter_mut()) {
+                if !*used
+                    && vault
+                        .verify(signature.data(), public_key, change_id.as_ref())
+                        .await?
+                {
+                    *used = true;
+                    verified = true;
+                    break;
+                }
+            }
+            if !verified {
                 return deny();
             }
 
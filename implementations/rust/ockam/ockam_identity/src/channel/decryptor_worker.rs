@@ -19,7 +19,7 @@ use ockam_core::{
     LocalSourceOnly, Mailbox, Mailboxes,
 };
 use ockam_core::{
-    route, Address, Any, Decodable, Encodable, LocalMessage, Result, Route, Routed,
+    route, Address, Any, Decodable, Encodable, LocalMessage, Priority, Result, Route, Routed,
     TransportMessage, Worker,
 };
 use ockam_core::{NewKeyExchanger, OutgoingAccessControl};
@@ -202,16 +202,22 @@ impl DecryptorWorker {
     ) -> Result<()> {
         if let Some(custom_payload) = custom_payload {
             // First message from initiator goes to the channel listener
-            ctx.send_from_address(
+            ctx.send_from_address_with_priority(
                 remote_route,
                 CreateResponderChannelMessage::new(payload, Some(custom_payload)),
                 decryptor_remote,
+                Priority::Control,
             )
             .await
         } else {
             // Other messages go to the channel worker itself
-            ctx.send_from_address(remote_route, payload, decryptor_remote)
-                .await
+            ctx.send_from_address_with_priority(
+                remote_route,
+                payload,
+                decryptor_remote,
+                Priority::Control,
+            )
+            .await
         }
     }
 
@@ -445,10 +451,11 @@ impl DecryptorWorker {
 
             let encrypted = state.encryptor.encrypt(&data).await?;
 
-            ctx.send_from_address(
+            ctx.send_from_address_with_priority(
                 self.remote_route.clone(),
                 encrypted,
                 self.addresses.decryptor_remote.clone(),
+                Priority::Control,
             )
             .await?;
             debug!(
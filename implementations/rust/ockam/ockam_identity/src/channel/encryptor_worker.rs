@@ -97,7 +97,9 @@ impl EncryptorWorker {
         // Encrypt the message
         let encrypted_payload = self.encryptor.encrypt(&msg.encode()?).await?;
 
-        // Send the message to the decryptor on the other side
+        // Send the message to the decryptor on the other side. Encrypted
+        // messages all have the normal priority, so that they don't tell
+        // which messages are control messages
         ctx.send_from_address(
             self.remote_route.clone(),
             encrypted_payload,
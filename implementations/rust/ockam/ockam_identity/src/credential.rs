@@ -1,5 +1,6 @@
 #![allow(missing_docs)]
 
+mod delegation;
 mod identity;
 mod public_identity;
 mod worker;
@@ -7,10 +8,12 @@ mod worker;
 pub mod access_control;
 pub mod one_time_code;
 
+pub use delegation::*;
+
 use ockam_core::compat::collections::HashMap;
 pub use one_time_code::*;
 
-use crate::IdentityIdentifier;
+use crate::{IdentityError, IdentityIdentifier};
 use core::fmt;
 use core::marker::PhantomData;
 use core::time::Duration;
@@ -54,6 +57,58 @@ pub struct Credential {
     /// Cryptographic signature of attributes data.
     #[cbor(with = "minicbor::bytes")]
     #[b(2)] signature: Vec<u8>,
+    /// Disclosed attributes of a selective-disclosure credential.
+    #[b(3)] disclosures: Option<Vec<Disclosure>>,
+    /// Delegation chain of a credential issued by a delegate.
+    #[b(4)] delegations: Option<Vec<Delegation>>,
+}
+
+/// Salted value of a single attribute of a selective-disclosure credential.
+///
+/// The signed [`CredentialData`] only contains a hash commitment of each
+/// disclosure, the holder chooses which disclosures are presented.
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct Disclosure {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<6391704>,
+    #[cbor(with = "minicbor::bytes")]
+    #[b(1)] salt: Vec<u8>,
+    #[b(2)] name: String,
+    #[cbor(with = "minicbor::bytes")]
+    #[b(3)] value: Vec<u8>,
+}
+
+impl Disclosure {
+    pub(crate) fn new(salt: Vec<u8>, name: String, value: Vec<u8>) -> Self {
+        Disclosure {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            salt,
+            name,
+            value,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// Data committed to in the credential, the name is length-prefixed so
+    /// that name and value boundaries can't be shifted.
+    pub(crate) fn commitment_data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.salt.len() + 4 + self.name.len() + self.value.len());
+        data.extend_from_slice(&self.salt);
+        data.extend_from_slice(&(self.name.len() as u32).to_be_bytes());
+        data.extend_from_slice(self.name.as_bytes());
+        data.extend_from_slice(&self.value);
+        data
+    }
 }
 
 impl fmt::Display for Credential {
@@ -63,6 +118,18 @@ impl fmt::Display for Credential {
             .map_err(|_| fmt::Error)?
             .into_verified();
         write!(f, "{}", data)?;
+        if !self.disclosures().is_empty() {
+            write!(f, "Disclosed:  ")?;
+            f.debug_map()
+                .entries(self.disclosures().iter().map(|d| {
+                    (
+                        d.name(),
+                        std::str::from_utf8(d.value()).unwrap_or("**binary**"),
+                    )
+                }))
+                .finish()?;
+            writeln!(f)?;
+        }
         writeln!(f, "Signature:  {}", hex::encode(self.signature.deref()))
     }
 
@@ -109,6 +176,22 @@ impl fmt::Display for CredentialData<Verified> {
             };
         writeln!(f, "Created:    {}", human_readable_time(self.created))?;
         writeln!(f, "Expires:    {}", human_readable_time(self.expires))?;
+        if let Some(audience) = &self.audience {
+            writeln!(f, "Audience:   {}", audience.join(", "))?;
+        }
+        if let Some(delegation) = &self.delegation {
+            write!(f, "Delegation: ")?;
+            f.debug_map()
+                .entries(delegation.iter().map(|(k, v)| {
+                    let values: Vec<&str> = v
+                        .iter()
+                        .map(|v| std::str::from_utf8(v).unwrap_or("**binary**"))
+                        .collect();
+                    (k, values)
+                }))
+                .finish()?;
+            writeln!(f, " for at most {}s", delegation.max_validity().as_secs())?;
+        }
         write!(f, "Attributes: ")?;
         f.debug_map()
             .entries(
@@ -145,7 +228,11 @@ pub struct CredentialData<T> {
     /// The time this credential expires.
     #[n(7)] expires: Timestamp,
     /// Term to represent the verification status type.
-    #[n(8)] status: Option<PhantomData<T>>
+    #[n(8)] status: Option<PhantomData<T>>,
+    /// The parties this credential may be presented to, anyone when not set.
+    #[b(9)] audience: Option<Vec<String>>,
+    /// The credentials the subject may issue on behalf of the issuer, if any.
+    #[b(10)] delegation: Option<DelegationScope>
 }
 
 impl CredentialData<Unverified> {
@@ -159,6 +246,8 @@ impl CredentialData<Unverified> {
             created: self.created,
             expires: self.expires,
             status: None::<PhantomData<Verified>>,
+            audience: self.audience,
+            delegation: self.delegation,
         }
     }
 }
@@ -170,6 +259,9 @@ impl Credential {
             subject,
             attrs: Attributes::new(),
             validity: MAX_CREDENTIAL_VALIDITY,
+            selective_disclosure: false,
+            audience: Vec::new(),
+            delegation: None,
         }
     }
 
@@ -181,12 +273,55 @@ impl Credential {
         &self.data
     }
 
+    /// Disclosed attributes, only present on selective-disclosure credentials.
+    pub fn disclosures(&self) -> &[Disclosure] {
+        self.disclosures.as_deref().unwrap_or_default()
+    }
+
+    /// Delegation chain, only present on credentials issued by a delegate.
+    pub fn delegations(&self) -> &[Delegation] {
+        self.delegations.as_deref().unwrap_or_default()
+    }
+
+    /// Create a copy of this selective-disclosure credential which only
+    /// discloses the given attributes.
+    pub fn disclose(&self, names: &[&str]) -> Result<Credential> {
+        let disclosures = match &self.disclosures {
+            Some(d) => d,
+            None => return Err(IdentityError::SelectiveDisclosureNotSupported.into()),
+        };
+        let mut disclosed = Vec::with_capacity(names.len());
+        for name in names {
+            match disclosures.iter().find(|d| d.name == *name) {
+                Some(d) => disclosed.push(d.clone()),
+                None => return Err(IdentityError::UnknownDisclosure.into()),
+            }
+        }
+        Ok(Credential {
+            disclosures: Some(disclosed),
+            ..self.clone()
+        })
+    }
+
     fn new(data: Vec<u8>, signature: Vec<u8>) -> Self {
         Credential {
             #[cfg(feature = "tag")]
             tag: TypeTag,
             data,
             signature,
+            disclosures: None,
+            delegations: None,
+        }
+    }
+
+    fn new_with_disclosures(
+        data: Vec<u8>,
+        signature: Vec<u8>,
+        disclosures: Vec<Disclosure>,
+    ) -> Self {
+        Credential {
+            disclosures: Some(disclosures),
+            ..Credential::new(data, signature)
         }
     }
 }
@@ -223,6 +358,24 @@ impl CredentialData<Verified> {
     pub fn into_attributes(self) -> Attributes {
         self.attributes
     }
+
+    pub fn audience(&self) -> Option<&[String]> {
+        self.audience.as_deref()
+    }
+
+    /// Scope of the credentials the subject may issue, only set on delegation credentials.
+    pub fn delegation(&self) -> Option<&DelegationScope> {
+        self.delegation.as_ref()
+    }
+
+    /// Can this credential be presented to a party known by any of the given
+    /// audience values? Credentials without audience can be presented to anyone.
+    pub fn is_valid_for(&self, audience: &[String]) -> bool {
+        match &self.audience {
+            Some(scope) => scope.iter().any(|a| audience.contains(a)),
+            None => true,
+        }
+    }
 }
 
 impl CredentialData<Unverified> {
@@ -235,6 +388,9 @@ impl CredentialData<Unverified> {
     pub fn unverified_subject(&self) -> &IdentityIdentifier {
         &self.subject
     }
+    pub fn unverified_audience(&self) -> Option<&[String]> {
+        self.audience.as_deref()
+    }
 }
 
 impl TryFrom<&Credential> for CredentialData<Unverified> {
@@ -327,11 +483,32 @@ impl From<Timestamp> for u64 {
     }
 }
 
+impl From<u64> for Timestamp {
+    fn from(t: u64) -> Self {
+        Timestamp(t)
+    }
+}
+
 /// A schema identifier allows discriminate sets of credential attributes.
 #[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cbor(transparent)]
 pub struct SchemaId(#[n(0)] pub u64);
 
+impl SchemaId {
+    /// Bit set in the schema identifier of selective-disclosure credentials.
+    pub const SELECTIVE_DISCLOSURE: u64 = 1 << 63;
+
+    /// Mark this schema as using the selective-disclosure format.
+    pub fn with_selective_disclosure(self) -> Self {
+        SchemaId(self.0 | Self::SELECTIVE_DISCLOSURE)
+    }
+
+    /// Does this schema use the selective-disclosure format?
+    pub fn is_selective_disclosure(&self) -> bool {
+        self.0 & Self::SELECTIVE_DISCLOSURE != 0
+    }
+}
+
 impl From<SchemaId> for u64 {
     fn from(s: SchemaId) -> Self {
         s.0
@@ -350,6 +527,9 @@ pub struct CredentialBuilder {
     attrs: Attributes,
     subject: IdentityIdentifier,
     validity: Duration,
+    selective_disclosure: bool,
+    audience: Vec<String>,
+    delegation: Option<DelegationScope>,
 }
 
 impl CredentialBuilder {
@@ -372,6 +552,28 @@ impl CredentialBuilder {
         self
     }
 
+    /// Commit to each attribute separately, so that the holder can disclose
+    /// only some of them when presenting the credential.
+    pub fn with_selective_disclosure(mut self) -> Self {
+        self.selective_disclosure = true;
+        self
+    }
+
+    /// Restrict the credential to the given audience, e.g. a project id, the
+    /// identifier of a node or a service name. Can be called several times, the
+    /// credential is then valid for any of the given audience values.
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience.push(audience.into());
+        self
+    }
+
+    /// Make this a delegation credential, allowing the subject to issue
+    /// credentials within the given scope on behalf of the issuer.
+    pub fn with_delegation(mut self, scope: DelegationScope) -> Self {
+        self.delegation = Some(scope);
+        self
+    }
+
     /// Set validity duration of the credential.
     ///
     /// # Panics
@@ -504,6 +706,8 @@ Signature:  010203
             created: Timestamp(120),
             expires: Timestamp(200),
             status: None::<PhantomData<Verified>>,
+            audience: None,
+            delegation: None,
         }
     }
 }
@@ -9,6 +9,7 @@ use ockam_core::{async_trait, compat::boxed::Box, RelayMessage};
 #[derive(Clone)]
 pub struct CredentialAccessControl {
     required_attributes: Vec<(String, Vec<u8>)>,
+    audience: Vec<String>,
     storage: Arc<dyn IdentityAttributeStorage>,
 }
 
@@ -19,9 +20,18 @@ impl CredentialAccessControl {
     ) -> Self {
         Self {
             required_attributes: required_attributes.to_vec(),
+            audience: Vec::new(),
             storage: Arc::new(storage),
         }
     }
+
+    /// Also accept attributes from credentials valid for one of the given audience values,
+    /// e.g. the name of the service guarded by this access control. Attributes from credentials
+    /// restricted to an audience are rejected otherwise.
+    pub fn with_audience(mut self, audience: &[String]) -> Self {
+        self.audience = audience.to_vec();
+        self
+    }
 }
 
 impl Debug for CredentialAccessControl {
@@ -30,6 +40,7 @@ impl Debug for CredentialAccessControl {
 
         f.debug_struct("Credential Access Control")
             .field("Required attributes", &attributes)
+            .field("Audience", &self.audience)
             .finish()
     }
 }
@@ -49,6 +60,12 @@ impl IncomingAccessControl for CredentialAccessControl {
                 None => return Ok(false), // No attributes for that Identity
             };
 
+            if let Some(scope) = attributes.audience() {
+                if !scope.iter().any(|a| self.audience.contains(a)) {
+                    return Ok(false); // Credential was issued for another audience
+                }
+            }
+
             for required_attribute in self.required_attributes.iter() {
                 let attr_val = match attributes.attrs().get(&required_attribute.0) {
                     Some(v) => v,
@@ -0,0 +1,254 @@
+use crate::change_history::IdentityHistoryComparison;
+use crate::credential::{
+    Credential, CredentialBuilder, CredentialData, Timestamp, Unverified, Verified,
+};
+use crate::{Identity, IdentityError, IdentityIdentifier, PublicIdentity};
+use core::time::Duration;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::{collections::BTreeMap, string::String, vec::Vec};
+use ockam_core::Result;
+
+#[cfg(feature = "tag")]
+use crate::TypeTag;
+
+/// Restrictions on the credentials a delegate may issue.
+///
+/// A delegation credential carries a scope and allows its subject to issue
+/// credentials that verifiers trusting the delegating authority accept, as
+/// long as they only contain the allowed attributes and don't exceed the
+/// maximum validity.
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct DelegationScope {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<8294017>,
+    /// Attribute names the delegate may issue with their allowed values,
+    /// any value is allowed when the list is empty.
+    #[b(1)] attributes: BTreeMap<String, Vec<ByteVec>>,
+    /// Maximum validity of the issued credentials, in seconds.
+    #[n(2)] max_validity: u64,
+}
+
+impl DelegationScope {
+    /// Create a scope without any allowed attribute.
+    pub fn new(max_validity: Duration) -> Self {
+        DelegationScope {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            attributes: BTreeMap::new(),
+            max_validity: max_validity.as_secs(),
+        }
+    }
+
+    /// Allow the attribute with any value.
+    pub fn allow_attribute(mut self, name: &str) -> Self {
+        self.attributes.insert(name.into(), Vec::new());
+        self
+    }
+
+    /// Allow the attribute with the given value. Can be called several times
+    /// to allow several values.
+    pub fn allow_value(mut self, name: &str, value: &[u8]) -> Self {
+        self.attributes
+            .entry(name.into())
+            .or_default()
+            .push(value.to_vec().into());
+        self
+    }
+
+    pub fn max_validity(&self) -> Duration {
+        Duration::from_secs(self.max_validity)
+    }
+
+    /// Is the attribute with the given value allowed by this scope?
+    pub fn allows(&self, name: &str, value: &[u8]) -> bool {
+        match self.attributes.get(name) {
+            Some(values) => values.is_empty() || values.iter().any(|v| &***v == value),
+            None => false,
+        }
+    }
+
+    /// Is this scope at most as permissive as the given one?
+    pub fn is_within(&self, other: &DelegationScope) -> bool {
+        self.max_validity <= other.max_validity
+            && self
+                .attributes
+                .iter()
+                .all(|(name, values)| match other.attributes.get(name) {
+                    Some(allowed) if allowed.is_empty() => true,
+                    Some(allowed) => {
+                        !values.is_empty() && values.iter().all(|v| allowed.contains(v))
+                    }
+                    None => false,
+                })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<ByteVec>)> {
+        self.attributes.iter()
+    }
+}
+
+/// A link of the delegation chain attached to a credential issued by a delegate.
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct Delegation {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<5930261>,
+    /// Delegation credential issued to the delegate.
+    #[b(1)] credential: Credential,
+    /// Exported change history of the delegate, needed to check its signatures.
+    #[cbor(with = "minicbor::bytes")]
+    #[b(2)] delegate: Vec<u8>,
+}
+
+impl Delegation {
+    fn new(credential: Credential, delegate: Vec<u8>) -> Self {
+        Delegation {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            credential,
+            delegate,
+        }
+    }
+
+    pub fn credential(&self) -> &Credential {
+        &self.credential
+    }
+}
+
+impl Identity {
+    /// Create a signed credential on behalf of the authority which issued us the given
+    /// delegation credential. The delegation chain is attached to the credential so that
+    /// verifiers only need to trust the authority at the root of the chain.
+    ///
+    /// The validity of the credential is capped to the maximum validity of the delegation
+    /// scope and to the expiry of the delegation credential.
+    pub async fn issue_delegated_credential(
+        &self,
+        mut builder: CredentialBuilder,
+        delegation: &Credential,
+    ) -> Result<Credential> {
+        let data: CredentialData<Unverified> = minicbor::decode(delegation.unverified_data())?;
+        let scope = match &data.delegation {
+            Some(scope) if data.unverified_subject() == self.identifier() => scope,
+            _ => return Err(IdentityError::InvalidDelegation.into()),
+        };
+        let remaining = Timestamp::now()
+            .and_then(|now| data.expires.elapsed(now))
+            .ok_or(IdentityError::InvalidDelegation)?;
+        builder.validity = builder.validity.min(scope.max_validity()).min(remaining);
+
+        let mut chain = delegation.delegations().to_vec();
+        chain.push(Delegation::new(
+            Credential {
+                delegations: None,
+                ..delegation.clone()
+            },
+            self.export().await?,
+        ));
+
+        let credential = self.issue_credential(builder).await?;
+        Ok(Credential {
+            delegations: Some(chain),
+            ..credential
+        })
+    }
+
+    /// Verify a credential issued by a delegate: every link of the delegation chain must be
+    /// signed by the previous delegate, the first one by an authority, and the credential
+    /// must stay within the scope and lifetime of every delegation.
+    ///
+    /// The change history of a delegate we already know is used instead of the one in the
+    /// chain when it is more recent, so that a link signed with a revoked key is rejected.
+    pub(crate) async fn verify_delegated_credential(
+        &self,
+        sender: &IdentityIdentifier,
+        credential: &Credential,
+        authorities: &[&PublicIdentity],
+    ) -> Result<CredentialData<Verified>> {
+        let vault = self.vault.clone();
+        let mut issuer: Option<PublicIdentity> = None;
+        let mut scope: Option<DelegationScope> = None;
+        let mut not_after = None;
+        let mut delegates = Vec::new();
+
+        for delegation in credential.delegations() {
+            let mut delegate = PublicIdentity::import(&delegation.delegate, vault.clone()).await?;
+            if let Some(known) = self.get_known_identity(delegate.identifier()).await? {
+                match delegate.changes().compare(known.changes()) {
+                    IdentityHistoryComparison::Equal | IdentityHistoryComparison::Newer => {}
+                    IdentityHistoryComparison::Older => delegate = known,
+                    IdentityHistoryComparison::Conflict => {
+                        return Err(IdentityError::InvalidDelegation.into())
+                    }
+                }
+            }
+            let signer = match &issuer {
+                Some(i) => i,
+                None => {
+                    let data: CredentialData<Unverified> =
+                        match minicbor::decode(delegation.credential.unverified_data()) {
+                            Ok(d) => d,
+                            Err(_) => return Err(IdentityError::InvalidCredentialFormat.into()),
+                        };
+                    match authorities
+                        .iter()
+                        .find(|a| a.identifier() == data.unverified_issuer())
+                    {
+                        Some(a) => *a,
+                        None => return Err(IdentityError::UnknownAuthority.into()),
+                    }
+                }
+            };
+            let data = match signer
+                .verify_credential(&delegation.credential, delegate.identifier(), vault.clone())
+                .await
+            {
+                Ok(d) => d,
+                Err(_) => return Err(IdentityError::InvalidDelegation.into()),
+            };
+            let link_scope = match data.delegation {
+                Some(s) if scope.as_ref().map_or(true, |p| s.is_within(p)) => s,
+                _ => return Err(IdentityError::InvalidDelegation.into()),
+            };
+
+            not_after = Some(not_after.map_or(data.expires, |n| core::cmp::min(n, data.expires)));
+            scope = Some(link_scope);
+            delegates.push(delegate.clone());
+            issuer = Some(delegate);
+        }
+
+        let (issuer, scope, not_after) = match (issuer, scope, not_after) {
+            (Some(i), Some(s), Some(n)) => (i, s, n),
+            _ => return Err(IdentityError::UnknownAuthority.into()),
+        };
+        let data = match issuer.verify_credential(credential, sender, vault).await {
+            Ok(d) => d,
+            Err(_) => return Err(IdentityError::CredentialVerificationFailed.into()),
+        };
+
+        let within_lifetime = data.expires <= not_after
+            && data
+                .expires
+                .elapsed(data.created)
+                .map_or(false, |v| v <= scope.max_validity());
+        let within_scope = data
+            .attributes
+            .iter()
+            .all(|(name, value)| scope.allows(name, value));
+        if !within_lifetime || !within_scope {
+            return Err(IdentityError::InvalidDelegation.into());
+        }
+
+        // Remember the delegates so that older change histories are rejected later on
+        for delegate in delegates {
+            self.update_known_identity(delegate.identifier(), &delegate)
+                .await?;
+        }
+
+        Ok(data)
+    }
+}
@@ -2,15 +2,17 @@ use crate::alloc::string::ToString;
 use crate::authenticated_storage::{AttributesEntry, IdentityAttributeStorage};
 use crate::credential::worker::CredentialExchangeWorker;
 use crate::credential::{
-    Credential, CredentialBuilder, CredentialData, Timestamp, Unverified, Verified,
+    Attributes, Credential, CredentialBuilder, CredentialData, Disclosure, SchemaId, Timestamp,
+    Unverified, Verified,
 };
 use crate::{
     Identity, IdentityError, IdentityIdentifier, IdentitySecureChannelLocalInfo,
-    IdentityStateConst, IdentityVault, PublicIdentity,
+    IdentityStateConst, PublicIdentity,
 };
 use core::marker::PhantomData;
 use minicbor::Decoder;
 use ockam_core::api::{Request, Response, Status};
+use ockam_core::compat::rand::{self, RngCore};
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::errcode::{Kind, Origin};
@@ -35,24 +37,56 @@ impl Identity {
         let now = Timestamp::now()
             .ok_or_else(|| Error::new(Origin::Core, Kind::Internal, "invalid system time"))?;
         let exp = Timestamp(u64::from(now).saturating_add(builder.validity.as_secs()));
+
+        // Selective-disclosure credentials only sign a salted commitment of every attribute
+        let (schema, attributes, disclosures) = if builder.selective_disclosure {
+            let schema = builder
+                .schema
+                .unwrap_or(SchemaId(0))
+                .with_selective_disclosure();
+            let mut attributes = Attributes::new();
+            let mut disclosures = Vec::with_capacity(builder.attrs.len());
+            for (name, value) in builder.attrs.iter() {
+                let mut salt = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut salt);
+                let disclosure = Disclosure::new(salt.to_vec(), name.clone(), value.to_vec());
+                let digest = self.vault.sha256(&disclosure.commitment_data()).await?;
+                attributes.put(name, &digest);
+                disclosures.push(disclosure);
+            }
+            (Some(schema), attributes, Some(disclosures))
+        } else {
+            (builder.schema, builder.attrs, None)
+        };
+
         let dat = CredentialData {
-            schema: builder.schema,
-            attributes: builder.attrs,
+            schema,
+            attributes,
             subject: builder.subject,
             issuer: self.identifier().clone(),
             issuer_key_label: key_label.into(),
             created: now,
             expires: exp,
             status: None::<PhantomData<Verified>>,
+            audience: (!builder.audience.is_empty()).then_some(builder.audience),
+            delegation: builder.delegation,
         };
         let bytes = minicbor::to_vec(&dat)?;
 
         let sig = self.create_signature(&bytes, None).await?;
-        Ok(Credential::new(bytes, SignatureVec::from(sig)))
+        match disclosures {
+            Some(disclosures) => Ok(Credential::new_with_disclosures(
+                bytes,
+                SignatureVec::from(sig),
+                disclosures,
+            )),
+            None => Ok(Credential::new(bytes, SignatureVec::from(sig))),
+        }
     }
 
     /// Start worker that will be available to receive others attributes and put them into storage,
-    /// after successful verification
+    /// after successful verification. Attributes of credentials restricted to an audience are
+    /// stored along with it, so that access controls only use them for that audience.
     pub async fn start_credential_exchange_worker(
         &self,
         authorities: Vec<PublicIdentity>,
@@ -83,8 +117,11 @@ impl Identity {
         &self,
         route: impl Into<Route>,
         provided_credential: Option<&Credential>,
+        disclosed_attributes: Option<&[&str]>,
     ) -> Result<()> {
-        let credential = self.get_credential_or_provided(provided_credential).await?;
+        let credential = self
+            .get_credential_or_provided(provided_credential, disclosed_attributes)
+            .await?;
 
         let buf = request(
             &self.ctx,
@@ -114,8 +151,11 @@ impl Identity {
         authorities: impl IntoIterator<Item = &PublicIdentity>,
         attributes_storage: Arc<dyn IdentityAttributeStorage>,
         provided_credential: Option<&Credential>,
+        disclosed_attributes: Option<&[&str]>,
     ) -> Result<()> {
-        let credential = self.get_credential_or_provided(provided_credential).await?;
+        let credential = self
+            .get_credential_or_provided(provided_credential, disclosed_attributes)
+            .await?;
 
         let path = "actions/present_mutual";
         let (buf, local_info) = request_with_local_info(
@@ -162,26 +202,33 @@ impl Identity {
 
 impl Identity {
     async fn verify_credential(
+        &self,
         sender: &IdentityIdentifier,
         credential: &Credential,
         authorities: impl IntoIterator<Item = &PublicIdentity>,
-        vault: Arc<dyn IdentityVault>,
     ) -> Result<CredentialData<Verified>> {
         let credential_data: CredentialData<Unverified> = match minicbor::decode(&credential.data) {
             Ok(c) => c,
             Err(_) => return Err(IdentityError::InvalidCredentialFormat.into()),
         };
 
+        let authorities: Vec<&PublicIdentity> = authorities.into_iter().collect();
         let issuer = authorities
-            .into_iter()
+            .iter()
             .find(|&x| x.identifier() == &credential_data.issuer);
         let issuer = match issuer {
             Some(i) => i,
+            // Credentials issued by a delegate are checked against their delegation chain
+            None if !credential.delegations().is_empty() => {
+                return self
+                    .verify_delegated_credential(sender, credential, &authorities)
+                    .await
+            }
             None => return Err(IdentityError::UnknownAuthority.into()),
         };
 
         let credential_data = match issuer
-            .verify_credential(credential, sender, vault.clone())
+            .verify_credential(credential, sender, self.vault.clone())
             .await
         {
             Ok(d) => d,
@@ -195,15 +242,9 @@ impl Identity {
         &self,
         credential: &Credential,
         authorities: impl IntoIterator<Item = &PublicIdentity>,
-    ) -> Result<()> {
-        let _ = Self::verify_credential(
-            self.identifier(),
-            credential,
-            authorities,
-            self.vault.clone(),
-        )
-        .await?;
-        Ok(())
+    ) -> Result<CredentialData<Verified>> {
+        self.verify_credential(self.identifier(), credential, authorities)
+            .await
     }
 
     pub(crate) async fn receive_presented_credential(
@@ -213,8 +254,9 @@ impl Identity {
         authorities: impl IntoIterator<Item = &PublicIdentity>,
         attributes_storage: Arc<dyn IdentityAttributeStorage>,
     ) -> Result<()> {
-        let credential_data =
-            Self::verify_credential(&sender, &credential, authorities, self.vault.clone()).await?;
+        let credential_data = self
+            .verify_credential(&sender, &credential, authorities)
+            .await?;
 
         //TODO: review the credential' attributes types.   They are references and has lifetimes,
         //etc,  but in reality this is always just deserizalided (either from wire or from
@@ -233,7 +275,8 @@ impl Identity {
                     Timestamp::now().unwrap(),
                     Some(credential_data.expires),
                     Some(credential_data.issuer),
-                ),
+                )
+                .with_audience(credential_data.audience),
             )
             .await?;
 
@@ -241,10 +284,12 @@ impl Identity {
     }
 
     /// Gets a clone of the identities current credential
-    /// or uses the provided credential if one exists
+    /// or uses the provided credential if one exists.
+    /// Only the given attributes are disclosed for selective-disclosure credentials.
     async fn get_credential_or_provided(
         &self,
         provided_cred: Option<&Credential>,
+        disclosed_attributes: Option<&[&str]>,
     ) -> Result<Credential> {
         let rw_credential = self.credential.read().await;
         let credential = match provided_cred {
@@ -258,6 +303,9 @@ impl Identity {
             })?,
         };
 
-        Ok(credential.clone())
+        match disclosed_attributes {
+            Some(names) => credential.disclose(names),
+            None => Ok(credential.clone()),
+        }
     }
 }
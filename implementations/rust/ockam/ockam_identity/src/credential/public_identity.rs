@@ -1,6 +1,6 @@
 use crate::alloc::borrow::ToOwned;
 use crate::authenticated_storage::IdentityAttributeStorage;
-use crate::credential::{Credential, CredentialData, Timestamp, Verified};
+use crate::credential::{Attributes, Credential, CredentialData, Disclosure, Timestamp, Verified};
 use crate::PublicIdentity;
 use crate::{IdentityIdentifier, IdentityStateConst, IdentityVault};
 use ockam_core::compat::collections::BTreeMap;
@@ -61,7 +61,7 @@ impl PublicIdentity {
                 &sig,
                 credential.unverified_data(),
                 Some(dat.unverified_key_label()),
-                vault,
+                vault.clone(),
             )
             .await?
         {
@@ -71,7 +71,50 @@ impl PublicIdentity {
                 "invalid signature",
             ));
         }
-        Ok(dat.into_verified())
+
+        let mut dat = dat.into_verified();
+        match dat.schema {
+            Some(schema) if schema.is_selective_disclosure() => {
+                dat.attributes =
+                    Self::verify_disclosures(&dat.attributes, credential.disclosures(), vault)
+                        .await?;
+            }
+            _ => {
+                if !credential.disclosures().is_empty() {
+                    return Err(Error::new(
+                        Origin::Application,
+                        Kind::Invalid,
+                        "unexpected disclosures",
+                    ));
+                }
+            }
+        }
+        Ok(dat)
+    }
+
+    /// Check the disclosures of a selective-disclosure credential against the signed
+    /// commitments and return the disclosed attributes.
+    async fn verify_disclosures(
+        commitments: &Attributes,
+        disclosures: &[Disclosure],
+        vault: Arc<dyn IdentityVault>,
+    ) -> Result<Attributes> {
+        let mut attributes = Attributes::new();
+        for disclosure in disclosures {
+            let commitment = commitments.get(disclosure.name()).ok_or_else(|| {
+                Error::new(Origin::Application, Kind::Invalid, "unknown disclosure")
+            })?;
+            let digest = vault.sha256(&disclosure.commitment_data()).await?;
+            if commitment != digest.as_slice() {
+                return Err(Error::new(
+                    Origin::Application,
+                    Kind::Invalid,
+                    "invalid disclosure",
+                ));
+            }
+            attributes.put(disclosure.name(), disclosure.value());
+        }
+        Ok(attributes)
     }
 
     /// Return authenticated non-expired attributes attached to that Identity
@@ -46,6 +46,24 @@ pub enum IdentityError {
     SecureChannelNotFound,
     /// Sessions setup inconsistency
     SessionsInconsistency,
+    /// The key was revoked
+    KeyRevoked,
+    /// Invalid number of root keys or threshold
+    InvalidThreshold,
+    /// Not enough root keys are available to sign
+    ThresholdNotReached,
+    /// `IdentityChangeHistory` conflicts with the known one
+    IdentityHistoryConflict,
+    /// `Identity` is not known
+    UnknownIdentity,
+    /// `Credential` doesn't support selective disclosure
+    SelectiveDisclosureNotSupported,
+    /// Attribute is not part of the selective-disclosure `Credential`
+    UnknownDisclosure,
+    /// `Credential` was presented outside of its audience
+    InvalidCredentialAudience,
+    /// Delegation chain of a `Credential` is invalid or the `Credential` exceeds its scope
+    InvalidDelegation,
 }
 
 impl ockam_core::compat::error::Error for IdentityError {}
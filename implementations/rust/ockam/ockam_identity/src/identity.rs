@@ -11,7 +11,7 @@ use crate::{
 use ockam_core::compat::{boxed::Box, string::String, sync::Arc, vec::Vec};
 use ockam_core::vault::Secret::Key;
 use ockam_core::vault::{
-    SecretKey, SecretPersistence, SecretType, Signature, CURVE25519_SECRET_LENGTH_U32,
+    PublicKey, SecretKey, SecretPersistence, SecretType, Signature, CURVE25519_SECRET_LENGTH_U32,
 };
 use ockam_core::{Address, Result};
 use ockam_core::{AsyncTryClone, DenyAll};
@@ -31,6 +31,7 @@ pub struct Identity {
     pub(crate) authenticated_storage: Arc<dyn AuthenticatedStorage>,
     pub(crate) secure_channel_registry: SecureChannelRegistry,
     pub(crate) vault: Arc<dyn IdentityVault>,
+    pub(crate) cosigner_vaults: Arc<RwLock<Vec<Arc<dyn IdentityVault>>>>,
 }
 
 /// `Identity`-related constants
@@ -42,8 +43,12 @@ impl IdentityStateConst {
     pub const INITIAL_CHANGE: &'static [u8] = "OCKAM_INITIAL_CHANGE".as_bytes();
     /// Label for [`crate::Identity`] update key
     pub const ROOT_LABEL: &'static str = "OCKAM_RK";
+    /// Label for [`crate::Identity`] recovery key commitment
+    pub const RECOVERY_LABEL: &'static str = "OCKAM_RECOVERY_KEY";
     /// Change history key for AuthenticatedStorage
     pub const CHANGE_HISTORY_KEY: &'static str = "CHANGE_HISTORY";
+    /// Conflicting change history key for AuthenticatedStorage
+    pub const CONFLICTING_CHANGE_HISTORY_KEY: &'static str = "CONFLICTING_CHANGE_HISTORY";
     /// Attributes key for AuthenticatedStorage
     pub const ATTRIBUTES_KEY: &'static str = "ATTRIBUTES";
 }
@@ -66,6 +71,7 @@ impl Identity {
             authenticated_storage,
             secure_channel_registry,
             vault,
+            cosigner_vaults: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
@@ -128,7 +134,6 @@ impl Identity {
             kid,
             initial_change_id,
             key_attribs.clone(),
-            None,
             vault.clone(),
         )
         .await?;
@@ -324,7 +329,7 @@ impl Identity {
         self.vault.compute_key_id_for_public_key(&public_key).await
     }
 
-    async fn add_change(&self, change: IdentitySignedChange) -> Result<()> {
+    pub(crate) async fn add_change(&self, change: IdentitySignedChange) -> Result<()> {
         self.change_history
             .write()
             .await
@@ -379,13 +384,41 @@ impl Identity {
         self.add_change(change).await
     }
 
+    /// Commit the hash of a recovery key, the recovery key can later replace
+    /// the root key if the latter is lost. The recovery key itself is
+    /// expected to be stored offline
+    pub async fn commit_recovery_key(&self, recovery_key: &PublicKey) -> Result<()> {
+        let change = self.make_commit_recovery_key_change(recovery_key).await?;
+
+        self.add_change(change).await
+    }
+
+    /// Replace the root key with the committed recovery key. The recovery
+    /// key must be present in this `Identity` vault
+    pub async fn recover_root_key(&self, recovery_key: &KeyId) -> Result<()> {
+        let change = self.make_recover_root_key_change(recovery_key).await?;
+
+        self.add_change(change).await
+    }
+
+    /// Revoke an existing key with a given label, signatures made with
+    /// that key are rejected once the updated history is known.
+    /// The root key cannot be revoked
+    pub async fn revoke_key(&self, label: &str) -> Result<()> {
+        let change = self
+            .make_revoke_key_change(KeyAttributes::default_with_label(label.to_string()))
+            .await?;
+
+        self.add_change(change).await
+    }
+
     /// Get [`Secret`] key. Key is uniquely identified by label in [`KeyAttributes`]
     pub(crate) async fn get_root_secret_key(&self) -> Result<KeyId> {
         self.get_secret_key(IdentityStateConst::ROOT_LABEL).await
     }
 
     pub(crate) async fn get_secret_key(&self, label: &str) -> Result<KeyId> {
-        let change = IdentityChangeHistory::find_last_key_change(
+        let change = IdentityChangeHistory::find_current_key_change(
             self.change_history.read().await.as_ref(),
             label,
         )?
@@ -401,6 +434,13 @@ impl Identity {
         data: &[u8],
         key_label: Option<&str>,
     ) -> Result<Signature> {
+        let key_label = key_label.filter(|label| *label != IdentityStateConst::ROOT_LABEL);
+        if key_label.is_none()
+            && IdentityChangeHistory::has_threshold_root(self.change_history.read().await.as_ref())?
+        {
+            return self.create_threshold_signature(data).await;
+        }
+
         let secret = match key_label {
             Some(label) => self.get_secret_key(label).await?,
             None => self.get_root_secret_key().await?,
@@ -477,9 +517,10 @@ impl Identity {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::change::{self, IdentityChange, RotateKeyChangeData, SignatureType};
     use ockam_core::errcode::{Kind, Origin};
-    use ockam_core::vault::PublicKey;
-    use ockam_core::Error;
+    use ockam_core::vault::{PublicKey, SecretVault, Signer};
+    use ockam_core::{Encodable, Error};
     use ockam_vault::Vault;
 
     fn test_error<S: Into<String>>(error: S) -> Result<()> {
@@ -561,4 +602,204 @@ mod test {
 
         Ok(())
     }
+
+    #[ockam_macros::test]
+    async fn test_revoke_key(ctx: &mut Context) -> Result<()> {
+        let vault = Vault::create();
+
+        let identity = Identity::create(ctx, vault.clone()).await?;
+        identity.create_key("Credentials".to_string()).await?;
+
+        let data = b"credential";
+        let signature = identity.create_signature(data, Some("Credentials")).await?;
+        let public_identity = identity.to_public().await?;
+        if !public_identity
+            .verify_signature(&signature, data, Some("Credentials"), vault.clone())
+            .await?
+        {
+            return test_error("signature verification failed before revocation");
+        }
+
+        identity.revoke_key("Credentials").await?;
+
+        if !identity.verify_changes().await? {
+            return test_error("verify_changes failed");
+        }
+
+        if identity.get_public_key("Credentials").await.is_ok() {
+            return test_error("revoked public key is still available");
+        }
+
+        if identity
+            .create_signature(data, Some("Credentials"))
+            .await
+            .is_ok()
+        {
+            return test_error("signature created with a revoked key");
+        }
+
+        let public_identity = identity.to_public().await?;
+        if public_identity
+            .verify_signature(&signature, data, Some("Credentials"), vault.clone())
+            .await
+            .is_ok()
+        {
+            return test_error("signature of a revoked key was accepted");
+        }
+
+        if identity.rotate_key("Credentials").await.is_ok() {
+            return test_error("revoked key was rotated");
+        }
+
+        if identity.create_key("Credentials".to_string()).await.is_ok() {
+            return test_error("revoked key was created again");
+        }
+
+        if identity.revoke_key("Credentials").await.is_ok() {
+            return test_error("revoked key was revoked twice");
+        }
+
+        if identity
+            .revoke_key(IdentityStateConst::ROOT_LABEL)
+            .await
+            .is_ok()
+        {
+            return test_error("root key was revoked");
+        }
+
+        identity.rotate_root_key().await?;
+
+        if !identity.verify_changes().await? {
+            return test_error("verify_changes failed");
+        }
+
+        let imported = PublicIdentity::import(&identity.export().await?, vault).await?;
+        if imported.get_public_key("Credentials").is_ok() {
+            return test_error("revoked public key is available after import");
+        }
+
+        ctx.stop().await?;
+
+        Ok(())
+    }
+
+    fn recovery_key_attributes() -> SecretAttributes {
+        SecretAttributes::new(
+            SecretType::Ed25519,
+            SecretPersistence::Persistent,
+            CURVE25519_SECRET_LENGTH_U32,
+        )
+    }
+
+    #[ockam_macros::test]
+    async fn test_recover_root_key(ctx: &mut Context) -> Result<()> {
+        let vault = Vault::create();
+
+        let identity = Identity::create(ctx, vault.clone()).await?;
+
+        // The recovery key is generated and kept outside of the identity vault
+        let recovery_vault = Vault::create();
+        let recovery_key = recovery_vault
+            .secret_generate(recovery_key_attributes())
+            .await?;
+        let recovery_public_key = recovery_vault.secret_public_key_get(&recovery_key).await?;
+
+        identity.commit_recovery_key(&recovery_public_key).await?;
+        identity.rotate_root_key().await?;
+
+        if !identity.verify_changes().await? {
+            return test_error("verify_changes failed");
+        }
+
+        // Lose the root key, then import the recovery key
+        let lost_vault = Vault::create();
+        let identity = Identity::import(ctx, &identity.export().await?, lost_vault.clone()).await?;
+        if identity.create_signature(b"data", None).await.is_ok() {
+            return test_error("root key is still present");
+        }
+
+        let secret = recovery_vault.secret_export(&recovery_key).await?;
+        let recovery_key = lost_vault
+            .secret_import(secret, recovery_key_attributes())
+            .await?;
+
+        let identifier = identity.identifier().clone();
+        identity.recover_root_key(&recovery_key).await?;
+
+        if !identity.verify_changes().await? {
+            return test_error("verify_changes failed");
+        }
+
+        if identity.get_root_public_key().await? != recovery_public_key {
+            return test_error("root key was not replaced by the recovery key");
+        }
+
+        let public_identity = PublicIdentity::import(&identity.export().await?, vault).await?;
+        if public_identity.identifier() != &identifier {
+            return test_error("identifier changed after recovery");
+        }
+
+        // The recovery key can be used only once
+        if identity.recover_root_key(&recovery_key).await.is_ok() {
+            return test_error("recovery key was used twice");
+        }
+
+        // The root key can be used normally after recovery
+        identity.create_key("Truck management".to_string()).await?;
+        identity.rotate_root_key().await?;
+
+        if !identity.verify_changes().await? {
+            return test_error("verify_changes failed");
+        }
+
+        ctx.stop().await?;
+
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn test_recover_root_key_without_commitment(ctx: &mut Context) -> Result<()> {
+        let vault = Vault::create();
+
+        let identity = Identity::create(ctx, vault.clone()).await?;
+
+        let committed_key = vault.secret_generate(recovery_key_attributes()).await?;
+        let other_key = vault.secret_generate(recovery_key_attributes()).await?;
+
+        if identity.recover_root_key(&other_key).await.is_ok() {
+            return test_error("root key recovered without a commitment");
+        }
+
+        let identity = Identity::create(ctx, vault.clone()).await?;
+        identity
+            .commit_recovery_key(&vault.secret_public_key_get(&committed_key).await?)
+            .await?;
+
+        if identity.recover_root_key(&other_key).await.is_ok() {
+            return test_error("root key recovered with a key that wasn't committed");
+        }
+
+        // A forged change signed by a key that wasn't committed is rejected
+        let change_history = identity.change_history.read().await;
+        let data = RotateKeyChangeData::new(
+            change_history.get_last_change_id()?,
+            KeyAttributes::default_with_label(IdentityStateConst::ROOT_LABEL),
+            vault.secret_public_key_get(&other_key).await?,
+        );
+        let change = IdentityChange::RotateKey(data);
+        let change_id = ChangeIdentifier::from_hash(vault.sha256(&change.encode()?).await?);
+        let signature = vault.sign(&other_key, change_id.as_ref()).await?;
+        let change = IdentitySignedChange::new(
+            change_id,
+            change,
+            vec![change::Signature::new(SignatureType::SelfSign, signature)],
+        );
+        if IdentityChangeHistory::verify_change(change_history.as_ref(), &change, vault).await? {
+            return test_error("forged root key recovery was accepted");
+        }
+
+        ctx.stop().await?;
+
+        Ok(())
+    }
 }
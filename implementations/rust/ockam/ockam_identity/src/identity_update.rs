@@ -0,0 +1,315 @@
+mod worker;
+
+use crate::alloc::string::ToString;
+use crate::change_history::IdentityHistoryComparison;
+use crate::identity_update::worker::IdentityUpdateWorker;
+use crate::{Identity, IdentityError, IdentityIdentifier, IdentityStateConst, PublicIdentity};
+use minicbor::{Decode, Decoder, Encode};
+use ockam_core::api::{Request, Response, Status};
+use ockam_core::compat::{sync::Arc, vec::Vec};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{
+    route, Address, AllowAll, AsyncTryClone, Error, IncomingAccessControl, Mailboxes, Result, Route,
+};
+use ockam_node::api::request;
+use ockam_node::WorkerBuilder;
+use tracing::{debug, info, warn};
+
+#[cfg(feature = "tag")]
+use ockam_core::TypeTag;
+
+/// Exported change history of an `Identity`, exchanged by the identity update service
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct IdentityUpdate {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<2907815>,
+    /// Exported [`PublicIdentity`]
+    #[cbor(with = "minicbor::bytes")]
+    #[b(1)] identity: Vec<u8>,
+}
+
+impl IdentityUpdate {
+    /// Constructor
+    pub fn new(identity: Vec<u8>) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            identity,
+        }
+    }
+
+    /// Exported [`PublicIdentity`]
+    pub fn identity(&self) -> &[u8] {
+        &self.identity
+    }
+}
+
+impl Identity {
+    /// Start worker that will serve our own and known change histories to other nodes and
+    /// accept newer change histories pushed by them.
+    /// Only change histories of the sender itself, as authenticated by a secure channel, or of
+    /// identities we already know are accepted, so that other parties can't fill our storage.
+    pub async fn start_identity_update_worker(
+        &self,
+        address: impl Into<Address>,
+        incoming_access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Result<()> {
+        let s = self.async_try_clone().await?;
+        let worker = IdentityUpdateWorker::new(s);
+
+        WorkerBuilder::with_mailboxes(
+            Mailboxes::main(
+                address.into(),
+                incoming_access_control,
+                Arc::new(AllowAll), // FIXME: @ac Allow to respond anywhere using return_route
+            ),
+            worker,
+        )
+        .start(&self.ctx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Push our own change history to the identity update service of other party
+    pub async fn push_identity_update(&self, route: impl Into<Route>) -> Result<()> {
+        let identity = self.export().await?;
+        self.send_identity_update(route.into(), identity).await
+    }
+
+    /// Push the change history of a known `Identity` to the identity update service of
+    /// other party
+    pub async fn push_known_identity(
+        &self,
+        route: impl Into<Route>,
+        their_identity_id: &IdentityIdentifier,
+    ) -> Result<()> {
+        let known = self
+            .get_known_identity(their_identity_id)
+            .await?
+            .ok_or(IdentityError::UnknownIdentity)?;
+        self.send_identity_update(route.into(), known.export()?)
+            .await
+    }
+
+    /// Get the latest change history of the given `Identity` from the identity update service
+    /// of other party and update the known one
+    pub async fn pull_identity_update(
+        &self,
+        route: impl Into<Route>,
+        their_identity_id: &IdentityIdentifier,
+    ) -> Result<IdentityHistoryComparison> {
+        let buf = request(
+            &self.ctx,
+            "identity_update",
+            None,
+            route.into(),
+            Request::get(format!("identities/{}", their_identity_id)),
+        )
+        .await?;
+
+        let mut dec = Decoder::new(&buf);
+        let res: Response = dec.decode()?;
+        match res.status() {
+            Some(Status::Ok) => {}
+            Some(Status::NotFound) => return Err(IdentityError::UnknownIdentity.into()),
+            _ => {
+                return Err(Error::new(
+                    Origin::Application,
+                    Kind::Invalid,
+                    "identity update pull failed",
+                ))
+            }
+        }
+
+        let update: IdentityUpdate = dec.decode()?;
+        let update = PublicIdentity::import(update.identity(), self.vault.clone()).await?;
+        if update.identifier() != their_identity_id {
+            return Err(IdentityError::IdentityVerificationFailed.into());
+        }
+
+        self.receive_identity_update(&update).await
+    }
+
+    /// Exchange change histories with the other party of each of our secure channels: pull
+    /// their latest change history and push ours to the identity update service at
+    /// `service_address`. Newer change histories are applied, conflicts are reported.
+    pub async fn sync_identity_updates(&self, service_address: impl Into<Address>) {
+        let service_address = service_address.into();
+        for channel in self.secure_channel_registry().get_channel_list() {
+            let route = route![
+                channel.encryptor_messaging_address().clone(),
+                service_address.clone()
+            ];
+            match self
+                .pull_identity_update(route.clone(), channel.their_id())
+                .await
+            {
+                Ok(IdentityHistoryComparison::Conflict) => warn!(
+                    "The change history of {} conflicts with the known one",
+                    channel.their_id()
+                ),
+                Ok(_) => {}
+                Err(e) => debug!(
+                    "Failed to pull the change history of {}: {}",
+                    channel.their_id(),
+                    e
+                ),
+            }
+            if let Err(e) = self.push_identity_update(route).await {
+                if e.code().kind == Kind::Conflict {
+                    warn!(
+                        "{} knows a change history conflicting with our own",
+                        channel.their_id()
+                    );
+                } else {
+                    debug!(
+                        "Failed to push our change history to {}: {}",
+                        channel.their_id(),
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Process a change history of another `Identity` received from the network.
+    /// Newer change histories replace the known one, older ones are ignored and conflicting
+    /// ones are reported and kept aside for inspection. The given `PublicIdentity` is expected
+    /// to be verified already, which is the case for `PublicIdentity::import`.
+    pub async fn receive_identity_update(
+        &self,
+        update: &PublicIdentity,
+    ) -> Result<IdentityHistoryComparison> {
+        let their_identity_id = update.identifier();
+
+        if their_identity_id == self.identifier() {
+            let comparison = update.changes().compare(&*self.change_history.read().await);
+            if comparison == IdentityHistoryComparison::Conflict {
+                warn!(
+                    "Received a change history conflicting with our own identity {}",
+                    their_identity_id
+                );
+            }
+            return Ok(comparison);
+        }
+
+        let comparison = match self.get_known_identity(their_identity_id).await? {
+            Some(known) => update.changes().compare(known.changes()),
+            None => IdentityHistoryComparison::Newer,
+        };
+
+        match comparison {
+            IdentityHistoryComparison::Newer => {
+                info!("Updating change history of {}", their_identity_id);
+                self.authenticated_storage
+                    .set(
+                        &their_identity_id.to_string(),
+                        IdentityStateConst::CHANGE_HISTORY_KEY.to_string(),
+                        update.export()?,
+                    )
+                    .await?;
+            }
+            IdentityHistoryComparison::Conflict => {
+                warn!(
+                    "Received a change history conflicting with the known one for {}",
+                    their_identity_id
+                );
+                self.authenticated_storage
+                    .set(
+                        &their_identity_id.to_string(),
+                        IdentityStateConst::CONFLICTING_CHANGE_HISTORY_KEY.to_string(),
+                        update.export()?,
+                    )
+                    .await?;
+            }
+            IdentityHistoryComparison::Equal | IdentityHistoryComparison::Older => {
+                debug!(
+                    "Ignoring change history of {}: {:?}",
+                    their_identity_id, comparison
+                );
+            }
+        }
+
+        Ok(comparison)
+    }
+
+    /// List all `Identities` we know about
+    pub async fn known_identities(&self) -> Result<Vec<PublicIdentity>> {
+        let mut known = Vec::new();
+        for id in self
+            .authenticated_storage
+            .keys(IdentityStateConst::CHANGE_HISTORY_KEY)
+            .await?
+        {
+            let id = IdentityIdentifier::try_from(id)?;
+            if let Some(identity) = self.get_known_identity(&id).await? {
+                known.push(identity)
+            }
+        }
+        Ok(known)
+    }
+
+    /// Get the last received change history that conflicted with the known one for the
+    /// given `Identity`
+    pub async fn get_conflicting_identity(
+        &self,
+        their_identity_id: &IdentityIdentifier,
+    ) -> Result<Option<PublicIdentity>> {
+        match self
+            .authenticated_storage
+            .get(
+                &their_identity_id.to_string(),
+                IdentityStateConst::CONFLICTING_CHANGE_HISTORY_KEY,
+            )
+            .await?
+        {
+            Some(conflicting) => Ok(Some(
+                PublicIdentity::import(&conflicting, self.vault.clone()).await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    async fn send_identity_update(&self, route: Route, identity: Vec<u8>) -> Result<()> {
+        let buf = request(
+            &self.ctx,
+            "identity_update",
+            None,
+            route,
+            Request::post("identities").body(IdentityUpdate::new(identity)),
+        )
+        .await?;
+
+        let mut dec = Decoder::new(&buf);
+        let res: Response = dec.decode()?;
+        match res.status() {
+            Some(Status::Ok) => {}
+            Some(Status::Conflict) => {
+                return Err(Error::new(
+                    Origin::Identity,
+                    Kind::Conflict,
+                    IdentityError::IdentityHistoryConflict,
+                ))
+            }
+            _ => {
+                return Err(Error::new(
+                    Origin::Application,
+                    Kind::Invalid,
+                    "identity update push failed",
+                ))
+            }
+        }
+
+        // The other party knows a newer change history and sent it back
+        if res.has_body() {
+            let update: IdentityUpdate = dec.decode()?;
+            let update = PublicIdentity::import(update.identity(), self.vault.clone()).await?;
+            self.receive_identity_update(&update).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+use crate::change_history::IdentityHistoryComparison;
+use crate::identity_update::IdentityUpdate;
+use crate::{Identity, IdentityIdentifier, IdentitySecureChannelLocalInfo, PublicIdentity};
+use minicbor::Decoder;
+use ockam_core::api::{forbidden, Error, Id, Request, Response, ResponseBuilder, Status};
+use ockam_core::async_trait;
+use ockam_core::compat::{boxed::Box, string::ToString, vec::Vec};
+use ockam_core::{Result, Routed, Worker};
+use ockam_node::Context;
+use tracing::{error, trace, warn};
+
+const TARGET: &str = "ockam::identity_update_worker::service";
+
+/// Worker responsible for serving and receiving change histories of identities
+pub struct IdentityUpdateWorker {
+    identity: Identity,
+}
+
+impl IdentityUpdateWorker {
+    pub fn new(identity: Identity) -> Self {
+        Self { identity }
+    }
+}
+
+impl IdentityUpdateWorker {
+    /// Create a generic bad request response.
+    pub fn bad_request<'a>(id: Id, path: &'a str, msg: &'a str) -> ResponseBuilder<Error<'a>> {
+        let e = Error::new(path).with_message(msg);
+        Response::bad_request(id).body(e)
+    }
+
+    async fn handle_request(
+        &mut self,
+        req: &Request<'_>,
+        dec: &mut Decoder<'_>,
+        sender: Option<&IdentityIdentifier>,
+    ) -> Result<Vec<u8>> {
+        trace! {
+            target: TARGET,
+            id     = %req.id(),
+            method = ?req.method(),
+            path   = %req.path(),
+            body   = %req.has_body(),
+            "request"
+        }
+
+        use ockam_core::api::Method::*;
+        let path = req.path();
+        let path_segments = req.path_segments::<5>();
+        let method = match req.method() {
+            Some(m) => m,
+            None => {
+                return Ok(Response::bad_request(req.id())
+                    .body("Invalid method")
+                    .to_vec()?)
+            }
+        };
+
+        let r = match (method, path_segments.as_slice()) {
+            (Get, ["identities", id]) => {
+                let id = match IdentityIdentifier::try_from(*id) {
+                    Ok(id) => id,
+                    Err(err) => {
+                        return Ok(Self::bad_request(req.id(), path, &err.to_string()).to_vec()?)
+                    }
+                };
+
+                let identity = if &id == self.identity.identifier() {
+                    Some(self.identity.export().await?)
+                } else {
+                    match self.identity.get_known_identity(&id).await? {
+                        Some(known) => Some(known.export()?),
+                        None => None,
+                    }
+                };
+
+                match identity {
+                    Some(identity) => Response::ok(req.id())
+                        .body(IdentityUpdate::new(identity))
+                        .to_vec()?,
+                    None => Response::not_found(req.id()).to_vec()?,
+                }
+            }
+            (Post, ["identities"]) => {
+                let update: IdentityUpdate = dec.decode()?;
+                let update =
+                    match PublicIdentity::import(update.identity(), self.identity.vault()).await {
+                        Ok(update) => update,
+                        Err(err) => {
+                            return Ok(Self::bad_request(req.id(), path, &err.to_string()).to_vec()?)
+                        }
+                    };
+
+                // Only accept the change history of the sender itself or of an identity we
+                // already know, so that the storage doesn't grow with unsolicited identities
+                if Some(update.identifier()) != sender
+                    && update.identifier() != self.identity.identifier()
+                    && self
+                        .identity
+                        .get_known_identity(update.identifier())
+                        .await?
+                        .is_none()
+                {
+                    warn!(
+                        "Rejecting the change history of unknown identity {}",
+                        update.identifier()
+                    );
+                    return Ok(forbidden(req, "unknown identity").to_vec()?);
+                }
+
+                match self.identity.receive_identity_update(&update).await? {
+                    IdentityHistoryComparison::Conflict => {
+                        Response::builder(req.id(), Status::Conflict).to_vec()?
+                    }
+                    // Let the other party know about the newer change history we have
+                    IdentityHistoryComparison::Older => {
+                        let known = if update.identifier() == self.identity.identifier() {
+                            self.identity.export().await?
+                        } else {
+                            match self
+                                .identity
+                                .get_known_identity(update.identifier())
+                                .await?
+                            {
+                                Some(known) => known.export()?,
+                                None => return Ok(Response::ok(req.id()).to_vec()?),
+                            }
+                        };
+                        Response::ok(req.id())
+                            .body(IdentityUpdate::new(known))
+                            .to_vec()?
+                    }
+                    IdentityHistoryComparison::Equal | IdentityHistoryComparison::Newer => {
+                        Response::ok(req.id()).to_vec()?
+                    }
+                }
+            }
+
+            // ==*== Catch-all for Unimplemented APIs ==*==
+            _ => {
+                warn!(%method, %path, "Called invalid endpoint");
+                Response::bad_request(req.id())
+                    .body(format!("Invalid endpoint: {}", path))
+                    .to_vec()?
+            }
+        };
+        Ok(r)
+    }
+}
+
+#[async_trait]
+impl Worker for IdentityUpdateWorker {
+    type Message = Vec<u8>;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let mut dec = Decoder::new(msg.as_body());
+        let req: Request = match dec.decode() {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to decode request: {:?}", e);
+                return Ok(());
+            }
+        };
+
+        let sender = IdentitySecureChannelLocalInfo::find_info(msg.local_message())
+            .map(|info| info.their_identity_id().clone())
+            .ok();
+        let r = match self.handle_request(&req, &mut dec, sender.as_ref()).await {
+            Ok(r) => r,
+            // If an error occurs, send a response with the error code so the listener can
+            // fail fast instead of failing silently here and force the listener to timeout.
+            Err(err) => {
+                error!(?err, "Failed to handle message");
+                Response::builder(req.id(), Status::InternalServerError)
+                    .body(err.to_string())
+                    .to_vec()?
+            }
+        };
+        ctx.send(msg.return_route(), r).await
+    }
+}
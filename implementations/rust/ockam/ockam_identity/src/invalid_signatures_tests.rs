@@ -288,3 +288,30 @@ async fn test_eject_signatures(ctx: &mut Context) -> Result<()> {
 
     Ok(())
 }
+
+#[ockam_macros::test]
+async fn test_eject_signatures_threshold_root(ctx: &mut Context) -> Result<()> {
+    for _ in 0..20 {
+        let vaults: Vec<Arc<dyn IdentityVault>> =
+            vec![Vault::create(), Vault::create(), Vault::create()];
+
+        let identity = Identity::create_threshold(ctx, vaults, 2).await?;
+
+        let j: i32 = thread_rng().gen_range(0..5);
+        for _ in 0..j {
+            let label: [u8; 16] = thread_rng().gen();
+            identity.create_key(hex::encode(label)).await?;
+        }
+
+        let res = PublicIdentity::import(&identity.export().await?, Vault::create()).await;
+        assert!(res.is_ok());
+
+        let identity = identity.eject_random_signature().await?;
+        let res = PublicIdentity::import(&identity.export().await?, Vault::create()).await;
+        assert!(res.is_err());
+    }
+
+    ctx.stop().await?;
+
+    Ok(())
+}
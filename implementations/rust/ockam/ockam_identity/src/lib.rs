@@ -42,12 +42,16 @@ pub mod error;
 
 pub use error::*;
 
+/// Service propagating updated change histories of identities
+pub mod identity_update;
+
 mod channel;
 mod identifiers;
 mod identity;
 mod identity_builder;
 mod key_attributes;
 mod public_identity;
+mod threshold_signature;
 
 pub use channel::*;
 pub use identifiers::*;
@@ -55,6 +59,7 @@ pub use identity::*;
 pub use identity_builder::*;
 pub use key_attributes::*;
 pub use public_identity::*;
+pub use threshold_signature::*;
 
 mod signature;
 
@@ -1,5 +1,5 @@
 use crate::change_history::{IdentityChangeHistory, IdentityHistoryComparison};
-use crate::{IdentityError, IdentityIdentifier, IdentityVault};
+use crate::{IdentityError, IdentityIdentifier, IdentityStateConst, IdentityVault};
 use ockam_core::compat::fmt;
 use ockam_core::compat::fmt::{Display, Formatter};
 use ockam_core::compat::sync::Arc;
@@ -58,10 +58,6 @@ impl PublicIdentity {
         &self.id
     }
 
-    pub(crate) fn get_root_public_key(&self) -> Result<PublicKey> {
-        self.change_history.get_root_public_key()
-    }
-
     pub(crate) fn get_public_key(&self, label: &str) -> Result<PublicKey> {
         self.change_history.get_public_key(label)
     }
@@ -75,8 +71,13 @@ impl PublicIdentity {
         vault: Arc<dyn IdentityVault>,
     ) -> Result<bool> {
         let public_key = match key_label {
-            Some(label) => self.get_public_key(label)?,
-            None => self.get_root_public_key()?,
+            Some(label) if label != IdentityStateConst::ROOT_LABEL => self.get_public_key(label)?,
+            _ => {
+                return self
+                    .change_history
+                    .verify_root_signature(signature, data, vault)
+                    .await
+            }
         };
 
         vault.verify(signature, &public_key, data).await
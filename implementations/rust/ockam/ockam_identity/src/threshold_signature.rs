@@ -0,0 +1,268 @@
+use crate::change::{self, IdentitySignedChange, SignatureType};
+use crate::change_history::IdentityChangeHistory;
+use crate::{
+    ChangeIdentifier, Identity, IdentityError, IdentityStateConst, IdentityVault, KeyAttributes,
+    PublicIdentity,
+};
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::collections::BTreeSet;
+use ockam_core::compat::{string::ToString, sync::Arc, vec::Vec};
+use ockam_core::vault::{PublicKey, Signature};
+use ockam_core::Result;
+use ockam_node::Context;
+
+use crate::authenticated_storage::mem::InMemoryStorage;
+use crate::authenticated_storage::AuthenticatedStorage;
+#[cfg(feature = "tag")]
+use ockam_core::TypeTag;
+
+/// Signature made by the root keys of an `Identity` having a threshold root,
+/// it holds at least `threshold` signatures made by distinct root keys
+#[derive(Clone, Debug, Default, Decode, Encode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ThresholdSignature {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<5836213>,
+    #[n(1)] signatures: Vec<PartialSignature>,
+}
+
+/// Signature made by a single root key
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PartialSignature {
+    /// Index of the key in the root keys
+    #[n(1)] key_index: u8,
+    #[cbor(with = "minicbor::bytes")]
+    #[b(2)] signature: ByteVec,
+}
+
+impl ThresholdSignature {
+    /// Partial signatures collected so far
+    pub fn signatures(&self) -> &[PartialSignature] {
+        &self.signatures
+    }
+
+    /// Add the partial signatures made by another cosigner
+    pub fn merge(&mut self, other: ThresholdSignature) {
+        for signature in other.signatures {
+            if !self
+                .signatures
+                .iter()
+                .any(|s| s.key_index == signature.key_index)
+            {
+                self.signatures.push(signature);
+            }
+        }
+    }
+
+    /// Encode as a signature that can be verified with
+    /// [`PublicIdentity::verify_signature`]
+    pub fn to_signature(&self) -> Result<Signature> {
+        Ok(Signature::new(minicbor::to_vec(self)?))
+    }
+
+    /// Decode a signature made by a threshold root
+    pub fn from_signature(signature: &Signature) -> Result<Self> {
+        Ok(minicbor::decode(signature.as_ref())?)
+    }
+}
+
+impl PartialSignature {
+    /// Index of the key in the root keys
+    pub fn key_index(&self) -> u8 {
+        self.key_index
+    }
+
+    /// Signature data
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+}
+
+impl Identity {
+    /// Create an `Identity` whose root is made of one key per vault, `threshold`
+    /// of them are needed to sign changes and credentials. The first vault
+    /// is the `Identity` vault, the other ones are added as cosigner vaults
+    pub async fn create_threshold(
+        ctx: &Context,
+        vaults: Vec<Arc<dyn IdentityVault>>,
+        threshold: u8,
+    ) -> Result<Self> {
+        Self::create_threshold_ext(ctx, Arc::new(InMemoryStorage::new()), vaults, threshold).await
+    }
+
+    /// Create an `Identity` with a threshold root. Extended version
+    pub async fn create_threshold_ext(
+        ctx: &Context,
+        authenticated_storage: Arc<dyn AuthenticatedStorage>,
+        vaults: Vec<Arc<dyn IdentityVault>>,
+        threshold: u8,
+    ) -> Result<Self> {
+        let vault = vaults
+            .first()
+            .cloned()
+            .ok_or(IdentityError::InvalidThreshold)?;
+        let identity = Self::create_ext(ctx, authenticated_storage, vault).await?;
+
+        let mut public_keys = vec![identity.change_history.read().await.get_root_public_key()?];
+        for cosigner in vaults.into_iter().skip(1) {
+            let attributes =
+                KeyAttributes::default_with_label(IdentityStateConst::ROOT_LABEL.to_string())
+                    .secret_attributes();
+            let key = cosigner.secret_generate(attributes).await?;
+            public_keys.push(cosigner.secret_public_key_get(&key).await?);
+            identity.add_cosigner_vault(cosigner).await;
+        }
+
+        identity.set_root_keys(public_keys, threshold).await?;
+        Ok(identity)
+    }
+
+    /// Add a vault holding some of the root keys, it is used to collect
+    /// partial signatures when a root signature is needed
+    pub async fn add_cosigner_vault(&self, vault: Arc<dyn IdentityVault>) {
+        self.cosigner_vaults.write().await.push(vault);
+    }
+
+    /// Replace the root keys, `threshold` of them will be needed for every
+    /// root signature. Each new key has to be available in this `Identity`
+    /// vault or in a cosigner vault
+    pub async fn set_root_keys(&self, public_keys: Vec<PublicKey>, threshold: u8) -> Result<()> {
+        let change = self
+            .make_set_root_keys_change(public_keys, threshold)
+            .await?;
+
+        self.add_change(change).await
+    }
+
+    /// Sign the data with every root key available in this `Identity` vault
+    /// and its cosigner vaults. The partial signatures of several
+    /// cosigners can be merged until the threshold is reached
+    pub async fn create_partial_signature(&self, data: &[u8]) -> Result<ThresholdSignature> {
+        let (public_keys, _) = IdentityChangeHistory::get_current_root_keys(
+            self.change_history.read().await.as_ref(),
+        )?;
+
+        let mut signatures = Vec::new();
+        for (key_index, public_key) in public_keys.iter().enumerate() {
+            if let Some(signature) = self.sign_with_available_key(public_key, data).await? {
+                signatures.push(PartialSignature {
+                    key_index: key_index as u8,
+                    signature: signature.as_ref().to_vec().into(),
+                });
+            }
+        }
+
+        Ok(ThresholdSignature {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            signatures,
+        })
+    }
+
+    /// Root signature of an `Identity` having a threshold root
+    pub(crate) async fn create_threshold_signature(&self, data: &[u8]) -> Result<Signature> {
+        let (_, threshold) = IdentityChangeHistory::get_current_root_keys(
+            self.change_history.read().await.as_ref(),
+        )?;
+        let signature = self.create_partial_signature(data).await?;
+        if signature.signatures.len() < threshold as usize {
+            return Err(IdentityError::ThresholdNotReached.into());
+        }
+        signature.to_signature()
+    }
+
+    /// Root signatures of a change, as many as required by the current root
+    pub(crate) async fn create_root_signatures(
+        &self,
+        existing_changes: &[IdentitySignedChange],
+        change_id: &ChangeIdentifier,
+    ) -> Result<Vec<change::Signature>> {
+        let (public_keys, threshold) =
+            IdentityChangeHistory::get_current_root_keys(existing_changes)?;
+
+        let mut signatures = Vec::new();
+        for public_key in public_keys.iter() {
+            if signatures.len() == threshold as usize {
+                break;
+            }
+            if let Some(signature) = self
+                .sign_with_available_key(public_key, change_id.as_ref())
+                .await?
+            {
+                signatures.push(change::Signature::new(SignatureType::RootSign, signature));
+            }
+        }
+
+        if signatures.len() < threshold as usize {
+            return Err(IdentityError::ThresholdNotReached.into());
+        }
+        Ok(signatures)
+    }
+
+    /// Sign with the given key if this `Identity` vault or a cosigner vault has it
+    pub(crate) async fn sign_with_available_key(
+        &self,
+        public_key: &PublicKey,
+        data: &[u8],
+    ) -> Result<Option<Signature>> {
+        let mut vaults = vec![self.vault.clone()];
+        vaults.extend(self.cosigner_vaults.read().await.iter().cloned());
+
+        for vault in vaults {
+            let key_id = vault.compute_key_id_for_public_key(public_key).await?;
+            if let Ok(signature) = vault.sign(&key_id, data).await {
+                return Ok(Some(signature));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl IdentityChangeHistory {
+    /// Verify a signature made by the current root keys
+    pub(crate) async fn verify_root_signature(
+        &self,
+        signature: &Signature,
+        data: &[u8],
+        vault: Arc<dyn IdentityVault>,
+    ) -> Result<bool> {
+        if !Self::has_threshold_root(self.as_ref())? {
+            let public_key = self.get_root_public_key()?;
+            return vault.verify(signature, &public_key, data).await;
+        }
+
+        let (public_keys, threshold) = Self::get_current_root_keys(self.as_ref())?;
+        let signature = match ThresholdSignature::from_signature(signature) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(false),
+        };
+
+        let mut signers = BTreeSet::new();
+        for partial in signature.signatures() {
+            let public_key = match public_keys.get(partial.key_index() as usize) {
+                Some(public_key) => public_key,
+                None => return Ok(false),
+            };
+            if !signers.insert(partial.key_index()) {
+                return Ok(false);
+            }
+            let partial_signature = Signature::new(partial.signature().to_vec());
+            if !vault.verify(&partial_signature, public_key, data).await? {
+                return Ok(false);
+            }
+        }
+
+        Ok(signers.len() >= threshold as usize)
+    }
+}
+
+impl PublicIdentity {
+    /// Return true if this `Identity` root is made of several keys
+    pub fn has_threshold_root(&self) -> Result<bool> {
+        IdentityChangeHistory::has_threshold_root(self.changes().as_ref())
+    }
+}
@@ -7,7 +7,8 @@ use ockam_identity::api::{DecryptionResponse, EncryptionRequest, EncryptionRespo
 use ockam_identity::{
     Identity, IdentitySecureChannelLocalInfo, TrustEveryonePolicy, TrustIdentifierPolicy,
 };
-use ockam_node::{Context, WorkerBuilder};
+use ockam_node::sim::{self, Link, Network};
+use ockam_node::{Context, MessageReceiveOptions, WorkerBuilder};
 use ockam_vault::Vault;
 use tokio::time::sleep;
 
@@ -66,6 +67,58 @@ async fn test_channel(ctx: &mut Context) -> Result<()> {
     ctx.stop().await
 }
 
+#[ockam_macros::test(virtual_clock)]
+async fn test_channel_over_simulated_network(ctx: &mut Context) -> Result<()> {
+    let network = Network::new(0);
+    network.set_default_link(Link::new(Duration::from_millis(100)));
+    network.attach(ctx, "alice").await?;
+    let mut bob_node = network.start_node("bob").await?;
+
+    let alice = Identity::create(ctx, Vault::create()).await?;
+    let bob = Identity::create(&bob_node, Vault::create()).await?;
+
+    let bob_trust_policy = TrustIdentifierPolicy::new(alice.identifier().clone());
+    bob.create_secure_channel_listener("bob_listener", bob_trust_policy)
+        .await?;
+
+    let alice_trust_policy = TrustIdentifierPolicy::new(bob.identifier().clone());
+    let alice_channel = alice
+        .create_secure_channel(
+            route![sim::address("bob"), "bob_listener"],
+            alice_trust_policy,
+        )
+        .await?;
+
+    ctx.send(
+        route![alice_channel.clone(), "app"],
+        "Hello, Bob!".to_string(),
+    )
+    .await?;
+    let msg = bob_node.receive::<String>().await?;
+    let return_route = msg.return_route();
+    assert_eq!("Hello, Bob!", msg.body());
+    bob_node
+        .send(return_route, "Hello, Alice!".to_string())
+        .await?;
+    assert_eq!("Hello, Alice!", ctx.receive::<String>().await?.body());
+
+    // Messages sent during a partition are lost, the channel keeps working
+    // once the network heals
+    network.partition(&["alice"], &["bob"]);
+    ctx.send(route![alice_channel.clone(), "app"], "Lost".to_string())
+        .await?;
+    let options = MessageReceiveOptions::new().with_timeout(Duration::from_secs(5));
+    assert!(bob_node.receive_extended::<String>(options).await.is_err());
+
+    network.heal();
+    ctx.send(route![alice_channel, "app"], "Back".to_string())
+        .await?;
+    assert_eq!("Back", bob_node.receive::<String>().await?.body());
+
+    bob_node.stop().await?;
+    ctx.stop().await
+}
+
 #[ockam_macros::test]
 async fn test_channel_registry(ctx: &mut Context) -> Result<()> {
     let alice_vault = Vault::create();
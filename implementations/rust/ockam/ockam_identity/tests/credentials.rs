@@ -6,7 +6,7 @@ use ockam_identity::authenticated_storage::{
     IdentityAttributeStorageReader,
 };
 use ockam_identity::credential::access_control::CredentialAccessControl;
-use ockam_identity::credential::Credential;
+use ockam_identity::credential::{Credential, DelegationScope};
 use ockam_identity::{Identity, TrustEveryonePolicy, TrustIdentifierPolicy};
 
 use ockam_node::{Context, WorkerBuilder};
@@ -54,7 +54,7 @@ async fn full_flow_oneway(ctx: &mut Context) -> Result<()> {
     client.set_credential(credential).await;
 
     client
-        .present_credential(route![channel, "credential_exchange"], None)
+        .present_credential(route![channel, "credential_exchange"], None, None)
         .await?;
 
     let attrs = authenticated_attribute_storage
@@ -69,6 +69,441 @@ async fn full_flow_oneway(ctx: &mut Context) -> Result<()> {
     ctx.stop().await
 }
 
+#[ockam_macros::test]
+async fn full_flow_selective_disclosure(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+
+    let authenticated_attribute_storage =
+        AuthenticatedAttributeStorage::new(Arc::new(InMemoryStorage::new()));
+
+    let authority = Identity::create(ctx, vault.clone()).await?;
+    let server = Identity::create(ctx, vault.clone()).await?;
+
+    server
+        .create_secure_channel_listener("listener", TrustEveryonePolicy)
+        .await?;
+
+    let authorities = vec![authority.to_public().await?];
+    server
+        .start_credential_exchange_worker(
+            authorities.clone(),
+            "credential_exchange",
+            false,
+            Arc::new(authenticated_attribute_storage.clone()),
+        )
+        .await?;
+
+    let client = Identity::create(ctx, vault).await?;
+    let channel = client
+        .create_secure_channel(
+            route!["listener"],
+            TrustIdentifierPolicy::new(server.identifier().clone()),
+        )
+        .await?;
+
+    let credential = Credential::builder(client.identifier().clone())
+        .with_attribute("is_superuser", b"true")
+        .with_attribute("name", b"client")
+        .with_selective_disclosure();
+    let credential = authority.issue_credential(credential).await?;
+    assert_eq!(credential.disclosures().len(), 2);
+
+    // Every attribute can be disclosed to the holder itself
+    let data = authorities[0]
+        .verify_credential(&credential, client.identifier(), Vault::create())
+        .await?;
+    assert!(data.schema().unwrap().is_selective_disclosure());
+    assert_eq!(data.attributes().get("name"), Some(b"client".as_slice()));
+
+    assert!(credential.disclose(&["unknown"]).is_err());
+
+    client.set_credential(credential).await;
+
+    client
+        .present_credential(
+            route![channel, "credential_exchange"],
+            None,
+            Some(&["is_superuser"]),
+        )
+        .await?;
+
+    let attrs = authenticated_attribute_storage
+        .get_attributes(client.identifier())
+        .await?
+        .unwrap();
+
+    assert_eq!(attrs.attrs().len(), 1);
+    assert_eq!(
+        attrs.attrs().get("is_superuser").unwrap().as_slice(),
+        b"true"
+    );
+    assert!(attrs.attrs().get("name").is_none());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn selective_disclosure_forged_value(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+
+    let authority = Identity::create(ctx, vault.clone()).await?;
+    let client = Identity::create(ctx, vault).await?;
+
+    let credential = Credential::builder(client.identifier().clone())
+        .with_attribute("is_superuser", b"false")
+        .with_selective_disclosure();
+    let credential = authority.issue_credential(credential).await?;
+
+    // Replace the disclosed value while keeping the signed commitments
+    let mut encoded = minicbor::to_vec(&credential)?;
+    let position = encoded.windows(5).position(|w| w == b"false").unwrap();
+    encoded[position..position + 5].copy_from_slice(b"truee");
+    let forged: Credential = minicbor::decode(&encoded)?;
+
+    let res = authority
+        .to_public()
+        .await?
+        .verify_credential(&forged, client.identifier(), Vault::create())
+        .await;
+    assert!(res.is_err());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn full_flow_audience(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+    let storage = Arc::new(InMemoryStorage::new());
+
+    let authority = Identity::create(ctx, vault.clone()).await?;
+    let server = Identity::create(ctx, vault.clone()).await?;
+
+    server
+        .create_secure_channel_listener("listener", TrustEveryonePolicy)
+        .await?;
+
+    let authorities = vec![authority.to_public().await?];
+    server
+        .start_credential_exchange_worker(
+            authorities,
+            "credential_exchange",
+            false,
+            Arc::new(AuthenticatedAttributeStorage::new(storage.clone())),
+        )
+        .await?;
+
+    // Each service only accepts credentials without audience or valid for its name
+    let required_attributes = vec![("is_superuser".to_string(), b"true".to_vec())];
+    let mut counters = vec![];
+    for service in ["service_a", "service_b"] {
+        let counter = Arc::new(AtomicI8::new(0));
+        let access_control = CredentialAccessControl::new(
+            &required_attributes,
+            AuthenticatedAttributeStorage::new(storage.clone()),
+        )
+        .with_audience(&[service.to_string()]);
+        WorkerBuilder::with_access_control(
+            Arc::new(access_control),
+            Arc::new(DenyAll),
+            service,
+            CountingWorker {
+                msgs_count: counter.clone(),
+            },
+        )
+        .start(ctx)
+        .await?;
+        counters.push(counter);
+    }
+
+    let client = Identity::create(ctx, vault).await?;
+    let channel = client
+        .create_secure_channel(
+            route!["listener"],
+            TrustIdentifierPolicy::new(server.identifier().clone()),
+        )
+        .await?;
+
+    let credential = Credential::builder(client.identifier().clone())
+        .with_attribute("is_superuser", b"true")
+        .with_audience("service_a");
+    let credential = authority.issue_credential(credential).await?;
+    client
+        .present_credential(
+            route![channel.clone(), "credential_exchange"],
+            Some(&credential),
+            None,
+        )
+        .await?;
+
+    let attrs = AuthenticatedAttributeStorage::new(storage.clone())
+        .get_attributes(client.identifier())
+        .await?
+        .unwrap();
+    assert_eq!(attrs.audience(), Some(&["service_a".to_string()][..]));
+
+    let child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+    for service in ["service_a", "service_b"] {
+        child_ctx
+            .send(route![channel.clone(), service], "Hello".to_string())
+            .await?;
+    }
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(counters[0].load(Ordering::Relaxed), 1);
+    assert_eq!(counters[1].load(Ordering::Relaxed), 0);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn full_flow_delegation(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+
+    let authenticated_attribute_storage =
+        AuthenticatedAttributeStorage::new(Arc::new(InMemoryStorage::new()));
+
+    let authority = Identity::create(ctx, vault.clone()).await?;
+    let regional = Identity::create(ctx, vault.clone()).await?;
+    let server = Identity::create(ctx, vault.clone()).await?;
+
+    server
+        .create_secure_channel_listener("listener", TrustEveryonePolicy)
+        .await?;
+
+    let authorities = vec![authority.to_public().await?];
+    server
+        .start_credential_exchange_worker(
+            authorities,
+            "credential_exchange",
+            false,
+            Arc::new(authenticated_attribute_storage.clone()),
+        )
+        .await?;
+
+    let client = Identity::create(ctx, vault).await?;
+    let channel = client
+        .create_secure_channel(
+            route!["listener"],
+            TrustIdentifierPolicy::new(server.identifier().clone()),
+        )
+        .await?;
+
+    let scope = DelegationScope::new(Duration::from_secs(3600))
+        .allow_value("role", b"member")
+        .allow_attribute("region");
+    let delegation = authority
+        .issue_credential(
+            Credential::builder(regional.identifier().clone()).with_delegation(scope.clone()),
+        )
+        .await?;
+
+    // Attributes outside of the delegation scope are rejected
+    let credential = Credential::builder(client.identifier().clone())
+        .with_attribute("role", b"admin")
+        .valid_for(Duration::from_secs(60));
+    let credential = regional
+        .issue_delegated_credential(credential, &delegation)
+        .await?;
+    assert!(client
+        .present_credential(
+            route![channel.clone(), "credential_exchange"],
+            Some(&credential),
+            None
+        )
+        .await
+        .is_err());
+
+    // Credentials of the delegate without the delegation chain are rejected
+    let credential = Credential::builder(client.identifier().clone())
+        .with_attribute("role", b"member")
+        .valid_for(Duration::from_secs(60));
+    let credential = regional.issue_credential(credential).await?;
+    assert!(client
+        .present_credential(
+            route![channel.clone(), "credential_exchange"],
+            Some(&credential),
+            None
+        )
+        .await
+        .is_err());
+
+    // A delegate can't widen the scope it was given
+    let subregional = Identity::create(ctx, Vault::create()).await?;
+    let wider = regional
+        .issue_delegated_credential(
+            Credential::builder(subregional.identifier().clone())
+                .with_delegation(scope.clone().allow_attribute("role")),
+            &delegation,
+        )
+        .await?;
+    let credential = Credential::builder(client.identifier().clone())
+        .with_attribute("role", b"member")
+        .valid_for(Duration::from_secs(60));
+    let credential = subregional
+        .issue_delegated_credential(credential, &wider)
+        .await?;
+    assert!(client
+        .present_credential(
+            route![channel.clone(), "credential_exchange"],
+            Some(&credential),
+            None
+        )
+        .await
+        .is_err());
+    assert!(authenticated_attribute_storage
+        .get_attributes(client.identifier())
+        .await?
+        .is_none());
+
+    // Credentials within the scope of the delegation chain are accepted
+    let narrower = regional
+        .issue_delegated_credential(
+            Credential::builder(subregional.identifier().clone()).with_delegation(
+                DelegationScope::new(Duration::from_secs(600))
+                    .allow_value("role", b"member")
+                    .allow_value("region", b"eu"),
+            ),
+            &delegation,
+        )
+        .await?;
+    let credential = Credential::builder(client.identifier().clone())
+        .with_attribute("role", b"member")
+        .with_attribute("region", b"eu")
+        .valid_for(Duration::from_secs(60));
+    let credential = subregional
+        .issue_delegated_credential(credential, &narrower)
+        .await?;
+    assert_eq!(credential.delegations().len(), 2);
+    client
+        .present_credential(
+            route![channel.clone(), "credential_exchange"],
+            Some(&credential),
+            None,
+        )
+        .await?;
+
+    let attrs = authenticated_attribute_storage
+        .get_attributes(client.identifier())
+        .await?
+        .unwrap();
+    assert_eq!(attrs.attrs().get("region").unwrap().as_slice(), b"eu");
+    assert_eq!(attrs.attested_by().as_ref(), Some(subregional.identifier()));
+    assert!(attrs.expires().unwrap().elapsed(attrs.added()).unwrap() <= Duration::from_secs(60));
+
+    // The validity of delegated credentials is capped to the delegation scope
+    let credential = Credential::builder(client.identifier().clone())
+        .with_attribute("role", b"member")
+        .valid_for(Duration::from_secs(7200));
+    let credential = subregional
+        .issue_delegated_credential(credential, &narrower)
+        .await?;
+    client
+        .present_credential(
+            route![channel, "credential_exchange"],
+            Some(&credential),
+            None,
+        )
+        .await?;
+    let attrs = authenticated_attribute_storage
+        .get_attributes(client.identifier())
+        .await?
+        .unwrap();
+    assert!(attrs.expires().unwrap().elapsed(attrs.added()).unwrap() <= Duration::from_secs(600));
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn delegation_rotated_key(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+
+    let authenticated_attribute_storage =
+        AuthenticatedAttributeStorage::new(Arc::new(InMemoryStorage::new()));
+
+    let authority = Identity::create(ctx, vault.clone()).await?;
+    let regional = Identity::create(ctx, vault.clone()).await?;
+    let server = Identity::create(ctx, vault.clone()).await?;
+
+    server
+        .create_secure_channel_listener("listener", TrustEveryonePolicy)
+        .await?;
+
+    let authorities = vec![authority.to_public().await?];
+    server
+        .start_credential_exchange_worker(
+            authorities,
+            "credential_exchange",
+            false,
+            Arc::new(authenticated_attribute_storage.clone()),
+        )
+        .await?;
+
+    let client = Identity::create(ctx, vault).await?;
+    let channel = client
+        .create_secure_channel(
+            route!["listener"],
+            TrustIdentifierPolicy::new(server.identifier().clone()),
+        )
+        .await?;
+
+    let delegation = authority
+        .issue_credential(
+            Credential::builder(regional.identifier().clone()).with_delegation(
+                DelegationScope::new(Duration::from_secs(3600)).allow_attribute("role"),
+            ),
+        )
+        .await?;
+    let builder = || {
+        Credential::builder(client.identifier().clone())
+            .with_attribute("role", b"member")
+            .valid_for(Duration::from_secs(60))
+    };
+    let before_rotation = regional
+        .issue_delegated_credential(builder(), &delegation)
+        .await?;
+
+    // Once the server knows that the delegate rotated its key, credentials
+    // signed with the previous key are rejected
+    regional.rotate_root_key().await?;
+    server
+        .update_known_identity(regional.identifier(), &regional.to_public().await?)
+        .await?;
+    assert!(client
+        .present_credential(
+            route![channel.clone(), "credential_exchange"],
+            Some(&before_rotation),
+            None
+        )
+        .await
+        .is_err());
+    assert!(authenticated_attribute_storage
+        .get_attributes(client.identifier())
+        .await?
+        .is_none());
+
+    let after_rotation = regional
+        .issue_delegated_credential(builder(), &delegation)
+        .await?;
+    client
+        .present_credential(
+            route![channel, "credential_exchange"],
+            Some(&after_rotation),
+            None,
+        )
+        .await?;
+    assert!(authenticated_attribute_storage
+        .get_attributes(client.identifier())
+        .await?
+        .is_some());
+
+    ctx.stop().await
+}
+
 #[ockam_macros::test]
 async fn full_flow_twoway(ctx: &mut Context) -> Result<()> {
     let vault = Vault::create();
@@ -121,6 +556,7 @@ async fn full_flow_twoway(ctx: &mut Context) -> Result<()> {
             &authorities,
             storage,
             None,
+            None,
         )
         .await?;
 
@@ -236,7 +672,7 @@ async fn access_control(ctx: &mut Context) -> Result<()> {
     assert_eq!(counter.load(Ordering::Relaxed), 0);
 
     client
-        .present_credential(route![channel.clone(), "credential_exchange"], None)
+        .present_credential(route![channel.clone(), "credential_exchange"], None, None)
         .await?;
 
     child_ctx
@@ -0,0 +1,181 @@
+use ockam_core::compat::sync::Arc;
+use ockam_core::{route, Address, Result};
+use ockam_identity::access_control::IdentityAccessControlBuilder;
+use ockam_identity::change_history::IdentityHistoryComparison;
+use ockam_identity::{Identity, TrustEveryonePolicy, TrustIdentifierPolicy};
+use ockam_node::Context;
+use ockam_vault::Vault;
+
+async fn start_identity_update_service(identity: &Identity) -> Result<()> {
+    identity
+        .create_secure_channel_listener("listener", TrustEveryonePolicy)
+        .await?;
+    identity
+        .start_identity_update_worker(
+            "identity_update",
+            Arc::new(IdentityAccessControlBuilder::new_with_any_id()),
+        )
+        .await
+}
+
+async fn connect(from: &Identity, to: &Identity) -> Result<Address> {
+    from.create_secure_channel(
+        route!["listener"],
+        TrustIdentifierPolicy::new(to.identifier().clone()),
+    )
+    .await
+}
+
+#[ockam_macros::test]
+async fn push_and_pull_identity_update(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+
+    let alice = Identity::create(ctx, vault.clone()).await?;
+    let bob = Identity::create(ctx, vault.clone()).await?;
+    let carol = Identity::create(ctx, vault.clone()).await?;
+    let dave = Identity::create(ctx, vault).await?;
+
+    start_identity_update_service(&alice).await?;
+
+    let channel = connect(&bob, &alice).await?;
+    bob.rotate_key("OCKAM_RK").await?;
+    bob.push_identity_update(route![channel, "identity_update"])
+        .await?;
+
+    let known_bob = alice.get_known_identity(bob.identifier()).await?.unwrap();
+    assert_eq!(known_bob.export()?, bob.export().await?);
+
+    let channel = connect(&carol, &alice).await?;
+    assert!(carol.get_known_identity(bob.identifier()).await?.is_none());
+    let comparison = carol
+        .pull_identity_update(route![channel.clone(), "identity_update"], bob.identifier())
+        .await?;
+    assert_eq!(comparison, IdentityHistoryComparison::Newer);
+
+    let known_bob = carol.get_known_identity(bob.identifier()).await?.unwrap();
+    assert_eq!(known_bob.export()?, bob.export().await?);
+
+    let res = carol
+        .pull_identity_update(route![channel, "identity_update"], dave.identifier())
+        .await;
+    assert!(res.is_err());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn push_older_identity_update(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+
+    let alice = Identity::create(ctx, vault.clone()).await?;
+    let bob = Identity::create(ctx, vault.clone()).await?;
+    let carol = Identity::create(ctx, vault).await?;
+
+    start_identity_update_service(&alice).await?;
+
+    carol
+        .receive_identity_update(&bob.to_public().await?)
+        .await?;
+    let channel = connect(&bob, &alice).await?;
+    bob.rotate_key("OCKAM_RK").await?;
+    bob.push_identity_update(route![channel, "identity_update"])
+        .await?;
+
+    // Alice knows a newer history and sends it back to Carol
+    let channel = connect(&carol, &alice).await?;
+    carol
+        .push_known_identity(route![channel, "identity_update"], bob.identifier())
+        .await?;
+
+    let known_bob = carol.get_known_identity(bob.identifier()).await?.unwrap();
+    assert_eq!(known_bob.export()?, bob.export().await?);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn push_conflicting_identity_update(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+
+    let alice = Identity::create(ctx, vault.clone()).await?;
+    let bob = Identity::create(ctx, vault.clone()).await?;
+    let carol = Identity::create(ctx, vault.clone()).await?;
+    let forked_bob = Identity::import(ctx, &bob.export().await?, vault).await?;
+
+    start_identity_update_service(&alice).await?;
+
+    let channel = connect(&bob, &alice).await?;
+    bob.rotate_key("OCKAM_RK").await?;
+    bob.push_identity_update(route![channel, "identity_update"])
+        .await?;
+
+    forked_bob
+        .create_key("Truck management".to_string())
+        .await?;
+    carol
+        .receive_identity_update(&forked_bob.to_public().await?)
+        .await?;
+    let channel = connect(&carol, &alice).await?;
+    let res = carol
+        .push_known_identity(route![channel, "identity_update"], bob.identifier())
+        .await;
+    assert!(res.is_err());
+
+    let known_bob = alice.get_known_identity(bob.identifier()).await?.unwrap();
+    assert_eq!(known_bob.export()?, bob.export().await?);
+
+    let conflicting = alice
+        .get_conflicting_identity(bob.identifier())
+        .await?
+        .unwrap();
+    assert_eq!(conflicting.export()?, forked_bob.export().await?);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn push_unknown_identity_update_rejected(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+
+    let alice = Identity::create(ctx, vault.clone()).await?;
+    let carol = Identity::create(ctx, vault.clone()).await?;
+    let dave = Identity::create(ctx, vault).await?;
+
+    start_identity_update_service(&alice).await?;
+
+    carol
+        .receive_identity_update(&dave.to_public().await?)
+        .await?;
+    let channel = connect(&carol, &alice).await?;
+    let res = carol
+        .push_known_identity(route![channel, "identity_update"], dave.identifier())
+        .await;
+    assert!(res.is_err());
+    assert!(alice.get_known_identity(dave.identifier()).await?.is_none());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn sync_identity_updates_over_secure_channels(ctx: &mut Context) -> Result<()> {
+    let vault = Vault::create();
+
+    let alice = Identity::create(ctx, vault.clone()).await?;
+    let bob = Identity::create(ctx, vault).await?;
+
+    start_identity_update_service(&alice).await?;
+
+    connect(&bob, &alice).await?;
+    bob.rotate_key("OCKAM_RK").await?;
+    alice.rotate_key("OCKAM_RK").await?;
+
+    // Bob pulls the newer history of Alice and pushes his own
+    bob.sync_identity_updates("identity_update").await;
+
+    let known_alice = bob.get_known_identity(alice.identifier()).await?.unwrap();
+    assert_eq!(known_alice.export()?, alice.export().await?);
+    let known_bob = alice.get_known_identity(bob.identifier()).await?.unwrap();
+    assert_eq!(known_bob.export()?, bob.export().await?);
+
+    ctx.stop().await
+}
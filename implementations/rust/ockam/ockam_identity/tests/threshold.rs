@@ -0,0 +1,135 @@
+use ockam_core::compat::sync::Arc;
+use ockam_core::{route, Result};
+use ockam_identity::credential_issuer::{CredentialIssuer, CredentialIssuerApi};
+use ockam_identity::{
+    Identity, IdentityVault, PublicIdentity, TrustEveryonePolicy, TrustIdentifierPolicy,
+};
+use ockam_node::Context;
+use ockam_vault::Vault;
+
+fn create_vaults(n: usize) -> Vec<Arc<dyn IdentityVault>> {
+    (0..n)
+        .map(|_| Vault::create() as Arc<dyn IdentityVault>)
+        .collect()
+}
+
+#[ockam_macros::test]
+async fn threshold_root_changes_signed_by_threshold_verified(ctx: &mut Context) -> Result<()> {
+    let vaults = create_vaults(3);
+    let identity = Identity::create_threshold(ctx, vaults.clone(), 2).await?;
+
+    identity.create_key("Truck management".into()).await?;
+    identity.rotate_key("Truck management").await?;
+
+    let public_identity =
+        PublicIdentity::import(&identity.export().await?, vaults[0].clone()).await?;
+    assert!(public_identity.has_threshold_root()?);
+    assert_eq!(public_identity.identifier(), identity.identifier());
+
+    // The root keys cannot be rotated individually
+    assert!(identity.rotate_root_key().await.is_err());
+
+    // With a single root key left, changes cannot be signed
+    let alone = Identity::import(ctx, &identity.export().await?, vaults[1].clone()).await?;
+    assert!(alone.create_key("Other".into()).await.is_err());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn threshold_root_partial_signatures_merged(ctx: &mut Context) -> Result<()> {
+    let vaults = create_vaults(3);
+    let identity = Identity::create_threshold(ctx, vaults.clone(), 2).await?;
+    let public_identity = identity.to_public().await?;
+    let data = b"data";
+
+    // Each cosigner only holds its own root key
+    let exported = identity.export().await?;
+    let cosigner1 = Identity::import(ctx, &exported, vaults[1].clone()).await?;
+    let cosigner2 = Identity::import(ctx, &exported, vaults[2].clone()).await?;
+
+    assert!(cosigner1.create_signature(data, None).await.is_err());
+
+    let mut signature = cosigner1.create_partial_signature(data).await?;
+    assert_eq!(signature.signatures().len(), 1);
+    assert!(
+        !public_identity
+            .verify_signature(&signature.to_signature()?, data, None, vaults[0].clone())
+            .await?
+    );
+
+    // The same partial signature cannot be counted twice
+    signature.merge(cosigner1.create_partial_signature(data).await?);
+    assert_eq!(signature.signatures().len(), 1);
+
+    signature.merge(cosigner2.create_partial_signature(data).await?);
+    assert!(
+        public_identity
+            .verify_signature(&signature.to_signature()?, data, None, vaults[0].clone())
+            .await?
+    );
+
+    // The identity holding every vault signs on its own
+    let signature = identity.create_signature(data, None).await?;
+    assert!(
+        public_identity
+            .verify_signature(&signature, data, None, vaults[0].clone())
+            .await?
+    );
+    assert!(
+        !public_identity
+            .verify_signature(&signature, b"other data", None, vaults[0].clone())
+            .await?
+    );
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn threshold_root_credential_issuer_credential_verified(ctx: &mut Context) -> Result<()> {
+    let vaults = create_vaults(3);
+    let authority = Identity::create_threshold(ctx, vaults.clone(), 2).await?;
+    let subject = Identity::create(ctx, vaults[0].clone()).await?;
+
+    let issuer = CredentialIssuer::new(authority);
+    issuer
+        .put_attribute_value(subject.identifier(), "name", "subject")
+        .await?;
+    let credential = issuer
+        .get_credential(subject.identifier())
+        .await?
+        .expect("a credential");
+
+    let data = issuer
+        .public_identity()
+        .await?
+        .verify_credential(&credential, subject.identifier(), vaults[0].clone())
+        .await?;
+    assert_eq!(data.attributes().get("name"), Some(b"subject".as_slice()));
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn threshold_root_secure_channel_established(ctx: &mut Context) -> Result<()> {
+    let vaults = create_vaults(2);
+    let server = Identity::create_threshold(ctx, vaults.clone(), 2).await?;
+    let client = Identity::create(ctx, vaults[0].clone()).await?;
+
+    server
+        .create_secure_channel_listener("listener", TrustEveryonePolicy)
+        .await?;
+    let channel = client
+        .create_secure_channel(
+            route!["listener"],
+            TrustIdentifierPolicy::new(server.identifier().clone()),
+        )
+        .await?;
+
+    ctx.send(route![channel, ctx.address()], "Hello".to_string())
+        .await?;
+    let message = ctx.receive::<String>().await?;
+    assert_eq!("Hello", message.body());
+
+    ctx.stop().await
+}
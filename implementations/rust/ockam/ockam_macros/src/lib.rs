@@ -116,6 +116,11 @@ pub fn node(attrs: TokenStream, item: TokenStream) -> TokenStream {
 ///   indefinitely. If the test times out it will panic. Defaults to 30000 (30
 ///   seconds).
 ///
+/// - `#[ockam::test(virtual_clock)]`: the node runs on a single thread with a
+///   virtual clock, which advances as soon as all the tasks of the node are
+///   idle. Timers and the test timeout don't wait for real time. Requires
+///   the `sim` feature of `ockam_node`.
+///
 /// Example of use:
 ///
 /// ```ignore
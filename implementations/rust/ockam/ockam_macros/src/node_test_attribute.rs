@@ -1,10 +1,14 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{
-    parse2, punctuated::Punctuated, AttributeArgs, ItemFn, Meta::NameValue, NestedMeta, ReturnType,
+    parse2,
+    punctuated::Punctuated,
+    AttributeArgs, ItemFn,
+    Meta::{NameValue, Path},
+    NestedMeta, ReturnType,
 };
 
-use crate::internals::attr::{parse_lit_into_int, parse_lit_into_path, Attr};
+use crate::internals::attr::{parse_lit_into_int, parse_lit_into_path, Attr, BoolAttr};
 use crate::internals::{ast, ast::FnVariable, check, ctx::Context, symbol::*};
 
 /// This macro will split the input function in two: the wrapper function that will be
@@ -92,6 +96,11 @@ fn output(mut cont: Container) -> TokenStream {
     let test_fn_ident = &cont.test_fn.sig.ident;
     let ockam_crate = cont.data.attrs.ockam_crate;
     let timeout_ms = cont.data.attrs.timeout_ms;
+    let node_builder = if cont.data.attrs.virtual_clock {
+        quote! { NodeBuilder::new().with_virtual_clock() }
+    } else {
+        quote! { NodeBuilder::new() }
+    };
     cont.original_fn.block = parse2(quote! {
         {
             use core::panic::AssertUnwindSafe;
@@ -99,7 +108,7 @@ fn output(mut cont: Container) -> TokenStream {
             use ockam_core::{Error, errcode::{Origin, Kind}};
             use #ockam_crate::{NodeBuilder, compat::{tokio::time::timeout, futures::FutureExt}};
 
-            let (mut #ctx_ident, mut executor) = NodeBuilder::new().build();
+            let (mut #ctx_ident, mut executor) = #node_builder.build();
             executor
                 .execute(async move {
                     // Wraps the test function call in a `catch_unwind` to catch possible panics.
@@ -214,12 +223,14 @@ impl<'a> Data<'a> {
 struct Attributes {
     ockam_crate: TokenStream,
     timeout_ms: u64,
+    virtual_clock: bool,
 }
 
 impl Attributes {
     fn from_ast(ctx: &Context, attrs: &AttributeArgs) -> Self {
         let mut ockam_crate = Attr::none(ctx, OCKAM_CRATE);
         let mut timeout_ms = Attr::none(ctx, TIMEOUT_MS);
+        let mut virtual_clock = BoolAttr::none(ctx, VIRTUAL_CLOCK);
         for attr in attrs {
             match attr {
                 // Parse `#[ockam::test(crate = "ockam")]`
@@ -234,6 +245,10 @@ impl Attributes {
                         timeout_ms.set(&nv.path, timeout);
                     }
                 }
+                // Parse `#[ockam::test(virtual_clock)]`
+                NestedMeta::Meta(Path(p)) if p == VIRTUAL_CLOCK => {
+                    virtual_clock.set_true(p);
+                }
                 NestedMeta::Meta(m) => {
                     let path = m.path().into_token_stream().to_string().replace(' ', "");
                     ctx.error_spanned_by(m.path(), format!("unknown attribute `{}`", path));
@@ -246,6 +261,7 @@ impl Attributes {
         Self {
             ockam_crate: ockam_crate.get().unwrap_or(quote! { ockam_node }),
             timeout_ms: timeout_ms.get().unwrap_or(30_000),
+            virtual_clock: virtual_clock.get(),
         }
     }
 }
@@ -4,11 +4,13 @@ use crate::{Context, MessageSendReceiveOptions};
 use core::fmt::Display;
 use core::time::Duration;
 use minicbor::Encode;
-use ockam_core::api::RequestBuilder;
+use ockam_core::api::rpc::Endpoint;
+use ockam_core::api::{decode_option_with, Request, RequestBuilder};
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{
-    Address, AllowAll, AllowOnwardAddress, LocalInfo, Mailbox, Mailboxes, Result, Route,
+    Address, AllowAll, AllowOnwardAddress, DenyAll, LocalInfo, Mailbox, Mailboxes, Result, Route,
 };
 
 #[cfg(feature = "tag")]
@@ -134,3 +136,83 @@ where
 
     Ok((body, local_info))
 }
+
+/// Typed client of the [`Endpoint`]s of an API service.
+pub struct Client {
+    ctx: Context,
+    route: Route,
+    timeout: Option<Duration>,
+    buf: Vec<u8>,
+}
+
+impl Client {
+    /// Create a client of the service at the given route.
+    pub async fn new(route: Route, ctx: &Context) -> Result<Self> {
+        let ctx = ctx
+            .new_detached(
+                Address::random_tagged("ApiClient.detached"),
+                DenyAll,
+                DenyAll,
+            )
+            .await?;
+        Ok(Client {
+            ctx,
+            route,
+            timeout: None,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Wait at most `timeout` for each response.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send a request to the endpoint `E` and decode its response.
+    ///
+    /// `params` are the values of the parameters of the path pattern of
+    /// the endpoint.  A `NotFound` response is an error.
+    pub async fn call<E: Endpoint>(
+        &mut self,
+        params: &[&str],
+        body: E::Req<'_>,
+    ) -> Result<E::Res<'_>> {
+        self.call_option::<E>(params, body).await?.ok_or_else(|| {
+            ockam_core::Error::new(
+                Origin::Api,
+                Kind::NotFound,
+                format!("{} not found", E::PATH),
+            )
+        })
+    }
+
+    /// Send a request to the endpoint `E` and decode its response, if
+    /// it was found.
+    pub async fn call_option<E: Endpoint>(
+        &mut self,
+        params: &[&str],
+        body: E::Req<'_>,
+    ) -> Result<Option<E::Res<'_>>> {
+        let mut ctx = E::context(&body);
+        let req = Request::builder(E::METHOD, E::path(params)?);
+        self.buf = if E::HAS_BODY {
+            self.send(E::PATH, req.body(body)).await?
+        } else {
+            self.send(E::PATH, req).await?
+        };
+        decode_option_with(E::PATH, None, &self.buf, &mut ctx)
+    }
+
+    async fn send<T: Encode<()>>(
+        &self,
+        label: &str,
+        req: RequestBuilder<'_, T>,
+    ) -> Result<Vec<u8>> {
+        let route = self.route.clone();
+        match self.timeout {
+            Some(t) => request_with_timeout(&self.ctx, label, None, route, req, t).await,
+            None => request(&self.ctx, label, None, route, req).await,
+        }
+    }
+}
@@ -1,3 +1,8 @@
+use crate::tokio::sync::mpsc::error::SendError;
+use futures::future::{select, Either};
+use futures::pin_mut;
+use ockam_core::{Priority, RelayMessage};
+
 /// Sender used to send payload messages
 pub type MessageSender<T> = crate::tokio::sync::mpsc::Sender<T>;
 /// Receiver used to receive payload messages
@@ -8,6 +13,64 @@ pub fn message_channel<T>() -> (MessageSender<T>, MessageReceiver<T>) {
     crate::tokio::sync::mpsc::channel(16)
 }
 
+/// Sender of the messages of a worker, with one lane per [`Priority`]
+#[derive(Clone, Debug)]
+pub struct MailboxSender {
+    normal: MessageSender<RelayMessage>,
+    control: MessageSender<RelayMessage>,
+}
+
+impl MailboxSender {
+    /// Send a message on the lane of its priority
+    pub async fn send(&self, msg: RelayMessage) -> Result<(), SendError<RelayMessage>> {
+        match msg.priority() {
+            Priority::Normal => self.normal.send(msg).await,
+            Priority::Control => self.control.send(msg).await,
+        }
+    }
+}
+
+/// Receiver of the messages of a worker
+///
+/// Waiting control messages are received before normal messages, so
+/// that control messages are not queued behind a backlog of data.
+#[derive(Debug)]
+pub struct MailboxReceiver {
+    normal: MessageReceiver<RelayMessage>,
+    control: MessageReceiver<RelayMessage>,
+}
+
+impl MailboxReceiver {
+    /// Receive the next message, or `None` once all senders are dropped
+    pub async fn recv(&mut self) -> Option<RelayMessage> {
+        let control = self.control.recv();
+        let normal = self.normal.recv();
+        pin_mut!(control, normal);
+        // The control lane is polled first
+        match select(control, normal).await {
+            Either::Left((Some(msg), _)) | Either::Right((Some(msg), _)) => Some(msg),
+            Either::Left((None, normal)) => normal.await,
+            Either::Right((None, control)) => control.await,
+        }
+    }
+}
+
+/// Create the mailbox channel of a worker
+pub fn mailbox_channel() -> (MailboxSender, MailboxReceiver) {
+    let (normal_tx, normal_rx) = message_channel();
+    let (control_tx, control_rx) = message_channel();
+    (
+        MailboxSender {
+            normal: normal_tx,
+            control: control_tx,
+        },
+        MailboxReceiver {
+            normal: normal_rx,
+            control: control_rx,
+        },
+    )
+}
+
 /// Router sender
 pub type RouterSender<T> = crate::tokio::sync::mpsc::Sender<T>;
 /// Router receiver
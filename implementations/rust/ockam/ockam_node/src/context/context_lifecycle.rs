@@ -1,5 +1,5 @@
 use crate::async_drop::AsyncDrop;
-use crate::channel_types::{message_channel, small_channel, SmallReceiver, SmallSender};
+use crate::channel_types::{mailbox_channel, small_channel, SmallReceiver, SmallSender};
 use crate::tokio::{self, runtime::Handle};
 use crate::{debugger, Context};
 use crate::{error::*, relay::CtrlSignal, router::SenderPair, NodeMessage};
@@ -56,7 +56,7 @@ impl Context {
         mailboxes: Mailboxes,
         async_drop_sender: Option<AsyncDropSender>,
     ) -> (Self, SenderPair, SmallReceiver<CtrlSignal>) {
-        let (mailbox_tx, receiver) = message_channel();
+        let (mailbox_tx, receiver) = mailbox_channel();
         let (ctrl_tx, ctrl_rx) = small_channel();
         (
             Self {
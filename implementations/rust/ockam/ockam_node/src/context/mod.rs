@@ -1,4 +1,5 @@
 mod context_lifecycle;
+mod monitor;
 mod receive_message;
 mod register_router;
 mod send_message;
@@ -6,18 +7,19 @@ mod stop_env;
 mod worker_lifecycle;
 
 pub use context_lifecycle::*;
+pub use monitor::*;
 pub use receive_message::*;
 pub use register_router::*;
 pub use send_message::*;
 pub use stop_env::*;
 pub use worker_lifecycle::*;
 
-use crate::channel_types::{SmallReceiver, SmallSender};
+use crate::channel_types::{MailboxReceiver, SmallSender};
 use crate::tokio::runtime::Handle;
 use crate::{error::*, NodeMessage};
 use core::sync::atomic::AtomicUsize;
 use ockam_core::compat::{string::String, sync::Arc, vec::Vec};
-use ockam_core::{Address, Mailboxes, RelayMessage, Result};
+use ockam_core::{Address, Mailboxes, Result};
 
 #[cfg(feature = "std")]
 use core::fmt::{Debug, Formatter};
@@ -30,7 +32,7 @@ pub struct Context {
     mailboxes: Mailboxes,
     sender: SmallSender<NodeMessage>,
     rt: Handle,
-    receiver: SmallReceiver<RelayMessage>,
+    receiver: MailboxReceiver,
     async_drop_sender: Option<AsyncDropSender>,
     mailbox_count: Arc<AtomicUsize>,
 }
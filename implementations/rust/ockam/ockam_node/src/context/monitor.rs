@@ -0,0 +1,56 @@
+use crate::channel_types::SmallReceiver;
+use crate::{Context, NodeError, NodeMessage, NodeReason, NodeReplyResult};
+use ockam_core::{Address, Message, Result};
+use serde::{Deserialize, Serialize};
+
+/// Message received by the monitors of a worker or processor when it stops
+///
+/// See [`Context::monitor()`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Message)]
+pub struct WorkerDown {
+    /// Primary address of the stopped worker or processor
+    pub address: Address,
+}
+
+impl WorkerDown {
+    pub(crate) fn new(address: Address) -> Self {
+        Self { address }
+    }
+}
+
+impl Context {
+    /// Get notified when the worker or processor at the given address stops
+    ///
+    /// A [`WorkerDown`] message is sent to the primary address of this
+    /// context when the target stops, either because it was stopped or
+    /// because its relay exited.  The message type of a worker using
+    /// this function must be able to decode `WorkerDown`, e.g.
+    /// [`Any`](ockam_core::Any).
+    pub async fn monitor(&self, addr: impl Into<Address>) -> Result<()> {
+        let (msg, rx) = NodeMessage::monitor(addr.into(), self.address());
+        self.router_request(msg, rx).await
+    }
+
+    /// Link this worker to the worker or processor at the given address
+    ///
+    /// When either of them stops, the other one is stopped as well.
+    pub async fn link(&self, addr: impl Into<Address>) -> Result<()> {
+        let (msg, rx) = NodeMessage::link(self.address(), addr.into());
+        self.router_request(msg, rx).await
+    }
+
+    async fn router_request(
+        &self,
+        msg: NodeMessage,
+        mut rx: SmallReceiver<NodeReplyResult>,
+    ) -> Result<()> {
+        self.sender
+            .send(msg)
+            .await
+            .map_err(NodeError::from_send_err)?;
+        rx.recv()
+            .await
+            .ok_or_else(|| NodeError::NodeState(NodeReason::Unknown).internal())??;
+        Ok(())
+    }
+}
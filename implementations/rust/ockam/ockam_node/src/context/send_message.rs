@@ -8,7 +8,7 @@ use ockam_core::sessions::{SessionId, SessionPolicy, Sessions};
 use ockam_core::{
     errcode::{Kind, Origin},
     route, Address, AllowAll, AllowOnwardAddress, Error, LocalMessage, Mailboxes, Message,
-    RelayMessage, Result, Route, TransportMessage,
+    Priority, RelayMessage, Result, Route, TransportMessage,
 };
 use ockam_core::{LocalInfo, Mailbox};
 
@@ -195,8 +195,36 @@ impl Context {
         R: Into<Route>,
         M: Message + Send + 'static,
     {
-        self.send_from_address_impl(route.into(), msg, self.address(), local_info)
-            .await
+        self.send_from_address_impl(
+            route.into(),
+            msg,
+            self.address(),
+            local_info,
+            Priority::Normal,
+            true,
+        )
+        .await
+    }
+
+    /// Send a message to an address or via a fully-qualified route
+    /// with the given [`Priority`].
+    ///
+    /// Control messages are received before the normal messages waiting
+    /// in the mailbox of each worker on the route of this node.
+    pub async fn send_with_priority<R, M>(&self, route: R, msg: M, priority: Priority) -> Result<()>
+    where
+        R: Into<Route>,
+        M: Message + Send + 'static,
+    {
+        self.send_from_address_impl(
+            route.into(),
+            msg,
+            self.address(),
+            Vec::new(),
+            priority,
+            true,
+        )
+        .await
     }
 
     /// Send a message to an address or via a fully-qualified route
@@ -222,8 +250,60 @@ impl Context {
         R: Into<Route>,
         M: Message + Send + 'static,
     {
-        self.send_from_address_impl(route.into(), msg, sending_address, Vec::new())
-            .await
+        self.send_from_address_impl(
+            route.into(),
+            msg,
+            sending_address,
+            Vec::new(),
+            Priority::Normal,
+            true,
+        )
+        .await
+    }
+
+    /// Send a message from the given sending address with the given
+    /// [`Priority`]
+    ///
+    /// See [`Context::send_from_address`] and [`Context::send_with_priority`].
+    pub async fn send_from_address_with_priority<R, M>(
+        &self,
+        route: R,
+        msg: M,
+        sending_address: Address,
+        priority: Priority,
+    ) -> Result<()>
+    where
+        R: Into<Route>,
+        M: Message + Send + 'static,
+    {
+        self.send_from_address_impl(
+            route.into(),
+            msg,
+            sending_address,
+            Vec::new(),
+            priority,
+            true,
+        )
+        .await
+    }
+
+    /// Send a runtime notification, such as a worker lifecycle
+    /// event, which is not subject to the outgoing access control of
+    /// this context
+    #[cfg(feature = "std")]
+    pub(crate) async fn notify<M>(&self, addr: Address, msg: M) -> Result<()>
+    where
+        M: Message + Send + 'static,
+    {
+        self.send_from_address_impl(
+            addr.into(),
+            msg,
+            self.address(),
+            Vec::new(),
+            Priority::Normal,
+            false,
+        )
+        .await
     }
 
     async fn send_from_address_impl<M>(
@@ -232,6 +312,8 @@ impl Context {
         msg: M,
         sending_address: Address,
         local_info: Vec<LocalInfo>,
+        priority: Priority,
+        check_outgoing: bool,
     ) -> Result<()>
     where
         M: Message + Send + 'static,
@@ -268,14 +350,14 @@ impl Context {
         let transport_msg = TransportMessage::v1(route, route![sending_address.clone()], payload);
 
         // Pack transport message into a LocalMessage wrapper
-        let local_msg = LocalMessage::new(transport_msg, local_info);
+        let local_msg = LocalMessage::new(transport_msg, local_info).with_priority(priority);
 
         // Pack local message into a RelayMessage wrapper
         let relay_msg = RelayMessage::new(sending_address.clone(), addr, local_msg);
 
         debugger::log_outgoing_message(self, &relay_msg);
 
-        if !self.mailboxes.is_outgoing_authorized(&relay_msg).await? {
+        if check_outgoing && !self.mailboxes.is_outgoing_authorized(&relay_msg).await? {
             warn!(
                 "Message sent from {} to {} did not pass outgoing access control",
                 relay_msg.source(),
@@ -59,6 +59,26 @@ impl Executor {
         Executor::default()
     }
 
+    /// Create an executor running on a single-threaded runtime whose
+    /// clock is paused, see [`sim`](crate::sim)
+    #[cfg(feature = "sim")]
+    pub(crate) fn with_virtual_clock() -> Self {
+        let rt = crate::tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .unwrap();
+        let router = Router::new();
+        #[cfg(feature = "metrics")]
+        let metrics = Metrics::new(&rt, router.get_metrics_readout());
+        Self {
+            rt,
+            router,
+            #[cfg(feature = "metrics")]
+            metrics,
+        }
+    }
+
     /// Get access to the internal message sender
     pub(crate) fn sender(&self) -> SmallSender<NodeMessage> {
         self.router.sender()
@@ -121,18 +141,18 @@ impl Executor {
     ///
     /// Any errors encountered by the router or provided application
     /// code will be returned from this function.
-    // TODO @antoinevg - support @thomm join & merge with std version
-    pub fn execute<F>(&mut self, future: F) -> Result<()>
+    pub fn execute<F>(&mut self, future: F) -> Result<F::Output>
     where
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        let _join = self.rt.spawn(future);
+        let join_body = self.rt.spawn(future);
 
         // Block this task executing the primary message router,
         // returning any critical failures that it encounters.
-        let future = self.router.run();
-        crate::tokio::runtime::execute(&self.rt, async move { future.await.unwrap() });
-        Ok(())
+        crate::tokio::runtime::block_future(&self.rt, self.router.run())?;
+
+        // Last join user code
+        Ok(crate::tokio::runtime::block_future(&self.rt, join_body))
     }
 }
@@ -41,6 +41,12 @@ pub mod api;
 /// Debugger
 pub mod debugger;
 
+#[cfg(feature = "std")]
+pub mod loopback;
+
+#[cfg(feature = "sim")]
+pub mod sim;
+
 mod async_drop;
 mod context;
 mod delayed;
@@ -52,7 +58,10 @@ mod parser;
 mod processor_builder;
 mod relay;
 mod router;
+#[cfg(feature = "std")]
+mod supervisor;
 mod worker_builder;
+mod worker_pool;
 
 pub use context::*;
 pub use delayed::*;
@@ -60,7 +69,10 @@ pub use error::*;
 pub use executor::*;
 pub use messages::*;
 pub use processor_builder::ProcessorBuilder;
+#[cfg(feature = "std")]
+pub use supervisor::*;
 pub use worker_builder::WorkerBuilder;
+pub use worker_pool::{Dispatch, WorkerPool};
 
 pub use node::{NodeBuilder, NullWorker};
 
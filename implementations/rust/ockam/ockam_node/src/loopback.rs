@@ -0,0 +1,151 @@
+//! An in-process transport connecting nodes started in the same runtime
+//!
+//! Nodes started with [`NodeBuilder::spawn`] share a runtime but each
+//! have their own router and address space, so a message can't be sent
+//! from one to another with a local route.  A [`LoopbackTransport`] gives
+//! each node attached to it a name, and the address returned by
+//! [`address`] routes messages to the node with that name:
+//!
+//! ```ignore
+//! let loopback = LoopbackTransport::new();
+//! let server = loopback.start_node("server").await?;
+//! server.start_worker("echoer", Echoer, AllowAll, AllowAll).await?;
+//!
+//! let mut client = loopback.start_node("client").await?;
+//! let route = route![loopback::address("server"), "echoer"];
+//! client.send(route, "Hello".to_string()).await?;
+//! ```
+//!
+//! The receiving node prepends the address of the sending node to the
+//! return route of each message, so that replies are routed back.
+
+use crate::channel_types::{message_channel, MessageReceiver, MessageSender};
+use crate::{Context, NodeBuilder, NodeError};
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::{
+    async_trait, Address, AllowAll, Any, LocalMessage, Processor, Result, Routed, TransportMessage,
+    TransportType, Worker,
+};
+
+/// The address type of the loopback transport
+pub const LOOPBACK: TransportType = TransportType::new(7);
+
+/// The address of the node with the given name on a [`LoopbackTransport`]
+pub fn address(node: &str) -> Address {
+    Address::new(LOOPBACK, node)
+}
+
+/// A message sent by another node
+struct Delivery {
+    from: String,
+    msg: TransportMessage,
+}
+
+/// An in-process transport between nodes
+///
+/// Cloning a transport returns a handle to the same transport.
+#[derive(Clone, Default)]
+pub struct LoopbackTransport {
+    nodes: Arc<Mutex<BTreeMap<String, MessageSender<Delivery>>>>,
+}
+
+impl LoopbackTransport {
+    /// Create a transport without nodes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new node in the current runtime and attach it to the
+    /// transport with the given name
+    pub async fn start_node(&self, name: &str) -> Result<Context> {
+        let ctx = NodeBuilder::new().spawn();
+        self.attach(&ctx, name).await?;
+        Ok(ctx)
+    }
+
+    /// Attach a node to the transport with the given name
+    ///
+    /// The name of a stopped node can be reused.
+    pub async fn attach(&self, ctx: &Context, name: &str) -> Result<()> {
+        let (sender, receiver) = message_channel();
+        {
+            let mut nodes = self.nodes.lock().unwrap();
+            if nodes.get(name).map_or(false, |s| !s.is_closed()) {
+                return Err(NodeError::Address(address(name)).already_exists());
+            }
+            nodes.insert(name.to_string(), sender);
+        }
+
+        let router_addr = Address::random_tagged("LoopbackTransport.router");
+        let router = LoopbackRouter {
+            transport: self.clone(),
+            name: name.to_string(),
+        };
+        ctx.start_worker(router_addr.clone(), router, AllowAll, AllowAll)
+            .await?;
+        ctx.start_processor(
+            Address::random_tagged("LoopbackTransport.receiver"),
+            LoopbackReceiver { receiver },
+            AllowAll,
+            AllowAll,
+        )
+        .await?;
+
+        trace!("Registering loopback router for type = {}", LOOPBACK);
+        ctx.register(LOOPBACK, router_addr).await
+    }
+
+    fn sender(&self, name: &str) -> Option<MessageSender<Delivery>> {
+        self.nodes.lock().unwrap().get(name).cloned()
+    }
+}
+
+/// Sends the messages routed to other nodes to their receiver
+struct LoopbackRouter {
+    transport: LoopbackTransport,
+    name: String,
+}
+
+#[async_trait]
+impl Worker for LoopbackRouter {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(&mut self, _ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut msg = msg.into_transport_message();
+        let next = msg.onward_route.step()?;
+        let delivery = Delivery {
+            from: self.name.clone(),
+            msg,
+        };
+        let sent = match self.transport.sender(next.address()) {
+            Some(sender) => sender.send(delivery).await.is_ok(),
+            None => false,
+        };
+        if !sent {
+            debug!("Dropping message to unknown or stopped node {}", next);
+        }
+        Ok(())
+    }
+}
+
+/// Forwards the messages sent by other nodes into the node
+struct LoopbackReceiver {
+    receiver: MessageReceiver<Delivery>,
+}
+
+#[async_trait]
+impl Processor for LoopbackReceiver {
+    type Context = Context;
+
+    async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
+        let Delivery { from, mut msg } = match self.receiver.recv().await {
+            Some(delivery) => delivery,
+            None => return Ok(false),
+        };
+        msg.return_route.modify().prepend(address(&from));
+        ctx.forward(LocalMessage::new(msg, vec![])).await?;
+        Ok(true)
+    }
+}
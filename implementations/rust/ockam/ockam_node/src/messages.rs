@@ -1,11 +1,11 @@
-use crate::channel_types::{small_channel, MessageSender, SmallReceiver, SmallSender};
+use crate::channel_types::{small_channel, MailboxSender, SmallReceiver, SmallSender};
 use crate::{
     error::{NodeError, NodeReason, RouterReason, WorkerReason},
     router::SenderPair,
 };
 use core::{fmt, sync::atomic::AtomicUsize};
 use ockam_core::compat::{string::String, sync::Arc, vec::Vec};
-use ockam_core::{Address, Error, RelayMessage, Result, TransportType};
+use ockam_core::{Address, Error, Result, TransportType};
 
 /// Messages sent from the Node to the Executor
 #[derive(Debug)]
@@ -47,6 +47,10 @@ pub enum NodeMessage {
     SetReady(Address),
     /// Check whether an address has been marked as "ready"
     CheckReady(Address, SmallSender<NodeReplyResult>),
+    /// Notify the second address when the first one stops
+    Monitor(Address, Address, SmallSender<NodeReplyResult>),
+    /// Stop both addresses when one of them stops
+    Link(Address, Address, SmallSender<NodeReplyResult>),
 }
 
 impl fmt::Display for NodeMessage {
@@ -65,6 +69,8 @@ impl fmt::Display for NodeMessage {
             NodeMessage::Router(_, _, _) => write!(f, "Router"),
             NodeMessage::SetReady(_) => write!(f, "SetReady"),
             NodeMessage::CheckReady(_, _) => write!(f, "CheckReady"),
+            NodeMessage::Monitor(_, _, _) => write!(f, "Monitor"),
+            NodeMessage::Link(_, _, _) => write!(f, "Link"),
         }
     }
 }
@@ -152,6 +158,18 @@ impl NodeMessage {
         let (tx, rx) = small_channel();
         (Self::CheckReady(addr, tx), rx)
     }
+
+    /// Create a Monitor message and reply receiver
+    pub fn monitor(target: Address, watcher: Address) -> (Self, SmallReceiver<NodeReplyResult>) {
+        let (tx, rx) = small_channel();
+        (Self::Monitor(target, watcher, tx), rx)
+    }
+
+    /// Create a Link message and reply receiver
+    pub fn link(a: Address, b: Address) -> (Self, SmallReceiver<NodeReplyResult>) {
+        let (tx, rx) = small_channel();
+        (Self::Link(a, b, tx), rx)
+    }
 }
 
 /// The reply/result of a Node
@@ -169,7 +187,7 @@ pub enum RouterReply {
         /// The address a message is being sent to
         addr: Address,
         /// The relay sender
-        sender: MessageSender<RelayMessage>,
+        sender: MailboxSender,
     },
     /// Indicate the 'ready' state of an address
     State(bool),
@@ -260,12 +278,12 @@ impl RouterReply {
     }
 
     /// Return [RouterReply::Sender] for the given information
-    pub fn sender(addr: Address, sender: MessageSender<RelayMessage>) -> NodeReplyResult {
+    pub fn sender(addr: Address, sender: MailboxSender) -> NodeReplyResult {
         Ok(RouterReply::Sender { addr, sender })
     }
 
     /// Consume the wrapper and return [RouterReply::Sender]
-    pub fn take_sender(self) -> Result<(Address, MessageSender<RelayMessage>)> {
+    pub fn take_sender(self) -> Result<(Address, MailboxSender)> {
         match self {
             Self::Sender { addr, sender } => Ok((addr, sender)),
             _ => Err(NodeError::NodeState(NodeReason::Unknown).internal()),
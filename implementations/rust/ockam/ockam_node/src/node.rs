@@ -1,4 +1,6 @@
 use crate::{debugger, Context, Executor};
+#[cfg(feature = "std")]
+use crate::{router::Router, tokio::runtime::Handle};
 use ockam_core::compat::sync::Arc;
 use ockam_core::{Address, AllowAll, Mailbox, Mailboxes};
 
@@ -17,6 +19,8 @@ impl ockam_core::Worker for NullWorker {
 /// builder API to customise the underlying node that is created.
 pub struct NodeBuilder {
     logging: bool,
+    #[cfg(feature = "sim")]
+    virtual_clock: bool,
 }
 
 impl Default for NodeBuilder {
@@ -28,12 +32,30 @@ impl Default for NodeBuilder {
 impl NodeBuilder {
     /// Create a node
     pub fn new() -> Self {
-        Self { logging: true }
+        Self {
+            logging: true,
+            #[cfg(feature = "sim")]
+            virtual_clock: false,
+        }
     }
 
     /// Disable logging on this node
-    pub fn no_logging(self) -> Self {
-        Self { logging: false }
+    pub fn no_logging(mut self) -> Self {
+        self.logging = false;
+        self
+    }
+
+    /// Run this node on a single-threaded runtime with a virtual clock
+    ///
+    /// Time only advances when every task of the node is idle, or when
+    /// [`sim::advance`](crate::sim::advance) is called, which makes
+    /// timers and timeouts deterministic.
+    #[cfg(feature = "sim")]
+    pub fn with_virtual_clock(self) -> Self {
+        Self {
+            virtual_clock: true,
+            ..self
+        }
     }
 
     /// Consume this builder and yield a new Ockam Node
@@ -45,6 +67,13 @@ impl NodeBuilder {
 
         info!("Initializing ockam node");
 
+        #[cfg(feature = "sim")]
+        let mut exe = if self.virtual_clock {
+            Executor::with_virtual_clock()
+        } else {
+            Executor::new()
+        };
+        #[cfg(not(feature = "sim"))]
         let mut exe = Executor::new();
         let addr: Address = "app".into();
 
@@ -68,6 +97,47 @@ impl NodeBuilder {
         // Then return the root context and executor
         (ctx, exe)
     }
+
+    /// Start the node inside the current runtime and return its root
+    /// context
+    ///
+    /// Unlike [`build`](Self::build), which creates a runtime for a single
+    /// node, this starts any number of nodes in one runtime, each with its
+    /// own router, addresses and transports.  The
+    /// [`loopback`](crate::loopback) transport connects them.  The node
+    /// runs until [`Context::stop`] is called on its root context.
+    ///
+    /// # Panics
+    ///
+    /// Panics when called outside of a runtime.
+    #[cfg(feature = "std")]
+    pub fn spawn(self) -> Context {
+        if self.logging {
+            setup_tracing();
+        }
+
+        info!("Initializing ockam node");
+
+        let mut router = Router::new();
+        let (ctx, sender, _) = Context::new(
+            Handle::current(),
+            router.sender(),
+            Mailboxes::new(
+                Mailbox::new("app", Arc::new(AllowAll), Arc::new(AllowAll)),
+                vec![],
+            ),
+            None,
+        );
+        debugger::log_inherit_context("NODE", &ctx, &ctx);
+        router.init("app".into(), sender);
+
+        crate::tokio::spawn(async move {
+            if let Err(e) = router.run().await {
+                error!("Node router failed: {}", e)
+            }
+        });
+        ctx
+    }
 }
 
 /// Utility to setup tracing-subscriber from the environment.
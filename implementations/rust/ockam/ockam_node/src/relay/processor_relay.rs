@@ -18,8 +18,6 @@ where
         Self { processor, ctx }
     }
 
-    #[cfg_attr(not(feature = "std"), allow(unused_mut))]
-    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
     async fn run(self, mut ctrl_rx: SmallReceiver<CtrlSignal>) {
         let mut ctx = self.ctx;
         let mut processor = self.processor;
@@ -81,12 +79,23 @@ where
             };
         }
 
-        // TODO wait on run_loop until we have a no_std select! implementation
         #[cfg(not(feature = "std"))]
-        match run_loop.await {
-            Ok(_) => trace!("Processor shut down cleanly {}", ctx_addr),
-            Err(err) => error!("processor run loop aborted with error: {:?}", err),
-        };
+        {
+            use futures::future::{select, Either};
+
+            // This future resolves when a stop control signal is received
+            let shutdown_signal = async { ctrl_rx.recv().await };
+
+            // Then select over the two futures
+            futures::pin_mut!(shutdown_signal, run_loop);
+            match select(shutdown_signal, run_loop).await {
+                Either::Left(_) => debug!("Shutting down processor {}", ctx_addr),
+                Either::Right((Ok(_), _)) => trace!("Processor shut down cleanly {}", ctx_addr),
+                Either::Right((Err(err), _)) => {
+                    error!("processor run loop aborted with error: {:?}", err)
+                }
+            }
+        }
 
         // If we reach this point the router has signalled us to shut down
         match processor.shutdown(&mut ctx).await {
@@ -5,6 +5,15 @@ use crate::{parser, Context};
 use core::marker::PhantomData;
 use ockam_core::{Message, RelayMessage, Result, Routed, Worker};
 
+#[cfg(feature = "std")]
+use crate::{Supervision, SupervisorMessage, WorkerEvent, WorkerEventKind};
+#[cfg(feature = "std")]
+use futures::FutureExt;
+#[cfg(feature = "std")]
+use ockam_core::compat::string::{String, ToString};
+#[cfg(feature = "std")]
+use std::panic::AssertUnwindSafe;
+
 /// Worker relay machinery
 ///
 /// Every worker in the Ockam runtime needs a certain amount of logic
@@ -17,6 +26,11 @@ where
 {
     worker: W,
     ctx: Context,
+    #[cfg(feature = "std")]
+    supervision: Supervision<W>,
+    /// Number of consecutive restarts of the worker
+    #[cfg(feature = "std")]
+    restarts: u32,
     _phantom: PhantomData<M>,
 }
 
@@ -25,10 +39,18 @@ where
     W: Worker<Context = Context, Message = M>,
     M: Message + Send + 'static,
 {
-    pub fn new(worker: W, ctx: Context) -> Self {
+    pub(crate) fn new(
+        worker: W,
+        ctx: Context,
+        #[cfg(feature = "std")] supervision: Supervision<W>,
+    ) -> Self {
         Self {
             worker,
             ctx,
+            #[cfg(feature = "std")]
+            supervision,
+            #[cfg(feature = "std")]
+            restarts: 0,
             _phantom: PhantomData,
         }
     }
@@ -77,14 +99,84 @@ where
             }
         };
 
+        // Messages which can't be decoded are not a failure of the worker
+        let routed = match Self::wrap_direct_message(relay_msg) {
+            Ok(routed) => routed,
+            Err(_) => return Ok(true),
+        };
+
         // Call the worker handle function - pass errors up
-        let routed = Self::wrap_direct_message(relay_msg)?;
         self.worker.handle_message(&mut self.ctx, routed).await?;
 
         // Signal to the outer loop that we would like to run again
         Ok(true)
     }
 
+    /// Report a lifecycle event to the supervisor of this worker
+    #[cfg(feature = "std")]
+    async fn notify(&mut self, kind: WorkerEventKind) {
+        if let Some(supervisor) = &self.supervision.supervisor {
+            let event = WorkerEvent::new(self.ctx.address(), kind);
+            if let Err(e) = self
+                .ctx
+                .notify(supervisor.clone(), SupervisorMessage::Event(event))
+                .await
+            {
+                debug!("Failed to notify supervisor '{}': {}", supervisor, e);
+            }
+        }
+    }
+
+    /// Apply the restart policy after a failure of the worker
+    ///
+    /// Return whether the run-loop should keep running
+    #[cfg(feature = "std")]
+    async fn on_failure(
+        &mut self,
+        panicked: bool,
+        ctrl_rx: &mut SmallReceiver<CtrlSignal>,
+    ) -> bool {
+        let (backoff, factory) = match (
+            self.supervision.policy.backoff(panicked),
+            &self.supervision.factory,
+        ) {
+            (Some(backoff), Some(factory)) => (backoff.clone(), factory.clone()),
+            // Errors are logged and ignored, panics stop the worker
+            _ => return !panicked,
+        };
+
+        let address = self.ctx.address();
+        if self.restarts >= backoff.max_restarts() {
+            error!(
+                "Worker '{}' failed after {} restarts, stopping it",
+                address, self.restarts
+            );
+            self.notify(WorkerEventKind::GaveUp).await;
+            return false;
+        }
+
+        crate::tokio::select! {
+            _ = crate::tokio::time::sleep(backoff.delay(self.restarts)) => {},
+            _ = ctrl_rx.recv() => return false,
+        }
+        self.restarts += 1;
+
+        // The state of a panicked worker can't be trusted to shut down
+        if !panicked {
+            if let Err(e) = self.worker.shutdown(&mut self.ctx).await {
+                error!("Failure during '{}' worker shutdown: {}", address, e);
+            }
+        }
+        self.worker = factory();
+        if let Err(e) = self.worker.initialize(&mut self.ctx).await {
+            error!("Failure during '{}' worker initialisation: {}", address, e);
+        }
+
+        info!("Restarted worker '{}' ({})", address, self.restarts);
+        self.notify(WorkerEventKind::Restarted(self.restarts)).await;
+        true
+    }
+
     #[cfg_attr(not(feature = "std"), allow(unused_mut))]
     #[cfg_attr(not(feature = "std"), allow(unused_variables))]
     async fn run(mut self, mut ctrl_rx: SmallReceiver<CtrlSignal>) {
@@ -105,26 +197,17 @@ where
             error!("Failed to mark worker '{}' as 'ready': {}", address, e);
         }
 
+        #[cfg(feature = "std")]
+        self.notify(WorkerEventKind::Started).await;
+
+        // Whether the worker panicked and was not restarted
+        #[cfg(feature = "std")]
+        let mut poisoned = false;
+
         #[cfg(feature = "std")]
         loop {
-            crate::tokio::select! {
-                result = self.recv_message() => {
-                    match result {
-                        // Successful message handling -- keep running
-                        Ok(true) => {},
-                        // Successful message handling -- stop now
-                        Ok(false) => {
-                            break;
-                        },
-                        // An error occurred -- log and continue
-                        Err(e) => {
-                            #[cfg(feature = "debugger")]
-                            error!("Error encountered during '{}' message handling: {:?}", address, e);
-                            #[cfg(not(feature = "debugger"))]
-                            error!("Error encountered during '{}' message handling: {}", address, e);
-                        }
-                    }
-                },
+            let result = crate::tokio::select! {
+                result = AssertUnwindSafe(self.recv_message()).catch_unwind() => result,
                 result = ctrl_rx.recv() => {
                     if result.is_some() {
                         debug!("Relay received shutdown signal, terminating!");
@@ -132,8 +215,50 @@ where
                     }
 
                     // We are stopping
+                    continue;
                 }
             };
+
+            match result {
+                // Successful message handling -- keep running
+                Ok(Ok(true)) => self.restarts = 0,
+                // Successful message handling -- stop now
+                Ok(Ok(false)) => {
+                    break;
+                }
+                // An error occurred -- log and apply the restart policy
+                Ok(Err(e)) => {
+                    #[cfg(feature = "debugger")]
+                    error!(
+                        "Error encountered during '{}' message handling: {:?}",
+                        address, e
+                    );
+                    #[cfg(not(feature = "debugger"))]
+                    error!(
+                        "Error encountered during '{}' message handling: {}",
+                        address, e
+                    );
+
+                    self.notify(WorkerEventKind::Failed(e.to_string())).await;
+                    if !self.on_failure(false, &mut ctrl_rx).await {
+                        break;
+                    }
+                }
+                // The worker panicked -- log and apply the restart policy
+                Err(panic) => {
+                    let reason = panic_reason(panic.as_ref());
+                    error!(
+                        "Worker '{}' panicked during message handling: {}",
+                        address, reason
+                    );
+
+                    self.notify(WorkerEventKind::Panicked(reason)).await;
+                    poisoned = !self.on_failure(true, &mut ctrl_rx).await;
+                    if poisoned {
+                        break;
+                    }
+                }
+            }
         }
         #[cfg(not(feature = "std"))]
         loop {
@@ -153,17 +278,26 @@ where
         }
 
         // Run the shutdown hook for this worker
-        match self.worker.shutdown(&mut self.ctx).await {
-            Ok(()) => {}
-            Err(e) => {
-                error!(
-                    "Failure during '{}' worker shutdown: {}",
-                    self.ctx.address(),
-                    e
-                );
+        #[cfg(feature = "std")]
+        let run_shutdown = !poisoned;
+        #[cfg(not(feature = "std"))]
+        let run_shutdown = true;
+        if run_shutdown {
+            match self.worker.shutdown(&mut self.ctx).await {
+                Ok(()) => {}
+                Err(e) => {
+                    error!(
+                        "Failure during '{}' worker shutdown: {}",
+                        self.ctx.address(),
+                        e
+                    );
+                }
             }
         }
 
+        #[cfg(feature = "std")]
+        self.notify(WorkerEventKind::Stopped).await;
+
         // Finally send the router a stop ACK -- log errors
         trace!("Sending shutdown ACK");
         if let Err(e) = self.ctx.send_stop_ack().await {
@@ -172,8 +306,31 @@ where
     }
 
     /// Build and spawn a new worker relay, returning a send handle to it
-    pub(crate) fn init(rt: &Handle, worker: W, ctx: Context, ctrl_rx: SmallReceiver<CtrlSignal>) {
-        let relay = WorkerRelay::<W, M>::new(worker, ctx);
+    pub(crate) fn init(
+        rt: &Handle,
+        worker: W,
+        ctx: Context,
+        ctrl_rx: SmallReceiver<CtrlSignal>,
+        #[cfg(feature = "std")] supervision: Supervision<W>,
+    ) {
+        let relay = WorkerRelay::<W, M>::new(
+            worker,
+            ctx,
+            #[cfg(feature = "std")]
+            supervision,
+        );
         rt.spawn(relay.run(ctrl_rx));
     }
 }
+
+/// Extract a printable reason from a panic payload
+#[cfg(feature = "std")]
+fn panic_reason(panic: &(dyn core::any::Any + Send)) -> String {
+    if let Some(reason) = panic.downcast_ref::<&str>() {
+        reason.to_string()
+    } else if let Some(reason) = panic.downcast_ref::<String>() {
+        reason.clone()
+    } else {
+        "unknown reason".to_string()
+    }
+}
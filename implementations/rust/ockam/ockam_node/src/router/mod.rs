@@ -1,3 +1,4 @@
+mod monitor;
 mod record;
 mod shutdown;
 mod start_processor;
@@ -13,19 +14,19 @@ use std::sync::atomic::AtomicUsize;
 use record::{AddressMeta, AddressRecord, InternalMap};
 use state::{NodeState, RouterState};
 
-use crate::channel_types::{router_channel, MessageSender, RouterReceiver, SmallSender};
+use crate::channel_types::{router_channel, MailboxSender, RouterReceiver, SmallSender};
 use crate::{
     error::{NodeError, NodeReason},
     relay::CtrlSignal,
     NodeMessage, NodeReplyResult, RouterReply, ShutdownType,
 };
 use ockam_core::compat::{collections::BTreeMap, sync::Arc};
-use ockam_core::{Address, RelayMessage, Result, TransportType};
+use ockam_core::{Address, Result, TransportType};
 
 /// A pair of senders to a worker relay
 #[derive(Debug)]
 pub struct SenderPair {
-    pub msgs: MessageSender<RelayMessage>,
+    pub msgs: MailboxSender,
     pub ctrl: SmallSender<CtrlSignal>,
 }
 
@@ -229,6 +230,7 @@ impl Router {
                         self.map.addr_map.remove(addr);
                     });
                 }
+                monitor::down(self, addr).await?;
             }
 
             StopAck(addr) => {
@@ -274,6 +276,12 @@ impl Router {
                 }
             }
 
+            //// ==! Monitors and links
+            Monitor(ref target, watcher, ref reply) => {
+                monitor::monitor(self, target, watcher, reply).await?
+            }
+            Link(ref a, ref b, ref reply) => monitor::link(self, a, b, reply).await?,
+
             CheckReady(addr, reply) => {
                 let ready = self.map.get_ready(addr, reply.clone());
                 if ready {
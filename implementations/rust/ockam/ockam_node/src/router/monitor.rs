@@ -0,0 +1,145 @@
+use super::Router;
+use crate::channel_types::SmallSender;
+use crate::{
+    error::{NodeError, NodeReason},
+    NodeReplyResult, RouterReply, WorkerDown,
+};
+use ockam_core::{
+    compat::vec::Vec, route, Address, Encodable, LocalMessage, RelayMessage, Result,
+    TransportMessage,
+};
+
+/// Register `watcher` to be notified when `target` stops
+pub(super) async fn monitor(
+    router: &mut Router,
+    target: &Address,
+    watcher: Address,
+    reply: &SmallSender<NodeReplyResult>,
+) -> Result<()> {
+    let msg = match router.map.addr_map.get(target) {
+        Some(primary) => {
+            trace!("Monitoring '{}' for '{}'", primary, watcher);
+            router
+                .map
+                .monitors
+                .entry(primary.clone())
+                .or_default()
+                .insert(watcher);
+            RouterReply::ok()
+        }
+        None => RouterReply::no_such_address(target.clone()),
+    };
+
+    reply
+        .send(msg)
+        .await
+        .map_err(|_| NodeError::NodeState(NodeReason::Unknown).internal())?;
+    Ok(())
+}
+
+/// Link two addresses so that they are stopped together
+pub(super) async fn link(
+    router: &mut Router,
+    a: &Address,
+    b: &Address,
+    reply: &SmallSender<NodeReplyResult>,
+) -> Result<()> {
+    let msg = match (router.map.addr_map.get(a), router.map.addr_map.get(b)) {
+        (Some(a), Some(b)) => {
+            let (a, b) = (a.clone(), b.clone());
+            trace!("Linking '{}' and '{}'", a, b);
+            router
+                .map
+                .links
+                .entry(a.clone())
+                .or_default()
+                .insert(b.clone());
+            router.map.links.entry(b).or_default().insert(a);
+            RouterReply::ok()
+        }
+        (None, _) => RouterReply::no_such_address(a.clone()),
+        (_, None) => RouterReply::no_such_address(b.clone()),
+    };
+
+    reply
+        .send(msg)
+        .await
+        .map_err(|_| NodeError::NodeState(NodeReason::Unknown).internal())?;
+    Ok(())
+}
+
+/// Handle a stopped primary address
+///
+/// The monitors of the address receive a [`WorkerDown`] message and
+/// the addresses linked to it are stopped, which in turn notifies
+/// their own monitors and links once they have stopped.
+pub(super) async fn down(router: &mut Router, primary: Address) -> Result<()> {
+    let mut stopped = vec![primary];
+
+    while let Some(addr) = stopped.pop() {
+        if let Some(watchers) = router.map.monitors.remove(&addr) {
+            for watcher in watchers {
+                notify(router, &addr, &watcher)?;
+            }
+        }
+        for watchers in router.map.monitors.values_mut() {
+            watchers.remove(&addr);
+        }
+
+        for linked in router.map.links.remove(&addr).unwrap_or_default() {
+            if let Some(links) = router.map.links.get_mut(&linked) {
+                links.remove(&addr);
+            }
+            match router.map.internal.get_mut(&linked) {
+                // Detached contexts have no relay to run the shutdown
+                Some(record) if record.is_detached() => {
+                    debug!("Stopping detached '{}' linked to '{}'", linked, addr);
+                    router.map.free_address(linked.clone());
+                    stopped.push(linked);
+                }
+                Some(record) if record.check() => {
+                    debug!("Stopping '{}' linked to '{}'", linked, addr);
+                    record.stop().await?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Deliver a [`WorkerDown`] message for `addr` to the mailbox of `watcher`
+fn notify(router: &Router, addr: &Address, watcher: &Address) -> Result<()> {
+    let record = router
+        .map
+        .addr_map
+        .get(watcher)
+        .and_then(|primary| router.map.internal.get(primary));
+    let sender = match record {
+        Some(record) if record.check() => {
+            record.increment_msg_count();
+            record.sender()
+        }
+        _ => return Ok(()),
+    };
+
+    let payload = WorkerDown::new(addr.clone())
+        .encode()
+        .map_err(|_| NodeError::Data.internal())?;
+    let msg = TransportMessage::v1(route![watcher.clone()], route![addr.clone()], payload);
+    let relay_msg = RelayMessage::new(
+        addr.clone(),
+        watcher.clone(),
+        LocalMessage::new(msg, Vec::new()),
+    );
+
+    // Don't block the router on a full mailbox
+    let watcher = watcher.clone();
+    crate::spawn(async move {
+        if sender.send(relay_msg).await.is_err() {
+            debug!("Failed to notify '{}' of a stopped worker", watcher);
+        }
+    });
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::channel_types::{MessageSender, SmallSender};
+use crate::channel_types::{MailboxSender, SmallSender};
 use crate::relay::CtrlSignal;
 use crate::{
     error::{NodeError, NodeReason},
@@ -12,7 +12,7 @@ use ockam_core::{
         sync::Arc,
         vec::Vec,
     },
-    Address, RelayMessage, Result,
+    Address, Result,
 };
 
 /// Address states and associated logic
@@ -28,6 +28,10 @@ pub struct InternalMap {
     clusters: BTreeMap<String, BTreeSet<Address>>,
     /// Track stop information
     stopping: BTreeSet<Address>,
+    /// Addresses to notify when the primary address stops
+    pub(super) monitors: BTreeMap<Address, BTreeSet<Address>>,
+    /// Addresses to stop when the primary address stops
+    pub(super) links: BTreeMap<Address, BTreeSet<Address>>,
     /// Metrics collection and sharing
     #[cfg(feature = "metrics")]
     metrics: (Arc<AtomicUsize>, Arc<AtomicUsize>),
@@ -163,7 +167,7 @@ pub struct AddressMeta {
 #[derive(Debug)]
 pub struct AddressRecord {
     address_set: Vec<Address>,
-    sender: Option<MessageSender<RelayMessage>>,
+    sender: Option<MailboxSender>,
     ctrl_tx: SmallSender<CtrlSignal>,
     state: AddressState,
     ready: ReadyState,
@@ -175,15 +179,18 @@ impl AddressRecord {
     pub fn address_set(&self) -> &[Address] {
         &self.address_set
     }
-    pub fn sender(&self) -> MessageSender<RelayMessage> {
+    pub fn sender(&self) -> MailboxSender {
         self.sender.clone().expect("No such sender!")
     }
     pub fn sender_drop(&mut self) {
         self.sender = None;
     }
+    pub fn is_detached(&self) -> bool {
+        self.meta.detached
+    }
     pub fn new(
         address_set: Vec<Address>,
-        sender: MessageSender<RelayMessage>,
+        sender: MailboxSender,
         ctrl_tx: SmallSender<CtrlSignal>,
         msg_count: Arc<AtomicUsize>,
         meta: AddressMeta,
@@ -91,6 +91,10 @@ pub(super) async fn graceful(
         let dur = Duration::from_secs(seconds as u64);
         task::spawn(async move {
             time::sleep(dur).await;
+            // The router is gone once the node stopped
+            if sender.is_closed() {
+                return;
+            }
             warn!("Shutdown timeout reached; aborting node!");
             if sender.send(NodeMessage::AbortNode).await.is_err() {
                 error!("Failed to send node abort signal to router");
@@ -59,7 +59,10 @@ pub(super) async fn exec(
     if !detached {
         record.sender_drop();
     } else {
-        router.map.free_address(primary_address);
+        router.map.free_address(primary_address.clone());
+        if router.state.running() {
+            super::monitor::down(router, primary_address).await?;
+        }
     }
 
     Ok(())
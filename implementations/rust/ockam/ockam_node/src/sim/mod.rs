@@ -0,0 +1,57 @@
+//! Deterministic simulation of several nodes in one process
+//!
+//! A [`Network`] connects nodes through a simulated transport of type
+//! [`SIM`].  Each node of a network has a name, and the address returned
+//! by [`address`] routes messages to the node with that name.  The links
+//! between nodes can add latency, jitter, which reorders messages, and
+//! packet loss, and nodes can be partitioned from each other.
+//!
+//! When the nodes run on a virtual clock, see
+//! [`NodeBuilder::with_virtual_clock`](crate::NodeBuilder::with_virtual_clock),
+//! latencies, timers and timeouts don't wait for real time, and two runs
+//! with the same network seed deliver and drop the same messages.  Tests
+//! get a virtual clock with `#[ockam_macros::test(virtual_clock)]`:
+//!
+//! ```ignore
+//! #[ockam_macros::test(virtual_clock)]
+//! async fn echo(ctx: &mut Context) -> Result<()> {
+//!     let network = Network::new(42);
+//!     network.set_default_link(Link::new(Duration::from_millis(50)));
+//!     network.attach(ctx, "alice").await?;
+//!
+//!     let bob = network.start_node("bob").await?;
+//!     bob.start_worker("echoer", Echoer, AllowAll, AllowAll).await?;
+//!
+//!     let route = route![sim::address("bob"), "echoer"];
+//!     ctx.send(route, "Hello".to_string()).await?;
+//!     assert_eq!(ctx.receive::<String>().await?.body(), "Hello");
+//!
+//!     bob.stop().await?;
+//!     ctx.stop().await
+//! }
+//! ```
+
+mod network;
+mod transport;
+
+pub use network::{Link, Network};
+
+use core::time::Duration;
+use ockam_core::{Address, TransportType};
+
+/// The address type of the simulated transport
+pub const SIM: TransportType = TransportType::new(6);
+
+/// The address of the node with the given name on a [`Network`]
+pub fn address(node: &str) -> Address {
+    Address::new(SIM, node)
+}
+
+/// Advance the virtual clock of the current node by `duration`
+///
+/// # Panics
+///
+/// Panics if the node doesn't run on a virtual clock.
+pub async fn advance(duration: Duration) {
+    crate::tokio::time::advance(duration).await
+}
@@ -0,0 +1,215 @@
+use super::address;
+use super::transport::SimTransport;
+use crate::tokio::sync::Notify;
+use crate::tokio::time::Instant;
+use crate::{Context, NodeBuilder, NodeError};
+use core::time::Duration;
+use ockam_core::compat::collections::{BTreeMap, BTreeSet};
+use ockam_core::compat::rand::prelude::{Rng, SeedableRng};
+use ockam_core::compat::rand::rngs::StdRng;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::{Result, TransportMessage};
+
+/// The properties of the link from one node to another
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Link {
+    latency: Duration,
+    jitter: Duration,
+    loss: f64,
+}
+
+impl Link {
+    /// A link delivering messages after `latency`
+    pub fn new(latency: Duration) -> Self {
+        Self {
+            latency,
+            ..Self::default()
+        }
+    }
+
+    /// Delay each message by an additional random duration up to `jitter`
+    ///
+    /// Messages sent within less than the jitter of each other may be
+    /// delivered out of order.
+    pub fn with_jitter(self, jitter: Duration) -> Self {
+        Self { jitter, ..self }
+    }
+
+    /// Drop each message with the probability `loss`, between 0 and 1
+    pub fn with_loss(self, loss: f64) -> Self {
+        Self { loss, ..self }
+    }
+}
+
+/// A message travelling from one node to another
+pub(super) struct Packet {
+    pub(super) from: String,
+    pub(super) msg: TransportMessage,
+}
+
+/// What a node receives next
+pub(super) enum Next {
+    /// A packet to deliver now
+    Packet(Packet),
+    /// Nothing before the given time
+    At(Instant),
+    /// Nothing until a message is sent to the node
+    Idle,
+}
+
+/// The messages in flight to a node, by delivery time and send order
+struct Inbox {
+    packets: BTreeMap<(Instant, u64), Packet>,
+    notify: Arc<Notify>,
+}
+
+struct State {
+    rng: StdRng,
+    sent: u64,
+    default_link: Link,
+    links: BTreeMap<(String, String), Link>,
+    partitions: BTreeSet<(String, String)>,
+    inboxes: BTreeMap<String, Inbox>,
+}
+
+/// A simulated network connecting nodes running in the same process
+///
+/// Cloning a network returns a handle to the same network.
+#[derive(Clone)]
+pub struct Network {
+    state: Arc<Mutex<State>>,
+}
+
+impl Network {
+    /// Create a network, seeding the random choices of jitter and loss
+    pub fn new(seed: u64) -> Self {
+        let state = State {
+            rng: StdRng::seed_from_u64(seed),
+            sent: 0,
+            default_link: Link::default(),
+            links: BTreeMap::new(),
+            partitions: BTreeSet::new(),
+            inboxes: BTreeMap::new(),
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Start a new node on the current runtime and attach it to the
+    /// network with the given name
+    ///
+    /// The node has its own router and workers, and stops with
+    /// [`Context::stop`] on the returned context.
+    pub async fn start_node(&self, name: &str) -> Result<Context> {
+        let notify = self.add_node(name)?;
+        let ctx = NodeBuilder::new().spawn();
+        SimTransport::create(&ctx, self.clone(), name, notify).await?;
+        Ok(ctx)
+    }
+
+    /// Attach an existing node to the network with the given name
+    ///
+    /// A node can only be attached to one network.
+    pub async fn attach(&self, ctx: &Context, name: &str) -> Result<()> {
+        let notify = self.add_node(name)?;
+        SimTransport::create(ctx, self.clone(), name, notify).await
+    }
+
+    fn add_node(&self, name: &str) -> Result<Arc<Notify>> {
+        let mut state = self.state.lock().unwrap();
+        if state.inboxes.contains_key(name) {
+            return Err(NodeError::Address(address(name)).already_exists());
+        }
+        let notify = Arc::new(Notify::new());
+        let inbox = Inbox {
+            packets: BTreeMap::new(),
+            notify: notify.clone(),
+        };
+        state.inboxes.insert(name.to_string(), inbox);
+        Ok(notify)
+    }
+
+    /// Set the link used between nodes without a specific link
+    pub fn set_default_link(&self, link: Link) {
+        self.state.lock().unwrap().default_link = link
+    }
+
+    /// Set the link from the node `from` to the node `to`
+    pub fn set_link(&self, from: &str, to: &str, link: Link) {
+        self.state
+            .lock()
+            .unwrap()
+            .links
+            .insert((from.to_string(), to.to_string()), link);
+    }
+
+    /// Drop all messages between the nodes of `side_a` and the nodes of
+    /// `side_b`, in both directions, until the network is healed
+    pub fn partition(&self, side_a: &[&str], side_b: &[&str]) {
+        let mut state = self.state.lock().unwrap();
+        for a in side_a {
+            for b in side_b {
+                state.partitions.insert((a.to_string(), b.to_string()));
+                state.partitions.insert((b.to_string(), a.to_string()));
+            }
+        }
+    }
+
+    /// Remove all partitions
+    pub fn heal(&self) {
+        self.state.lock().unwrap().partitions.clear()
+    }
+
+    /// Send a message from the node `from` to the node `to`
+    pub(super) fn send(&self, from: &str, to: &str, msg: TransportMessage) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let key = (from.to_string(), to.to_string());
+        if state.partitions.contains(&key) {
+            debug!("Dropping message from {} to {}: partitioned", from, to);
+            return;
+        }
+        let link = state.links.get(&key).copied().unwrap_or(state.default_link);
+        if link.loss > 0.0 && state.rng.gen::<f64>() < link.loss {
+            debug!("Dropping message from {} to {}: lost", from, to);
+            return;
+        }
+        let mut delay = link.latency;
+        if !link.jitter.is_zero() {
+            delay += link.jitter.mul_f64(state.rng.gen::<f64>());
+        }
+        let inbox = match state.inboxes.get_mut(to) {
+            Some(inbox) => inbox,
+            None => {
+                debug!("Dropping message from {} to {}: unknown node", from, to);
+                return;
+            }
+        };
+        state.sent += 1;
+        inbox.packets.insert(
+            (Instant::now() + delay, state.sent),
+            Packet {
+                from: from.to_string(),
+                msg,
+            },
+        );
+        inbox.notify.notify_one()
+    }
+
+    /// Take the next packet delivered to a node
+    pub(super) fn next(&self, name: &str) -> Next {
+        let mut state = self.state.lock().unwrap();
+        let inbox = match state.inboxes.get_mut(name) {
+            Some(inbox) => inbox,
+            None => return Next::Idle,
+        };
+        match inbox.packets.keys().next().copied() {
+            Some(key) if key.0 <= Instant::now() => {
+                Next::Packet(inbox.packets.remove(&key).unwrap())
+            }
+            Some((at, _)) => Next::At(at),
+            None => Next::Idle,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use super::network::{Network, Next};
+use super::{address, SIM};
+use crate::tokio::sync::Notify;
+use crate::tokio::time::sleep_until;
+use crate::Context;
+use ockam_core::compat::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use ockam_core::{
+    async_trait, Address, AllowAll, Any, LocalMessage, Processor, Result, Routed, Worker,
+};
+
+/// The simulated transport of one node of a [`Network`]
+///
+/// A router worker, registered for the [`SIM`] address type, hands the
+/// messages sent to other nodes to the network, and a receiver processor
+/// forwards the messages delivered by the network into the node.  The
+/// receiver prepends the address of the sending node to the return route
+/// so that replies are routed back to it.
+pub(super) struct SimTransport;
+
+impl SimTransport {
+    pub(super) async fn create(
+        ctx: &Context,
+        network: Network,
+        name: &str,
+        notify: Arc<Notify>,
+    ) -> Result<()> {
+        let router_addr = Address::random_tagged("SimTransport.router");
+        let router = SimRouter {
+            network: network.clone(),
+            name: name.to_string(),
+        };
+        ctx.start_worker(router_addr.clone(), router, AllowAll, AllowAll)
+            .await?;
+
+        let receiver = SimReceiver {
+            network,
+            name: name.to_string(),
+            notify,
+        };
+        ctx.start_processor(
+            Address::random_tagged("SimTransport.receiver"),
+            receiver,
+            AllowAll,
+            AllowAll,
+        )
+        .await?;
+
+        trace!("Registering simulated router for type = {}", SIM);
+        ctx.register(SIM, router_addr).await
+    }
+}
+
+/// Hands the messages routed to other nodes to the network
+struct SimRouter {
+    network: Network,
+    name: String,
+}
+
+#[async_trait]
+impl Worker for SimRouter {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(&mut self, _ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut msg = msg.into_transport_message();
+        let next = msg.onward_route.step()?;
+        self.network.send(&self.name, next.address(), msg);
+        Ok(())
+    }
+}
+
+/// Forwards the messages delivered by the network into the node
+struct SimReceiver {
+    network: Network,
+    name: String,
+    notify: Arc<Notify>,
+}
+
+#[async_trait]
+impl Processor for SimReceiver {
+    type Context = Context;
+
+    async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
+        let packet = loop {
+            match self.network.next(&self.name) {
+                Next::Packet(packet) => break packet,
+                Next::At(at) => {
+                    crate::tokio::select! {
+                        _ = sleep_until(at) => {}
+                        _ = self.notify.notified() => {}
+                    }
+                }
+                Next::Idle => self.notify.notified().await,
+            }
+        };
+
+        let mut msg = packet.msg;
+        msg.return_route.modify().prepend(address(&packet.from));
+        trace!(
+            "Received message from {}: onward_route = {}, return_route = {}",
+            packet.from,
+            msg.onward_route,
+            msg.return_route
+        );
+        ctx.forward(LocalMessage::new(msg, Vec::new())).await?;
+        Ok(true)
+    }
+}
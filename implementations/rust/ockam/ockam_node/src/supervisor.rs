@@ -0,0 +1,247 @@
+use crate::Context;
+use core::time::Duration;
+use ockam_core::compat::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use ockam_core::{
+    async_trait, Address, LocalOnwardOnly, LocalSourceOnly, Message, Result, Routed, Worker,
+};
+use serde::{Deserialize, Serialize};
+
+/// Decide whether a failing worker is restarted
+///
+/// Restarts happen in place: the worker keeps its addresses and its
+/// mailbox, and the messages queued while it restarts are handled by
+/// the new instance.  A worker which is not restarted after a panic is
+/// stopped and removed from the router.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Never restart the worker
+    #[default]
+    Never,
+    /// Restart the worker when handling a message returns an error or
+    /// panics
+    OnError(Backoff),
+    /// Restart the worker when handling a message panics, errors are
+    /// only logged
+    OnPanic(Backoff),
+}
+
+impl RestartPolicy {
+    /// Return the backoff to apply for the given failure, if the
+    /// worker must be restarted
+    pub(crate) fn backoff(&self, panicked: bool) -> Option<&Backoff> {
+        match self {
+            RestartPolicy::OnError(backoff) => Some(backoff),
+            RestartPolicy::OnPanic(backoff) if panicked => Some(backoff),
+            _ => None,
+        }
+    }
+}
+
+/// Exponential delay between the restarts of a worker
+///
+/// The number of restarts is reset once the restarted worker has
+/// successfully handled a message.  The worker is stopped when it fails
+/// again after `max_restarts` restarts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    max_restarts: u32,
+}
+
+impl Backoff {
+    /// Create a backoff starting at `initial` and doubling up to `max`
+    pub fn new(initial: Duration, max: Duration, max_restarts: u32) -> Self {
+        Self {
+            initial,
+            max,
+            max_restarts,
+        }
+    }
+
+    /// Delay before the given restart attempt, starting at 0
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max)
+    }
+
+    /// Maximum number of consecutive restarts
+    pub fn max_restarts(&self) -> u32 {
+        self.max_restarts
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(100), Duration::from_secs(10), 5)
+    }
+}
+
+/// Supervision options of a worker, set via the [`WorkerBuilder`](crate::WorkerBuilder)
+pub(crate) struct Supervision<W> {
+    pub(crate) policy: RestartPolicy,
+    pub(crate) factory: Option<Arc<dyn Fn() -> W + Send + Sync>>,
+    pub(crate) supervisor: Option<Address>,
+}
+
+impl<W> Default for Supervision<W> {
+    fn default() -> Self {
+        Self {
+            policy: RestartPolicy::Never,
+            factory: None,
+            supervisor: None,
+        }
+    }
+}
+
+/// A lifecycle event of a supervised worker
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Message)]
+pub struct WorkerEvent {
+    /// Primary address of the worker
+    pub address: Address,
+    /// What happened to the worker
+    pub kind: WorkerEventKind,
+}
+
+/// The kinds of [`WorkerEvent`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum WorkerEventKind {
+    /// The worker was initialised and is handling messages
+    Started,
+    /// Handling a message returned the given error
+    Failed(String),
+    /// Handling a message panicked with the given reason
+    Panicked(String),
+    /// The worker was restarted, with the number of consecutive restarts
+    Restarted(u32),
+    /// The worker failed after its maximum number of restarts
+    GaveUp,
+    /// The worker was stopped
+    Stopped,
+}
+
+impl WorkerEvent {
+    pub(crate) fn new(address: Address, kind: WorkerEventKind) -> Self {
+        Self { address, kind }
+    }
+}
+
+/// Messages handled by a [`Supervisor`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Message)]
+pub enum SupervisorMessage {
+    /// Lifecycle event sent by a supervised worker relay
+    Event(WorkerEvent),
+    /// Take ownership of a worker or processor which was not started
+    /// with [`WorkerBuilder::with_supervisor`](crate::WorkerBuilder::with_supervisor)
+    Adopt {
+        /// Primary address of the child
+        address: Address,
+        /// Whether the child is a processor
+        processor: bool,
+    },
+    /// Give up the ownership of a child, which is not stopped
+    Release(Address),
+    /// Send the lifecycle events of all children to the given address
+    Subscribe(Address),
+    /// Stop sending lifecycle events to the given address
+    Unsubscribe(Address),
+}
+
+struct Child {
+    address: Address,
+    processor: bool,
+}
+
+/// A worker owning a set of children
+///
+/// Workers started with
+/// [`WorkerBuilder::with_supervisor`](crate::WorkerBuilder::with_supervisor)
+/// report their lifecycle to the supervisor, which forwards these
+/// events to its subscribers as [`WorkerEvent`]s.  Restarts are driven
+/// by the [`RestartPolicy`] of each child.
+///
+/// When the supervisor is stopped it stops its remaining children, in
+/// the reverse order of their start.
+#[derive(Default)]
+pub struct Supervisor {
+    children: Vec<Child>,
+    subscribers: Vec<Address>,
+}
+
+impl Supervisor {
+    /// Start a supervisor at the given address, only reachable from
+    /// this node
+    pub async fn start(ctx: &Context, address: impl Into<Address>) -> Result<()> {
+        ctx.start_worker(
+            address.into(),
+            Supervisor::default(),
+            LocalSourceOnly,
+            LocalOnwardOnly,
+        )
+        .await
+    }
+
+    fn add_child(&mut self, address: Address, processor: bool) {
+        if !self.children.iter().any(|c| c.address == address) {
+            self.children.push(Child { address, processor });
+        }
+    }
+
+    fn remove_child(&mut self, address: &Address) {
+        self.children.retain(|c| &c.address != address);
+    }
+}
+
+#[async_trait]
+impl Worker for Supervisor {
+    type Context = Context;
+    type Message = SupervisorMessage;
+
+    async fn shutdown(&mut self, ctx: &mut Context) -> Result<()> {
+        while let Some(child) = self.children.pop() {
+            let res = if child.processor {
+                ctx.stop_processor(child.address.clone()).await
+            } else {
+                ctx.stop_worker(child.address.clone()).await
+            };
+            if let Err(e) = res {
+                debug!("Supervised '{}' was already stopped: {}", child.address, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<SupervisorMessage>,
+    ) -> Result<()> {
+        match msg.body() {
+            SupervisorMessage::Event(event) => {
+                match &event.kind {
+                    WorkerEventKind::Started => self.add_child(event.address.clone(), false),
+                    WorkerEventKind::Stopped => self.remove_child(&event.address),
+                    WorkerEventKind::GaveUp => {
+                        warn!("Supervised worker '{}' gave up restarting", event.address)
+                    }
+                    _ => {}
+                }
+                for subscriber in &self.subscribers {
+                    if let Err(e) = ctx.send(subscriber.clone(), event.clone()).await {
+                        debug!("Failed to notify subscriber '{}': {}", subscriber, e);
+                    }
+                }
+            }
+            SupervisorMessage::Adopt { address, processor } => self.add_child(address, processor),
+            SupervisorMessage::Release(address) => self.remove_child(&address),
+            SupervisorMessage::Subscribe(address) => {
+                if !self.subscribers.contains(&address) {
+                    self.subscribers.push(address);
+                }
+            }
+            SupervisorMessage::Unsubscribe(address) => self.subscribers.retain(|s| s != &address),
+        }
+        Ok(())
+    }
+}
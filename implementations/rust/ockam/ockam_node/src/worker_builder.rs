@@ -1,6 +1,8 @@
 use crate::debugger;
 use crate::error::{NodeError, NodeReason};
 use crate::{relay::WorkerRelay, Context, NodeMessage};
+#[cfg(feature = "std")]
+use crate::{RestartPolicy, Supervision};
 use ockam_core::compat::sync::Arc;
 use ockam_core::{
     errcode::{Kind, Origin},
@@ -22,6 +24,8 @@ use ockam_core::{
 pub struct WorkerBuilder<W> {
     mailboxes: Mailboxes,
     worker: W,
+    #[cfg(feature = "std")]
+    supervision: Supervision<W>,
 }
 
 impl<W> WorkerBuilder<W> {
@@ -49,13 +53,40 @@ where
             outgoing_access_control,
         );
 
-        Self { mailboxes, worker }
+        Self::with_mailboxes(mailboxes, worker)
     }
 
     /// Create a worker which uses the access control from the given
     /// [`Mailboxes`]
     pub fn with_mailboxes(mailboxes: Mailboxes, worker: W) -> Self {
-        Self { mailboxes, worker }
+        Self {
+            mailboxes,
+            worker,
+            #[cfg(feature = "std")]
+            supervision: Supervision::default(),
+        }
+    }
+
+    /// Restart the worker after failures according to the given
+    /// [`RestartPolicy`], creating the new instances with `factory`
+    #[cfg(feature = "std")]
+    pub fn with_restart_policy(
+        mut self,
+        policy: RestartPolicy,
+        factory: impl Fn() -> W + Send + Sync + 'static,
+    ) -> Self {
+        self.supervision.policy = policy;
+        self.supervision.factory = Some(Arc::new(factory));
+        self
+    }
+
+    /// Run the worker under the [`Supervisor`](crate::Supervisor) at
+    /// the given address, which owns the worker and is notified of its
+    /// lifecycle events
+    #[cfg(feature = "std")]
+    pub fn with_supervisor(mut self, supervisor: impl Into<Address>) -> Self {
+        self.supervision.supervisor = Some(supervisor.into());
+        self
     }
 
     /// Consume this builder and start a new Ockam [`Worker`] from the given context
@@ -83,7 +114,14 @@ where
         debugger::log_inherit_context("WORKER", context, &ctx);
 
         // Then initialise the worker message relay
-        WorkerRelay::<W, M>::init(context.runtime(), self.worker, ctx, ctrl_rx);
+        WorkerRelay::<W, M>::init(
+            context.runtime(),
+            self.worker,
+            ctx,
+            ctrl_rx,
+            #[cfg(feature = "std")]
+            self.supervision,
+        );
 
         // Send start request to router
         let (msg, mut rx) =
@@ -0,0 +1,248 @@
+use crate::error::{NodeError, NodeReason};
+use crate::{Context, NodeMessage, WorkerBuilder};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ockam_core::compat::{boxed::Box, sync::Arc, vec::Vec};
+use ockam_core::{
+    async_trait,
+    errcode::{Kind, Origin},
+    Address, AllowOnwardAddresses, AllowSourceAddress, Any, AnyIncomingAccessControl, DenyAll,
+    Error, IncomingAccessControl, Mailbox, Mailboxes, Message, OutgoingAccessControl, Result,
+    Routed, Worker,
+};
+
+/// How a [`WorkerPool`] chooses the member handling a message
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dispatch {
+    /// Hand the messages to each member in turn
+    #[default]
+    RoundRobin,
+    /// Hand each message to the member with the fewest messages waiting
+    /// or being handled
+    LeastLoaded,
+}
+
+/// Start a pool of identical workers behind one address
+///
+/// A [`Worker`] handles one message at a time.  A pool starts `size`
+/// instances of a worker, created by a factory, and a dispatcher at the
+/// pool address which hands each incoming message to one of them, so
+/// that up to `size` messages are handled concurrently.  This is meant
+/// for stateless services: the messages of one sender can be handled by
+/// different members, and in a different order than they were sent.
+///
+/// The incoming access control of the pool applies to the messages sent
+/// to the pool address.  Each member has its own address, from which it
+/// sends its replies, and only accepts the messages dispatched by the
+/// pool and the messages passing the incoming access control of the
+/// pool.  The outgoing access control of the pool applies to the
+/// messages sent by the members.
+///
+/// The dispatcher and the members are linked with
+/// [`Context::link`](crate::Context::link): stopping the pool address,
+/// or any member, stops the whole pool.
+///
+/// ```ignore
+/// WorkerPool::new("echoer", 4, || Echoer)
+///     .with_dispatch(Dispatch::LeastLoaded)
+///     .start(ctx)
+///     .await?;
+/// ```
+pub struct WorkerPool<F> {
+    address: Address,
+    size: usize,
+    factory: F,
+    dispatch: Dispatch,
+    incoming_access_control: Arc<dyn IncomingAccessControl>,
+    outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+}
+
+impl<M, W, F> WorkerPool<F>
+where
+    M: Message + Send + 'static,
+    W: Worker<Context = Context, Message = M>,
+    F: Fn() -> W,
+{
+    /// Create a pool of `size` workers created by `factory`, which deny
+    /// all incoming and outgoing messages until access controls are set
+    pub fn new(address: impl Into<Address>, size: usize, factory: F) -> Self {
+        Self {
+            address: address.into(),
+            size,
+            factory,
+            dispatch: Dispatch::default(),
+            incoming_access_control: Arc::new(DenyAll),
+            outgoing_access_control: Arc::new(DenyAll),
+        }
+    }
+
+    /// Set the access controls of the pool
+    pub fn with_access_control(
+        mut self,
+        incoming_access_control: impl IncomingAccessControl,
+        outgoing_access_control: impl OutgoingAccessControl,
+    ) -> Self {
+        self.incoming_access_control = Arc::new(incoming_access_control);
+        self.outgoing_access_control = Arc::new(outgoing_access_control);
+        self
+    }
+
+    /// Set how the members handling each message are chosen
+    pub fn with_dispatch(mut self, dispatch: Dispatch) -> Self {
+        self.dispatch = dispatch;
+        self
+    }
+
+    /// Consume this builder and start the pool from the given context
+    pub async fn start(self, context: &Context) -> Result<Address> {
+        if self.size == 0 {
+            return Err(Error::new(
+                Origin::Node,
+                Kind::Invalid,
+                "a worker pool needs at least one member",
+            ));
+        }
+
+        let mut members = Vec::with_capacity(self.size);
+        for _ in 0..self.size {
+            let address = Address::random_tagged("WorkerPool.member");
+            let incoming = AnyIncomingAccessControl::new(vec![
+                Arc::new(AllowSourceAddress(self.address.clone())),
+                self.incoming_access_control.clone(),
+            ]);
+            let mailboxes = Mailboxes::main(
+                address.clone(),
+                Arc::new(incoming),
+                self.outgoing_access_control.clone(),
+            );
+            let load = Arc::new(AtomicUsize::new(0));
+            let member = PoolMember {
+                worker: (self.factory)(),
+                load: load.clone(),
+            };
+            WorkerBuilder::with_mailboxes(mailboxes, member)
+                .start(context)
+                .await?;
+            members.push(Member { address, load });
+        }
+
+        let member_addresses: Vec<Address> = members.iter().map(|m| m.address.clone()).collect();
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(
+                self.address.clone(),
+                self.incoming_access_control,
+                Arc::new(AllowOnwardAddresses(member_addresses.clone())),
+            ),
+            vec![],
+        );
+        let dispatcher = Dispatcher {
+            members,
+            dispatch: self.dispatch,
+            next: 0,
+        };
+        WorkerBuilder::with_mailboxes(mailboxes, dispatcher)
+            .start(context)
+            .await?;
+
+        for member in member_addresses {
+            let (msg, mut rx) = NodeMessage::link(self.address.clone(), member);
+            context
+                .sender()
+                .send(msg)
+                .await
+                .map_err(NodeError::from_send_err)?;
+            rx.recv()
+                .await
+                .ok_or_else(|| NodeError::NodeState(NodeReason::Unknown).internal())??;
+        }
+
+        Ok(self.address)
+    }
+}
+
+/// A member of a pool, and the number of messages dispatched to it
+/// which it hasn't handled yet
+struct Member {
+    address: Address,
+    load: Arc<AtomicUsize>,
+}
+
+/// Hands the messages sent to the pool address to its members
+struct Dispatcher {
+    members: Vec<Member>,
+    dispatch: Dispatch,
+    next: usize,
+}
+
+impl Dispatcher {
+    fn choose(&mut self) -> &Member {
+        let index = match self.dispatch {
+            Dispatch::RoundRobin => {
+                let index = self.next;
+                self.next = (self.next + 1) % self.members.len();
+                index
+            }
+            Dispatch::LeastLoaded => (0..self.members.len())
+                .min_by_key(|i| self.members[*i].load.load(Ordering::Acquire))
+                .unwrap_or_default(),
+        };
+        &self.members[index]
+    }
+}
+
+#[async_trait]
+impl Worker for Dispatcher {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut local_msg = msg.into_local_message();
+        let onward_route = &mut local_msg.transport_mut().onward_route;
+        onward_route.step()?;
+
+        let member = self.choose();
+        onward_route.modify().prepend(member.address.clone());
+        member.load.fetch_add(1, Ordering::AcqRel);
+        let result = ctx.forward(local_msg).await;
+        if result.is_err() {
+            // The member won't handle this message
+            member.load.fetch_sub(1, Ordering::AcqRel);
+        }
+        result
+    }
+}
+
+/// Wraps the worker of a pool member to track its load
+struct PoolMember<W> {
+    worker: W,
+    load: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl<M, W> Worker for PoolMember<W>
+where
+    M: Message + Send + 'static,
+    W: Worker<Context = Context, Message = M>,
+{
+    type Message = Any;
+    type Context = Context;
+
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        self.worker.initialize(ctx).await
+    }
+
+    async fn shutdown(&mut self, ctx: &mut Context) -> Result<()> {
+        self.worker.shutdown(ctx).await
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let result = match msg.cast::<M>() {
+            Ok(msg) => self.worker.handle_message(ctx, msg).await,
+            Err(_) => {
+                error!("Failed to decode message payload for worker pool member");
+                Ok(())
+            }
+        };
+        self.load.fetch_sub(1, Ordering::AcqRel);
+        result
+    }
+}
@@ -0,0 +1,64 @@
+use core::time::Duration;
+use ockam_core::{async_trait, route, AllowAll, Result, Routed, Worker};
+use ockam_node::loopback::{self, LoopbackTransport};
+use ockam_node::{Context, MessageReceiveOptions};
+
+/// Replies with the name of its node
+struct Greeter {
+    node: &'static str,
+}
+
+#[async_trait]
+impl Worker for Greeter {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(
+            msg.return_route(),
+            format!("{} from {}", msg.body(), self.node),
+        )
+        .await
+    }
+}
+
+async fn greet(ctx: &mut Context, node: &str) -> Result<String> {
+    ctx.send(
+        route![loopback::address(node), "greeter"],
+        "Hello".to_string(),
+    )
+    .await?;
+    let options = MessageReceiveOptions::new().with_timeout(Duration::from_millis(500));
+    Ok(ctx.receive_extended::<String>(options).await?.body())
+}
+
+#[ockam_macros::test]
+async fn nodes_are_isolated(ctx: &mut Context) -> Result<()> {
+    let loopback = LoopbackTransport::new();
+    loopback.attach(ctx, "test").await?;
+
+    // Each node has its own address space
+    let mut n1 = loopback.start_node("n1").await?;
+    n1.start_worker("greeter", Greeter { node: "n1" }, AllowAll, AllowAll)
+        .await?;
+    let n2 = loopback.start_node("n2").await?;
+    n2.start_worker("greeter", Greeter { node: "n2" }, AllowAll, AllowAll)
+        .await?;
+    assert!(loopback.start_node("n2").await.is_err());
+
+    assert_eq!("Hello from n1", greet(ctx, "n1").await?);
+    assert_eq!("Hello from n2", greet(ctx, "n2").await?);
+
+    // Stopping a node leaves the others running
+    n1.stop().await?;
+    assert!(greet(ctx, "n1").await.is_err());
+    assert_eq!("Hello from n2", greet(ctx, "n2").await?);
+
+    // The name of a stopped node can be reused
+    let n1 = loopback.start_node("n1").await?;
+    n1.start_worker("greeter", Greeter { node: "new n1" }, AllowAll, AllowAll)
+        .await?;
+    assert_eq!("Hello from new n1", greet(ctx, "n1").await?);
+
+    ctx.stop().await
+}
@@ -0,0 +1,91 @@
+use core::time::Duration;
+use ockam_core::{async_trait, Address, AllowAll, Any, Result, Routed, Worker};
+use ockam_node::{Context, WorkerDown};
+
+struct Idle;
+
+#[async_trait]
+impl Worker for Idle {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(&mut self, _: &mut Context, _: Routed<Any>) -> Result<()> {
+        panic!("idle worker received a message")
+    }
+}
+
+async fn is_running(ctx: &Context, addr: &str) -> Result<bool> {
+    Ok(ctx.list_workers().await?.contains(&Address::from(addr)))
+}
+
+#[ockam_macros::test]
+async fn monitor_stopped_worker(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("idle", Idle, AllowAll, AllowAll).await?;
+    let mut watcher = ctx.new_detached("watcher", AllowAll, AllowAll).await?;
+    watcher.monitor("idle").await?;
+
+    ctx.stop_worker("idle").await?;
+    let down = watcher.receive::<WorkerDown>().await?.body();
+    assert_eq!(down.address, "idle".into());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn monitor_panicking_worker(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("idle", Idle, AllowAll, AllowAll).await?;
+    let mut watcher = ctx.new_detached("watcher", AllowAll, AllowAll).await?;
+    watcher.monitor("idle").await?;
+
+    ctx.send("idle", "hello".to_string()).await?;
+    let down = watcher.receive::<WorkerDown>().await?.body();
+    assert_eq!(down.address, "idle".into());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn monitor_dropped_detached_context(ctx: &mut Context) -> Result<()> {
+    let detached = ctx.new_detached("detached", AllowAll, AllowAll).await?;
+    let mut watcher = ctx.new_detached("watcher", AllowAll, AllowAll).await?;
+    watcher.monitor("detached").await?;
+
+    drop(detached);
+    let down = watcher.receive::<WorkerDown>().await?.body();
+    assert_eq!(down.address, "detached".into());
+
+    assert!(watcher.monitor("unknown").await.is_err());
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn linked_workers_stop_together(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("a", Idle, AllowAll, AllowAll).await?;
+    ctx.start_worker("b", Idle, AllowAll, AllowAll).await?;
+    ctx.start_worker("c", Idle, AllowAll, AllowAll).await?;
+    let linker = ctx.new_detached("linker", AllowAll, AllowAll).await?;
+    let mut watcher = ctx.new_detached("watcher", AllowAll, AllowAll).await?;
+    watcher.monitor("c").await?;
+
+    // A context linked to two workers propagates the stop of one to the other
+    linker.link("a").await?;
+    linker.link("b").await?;
+    ctx.start_worker("d", Idle, AllowAll, AllowAll).await?;
+    assert!(linker.link("unknown").await.is_err());
+
+    ctx.stop_worker("a").await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert!(!is_running(ctx, "a").await?);
+    assert!(!is_running(ctx, "linker").await?);
+    assert!(!is_running(ctx, "b").await?);
+    assert!(is_running(ctx, "c").await?);
+    assert!(is_running(ctx, "d").await?);
+
+    // Stopping a worker doesn't stop the workers it was unlinked from
+    ctx.stop_worker("c").await?;
+    let down = watcher.receive::<WorkerDown>().await?.body();
+    assert_eq!(down.address, "c".into());
+    assert!(is_running(ctx, "d").await?);
+
+    ctx.stop().await
+}
@@ -0,0 +1,44 @@
+use core::time::Duration;
+use ockam_core::{async_trait, AllowAll, Priority, Result, Routed, Worker};
+use ockam_node::Context;
+use tokio::time::sleep;
+
+/// Replies to each message after a delay
+struct SlowEchoer;
+
+#[async_trait]
+impl Worker for SlowEchoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        sleep(Duration::from_millis(50)).await;
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+#[ockam_macros::test]
+async fn control_messages_overtake_normal_backlog(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("echoer", SlowEchoer, AllowAll, AllowAll)
+        .await?;
+
+    for i in 0..5 {
+        ctx.send("echoer", format!("normal {}", i)).await?;
+    }
+    ctx.send_with_priority("echoer", "control".to_string(), Priority::Control)
+        .await?;
+
+    let mut received = vec![];
+    for _ in 0..6 {
+        received.push(ctx.receive::<String>().await?.body());
+    }
+
+    // The worker may already handle the first normal message
+    let control = received.iter().position(|m| m == "control").unwrap();
+    assert!(control <= 1, "control message received at {}", control);
+    received.remove(control);
+    let normal: Vec<String> = (0..5).map(|i| format!("normal {}", i)).collect();
+    assert_eq!(normal, received);
+
+    ctx.stop().await
+}
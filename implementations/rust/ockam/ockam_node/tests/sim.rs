@@ -0,0 +1,112 @@
+use core::time::Duration;
+use ockam_core::{async_trait, route, AllowAll, Result, Routed, Worker};
+use ockam_node::sim::{self, Link, Network};
+use ockam_node::tokio::time::Instant;
+use ockam_node::{Context, MessageReceiveOptions};
+
+struct Echoer;
+
+#[async_trait]
+impl Worker for Echoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+/// Receive messages until none arrives within a second
+async fn drain(ctx: &mut Context) -> Vec<String> {
+    let mut received = vec![];
+    let options = || MessageReceiveOptions::new().with_timeout(Duration::from_secs(1));
+    while let Ok(msg) = ctx.receive_extended::<String>(options()).await {
+        received.push(msg.body())
+    }
+    received
+}
+
+/// Send numbered messages from alice to bob's echoer over a lossy link
+/// with jitter
+async fn lossy_round(seed: u64) -> Result<Vec<String>> {
+    let network = Network::new(seed);
+    let mut alice = network.start_node("alice").await?;
+    let mut bob = network.start_node("bob").await?;
+    bob.start_worker("echoer", Echoer, AllowAll, AllowAll)
+        .await?;
+    network.set_link(
+        "alice",
+        "bob",
+        Link::new(Duration::from_millis(10))
+            .with_jitter(Duration::from_millis(100))
+            .with_loss(0.3),
+    );
+
+    for i in 0..20 {
+        alice
+            .send(route![sim::address("bob"), "echoer"], i.to_string())
+            .await?;
+    }
+    let received = drain(&mut alice).await;
+    alice.stop().await?;
+    bob.stop().await?;
+    Ok(received)
+}
+
+#[ockam_macros::test(virtual_clock)]
+async fn latency_uses_the_virtual_clock(ctx: &mut Context) -> Result<()> {
+    let network = Network::new(0);
+    network.set_default_link(Link::new(Duration::from_secs(10)));
+    network.attach(ctx, "alice").await?;
+    let mut bob = network.start_node("bob").await?;
+    bob.start_worker("echoer", Echoer, AllowAll, AllowAll)
+        .await?;
+
+    let start = Instant::now();
+    ctx.send(route![sim::address("bob"), "echoer"], "Hello".to_string())
+        .await?;
+    assert_eq!("Hello", ctx.receive::<String>().await?.body());
+    assert_eq!(Duration::from_secs(20), start.elapsed());
+
+    sim::advance(Duration::from_secs(5)).await;
+    assert_eq!(Duration::from_secs(25), start.elapsed());
+
+    bob.stop().await?;
+    ctx.stop().await
+}
+
+#[ockam_macros::test(virtual_clock)]
+async fn jitter_and_loss_are_deterministic(ctx: &mut Context) -> Result<()> {
+    let first = lossy_round(7).await?;
+    let second = lossy_round(7).await?;
+
+    assert_eq!(first, second);
+    assert!(first.len() < 20);
+    let mut sorted = first.clone();
+    sorted.sort_by_key(|i| i.parse::<u32>().unwrap());
+    assert_ne!(first, sorted);
+    ctx.stop().await
+}
+
+#[ockam_macros::test(virtual_clock)]
+async fn partitions_drop_messages_until_healed(ctx: &mut Context) -> Result<()> {
+    let network = Network::new(0);
+    network.attach(ctx, "alice").await?;
+    let mut bob = network.start_node("bob").await?;
+    bob.start_worker("echoer", Echoer, AllowAll, AllowAll)
+        .await?;
+    assert!(network.start_node("bob").await.is_err());
+
+    network.partition(&["alice"], &["bob"]);
+    ctx.send(route![sim::address("bob"), "echoer"], "1".to_string())
+        .await?;
+    assert!(drain(ctx).await.is_empty());
+
+    network.heal();
+    ctx.send(route![sim::address("bob"), "echoer"], "2".to_string())
+        .await?;
+    assert_eq!(vec!["2"], drain(ctx).await);
+
+    bob.stop().await?;
+    ctx.stop().await
+}
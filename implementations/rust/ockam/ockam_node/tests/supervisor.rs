@@ -0,0 +1,203 @@
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, route, Address, AllowAll, Error, Mailboxes, Result, Routed, Worker};
+use ockam_node::{
+    Backoff, Context, RestartPolicy, Supervisor, SupervisorMessage, WorkerBuilder, WorkerEvent,
+    WorkerEventKind,
+};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Replies with the number of its instance, fails on "fail" and panics on "panic"
+struct Flaky {
+    instance: u32,
+}
+
+impl Flaky {
+    fn factory() -> impl Fn() -> Flaky + Send + Sync + 'static {
+        let instances = Arc::new(AtomicU32::new(0));
+        move || Flaky {
+            instance: instances.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+#[async_trait]
+impl Worker for Flaky {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        match msg.as_body().as_str() {
+            "panic" => panic!("flaky worker panicked"),
+            "fail" => Err(Error::new_without_cause(Origin::Application, Kind::Invalid)),
+            _ => {
+                ctx.send(msg.return_route(), self.instance.to_string())
+                    .await
+            }
+        }
+    }
+}
+
+fn backoff(max_restarts: u32) -> Backoff {
+    Backoff::new(
+        Duration::from_millis(10),
+        Duration::from_millis(50),
+        max_restarts,
+    )
+}
+
+async fn start_flaky(
+    ctx: &Context,
+    address: &str,
+    policy: RestartPolicy,
+    supervisor: &str,
+) -> Result<()> {
+    let factory = Flaky::factory();
+    WorkerBuilder::with_mailboxes(
+        Mailboxes::main(address, Arc::new(AllowAll), Arc::new(AllowAll)),
+        factory(),
+    )
+    .with_restart_policy(policy, factory)
+    .with_supervisor(supervisor)
+    .start(ctx)
+    .await?;
+    Ok(())
+}
+
+async fn next_event(ctx: &mut Context) -> Result<WorkerEventKind> {
+    Ok(ctx.receive::<WorkerEvent>().await?.body().kind)
+}
+
+#[ockam_macros::test]
+async fn restart_on_panic(ctx: &mut Context) -> Result<()> {
+    Supervisor::start(ctx, "supervisor").await?;
+    let mut events = ctx.new_detached("events", AllowAll, AllowAll).await?;
+    ctx.send("supervisor", SupervisorMessage::Subscribe("events".into()))
+        .await?;
+    start_flaky(
+        ctx,
+        "flaky",
+        RestartPolicy::OnPanic(backoff(2)),
+        "supervisor",
+    )
+    .await?;
+    assert_eq!(next_event(&mut events).await?, WorkerEventKind::Started);
+
+    let mut client = ctx.new_detached("client", AllowAll, AllowAll).await?;
+    client.send(route!["flaky"], "ping".to_string()).await?;
+    assert_eq!(client.receive::<String>().await?.body(), "0");
+
+    // Errors are ignored with this policy
+    client.send(route!["flaky"], "fail".to_string()).await?;
+    assert!(matches!(
+        next_event(&mut events).await?,
+        WorkerEventKind::Failed(_)
+    ));
+    client.send(route!["flaky"], "ping".to_string()).await?;
+    assert_eq!(client.receive::<String>().await?.body(), "0");
+
+    // A panic restarts the worker at the same address, queued messages are kept
+    client.send(route!["flaky"], "panic".to_string()).await?;
+    client.send(route!["flaky"], "ping".to_string()).await?;
+    assert_eq!(
+        next_event(&mut events).await?,
+        WorkerEventKind::Panicked("flaky worker panicked".into())
+    );
+    assert_eq!(
+        next_event(&mut events).await?,
+        WorkerEventKind::Restarted(1)
+    );
+    assert_eq!(client.receive::<String>().await?.body(), "1");
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn give_up_after_max_restarts(ctx: &mut Context) -> Result<()> {
+    Supervisor::start(ctx, "supervisor").await?;
+    let mut events = ctx.new_detached("events", AllowAll, AllowAll).await?;
+    ctx.send("supervisor", SupervisorMessage::Subscribe("events".into()))
+        .await?;
+    start_flaky(
+        ctx,
+        "flaky",
+        RestartPolicy::OnError(backoff(1)),
+        "supervisor",
+    )
+    .await?;
+    assert_eq!(next_event(&mut events).await?, WorkerEventKind::Started);
+
+    ctx.send("flaky", "fail".to_string()).await?;
+    assert!(matches!(
+        next_event(&mut events).await?,
+        WorkerEventKind::Failed(_)
+    ));
+    assert_eq!(
+        next_event(&mut events).await?,
+        WorkerEventKind::Restarted(1)
+    );
+
+    ctx.send("flaky", "fail".to_string()).await?;
+    assert!(matches!(
+        next_event(&mut events).await?,
+        WorkerEventKind::Failed(_)
+    ));
+    assert_eq!(next_event(&mut events).await?, WorkerEventKind::GaveUp);
+    assert_eq!(next_event(&mut events).await?, WorkerEventKind::Stopped);
+    assert!(!ctx.list_workers().await?.contains(&Address::from("flaky")));
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn panicking_worker_is_removed(ctx: &mut Context) -> Result<()> {
+    Supervisor::start(ctx, "supervisor").await?;
+    let mut events = ctx.new_detached("events", AllowAll, AllowAll).await?;
+    ctx.send("supervisor", SupervisorMessage::Subscribe("events".into()))
+        .await?;
+    start_flaky(ctx, "flaky", RestartPolicy::Never, "supervisor").await?;
+    assert_eq!(next_event(&mut events).await?, WorkerEventKind::Started);
+
+    ctx.send("flaky", "panic".to_string()).await?;
+    assert!(matches!(
+        next_event(&mut events).await?,
+        WorkerEventKind::Panicked(_)
+    ));
+    assert_eq!(next_event(&mut events).await?, WorkerEventKind::Stopped);
+    assert!(!ctx.list_workers().await?.contains(&Address::from("flaky")));
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn supervisor_stops_children(ctx: &mut Context) -> Result<()> {
+    Supervisor::start(ctx, "supervisor").await?;
+    let mut events = ctx.new_detached("events", AllowAll, AllowAll).await?;
+    ctx.send("supervisor", SupervisorMessage::Subscribe("events".into()))
+        .await?;
+    start_flaky(ctx, "flaky", RestartPolicy::Never, "supervisor").await?;
+    assert_eq!(next_event(&mut events).await?, WorkerEventKind::Started);
+
+    ctx.start_worker("adopted", Flaky { instance: 0 }, AllowAll, AllowAll)
+        .await?;
+    ctx.send(
+        "supervisor",
+        SupervisorMessage::Adopt {
+            address: "adopted".into(),
+            processor: false,
+        },
+    )
+    .await?;
+
+    // Wait for the supervisor to handle the adoption
+    ctx.sleep(Duration::from_millis(100)).await;
+    ctx.stop_worker("supervisor").await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    let workers = ctx.list_workers().await?;
+    assert!(!workers.contains(&Address::from("flaky")));
+    assert!(!workers.contains(&Address::from("adopted")));
+
+    ctx.stop().await
+}
@@ -0,0 +1,149 @@
+use core::time::Duration;
+use ockam_core::{async_trait, Address, AllowAll, DenyAll, Result, Routed, Worker};
+use ockam_node::{Context, Dispatch, MessageReceiveOptions, WorkerPool};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::time::sleep;
+
+/// Replies to each message after a delay
+struct SlowEchoer;
+
+#[async_trait]
+impl Worker for SlowEchoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        sleep(Duration::from_millis(300)).await;
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+/// Replies to each message after a delay, counting the messages being
+/// handled by all the workers sharing the counters
+struct CountingEchoer {
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Worker for CountingEchoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        sleep(Duration::from_millis(300)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+/// Start a pool and return the addresses of its members
+async fn start_pool<F>(ctx: &Context, pool: WorkerPool<F>) -> Result<Vec<Address>>
+where
+    F: Fn() -> SlowEchoer,
+{
+    let before = ctx.list_workers().await?;
+    let address = pool.start(ctx).await?;
+    let mut members = ctx.list_workers().await?;
+    members.retain(|a| !before.contains(a) && *a != address);
+    Ok(members)
+}
+
+/// Send `count` messages to the pool and return the addresses of the
+/// members which replied
+async fn round(ctx: &mut Context, count: usize) -> Result<Vec<Address>> {
+    for i in 0..count {
+        ctx.send("pool", i.to_string()).await?;
+    }
+    let mut members = vec![];
+    for _ in 0..count {
+        members.push(ctx.receive::<String>().await?.return_route().recipient()?);
+    }
+    Ok(members)
+}
+
+#[ockam_macros::test(virtual_clock)]
+async fn messages_are_handled_concurrently(ctx: &mut Context) -> Result<()> {
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let counters = (in_flight.clone(), max_in_flight.clone());
+    WorkerPool::new("pool", 4, move || CountingEchoer {
+        in_flight: counters.0.clone(),
+        max_in_flight: counters.1.clone(),
+    })
+    .with_access_control(AllowAll, AllowAll)
+    .start(ctx)
+    .await?;
+
+    let members = round(ctx, 4).await?;
+    assert_eq!(4, max_in_flight.load(Ordering::SeqCst));
+    assert_eq!(0, in_flight.load(Ordering::SeqCst));
+    assert_eq!(4, members.iter().collect::<BTreeSet<_>>().len());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test(virtual_clock)]
+async fn least_loaded_dispatch_spreads_messages(ctx: &mut Context) -> Result<()> {
+    WorkerPool::new("pool", 2, || SlowEchoer)
+        .with_access_control(AllowAll, AllowAll)
+        .with_dispatch(Dispatch::LeastLoaded)
+        .start(ctx)
+        .await?;
+
+    let members = round(ctx, 4).await?;
+    let distinct: BTreeSet<_> = members.iter().collect();
+    assert_eq!(2, distinct.len());
+    for member in distinct {
+        assert_eq!(2, members.iter().filter(|m| *m == member).count());
+    }
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test(virtual_clock)]
+async fn access_control_applies_to_pool_and_members(ctx: &mut Context) -> Result<()> {
+    let members = start_pool(
+        ctx,
+        WorkerPool::new("pool", 2, || SlowEchoer).with_access_control(DenyAll, AllowAll),
+    )
+    .await?;
+    assert_eq!(2, members.len());
+    let options = || MessageReceiveOptions::new().with_timeout(Duration::from_secs(1));
+
+    // The pool denies the message
+    ctx.send("pool", "1".to_string()).await?;
+    assert!(ctx.receive_extended::<String>(options()).await.is_err());
+
+    // A member of the pool denies messages sent directly to it
+    ctx.send(members[0].clone(), "2".to_string()).await?;
+    assert!(ctx.receive_extended::<String>(options()).await.is_err());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test(virtual_clock)]
+async fn stopping_the_pool_stops_its_members(ctx: &mut Context) -> Result<()> {
+    let members = start_pool(
+        ctx,
+        WorkerPool::new("pool", 3, || SlowEchoer).with_access_control(AllowAll, AllowAll),
+    )
+    .await?;
+    assert_eq!(3, members.len());
+
+    ctx.stop_worker("pool").await?;
+    sleep(Duration::from_millis(100)).await;
+    let workers = ctx.list_workers().await?;
+    assert!(!workers.contains(&"pool".into()));
+    assert!(!members.iter().any(|a| workers.contains(a)));
+
+    assert!(WorkerPool::new("pool", 0, || SlowEchoer)
+        .start(ctx)
+        .await
+        .is_err());
+    ctx.stop().await
+}
@@ -13,6 +13,10 @@ pub mod bluetooth_hci;
 ))]
 pub mod btleplug;
 
+/// support for serial (UART) links
+#[cfg(feature = "use_serial")]
+pub mod serial;
+
 #[cfg(not(feature = "std"))]
 mod mutex;
 mod packet;
@@ -87,8 +87,8 @@ impl Inner {
     /// Returns `true` if another blocked operation from the set was notified.
     fn cancel(&mut self, key: usize) -> bool {
         match self.entries.remove(&key) {
-            Some(_) => self.notifiable -= 1,
-            None => {
+            Some(Some(_)) => self.notifiable -= 1,
+            _ => {
                 // The operation was cancelled and notified so notify another operation instead.
                 for (_, opt_waker) in self.entries.iter_mut() {
                     // If there is no waker in this entry, that means it was already woken.
@@ -155,7 +155,8 @@ impl Inner {
 
     /// Removes the waker of an operation.
     fn remove(&mut self, key: usize) {
-        if self.entries.remove(&key).is_some() {
+        // An entry without a waker was already notified
+        if let Some(Some(_)) = self.entries.remove(&key) {
             self.notifiable -= 1;
         }
     }
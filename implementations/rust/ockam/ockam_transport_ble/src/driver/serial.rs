@@ -0,0 +1,266 @@
+//! Driver for serial (UART) links
+//!
+//! A [`SerialAdapter`] carries the packet fragments of the BLE
+//! transport over a UART implementing the
+//! [embedded-hal](https://crates.io/crates/embedded-hal) serial
+//! traits, so that two devices wired together can be connected with a
+//! [`BleTransport`](crate::BleTransport) without a radio.
+//!
+//! Each fragment is followed by its CRC-16/CCITT-FALSE checksum and
+//! written as a [SLIP](https://www.rfc-editor.org/rfc/rfc1055) frame,
+//! delimited by `END` bytes.  A receiver drops the frames with a wrong
+//! checksum, or too long to be a fragment, and resynchronizes on the
+//! next `END` byte after noise or lost bytes.  The link has no
+//! connection handshake: a server accepts the connection as soon as it
+//! listens.
+
+use core::time::Duration;
+use embedded_hal::serial::{Read, Write};
+use nb::block;
+
+use ockam_core::async_trait;
+use ockam_core::compat::boxed::Box;
+use ockam_core::Result;
+
+use crate::driver::CHARACTERISTIC_VALUE_LENGTH;
+use crate::driver::{BleClientDriver, BleEvent, BleServerDriver, BleStreamDriver};
+use crate::error::BleError;
+use crate::BleAddr;
+
+/// How long to wait before reading from the UART again when it has
+/// no data, so that the executor can sleep in the meantime
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Delimits frames
+const END: u8 = 0xC0;
+/// Escapes the `END` and `ESC` bytes in a frame
+const ESC: u8 = 0xDB;
+/// An escaped `END` byte
+const ESC_END: u8 = 0xDC;
+/// An escaped `ESC` byte
+const ESC_ESC: u8 = 0xDD;
+
+/// The length of the checksum following each fragment
+const CRC_LENGTH: usize = 2;
+
+/// The CRC-16/CCITT-FALSE checksum of `data`
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFF_u16;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// A BLE adapter writing packet fragments to a UART
+pub struct SerialAdapter<U> {
+    uart: U,
+    connected: bool,
+    /// The unescaped bytes of the frame being received
+    frame: [u8; CHARACTERISTIC_VALUE_LENGTH + CRC_LENGTH],
+    frame_len: usize,
+    /// Whether the previous byte was an `ESC`
+    escaped: bool,
+    /// Whether the frame being received is dropped
+    dropped: bool,
+}
+
+impl<U> SerialAdapter<U>
+where
+    U: Read<u8> + Write<u8> + Send,
+{
+    /// Create an adapter for the given UART
+    pub fn new(uart: U) -> Self {
+        Self {
+            uart,
+            connected: false,
+            frame: [0_u8; CHARACTERISTIC_VALUE_LENGTH + CRC_LENGTH],
+            frame_len: 0,
+            escaped: false,
+            dropped: false,
+        }
+    }
+
+    /// Release the UART
+    pub fn free(self) -> U {
+        self.uart
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<()> {
+        block!(self.uart.write(byte)).map_err(|_| BleError::WriteError.into())
+    }
+
+    /// Write a byte of a frame, escaped if needed
+    fn write_escaped(&mut self, byte: u8) -> Result<()> {
+        match byte {
+            END => {
+                self.write_byte(ESC)?;
+                self.write_byte(ESC_END)
+            }
+            ESC => {
+                self.write_byte(ESC)?;
+                self.write_byte(ESC_ESC)
+            }
+            _ => self.write_byte(byte),
+        }
+    }
+
+    /// Start receiving a new frame
+    fn reset_frame(&mut self) {
+        self.frame_len = 0;
+        self.escaped = false;
+        self.dropped = false;
+    }
+
+    /// Add an unescaped byte to the frame being received
+    fn push_byte(&mut self, byte: u8) {
+        if self.frame_len == self.frame.len() {
+            error!("SerialAdapter::poll frame too long, dropping it");
+            self.dropped = true;
+        } else {
+            self.frame[self.frame_len] = byte;
+            self.frame_len += 1;
+        }
+    }
+
+    /// The length of the fragment in the frame received, if its
+    /// checksum is valid
+    fn check_frame(&self) -> Option<usize> {
+        if self.frame_len <= CRC_LENGTH {
+            error!("SerialAdapter::poll frame too short: {}", self.frame_len);
+            return None;
+        }
+        let fragment_len = self.frame_len - CRC_LENGTH;
+        let (fragment, crc) = self.frame[..self.frame_len].split_at(fragment_len);
+        if crc16(fragment).to_be_bytes() != crc {
+            error!("SerialAdapter::poll invalid checksum, dropping fragment");
+            return None;
+        }
+        Some(fragment_len)
+    }
+}
+
+#[async_trait]
+impl<U> BleClientDriver for SerialAdapter<U>
+where
+    U: Read<u8> + Write<u8> + Send,
+{
+    async fn scan(&mut self, _ble_addr: &BleAddr) -> Result<()> {
+        Ok(())
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        self.connected = true;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<U> BleServerDriver for SerialAdapter<U>
+where
+    U: Read<u8> + Write<u8> + Send,
+{
+    async fn bind(&mut self, _ble_addr: &BleAddr) -> Result<()> {
+        Ok(())
+    }
+
+    async fn start_advertising(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<U> BleStreamDriver for SerialAdapter<U>
+where
+    U: Read<u8> + Write<u8> + Send,
+{
+    async fn poll<'b>(&mut self, buffer: &'b mut [u8]) -> Result<BleEvent<'b>> {
+        if !self.connected {
+            self.connected = true;
+            return Ok(BleEvent::ConnectionComplete);
+        }
+
+        loop {
+            let byte = match self.uart.read() {
+                Ok(byte) => Some(byte),
+                Err(nb::Error::WouldBlock) => None,
+                Err(nb::Error::Other(_)) => {
+                    error!("SerialAdapter::poll read error, dropping fragment");
+                    self.dropped = true;
+                    return Ok(BleEvent::Unknown);
+                }
+            };
+            let byte = match byte {
+                Some(byte) => byte,
+                None => {
+                    ockam_node::tokio::time::sleep(POLL_INTERVAL).await;
+                    return Ok(BleEvent::None);
+                }
+            };
+
+            if byte == END {
+                let frame = if self.dropped || self.frame_len == 0 {
+                    None
+                } else {
+                    self.check_frame()
+                };
+                self.reset_frame();
+                let fragment_len = match frame {
+                    Some(fragment_len) => fragment_len,
+                    // skip empty, dropped and invalid frames
+                    None => continue,
+                };
+                if buffer.len() < fragment_len {
+                    error!("SerialAdapter::poll buffer too small: {}", buffer.len());
+                    return Err(BleError::ReadError.into());
+                }
+                buffer[..fragment_len].copy_from_slice(&self.frame[..fragment_len]);
+                return Ok(BleEvent::Received(&buffer[..fragment_len]));
+            }
+
+            if self.dropped {
+                continue;
+            }
+            if self.escaped {
+                self.escaped = false;
+                match byte {
+                    ESC_END => self.push_byte(END),
+                    ESC_ESC => self.push_byte(ESC),
+                    _ => {
+                        error!("SerialAdapter::poll invalid escape: {}", byte);
+                        self.dropped = true;
+                    }
+                }
+            } else if byte == ESC {
+                self.escaped = true;
+            } else {
+                self.push_byte(byte);
+            }
+        }
+    }
+
+    async fn write(&mut self, buffer: &[u8]) -> Result<()> {
+        if buffer.is_empty() || buffer.len() > CHARACTERISTIC_VALUE_LENGTH {
+            error!(
+                "SerialAdapter::write invalid fragment length: {}",
+                buffer.len()
+            );
+            return Err(BleError::WriteError.into());
+        }
+
+        // The leading END ends any noise received before the frame
+        self.write_byte(END)?;
+        for byte in buffer.iter().chain(&crc16(buffer).to_be_bytes()) {
+            self.write_escaped(*byte)?;
+        }
+        self.write_byte(END)?;
+        block!(self.uart.flush()).map_err(|_| BleError::WriteError.into())
+    }
+}
@@ -32,3 +32,17 @@ macro_rules! wait_ms {
         }
     };
 }
+
+/// Other targets wait with the timers of the executor, from an async
+/// context only
+#[cfg(all(
+    not(feature = "std"),
+    not(target_arch = "arm"),
+    not(target_arch = "mips")
+))]
+#[macro_export]
+macro_rules! wait_ms {
+    ($millis:expr) => {
+        ockam_node::tokio::time::sleep(core::time::Duration::from_millis($millis)).await;
+    };
+}
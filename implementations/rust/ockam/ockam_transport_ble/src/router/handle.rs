@@ -4,7 +4,7 @@ use crate::{
     BleAddr, BleClient, BleServer,
 };
 
-use crate::router::BleRouterMessage;
+use crate::router::{BleRouterMessage, BleRouterResponse};
 use ockam_core::{
     async_trait,
     compat::{boxed::Box, string::String, vec::Vec},
@@ -57,12 +57,16 @@ impl BleRouterHandle {
 
         trace!("BleRouterHandle accepts: {:?} -> {:?}", accepts, self_addr);
 
-        self.ctx
-            .send(
+        let response: BleRouterResponse = self
+            .ctx
+            .send_and_receive(
                 self.api_addr.clone(),
                 BleRouterMessage::Register { accepts, self_addr },
             )
-            .await
+            .await?;
+
+        let BleRouterResponse::Register(res) = response;
+        res
     }
 
     /// Bind an incoming connection listener for this router
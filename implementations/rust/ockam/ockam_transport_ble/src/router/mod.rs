@@ -2,14 +2,13 @@ mod handle;
 
 use ockam_core::{
     async_trait,
-    compat::{boxed::Box, collections::BTreeMap, vec::Vec},
+    compat::{boxed::Box, collections::BTreeMap, sync::Arc, vec::Vec},
     AllowAll, Any, Mailbox, Mailboxes,
 };
 use ockam_core::{Address, Decodable, LocalMessage, Message, Result, Routed, Worker};
 use ockam_node::{Context, WorkerBuilder};
 use ockam_transport_core::TransportError;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
 
 pub(crate) use handle::BleRouterHandle;
 
@@ -24,6 +23,12 @@ pub enum BleRouterMessage {
     },
 }
 
+#[derive(Serialize, Deserialize, Debug, Message)]
+pub enum BleRouterResponse {
+    /// Response containing a result when attempting to register a new client.
+    Register(Result<()>),
+}
+
 /// A Bluetooth Low Energy address router and connection listener
 ///
 /// In order to create new BLE connection workers you need a router to
@@ -113,10 +118,11 @@ impl Worker for BleRouter {
     }
 
     async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let return_route = msg.return_route();
         let msg_addr = msg.msg_addr();
 
         if msg_addr == self.main_addr {
-            let msg = LocalMessage::decode(msg.payload())?;
+            let msg = msg.into_local_message();
             trace!("handle_message route: {:?}", msg.transport().onward_route);
             self.handle_route(ctx, msg).await?;
         } else if msg_addr == self.api_addr {
@@ -124,7 +130,10 @@ impl Worker for BleRouter {
             match msg {
                 BleRouterMessage::Register { accepts, self_addr } => {
                     trace!("handle_message register: {:?} => {:?}", accepts, self_addr);
-                    self.handle_register(accepts, self_addr).await?;
+                    let res = self.handle_register(accepts, self_addr).await;
+
+                    ctx.send(return_route, BleRouterResponse::Register(res))
+                        .await?;
                 }
             };
         } else {
@@ -60,8 +60,9 @@ where
     }
 
     async fn process(&mut self, ctx: &mut Self::Context) -> Result<bool> {
+        // The connection was handed over to a worker pair
         if self.inner.is_none() {
-            return Ok(true);
+            return Ok(false);
         }
 
         // Wait for an incoming connection from a BleClient
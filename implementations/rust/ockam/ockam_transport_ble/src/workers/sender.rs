@@ -1,6 +1,7 @@
 use ockam_core::compat::{boxed::Box, string::String, vec::Vec};
 use ockam_core::{
-    async_trait, Address, AllowAll, Encodable, Result, Routed, TransportMessage, Worker,
+    async_trait, Address, AllowAll, Any, Decodable, Encodable, LocalMessage, Result, Routed,
+    Worker,
 };
 use ockam_node::Context;
 use ockam_transport_core::TransportError;
@@ -86,7 +87,7 @@ where
     A: BleStreamDriver + Send + 'static,
 {
     type Context = Context;
-    type Message = TransportMessage;
+    type Message = Any;
 
     async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
         ctx.set_cluster(crate::CLUSTER_NAME).await?;
@@ -115,20 +116,17 @@ where
 
     // BleSendWorker will receive messages from the BleRouter to send
     // across the TcpStream to the next remote peer.
-    async fn handle_message(
-        &mut self,
-        ctx: &mut Context,
-        mut msg: Routed<TransportMessage>,
-    ) -> Result<()> {
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
         trace!("BleSendWorker::handle_message -> {:?}", msg);
 
+        let mut msg = LocalMessage::decode(msg.payload())?.into_transport_message();
+
         // Remove our own address from the route so the other end
         // knows what to do with the incoming message
         msg.onward_route.step()?;
 
         // encode message
         let msg = msg
-            .body()
             .encode()
             .map_err(|_| TransportError::SendBadMessage)?;
 
@@ -0,0 +1,46 @@
+use ockam_core::{route, AllowAll, Priority, Result, Routed, Worker};
+use ockam_node::Context;
+use ockam_transport_tcp::{TcpConnectionTrustOptions, TcpListenerTrustOptions, TcpTransport};
+
+/// Replies with the priority of each received message
+struct PriorityEchoer;
+
+#[ockam_core::worker]
+impl Worker for PriorityEchoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        let priority = msg.local_message().priority();
+        ctx.send(msg.return_route(), format!("{:?}", priority))
+            .await
+    }
+}
+
+#[ockam_macros::test]
+async fn priority_is_local_to_the_node(ctx: &mut Context) -> Result<()> {
+    let transport = TcpTransport::create(ctx).await?;
+    let (listener_address, _) = transport
+        .listen("127.0.0.1:0", TcpListenerTrustOptions::new())
+        .await?;
+    ctx.start_worker("echoer", PriorityEchoer, AllowAll, AllowAll)
+        .await?;
+
+    let addr = transport
+        .connect(
+            listener_address.to_string(),
+            TcpConnectionTrustOptions::new(),
+        )
+        .await?;
+    let r = route![addr, "echoer"];
+
+    ctx.send(r.clone(), "data".to_string()).await?;
+    assert_eq!(ctx.receive::<String>().await?.body(), "Normal");
+
+    // The priority is not sent to the peer
+    ctx.send_with_priority(r, "ping".to_string(), Priority::Control)
+        .await?;
+    assert_eq!(ctx.receive::<String>().await?.body(), "Normal");
+
+    ctx.stop().await
+}
@@ -56,6 +56,8 @@ impl AsymmetricVault for Vault {
         let dh = Self::ecdh_internal(entry, peer_public_key)?;
 
         // Prevent dead-lock by freeing entries lock, since we don't need it
+        // (the no_std lock is not held, so the guard has nothing to release)
+        #[cfg_attr(not(feature = "std"), allow(clippy::drop_non_drop))]
         drop(entries);
 
         let attributes = SecretAttributes::new(
@@ -1,5 +1,5 @@
 use crate::{Vault, VaultError};
-use aes_gcm::aead::{generic_array::GenericArray, Aead, NewAead, Payload};
+use aes_gcm::aead::{self, generic_array::GenericArray, AeadInPlace, NewAead};
 use aes_gcm::{Aes128Gcm, Aes256Gcm};
 use ockam_core::vault::{
     Buffer, KeyId, SecretType, SymmetricVault, AES128_SECRET_LENGTH_U32,
@@ -7,6 +7,14 @@ use ockam_core::vault::{
 };
 use ockam_core::{async_trait, compat::boxed::Box, Result};
 
+/// Copy `data` into a new buffer, which is encrypted or decrypted in
+/// place so that a heapless `Buffer` needs no intermediate allocation
+fn buffer_from(data: &[u8]) -> Option<Buffer<u8>> {
+    let mut buffer = Buffer::new();
+    aead::Buffer::extend_from_slice(&mut buffer, data).ok()?;
+    Some(buffer)
+}
+
 #[async_trait]
 impl SymmetricVault for Vault {
     async fn aead_aes_gcm_encrypt(
@@ -28,10 +36,7 @@ impl SymmetricVault for Vault {
         }
 
         let nonce = GenericArray::from_slice(nonce);
-        let payload = Payload {
-            aad,
-            msg: plaintext,
-        };
+        let mut buffer = buffer_from(plaintext).ok_or(VaultError::AeadAesGcmEncrypt)?;
 
         match entry.key_attributes().length() {
             AES128_SECRET_LENGTH_U32 => {
@@ -42,8 +47,8 @@ impl SymmetricVault for Vault {
 
                 let key = GenericArray::from_slice(key);
                 Aes128Gcm::new(key)
-                    .encrypt(nonce, payload)
-                    .map_err(|_| VaultError::AeadAesGcmEncrypt.into())
+                    .encrypt_in_place(nonce, aad, &mut buffer)
+                    .map_err(|_| VaultError::AeadAesGcmEncrypt)?;
             }
             AES256_SECRET_LENGTH_U32 => {
                 let key = entry.secret().try_as_key()?.as_ref();
@@ -53,11 +58,13 @@ impl SymmetricVault for Vault {
 
                 let key = GenericArray::from_slice(key);
                 Aes256Gcm::new(key)
-                    .encrypt(nonce, payload)
-                    .map_err(|_| VaultError::AeadAesGcmEncrypt.into())
+                    .encrypt_in_place(nonce, aad, &mut buffer)
+                    .map_err(|_| VaultError::AeadAesGcmEncrypt)?;
             }
-            _ => Err(VaultError::AeadAesGcmEncrypt.into()),
+            _ => return Err(VaultError::AeadAesGcmEncrypt.into()),
         }
+
+        Ok(buffer)
     }
 
     async fn aead_aes_gcm_decrypt(
@@ -79,10 +86,7 @@ impl SymmetricVault for Vault {
         }
 
         let nonce = GenericArray::from_slice(nonce);
-        let payload = Payload {
-            aad,
-            msg: cipher_text,
-        };
+        let mut buffer = buffer_from(cipher_text).ok_or(VaultError::AeadAesGcmEncrypt)?;
 
         match entry.key_attributes().length() {
             AES128_SECRET_LENGTH_U32 => {
@@ -92,8 +96,8 @@ impl SymmetricVault for Vault {
                 }
                 let key = GenericArray::from_slice(key);
                 Aes128Gcm::new(key)
-                    .decrypt(nonce, payload)
-                    .map_err(|_| VaultError::AeadAesGcmEncrypt.into())
+                    .decrypt_in_place(nonce, aad, &mut buffer)
+                    .map_err(|_| VaultError::AeadAesGcmEncrypt)?;
             }
             AES256_SECRET_LENGTH_U32 => {
                 let key = entry.secret().try_as_key()?.as_ref();
@@ -102,11 +106,13 @@ impl SymmetricVault for Vault {
                 }
                 let key = GenericArray::from_slice(key);
                 Aes256Gcm::new(key)
-                    .decrypt(nonce, payload)
-                    .map_err(|_| VaultError::AeadAesGcmEncrypt.into())
+                    .decrypt_in_place(nonce, aad, &mut buffer)
+                    .map_err(|_| VaultError::AeadAesGcmEncrypt)?;
             }
-            _ => Err(VaultError::AeadAesGcmEncrypt.into()),
+            _ => return Err(VaultError::AeadAesGcmEncrypt.into()),
         }
+
+        Ok(buffer)
     }
 }
 